@@ -16,6 +16,8 @@ pub use lamco_rdp_clipboard::{
 };
 
 use crate::clipboard::manager::ClipboardManager;
+use crate::protocol::capture::{CapturingCliprdrBackend, PduCapture};
+use crate::utils::spawn_in_current_span;
 
 /// Server-specific clipboard backend factory
 ///
@@ -43,6 +45,9 @@ pub struct LamcoCliprdrFactory {
 
     /// Server event sender for IronRDP (set via ServerEventSender trait)
     server_event_sender: Option<mpsc::UnboundedSender<ironrdp_server::ServerEvent>>,
+
+    /// PDU capture the backends record to
+    capture: Option<PduCapture>,
 }
 
 impl LamcoCliprdrFactory {
@@ -66,9 +71,16 @@ impl LamcoCliprdrFactory {
             clipboard_manager,
             event_sender,
             server_event_sender: None,
+            capture: None,
         }
     }
 
+    /// Record the clipboard PDUs of each connection to `capture`
+    pub fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
     /// Start the event bridge task
     ///
     /// This task polls the ClipboardEventReceiver and forwards RDP backend events
@@ -80,7 +92,7 @@ impl LamcoCliprdrFactory {
     ) {
         use lamco_clipboard_core::ClipboardFormat;
 
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             info!("🔗 RDP clipboard event bridge task started");
 
             loop {
@@ -235,7 +247,13 @@ impl CliprdrBackendFactory for LamcoCliprdrFactory {
             self.event_sender.clone(),
         );
 
-        Box::new(backend)
+        match &self.capture {
+            Some(capture) => Box::new(CapturingCliprdrBackend::new(
+                Box::new(backend),
+                capture.clone(),
+            )),
+            None => Box::new(backend),
+        }
     }
 }
 
@@ -247,7 +265,7 @@ impl ServerEventSender for LamcoCliprdrFactory {
         // Register sender with ClipboardManager for delayed rendering requests
         let manager = Arc::clone(&self.clipboard_manager);
         let sender_clone = sender;
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             if let Ok(mgr) = manager.try_lock() {
                 mgr.set_server_event_sender(sender_clone).await;
             }
@@ -16,10 +16,12 @@
 use crate::clipboard::error::{ClipboardError, Result};
 use crate::clipboard::sync::{ClipboardState, SyncManager};
 use crate::clipboard::FormatConverterExt; // Extension trait for converter methods
+use crate::utils::spawn_in_current_span;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, error, info, trace, warn};
@@ -64,6 +66,13 @@ pub struct ClipboardConfig {
     /// Minimum milliseconds between forwarded clipboard events (rate limiting)
     /// Prevents rapid-fire D-Bus signals from overwhelming Portal. Set to 0 to disable.
     pub rate_limit_ms: u64,
+
+    /// Portal SelectionOwnerChanged reports every local copy
+    ///
+    /// When false (GNOME), local changes also come from the clipboard
+    /// extension's D-Bus bridge. When true the bridge only starts if the
+    /// portal listener can't.
+    pub owner_signals_reliable: bool,
 }
 
 impl Default for ClipboardConfig {
@@ -78,8 +87,126 @@ impl Default for ClipboardConfig {
             timeout_ms: 5000,
             loop_detection_window_ms: 500,
             rate_limit_ms: 200, // Max 5 events/second
+            owner_signals_reliable: true,
+        }
+    }
+}
+
+/// Clipboard settings that can change while a session is running
+///
+/// Cloned handles share state, so a policy obtained from
+/// [`ClipboardManager::policy`] updates the running manager.
+#[derive(Debug, Clone)]
+pub struct ClipboardPolicy {
+    enabled: Arc<AtomicBool>,
+    rate_limit_ms: Arc<AtomicU64>,
+    paused: Arc<AtomicBool>,
+    last_transfer: Arc<std::sync::Mutex<Option<std::time::Instant>>>,
+    transfer_observer: Arc<ObserverSlot>,
+}
+
+/// Clipboard data moving between client and host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardTransfer {
+    /// The client asked for host data in a format
+    ToClient {
+        /// Requested format ID
+        format_id: u32,
+    },
+    /// The client sent data to the host
+    ToHost {
+        /// Size of the data
+        bytes: usize,
+    },
+}
+
+/// Callback told of each clipboard transfer
+pub type TransferObserver = Arc<dyn Fn(ClipboardTransfer) + Send + Sync>;
+
+#[derive(Default)]
+struct ObserverSlot(std::sync::Mutex<Option<TransferObserver>>);
+
+impl std::fmt::Debug for ObserverSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObserverSlot")
+    }
+}
+
+impl ClipboardPolicy {
+    fn new(rate_limit_ms: u64) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(true)),
+            rate_limit_ms: Arc::new(AtomicU64::new(rate_limit_ms)),
+            paused: Arc::new(AtomicBool::new(false)),
+            last_transfer: Arc::new(std::sync::Mutex::new(None)),
+            transfer_observer: Arc::default(),
+        }
+    }
+
+    /// Whether new clipboard contents are offered in either direction
+    ///
+    /// False while disabled by configuration or paused by the local user.
+    pub fn is_syncing(&self) -> bool {
+        self.is_enabled() && !self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume synchronization, independent of the configuration
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// When clipboard data last moved between client and host
+    pub fn last_transfer(&self) -> Option<std::time::Instant> {
+        *self.last_transfer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Forget the last transfer (new session on a reused pipeline)
+    pub fn clear_last_transfer(&self) {
+        *self.last_transfer.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Tell `observer` of each transfer from now on
+    pub fn set_transfer_observer(&self, observer: TransferObserver) {
+        *self
+            .transfer_observer
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(observer);
+    }
+
+    fn record_transfer(&self, transfer: Option<ClipboardTransfer>) {
+        *self.last_transfer.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(std::time::Instant::now());
+        let observer = self
+            .transfer_observer
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let (Some(observer), Some(transfer)) = (observer, transfer) {
+            observer(transfer);
         }
     }
+
+    /// Whether clipboard changes are synchronized
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable synchronization of new clipboard contents
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Minimum milliseconds between forwarded clipboard events
+    pub fn rate_limit_ms(&self) -> u64 {
+        self.rate_limit_ms.load(Ordering::Relaxed)
+    }
+
+    /// Change the rate limit (0 disables it)
+    pub fn set_rate_limit_ms(&self, rate_limit_ms: u64) {
+        self.rate_limit_ms.store(rate_limit_ms, Ordering::Relaxed);
+    }
 }
 
 /// Response callback for sending data back to RDP
@@ -179,6 +306,9 @@ pub struct ClipboardManager {
     /// Configuration
     config: ClipboardConfig,
 
+    /// Runtime-adjustable policy (enable switch, rate limit)
+    policy: ClipboardPolicy,
+
     /// Format converter
     converter: Arc<FormatConverter>,
 
@@ -515,8 +645,11 @@ impl ClipboardManager {
         let fuse_manager = Arc::new(RwLock::new(fuse_manager));
         let pending_fuse_responses = Arc::new(RwLock::new(HashMap::new()));
 
+        let policy = ClipboardPolicy::new(config.rate_limit_ms);
+
         let mut manager = Self {
             config,
+            policy,
             converter,
             transfer_engine,
             sync_manager,
@@ -547,6 +680,11 @@ impl ClipboardManager {
         Ok(manager)
     }
 
+    /// Handle for changing the clipboard policy at runtime
+    pub fn policy(&self) -> ClipboardPolicy {
+        self.policy.clone()
+    }
+
     /// Get event sender for external components
     pub fn event_sender(&self) -> mpsc::Sender<ClipboardEvent> {
         self.event_tx.clone()
@@ -641,11 +779,17 @@ impl ClipboardManager {
             .await;
 
         // Start SelectionOwnerChanged listener for local clipboard monitoring (Linux → Windows copy)
-        self.start_owner_changed_listener(Arc::clone(&portal), Arc::clone(&session))
+        let owner_signals = self
+            .start_owner_changed_listener(Arc::clone(&portal), Arc::clone(&session))
             .await;
 
-        // D-Bus bridge fallback - SelectionOwnerChanged unreliable on GNOME
-        self.start_dbus_clipboard_listener().await;
+        // D-Bus bridge fallback - only where owner-change signals can't be trusted,
+        // otherwise both sources would announce the same copy twice
+        if !owner_signals || !self.config.owner_signals_reliable {
+            self.start_dbus_clipboard_listener().await;
+        } else {
+            debug!("Portal owner-change signals are reliable here - D-Bus bridge not needed");
+        }
     }
 
     /// Start SelectionTransfer listener for delayed rendering (Windows → Linux paste)
@@ -679,7 +823,7 @@ impl ClipboardManager {
                 let current_rdp_formats = Arc::clone(&self.current_rdp_formats);
 
                 // Spawn task to handle SelectionTransfer events
-                tokio::spawn(async move {
+                spawn_in_current_span(async move {
                     while let Some(transfer_event) = transfer_rx.recv().await {
                         info!(
                             "SelectionTransfer signal: {} (serial {})",
@@ -858,7 +1002,7 @@ impl ClipboardManager {
                                 let portal_clone = Arc::clone(&portal_clipboard);
                                 let session_clone = Arc::clone(&portal_session);
 
-                                tokio::spawn(async move {
+                                spawn_in_current_span(async move {
                                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
 
                                     // Check if request still pending in FIFO queue
@@ -917,6 +1061,8 @@ impl ClipboardManager {
     }
 
     /// Monitor local clipboard changes (Linux → Windows copy flow)
+    ///
+    /// Returns whether the portal listener is running.
     async fn start_owner_changed_listener(
         &self,
         portal: Arc<crate::portal::PortalClipboardManager>,
@@ -928,7 +1074,7 @@ impl ClipboardManager {
                 >,
             >,
         >,
-    ) {
+    ) -> bool {
         // Create channel for SelectionOwnerChanged events
         let (owner_tx, mut owner_rx) = mpsc::unbounded_channel();
 
@@ -940,7 +1086,7 @@ impl ClipboardManager {
                 let event_tx = self.event_tx.clone();
 
                 // Spawn task to handle clipboard ownership changes
-                tokio::spawn(async move {
+                spawn_in_current_span(async move {
                     info!(
                         "SelectionOwnerChanged handler task ready - waiting for clipboard changes"
                     );
@@ -979,11 +1125,13 @@ impl ClipboardManager {
 
                 debug!(" SelectionOwnerChanged listener started - monitoring Linux clipboard");
                 debug!("Using Portal path (KDE/Sway/wlroots mode) - NOT D-Bus extension");
+                true
             }
             Err(e) => {
                 error!("Failed to start SelectionOwnerChanged listener: {:#}", e);
                 warn!("Linux → Windows clipboard flow will not work via Portal signals");
                 warn!("Will attempt D-Bus bridge for GNOME extension fallback");
+                false
             }
         }
     }
@@ -1019,12 +1167,12 @@ impl ClipboardManager {
         // Clone event sender and hash tracker for the spawned task
         let event_tx = self.event_tx.clone();
         let recently_written_hashes = Arc::clone(&self.recently_written_hashes);
-        let rate_limit_ms = self.config.rate_limit_ms;
+        let policy = self.policy.clone();
 
         // Start background hash cleanup task
         // This removes the expensive cleanup from the clipboard event hot path
         let hashes_for_cleanup = Arc::clone(&self.recently_written_hashes);
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             const LOOP_SUPPRESSION_WINDOW_MS: u128 = 2000;
             const MAX_HASH_CACHE_SIZE: usize = 50;
 
@@ -1061,10 +1209,10 @@ impl ClipboardManager {
         });
 
         // Spawn task to forward D-Bus events to ClipboardManager
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             info!(
                 "D-Bus clipboard event forwarder started (rate limit: {}ms)",
-                rate_limit_ms
+                policy.rate_limit_ms()
             );
             let mut event_count = 0;
             let mut suppressed_count = 0;
@@ -1091,6 +1239,7 @@ impl ClipboardManager {
 
                 // RATE LIMITING: Enforce minimum interval between forwarded events
                 // This prevents rapid-fire D-Bus signals from overwhelming the Portal
+                let rate_limit_ms = policy.rate_limit_ms();
                 if rate_limit_ms > 0 {
                     if let Some(last_time) = last_forward_time {
                         let elapsed = last_time.elapsed().as_millis() as u64;
@@ -1183,7 +1332,7 @@ impl ClipboardManager {
         let server_event_sender = Arc::clone(&self.server_event_sender);
         let file_transfer_state = Arc::clone(&self.file_transfer_state);
 
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             debug!("FUSE request handler started");
 
             while let Some(request) = request_rx.recv().await {
@@ -1281,6 +1430,7 @@ impl ClipboardManager {
         let sync_manager = self.sync_manager.clone();
         let transfer_engine = self.transfer_engine.clone();
         let config = self.config.clone();
+        let policy = self.policy.clone();
         // Clone the Arc<RwLock<>> wrappers - they can be read dynamically
         let portal_clipboard = Arc::clone(&self.portal_clipboard);
         let portal_session = Arc::clone(&self.portal_session);
@@ -1295,10 +1445,38 @@ impl ClipboardManager {
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
         self.shutdown_tx = Some(shutdown_tx);
 
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             loop {
                 tokio::select! {
                     Some(event) = event_rx.recv() => {
+                        // While disabled or paused, no new clipboard contents are
+                        // announced in either direction; transfers already offered
+                        // complete
+                        if !policy.is_syncing()
+                            && matches!(
+                                event,
+                                ClipboardEvent::RdpFormatList(_)
+                                    | ClipboardEvent::PortalFormatsAvailable(..)
+                            )
+                        {
+                            debug!("Clipboard disabled or paused, ignoring {:?}", event);
+                            continue;
+                        }
+                        // File chunks count as activity, but only whole
+                        // transfers are reported
+                        match event {
+                            ClipboardEvent::RdpDataRequest(format_id, _) => policy
+                                .record_transfer(Some(ClipboardTransfer::ToClient { format_id })),
+                            ClipboardEvent::RdpDataResponse(ref data) => policy.record_transfer(
+                                Some(ClipboardTransfer::ToHost { bytes: data.len() }),
+                            ),
+                            ClipboardEvent::RdpFileContentsRequest { .. }
+                            | ClipboardEvent::RdpFileContentsResponse { .. } => {
+                                policy.record_transfer(None)
+                            }
+                            _ => {}
+                        }
+
                         if let Err(e) = Self::handle_event(
                             event,
                             &converter,
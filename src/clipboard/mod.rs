@@ -96,7 +96,10 @@ pub use error::{ClipboardError, ErrorContext, ErrorType, RecoveryAction, Result,
 pub use ironrdp_backend::LamcoCliprdrFactory;
 
 // Server clipboard manager
-pub use manager::{ClipboardConfig, ClipboardEvent, ClipboardManager};
+pub use manager::{
+    ClipboardConfig, ClipboardEvent, ClipboardManager, ClipboardPolicy, ClipboardTransfer,
+    TransferObserver,
+};
 
 // Server sync manager (state machine + echo protection)
 pub use sync::{ClipboardState, SyncDirection, SyncManager};
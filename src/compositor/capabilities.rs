@@ -67,6 +67,20 @@ impl CompositorType {
         }
     }
 
+    /// Stable lowercase ID (as used by the quirk database)
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Gnome { .. } => "gnome",
+            Self::Kde { .. } => "kde",
+            Self::Sway { .. } => "sway",
+            Self::Hyprland { .. } => "hyprland",
+            Self::Weston => "weston",
+            Self::Cosmic => "cosmic",
+            Self::Wlroots { .. } => "wlroots",
+            Self::Unknown { .. } => "unknown",
+        }
+    }
+
     /// Check if this is a wlroots-based compositor
     pub fn is_wlroots_based(&self) -> bool {
         matches!(
@@ -241,6 +255,11 @@ impl CompositorCapabilities {
         self.has_protocol("zwlr_screencopy_manager_v1", 1)
     }
 
+    /// Check if KWin's screencast protocol is offered to us
+    pub fn has_kde_screencast(&self) -> bool {
+        self.has_protocol("zkde_screencast_unstable_v1", 1)
+    }
+
     /// Check if ext-image-copy-capture is available
     pub fn has_ext_image_copy_capture(&self) -> bool {
         self.has_protocol("ext_image_copy_capture_manager_v1", 1)
@@ -275,13 +294,11 @@ impl CompositorCapabilities {
                 "native"
             }
         );
-        info!("  Portal version: {}", self.portal.version);
         info!(
-            "  Portal features: ScreenCast={}, RemoteDesktop={}, Clipboard={}",
-            self.portal.supports_screencast,
-            self.portal.supports_remote_desktop,
-            self.portal.supports_clipboard
+            "  Portal versions: ScreenCast v{}, RemoteDesktop v{}, Clipboard v{}",
+            self.portal.version, self.portal.remote_desktop_version, self.portal.clipboard_version
         );
+        self.portal.log_feature_matrix();
         info!("  Cursor modes: {:?}", self.portal.available_cursor_modes);
         info!(
             "  Recommended capture: {:?}",
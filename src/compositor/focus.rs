@@ -0,0 +1,164 @@
+//! Focused Window Query
+//!
+//! Wayland has no portable way to ask which window has focus. Compositors
+//! with an IPC interface of their own answer it: Sway through `swaymsg`,
+//! Hyprland through its IPC socket (see [`super::hyprland`]). On other
+//! compositors the focused window is unknown and features depending on it
+//! stay inactive.
+
+use std::process::Command;
+
+use serde_json::Value;
+
+use super::capabilities::CompositorType;
+
+/// Window that has keyboard focus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedWindow {
+    /// Wayland app ID, or the X11 class of XWayland windows
+    pub app_id: String,
+    /// Window title
+    pub title: String,
+    /// Where the window is in the layout, if the compositor reports it
+    pub rect: Option<WindowRect>,
+}
+
+/// Window geometry in layout (logical) coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Whether the focused window of `compositor` can be queried
+pub fn supports_focus_query(compositor: &CompositorType) -> bool {
+    matches!(
+        compositor,
+        CompositorType::Sway { .. } | CompositorType::Hyprland { .. }
+    )
+}
+
+/// Focused window of `compositor` (None without focus or query support)
+///
+/// Runs or talks to the compositor's IPC, so call it off the async runtime.
+pub fn focused_window(compositor: &CompositorType) -> Option<FocusedWindow> {
+    match compositor {
+        CompositorType::Sway { .. } => {
+            parse_sway_tree(&run_json("swaymsg", &["-t", "get_tree", "-r"])?)
+        }
+        CompositorType::Hyprland { .. } => super::hyprland::active_window(),
+        _ => None,
+    }
+}
+
+fn run_json(program: &str, args: &[&str]) -> Option<Value> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Focused node of a `swaymsg -t get_tree` tree
+fn parse_sway_tree(node: &Value) -> Option<FocusedWindow> {
+    if node["focused"].as_bool() == Some(true) {
+        let app_id = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str())?;
+        let rect = &node["rect"];
+        return Some(FocusedWindow {
+            app_id: app_id.to_string(),
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+            rect: match (
+                rect["x"].as_i64(),
+                rect["y"].as_i64(),
+                rect["width"].as_u64(),
+                rect["height"].as_u64(),
+            ) {
+                (Some(x), Some(y), Some(width), Some(height)) => Some(WindowRect {
+                    x: x as i32,
+                    y: y as i32,
+                    width: width as u32,
+                    height: height as u32,
+                }),
+                _ => None,
+            },
+        });
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(parse_sway_tree)
+}
+
+/// Window of Hyprland's `activewindow` (an empty object without focus)
+pub(super) fn parse_hyprland_window(window: &Value) -> Option<FocusedWindow> {
+    let app_id = window["class"].as_str().filter(|class| !class.is_empty())?;
+    let pair = |key: &str| Some((window[key][0].as_i64()?, window[key][1].as_i64()?));
+    Some(FocusedWindow {
+        app_id: app_id.to_string(),
+        title: window["title"].as_str().unwrap_or_default().to_string(),
+        rect: match (pair("at"), pair("size")) {
+            (Some((x, y)), Some((width, height))) => Some(WindowRect {
+                x: x as i32,
+                y: y as i32,
+                width: width as u32,
+                height: height as u32,
+            }),
+            _ => None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_focused_window() {
+        let tree = json!({
+            "focused": false,
+            "nodes": [{
+                "focused": false,
+                "nodes": [
+                    { "focused": false, "app_id": "foot", "name": "shell" },
+                ],
+                "floating_nodes": [{
+                    "focused": true,
+                    "app_id": null,
+                    "window_properties": { "class": "steam_app_570" },
+                    "name": "Dota 2",
+                    "rect": { "x": 10, "y": 20, "width": 800, "height": 600 },
+                }],
+            }],
+        });
+        assert_eq!(
+            parse_sway_tree(&tree),
+            Some(FocusedWindow {
+                app_id: "steam_app_570".to_string(),
+                title: "Dota 2".to_string(),
+                rect: Some(WindowRect {
+                    x: 10,
+                    y: 20,
+                    width: 800,
+                    height: 600,
+                }),
+            })
+        );
+
+        let window = json!({
+            "class": "org.gnome.Nautilus",
+            "title": "Home",
+            "at": [1930, 40],
+            "size": [1200, 800],
+        });
+        let window = parse_hyprland_window(&window).unwrap();
+        assert_eq!(window.app_id, "org.gnome.Nautilus");
+        assert_eq!(window.rect.map(|rect| rect.x), Some(1930));
+        assert_eq!(parse_hyprland_window(&json!({})), None);
+    }
+}
@@ -0,0 +1,407 @@
+//! GNOME Session Recommendations
+//!
+//! A GNOME desktop set up for local use gets in the way of remote use in
+//! ways the server can only report: the screen locks or blanks while nobody
+//! sits at the machine, the machine suspends when idle, Linux → Windows
+//! clipboard needs our Shell extension (see `extension/`), and GNOME's own
+//! gnome-remote-desktop may already serve RDP on port 3389.
+//!
+//! [`GnomeSettings::read`] reads the relevant keys with `gsettings`, and
+//! [`recommendations`] turns them into warnings with the command that fixes
+//! each. They are logged at startup and listed by `--diagnose`. A port held
+//! by gnome-remote-desktop can also be avoided automatically with
+//! `server.deconflict_port` (see [`free_port_after`]).
+
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// UUID of the clipboard extension
+pub const CLIPBOARD_EXTENSION_UUID: &str = "wayland-rdp-clipboard@wayland-rdp.io";
+
+/// Port gnome-remote-desktop serves RDP on unless configured otherwise
+const GRD_DEFAULT_PORT: u16 = 3389;
+
+/// Ports tried after a taken one
+const PORT_SEARCH_RANGE: u16 = 10;
+
+/// Settings of the GNOME session that matter to remote use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GnomeSettings {
+    /// Screen locks after blanking (`org.gnome.desktop.screensaver lock-enabled`)
+    pub lock_enabled: bool,
+    /// Seconds of inactivity before the screen blanks, 0 = never
+    /// (`org.gnome.desktop.session idle-delay`)
+    pub idle_delay_secs: u32,
+    /// Action on inactivity on AC power ("suspend", "nothing", ...)
+    /// (`org.gnome.settings-daemon.plugins.power sleep-inactive-ac-type`)
+    pub sleep_inactive_type: String,
+    /// Seconds of inactivity before that action
+    pub sleep_inactive_secs: u32,
+    /// `org.gnome.shell disable-user-extensions`
+    pub user_extensions_disabled: bool,
+    /// `org.gnome.shell enabled-extensions`
+    pub enabled_extensions: Vec<String>,
+    /// The clipboard extension is installed for the user or system-wide
+    pub clipboard_extension_installed: bool,
+    /// gnome-remote-desktop serves RDP
+    /// (`org.gnome.desktop.remote-desktop.rdp enable`)
+    pub grd_rdp_enabled: bool,
+    /// Port gnome-remote-desktop listens on
+    pub grd_rdp_port: u16,
+    /// A gnome-remote-desktop daemon is running
+    pub grd_running: bool,
+}
+
+impl Default for GnomeSettings {
+    /// GNOME's defaults
+    fn default() -> Self {
+        Self {
+            lock_enabled: true,
+            idle_delay_secs: 300,
+            sleep_inactive_type: "suspend".to_string(),
+            sleep_inactive_secs: 900,
+            user_extensions_disabled: false,
+            enabled_extensions: Vec::new(),
+            clipboard_extension_installed: false,
+            grd_rdp_enabled: false,
+            grd_rdp_port: GRD_DEFAULT_PORT,
+            grd_running: false,
+        }
+    }
+}
+
+impl GnomeSettings {
+    /// Read the settings of the current user's session
+    ///
+    /// Keys that cannot be read (schema not installed, no dconf access) keep
+    /// GNOME's defaults.
+    pub fn read() -> Self {
+        let defaults = Self::default();
+        Self {
+            lock_enabled: gsettings("org.gnome.desktop.screensaver", "lock-enabled")
+                .and_then(|value| parse_bool(&value))
+                .unwrap_or(defaults.lock_enabled),
+            idle_delay_secs: gsettings("org.gnome.desktop.session", "idle-delay")
+                .and_then(|value| parse_uint(&value))
+                .unwrap_or(defaults.idle_delay_secs),
+            sleep_inactive_type: gsettings(
+                "org.gnome.settings-daemon.plugins.power",
+                "sleep-inactive-ac-type",
+            )
+            .map(|value| parse_string(&value))
+            .unwrap_or(defaults.sleep_inactive_type),
+            sleep_inactive_secs: gsettings(
+                "org.gnome.settings-daemon.plugins.power",
+                "sleep-inactive-ac-timeout",
+            )
+            .and_then(|value| parse_int(&value))
+            .map(|secs| secs.max(0) as u32)
+            .unwrap_or(defaults.sleep_inactive_secs),
+            user_extensions_disabled: gsettings("org.gnome.shell", "disable-user-extensions")
+                .and_then(|value| parse_bool(&value))
+                .unwrap_or(defaults.user_extensions_disabled),
+            enabled_extensions: gsettings("org.gnome.shell", "enabled-extensions")
+                .map(|value| parse_string_array(&value))
+                .unwrap_or_default(),
+            clipboard_extension_installed: extension_dirs()
+                .iter()
+                .any(|dir| dir.join(CLIPBOARD_EXTENSION_UUID).is_dir()),
+            grd_rdp_enabled: gsettings("org.gnome.desktop.remote-desktop.rdp", "enable")
+                .and_then(|value| parse_bool(&value))
+                .unwrap_or(defaults.grd_rdp_enabled),
+            // The key exists since GNOME 46
+            grd_rdp_port: gsettings("org.gnome.desktop.remote-desktop.rdp", "port")
+                .and_then(|value| parse_uint(&value))
+                .and_then(|port| u16::try_from(port).ok())
+                .unwrap_or(defaults.grd_rdp_port),
+            grd_running: grd_running(),
+        }
+    }
+
+    /// Whether gnome-remote-desktop is likely to hold `port`
+    pub fn grd_holds_port(&self, port: u16) -> bool {
+        self.grd_rdp_enabled && self.grd_running && self.grd_rdp_port == port
+    }
+}
+
+/// How much a finding matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Remote use works, with a limitation
+    Info,
+    /// Remote use will fail or degrade
+    Warning,
+}
+
+/// A setting in the way of remote use, and how to change it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recommendation {
+    pub severity: Severity,
+    /// What goes wrong
+    pub problem: String,
+    /// Command or setting that fixes it
+    pub action: String,
+}
+
+impl Recommendation {
+    fn new(severity: Severity, problem: String, action: impl Into<String>) -> Self {
+        Self {
+            severity,
+            problem,
+            action: action.into(),
+        }
+    }
+}
+
+/// Recommendations for a server listening on `port`, warnings first
+///
+/// `clipboard` is whether clipboard sync is enabled.
+pub fn recommendations(
+    settings: &GnomeSettings,
+    port: u16,
+    clipboard: bool,
+) -> Vec<Recommendation> {
+    let mut found = Vec::new();
+
+    if settings.grd_rdp_enabled && settings.grd_rdp_port == port {
+        found.push(Recommendation::new(
+            Severity::Warning,
+            format!(
+                "gnome-remote-desktop serves RDP on port {} too{}",
+                port,
+                if settings.grd_running {
+                    ""
+                } else {
+                    " (not running now)"
+                }
+            ),
+            "turn off Remote Desktop in Settings → System → Remote Desktop (grdctl rdp disable), \
+             use another server.listen_addr, or set server.deconflict_port = true",
+        ));
+    }
+
+    // "blank" and "interactive" leave the session running
+    let ends_session = matches!(
+        settings.sleep_inactive_type.as_str(),
+        "suspend" | "hibernate" | "shutdown" | "logout"
+    );
+    if ends_session && settings.sleep_inactive_secs > 0 {
+        found.push(Recommendation::new(
+            Severity::Warning,
+            format!(
+                "on AC power the machine does '{}' after {} of inactivity, ending remote sessions",
+                settings.sleep_inactive_type,
+                duration(settings.sleep_inactive_secs)
+            ),
+            "gsettings set org.gnome.settings-daemon.plugins.power sleep-inactive-ac-type nothing",
+        ));
+    }
+
+    if settings.idle_delay_secs > 0 {
+        if settings.lock_enabled {
+            found.push(Recommendation::new(
+                Severity::Warning,
+                format!(
+                    "the screen locks after {} of inactivity; remote users then see the lock screen",
+                    duration(settings.idle_delay_secs)
+                ),
+                "gsettings set org.gnome.desktop.screensaver lock-enabled false \
+                 (Settings → Privacy & Security → Screen Lock)",
+            ));
+        }
+        found.push(Recommendation::new(
+            Severity::Info,
+            format!(
+                "the screen blanks after {} of inactivity, which may pause the capture",
+                duration(settings.idle_delay_secs)
+            ),
+            "gsettings set org.gnome.desktop.session idle-delay 0, or enable [capture_watchdog]",
+        ));
+    }
+
+    if clipboard {
+        let enabled = settings
+            .enabled_extensions
+            .iter()
+            .any(|uuid| uuid == CLIPBOARD_EXTENSION_UUID);
+        if !settings.clipboard_extension_installed {
+            found.push(Recommendation::new(
+                Severity::Info,
+                "copying on the host reaches the client only once the clipboard extension is installed"
+                    .to_string(),
+                "install it as described in extension/README.md",
+            ));
+        } else if settings.user_extensions_disabled {
+            found.push(Recommendation::new(
+                Severity::Warning,
+                "user extensions are disabled, so the clipboard extension does not run".to_string(),
+                "gsettings set org.gnome.shell disable-user-extensions false",
+            ));
+        } else if !enabled {
+            found.push(Recommendation::new(
+                Severity::Warning,
+                "the clipboard extension is installed but not enabled".to_string(),
+                format!("gnome-extensions enable {}", CLIPBOARD_EXTENSION_UUID),
+            ));
+        }
+    }
+
+    found.sort_by(|a, b| b.severity.cmp(&a.severity));
+    found
+}
+
+/// First port after the one of `addr` that can be listened on
+pub fn free_port_after(addr: SocketAddr) -> Option<SocketAddr> {
+    (1..=PORT_SEARCH_RANGE)
+        .filter_map(|offset| addr.port().checked_add(offset))
+        .map(|port| SocketAddr::new(addr.ip(), port))
+        .find(|candidate| TcpListener::bind(candidate).is_ok())
+}
+
+fn duration(secs: u32) -> String {
+    match secs {
+        0..=119 => format!("{}s", secs),
+        _ => format!("{} min", secs / 60),
+    }
+}
+
+/// Value of `key`, as printed by `gsettings get`
+fn gsettings(schema: &str, key: &str) -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", schema, key])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn grd_running() -> bool {
+    // Process names are cut to 15 characters
+    Command::new("pgrep")
+        .args(["-f", "gnome-remote-desktop-daemon"])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn extension_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(data) = std::env::var_os("XDG_DATA_HOME") {
+        dirs.push(PathBuf::from(data).join("gnome-shell/extensions"));
+    } else if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".local/share/gnome-shell/extensions"));
+    }
+    dirs.push(PathBuf::from("/usr/share/gnome-shell/extensions"));
+    dirs.push(PathBuf::from("/usr/local/share/gnome-shell/extensions"));
+    dirs
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    value.parse().ok()
+}
+
+/// "uint32 300" or "300"
+fn parse_uint(value: &str) -> Option<u32> {
+    value.rsplit(' ').next()?.parse().ok()
+}
+
+fn parse_int(value: &str) -> Option<i64> {
+    value.rsplit(' ').next()?.parse().ok()
+}
+
+/// "'suspend'"
+fn parse_string(value: &str) -> String {
+    value.trim_matches('\'').to_string()
+}
+
+/// "['a@b', 'c@d']" or "@as []"
+fn parse_string_array(value: &str) -> Vec<String> {
+    let value = value.trim_start_matches("@as").trim();
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| parse_string(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gsettings_values() {
+        assert_eq!(parse_uint("uint32 300"), Some(300));
+        assert_eq!(parse_int("1200"), Some(1200));
+        assert_eq!(parse_bool("true"), Some(true));
+        assert_eq!(parse_string("'nothing'"), "nothing");
+        assert_eq!(
+            parse_string_array("['a@b.org', 'wayland-rdp-clipboard@wayland-rdp.io']"),
+            vec!["a@b.org", CLIPBOARD_EXTENSION_UUID]
+        );
+        assert!(parse_string_array("@as []").is_empty());
+    }
+
+    #[test]
+    fn test_recommendations_for_defaults() {
+        let found = recommendations(&GnomeSettings::default(), 3389, true);
+        assert!(found[0].severity == Severity::Warning);
+        assert!(found
+            .iter()
+            .any(|r| r.action.contains("sleep-inactive-ac-type")));
+        assert!(found
+            .iter()
+            .any(|r| r.action.contains("lock-enabled false")));
+        assert!(found
+            .iter()
+            .any(|r| r.problem.contains("clipboard extension")));
+        assert!(!found
+            .iter()
+            .any(|r| r.problem.contains("gnome-remote-desktop")));
+    }
+
+    #[test]
+    fn test_remote_ready_settings_have_no_recommendations() {
+        let settings = GnomeSettings {
+            lock_enabled: false,
+            idle_delay_secs: 0,
+            sleep_inactive_type: "nothing".to_string(),
+            enabled_extensions: vec![CLIPBOARD_EXTENSION_UUID.to_string()],
+            clipboard_extension_installed: true,
+            ..Default::default()
+        };
+        assert!(recommendations(&settings, 3389, true).is_empty());
+    }
+
+    #[test]
+    fn test_grd_port_conflict() {
+        let settings = GnomeSettings {
+            grd_rdp_enabled: true,
+            grd_running: true,
+            ..Default::default()
+        };
+        assert!(settings.grd_holds_port(3389));
+        assert!(!settings.grd_holds_port(3390));
+        let found = recommendations(&settings, 3389, false);
+        assert!(found[0]
+            .problem
+            .starts_with("gnome-remote-desktop serves RDP on port 3389"));
+        assert!(recommendations(&settings, 3390, false)
+            .iter()
+            .all(|r| !r.problem.contains("gnome-remote-desktop")));
+    }
+
+    #[test]
+    fn test_free_port_after() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = taken.local_addr().unwrap();
+        if let Some(free) = free_port_after(addr) {
+            assert!(free.port() > addr.port());
+            assert_eq!(free.ip(), addr.ip());
+        }
+    }
+}
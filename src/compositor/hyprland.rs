@@ -0,0 +1,161 @@
+//! Hyprland IPC
+//!
+//! Hyprland answers queries on a Unix socket of its instance,
+//! `$XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE/.socket.sock`
+//! (`/tmp/hypr/...` before Hyprland 0.40); a `j/` prefix asks for JSON.
+//! Asking the socket directly saves spawning `hyprctl` for every focus
+//! poll, and its monitor list has what wl_output lacks: which monitors are
+//! disabled or mirror another one, and which has focus.
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::focus::{parse_hyprland_window, FocusedWindow};
+
+/// Variable Hyprland sets for the clients of an instance
+pub const SIGNATURE_VAR: &str = "HYPRLAND_INSTANCE_SIGNATURE";
+
+/// Longest wait for an answer
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A monitor of `j/monitors`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyprMonitor {
+    /// Connector name ("DP-1"), the wl_output name
+    pub name: String,
+    /// Position in the layout (logical pixels)
+    pub x: i32,
+    pub y: i32,
+    /// Mode size (physical pixels)
+    pub width: u32,
+    pub height: u32,
+    pub scale: f64,
+    /// Has keyboard focus
+    pub focused: bool,
+    /// Shows the contents of this monitor instead of its own
+    pub mirror_of: Option<String>,
+    pub disabled: bool,
+}
+
+impl HyprMonitor {
+    /// Whether the monitor shows contents of its own
+    pub fn is_active(&self) -> bool {
+        !self.disabled && self.mirror_of.is_none()
+    }
+}
+
+/// Whether the server runs in a Hyprland session whose socket is reachable
+pub fn is_running() -> bool {
+    socket_path().is_some_and(|path| path.exists())
+}
+
+fn socket_path() -> Option<PathBuf> {
+    let signature = std::env::var(SIGNATURE_VAR).ok()?;
+    let runtime = std::env::var_os("XDG_RUNTIME_DIR").map(|dir| {
+        PathBuf::from(dir)
+            .join("hypr")
+            .join(&signature)
+            .join(".socket.sock")
+    });
+    let legacy = PathBuf::from("/tmp/hypr")
+        .join(&signature)
+        .join(".socket.sock");
+    Some(match runtime {
+        Some(path) if path.exists() => path,
+        _ => legacy,
+    })
+}
+
+/// JSON answer to `command` ("monitors", "activewindow", ...)
+///
+/// Blocks for up to a second, so call it off the async runtime.
+pub fn query(command: &str) -> Result<Value> {
+    let path = socket_path().with_context(|| format!("{} is not set", SIGNATURE_VAR))?;
+    let mut socket = UnixStream::connect(&path)
+        .with_context(|| format!("Failed to connect to {}", path.display()))?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    socket.set_write_timeout(Some(TIMEOUT))?;
+    socket
+        .write_all(format!("j/{}", command).as_bytes())
+        .context("Failed to send Hyprland IPC request")?;
+
+    let mut answer = Vec::new();
+    socket
+        .read_to_end(&mut answer)
+        .context("Failed to read Hyprland IPC answer")?;
+    serde_json::from_slice(&answer).with_context(|| {
+        format!(
+            "Unexpected answer to {}: {}",
+            command,
+            String::from_utf8_lossy(&answer)
+        )
+    })
+}
+
+/// Monitors of the session, disabled ones included
+pub fn monitors() -> Result<Vec<HyprMonitor>> {
+    // Disabled monitors are only listed with "all"
+    Ok(parse_monitors(&query("monitors all")?))
+}
+
+/// Window with keyboard focus
+pub fn active_window() -> Option<FocusedWindow> {
+    parse_hyprland_window(&query("activewindow").ok()?)
+}
+
+fn parse_monitors(monitors: &Value) -> Vec<HyprMonitor> {
+    let int = |monitor: &Value, key: &str| monitor[key].as_i64().unwrap_or_default();
+    monitors
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|monitor| {
+            Some(HyprMonitor {
+                name: monitor["name"].as_str()?.to_string(),
+                x: int(monitor, "x") as i32,
+                y: int(monitor, "y") as i32,
+                width: int(monitor, "width") as u32,
+                height: int(monitor, "height") as u32,
+                scale: monitor["scale"].as_f64().unwrap_or(1.0),
+                focused: monitor["focused"].as_bool().unwrap_or(false),
+                mirror_of: monitor["mirrorOf"]
+                    .as_str()
+                    .filter(|source| *source != "none")
+                    .map(str::to_string),
+                disabled: monitor["disabled"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_monitors() {
+        let monitors = parse_monitors(&json!([
+            {
+                "id": 0, "name": "DP-1", "width": 3840, "height": 2160,
+                "x": 0, "y": 0, "scale": 2.0, "focused": true,
+                "mirrorOf": "none", "disabled": false
+            },
+            {
+                "id": 1, "name": "HDMI-A-1", "width": 1920, "height": 1080,
+                "x": 1920, "y": 0, "scale": 1.0, "focused": false,
+                "mirrorOf": "DP-1", "disabled": false
+            },
+        ]));
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[0].width, 3840);
+        assert!(monitors[0].focused && monitors[0].is_active());
+        assert_eq!(monitors[1].mirror_of.as_deref(), Some("DP-1"));
+        assert!(!monitors[1].is_active());
+    }
+}
@@ -11,7 +11,8 @@
 //! 1. **Identifies** the compositor type from environment and D-Bus
 //! 2. **Probes** available protocols and features
 //! 3. **Creates** a capability profile with recommended settings
-//! 4. **Applies** quirks and workarounds automatically
+//! 4. **Applies** quirks and workarounds automatically, from a database of
+//!    version-specific behavior that sites can extend (see [`QuirkDatabase`])
 //!
 //! # Architecture
 //!
@@ -27,6 +28,9 @@
 //!
 //! Profile Generation
 //!   └─> CompositorProfile with recommended settings
+//!
+//! Quirk Database
+//!   └─> Built-in entries + /etc/lamco-rdp-server/quirks.d/*.toml
 //! ```
 //!
 //! # Usage
@@ -50,16 +54,25 @@
 //! ```
 
 mod capabilities;
+mod focus;
+pub mod gnome;
+pub mod hyprland;
 mod portal_caps;
 mod probing;
 mod profiles;
+mod quirks;
 
 pub use capabilities::{
     BufferType, CaptureBackend, CompositorCapabilities, CompositorType, WaylandGlobal,
 };
-pub use portal_caps::{CursorMode, PortalCapabilities, SourceType};
-pub use probing::{detect_os_release, identify_compositor, probe_capabilities, OsRelease};
+pub use focus::{focused_window, supports_focus_query, FocusedWindow, WindowRect};
+pub use portal_caps::{CursorMode, PortalCapabilities, PortalFeature, SourceType};
+pub use probing::{
+    detect_nvidia_driver, detect_os_release, identify_compositor, probe_capabilities,
+    reprobe_capabilities, OsRelease,
+};
 pub use profiles::{CompositorProfile, Quirk};
+pub use quirks::{CursorBehavior, QuirkDatabase, QuirkEntry, QUIRKS_DIR};
 
 /// Check if we're running in a Wayland session
 pub fn is_wayland_session() -> bool {
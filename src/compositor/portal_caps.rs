@@ -7,7 +7,7 @@
 use anyhow::{Context, Result};
 use ashpd::desktop::screencast::CursorMode as AshpdCursorMode;
 use ashpd::desktop::screencast::SourceType as AshpdSourceType;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 use zbus::Connection;
 
 /// Cursor rendering mode
@@ -52,12 +52,78 @@ impl From<AshpdSourceType> for SourceType {
     }
 }
 
+const SCREENCAST_INTERFACE: &str = "org.freedesktop.portal.ScreenCast";
+const REMOTE_DESKTOP_INTERFACE: &str = "org.freedesktop.portal.RemoteDesktop";
+const CLIPBOARD_INTERFACE: &str = "org.freedesktop.portal.Clipboard";
+
+/// Portal feature gated on an interface version
+///
+/// Strategies ask the capability matrix for these instead of calling
+/// the portal and seeing whether it fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortalFeature {
+    /// Screen capture through PipeWire
+    ScreenCast,
+    /// Input injection through NotifyPointer*/NotifyKeyboard*
+    RemoteDesktop,
+    /// Restore tokens with persist_mode (ScreenCast v4)
+    RestoreTokens,
+    /// Input through an EIS socket (RemoteDesktop v2)
+    ConnectToEis,
+    /// Clipboard on a RemoteDesktop session
+    Clipboard,
+    /// Cursor sent as PipeWire metadata (ScreenCast v2)
+    MetadataCursor,
+}
+
+impl PortalFeature {
+    /// Every feature, in matrix order
+    pub const ALL: [Self; 6] = [
+        Self::ScreenCast,
+        Self::RemoteDesktop,
+        Self::RestoreTokens,
+        Self::ConnectToEis,
+        Self::Clipboard,
+        Self::MetadataCursor,
+    ];
+
+    /// Interface that provides the feature and the first version that has it
+    pub fn requirement(self) -> (&'static str, u32) {
+        match self {
+            Self::ScreenCast => (SCREENCAST_INTERFACE, 1),
+            Self::RemoteDesktop => (REMOTE_DESKTOP_INTERFACE, 1),
+            Self::RestoreTokens => (SCREENCAST_INTERFACE, 4),
+            Self::ConnectToEis => (REMOTE_DESKTOP_INTERFACE, 2),
+            Self::Clipboard => (CLIPBOARD_INTERFACE, 1),
+            Self::MetadataCursor => (SCREENCAST_INTERFACE, 2),
+        }
+    }
+
+    /// Short name for logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ScreenCast => "ScreenCast",
+            Self::RemoteDesktop => "RemoteDesktop",
+            Self::RestoreTokens => "Restore tokens",
+            Self::ConnectToEis => "ConnectToEIS",
+            Self::Clipboard => "Clipboard",
+            Self::MetadataCursor => "Metadata cursor",
+        }
+    }
+}
+
 /// Portal capability information
 #[derive(Debug, Clone)]
 pub struct PortalCapabilities {
-    /// Portal interface version
+    /// ScreenCast interface version (0 = unavailable)
     pub version: u32,
 
+    /// RemoteDesktop interface version (0 = unavailable)
+    pub remote_desktop_version: u32,
+
+    /// Clipboard interface version (0 = unavailable)
+    pub clipboard_version: u32,
+
     /// ScreenCast portal available
     pub supports_screencast: bool,
 
@@ -88,6 +154,8 @@ impl Default for PortalCapabilities {
     fn default() -> Self {
         Self {
             version: 0,
+            remote_desktop_version: 0,
+            clipboard_version: 0,
             supports_screencast: false,
             supports_remote_desktop: false,
             supports_clipboard: false,
@@ -135,13 +203,7 @@ impl PortalCapabilities {
 
     async fn probe_screencast(&mut self, connection: &Connection) {
         // Query ScreenCast portal interface
-        match query_portal_property::<u32>(
-            connection,
-            "org.freedesktop.portal.ScreenCast",
-            "version",
-        )
-        .await
-        {
+        match query_portal_property::<u32>(connection, SCREENCAST_INTERFACE, "version").await {
             Ok(version) => {
                 self.version = version;
                 self.supports_screencast = true;
@@ -150,7 +212,7 @@ impl PortalCapabilities {
                 // Query available source types
                 if let Ok(source_types) = query_portal_property::<u32>(
                     connection,
-                    "org.freedesktop.portal.ScreenCast",
+                    SCREENCAST_INTERFACE,
                     "AvailableSourceTypes",
                 )
                 .await
@@ -161,7 +223,7 @@ impl PortalCapabilities {
                 // Query available cursor modes
                 if let Ok(cursor_modes) = query_portal_property::<u32>(
                     connection,
-                    "org.freedesktop.portal.ScreenCast",
+                    SCREENCAST_INTERFACE,
                     "AvailableCursorModes",
                 )
                 .await
@@ -177,14 +239,9 @@ impl PortalCapabilities {
     }
 
     async fn probe_remote_desktop(&mut self, connection: &Connection) {
-        match query_portal_property::<u32>(
-            connection,
-            "org.freedesktop.portal.RemoteDesktop",
-            "version",
-        )
-        .await
-        {
+        match query_portal_property::<u32>(connection, REMOTE_DESKTOP_INTERFACE, "version").await {
             Ok(version) => {
+                self.remote_desktop_version = version;
                 self.supports_remote_desktop = true;
                 debug!("RemoteDesktop portal version: {}", version);
             }
@@ -196,27 +253,18 @@ impl PortalCapabilities {
     }
 
     async fn probe_clipboard(&mut self, connection: &Connection) {
-        // Clipboard is part of RemoteDesktop portal (requires version >= 2)
-        // Check if RemoteDesktop supports clipboard by checking version
-        match query_portal_property::<u32>(
-            connection,
-            "org.freedesktop.portal.RemoteDesktop",
-            "version",
-        )
-        .await
-        {
-            Ok(version) if version >= 2 => {
-                self.supports_clipboard = true;
-                debug!("Clipboard support available (RemoteDesktop v{})", version);
-            }
+        // Clipboard is its own interface (xdg-desktop-portal 1.18+), enabled
+        // on a RemoteDesktop session, so RemoteDesktop v2 alone isn't enough
+        match query_portal_property::<u32>(connection, CLIPBOARD_INTERFACE, "version").await {
             Ok(version) => {
-                self.supports_clipboard = false;
-                debug!("Clipboard not available (RemoteDesktop v{} < 2)", version);
+                self.clipboard_version = version;
+                debug!("Clipboard portal version: {}", version);
             }
-            Err(_) => {
-                self.supports_clipboard = false;
+            Err(e) => {
+                debug!("Clipboard portal not available: {}", e);
             }
         }
+        self.supports_clipboard = self.supports(PortalFeature::Clipboard);
     }
 
     async fn detect_backend(&mut self, connection: &Connection) {
@@ -295,13 +343,63 @@ impl PortalCapabilities {
         self.available_source_types.contains(&SourceType::Window)
     }
 
+    /// Interface version the portal reported (0 = unavailable)
+    pub fn interface_version(&self, interface: &str) -> u32 {
+        match interface {
+            SCREENCAST_INTERFACE => self.version,
+            REMOTE_DESKTOP_INTERFACE => self.remote_desktop_version,
+            CLIPBOARD_INTERFACE => self.clipboard_version,
+            _ => 0,
+        }
+    }
+
+    /// Look up a feature in the capability matrix
+    pub fn supports(&self, feature: PortalFeature) -> bool {
+        let (interface, min_version) = feature.requirement();
+        if self.interface_version(interface) < min_version {
+            return false;
+        }
+        match feature {
+            // Clipboard is requested on a RemoteDesktop session
+            PortalFeature::Clipboard => {
+                self.supports(PortalFeature::RemoteDesktop) && self.remote_desktop_version >= 2
+            }
+            PortalFeature::MetadataCursor => self.supports_metadata_cursor(),
+            _ => true,
+        }
+    }
+
+    /// Check if input can go through an EIS socket (libei)
+    pub fn supports_connect_to_eis(&self) -> bool {
+        self.supports(PortalFeature::ConnectToEis)
+    }
+
+    /// Log which portal features are usable and why the others aren't
+    pub fn log_feature_matrix(&self) {
+        info!("Portal feature matrix:");
+        for feature in PortalFeature::ALL {
+            let (interface, min_version) = feature.requirement();
+            let short = interface.rsplit('.').next().unwrap_or(interface);
+            if self.supports(feature) {
+                info!("  ✅ {}", feature.name());
+            } else {
+                info!(
+                    "  ❌ {} (needs {} v{}, have v{})",
+                    feature.name(),
+                    short,
+                    min_version,
+                    self.interface_version(interface)
+                );
+            }
+        }
+    }
+
     /// Probe restore token support (Phase 2)
     ///
     /// Portal v4+ supports restore tokens for session persistence.
     /// This method sets the supports_restore_tokens and max_persist_mode fields.
     fn probe_persistence_support(&mut self) {
-        // Check portal version
-        if self.version >= 4 {
+        if self.supports(PortalFeature::RestoreTokens) {
             self.supports_restore_tokens = true;
             self.max_persist_mode = 2; // ExplicitlyRevoked mode available
             debug!(
@@ -385,6 +483,31 @@ mod tests {
         assert_eq!(caps.available_cursor_modes.len(), 3);
     }
 
+    #[test]
+    fn test_feature_matrix() {
+        let mut caps = PortalCapabilities::default();
+        assert!(PortalFeature::ALL.iter().all(|f| !caps.supports(*f)));
+
+        // xdg-desktop-portal 1.16: ScreenCast v4, RemoteDesktop v2, no Clipboard
+        caps.supports_screencast = true;
+        caps.supports_remote_desktop = true;
+        caps.version = 4;
+        caps.remote_desktop_version = 2;
+        caps.parse_cursor_modes(7);
+        assert!(caps.supports(PortalFeature::RestoreTokens));
+        assert!(caps.supports_connect_to_eis());
+        assert!(caps.supports(PortalFeature::MetadataCursor));
+        assert!(!caps.supports(PortalFeature::Clipboard));
+
+        caps.clipboard_version = 1;
+        assert!(caps.supports(PortalFeature::Clipboard));
+
+        // RHEL 9: RemoteDesktop v1 has neither EIS nor clipboard
+        caps.remote_desktop_version = 1;
+        assert!(!caps.supports_connect_to_eis());
+        assert!(!caps.supports(PortalFeature::Clipboard));
+    }
+
     #[test]
     fn test_default_caps() {
         let caps = PortalCapabilities::default();
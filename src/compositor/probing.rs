@@ -33,25 +33,9 @@ use super::portal_caps::PortalCapabilities;
 pub async fn probe_capabilities() -> Result<CompositorCapabilities> {
     info!("Probing compositor capabilities...");
 
-    // Step 1: Identify compositor from environment
-    let compositor = identify_compositor();
-    info!("Detected compositor: {}", compositor);
-
-    // Step 2: Probe Portal capabilities
-    let portal = match PortalCapabilities::probe().await {
-        Ok(caps) => caps,
-        Err(e) => {
-            warn!("Failed to probe Portal capabilities: {}", e);
-            PortalCapabilities::default()
-        }
-    };
-
-    // Step 3: Enumerate Wayland globals (if possible)
-    let wayland_globals = enumerate_wayland_globals().unwrap_or_default();
-    debug!("Found {} Wayland globals", wayland_globals.len());
-
-    // Step 4: Create capability structure (includes profile generation and deployment detection)
-    let mut capabilities = CompositorCapabilities::new(compositor, portal, wayland_globals);
+    // Steps 1-4: compositor, portal, Wayland globals and profile
+    let mut capabilities = probe_session().await;
+    info!("Detected compositor: {}", capabilities.compositor);
 
     // Step 5: Detect credential storage (Phase 2)
     let (storage_method, encryption, accessible) =
@@ -71,6 +55,43 @@ pub async fn probe_capabilities() -> Result<CompositorCapabilities> {
     Ok(capabilities)
 }
 
+/// Probe the session again for a server that is already running
+///
+/// Same as [`probe_capabilities`], quirk database included, but the
+/// credential storage detected by `previous` is kept and no summary is
+/// logged: the result is compared against `previous` to tell whether the
+/// session changed (see `server::reprobe`).
+pub async fn reprobe_capabilities(previous: &CompositorCapabilities) -> CompositorCapabilities {
+    let mut capabilities = probe_session().await;
+    debug!("Detected compositor: {}", capabilities.compositor);
+    capabilities.credential_storage_method = previous.credential_storage_method;
+    capabilities.credential_encryption = previous.credential_encryption;
+    capabilities.credential_storage_accessible = previous.credential_storage_accessible;
+    capabilities
+}
+
+/// Identify the compositor, probe the portal and the Wayland globals
+async fn probe_session() -> CompositorCapabilities {
+    // Step 1: Identify compositor from environment
+    let compositor = identify_compositor();
+
+    // Step 2: Probe Portal capabilities
+    let portal = match PortalCapabilities::probe().await {
+        Ok(caps) => caps,
+        Err(e) => {
+            warn!("Failed to probe Portal capabilities: {}", e);
+            PortalCapabilities::default()
+        }
+    };
+
+    // Step 3: Enumerate Wayland globals (if possible)
+    let wayland_globals = enumerate_wayland_globals().unwrap_or_default();
+    debug!("Found {} Wayland globals", wayland_globals.len());
+
+    // Step 4: Create capability structure (includes profile generation and deployment detection)
+    CompositorCapabilities::new(compositor, portal, wayland_globals)
+}
+
 /// Identify the running compositor
 ///
 /// Detection order:
@@ -408,13 +429,41 @@ pub fn detect_os_release() -> Option<OsRelease> {
     Some(release)
 }
 
-/// Enumerate Wayland globals via wlr-randr or similar tools
+/// Detect the NVIDIA proprietary kernel driver
+///
+/// Returns the driver version from `/proc/driver/nvidia/version`, or
+/// `None` when the driver isn't loaded (nouveau, Mesa drivers, no GPU).
+/// The proprietary driver has no implicit DMA-BUF fencing, so buffers from
+/// explicit-sync compositors are only complete once their sync point
+/// signals.
+pub fn detect_nvidia_driver() -> Option<String> {
+    let content = fs::read_to_string("/proc/driver/nvidia/version").ok()?;
+    let version = parse_nvidia_version(&content);
+    debug!("NVIDIA driver loaded: {:?}", version);
+    Some(version.unwrap_or_else(|| "unknown".to_string()))
+}
+
+/// Version from the `NVRM version:` line of `/proc/driver/nvidia/version`
+fn parse_nvidia_version(content: &str) -> Option<String> {
+    let line = content.lines().find(|l| l.starts_with("NVRM version:"))?;
+    line.split_whitespace()
+        .find(|word| word.contains('.') && word.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        .map(String::from)
+}
+
+/// Enumerate Wayland globals
 ///
-/// Note: This is a best-effort enumeration. Full Wayland global
-/// enumeration would require linking wayland-client and doing
-/// a registry roundtrip. For most use cases, the Portal-based
+/// With the `wayland` feature this is a registry roundtrip on the session's
+/// Wayland display. Otherwise (or without a display) it is a best-effort
+/// guess from installed wlroots tools; for most use cases, the Portal-based
 /// detection is sufficient.
 fn enumerate_wayland_globals() -> Result<Vec<WaylandGlobal>> {
+    #[cfg(feature = "wayland")]
+    match registry_globals() {
+        Ok(globals) => return Ok(globals),
+        Err(e) => debug!("Wayland registry enumeration failed: {}", e),
+    }
+
     // Try to get info from wlr-randr or other tools
     // This is a simplified implementation - full Wayland enumeration
     // would require wayland-client dependency
@@ -469,6 +518,40 @@ fn enumerate_wayland_globals() -> Result<Vec<WaylandGlobal>> {
     Ok(globals)
 }
 
+/// Globals advertised by the compositor's Wayland registry
+#[cfg(feature = "wayland")]
+fn registry_globals() -> Result<Vec<WaylandGlobal>> {
+    use wayland_client::globals::{registry_queue_init, GlobalListContents};
+    use wayland_client::protocol::wl_registry;
+    use wayland_client::{Connection, Dispatch, QueueHandle};
+
+    struct Probe;
+
+    impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Probe {
+        fn event(
+            _state: &mut Self,
+            _proxy: &wl_registry::WlRegistry,
+            _event: wl_registry::Event,
+            _data: &GlobalListContents,
+            _conn: &Connection,
+            _qhandle: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    let conn = Connection::connect_to_env()?;
+    let (globals, _queue) = registry_queue_init::<Probe>(&conn)?;
+    Ok(globals.contents().with_list(|list| {
+        list.iter()
+            .map(|global| WaylandGlobal {
+                interface: global.interface.clone(),
+                version: global.version,
+                name: global.name,
+            })
+            .collect()
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -492,6 +575,18 @@ mod tests {
         assert_eq!(unknown.to_string(), "Unknown");
     }
 
+    #[test]
+    fn test_parse_nvidia_version() {
+        let content = "NVRM version: NVIDIA UNIX x86_64 Kernel Module  550.54.14  Thu Feb 22 01:44:30 UTC 2024\nGCC version:  gcc version 13.2.1 (GCC)\n";
+        assert_eq!(parse_nvidia_version(content).as_deref(), Some("550.54.14"));
+
+        let open =
+            "NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  560.35.03  Release Build\n";
+        assert_eq!(parse_nvidia_version(open).as_deref(), Some("560.35.03"));
+
+        assert_eq!(parse_nvidia_version("GCC version: 13.2.1\n"), None);
+    }
+
     #[test]
     fn test_enumerate_wayland_globals() {
         // Should not panic, even if tools aren't available
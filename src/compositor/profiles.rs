@@ -8,7 +8,8 @@
 //! AVC444 blur issue on RHEL 9.
 
 use super::capabilities::{BufferType, CaptureBackend, CompositorType};
-use super::probing::detect_os_release;
+use super::probing::{detect_nvidia_driver, detect_os_release};
+use super::quirks::QuirkDatabase;
 
 /// Known compositor quirks that require workarounds
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -25,6 +26,19 @@ pub enum Quirk {
     /// Cursor compositing needed (no metadata cursor)
     NeedsExplicitCursorComposite,
 
+    /// Screencast frames contain the cursor whatever cursor mode is requested
+    ///
+    /// The compositor paints the cursor into frames even in metadata mode,
+    /// and may attach cursor metadata as well. A pointer drawn by the client
+    /// (or painted again by the server) then shows up twice, the client's
+    /// trailing the video's. The client's pointer is hidden instead and the
+    /// cursor in the video is the only one shown.
+    ///
+    /// Known affected platforms:
+    /// - KDE Plasma 5 (KWin screencast)
+    /// - COSMIC
+    CursorAlwaysInFrame,
+
     /// Frame timing is inconsistent
     InconsistentFrameTiming,
 
@@ -72,9 +86,76 @@ pub enum Quirk {
     /// - RHEL 9.x (Portal v1, GNOME 40)
     /// - Any system with Portal < version 2
     ClipboardUnavailable,
+
+    /// Portal SelectionOwnerChanged is not emitted for every local copy
+    ///
+    /// Mutter drops owner-change signals for clipboard set by some
+    /// clients, so Linux → Windows copy also listens to the GNOME
+    /// clipboard extension over D-Bus. Elsewhere the portal signal is
+    /// the only change source.
+    UnreliableClipboardOwnerSignals,
+
+    /// DMA-BUF frames are only complete once their sync timeline point signals
+    ///
+    /// Set for explicit-sync compositors on the NVIDIA proprietary driver,
+    /// which has no implicit fencing to fall back on. The capture path does
+    /// not wait on `SPA_META_SyncTimeline` acquire points, so reading such
+    /// a buffer early shows half-rendered frames (tearing, stale tiles).
+    /// Streams negotiate MemFd buffers instead, which the compositor has
+    /// finished writing when it queues them.
+    DmaBufNeedsExplicitSync,
 }
 
 impl Quirk {
+    /// Every quirk
+    pub const ALL: [Quirk; 17] = [
+        Self::RequiresWaylandSession,
+        Self::SlowPortalPermissions,
+        Self::PoorDmaBufSupport,
+        Self::NeedsExplicitCursorComposite,
+        Self::CursorAlwaysInFrame,
+        Self::InconsistentFrameTiming,
+        Self::InaccurateScreenSize,
+        Self::RestartCaptureOnResize,
+        Self::ClipboardExtraHandshake,
+        Self::MultiMonitorPositionQuirk,
+        Self::LimitedBufferFormats,
+        Self::SessionTimeoutOnIdle,
+        Self::ColorSpaceQuirk,
+        Self::Avc444Unreliable,
+        Self::ClipboardUnavailable,
+        Self::UnreliableClipboardOwnerSignals,
+        Self::DmaBufNeedsExplicitSync,
+    ];
+
+    /// Name in quirk database files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RequiresWaylandSession => "requires_wayland_session",
+            Self::SlowPortalPermissions => "slow_portal_permissions",
+            Self::PoorDmaBufSupport => "poor_dmabuf_support",
+            Self::NeedsExplicitCursorComposite => "needs_cursor_composite",
+            Self::CursorAlwaysInFrame => "cursor_always_in_frame",
+            Self::InconsistentFrameTiming => "inconsistent_frame_timing",
+            Self::InaccurateScreenSize => "inaccurate_screen_size",
+            Self::RestartCaptureOnResize => "restart_capture_on_resize",
+            Self::ClipboardExtraHandshake => "clipboard_extra_handshake",
+            Self::MultiMonitorPositionQuirk => "multi_monitor_position",
+            Self::LimitedBufferFormats => "limited_buffer_formats",
+            Self::SessionTimeoutOnIdle => "session_timeout_on_idle",
+            Self::ColorSpaceQuirk => "color_space",
+            Self::Avc444Unreliable => "avc444_unreliable",
+            Self::ClipboardUnavailable => "clipboard_unavailable",
+            Self::UnreliableClipboardOwnerSignals => "unreliable_clipboard_owner_signals",
+            Self::DmaBufNeedsExplicitSync => "dmabuf_needs_explicit_sync",
+        }
+    }
+
+    /// Quirk of a quirk database name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|quirk| quirk.name() == name)
+    }
+
     /// Get a human-readable description
     pub fn description(&self) -> &'static str {
         match self {
@@ -82,6 +163,7 @@ impl Quirk {
             Self::SlowPortalPermissions => "Slow portal permission dialogs",
             Self::PoorDmaBufSupport => "Unreliable DMA-BUF support",
             Self::NeedsExplicitCursorComposite => "Needs explicit cursor compositing",
+            Self::CursorAlwaysInFrame => "Cursor always painted into frames",
             Self::InconsistentFrameTiming => "Inconsistent frame timing",
             Self::InaccurateScreenSize => "May report inaccurate screen size",
             Self::RestartCaptureOnResize => "Restart capture after resize",
@@ -92,6 +174,10 @@ impl Quirk {
             Self::ColorSpaceQuirk => "Color space may be incorrect",
             Self::Avc444Unreliable => "AVC444 codec produces artifacts (use AVC420)",
             Self::ClipboardUnavailable => "Clipboard sync not available (Portal v1)",
+            Self::UnreliableClipboardOwnerSignals => {
+                "Portal clipboard owner-change signals unreliable"
+            }
+            Self::DmaBufNeedsExplicitSync => "DMA-BUF frames need explicit sync (using MemFd)",
         }
     }
 }
@@ -128,6 +214,15 @@ pub struct CompositorProfile {
 
     /// Recommended portal timeout (milliseconds)
     pub portal_timeout_ms: u64,
+
+    /// Whether the portal backend honors restore tokens
+    ///
+    /// The portal interface version can advertise them before the
+    /// compositor's backend implements them.
+    pub supports_restore_tokens: bool,
+
+    /// Whether the portal backend implements RemoteDesktop `ConnectToEIS`
+    pub supports_eis: bool,
 }
 
 impl Default for CompositorProfile {
@@ -143,14 +238,24 @@ impl Default for CompositorProfile {
             quirks: vec![],
             recommended_fps_cap: 30,
             portal_timeout_ms: 30000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 }
 
 impl CompositorProfile {
     /// Create a profile for a specific compositor type
+    ///
+    /// Applies the quirk database, built-in entries and the site's
+    /// drop-ins, for the compositor's version.
     pub fn for_compositor(compositor: &CompositorType) -> Self {
-        match compositor {
+        Self::for_compositor_with_quirks(compositor, &QuirkDatabase::load())
+    }
+
+    /// Create a profile for a specific compositor type from `quirks`
+    pub fn for_compositor_with_quirks(compositor: &CompositorType, quirks: &QuirkDatabase) -> Self {
+        let mut profile = match compositor {
             CompositorType::Gnome { version } => Self::gnome_profile(version.as_deref()),
             CompositorType::Kde { version } => Self::kde_profile(version.as_deref()),
             CompositorType::Sway { version } => Self::sway_profile(version.as_deref()),
@@ -161,7 +266,22 @@ impl CompositorProfile {
             CompositorType::Unknown { session_info } => {
                 Self::unknown_profile(session_info.as_deref())
             }
+        };
+        quirks.apply(&mut profile);
+
+        // Without implicit fencing, DMA-BUF frames from explicit-sync
+        // compositors may still be rendering when we read them
+        if profile.supports_explicit_sync {
+            if let Some(version) = detect_nvidia_driver() {
+                tracing::info!(
+                    "NVIDIA driver {} with an explicit-sync compositor - capturing through MemFd",
+                    version
+                );
+                profile.quirks.push(Quirk::DmaBufNeedsExplicitSync);
+            }
         }
+
+        profile
     }
 
     /// GNOME Shell / Mutter profile
@@ -169,17 +289,15 @@ impl CompositorProfile {
     /// This profile handles GNOME-specific quirks including platform-specific
     /// issues like the AVC444 blur on RHEL 9.
     fn gnome_profile(version: Option<&str>) -> Self {
-        let is_modern = version
-            .and_then(|v| v.split('.').next())
-            .and_then(|major| major.parse::<u32>().ok())
-            .map(|major| major >= 45)
-            .unwrap_or(false);
-
         // Detect OS for platform-specific quirks
         let os_release = detect_os_release();
 
         // Build quirk list based on compositor and platform
-        let mut quirks = vec![Quirk::RequiresWaylandSession, Quirk::RestartCaptureOnResize];
+        let mut quirks = vec![
+            Quirk::RequiresWaylandSession,
+            Quirk::RestartCaptureOnResize,
+            Quirk::UnreliableClipboardOwnerSignals,
+        ];
 
         // RHEL 9 specific quirks
         if let Some(ref os) = os_release {
@@ -211,22 +329,20 @@ impl CompositorProfile {
             recommended_capture: CaptureBackend::Portal,
             // GNOME works best with MemFd (shm) - DMA-BUF support varies
             recommended_buffer_type: BufferType::MemFd,
-            supports_damage_hints: is_modern, // GNOME 45+ has better damage tracking
-            supports_explicit_sync: false,    // Not yet in GNOME
+            supports_damage_hints: false,  // GNOME 45+ (quirk database)
+            supports_explicit_sync: false, // Not yet in GNOME
             quirks,
             recommended_fps_cap: 30,
             portal_timeout_ms: 30000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
     /// KDE Plasma / KWin profile
+    ///
+    /// Describes Plasma 5; the quirk database has what Plasma 6 changed.
     fn kde_profile(version: Option<&str>) -> Self {
-        let is_plasma6 = version
-            .and_then(|v| v.split('.').next())
-            .and_then(|major| major.parse::<u32>().ok())
-            .map(|major| major >= 6)
-            .unwrap_or(false);
-
         Self {
             compositor: CompositorType::Kde {
                 version: version.map(String::from),
@@ -235,20 +351,19 @@ impl CompositorProfile {
                 "wl_compositor".to_string(),
                 "xdg_wm_base".to_string(),
                 "org_kde_kwin_dpms".to_string(),
+                "zkde_screencast_unstable_v1".to_string(),
             ],
             portal_backend: Some("kde".to_string()),
             recommended_capture: CaptureBackend::Portal,
             // KDE has excellent DMA-BUF support
             recommended_buffer_type: BufferType::DmaBuf,
-            supports_damage_hints: is_plasma6, // Plasma 6 has improved damage
-            supports_explicit_sync: is_plasma6,
-            quirks: if is_plasma6 {
-                vec![]
-            } else {
-                vec![Quirk::MultiMonitorPositionQuirk]
-            },
+            supports_damage_hints: false,
+            supports_explicit_sync: false,
+            quirks: vec![Quirk::MultiMonitorPositionQuirk, Quirk::CursorAlwaysInFrame],
             recommended_fps_cap: 30,
             portal_timeout_ms: 30000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -275,6 +390,8 @@ impl CompositorProfile {
             ],
             recommended_fps_cap: 60, // Sway users often want higher FPS
             portal_timeout_ms: 15000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -301,6 +418,8 @@ impl CompositorProfile {
             ],
             recommended_fps_cap: 60,
             portal_timeout_ms: 15000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -317,6 +436,8 @@ impl CompositorProfile {
             quirks: vec![Quirk::LimitedBufferFormats, Quirk::InaccurateScreenSize],
             recommended_fps_cap: 30,
             portal_timeout_ms: 30000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -334,9 +455,11 @@ impl CompositorProfile {
             recommended_buffer_type: BufferType::DmaBuf,
             supports_damage_hints: true,
             supports_explicit_sync: true,
-            quirks: vec![], // Cosmic is modern and well-behaved
+            quirks: vec![Quirk::CursorAlwaysInFrame],
             recommended_fps_cap: 60,
             portal_timeout_ms: 15000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -359,6 +482,8 @@ impl CompositorProfile {
             quirks: vec![Quirk::NeedsExplicitCursorComposite],
             recommended_fps_cap: 30,
             portal_timeout_ms: 15000,
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -380,6 +505,8 @@ impl CompositorProfile {
             ],
             recommended_fps_cap: 30,
             portal_timeout_ms: 60000, // Longer timeout for unknown compositors
+            supports_restore_tokens: true,
+            supports_eis: true,
         }
     }
 
@@ -393,19 +520,42 @@ impl CompositorProfile {
 mod tests {
     use super::*;
 
+    fn profile(compositor: CompositorType) -> CompositorProfile {
+        CompositorProfile::for_compositor_with_quirks(&compositor, &QuirkDatabase::builtin())
+    }
+
     #[test]
     fn test_gnome_profile() {
-        let profile = CompositorProfile::gnome_profile(Some("46.0"));
+        let profile = profile(CompositorType::Gnome {
+            version: Some("46.0".to_string()),
+        });
         assert_eq!(profile.recommended_buffer_type, BufferType::MemFd);
         assert!(profile.supports_damage_hints);
         assert!(profile.has_quirk(&Quirk::RequiresWaylandSession));
+        assert!(profile.has_quirk(&Quirk::UnreliableClipboardOwnerSignals));
+
+        // Damage hints arrived in GNOME 45
+        let profile = profile(CompositorType::Gnome {
+            version: Some("44.2".to_string()),
+        });
+        assert!(!profile.supports_damage_hints);
     }
 
     #[test]
     fn test_kde_profile() {
-        let profile = CompositorProfile::kde_profile(Some("6.0"));
+        let profile = profile(CompositorType::Kde {
+            version: Some("6.0".to_string()),
+        });
         assert_eq!(profile.recommended_buffer_type, BufferType::DmaBuf);
         assert!(profile.supports_explicit_sync);
+        assert!(!profile.has_quirk(&Quirk::UnreliableClipboardOwnerSignals));
+        assert!(!profile.has_quirk(&Quirk::CursorAlwaysInFrame));
+
+        // Plasma 5 paints the cursor into metadata-mode frames
+        let profile = profile(CompositorType::Kde {
+            version: Some("5.27".to_string()),
+        });
+        assert!(profile.has_quirk(&Quirk::CursorAlwaysInFrame));
     }
 
     #[test]
@@ -0,0 +1,380 @@
+//! Compositor Quirk Database
+//!
+//! How a compositor and its portal backend behave changes between releases:
+//! Plasma 6 stopped painting the cursor into metadata-mode frames, GNOME 45
+//! added damage hints, backends gained restore tokens and `ConnectToEIS`
+//! one at a time. The base profiles in [`super::profiles`] describe what
+//! holds for every version of a compositor (and for unknown versions); the
+//! version-specific facts are entries of this database, applied over the
+//! profile in order:
+//!
+//! 1. the built-in entries
+//! 2. the `*.toml` files of `/etc/lamco-rdp-server/quirks.d`, in name order
+//!
+//! A later entry wins, so a drop-in can describe a new release or correct
+//! a built-in entry without a server update. A file that fails to parse is
+//! logged and skipped.
+//!
+//! # Format
+//!
+//! ```toml
+//! [[entry]]
+//! compositor = "kde"       # gnome, kde, sway, hyprland, weston, cosmic, wlroots, unknown
+//! since = "6.0"            # inclusive
+//! before = "6.1"           # exclusive
+//! cursor = "metadata"      # metadata, in-frame or composite
+//! damage_hints = true      # screencast frames carry damage regions
+//! explicit_sync = true
+//! restore_tokens = true    # portal backend honors restore tokens
+//! eis = false              # portal backend implements ConnectToEIS
+//! add = ["avc444_unreliable"]
+//! remove = ["restart_capture_on_resize"]
+//! note = "Why this entry exists"
+//! ```
+//!
+//! Every key but `compositor` is optional. Entries with `since` or `before`
+//! never match a compositor whose version could not be detected. `add` and
+//! `remove` take the names of [`Quirk::name`].
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use super::profiles::{CompositorProfile, Quirk};
+
+/// Directory of site-specific quirk files
+pub const QUIRKS_DIR: &str = "/etc/lamco-rdp-server/quirks.d";
+
+/// Compositor IDs entries can match (see [`super::CompositorType::id`])
+const COMPOSITORS: [&str; 8] = [
+    "gnome", "kde", "sway", "hyprland", "weston", "cosmic", "wlroots", "unknown",
+];
+
+const BUILTIN: &str = r#"
+# Mutter screencast streams carry damage regions since GNOME 45
+[[entry]]
+compositor = "gnome"
+since = "45"
+damage_hints = true
+
+[[entry]]
+compositor = "gnome"
+before = "46"
+eis = false
+note = "xdg-desktop-portal-gnome implements ConnectToEIS since 46"
+
+# Plasma 6 reworked KWin screencasting: cursor metadata without a painted
+# cursor, damage regions, explicit sync and correct output positions
+[[entry]]
+compositor = "kde"
+since = "6"
+cursor = "metadata"
+damage_hints = true
+explicit_sync = true
+remove = ["multi_monitor_position"]
+
+[[entry]]
+compositor = "kde"
+before = "5.27"
+restore_tokens = false
+note = "xdg-desktop-portal-kde restores sessions since Plasma 5.27"
+
+[[entry]]
+compositor = "kde"
+before = "6.1"
+eis = false
+note = "xdg-desktop-portal-kde implements ConnectToEIS since Plasma 6.1"
+"#;
+
+/// What the cursor looks like in screencast frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorBehavior {
+    /// Cursor as metadata, frames without it
+    Metadata,
+    /// Cursor painted into frames whatever mode is requested
+    InFrame,
+    /// No cursor at all; the server has to composite it
+    Composite,
+}
+
+/// One entry of the database
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuirkEntry {
+    /// Compositor ID
+    pub compositor: String,
+    /// First version the entry applies to
+    pub since: Option<String>,
+    /// First version the entry no longer applies to
+    pub before: Option<String>,
+    pub cursor: Option<CursorBehavior>,
+    pub damage_hints: Option<bool>,
+    pub explicit_sync: Option<bool>,
+    pub restore_tokens: Option<bool>,
+    pub eis: Option<bool>,
+    /// Quirks to add
+    #[serde(default)]
+    pub add: Vec<String>,
+    /// Quirks to remove
+    #[serde(default)]
+    pub remove: Vec<String>,
+    /// Why the entry exists (logged when it applies)
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuirkFile {
+    #[serde(default)]
+    entry: Vec<QuirkEntry>,
+}
+
+impl QuirkEntry {
+    /// Whether the entry applies to compositor `id` at `version`
+    fn matches(&self, id: &str, version: Option<&str>) -> bool {
+        if self.compositor != id {
+            return false;
+        }
+        if self.since.is_none() && self.before.is_none() {
+            return true;
+        }
+        let Some(version) = version else {
+            return false;
+        };
+        let at_least = |bound: &str| compare_versions(version, bound) != Ordering::Less;
+        self.since.as_deref().map_or(true, at_least)
+            && self
+                .before
+                .as_deref()
+                .map_or(true, |bound| !at_least(bound))
+    }
+
+    fn apply(&self, profile: &mut CompositorProfile) {
+        if let Some(cursor) = self.cursor {
+            profile.quirks.retain(|quirk| {
+                !matches!(
+                    quirk,
+                    Quirk::CursorAlwaysInFrame | Quirk::NeedsExplicitCursorComposite
+                )
+            });
+            match cursor {
+                CursorBehavior::Metadata => {}
+                CursorBehavior::InFrame => profile.quirks.push(Quirk::CursorAlwaysInFrame),
+                CursorBehavior::Composite => {
+                    profile.quirks.push(Quirk::NeedsExplicitCursorComposite)
+                }
+            }
+        }
+        if let Some(damage_hints) = self.damage_hints {
+            profile.supports_damage_hints = damage_hints;
+        }
+        if let Some(explicit_sync) = self.explicit_sync {
+            profile.supports_explicit_sync = explicit_sync;
+        }
+        if let Some(restore_tokens) = self.restore_tokens {
+            profile.supports_restore_tokens = restore_tokens;
+        }
+        if let Some(eis) = self.eis {
+            profile.supports_eis = eis;
+        }
+        // Names were checked when the entry was parsed
+        for quirk in self.add.iter().filter_map(|name| Quirk::from_name(name)) {
+            if !profile.has_quirk(&quirk) {
+                profile.quirks.push(quirk);
+            }
+        }
+        for quirk in self.remove.iter().filter_map(|name| Quirk::from_name(name)) {
+            profile.quirks.retain(|q| *q != quirk);
+        }
+    }
+}
+
+/// Built-in quirk entries plus the site's drop-ins
+#[derive(Debug, Clone, Default)]
+pub struct QuirkDatabase {
+    entries: Vec<QuirkEntry>,
+}
+
+impl QuirkDatabase {
+    /// The built-in entries only
+    pub fn builtin() -> Self {
+        Self {
+            entries: parse(BUILTIN).expect("built-in quirks are valid"),
+        }
+    }
+
+    /// The built-in entries and those of [`QUIRKS_DIR`]
+    pub fn load() -> Self {
+        Self::builtin().with_dir(Path::new(QUIRKS_DIR))
+    }
+
+    /// Add the entries of the `*.toml` files in `dir`, in name order
+    pub fn with_dir(mut self, dir: &Path) -> Self {
+        let Ok(files) = std::fs::read_dir(dir) else {
+            return self;
+        };
+        let mut paths: Vec<PathBuf> = files
+            .filter_map(|file| file.ok().map(|file| file.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let entries = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+                .and_then(|text| parse(&text));
+            match entries {
+                Ok(entries) => {
+                    debug!(
+                        "Loaded {} quirk entries from {}",
+                        entries.len(),
+                        path.display()
+                    );
+                    self.entries.extend(entries);
+                }
+                Err(e) => warn!("⚠️  Ignoring quirk file {}: {:#}", path.display(), e),
+            }
+        }
+        self
+    }
+
+    /// Apply the entries matching the profile's compositor and version
+    pub fn apply(&self, profile: &mut CompositorProfile) {
+        let id = profile.compositor.id();
+        let version = profile.compositor.version().map(str::to_string);
+        for entry in &self.entries {
+            if entry.matches(id, version.as_deref()) {
+                if let Some(ref note) = entry.note {
+                    debug!("Compositor quirk for {}: {}", profile.compositor, note);
+                }
+                entry.apply(profile);
+            }
+        }
+    }
+}
+
+/// Parse and check the entries of a quirk file
+fn parse(text: &str) -> Result<Vec<QuirkEntry>> {
+    let file: QuirkFile = toml::from_str(text).context("Invalid quirk file")?;
+    for entry in &file.entry {
+        if !COMPOSITORS.contains(&entry.compositor.as_str()) {
+            anyhow::bail!(
+                "Unknown compositor {:?} (expected one of: {})",
+                entry.compositor,
+                COMPOSITORS.join(", ")
+            );
+        }
+        for version in entry.since.iter().chain(&entry.before) {
+            if parse_version(version).is_none() {
+                anyhow::bail!("Invalid version {:?}", version);
+            }
+        }
+        for name in entry.add.iter().chain(&entry.remove) {
+            if Quirk::from_name(name).is_none() {
+                anyhow::bail!("Unknown quirk {:?}", name);
+            }
+        }
+    }
+    Ok(file.entry)
+}
+
+/// Numeric components of a version ("46.rc" -> [46])
+fn parse_version(version: &str) -> Option<Vec<u32>> {
+    let parts: Vec<u32> = version
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    (!parts.is_empty()).then_some(parts)
+}
+
+/// Compare dotted versions, missing components counting as 0
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (
+        parse_version(a).unwrap_or_default(),
+        parse_version(b).unwrap_or_default(),
+    );
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::CompositorType;
+
+    fn kde(version: Option<&str>) -> CompositorProfile {
+        CompositorProfile::for_compositor_with_quirks(
+            &CompositorType::Kde {
+                version: version.map(String::from),
+            },
+            &QuirkDatabase::builtin(),
+        )
+    }
+
+    #[test]
+    fn test_version_ranges() {
+        assert_eq!(compare_versions("6.0", "6"), Ordering::Equal);
+        assert_eq!(compare_versions("5.27.11", "6"), Ordering::Less);
+        assert_eq!(compare_versions("46.rc", "45"), Ordering::Greater);
+
+        let plasma5 = kde(Some("5.26"));
+        assert!(plasma5.has_quirk(&Quirk::CursorAlwaysInFrame));
+        assert!(!plasma5.supports_restore_tokens);
+        assert!(!plasma5.supports_eis);
+
+        let plasma61 = kde(Some("6.1.4"));
+        assert!(!plasma61.has_quirk(&Quirk::CursorAlwaysInFrame));
+        assert!(plasma61.supports_damage_hints);
+        assert!(plasma61.supports_eis);
+
+        // Unknown versions keep the conservative base profile
+        let unknown = kde(None);
+        assert!(unknown.has_quirk(&Quirk::CursorAlwaysInFrame));
+        assert!(unknown.supports_restore_tokens);
+    }
+
+    #[test]
+    fn test_drop_ins_override_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("50-plasma.toml"),
+            r#"
+            [[entry]]
+            compositor = "kde"
+            since = "6.2"
+            cursor = "in-frame"
+            add = ["avc444_unreliable"]
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("60-broken.toml"),
+            "[[entry]]\nkompositor = 1",
+        )
+        .unwrap();
+
+        let database = QuirkDatabase::builtin().with_dir(dir.path());
+        let profile = CompositorProfile::for_compositor_with_quirks(
+            &CompositorType::Kde {
+                version: Some("6.2".to_string()),
+            },
+            &database,
+        );
+        assert!(profile.has_quirk(&Quirk::CursorAlwaysInFrame));
+        assert!(profile.has_quirk(&Quirk::Avc444Unreliable));
+        // Built-in entries still apply
+        assert!(profile.supports_damage_hints);
+
+        assert!(parse("[[entry]]\ncompositor = \"kde\"\nadd = [\"fast\"]").is_err());
+        assert!(parse("[[entry]]\ncompositor = \"mutter\"").is_err());
+    }
+}
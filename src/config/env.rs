@@ -0,0 +1,204 @@
+//! Environment Variable Overrides
+//!
+//! Every setting can be overridden from the environment as
+//! `LAMCO_RDP_<SECTION>_<KEY>`: the setting's dotted key upper-cased with
+//! underscores for dots, e.g. `LAMCO_RDP_VIDEO_TARGET_FPS` or
+//! `LAMCO_RDP_PERFORMANCE_ADAPTIVE_FPS_MAX_FPS`. Containers can be
+//! configured this way without mounting a config file.
+//!
+//! Precedence is command line > environment > config file > defaults.
+//!
+//! Overrides are applied to the serialized config, so settings need no
+//! registration to be covered. Values of string settings are taken
+//! verbatim; all others are parsed as TOML values (`60`, `0.5`, `true`,
+//! `["a", "b"]`). Arrays of tables are replaced as a whole, e.g.
+//! `[{ app_id = "foot", mode = "painted" }]`. Optional settings without a
+//! value in the file or defaults (such as `logging.log_dir`) have no
+//! variable.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use toml::Value;
+use tracing::{info, warn};
+
+use super::Config;
+
+/// Prefix of override variables
+pub const ENV_PREFIX: &str = "LAMCO_RDP_";
+
+/// Variables with the prefix that are not settings: command-line options,
+/// the upgrade handoff and fault injection
+const NON_SETTING_VARS: [&str; 5] = [
+    "LAMCO_RDP_LISTEN_ADDR",
+    "LAMCO_RDP_PORT",
+    "LAMCO_RDP_CONNECT",
+    "LAMCO_RDP_HANDOFF",
+    "LAMCO_RDP_CHAOS",
+];
+
+impl Config {
+    /// Apply `LAMCO_RDP_*` overrides from the process environment
+    pub fn with_env_overrides(self) -> Result<Self> {
+        self.with_env_overrides_from(std::env::vars())
+    }
+
+    /// Apply `LAMCO_RDP_*` overrides from `vars`
+    ///
+    /// The result is validated when anything was overridden. Variables
+    /// matching no setting are logged and ignored.
+    pub fn with_env_overrides_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut vars: HashMap<String, String> = vars
+            .into_iter()
+            .filter(|(name, _)| {
+                name.starts_with(ENV_PREFIX) && !NON_SETTING_VARS.contains(&name.as_str())
+            })
+            .collect();
+        if vars.is_empty() {
+            return Ok(self);
+        }
+
+        let mut document = Value::try_from(&self).context("Failed to serialize config")?;
+        let mut applied = Vec::new();
+        apply(&mut document, &mut Vec::new(), &mut vars, &mut applied)?;
+        for name in vars.keys() {
+            warn!("⚠️  {} matches no setting, ignored", name);
+        }
+        if applied.is_empty() {
+            return Ok(self);
+        }
+
+        let mut config: Config = document
+            .try_into()
+            .context("Invalid setting from the environment")?;
+        if let Some((key, e)) = config.resolve_secrets().into_iter().next() {
+            return Err(e.context(format!("Invalid secret reference for {}", key)));
+        }
+        config
+            .validate()
+            .context("Invalid setting from the environment")?;
+        info!("Settings from the environment: {}", applied.join(", "));
+        Ok(config)
+    }
+}
+
+/// Name of the variable overriding the setting at `path`
+fn var_name(path: &[String]) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        path.join("_").to_uppercase().replace('-', "_")
+    )
+}
+
+/// Replace the settings under `value` that have a variable in `vars`
+fn apply(
+    value: &mut Value,
+    path: &mut Vec<String>,
+    vars: &mut HashMap<String, String>,
+    applied: &mut Vec<String>,
+) -> Result<()> {
+    let Value::Table(table) = value else {
+        return Ok(());
+    };
+    for (key, value) in table.iter_mut() {
+        path.push(key.clone());
+        if value.is_table() {
+            apply(value, path, vars, applied)?;
+        } else {
+            let name = var_name(path);
+            if let Some(raw) = vars.remove(&name) {
+                *value = parse(&raw, value)
+                    .with_context(|| format!("Invalid value for {}: {:?}", name, raw))?;
+                applied.push(path.join("."));
+            }
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Parse `raw` as a value of the same type as `current`
+fn parse(raw: &str, current: &Value) -> Result<Value> {
+    match current {
+        Value::String(_) => return Ok(Value::String(raw.to_string())),
+        Value::Boolean(_) => match raw.to_ascii_lowercase().as_str() {
+            "1" | "yes" | "on" => return Ok(Value::Boolean(true)),
+            "0" | "no" | "off" => return Ok(Value::Boolean(false)),
+            _ => {}
+        },
+        _ => {}
+    }
+
+    let mut table: toml::Table = toml::from_str(&format!("value = {}", raw))?;
+    let value = table.remove("value").context("missing value")?;
+    match (current, value) {
+        (Value::Float(_), Value::Integer(i)) => Ok(Value::Float(i as f64)),
+        (current, value) if current.same_type(&value) => Ok(value),
+        (current, value) => {
+            anyhow::bail!("expected {}, got {}", current.type_str(), value.type_str())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        let mut config = Config::default_config().unwrap();
+        // Validation needs existing certificate paths
+        config.security.cert_path = std::env::temp_dir();
+        config.security.key_path = std::env::temp_dir();
+        config
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_overrides_by_type() {
+        let config = config()
+            .with_env_overrides_from(vars(&[
+                ("LAMCO_RDP_VIDEO_TARGET_FPS", "60"),
+                ("LAMCO_RDP_PERFORMANCE_ADAPTIVE_FPS_MAX_FPS", "60"),
+                ("LAMCO_RDP_SERVER_LISTEN_ADDR", "0.0.0.0:5000"),
+                ("LAMCO_RDP_SECURITY_ENABLE_NLA", "no"),
+                ("LAMCO_RDP_CLIPBOARD_ALLOWED_TYPES", r#"["text/plain"]"#),
+                ("LAMCO_RDP_PORT", "4000"),
+                ("HOME", "/root"),
+            ]))
+            .unwrap();
+        assert_eq!(config.video.target_fps, 60);
+        assert_eq!(config.performance.adaptive_fps.max_fps, 60);
+        assert_eq!(config.server.listen_addr, "0.0.0.0:5000");
+        assert!(!config.security.enable_nla);
+        assert_eq!(config.clipboard.allowed_types, ["text/plain"]);
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let err = config()
+            .with_env_overrides_from(vars(&[("LAMCO_RDP_VIDEO_TARGET_FPS", "fast")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("LAMCO_RDP_VIDEO_TARGET_FPS"));
+
+        // Values are validated like the file's
+        assert!(config()
+            .with_env_overrides_from(vars(&[("LAMCO_RDP_VIDEO_ENCODER", "x265")]))
+            .is_err());
+
+        // Unknown variables change nothing
+        let config = config()
+            .with_env_overrides_from(vars(&[("LAMCO_RDP_VIDEO_FRAME_RATE", "60")]))
+            .unwrap();
+        assert_eq!(config.video.target_fps, 30);
+    }
+}
@@ -0,0 +1,184 @@
+//! Config File Migration
+//!
+//! Config files carry the schema version they were written for as
+//! `config_version`; files from before versioning have none and count as
+//! version 1. On load, older files are brought up to [`CONFIG_VERSION`] in
+//! memory (renamed keys moved, changed values rewritten) rather than
+//! failing to parse or validate and leaving the server on its defaults.
+//! Each change is reported, and `config migrate` writes it back to the file.
+//!
+//! # Versions
+//!
+//! - **1** - unversioned files
+//! - **2** - values of choice settings (modes, methods, codecs) are
+//!   lowercase. Version 1 files could have `"Metadata"` or `"AVC420"`,
+//!   which parts of the server matched ignoring case and validation
+//!   rejected.
+
+use anyhow::Result;
+use toml::{Table, Value};
+
+/// Schema version of config files this server writes
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Key holding a file's schema version
+pub const VERSION_KEY: &str = "config_version";
+
+/// Settings whose value is one of a fixed set of lowercase names
+const CHOICE_KEYS: [&str; 20] = [
+    "server.multi_client",
+    "security.auth_method",
+    "video.encoder",
+    "video.cursor_mode",
+    "multimon.follow_focus",
+    "performance.latency.mode",
+    "performance.scheduling.realtime",
+    "logging.level",
+    "egfx.zgfx_compression",
+    "egfx.codec",
+    "egfx.color_matrix",
+    "egfx.color_range",
+    "damage_tracking.method",
+    "hardware_encoding.quality_preset",
+    "display.scaling_policy",
+    "cursor.mode",
+    "cursor.predictor.model",
+    "broker.kind",
+    "resource_limits.action",
+    "capture_watchdog.action",
+];
+
+/// A change made to bring a file up to date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// Dotted key changed
+    pub key: String,
+    /// What changed, e.g. `"Metadata" -> "metadata"`
+    pub change: String,
+}
+
+/// Version of a parsed config file
+pub fn file_version(document: &Table) -> u32 {
+    document
+        .get(VERSION_KEY)
+        .and_then(Value::as_integer)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(1)
+}
+
+/// Bring a parsed config file up to [`CONFIG_VERSION`]
+///
+/// Returns the changes made, none for a current file. Fails for files of
+/// a newer server.
+pub fn migrate(document: &mut Table) -> Result<Vec<Migration>> {
+    let version = file_version(document);
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "{} is {}, newer than this server understands ({})",
+            VERSION_KEY,
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    let mut migrations = Vec::new();
+    if version < 2 {
+        lowercase_choices(document, &mut migrations);
+    }
+    if version < CONFIG_VERSION {
+        document.insert(
+            VERSION_KEY.to_string(),
+            Value::Integer(i64::from(CONFIG_VERSION)),
+        );
+    }
+    Ok(migrations)
+}
+
+/// Version 2: choice values are lowercase
+fn lowercase_choices(document: &mut Table, migrations: &mut Vec<Migration>) {
+    let mut lowercase = |key: String, value: &mut Value| {
+        if let Value::String(choice) = value {
+            let lower = choice.to_lowercase();
+            if *choice != lower {
+                migrations.push(Migration {
+                    key,
+                    change: format!("{:?} -> {:?}", choice, lower),
+                });
+                *choice = lower;
+            }
+        }
+    };
+
+    for key in CHOICE_KEYS {
+        if let Some(value) = get_mut(document, key) {
+            lowercase(key.to_string(), value);
+        }
+    }
+    // Modes of application cursor overrides
+    if let Some(Value::Array(overrides)) = get_mut(document, "cursor.app_overrides") {
+        for (i, entry) in overrides.iter_mut().enumerate() {
+            if let Some(mode) = entry.get_mut("mode") {
+                lowercase(format!("cursor.app_overrides[{}].mode", i), mode);
+            }
+        }
+    }
+}
+
+/// Value at a dotted key
+pub(super) fn get_mut<'a>(document: &'a mut Table, key: &str) -> Option<&'a mut Value> {
+    let (tables, last) = match key.rsplit_once('.') {
+        Some((tables, last)) => (Some(tables), last),
+        None => (None, key),
+    };
+    let mut table = document;
+    for name in tables.into_iter().flat_map(|tables| tables.split('.')) {
+        table = table.get_mut(name)?.as_table_mut()?;
+    }
+    table.get_mut(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_one_choices_lowercased() {
+        let mut document: Table = toml::from_str(
+            r#"
+            [video]
+            cursor_mode = "Metadata"
+            [egfx]
+            codec = "avc420"
+            [[cursor.app_overrides]]
+            app_id = "Foot"
+            mode = "Painted"
+            "#,
+        )
+        .unwrap();
+
+        let migrations = migrate(&mut document).unwrap();
+        assert_eq!(
+            migrations
+                .iter()
+                .map(|m| m.key.as_str())
+                .collect::<Vec<_>>(),
+            ["video.cursor_mode", "cursor.app_overrides[0].mode"]
+        );
+        assert_eq!(document["video"]["cursor_mode"].as_str(), Some("metadata"));
+        // Application IDs are not choices
+        assert_eq!(
+            document["cursor"]["app_overrides"][0]["app_id"].as_str(),
+            Some("Foot")
+        );
+        assert_eq!(file_version(&document), CONFIG_VERSION);
+
+        // A current file is left alone
+        assert!(migrate(&mut document).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let mut document: Table = toml::from_str("config_version = 99").unwrap();
+        assert!(migrate(&mut document).is_err());
+    }
+}
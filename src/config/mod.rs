@@ -13,18 +13,35 @@ use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
+mod env;
+pub mod migrate;
+pub mod presets;
+pub mod reload;
+pub mod schema;
+pub mod secrets;
+pub mod tunable;
 pub mod types;
 
 // Use types from types.rs
 use types::*;
 
 // Re-export types needed by other modules
+pub use env::ENV_PREFIX;
+pub use migrate::CONFIG_VERSION;
+pub use reload::ReloadReport;
+pub use schema::{ConfigIssue, Severity};
 pub use types::HardwareEncodingConfig;
-pub use types::{CursorConfig, CursorPredictorConfig};
+pub use types::{CursorAppOverride, CursorConfig, CursorPredictorConfig};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version of the file (see [`migrate`])
+    #[serde(default = "default_config_version")]
+    pub config_version: u32,
+    /// Built-in preset under the file's settings (see [`presets`])
+    #[serde(default)]
+    pub profile: Option<String>,
     /// Server configuration
     pub server: ServerConfig,
     /// Security configuration
@@ -61,28 +78,113 @@ pub struct Config {
     /// Cursor handling configuration (Premium)
     #[serde(default)]
     pub cursor: CursorConfig,
+    /// Session shadowing (view-only observers)
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+    /// Session broker integration
+    #[serde(default)]
+    pub broker: BrokerConfig,
+    /// HTTP admin API
+    #[serde(default)]
+    pub admin_api: AdminApiConfig,
+    /// Health-check endpoint
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Per-session resource limits
+    #[serde(default)]
+    pub resource_limits: ResourceLimitsConfig,
+    /// Session event hook commands
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Login banner / consent screen
+    #[serde(default)]
+    pub login_banner: LoginBannerConfig,
+    /// Connection quality overlay
+    #[serde(default)]
+    pub quality_overlay: QualityOverlayConfig,
+    /// Capture session loss recovery
+    #[serde(default)]
+    pub capture_recovery: CaptureRecoveryConfig,
+    /// Frozen capture watchdog
+    #[serde(default)]
+    pub capture_watchdog: CaptureWatchdogConfig,
+    /// Frame dumps for visual debugging
+    #[serde(default)]
+    pub frame_dump: FrameDumpConfig,
+    /// Crash reports
+    #[serde(default)]
+    pub crash_report: CrashReportConfig,
+    /// Lifecycle event stream
+    #[serde(default)]
+    pub event_stream: EventStreamConfig,
+    /// Heartbeat status file
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    /// GNOME Mutter direct capture
+    #[serde(default)]
+    pub mutter: MutterConfig,
+    /// KDE KWin direct capture
+    #[serde(default)]
+    pub kwin: KwinConfig,
+    /// wlroots native protocols
+    #[serde(default)]
+    pub wlr: WlrConfig,
+}
+
+/// Files from before versioning
+fn default_config_version() -> u32 {
+    1
 }
 
 impl Config {
     /// Load configuration from file
+    ///
+    /// Files of older versions are migrated in memory. Fails with every
+    /// error [`schema::check`] finds, located by key and line; warnings
+    /// (e.g. unknown keys, migrated settings) are logged. Settings changed
+    /// at runtime are applied from the drop-in (see [`tunable`]).
     pub fn load(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .context(format!("Failed to read config file: {}", path))?;
 
-        let config: Config = toml::from_str(&content).context("Failed to parse config file")?;
-
-        config.validate()?;
-        Ok(config)
+        let (config, issues) = schema::check(&content);
+        let mut errors = Vec::new();
+        for issue in &issues {
+            match issue.severity {
+                Severity::Warning => tracing::warn!("⚠️  {}", issue.describe(path)),
+                Severity::Error => errors.push(issue.describe(path)),
+            }
+        }
+        match config {
+            Some(config) if errors.is_empty() => {
+                config.with_drop_in(&tunable::drop_in_dir(std::path::Path::new(path)))
+            }
+            _ => anyhow::bail!("Invalid config file:\n  {}", errors.join("\n  ")),
+        }
     }
 
     /// Create default configuration
     pub fn default_config() -> Result<Self> {
         Ok(Config {
+            config_version: CONFIG_VERSION,
+            profile: None,
             server: ServerConfig {
                 listen_addr: "0.0.0.0:3389".to_string(),
                 max_connections: 10,
                 session_timeout: 0,
                 use_portals: true,
+                multi_client: "single".to_string(),
+                reverse_connect: String::new(),
+                reverse_heartbeat_secs: 30,
+                reverse_retry_secs: 5,
+                tcp_keepalive_secs: 10,
+                tcp_keepalive_interval_secs: 5,
+                tcp_keepalive_probes: 3,
+                dead_peer_timeout_secs: 20,
+                upgrade_handoff: false,
+                shutdown_grace_secs: 10,
+                idle_stop_secs: 30,
+                deconflict_port: false,
             },
             security: SecurityConfig {
                 cert_path: PathBuf::from("/etc/lamco-rdp-server/cert.pem"),
@@ -104,6 +206,10 @@ impl Config {
                 use_libei: true,
                 keyboard_layout: "auto".to_string(),
                 enable_touch: false,
+                fallback_chain: crate::session::failover::DEFAULT_CHAIN
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
             },
             clipboard: ClipboardConfig {
                 enabled: true,
@@ -114,6 +220,10 @@ impl Config {
             multimon: MultiMonitorConfig {
                 enabled: true,
                 max_monitors: 4,
+                follow_focus: "off".to_string(),
+                follow_focus_viewport: String::new(),
+                follow_focus_dwell_ms: 300,
+                monitors: Vec::new(),
             },
             performance: PerformanceConfig {
                 encoder_threads: 0,
@@ -122,11 +232,15 @@ impl Config {
                 zero_copy: true,
                 adaptive_fps: AdaptiveFpsConfig::default(),
                 latency: LatencyConfig::default(),
+                scheduling: SchedulingConfig::default(),
+                auto_profile: true,
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 log_dir: None,
                 metrics: true,
+                per_session_files: false,
+                pdu_capture: None,
             },
             egfx: EgfxConfig::default(),
             damage_tracking: DamageTrackingConfig::default(),
@@ -134,17 +248,183 @@ impl Config {
             display: DisplayConfig::default(),
             advanced_video: AdvancedVideoConfig::default(),
             cursor: CursorConfig::default(),
+            shadow: ShadowConfig::default(),
+            broker: BrokerConfig::default(),
+            admin_api: AdminApiConfig::default(),
+            health: HealthConfig::default(),
+            resource_limits: ResourceLimitsConfig::default(),
+            hooks: HooksConfig::default(),
+            login_banner: LoginBannerConfig::default(),
+            quality_overlay: QualityOverlayConfig::default(),
+            capture_recovery: CaptureRecoveryConfig::default(),
+            capture_watchdog: CaptureWatchdogConfig::default(),
+            frame_dump: FrameDumpConfig::default(),
+            crash_report: CrashReportConfig::default(),
+            event_stream: EventStreamConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            mutter: MutterConfig::default(),
+            kwin: KwinConfig::default(),
+            wlr: WlrConfig::default(),
         })
     }
 
     /// Validate configuration
     pub fn validate(&self) -> Result<()> {
+        // Validate preset name
+        if let Some(ref profile) = self.profile {
+            presets::settings(profile)?;
+        }
+
         // Validate listen address
         self.server
             .listen_addr
             .parse::<SocketAddr>()
             .context("Invalid listen address")?;
 
+        // Validate multi-client mode
+        match self.server.multi_client.as_str() {
+            "single" | "shared" | "separate" => {}
+            _ => anyhow::bail!("Invalid multi-client mode: {}", self.server.multi_client),
+        }
+
+        // Validate reverse connection target
+        if !self.server.reverse_connect.is_empty() {
+            match self.server.reverse_connect.rsplit_once(':') {
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+                _ => anyhow::bail!(
+                    "Invalid reverse connection target (expected host:port): {}",
+                    self.server.reverse_connect
+                ),
+            }
+        }
+
+        // Validate keepalive probing
+        if self.server.tcp_keepalive_secs > 0
+            && (self.server.tcp_keepalive_interval_secs == 0
+                || self.server.tcp_keepalive_probes == 0)
+        {
+            anyhow::bail!(
+                "server.tcp_keepalive_interval_secs and server.tcp_keepalive_probes must be greater than 0"
+            );
+        }
+
+        // Validate shadow listener
+        if self.shadow.enabled {
+            let shadow_addr = self
+                .shadow
+                .listen_addr
+                .parse::<SocketAddr>()
+                .context("Invalid shadow listen address")?;
+            if self.server.listen_addr.parse::<SocketAddr>().ok() == Some(shadow_addr) {
+                anyhow::bail!("shadow.listen_addr must differ from server.listen_addr");
+            }
+        }
+
+        // Validate session broker
+        if self.broker.enabled {
+            match self.broker.kind.as_str() {
+                "http" => {
+                    if !self.broker.url.starts_with("http://")
+                        && !self.broker.url.starts_with("https://")
+                    {
+                        anyhow::bail!(
+                            "broker.url must be an http(s) URL when broker.kind = \"http\""
+                        );
+                    }
+                }
+                "dbus" => {}
+                _ => anyhow::bail!("Invalid broker kind: {}", self.broker.kind),
+            }
+        }
+
+        // Per-session log files need a directory
+        if self.logging.per_session_files && self.logging.log_dir.is_none() {
+            anyhow::bail!("logging.per_session_files requires logging.log_dir");
+        }
+
+        // Validate admin API
+        if self.admin_api.enabled {
+            if self.admin_api.token.trim().is_empty() {
+                anyhow::bail!("admin_api.token must be set when the admin API is enabled");
+            }
+            let admin_addr = self
+                .admin_api
+                .listen_addr
+                .parse::<SocketAddr>()
+                .context("Invalid admin API listen address")?;
+            if !admin_addr.ip().is_loopback() && self.admin_api.client_ca_path.is_none() {
+                anyhow::bail!(
+                    "admin_api.listen_addr {} is not loopback; set admin_api.client_ca_path for mutual TLS",
+                    admin_addr
+                );
+            }
+        }
+
+        // Validate health endpoint
+        if self.health.enabled {
+            self.health
+                .listen_addr
+                .parse::<SocketAddr>()
+                .context("Invalid health endpoint listen address")?;
+        }
+
+        // Validate resource limit action
+        match self.resource_limits.action.as_str() {
+            "throttle" | "terminate" => {}
+            _ => anyhow::bail!(
+                "Invalid resource limit action: {} (expected throttle or terminate)",
+                self.resource_limits.action
+            ),
+        }
+
+        // Validate capture watchdog action
+        match self.capture_watchdog.action.as_str() {
+            "log" | "indicator" | "renegotiate" => {}
+            _ => anyhow::bail!(
+                "Invalid capture watchdog action: {} (expected log, indicator or renegotiate)",
+                self.capture_watchdog.action
+            ),
+        }
+
+        // Validate frame dumps (each frame is a few full-size images)
+        if !(1..=30).contains(&self.frame_dump.frames) {
+            anyhow::bail!(
+                "frame_dump.frames must be between 1 and 30, got {}",
+                self.frame_dump.frames
+            );
+        }
+
+        // Validate crash reports (the webhook may be a secret reference)
+        let webhook = &self.crash_report.webhook_url;
+        if !webhook.is_empty()
+            && !webhook.starts_with("file:")
+            && !webhook.starts_with("credential:")
+            && !webhook.starts_with("http://")
+            && !webhook.starts_with("https://")
+        {
+            anyhow::bail!("crash_report.webhook_url must be an http(s) URL");
+        }
+
+        // Validate the heartbeat (agents judge staleness by the interval)
+        if !(1..=300).contains(&self.heartbeat.interval_secs) {
+            anyhow::bail!(
+                "heartbeat.interval_secs must be between 1 and 300, got {}",
+                self.heartbeat.interval_secs
+            );
+        }
+
+        // Validate input fallback chain
+        crate::session::failover::parse_chain(&self.input.fallback_chain)
+            .context("Invalid input.fallback_chain")?;
+
+        // Validate login banner image
+        if self.login_banner.enabled && !self.login_banner.image_path.exists() {
+            anyhow::bail!(
+                "Login banner image not found: {:?}",
+                self.login_banner.image_path
+            );
+        }
+
         // Validate cert paths exist
         if !self.security.cert_path.exists() {
             anyhow::bail!("Certificate not found: {:?}", self.security.cert_path);
@@ -171,6 +451,38 @@ impl Config {
             _ => anyhow::bail!("Invalid cursor strategy mode: {}", self.cursor.mode),
         }
 
+        if self.mutter.monitor.trim().is_empty() {
+            anyhow::bail!("mutter.monitor must be \"auto\", \"virtual\" or a connector name");
+        }
+        self.kwin
+            .virtual_output_size()
+            .map_err(anyhow::Error::msg)?;
+
+        // Validate follow-focus configuration
+        match self.multimon.follow_focus.as_str() {
+            "off" | "monitor" | "pointer" => {}
+            _ => anyhow::bail!("Invalid follow-focus mode: {}", self.multimon.follow_focus),
+        }
+        self.multimon
+            .follow_focus_viewport_size()
+            .map_err(anyhow::Error::msg)?;
+
+        // Validate server-side scaling
+        match self.display.scaling_policy.as_str() {
+            "off" | "fit" | "fill" | "pan" => {}
+            _ => anyhow::bail!("Invalid scaling policy: {}", self.display.scaling_policy),
+        }
+        let scaling_output = self
+            .display
+            .scaling_output_dimensions()
+            .map_err(anyhow::Error::msg)?;
+        if self.display.scaling_policy != "off" && scaling_output.is_none() {
+            anyhow::bail!(
+                "display.scaling_output_size is required for scaling policy '{}'",
+                self.display.scaling_policy
+            );
+        }
+
         // Validate EGFX configuration
         match self.egfx.zgfx_compression.as_str() {
             "never" | "auto" | "always" => {}
@@ -225,10 +537,17 @@ impl Config {
     }
 
     /// Override config with CLI arguments
-    pub fn with_overrides(mut self, listen: Option<String>, port: u16) -> Self {
+    ///
+    /// Without `--port`, the port of `server.listen_addr` is kept.
+    pub fn with_overrides(mut self, listen: Option<String>, port: Option<u16>) -> Self {
+        let current_port = self
+            .server
+            .listen_addr
+            .parse::<SocketAddr>()
+            .map_or(3389, |addr| addr.port());
         if let Some(listen_addr) = listen {
-            self.server.listen_addr = format!("{}:{}", listen_addr, port);
-        } else {
+            self.server.listen_addr = format!("{}:{}", listen_addr, port.unwrap_or(current_port));
+        } else if let Some(port) = port {
             // Just update port
             if let Ok(mut addr) = self.server.listen_addr.parse::<SocketAddr>() {
                 addr.set_port(port);
@@ -239,6 +558,27 @@ impl Config {
         self
     }
 
+    /// Dial out to a broker instead of listening (`--connect host:port`)
+    pub fn with_reverse_connect(mut self, target: Option<String>) -> Self {
+        if let Some(target) = target {
+            self.server.reverse_connect = target;
+        }
+        self
+    }
+
+    /// Replace secrets with a placeholder, for reports meant to be shared
+    pub fn redact_secrets(&mut self) {
+        for secret in [
+            &mut self.admin_api.token,
+            &mut self.broker.token,
+            &mut self.crash_report.webhook_url,
+        ] {
+            if !secret.is_empty() {
+                *secret = "<redacted>".to_string();
+            }
+        }
+    }
+
     /// Convert server configuration to Portal configuration
     ///
     /// Maps relevant server settings to `lamco_portal::PortalConfig` for
@@ -313,10 +653,122 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_follow_focus_viewport_parsing() {
+        let mut config = Config::default_config().unwrap();
+        assert_eq!(config.multimon.follow_focus_viewport_size(), Ok(None));
+
+        config.multimon.follow_focus_viewport = "1280x720".to_string();
+        assert_eq!(
+            config.multimon.follow_focus_viewport_size(),
+            Ok(Some((1280, 720)))
+        );
+
+        config.multimon.follow_focus_viewport = "1280".to_string();
+        assert!(config.multimon.follow_focus_viewport_size().is_err());
+    }
+
+    #[test]
+    fn test_scaling_output_parsing() {
+        let mut config = Config::default_config().unwrap();
+        assert_eq!(config.display.scaling_output_dimensions(), Ok(None));
+
+        config.display.scaling_output_size = "1920X1080".to_string();
+        assert_eq!(
+            config.display.scaling_output_dimensions(),
+            Ok(Some((1920, 1080)))
+        );
+
+        config.display.scaling_output_size = "0x1080".to_string();
+        assert!(config.display.scaling_output_dimensions().is_err());
+    }
+
+    #[test]
+    fn test_shadow_listener_must_differ() {
+        let mut config = Config::default_config().unwrap();
+        config.shadow.enabled = true;
+        config.shadow.listen_addr = config.server.listen_addr.clone();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("shadow.listen_addr"));
+    }
+
+    #[test]
+    fn test_broker_requires_http_url() {
+        let mut config = Config::default_config().unwrap();
+        config.broker.enabled = true;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("broker.url"));
+
+        config.broker.kind = "grpc".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_per_session_logs_need_dir() {
+        let mut config = Config::default_config().unwrap();
+        config.logging.per_session_files = true;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("logging.log_dir"));
+    }
+
+    #[test]
+    fn test_frame_dump_frames_bounded() {
+        let mut config = Config::default_config().unwrap();
+        config.frame_dump.frames = 0;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("frame_dump.frames"));
+    }
+
+    #[test]
+    fn test_admin_api_validation() {
+        let mut config = Config::default_config().unwrap();
+        config.admin_api.enabled = true;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("admin_api.token"));
+
+        config.admin_api.token = "secret".to_string();
+        config.admin_api.listen_addr = "0.0.0.0:3392".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("client_ca_path"));
+    }
+
+    #[test]
+    fn test_reverse_connect_validation() {
+        let config = Config::default_config()
+            .unwrap()
+            .with_reverse_connect(Some("broker.example.com".to_string()));
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("reverse connection"));
+    }
+
     #[test]
     fn test_config_validation_invalid_cursor_mode() {
         let mut config = Config::default_config().unwrap();
         config.video.cursor_mode = "invalid_mode".to_string();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_load_rejects_exposed_secret_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let token = dir.path().join("admin-token");
+        std::fs::write(&token, "s3cret\n").unwrap();
+        std::fs::set_permissions(&token, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut config = Config::default_config().unwrap();
+        config.security.cert_path = dir.path().to_path_buf();
+        config.security.key_path = dir.path().to_path_buf();
+        config.admin_api.token = format!("file:{}", token.display());
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+        let path = path.to_str().unwrap();
+
+        let err = Config::load(path).unwrap_err();
+        assert!(err.to_string().contains("admin_api.token"), "{:#}", err);
+
+        std::fs::set_permissions(&token, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(Config::load(path).unwrap().admin_api.token, "s3cret");
+    }
 }
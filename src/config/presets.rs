@@ -0,0 +1,224 @@
+//! Config Presets
+//!
+//! `profile = "wan"` at the top of a config file selects a built-in preset:
+//! a coherent set of video, performance, damage tracking and clipboard
+//! settings for one kind of deployment. Keys the file sets itself override
+//! the preset's, and keys neither sets keep their defaults.
+//!
+//! - **lan** - wired local network: 60 FPS, high bitrate, interactive
+//!   latency, small damage tiles
+//! - **wan** - internet links: 30 FPS, low bitrate, compression, small and
+//!   rate-limited clipboard transfers
+//! - **low-power** - thin clients and battery-powered hosts: 15 FPS, coarse
+//!   damage tiles, the fastest hardware encoder preset
+//! - **kiosk** - public terminals: no clipboard, one client, one monitor
+//!
+//! The hardware profile (`performance.auto_profile`) only adjusts settings
+//! still at their built-in defaults, so preset values that differ stay.
+
+use anyhow::{Context, Result};
+use toml::{Table, Value};
+
+use super::Config;
+
+/// Names of the built-in presets
+pub const PRESETS: [&str; 4] = ["lan", "wan", "low-power", "kiosk"];
+
+/// Key selecting a preset
+pub const PROFILE_KEY: &str = "profile";
+
+const LAN: &str = r#"
+[video]
+target_fps = 60
+bitrate = 20000
+
+[egfx]
+h264_bitrate = 20000
+qp_default = 18
+
+[performance.adaptive_fps]
+max_fps = 60
+
+[performance.latency]
+mode = "interactive"
+
+[damage_tracking]
+tile_size = 16
+merge_distance = 16
+
+[clipboard]
+max_size = 104857600
+"#;
+
+const WAN: &str = r#"
+[video]
+target_fps = 30
+bitrate = 2000
+
+[egfx]
+h264_bitrate = 2000
+adaptive_bitrate = true
+zgfx_compression = "auto"
+
+[performance.adaptive_fps]
+enabled = true
+max_fps = 30
+
+[performance.latency]
+mode = "balanced"
+
+[damage_tracking]
+enabled = true
+tile_size = 32
+diff_threshold = 0.02
+pixel_threshold = 2
+
+[clipboard]
+max_size = 1048576
+rate_limit_ms = 500
+"#;
+
+const LOW_POWER: &str = r#"
+[video]
+target_fps = 15
+
+[egfx]
+h264_bitrate = 3000
+
+[performance.adaptive_fps]
+enabled = true
+min_fps = 2
+max_fps = 15
+
+[performance.latency]
+mode = "quality"
+
+[damage_tracking]
+enabled = true
+tile_size = 64
+diff_threshold = 0.05
+pixel_threshold = 4
+merge_distance = 32
+
+[hardware_encoding]
+quality_preset = "speed"
+"#;
+
+const KIOSK: &str = r#"
+[server]
+multi_client = "single"
+
+[multimon]
+max_monitors = 1
+
+[clipboard]
+enabled = false
+"#;
+
+/// Settings of preset `name`
+pub fn settings(name: &str) -> Result<Table> {
+    let text = match name {
+        "lan" => LAN,
+        "wan" => WAN,
+        "low-power" => LOW_POWER,
+        "kiosk" => KIOSK,
+        _ => anyhow::bail!(
+            "Unknown profile {:?} (expected one of: {})",
+            name,
+            PRESETS.join(", ")
+        ),
+    };
+    Ok(toml::from_str(text).expect("built-in presets are valid TOML"))
+}
+
+/// Fill in the settings of the file's `profile` that it doesn't set
+///
+/// Returns the profile applied, if any.
+pub fn apply(document: &mut Table) -> Result<Option<String>> {
+    let Some(name) = document.get(PROFILE_KEY) else {
+        return Ok(None);
+    };
+    let name = name
+        .as_str()
+        .context("profile must be a string")?
+        .to_string();
+    merge(document, settings(&name)?, false);
+    Ok(Some(name))
+}
+
+impl Config {
+    /// This config switched to preset `name`, its settings replacing the
+    /// current values (for the GUI, where every value is explicit)
+    pub fn with_profile(&self, name: &str) -> Result<Self> {
+        let Value::Table(mut document) =
+            Value::try_from(self).context("Failed to serialize config")?
+        else {
+            anyhow::bail!("Config did not serialize to a table");
+        };
+        merge(&mut document, settings(name)?, true);
+        document.insert(PROFILE_KEY.to_string(), Value::String(name.to_string()));
+        Value::Table(document)
+            .try_into()
+            .context("Failed to apply profile")
+    }
+}
+
+/// Copy the keys of `preset` into `document`, replacing existing values
+/// only with `replace`
+fn merge(document: &mut Table, preset: Table, replace: bool) {
+    for (key, value) in preset {
+        match (document.get_mut(&key), value) {
+            (Some(Value::Table(document)), Value::Table(preset)) => {
+                merge(document, preset, replace)
+            }
+            (Some(existing), value) => {
+                if replace {
+                    *existing = value;
+                }
+            }
+            (None, value) => {
+                document.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::check;
+
+    #[test]
+    fn test_presets_are_valid() {
+        let mut defaults = Config::default_config().unwrap();
+        // Validation needs existing certificate paths
+        defaults.security.cert_path = std::env::temp_dir();
+        defaults.security.key_path = std::env::temp_dir();
+        for name in PRESETS {
+            let config = defaults.with_profile(name).unwrap();
+            assert_eq!(config.profile.as_deref(), Some(name));
+            let (_, issues) = check(&toml::to_string(&config).unwrap());
+            assert!(issues.is_empty(), "{}: {:?}", name, issues);
+        }
+        assert!(settings("fast").is_err());
+    }
+
+    #[test]
+    fn test_file_keys_override_preset() {
+        let mut document: Table = toml::from_str(
+            r#"
+            profile = "wan"
+            [video]
+            target_fps = 20
+            "#,
+        )
+        .unwrap();
+        assert_eq!(apply(&mut document).unwrap().as_deref(), Some("wan"));
+        assert_eq!(document["video"]["target_fps"].as_integer(), Some(20));
+        assert_eq!(document["video"]["bitrate"].as_integer(), Some(2000));
+        assert_eq!(
+            document["clipboard"]["rate_limit_ms"].as_integer(),
+            Some(500)
+        );
+    }
+}
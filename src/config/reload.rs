@@ -0,0 +1,173 @@
+//! Configuration hot-reload
+//!
+//! Compares a freshly loaded configuration against the running one and
+//! splits the differences into settings that can be applied to live
+//! connections and settings that only take effect after a restart.
+//!
+//! # Runtime-Safe Settings
+//!
+//! - `logging.level`
+//! - `egfx.h264_bitrate` (bandwidth cap; the encoder is recreated in place)
+//! - `clipboard.enabled`, `clipboard.rate_limit_ms`
+//! - `performance.adaptive_fps.*` (FPS bounds and activity thresholds)
+//!
+//! Everything else (listen address, TLS, capture, codecs...) is reported as
+//! requiring a restart and left unchanged in the running configuration.
+
+use std::collections::BTreeMap;
+
+use super::Config;
+
+/// Setting paths (or path prefixes ending in `.`) that apply without a restart
+pub const RUNTIME_SETTINGS: &[&str] = &[
+    "logging.level",
+    "egfx.h264_bitrate",
+    "clipboard.enabled",
+    "clipboard.rate_limit_ms",
+    "performance.adaptive_fps.",
+];
+
+/// Outcome of comparing two configurations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Changed settings applied to the running server
+    pub applied: Vec<String>,
+    /// Changed settings that need a restart to take effect
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadReport {
+    /// Compare `current` with `new`
+    ///
+    /// Setting paths are dotted TOML keys, e.g. `server.listen_addr`.
+    pub fn between(current: &Config, new: &Config) -> Self {
+        let mut report = Self::default();
+        for path in changed_settings(current, new) {
+            if is_runtime_setting(&path) {
+                report.applied.push(path);
+            } else {
+                report.restart_required.push(path);
+            }
+        }
+        report
+    }
+
+    /// No settings changed
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.restart_required.is_empty()
+    }
+}
+
+/// Whether a setting can be changed on a running server
+pub fn is_runtime_setting(path: &str) -> bool {
+    RUNTIME_SETTINGS.iter().any(|setting| {
+        if setting.ends_with('.') {
+            path.starts_with(setting)
+        } else {
+            path == *setting
+        }
+    })
+}
+
+impl Config {
+    /// Copy the runtime-safe settings of `new` onto this configuration
+    ///
+    /// Settings that require a restart keep their current values.
+    pub fn with_runtime_settings_from(&self, new: &Config) -> Config {
+        let mut merged = self.clone();
+        merged.logging.level = new.logging.level.clone();
+        merged.egfx.h264_bitrate = new.egfx.h264_bitrate;
+        merged.clipboard.enabled = new.clipboard.enabled;
+        merged.clipboard.rate_limit_ms = new.clipboard.rate_limit_ms;
+        merged.performance.adaptive_fps = new.performance.adaptive_fps.clone();
+        merged
+    }
+}
+
+/// Dotted paths of all settings that differ between two configurations
+fn changed_settings(a: &Config, b: &Config) -> Vec<String> {
+    let a = flatten(a);
+    let b = flatten(b);
+    let mut changed: Vec<String> = a
+        .iter()
+        .filter(|(path, value)| b.get(*path) != Some(value))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(b.keys().filter(|path| !a.contains_key(*path)).cloned());
+    changed.sort();
+    changed
+}
+
+pub(super) fn flatten(config: &Config) -> BTreeMap<String, toml::Value> {
+    let mut settings = BTreeMap::new();
+    if let Ok(value) = toml::Value::try_from(config) {
+        flatten_into(&mut settings, String::new(), value);
+    }
+    settings
+}
+
+pub(super) fn flatten_into(
+    settings: &mut BTreeMap<String, toml::Value>,
+    prefix: String,
+    value: toml::Value,
+) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_into(settings, path, value);
+            }
+        }
+        value => {
+            settings.insert(prefix, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_configs_report_nothing() {
+        let config = Config::default_config().unwrap();
+        assert!(ReloadReport::between(&config, &config.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_classifies_changes() {
+        let current = Config::default_config().unwrap();
+        let mut new = current.clone();
+        new.logging.level = "debug".to_string();
+        new.performance.adaptive_fps.min_fps = 10;
+        new.server.listen_addr = "0.0.0.0:4000".to_string();
+        new.clipboard.max_size = 1;
+
+        let report = ReloadReport::between(&current, &new);
+        assert_eq!(
+            report.applied,
+            vec!["logging.level", "performance.adaptive_fps.min_fps"]
+        );
+        assert_eq!(
+            report.restart_required,
+            vec!["clipboard.max_size", "server.listen_addr"]
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_restart_settings() {
+        let current = Config::default_config().unwrap();
+        let mut new = current.clone();
+        new.egfx.h264_bitrate = 1234;
+        new.server.listen_addr = "0.0.0.0:4000".to_string();
+
+        let merged = current.with_runtime_settings_from(&new);
+        assert_eq!(merged.egfx.h264_bitrate, 1234);
+        assert_eq!(merged.server.listen_addr, current.server.listen_addr);
+        assert!(ReloadReport::between(&merged, &new).applied.is_empty());
+    }
+}
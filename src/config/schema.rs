@@ -0,0 +1,522 @@
+//! Configuration File Checks
+//!
+//! A config file that fails to parse used to come back as one serde error,
+//! and keys serde ignores (typos, settings of other versions) went
+//! unnoticed while their defaults applied. Files are now checked in layers,
+//! each reporting problems with the key and line they are about:
+//!
+//! 1. TOML syntax, after which files of older versions are migrated (see
+//!    [`super::migrate`]) and each migrated setting is a warning; the
+//!    settings of the selected preset ([`super::presets`]) are filled in
+//! 2. Types and required keys (deserializing into [`Config`])
+//! 3. Unknown keys, which have no effect (warnings)
+//! 4. Values outside the range the server can work with
+//! 5. Settings that don't work together, including features this build was
+//!    compiled without
+//! 6. The remaining checks of [`Config::validate`] (addresses, file paths,
+//!    mode names), which report no line
+//! 7. Secret references ([`super::secrets`]) that can't be read or whose
+//!    file permissions are too open
+//!
+//! Layers after a failed syntax or type check are skipped.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use toml::{Table, Value};
+
+use super::migrate::{migrate, VERSION_KEY};
+use super::presets::{self, PROFILE_KEY};
+use super::Config;
+
+/// How serious a problem in a config file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file loads, but probably not as intended
+    Warning,
+    /// The file cannot be used
+    Error,
+}
+
+/// A problem found in a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// Dotted key the problem is about (empty for the file as a whole)
+    pub key: String,
+    /// Line of the key in the file (1-based), where known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    /// `path:line: key: message`, as compilers report
+    pub fn describe(&self, path: &str) -> String {
+        let mut out = path.to_string();
+        if let Some(line) = self.line {
+            out.push_str(&format!(":{}", line));
+        }
+        if !self.key.is_empty() {
+            out.push_str(&format!(": {}", self.key));
+        }
+        format!("{}: {}", out, self.message)
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe("config"))
+    }
+}
+
+/// Check a config file's contents, layer by layer
+///
+/// Returns the config when it parsed, with every problem found. A config
+/// is returned even when later layers found errors.
+pub fn check(content: &str) -> (Option<Config>, Vec<ConfigIssue>) {
+    let lines = key_lines(content);
+    let at = |key: &str| lines.get(key).copied();
+    let issue = |severity, key: &str, message: String| ConfigIssue {
+        severity,
+        key: key.to_string(),
+        line: at(key),
+        message,
+    };
+
+    // 1. Syntax
+    let mut document: Table = match toml::from_str(content) {
+        Ok(document) => document,
+        Err(e) => return (None, vec![parse_issue(content, &lines, &e)]),
+    };
+
+    // Files of older versions are checked as migrated
+    let mut issues = Vec::new();
+    let migrations = match migrate(&mut document) {
+        Ok(migrations) => migrations,
+        Err(e) => {
+            return (
+                None,
+                vec![issue(Severity::Error, VERSION_KEY, format!("{:#}", e))],
+            )
+        }
+    };
+    for migration in &migrations {
+        issues.push(issue(
+            Severity::Warning,
+            &migration.key,
+            format!(
+                "{} (from an older version; run `config migrate` to update the file)",
+                migration.change
+            ),
+        ));
+    }
+    // Settings of the selected preset the file doesn't set
+    let profile = match presets::apply(&mut document) {
+        Ok(profile) => profile,
+        Err(e) => {
+            issues.push(issue(Severity::Error, PROFILE_KEY, format!("{:#}", e)));
+            return (None, issues);
+        }
+    };
+    let document = Value::Table(document);
+
+    // 2. Types and required keys
+    let parsed = if migrations.is_empty() && profile.is_none() {
+        // Parsing the text locates errors
+        toml::from_str(content)
+    } else {
+        document.clone().try_into()
+    };
+    let mut config: Config = match parsed {
+        Ok(config) => config,
+        Err(e) => {
+            issues.push(parse_issue(content, &lines, &e));
+            return (None, issues);
+        }
+    };
+
+    // 3. Unknown keys
+    if let Ok(known) = Value::try_from(&config) {
+        let mut unknown = Vec::new();
+        unknown_keys(&document, &known, "", &mut unknown);
+        for key in unknown {
+            issues.push(issue(
+                Severity::Warning,
+                &key,
+                "unknown key, ignored".to_string(),
+            ));
+        }
+    }
+
+    // 4.-6. Values of the parsed config
+    for mut value_issue in check_config(&config) {
+        value_issue.line = at(&value_issue.key);
+        issues.push(value_issue);
+    }
+
+    // 7. Secret references
+    for (key, e) in config.resolve_secrets() {
+        issues.push(issue(Severity::Error, key, format!("{:#}", e)));
+    }
+
+    (Some(config), issues)
+}
+
+/// Check the values of a parsed config (layers 4 to 6)
+///
+/// Issues carry no line. Used for files by [`check`], and for settings
+/// changed at runtime.
+pub fn check_config(config: &Config) -> Vec<ConfigIssue> {
+    let issue = |severity, key: &str, message: String| ConfigIssue {
+        severity,
+        key: key.to_string(),
+        line: None,
+        message,
+    };
+    let mut issues = Vec::new();
+
+    // 4. Ranges
+    for (key, value, range) in ranges(config) {
+        if !range.contains(&value) {
+            issues.push(issue(
+                Severity::Error,
+                key,
+                format!(
+                    "{} is out of range ({} to {})",
+                    value,
+                    range.start(),
+                    range.end()
+                ),
+            ));
+        }
+    }
+
+    // 5. Combinations
+    for (severity, key, message) in combinations(config) {
+        issues.push(issue(severity, key, message));
+    }
+
+    // 6. Everything else
+    if let Err(e) = config.validate() {
+        issues.push(issue(Severity::Error, "", format!("{:#}", e)));
+    }
+
+    issues
+}
+
+/// Issue for a TOML syntax or type error, located by its span
+fn parse_issue(content: &str, lines: &HashMap<String, usize>, e: &toml::de::Error) -> ConfigIssue {
+    let line = e.span().map(|span| {
+        content[..span.start.min(content.len())]
+            .matches('\n')
+            .count()
+            + 1
+    });
+    // The error's span covers the value or table; name the key on its line
+    let key = line
+        .and_then(|line| {
+            lines
+                .iter()
+                .filter(|&(_, &at)| at == line)
+                .map(|(key, _)| key)
+                .max_by_key(|key| key.len())
+        })
+        .cloned()
+        .unwrap_or_default();
+    ConfigIssue {
+        severity: Severity::Error,
+        key,
+        line,
+        message: e.message().trim().to_string(),
+    }
+}
+
+/// Keys of `input` that `known` (the parsed config) has no place for
+fn unknown_keys(input: &Value, known: &Value, path: &str, out: &mut Vec<String>) {
+    match (input, known) {
+        (Value::Table(input), Value::Table(known)) => {
+            for (key, value) in input {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match known.get(key) {
+                    Some(known) => unknown_keys(value, known, &path, out),
+                    None => out.push(path),
+                }
+            }
+        }
+        (Value::Array(input), Value::Array(known)) => {
+            for (i, (input, known)) in input.iter().zip(known).enumerate() {
+                unknown_keys(input, known, &format!("{}[{}]", path, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Numeric settings and the values the server can work with
+fn ranges(config: &Config) -> Vec<(&'static str, f64, RangeInclusive<f64>)> {
+    let fps = 1.0..=240.0;
+    let qp = 0.0..=51.0;
+    let ratio = 0.0..=1.0;
+    let adaptive = &config.performance.adaptive_fps;
+    vec![
+        (
+            "server.max_connections",
+            config.server.max_connections as f64,
+            1.0..=1024.0,
+        ),
+        (
+            "video.target_fps",
+            f64::from(config.video.target_fps),
+            fps.clone(),
+        ),
+        ("egfx.qp_min", f64::from(config.egfx.qp_min), qp.clone()),
+        ("egfx.qp_max", f64::from(config.egfx.qp_max), qp.clone()),
+        ("egfx.qp_default", f64::from(config.egfx.qp_default), qp),
+        (
+            "egfx.max_frames_in_flight",
+            f64::from(config.egfx.max_frames_in_flight),
+            1.0..=64.0,
+        ),
+        (
+            "performance.adaptive_fps.min_fps",
+            f64::from(adaptive.min_fps),
+            fps.clone(),
+        ),
+        (
+            "performance.adaptive_fps.max_fps",
+            f64::from(adaptive.max_fps),
+            fps.clone(),
+        ),
+        (
+            "performance.adaptive_fps.high_activity_threshold",
+            f64::from(adaptive.high_activity_threshold),
+            ratio.clone(),
+        ),
+        (
+            "performance.adaptive_fps.medium_activity_threshold",
+            f64::from(adaptive.medium_activity_threshold),
+            ratio.clone(),
+        ),
+        (
+            "performance.adaptive_fps.low_activity_threshold",
+            f64::from(adaptive.low_activity_threshold),
+            ratio.clone(),
+        ),
+        (
+            "cursor.cursor_update_fps",
+            f64::from(config.cursor.cursor_update_fps),
+            fps,
+        ),
+        (
+            "cursor.predictor.lookahead_rtt_fraction",
+            f64::from(config.cursor.predictor.lookahead_rtt_fraction),
+            ratio,
+        ),
+    ]
+}
+
+/// Settings that contradict each other or this build
+fn combinations(config: &Config) -> Vec<(Severity, &'static str, String)> {
+    let mut issues = Vec::new();
+    let hardware = &config.hardware_encoding;
+    let vaapi = cfg!(feature = "vaapi");
+    let nvenc = cfg!(feature = "nvenc");
+
+    if config.video.encoder == "vaapi" && !vaapi {
+        issues.push((
+            Severity::Error,
+            "video.encoder",
+            "\"vaapi\" needs a build with the vaapi feature".to_string(),
+        ));
+    }
+    if hardware.enabled && !vaapi && !nvenc {
+        let message = "hardware encoding needs a build with the vaapi or nvenc feature";
+        if hardware.fallback_to_software {
+            issues.push((
+                Severity::Warning,
+                "hardware_encoding.enabled",
+                format!("{}; software encoding is used", message),
+            ));
+        } else {
+            issues.push((
+                Severity::Error,
+                "hardware_encoding.enabled",
+                format!("{} (or fallback_to_software = true)", message),
+            ));
+        }
+    }
+    if hardware.enabled && hardware.prefer_nvenc && !nvenc {
+        issues.push((
+            Severity::Warning,
+            "hardware_encoding.prefer_nvenc",
+            "NVENC is not compiled into this build (nvenc feature); it is never used".to_string(),
+        ));
+    }
+
+    let adaptive = &config.performance.adaptive_fps;
+    if adaptive.min_fps > adaptive.max_fps {
+        issues.push((
+            Severity::Error,
+            "performance.adaptive_fps.min_fps",
+            format!(
+                "{} is above performance.adaptive_fps.max_fps ({})",
+                adaptive.min_fps, adaptive.max_fps
+            ),
+        ));
+    }
+    if !(adaptive.low_activity_threshold <= adaptive.medium_activity_threshold
+        && adaptive.medium_activity_threshold <= adaptive.high_activity_threshold)
+    {
+        issues.push((
+            Severity::Error,
+            "performance.adaptive_fps.medium_activity_threshold",
+            "activity thresholds must rise from low to medium to high".to_string(),
+        ));
+    }
+
+    if config.egfx.codec == "avc444" && !config.egfx.enabled {
+        issues.push((
+            Severity::Warning,
+            "egfx.codec",
+            "AVC444 is only sent over EGFX, which egfx.enabled turns off".to_string(),
+        ));
+    }
+
+    issues
+}
+
+/// Line (1-based) of each key and table set in `content`, by dotted path
+///
+/// Follows `[table]` and `[[array]]` headers and dotted keys; entries of
+/// arrays of tables get their index, e.g. `cursor.app_overrides[1].mode`.
+fn key_lines(content: &str) -> HashMap<String, usize> {
+    fn normalize(key: &str) -> Option<String> {
+        let parts: Vec<&str> = key
+            .split('.')
+            .map(|part| part.trim().trim_matches(|c| c == '"' || c == '\''))
+            .collect();
+        parts
+            .iter()
+            .all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            })
+            .then(|| parts.join("."))
+    }
+
+    let mut lines = HashMap::new();
+    let mut table = String::new();
+    let mut array_counts: HashMap<String, usize> = HashMap::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix("[[") {
+            let Some(name) = header.split("]]").next().and_then(normalize) else {
+                continue;
+            };
+            let count = array_counts.entry(name.clone()).or_insert(0);
+            table = format!("{}[{}]", name, count);
+            *count += 1;
+            lines.insert(table.clone(), n + 1);
+        } else if let Some(header) = line.strip_prefix('[') {
+            let Some(name) = header.split(']').next().and_then(normalize) else {
+                continue;
+            };
+            table = name;
+            lines.entry(table.clone()).or_insert(n + 1);
+        } else if let Some((key, _)) = line.split_once('=') {
+            let Some(key) = normalize(key) else {
+                continue;
+            };
+            let path = if table.is_empty() {
+                key
+            } else {
+                format!("{}.{}", table, key)
+            };
+            lines.entry(path).or_insert(n + 1);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_file() -> String {
+        toml::to_string(&Config::default_config().unwrap()).unwrap()
+    }
+
+    fn line_of(content: &str, text: &str) -> usize {
+        content.lines().position(|line| line == text).unwrap() + 1
+    }
+
+    #[test]
+    fn test_issues_carry_key_and_line() {
+        let content = default_file()
+            .replace("[video]\n", "[video]\nframe_rate = 60\n")
+            .replacen("target_fps = 30", "target_fps = 0", 1);
+        let (config, issues) = check(&content);
+        assert!(config.is_some());
+
+        let unknown = issues.iter().find(|i| i.key == "video.frame_rate").unwrap();
+        assert_eq!(unknown.severity, Severity::Warning);
+        assert_eq!(unknown.line, Some(line_of(&content, "frame_rate = 60")));
+
+        let range = issues.iter().find(|i| i.key == "video.target_fps").unwrap();
+        assert_eq!(range.severity, Severity::Error);
+        assert_eq!(range.line, Some(line_of(&content, "target_fps = 0")));
+        assert!(range.describe("a.toml").starts_with("a.toml:"));
+    }
+
+    #[test]
+    fn test_type_errors_are_located() {
+        let content = default_file().replacen("target_fps = 30", "target_fps = \"fast\"", 1);
+        let (config, issues) = check(&content);
+        assert!(config.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].line,
+            Some(line_of(&content, "target_fps = \"fast\""))
+        );
+        assert_eq!(issues[0].key, "video.target_fps");
+    }
+
+    #[test]
+    fn test_older_versions_migrated() {
+        let content = default_file()
+            .replace("config_version = 2\n", "")
+            .replace("cursor_mode = \"metadata\"", "cursor_mode = \"Metadata\"");
+        let (config, issues) = check(&content);
+        assert_eq!(config.unwrap().video.cursor_mode, "metadata");
+        let migrated = issues
+            .iter()
+            .find(|i| i.key == "video.cursor_mode")
+            .unwrap();
+        assert_eq!(migrated.severity, Severity::Warning);
+        assert_eq!(
+            migrated.line,
+            Some(line_of(&content, "cursor_mode = \"Metadata\""))
+        );
+
+        let (config, issues) = check("config_version = 99\n");
+        assert!(config.is_none());
+        assert_eq!(issues[0].key, VERSION_KEY);
+    }
+
+    #[test]
+    fn test_key_lines() {
+        let lines =
+            key_lines("top = 1\n[a]\nb.c = 2\n[[list]]\nx = 1\n[[list]]\n\"x\" = 2\nnot a key\n");
+        assert_eq!(lines["top"], 1);
+        assert_eq!(lines["a.b.c"], 3);
+        assert_eq!(lines["list[0].x"], 5);
+        assert_eq!(lines["list[1].x"], 7);
+    }
+}
@@ -0,0 +1,125 @@
+//! Secret References
+//!
+//! Credential-bearing settings (`admin_api.token`, `broker.token`,
+//! `crash_report.webhook_url`) can hold a reference instead of the secret
+//! itself, keeping it out of config.toml, which tends to be world-readable,
+//! kept in version control or attached to support tickets:
+//!
+//! - `"file:/etc/lamco-rdp-server/admin-token"` - read from a separate
+//!   file. It must be a regular file owned by root or the server's user
+//!   and not accessible by group or others (mode 0600 or 0400).
+//! - `"credential:admin-token"` - read from the systemd credential of that
+//!   name (`LoadCredential=admin-token:/path` in the unit), found in
+//!   `$CREDENTIALS_DIRECTORY`. The same permission checks apply.
+//!
+//! Surrounding whitespace such as a trailing newline is trimmed. References
+//! are resolved when the file is loaded; one that can't be resolved fails
+//! loading like any other config error.
+
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::Config;
+
+impl Config {
+    /// Replace secret references with the secrets they refer to
+    ///
+    /// Returns the key and error of each reference that could not be
+    /// resolved; its setting keeps the reference.
+    pub fn resolve_secrets(&mut self) -> Vec<(&'static str, anyhow::Error)> {
+        let mut errors = Vec::new();
+        for (key, value) in [
+            ("admin_api.token", &mut self.admin_api.token),
+            ("broker.token", &mut self.broker.token),
+            (
+                "crash_report.webhook_url",
+                &mut self.crash_report.webhook_url,
+            ),
+        ] {
+            match resolve(value) {
+                Ok(secret) => *value = secret,
+                Err(e) => errors.push((key, e)),
+            }
+        }
+        errors
+    }
+}
+
+/// The secret `value` refers to, or `value` itself if it is no reference
+pub fn resolve(value: &str) -> Result<String> {
+    let path = if let Some(path) = value.strip_prefix("file:") {
+        PathBuf::from(path)
+    } else if let Some(name) = value.strip_prefix("credential:") {
+        let dir = std::env::var_os("CREDENTIALS_DIRECTORY").context(
+            "$CREDENTIALS_DIRECTORY is not set (add LoadCredential= to the systemd unit)",
+        )?;
+        if name.is_empty() || name.contains('/') {
+            anyhow::bail!("Invalid credential name: {:?}", name);
+        }
+        Path::new(&dir).join(name)
+    } else {
+        return Ok(value.to_string());
+    };
+
+    check_permissions(&path)?;
+    let secret = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let secret = secret.trim();
+    if secret.is_empty() {
+        anyhow::bail!("{} is empty", path.display());
+    }
+    Ok(secret.to_string())
+}
+
+/// Refuse secret files others could read or replace
+fn check_permissions(path: &Path) -> Result<()> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("{} is not a regular file", path.display());
+    }
+    let mode = metadata.mode() & 0o777;
+    if mode & 0o077 != 0 {
+        anyhow::bail!(
+            "{} is accessible by group or others (mode {:03o}); restrict it with chmod 600",
+            path.display(),
+            mode
+        );
+    }
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != 0 && metadata.uid() != uid {
+        anyhow::bail!(
+            "{} is owned by uid {}, not root or the server's user",
+            path.display(),
+            metadata.uid()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_file_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "s3cret\n").unwrap();
+        let reference = format!("file:{}", path.display());
+
+        // Readable by others
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(resolve(&reference).is_err());
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(resolve(&reference).unwrap(), "s3cret");
+
+        // Plain values are secrets themselves
+        assert_eq!(resolve("s3cret").unwrap(), "s3cret");
+        assert!(resolve("file:/nonexistent/token").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Runtime-Tunable Settings
+//!
+//! The runtime-safe settings of [`super::reload`] can be read and changed
+//! one at a time on a running server through the admin API. The exception
+//! is `logging.level`, whose log filter belongs to the process rather than
+//! the server. Changes are validated like the config file and kept in a
+//! drop-in next to it, `<config>.d/runtime.toml`, so they survive restarts
+//! and reloads: [`Config::load`] applies the drop-in over the file, and
+//! environment and command-line overrides still come on top.
+//!
+//! Each change is recorded as an [`AuditEntry`] and appended as a JSON line
+//! to `<config>.d/runtime-audit.jsonl`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use super::migrate::get_mut;
+use super::reload::{flatten, flatten_into, is_runtime_setting};
+use super::schema::{check_config, Severity};
+use super::Config;
+
+/// Drop-in holding settings changed at runtime
+pub const DROP_IN_FILE: &str = "runtime.toml";
+
+/// Audit trail of settings changed at runtime (JSON lines)
+pub const AUDIT_FILE: &str = "runtime-audit.jsonl";
+
+/// A setting changed at runtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Time of the change (Unix seconds)
+    pub at: u64,
+    /// Who changed it, as reported by the client
+    pub actor: String,
+    /// Dotted key of the setting
+    pub key: String,
+    /// Value before the change
+    pub old: Value,
+    /// Value after the change
+    pub new: Value,
+}
+
+/// Drop-in directory of the config file at `config_path` (`<config>.d`)
+pub fn drop_in_dir(config_path: &Path) -> PathBuf {
+    let mut dir = config_path.as_os_str().to_owned();
+    dir.push(".d");
+    PathBuf::from(dir)
+}
+
+/// Whether `key` can be changed on a running server
+pub fn is_tunable(key: &str) -> bool {
+    key != "logging.level" && is_runtime_setting(key)
+}
+
+impl Config {
+    /// Current values of the tunable settings, by dotted key
+    pub fn tunable_settings(&self) -> BTreeMap<String, Value> {
+        flatten(self)
+            .into_iter()
+            .filter(|(key, _)| is_tunable(key))
+            .collect()
+    }
+
+    /// This config with tunable setting `key` set to `value`
+    ///
+    /// The result is checked like a config file; integers are accepted for
+    /// decimal settings.
+    pub fn with_tunable_setting(&self, key: &str, value: Value) -> Result<Self> {
+        if !is_tunable(key) {
+            anyhow::bail!("{} is not a runtime-tunable setting", key);
+        }
+        let Value::Table(mut document) =
+            Value::try_from(self).context("Failed to serialize config")?
+        else {
+            anyhow::bail!("Config did not serialize to a table");
+        };
+        let slot = get_mut(&mut document, key)
+            .with_context(|| format!("{} is not a runtime-tunable setting", key))?;
+        *slot = match (&*slot, value) {
+            (Value::Float(_), Value::Integer(i)) => Value::Float(i as f64),
+            (current, value) if current.same_type(&value) => value,
+            (current, value) => anyhow::bail!(
+                "{} expects {}, got {}",
+                key,
+                current.type_str(),
+                value.type_str()
+            ),
+        };
+
+        let config: Config = Value::Table(document)
+            .try_into()
+            .with_context(|| format!("Invalid value for {}", key))?;
+        let errors: Vec<String> = check_config(&config)
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.to_string())
+            .collect();
+        if !errors.is_empty() {
+            anyhow::bail!("{}", errors.join("; "));
+        }
+        Ok(config)
+    }
+
+    /// Apply the drop-in of settings changed at runtime, if there is one
+    pub fn with_drop_in(self, dir: &Path) -> Result<Self> {
+        let path = dir.join(DROP_IN_FILE);
+        if !path.exists() {
+            return Ok(self);
+        }
+        let mut config = self;
+        for (key, value) in read_drop_in(&path)? {
+            config = config
+                .with_tunable_setting(&key, value)
+                .with_context(|| format!("Invalid setting in {}", path.display()))?;
+        }
+        Ok(config)
+    }
+}
+
+/// Settings of a drop-in, by dotted key
+fn read_drop_in(path: &Path) -> Result<BTreeMap<String, Value>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let document: Table =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut settings = BTreeMap::new();
+    flatten_into(&mut settings, String::new(), Value::Table(document));
+    Ok(settings)
+}
+
+/// Keep `key = value` in the drop-in of `dir`, with earlier changes
+pub fn persist(dir: &Path, key: &str, value: &Value) -> Result<()> {
+    let path = dir.join(DROP_IN_FILE);
+    let mut settings = if path.exists() {
+        read_drop_in(&path)?
+    } else {
+        BTreeMap::new()
+    };
+    settings.insert(key.to_string(), value.clone());
+
+    let mut document = Table::new();
+    for (key, value) in settings {
+        let mut table = &mut document;
+        let mut names: Vec<&str> = key.split('.').collect();
+        let last = names.pop().unwrap_or_default();
+        for name in names {
+            table = table
+                .entry(name)
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("{} is not a table", name))?;
+        }
+        table.insert(last.to_string(), value);
+    }
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let content = format!(
+        "# Settings changed at runtime through the admin API; they override\n\
+         # the config file. Delete a key to return to the file's value.\n\n{}",
+        toml::to_string_pretty(&document).context("Failed to serialize settings")?
+    );
+    // Replace atomically so a crash never leaves a truncated drop-in
+    let temp = dir.join(format!(".{}.tmp", DROP_IN_FILE));
+    std::fs::write(&temp, content)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, &path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Append `entry` to the audit trail of `dir`
+pub fn record(dir: &Path, entry: &AuditEntry) -> Result<()> {
+    let path = dir.join(AUDIT_FILE);
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let line = serde_json::to_string(entry).context("Failed to serialize audit entry")?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        let mut config = Config::default_config().unwrap();
+        // Validation needs existing certificate paths
+        config.security.cert_path = std::env::temp_dir();
+        config.security.key_path = std::env::temp_dir();
+        config
+    }
+
+    #[test]
+    fn test_tunable_settings_validated() {
+        let config = config();
+        let settings = config.tunable_settings();
+        assert!(settings.contains_key("egfx.h264_bitrate"));
+        assert!(settings.contains_key("performance.adaptive_fps.max_fps"));
+        assert!(!settings.contains_key("logging.level"));
+
+        let updated = config
+            .with_tunable_setting("performance.adaptive_fps.max_fps", Value::Integer(60))
+            .unwrap();
+        assert_eq!(updated.performance.adaptive_fps.max_fps, 60);
+
+        // Decimal settings take integers
+        assert!(config
+            .with_tunable_setting(
+                "performance.adaptive_fps.high_activity_threshold",
+                Value::Integer(1)
+            )
+            .is_ok());
+        // Out of range, wrong type, not tunable
+        assert!(config
+            .with_tunable_setting("performance.adaptive_fps.max_fps", Value::Integer(0))
+            .is_err());
+        assert!(config
+            .with_tunable_setting("clipboard.enabled", Value::Integer(1))
+            .is_err());
+        assert!(config
+            .with_tunable_setting("server.listen_addr", Value::String("0.0.0.0:1".into()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_drop_in_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        persist(dir.path(), "egfx.h264_bitrate", &Value::Integer(2000)).unwrap();
+        persist(dir.path(), "clipboard.enabled", &Value::Boolean(false)).unwrap();
+
+        let config = config().with_drop_in(dir.path()).unwrap();
+        assert_eq!(config.egfx.h264_bitrate, 2000);
+        assert!(!config.clipboard.enabled);
+
+        let entry = AuditEntry {
+            at: 1,
+            actor: "alice".to_string(),
+            key: "egfx.h264_bitrate".to_string(),
+            old: Value::Integer(5000),
+            new: Value::Integer(2000),
+        };
+        record(dir.path(), &entry).unwrap();
+        let line = std::fs::read_to_string(dir.path().join(AUDIT_FILE)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AuditEntry>(line.trim()).unwrap(),
+            entry
+        );
+    }
+}
@@ -17,6 +17,89 @@ pub struct ServerConfig {
 
     /// Use XDG Desktop Portals for screen capture
     pub use_portals: bool,
+
+    /// Concurrent client handling: "single" (one client at a time),
+    /// "shared" (extra clients view the same capture), "separate"
+    /// (extra clients get their own Portal session and permission)
+    #[serde(default = "default_multi_client")]
+    pub multi_client: String,
+
+    /// Reverse connection target ("host:port"); when set the server dials
+    /// out to this broker instead of listening
+    #[serde(default)]
+    pub reverse_connect: String,
+
+    /// Keepalive interval on the reverse control connection (seconds)
+    #[serde(default = "default_reverse_heartbeat_secs")]
+    pub reverse_heartbeat_secs: u64,
+
+    /// Initial reconnect delay for the reverse control connection (seconds)
+    #[serde(default = "default_reverse_retry_secs")]
+    pub reverse_retry_secs: u64,
+
+    /// Idle time before TCP keepalive probes start (seconds, 0 = disabled)
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+
+    /// Interval between TCP keepalive probes (seconds)
+    #[serde(default = "default_tcp_keepalive_interval_secs")]
+    pub tcp_keepalive_interval_secs: u64,
+
+    /// Unanswered keepalive probes before the connection is dropped
+    #[serde(default = "default_tcp_keepalive_probes")]
+    pub tcp_keepalive_probes: u32,
+
+    /// Drop a client that has not acknowledged sent data for this long
+    /// (seconds, 0 = disabled)
+    #[serde(default = "default_dead_peer_timeout_secs")]
+    pub dead_peer_timeout_secs: u64,
+
+    /// Hand listening sockets to a re-executed binary on SIGUSR2
+    #[serde(default)]
+    pub upgrade_handoff: bool,
+
+    /// On SIGTERM/SIGINT, how long clients get to disconnect after being
+    /// told the server is ending their session (seconds)
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+
+    /// Stop capture and encoding after this long without clients, until
+    /// the next connection (seconds, 0 = keep capturing)
+    #[serde(default = "default_idle_stop_secs")]
+    pub idle_stop_secs: u64,
+
+    /// On GNOME, listen on the next free port when gnome-remote-desktop
+    /// holds the one of `listen_addr`
+    #[serde(default)]
+    pub deconflict_port: bool,
+}
+
+fn default_multi_client() -> String {
+    "single".to_string()
+}
+fn default_reverse_heartbeat_secs() -> u64 {
+    30
+}
+fn default_reverse_retry_secs() -> u64 {
+    5
+}
+fn default_tcp_keepalive_secs() -> u64 {
+    10
+}
+fn default_tcp_keepalive_interval_secs() -> u64 {
+    5
+}
+fn default_tcp_keepalive_probes() -> u32 {
+    3
+}
+fn default_dead_peer_timeout_secs() -> u64 {
+    20
+}
+fn default_shutdown_grace_secs() -> u64 {
+    10
+}
+fn default_idle_stop_secs() -> u64 {
+    30
 }
 
 /// Security and authentication configuration
@@ -71,6 +154,17 @@ pub struct InputConfig {
 
     /// Enable touch input support
     pub enable_touch: bool,
+
+    /// Input backends to fail over to, in order (empty: close the session)
+    #[serde(default = "default_input_fallback_chain")]
+    pub fallback_chain: Vec<String>,
+}
+
+fn default_input_fallback_chain() -> Vec<String> {
+    crate::session::failover::DEFAULT_CHAIN
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
 }
 
 /// Clipboard configuration
@@ -103,6 +197,95 @@ pub struct MultiMonitorConfig {
 
     /// Maximum number of monitors to support
     pub max_monitors: usize,
+
+    /// Follow-focus capture mode: "off", "monitor", "pointer"
+    /// - "off": Client sees the whole desktop
+    /// - "monitor": Client sees one monitor; push the pointer against an edge to switch
+    /// - "pointer": Client sees a viewport that pans with the pointer
+    #[serde(default = "default_follow_focus")]
+    pub follow_focus: String,
+
+    /// Client viewport size for follow-focus ("WIDTHxHEIGHT", empty = primary monitor size)
+    #[serde(default)]
+    pub follow_focus_viewport: String,
+
+    /// How long the pointer must push against an edge before switching monitors (ms)
+    #[serde(default = "default_follow_focus_dwell_ms")]
+    pub follow_focus_dwell_ms: u64,
+
+    /// Per-monitor layout (empty = share every monitor where the compositor
+    /// places it, at the pipeline frame rate)
+    #[serde(default)]
+    pub monitors: Vec<MonitorLayoutConfig>,
+}
+
+/// Layout of one monitor in the RDP virtual desktop
+///
+/// A monitor is identified by its position on the local desktop, which is
+/// what every capture source reports; `name` is informational.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorLayoutConfig {
+    /// Connector name, e.g. "DP-1"
+    #[serde(default)]
+    pub name: String,
+
+    /// Position on the local desktop
+    pub desktop_x: i32,
+    pub desktop_y: i32,
+
+    /// Include this monitor in the session
+    #[serde(default = "default_true")]
+    pub shared: bool,
+
+    /// Position in the RDP virtual desktop
+    pub x: i32,
+    pub y: i32,
+
+    /// Capture frame rate (0 = pipeline rate, `performance.adaptive_fps.max_fps`)
+    #[serde(default)]
+    pub fps: u32,
+}
+
+fn default_follow_focus() -> String {
+    "off".to_string()
+}
+
+fn default_follow_focus_dwell_ms() -> u64 {
+    300
+}
+
+impl MultiMonitorConfig {
+    /// Parse `follow_focus_viewport` into a size
+    ///
+    /// Returns `Ok(None)` when unset (use primary monitor size).
+    pub fn follow_focus_viewport_size(&self) -> Result<Option<(u32, u32)>, String> {
+        parse_resolution(&self.follow_focus_viewport, "follow-focus viewport")
+    }
+}
+
+/// Parse a `"WIDTHxHEIGHT"` resolution string
+///
+/// Returns `Ok(None)` for an empty string. `what` names the setting in errors.
+pub fn parse_resolution(spec: &str, what: &str) -> Result<Option<(u32, u32)>, String> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return Ok(None);
+    }
+    let (w, h) = spec
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("Invalid {}: {}", what, spec))?;
+    let width: u32 = w
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} width: {}", what, w))?;
+    let height: u32 = h
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} height: {}", what, h))?;
+    if width == 0 || height == 0 {
+        return Err(format!("{} must be non-zero: {}", what, spec));
+    }
+    Ok(Some((width, height)))
 }
 
 /// Performance tuning configuration
@@ -127,15 +310,26 @@ pub struct PerformanceConfig {
     /// Latency governor configuration (Premium feature)
     #[serde(default)]
     pub latency: LatencyConfig,
+
+    /// CPU placement and realtime priority of the capture and encode threads
+    #[serde(default)]
+    pub scheduling: SchedulingConfig,
+
+    /// Fit settings left at their defaults to a startup hardware probe
+    #[serde(default = "default_true")]
+    pub auto_profile: bool,
 }
 
 /// Adaptive FPS configuration
 ///
-/// Dynamically adjusts frame rate based on screen activity:
+/// Dynamically adjusts frame rate based on screen activity, within
+/// `min_fps..=max_fps`:
 /// - Static screen: 5 FPS (saves CPU/bandwidth)
 /// - Low activity: 15 FPS (typing, cursor)
-/// - Medium activity: 20 FPS (scrolling)
-/// - High activity: 30-60 FPS (video, dragging)
+/// - Medium activity: 30 FPS (scrolling)
+/// - High activity: 60 FPS (video, dragging)
+///
+/// A client whose frame acknowledgements lag behind gets one tier less.
 ///
 /// # High Performance Mode (60 FPS)
 ///
@@ -163,6 +357,8 @@ pub struct AdaptiveFpsConfig {
     pub min_fps: u32,
 
     /// Maximum FPS for high activity (default: 30, set to 60 for high-performance mode)
+    ///
+    /// PipeWire streams ask the compositor for at most this rate.
     #[serde(default = "default_max_fps")]
     pub max_fps: u32,
 
@@ -177,6 +373,11 @@ pub struct AdaptiveFpsConfig {
     /// Damage ratio threshold for low activity (0.0-1.0)
     #[serde(default = "default_low_activity")]
     pub low_activity_threshold: f32,
+
+    /// EGFX frame acknowledgement latency above which the client gets one
+    /// FPS tier less (0 = ignore acknowledgements)
+    #[serde(default = "default_max_ack_latency_ms")]
+    pub max_ack_latency_ms: u32,
 }
 
 fn default_min_fps() -> u32 {
@@ -194,6 +395,9 @@ fn default_medium_activity() -> f32 {
 fn default_low_activity() -> f32 {
     0.01
 }
+fn default_max_ack_latency_ms() -> u32 {
+    200
+}
 
 impl Default for AdaptiveFpsConfig {
     fn default() -> Self {
@@ -204,6 +408,50 @@ impl Default for AdaptiveFpsConfig {
             high_activity_threshold: 0.30,
             medium_activity_threshold: 0.10,
             low_activity_threshold: 0.01,
+            max_ack_latency_ms: default_max_ack_latency_ms(),
+        }
+    }
+}
+
+/// Thread scheduling configuration
+///
+/// Pins the PipeWire capture thread and the encode thread to CPUs and asks
+/// for a realtime policy, so frames keep their pace on a loaded system.
+/// Without privileges (CAP_SYS_NICE), realtime scheduling is requested from
+/// rtkit, which only grants SCHED_RR up to its own priority limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulingConfig {
+    /// CPUs for the PipeWire capture thread (empty = no pinning)
+    #[serde(default)]
+    pub pipewire_cpus: Vec<usize>,
+
+    /// CPUs for the encode thread (empty = no pinning)
+    #[serde(default)]
+    pub encoder_cpus: Vec<usize>,
+
+    /// Realtime policy: "none", "rr" or "fifo"
+    #[serde(default = "default_realtime_policy")]
+    pub realtime: String,
+
+    /// Realtime priority (1-99)
+    #[serde(default = "default_realtime_priority")]
+    pub realtime_priority: u32,
+}
+
+fn default_realtime_policy() -> String {
+    "none".to_string()
+}
+fn default_realtime_priority() -> u32 {
+    10
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            pipewire_cpus: Vec::new(),
+            encoder_cpus: Vec::new(),
+            realtime: default_realtime_policy(),
+            realtime_priority: default_realtime_priority(),
         }
     }
 }
@@ -214,6 +462,10 @@ impl Default for AdaptiveFpsConfig {
 /// - Interactive: <50ms (gaming, CAD)
 /// - Balanced: <100ms (general desktop)
 /// - Quality: <300ms (photo/video editing)
+///
+/// Frames that miss the mode's latency budget, or find the client with too
+/// many unacknowledged frames, are dropped. Interactive mode also caps the
+/// H.264 bitrate (8 Mbps) and the periodic IDR interval (2 s).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LatencyConfig {
     /// Latency mode: "interactive", "balanced", "quality"
@@ -299,11 +551,45 @@ pub struct CursorConfig {
     #[serde(default = "default_cursor_fps")]
     pub cursor_update_fps: u32,
 
+    /// Send cursor shapes as 24-bit color pointers with an AND mask instead
+    /// of 32-bit alpha pointers, for old clients that show a generic arrow
+    #[serde(default)]
+    pub color_pointers: bool,
+
+    /// Video frame rate below which a painted or predictive cursor is drawn
+    /// by the client from pointer updates instead (0 = never)
+    #[serde(default = "default_decouple_below_fps")]
+    pub decouple_below_fps: u32,
+
+    /// Cursor mode for specific applications while they have focus, e.g.
+    /// painted for games drawing a software cursor (Sway and Hyprland)
+    #[serde(default)]
+    pub app_overrides: Vec<CursorAppOverride>,
+
+    /// In hidden mode, keep finger taps from turning into tiny drags
+    #[serde(default = "default_true")]
+    pub direct_touch: bool,
+
+    /// In hidden mode, keep pen taps from turning into tiny drags
+    #[serde(default = "default_true")]
+    pub direct_pen: bool,
+
     /// Predictor configuration (for predictive mode)
     #[serde(default)]
     pub predictor: CursorPredictorConfig,
 }
 
+/// Cursor mode used while an application has focus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CursorAppOverride {
+    /// Wayland app ID or X11 class (case-insensitive); a trailing `*`
+    /// matches any suffix, e.g. "steam_app_*"
+    pub app_id: String,
+
+    /// Cursor mode: "metadata", "painted", "hidden", "predictive"
+    pub mode: String,
+}
+
 fn default_cursor_mode() -> String {
     "metadata".to_string()
 }
@@ -316,6 +602,10 @@ fn default_cursor_fps() -> u32 {
     60 // Hz - cursor updates faster than video for responsiveness
 }
 
+fn default_decouple_below_fps() -> u32 {
+    15
+}
+
 impl Default for CursorConfig {
     fn default() -> Self {
         Self {
@@ -323,6 +613,11 @@ impl Default for CursorConfig {
             auto_mode: true,
             predictive_latency_threshold_ms: 100,
             cursor_update_fps: 60,
+            color_pointers: false,
+            decouple_below_fps: 15,
+            app_overrides: Vec::new(),
+            direct_touch: true,
+            direct_pen: true,
             predictor: CursorPredictorConfig::default(),
         }
     }
@@ -365,6 +660,25 @@ pub struct CursorPredictorConfig {
     /// How quickly predicted position returns to actual when stopped
     #[serde(default = "default_convergence")]
     pub stop_convergence_rate: f32,
+
+    /// Motion model: "acceleration" (velocity and acceleration) or
+    /// "velocity" (overshoots less on jerky input such as touchpads)
+    #[serde(default = "default_prediction_model")]
+    pub model: String,
+
+    /// Share of the measured round trip to predict ahead
+    /// `lookahead_ms` applies until a round trip is measured
+    #[serde(default = "default_lookahead_rtt_fraction")]
+    pub lookahead_rtt_fraction: f32,
+
+    /// Longest lookahead (ms), however slow the link
+    #[serde(default = "default_max_lookahead_ms")]
+    pub max_lookahead_ms: f32,
+
+    /// Overshoot (pixels) at which the cursor snaps back to the actual
+    /// position (0 = never)
+    #[serde(default = "default_snap_back_distance")]
+    pub snap_back_distance: i32,
 }
 
 fn default_history_size() -> usize {
@@ -395,6 +709,22 @@ fn default_convergence() -> f32 {
     0.5
 }
 
+fn default_prediction_model() -> String {
+    "acceleration".to_string()
+}
+
+fn default_lookahead_rtt_fraction() -> f32 {
+    0.75
+}
+
+fn default_max_lookahead_ms() -> f32 {
+    150.0
+}
+
+fn default_snap_back_distance() -> i32 {
+    40
+}
+
 impl Default for CursorPredictorConfig {
     fn default() -> Self {
         Self {
@@ -405,6 +735,10 @@ impl Default for CursorPredictorConfig {
             max_prediction_distance: 100,
             min_velocity_threshold: 50.0,
             stop_convergence_rate: 0.5,
+            model: "acceleration".to_string(),
+            lookahead_rtt_fraction: 0.75,
+            max_lookahead_ms: 150.0,
+            snap_back_distance: 40,
         }
     }
 }
@@ -420,6 +754,14 @@ pub struct LoggingConfig {
 
     /// Enable metrics collection
     pub metrics: bool,
+
+    /// Also write each client session to `<log_dir>/session-<id>.log`
+    #[serde(default)]
+    pub per_session_files: bool,
+
+    /// Record channel PDUs to this file, for `lamco-rdp-server replay` (None = off)
+    #[serde(default)]
+    pub pdu_capture: Option<PathBuf>,
 }
 
 /// Video pipeline configuration
@@ -558,6 +900,29 @@ pub struct EgfxConfig {
     /// H.264 bitrate in kbps (main stream for AVC444)
     pub h264_bitrate: u32,
 
+    /// Follow the estimated link bandwidth with the H.264 bitrate
+    /// When true, the bitrate drops towards the bandwidth that gets through
+    /// while the link is congested, and recovers up to `h264_bitrate` once
+    /// it is clear. Each change restarts the encoder with an IDR frame.
+    /// Default: true
+    #[serde(default = "default_true")]
+    pub adaptive_bitrate: bool,
+
+    /// Send small updates without the H.264 encoder
+    /// When true, frames whose damage is a few regions of at most 64×64
+    /// (caret blinks, typed characters) go out as uncompressed pixels, which
+    /// is faster than encoding them and leaves a GPU encoder idle.
+    /// Default: true
+    #[serde(default = "default_true")]
+    pub small_update_fast_path: bool,
+
+    /// Step down to what the client can decode
+    /// When true, a client whose decode times or decode queue show it can't
+    /// keep up is moved from AVC444 to AVC420, then to 30 and 15 FPS.
+    /// Default: true
+    #[serde(default = "default_true")]
+    pub decode_downgrade: bool,
+
     /// ZGFX compression mode: "never", "auto", "always"
     pub zgfx_compression: String,
 
@@ -689,6 +1054,9 @@ impl Default for EgfxConfig {
             enabled: true,
             h264_level: "auto".to_string(),
             h264_bitrate: 5000,
+            adaptive_bitrate: true,
+            small_update_fast_path: true,
+            decode_downgrade: true,
             zgfx_compression: "never".to_string(),
             max_frames_in_flight: 3,
             frame_ack_timeout: 5000,
@@ -900,6 +1268,30 @@ pub struct DisplayConfig {
 
     /// Allow orientation changes
     pub allow_rotation: bool,
+
+    /// Server-side scaling policy when client and capture sizes differ:
+    /// "off", "fit" (letterbox), "fill" (crop), "pan" (1:1 with panning)
+    #[serde(default = "default_scaling_policy")]
+    pub scaling_policy: String,
+
+    /// Desktop size presented to the client when scaling ("WIDTHxHEIGHT")
+    ///
+    /// Required when `scaling_policy` is not "off".
+    #[serde(default)]
+    pub scaling_output_size: String,
+}
+
+fn default_scaling_policy() -> String {
+    "off".to_string()
+}
+
+impl DisplayConfig {
+    /// Parse `scaling_output_size` into a size
+    ///
+    /// Returns `Ok(None)` when unset.
+    pub fn scaling_output_dimensions(&self) -> Result<Option<(u32, u32)>, String> {
+        parse_resolution(&self.scaling_output_size, "scaling output size")
+    }
 }
 
 impl Default for DisplayConfig {
@@ -909,6 +1301,8 @@ impl Default for DisplayConfig {
             allowed_resolutions: vec![],
             dpi_aware: false,
             allow_rotation: false,
+            scaling_policy: default_scaling_policy(),
+            scaling_output_size: String::new(),
         }
     }
 }
@@ -939,3 +1333,660 @@ impl Default for AdvancedVideoConfig {
         }
     }
 }
+
+/// Session shadowing configuration
+///
+/// Observers connect on a dedicated listener and receive a view-only copy of
+/// the desktop: input is discarded and the clipboard is not shared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowConfig {
+    /// Enable the observer listener
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Address observers connect to (must differ from `server.listen_addr`)
+    #[serde(default = "default_shadow_listen_addr")]
+    pub listen_addr: String,
+
+    /// Ask the local user before an observer is attached
+    #[serde(default = "default_true")]
+    pub require_consent: bool,
+
+    /// Seconds to wait for consent before refusing the observer
+    #[serde(default = "default_shadow_consent_timeout")]
+    pub consent_timeout_secs: u64,
+
+    /// Show a notification on the host while observers are attached
+    #[serde(default = "default_true")]
+    pub show_indicator: bool,
+
+    /// Maximum concurrent observers
+    #[serde(default = "default_max_observers")]
+    pub max_observers: usize,
+}
+
+fn default_shadow_listen_addr() -> String {
+    "0.0.0.0:3390".to_string()
+}
+fn default_shadow_consent_timeout() -> u64 {
+    30
+}
+fn default_max_observers() -> usize {
+    2
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addr: default_shadow_listen_addr(),
+            require_consent: true,
+            consent_timeout_secs: default_shadow_consent_timeout(),
+            show_indicator: true,
+            max_observers: default_max_observers(),
+        }
+    }
+}
+
+/// Session broker configuration
+///
+/// When enabled, every incoming connection is routed through an external
+/// broker that can allow it, deny it, or redirect it to another host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokerConfig {
+    /// Consult the broker for every connection
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Broker transport: "http" or "dbus"
+    #[serde(default = "default_broker_kind")]
+    pub kind: String,
+
+    /// HTTP endpoint receiving the JSON decision request
+    #[serde(default)]
+    pub url: String,
+
+    /// D-Bus service name (session bus)
+    #[serde(default = "default_broker_dbus_destination")]
+    pub dbus_destination: String,
+
+    /// D-Bus object path
+    #[serde(default = "default_broker_dbus_path")]
+    pub dbus_path: String,
+
+    /// D-Bus interface providing `Decide`
+    #[serde(default = "default_broker_dbus_destination")]
+    pub dbus_interface: String,
+
+    /// Broker request timeout in milliseconds
+    #[serde(default = "default_broker_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Allow connections when the broker is unreachable
+    #[serde(default)]
+    pub fail_open: bool,
+
+    /// Host identifier reported to the broker (empty = hostname)
+    #[serde(default)]
+    pub host_id: String,
+
+    /// Bearer token sent to the HTTP broker (empty = none); may be a
+    /// `file:` or `credential:` reference (see [`crate::config::secrets`])
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_broker_kind() -> String {
+    "http".to_string()
+}
+fn default_broker_dbus_destination() -> String {
+    "ai.lamco.RdpBroker".to_string()
+}
+fn default_broker_dbus_path() -> String {
+    "/ai/lamco/RdpBroker".to_string()
+}
+fn default_broker_timeout_ms() -> u64 {
+    2000
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: default_broker_kind(),
+            url: String::new(),
+            dbus_destination: default_broker_dbus_destination(),
+            dbus_path: default_broker_dbus_path(),
+            dbus_interface: default_broker_dbus_destination(),
+            timeout_ms: default_broker_timeout_ms(),
+            fail_open: false,
+            host_id: String::new(),
+            token: String::new(),
+        }
+    }
+}
+
+/// HTTP admin API configuration
+///
+/// A token-authenticated REST interface for listing and disconnecting
+/// sessions, reading statistics and toggling runtime policies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminApiConfig {
+    /// Serve the admin API
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Listen address (loopback unless mutual TLS is configured)
+    #[serde(default = "default_admin_api_listen_addr")]
+    pub listen_addr: String,
+
+    /// Bearer token required on every request; may be a `file:` or
+    /// `credential:` reference (see [`crate::config::secrets`])
+    #[serde(default)]
+    pub token: String,
+
+    /// CA bundle for client certificates; enables mutual TLS using the
+    /// server certificate from `[security]`
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+}
+
+fn default_admin_api_listen_addr() -> String {
+    "127.0.0.1:3392".to_string()
+}
+
+impl Default for AdminApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addr: default_admin_api_listen_addr(),
+            token: String::new(),
+            client_ca_path: None,
+        }
+    }
+}
+
+/// Health endpoint configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Serve `GET /healthz`
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Listen address for the health endpoint
+    #[serde(default = "default_health_listen_addr")]
+    pub listen_addr: String,
+}
+
+fn default_health_listen_addr() -> String {
+    "127.0.0.1:3393".to_string()
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addr: default_health_listen_addr(),
+        }
+    }
+}
+
+/// Per-session resource limits
+///
+/// Usage is always accounted; limits are only enforced when enabled.
+/// A limit of 0 means unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLimitsConfig {
+    /// Enforce the limits below
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// CPU used by a session's pipeline, in percent of one core
+    #[serde(default)]
+    pub max_cpu_percent: u32,
+
+    /// Data sent to a client (kbit/s)
+    #[serde(default)]
+    pub max_bandwidth_kbps: u32,
+
+    /// Frame memory held by a session's pipeline (MB)
+    #[serde(default)]
+    pub max_memory_mb: u32,
+
+    /// What to do with a session over its CPU or bandwidth limit:
+    /// "throttle" (lower its frame rate) or "terminate"
+    #[serde(default = "default_limit_action")]
+    pub action: String,
+
+    /// How long a session may exceed a limit before action is taken (seconds)
+    #[serde(default = "default_limit_grace_secs")]
+    pub grace_secs: u64,
+}
+
+fn default_limit_action() -> String {
+    "throttle".to_string()
+}
+fn default_limit_grace_secs() -> u64 {
+    10
+}
+
+/// Hook commands run on session events
+///
+/// Each command runs through `/bin/sh -c` with the session details in
+/// `LAMCO_*` environment variables. Empty = no hook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run when a client is admitted
+    #[serde(default)]
+    pub on_connect: String,
+
+    /// Run when a session ends
+    #[serde(default)]
+    pub on_disconnect: String,
+
+    /// Run instead of `on_disconnect` when the client failed to log in
+    #[serde(default)]
+    pub on_auth_failure: String,
+
+    /// Kill hooks still running after this long (seconds)
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    10
+}
+
+/// Login banner shown before the desktop
+///
+/// The client sees the banner image instead of the desktop, and its input is
+/// withheld from the host, until the user acknowledges it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginBannerConfig {
+    /// Require acknowledgment of the banner before each session
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Banner image (PNG, JPEG or BMP), fitted to the client's desktop
+    #[serde(default)]
+    pub image_path: PathBuf,
+
+    /// Disconnect clients that have not acknowledged the banner after this
+    /// long (seconds, 0 = wait indefinitely)
+    #[serde(default = "default_banner_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_banner_timeout_secs() -> u64 {
+    120
+}
+
+impl Default for LoginBannerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image_path: PathBuf::new(),
+            timeout_secs: default_banner_timeout_secs(),
+        }
+    }
+}
+
+/// Connection quality overlay (FPS, bitrate and RTT badge)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityOverlayConfig {
+    /// Make the overlay available (Ctrl+Alt+O on the client or the admin API)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Show the overlay when a session starts
+    #[serde(default)]
+    pub show_on_connect: bool,
+}
+
+/// Recovery when the screen capture session is lost
+///
+/// The compositor ends the capture when the user stops sharing or the portal
+/// goes away. Connected clients are disconnected either way; with recovery
+/// enabled the server then re-creates the session (using the restore token,
+/// so without a permission dialog where the portal allows it).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRecoveryConfig {
+    /// Re-create the capture session after it is lost
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Consecutive failed re-creation attempts before giving up
+    #[serde(default = "default_recovery_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay before each re-creation attempt (seconds)
+    #[serde(default = "default_recovery_delay_secs")]
+    pub retry_delay_secs: u64,
+
+    /// Probe compositor capabilities again this often (seconds), besides
+    /// whenever the portal restarts; 0 = only on portal restarts
+    #[serde(default = "default_reprobe_interval_secs")]
+    pub reprobe_interval_secs: u64,
+}
+
+fn default_recovery_attempts() -> u32 {
+    3
+}
+
+fn default_recovery_delay_secs() -> u64 {
+    5
+}
+
+fn default_reprobe_interval_secs() -> u64 {
+    60
+}
+
+impl Default for CaptureRecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: default_recovery_attempts(),
+            retry_delay_secs: default_recovery_delay_secs(),
+            reprobe_interval_secs: default_reprobe_interval_secs(),
+        }
+    }
+}
+
+/// Watchdog for a capture that stops delivering frames
+///
+/// A compositor bug or a monitor going into DPMS standby can leave the
+/// capture session open but silent, freezing the client's screen. Some
+/// compositors also stop sending frames while the desktop is static, so the
+/// watchdog is off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureWatchdogConfig {
+    /// Watch connected sessions for frozen capture
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Time without frames before the capture counts as frozen (seconds)
+    #[serde(default = "default_watchdog_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Reaction to a frozen capture: "log", "indicator" (dim the client's
+    /// last frame) or "renegotiate" (re-create the PipeWire streams)
+    #[serde(default = "default_watchdog_action")]
+    pub action: String,
+}
+
+fn default_watchdog_timeout_secs() -> u64 {
+    10
+}
+
+fn default_watchdog_action() -> String {
+    "log".to_string()
+}
+
+impl Default for CaptureWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: default_watchdog_timeout_secs(),
+            action: default_watchdog_action(),
+        }
+    }
+}
+
+/// Frame dumps for visual debugging
+///
+/// On request (admin API or SIGUSR1) the display pipelines write their next
+/// frames to `dir`: the captured frame, its damage regions and the encoded
+/// bitstream. Meant for color and corruption reports; off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameDumpConfig {
+    /// Accept dump requests
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Directory dumps are written to (created when needed)
+    #[serde(default = "default_frame_dump_dir")]
+    pub dir: PathBuf,
+
+    /// Frames each pipeline writes per request
+    #[serde(default = "default_frame_dump_frames")]
+    pub frames: u32,
+
+    /// Requests closer together than this are refused (seconds)
+    #[serde(default = "default_frame_dump_interval_secs")]
+    pub min_interval_secs: u64,
+}
+
+fn default_frame_dump_dir() -> PathBuf {
+    PathBuf::from("/tmp/lamco-rdp-frames")
+}
+
+fn default_frame_dump_frames() -> u32 {
+    3
+}
+
+fn default_frame_dump_interval_secs() -> u64 {
+    30
+}
+
+impl Default for FrameDumpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_frame_dump_dir(),
+            frames: default_frame_dump_frames(),
+            min_interval_secs: default_frame_dump_interval_secs(),
+        }
+    }
+}
+
+/// Crash reports
+///
+/// A panic writes a JSON report (backtrace, sessions, encoder state, last
+/// log lines) to `dir` and optionally notifies a webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReportConfig {
+    /// Write crash reports
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Report directory (None = `$XDG_STATE_HOME/lamco-rdp-server/crashes`)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Log lines included in a report (at most 1000)
+    #[serde(default = "default_crash_log_lines")]
+    pub log_lines: usize,
+
+    /// Reports kept; older ones are removed
+    #[serde(default = "default_crash_max_reports")]
+    pub max_reports: usize,
+
+    /// URL a crash summary is POSTed to (empty = none)
+    #[serde(default)]
+    pub webhook_url: String,
+}
+
+fn default_crash_log_lines() -> usize {
+    200
+}
+
+fn default_crash_max_reports() -> usize {
+    20
+}
+
+impl Default for CrashReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+            log_lines: default_crash_log_lines(),
+            max_reports: default_crash_max_reports(),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+/// Lifecycle event stream
+///
+/// Newline-delimited JSON events (sessions, resolution, codec, clipboard,
+/// logins) on a Unix socket, for desktop indicators and automation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStreamConfig {
+    /// Publish events
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Socket path (None = `$XDG_RUNTIME_DIR/lamco-rdp-server/events.sock`)
+    #[serde(default)]
+    pub socket_path: Option<PathBuf>,
+}
+
+/// Heartbeat status file
+///
+/// A JSON file with session counts and health flags, replaced every
+/// `interval_secs`, for monitoring agents without HTTP access.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// Write the status file
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Status file (None = `$XDG_RUNTIME_DIR/lamco-rdp-server/status.json`)
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Seconds between updates
+    #[serde(default = "default_heartbeat_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_heartbeat_interval_secs() -> u64 {
+    5
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            interval_secs: default_heartbeat_interval_secs(),
+        }
+    }
+}
+
+/// GNOME Mutter direct capture
+///
+/// On GNOME the server records the screen through Mutter's private
+/// ScreenCast/RemoteDesktop D-Bus APIs (as gnome-remote-desktop does), which
+/// need no permission dialog and can create virtual monitors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutterConfig {
+    /// Use the Mutter APIs when available (false = always use the portal)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Monitor to record: "auto" (first connected display, or a virtual
+    /// monitor when there is none), "virtual", or a connector name such as
+    /// "DP-1"
+    #[serde(default = "default_mutter_monitor")]
+    pub monitor: String,
+}
+
+fn default_mutter_monitor() -> String {
+    "auto".to_string()
+}
+
+impl Default for MutterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            monitor: default_mutter_monitor(),
+        }
+    }
+}
+
+/// KDE KWin direct capture
+///
+/// On Plasma the server can record through KWin's `zkde_screencast_unstable_v1`
+/// protocol, which needs no permission dialog and can create a virtual
+/// output. KWin only offers the protocol to clients whose desktop file lists
+/// it in `X-KDE-Wayland-Interfaces`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KwinConfig {
+    /// Use the KWin screencast protocol when available (false = portal)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Stream a new virtual output of this size ("WIDTHxHEIGHT") instead of
+    /// the physical outputs (empty = physical outputs)
+    #[serde(default)]
+    pub virtual_output: String,
+}
+
+impl KwinConfig {
+    /// Parse `virtual_output` into a size
+    ///
+    /// Returns `Ok(None)` when unset (stream the physical outputs).
+    pub fn virtual_output_size(&self) -> Result<Option<(u32, u32)>, String> {
+        parse_resolution(&self.virtual_output, "KWin virtual output")
+    }
+}
+
+impl Default for KwinConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            virtual_output: String::new(),
+        }
+    }
+}
+
+/// wlroots native protocols
+///
+/// On Sway, Hyprland and other wlroots compositors the server injects input
+/// through the virtual keyboard and pointer protocols. With screencopy it
+/// also captures video itself, so no portal is involved at all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WlrConfig {
+    /// Capture video with wlr-screencopy (false = input only)
+    #[serde(default = "default_true")]
+    pub screencopy: bool,
+
+    /// On Hyprland, take the outputs to capture from its IPC socket
+    /// (skips mirrored and disabled monitors)
+    #[serde(default = "default_true")]
+    pub hyprland_ipc: bool,
+}
+
+impl Default for WlrConfig {
+    fn default() -> Self {
+        Self {
+            screencopy: true,
+            hyprland_ipc: true,
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            on_connect: String::new(),
+            on_disconnect: String::new(),
+            on_auth_failure: String::new(),
+            timeout_secs: default_hook_timeout_secs(),
+        }
+    }
+}
+
+impl Default for ResourceLimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_cpu_percent: 0,
+            max_bandwidth_kbps: 0,
+            max_memory_mb: 0,
+            action: default_limit_action(),
+            grace_secs: default_limit_grace_secs(),
+        }
+    }
+}
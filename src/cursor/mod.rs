@@ -20,9 +20,14 @@
 //!
 //! **Algorithm:**
 //! - Track cursor velocity and acceleration from recent samples
-//! - Predict position N milliseconds ahead (based on measured latency)
+//! - Predict position N milliseconds ahead (a share of the measured round
+//!   trip, up to a configured horizon)
 //! - Apply smoothing to prevent jitter
-//! - Quickly converge when cursor stops
+//! - Quickly converge when cursor stops, and snap back when a prediction
+//!   overshoots
+//!
+//! With `auto_mode`, each session turns prediction on once its round trip
+//! exceeds the threshold, and off again once it is well below it.
 //!
 //! # Architecture
 //!
@@ -41,8 +46,8 @@
 mod predictor;
 mod strategy;
 
-pub use predictor::{CursorPredictor, PredictorConfig};
-pub use strategy::{CursorMode, CursorStrategy, CursorStrategyConfig};
+pub use predictor::{CursorPredictor, PredictionModel, PredictorConfig};
+pub use strategy::{CursorMode, CursorShape, CursorStrategy, CursorStrategyConfig};
 
 /// Default lookahead for predictive cursor (ms)
 pub const DEFAULT_LOOKAHEAD_MS: f32 = 50.0;
@@ -20,12 +20,52 @@
 //! ```text
 //! velocity_smooth = α * velocity_new + (1 - α) * velocity_old
 //! ```
+//!
+//! # Models
+//!
+//! The `acceleration` model uses the full formula above; the `velocity`
+//! model drops the acceleration term, which overshoots less on jerky input
+//! such as touchpads.
+//!
+//! # Snap-Back Correction
+//!
+//! Each prediction is kept until the time it predicted. When the actual
+//! position falls short of it by more than `snap_back_distance` pixels (the
+//! pointer stopped or turned), the prediction overshot: the painted cursor
+//! snaps back to the actual position and velocity is measured anew. A
+//! pointer outrunning the prediction needs no correction.
 
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::trace;
 
+/// Predictions awaiting comparison with the actual position
+const PENDING_PREDICTIONS_MAX: usize = 32;
+
+/// Motion model used for prediction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PredictionModel {
+    /// Constant velocity
+    Velocity,
+    /// Velocity and acceleration
+    #[default]
+    Acceleration,
+}
+
+impl std::str::FromStr for PredictionModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "velocity" | "linear" => Ok(Self::Velocity),
+            "acceleration" | "physics" => Ok(Self::Acceleration),
+            _ => Err(format!("Unknown prediction model: {}", s)),
+        }
+    }
+}
+
 /// Configuration for cursor predictor
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PredictorConfig {
@@ -56,6 +96,22 @@ pub struct PredictorConfig {
     /// Convergence rate when cursor stops (0.0-1.0)
     #[serde(default = "default_convergence")]
     pub stop_convergence_rate: f32,
+
+    /// Motion model
+    #[serde(default)]
+    pub model: PredictionModel,
+
+    /// Share of the measured round trip to predict ahead
+    #[serde(default = "default_lookahead_rtt_fraction")]
+    pub lookahead_rtt_fraction: f32,
+
+    /// Longest lookahead (ms), however slow the link
+    #[serde(default = "default_max_lookahead_ms")]
+    pub max_lookahead_ms: f32,
+
+    /// Prediction error (pixels) at which the cursor snaps back (0 = never)
+    #[serde(default = "default_snap_back_distance")]
+    pub snap_back_distance: i32,
 }
 
 fn default_history_size() -> usize {
@@ -79,6 +135,15 @@ fn default_min_velocity() -> f32 {
 fn default_convergence() -> f32 {
     0.5
 }
+fn default_lookahead_rtt_fraction() -> f32 {
+    0.75
+}
+fn default_max_lookahead_ms() -> f32 {
+    150.0
+}
+fn default_snap_back_distance() -> i32 {
+    40
+}
 
 impl Default for PredictorConfig {
     fn default() -> Self {
@@ -90,6 +155,10 @@ impl Default for PredictorConfig {
             max_prediction_distance: default_max_prediction(),
             min_velocity_threshold: default_min_velocity(),
             stop_convergence_rate: default_convergence(),
+            model: PredictionModel::default(),
+            lookahead_rtt_fraction: default_lookahead_rtt_fraction(),
+            max_lookahead_ms: default_max_lookahead_ms(),
+            snap_back_distance: default_snap_back_distance(),
         }
     }
 }
@@ -127,6 +196,13 @@ pub struct CursorPredictor {
 
     /// Frames since last movement
     frames_since_move: u32,
+
+    /// Predictions made (time they are for, position then, prediction),
+    /// oldest first
+    pending: VecDeque<(Instant, (i32, i32), (i32, i32))>,
+
+    /// Predictions discarded for diverging from the actual position
+    corrections: u64,
 }
 
 impl CursorPredictor {
@@ -141,12 +217,20 @@ impl CursorPredictor {
             acceleration: (0.0, 0.0),
             is_moving: false,
             frames_since_move: 0,
+            pending: VecDeque::new(),
+            corrections: 0,
         }
     }
 
     /// Update with new cursor position
     pub fn update(&mut self, x: i32, y: i32) {
-        let now = Instant::now();
+        self.update_at(x, y, Instant::now());
+    }
+
+    /// Update with the cursor position at `now`
+    pub fn update_at(&mut self, x: i32, y: i32, now: Instant) {
+        self.check_prediction(x, y, now);
+
         let moved = x != self.position.0 || y != self.position.1;
 
         // Track movement state
@@ -211,11 +295,12 @@ impl CursorPredictor {
         // Physics-based prediction: pos + vel*t + 0.5*acc*t²
         let dt = lookahead_ms / 1000.0;
 
-        let pred_x =
-            self.position.0 as f32 + self.velocity.0 * dt + 0.5 * self.acceleration.0 * dt * dt;
-
-        let pred_y =
-            self.position.1 as f32 + self.velocity.1 * dt + 0.5 * self.acceleration.1 * dt * dt;
+        let (ax, ay) = match self.config.model {
+            PredictionModel::Velocity => (0.0, 0.0),
+            PredictionModel::Acceleration => self.acceleration,
+        };
+        let pred_x = self.position.0 as f32 + self.velocity.0 * dt + 0.5 * ax * dt * dt;
+        let pred_y = self.position.1 as f32 + self.velocity.1 * dt + 0.5 * ay * dt * dt;
 
         // Clamp to maximum prediction distance
         let dx = pred_x - self.position.0 as f32;
@@ -235,10 +320,28 @@ impl CursorPredictor {
 
     /// Get predicted position using configured lookahead
     pub fn get_predicted_position(&mut self) -> (i32, i32) {
+        self.predicted_position_at(Instant::now())
+    }
+
+    /// Predicted position, made at `now`, for `now` plus the lookahead
+    pub fn predicted_position_at(&mut self, now: Instant) -> (i32, i32) {
         self.predicted_position = self.predict(self.config.lookahead_ms);
+        if self.is_moving {
+            let due = now + Duration::from_secs_f32(self.config.lookahead_ms.max(0.0) / 1000.0);
+            if self.pending.len() == PENDING_PREDICTIONS_MAX {
+                self.pending.pop_front();
+            }
+            self.pending
+                .push_back((due, self.position, self.predicted_position));
+        }
         self.predicted_position
     }
 
+    /// Predictions discarded by snap-back correction
+    pub fn corrections(&self) -> u64 {
+        self.corrections
+    }
+
     /// Get current actual position
     pub fn actual_position(&self) -> (i32, i32) {
         self.position
@@ -272,12 +375,52 @@ impl CursorPredictor {
     /// Reset predictor state
     pub fn reset(&mut self) {
         self.history.clear();
+        self.pending.clear();
         self.velocity = (0.0, 0.0);
         self.acceleration = (0.0, 0.0);
         self.is_moving = false;
         self.frames_since_move = 0;
     }
 
+    /// Compare the latest prediction due by `now` with the actual position,
+    /// snapping back when it overshot
+    fn check_prediction(&mut self, x: i32, y: i32, now: Instant) {
+        let mut due = None;
+        while self.pending.front().is_some_and(|&(at, _, _)| at <= now) {
+            due = self
+                .pending
+                .pop_front()
+                .map(|(_, origin, predicted)| (origin, predicted));
+        }
+        let Some(((ox, oy), (px, py))) = due else {
+            return;
+        };
+        let limit = self.config.snap_back_distance;
+        let (dx, dy) = ((px - x) as f32, (py - y) as f32);
+        if limit <= 0 || (dx * dx + dy * dy).sqrt() <= limit as f32 {
+            return;
+        }
+        // Short of the prediction, along the direction it went
+        let overshot = (px - ox) as f32 * dx + (py - oy) as f32 * dy > 0.0;
+        if !overshot {
+            return;
+        }
+
+        trace!(
+            "Cursor prediction off by ({:.0}, {:.0}): snapping back to ({}, {})",
+            dx,
+            dy,
+            x,
+            y
+        );
+        self.corrections += 1;
+        self.predicted_position = (x, y);
+        self.history.clear();
+        self.pending.clear();
+        self.velocity = (0.0, 0.0);
+        self.acceleration = (0.0, 0.0);
+    }
+
     fn update_velocity(&mut self) {
         if self.history.len() < 2 {
             return;
@@ -431,6 +574,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_snap_back_on_divergence() {
+        let start = Instant::now();
+        let ms = |n: u64| start + Duration::from_millis(n);
+        let mut predictor = CursorPredictor::new(PredictorConfig {
+            model: PredictionModel::Velocity,
+            ..PredictorConfig::default()
+        });
+
+        // Moving right at 1000 px/s; predictions 50ms ahead run along
+        for i in 0..6 {
+            predictor.update_at(i * 16, 0, ms(i as u64 * 16));
+            predictor.predicted_position_at(ms(i as u64 * 16));
+        }
+        assert!(predictor.predict(50.0).0 > 80);
+        assert_eq!(predictor.corrections(), 0);
+
+        // The pointer stops dead: the predictions overshoot and are dropped
+        predictor.update_at(80, 0, ms(140));
+        assert_eq!(predictor.corrections(), 1);
+        assert_eq!(predictor.velocity(), (0.0, 0.0));
+        assert_eq!(predictor.predicted_position_at(ms(140)), (80, 0));
+    }
+
+    #[test]
+    fn test_prediction_model_from_str() {
+        assert_eq!(
+            "velocity".parse::<PredictionModel>().unwrap(),
+            PredictionModel::Velocity
+        );
+        assert!("spline".parse::<PredictionModel>().is_err());
+    }
+
     #[test]
     fn test_velocity_calculation() {
         let config = PredictorConfig::default();
@@ -4,10 +4,17 @@
 //! each optimized for different scenarios.
 
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, info};
 
 use super::predictor::{CursorPredictor, PredictorConfig};
 
+/// Shortest lookahead (ms) once a round trip is measured
+const MIN_LOOKAHEAD_MS: f32 = 20.0;
+
+/// Share of the threshold the round trip has to drop below before an
+/// automatically enabled prediction is turned off again
+const PREDICTIVE_EXIT_RATIO: f32 = 0.8;
+
 /// Cursor rendering mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -117,6 +124,31 @@ impl Default for CursorStrategyConfig {
     }
 }
 
+impl From<&crate::config::CursorConfig> for CursorStrategyConfig {
+    fn from(config: &crate::config::CursorConfig) -> Self {
+        let predictor = &config.predictor;
+        Self {
+            mode: config.mode.parse().unwrap_or_default(),
+            auto_mode: config.auto_mode,
+            predictive_latency_threshold_ms: config.predictive_latency_threshold_ms,
+            predictor: PredictorConfig {
+                history_size: predictor.history_size,
+                lookahead_ms: predictor.lookahead_ms,
+                velocity_smoothing: predictor.velocity_smoothing,
+                acceleration_smoothing: predictor.acceleration_smoothing,
+                max_prediction_distance: predictor.max_prediction_distance,
+                min_velocity_threshold: predictor.min_velocity_threshold,
+                stop_convergence_rate: predictor.stop_convergence_rate,
+                model: predictor.model.parse().unwrap_or_default(),
+                lookahead_rtt_fraction: predictor.lookahead_rtt_fraction,
+                max_lookahead_ms: predictor.max_lookahead_ms,
+                snap_back_distance: predictor.snap_back_distance,
+            },
+            cursor_update_fps: config.cursor_update_fps,
+        }
+    }
+}
+
 /// Cursor strategy manager
 ///
 /// Manages cursor rendering mode and handles automatic
@@ -128,6 +160,10 @@ pub struct CursorStrategy {
     /// Current active mode
     active_mode: CursorMode,
 
+    /// Mode forced by the focused application, over configured and
+    /// automatic selection
+    override_mode: Option<CursorMode>,
+
     /// Cursor predictor (for predictive mode)
     predictor: Option<CursorPredictor>,
 
@@ -142,7 +178,7 @@ pub struct CursorStrategy {
 }
 
 /// Cursor shape information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CursorShape {
     /// Width in pixels
     pub width: u32,
@@ -167,6 +203,7 @@ impl CursorStrategy {
 
         Self {
             active_mode: config.mode,
+            override_mode: None,
             predictor,
             measured_latency_ms: 0,
             current_position: (0, 0),
@@ -194,15 +231,63 @@ impl CursorStrategy {
         self.measured_latency_ms = latency_ms;
 
         // Auto-switch mode if enabled
-        if self.config.auto_mode {
+        if self.config.auto_mode && self.override_mode.is_none() {
             self.auto_select_mode();
         }
 
-        // Update predictor lookahead based on latency
+        // Update predictor lookahead based on latency; the configured
+        // lookahead stands until a round trip is measured
+        if let Some(ref mut predictor) = self.predictor {
+            if latency_ms > 0 {
+                let config = &self.config.predictor;
+                let lookahead = (latency_ms as f32 * config.lookahead_rtt_fraction)
+                    .min(config.max_lookahead_ms)
+                    .max(MIN_LOOKAHEAD_MS);
+                predictor.set_lookahead(lookahead);
+            }
+        }
+    }
+
+    /// Force `mode` while an application needing it has focus (None
+    /// returns to configured and automatic selection)
+    pub fn set_override(&mut self, mode: Option<CursorMode>) {
+        if mode == self.override_mode {
+            return;
+        }
+        self.override_mode = mode;
+        match mode {
+            Some(mode) => {
+                if mode != self.active_mode {
+                    info!(
+                        "🖱️ Cursor mode {:?} -> {:?} for the focused application",
+                        self.active_mode, mode
+                    );
+                }
+                self.set_mode(mode);
+            }
+            None => {
+                if self.config.mode != self.active_mode {
+                    info!(
+                        "🖱️ Cursor mode {:?} -> {:?} (application override ended)",
+                        self.active_mode, self.config.mode
+                    );
+                }
+                self.set_mode(self.config.mode);
+                if self.config.auto_mode {
+                    self.auto_select_mode();
+                }
+            }
+        }
+    }
+
+    /// Start over for a new session: configured (or overridden) mode, no
+    /// latency measured
+    pub fn reset(&mut self) {
+        self.measured_latency_ms = 0;
+        self.set_mode(self.override_mode.unwrap_or(self.config.mode));
         if let Some(ref mut predictor) = self.predictor {
-            // Use 50-100% of measured latency as lookahead
-            let lookahead = (latency_ms as f32 * 0.75).clamp(20.0, 150.0);
-            predictor.set_lookahead(lookahead);
+            predictor.reset();
+            predictor.set_lookahead(self.config.predictor.lookahead_ms);
         }
     }
 
@@ -274,7 +359,15 @@ impl CursorStrategy {
     }
 
     fn auto_select_mode(&mut self) {
-        let should_predict = self.measured_latency_ms > self.config.predictive_latency_threshold_ms;
+        let threshold = self.config.predictive_latency_threshold_ms as f32;
+        let latency = self.measured_latency_ms as f32;
+        // Hysteresis keeps a round trip hovering at the threshold from
+        // switching modes back and forth
+        let should_predict = if self.active_mode == CursorMode::Predictive {
+            latency >= threshold * PREDICTIVE_EXIT_RATIO
+        } else {
+            latency > threshold
+        };
 
         let new_mode = if should_predict {
             CursorMode::Predictive
@@ -283,8 +376,8 @@ impl CursorStrategy {
         };
 
         if new_mode != self.active_mode {
-            debug!(
-                "Auto-switching cursor mode: {:?} -> {:?} (latency={}ms)",
+            info!(
+                "🖱️ Auto-switching cursor mode: {:?} -> {:?} (latency={}ms)",
                 self.active_mode, new_mode, self.measured_latency_ms
             );
             self.set_mode(new_mode);
@@ -333,9 +426,55 @@ mod tests {
         strategy.update_latency(150);
         assert_eq!(strategy.mode(), CursorMode::Predictive);
 
+        // Just under the threshold - stays predictive
+        strategy.update_latency(90);
+        assert_eq!(strategy.mode(), CursorMode::Predictive);
+
         // Low latency again - should switch back
         strategy.update_latency(50);
         assert_eq!(strategy.mode(), CursorMode::Metadata);
+
+        // A new session starts from the configured mode
+        strategy.update_latency(150);
+        strategy.reset();
+        assert_eq!(strategy.mode(), CursorMode::Metadata);
+        assert_eq!(strategy.latency(), 0);
+    }
+
+    #[test]
+    fn test_application_override() {
+        let mut strategy = CursorStrategy::new(CursorStrategyConfig::default());
+
+        strategy.set_override(Some(CursorMode::Painted));
+        assert_eq!(strategy.mode(), CursorMode::Painted);
+        assert!(strategy.needs_compositing());
+
+        // Latency does not switch an overridden mode
+        strategy.update_latency(150);
+        assert_eq!(strategy.mode(), CursorMode::Painted);
+
+        // Back to automatic selection once the application loses focus
+        strategy.set_override(None);
+        assert_eq!(strategy.mode(), CursorMode::Predictive);
+    }
+
+    #[test]
+    fn test_lookahead_follows_rtt() {
+        let mut config = CursorStrategyConfig::default();
+        config.mode = CursorMode::Predictive;
+        config.auto_mode = false;
+        config.predictor.lookahead_ms = 40.0;
+        config.predictor.max_lookahead_ms = 100.0;
+
+        let mut strategy = CursorStrategy::new(config);
+        strategy.update_latency(0);
+        assert_eq!(strategy.predictor().unwrap().lookahead(), 40.0);
+        strategy.update_latency(80);
+        assert_eq!(strategy.predictor().unwrap().lookahead(), 60.0);
+        strategy.update_latency(400);
+        assert_eq!(strategy.predictor().unwrap().lookahead(), 100.0);
+        strategy.update_latency(10);
+        assert_eq!(strategy.predictor().unwrap().lookahead(), MIN_LOOKAHEAD_MS);
     }
 
     #[test]
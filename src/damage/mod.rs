@@ -45,6 +45,8 @@
 
 use std::time::Instant;
 
+use crate::performance::BufferPool;
+
 // =============================================================================
 // Types
 // =============================================================================
@@ -585,7 +587,19 @@ impl DamageDetector {
         // Handle first frame, invalidation, or dimension change
         if self.previous_frame.is_none() || self.invalidated || dimensions_changed {
             self.update_tile_grid(width, height);
-            self.previous_frame = Some(frame.to_vec());
+            let pool = BufferPool::global();
+            let mut previous = match self.previous_frame.take() {
+                Some(previous) if previous.capacity() >= frame.len() => previous,
+                stale => {
+                    if let Some(stale) = stale {
+                        pool.recycle(stale);
+                    }
+                    pool.take(frame.len())
+                }
+            };
+            previous.clear();
+            previous.extend_from_slice(frame);
+            self.previous_frame = Some(previous);
             self.previous_dimensions = Some((width, height));
             self.invalidated = false;
 
@@ -780,6 +794,14 @@ impl DamageDetector {
     }
 }
 
+impl Drop for DamageDetector {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous_frame.take() {
+            BufferPool::global().recycle(previous);
+        }
+    }
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -0,0 +1,212 @@
+//! Frame Acknowledgement Latency
+//!
+//! The client acknowledges every EGFX frame after decoding it
+//! (RDPGFX_FRAME_ACKNOWLEDGE_PDU, MS-RDPEGFX 2.2.2.13). The time from
+//! sending a frame to its acknowledgement covers the network, the client's
+//! queue and its decoder, so a growing latency means the client can't keep
+//! up with the frame rate we send at. The bytes of acknowledged frames
+//! show how fast data actually reaches the client.
+//!
+//! Acknowledgements also carry the client's queue depth, and clients that
+//! send QoE reports (RDPGFX_QOE_FRAME_ACKNOWLEDGE_PDU, 2.2.2.21) say how
+//! long each frame took to decode and render. These separate a slow decoder
+//! from a slow network.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Frames remembered while waiting for their acknowledgement
+const MAX_PENDING: usize = 64;
+
+/// Weight of a new sample in the smoothed latency
+const SMOOTHING: f64 = 0.2;
+
+/// Smoothed send-to-acknowledgement latency of one EGFX channel
+///
+/// The frame sender records sent frames, the graphics handler their
+/// acknowledgements. Cloned handles share state.
+#[derive(Debug, Clone, Default)]
+pub struct FrameAckLatency {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Sent frames and their size, not acknowledged yet, oldest first
+    pending: VecDeque<(u32, Instant, usize)>,
+    smoothed_us: Option<f64>,
+    /// Total bytes of acknowledged frames
+    acked_bytes: u64,
+    /// Smoothed decode and render time from QoE reports
+    decode_us: Option<f64>,
+    /// Frames queued for decoding at the last acknowledgement
+    queue_depth: Option<u32>,
+}
+
+impl FrameAckLatency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note a frame of `bytes` encoded bytes sent to the client
+    pub fn frame_sent(&self, frame_id: u32, bytes: usize) {
+        let mut inner = self.lock();
+        if inner.pending.len() == MAX_PENDING {
+            inner.pending.pop_front();
+        }
+        inner.pending.push_back((frame_id, Instant::now(), bytes));
+    }
+
+    /// Note the client's acknowledgement of a frame
+    ///
+    /// Frames sent before it that were never acknowledged are forgotten,
+    /// though their bytes count as delivered.
+    pub fn frame_acked(&self, frame_id: u32) {
+        let mut inner = self.lock();
+        let Some(position) = inner.pending.iter().position(|(id, _, _)| *id == frame_id) else {
+            return;
+        };
+        let sent = inner.pending[position].1;
+        let bytes: usize = inner
+            .pending
+            .drain(..=position)
+            .map(|(_, _, bytes)| bytes)
+            .sum();
+        inner.acked_bytes += bytes as u64;
+
+        let sample = sent.elapsed().as_micros() as f64;
+        inner.smoothed_us = Some(match inner.smoothed_us {
+            Some(smoothed) => smoothed + SMOOTHING * (sample - smoothed),
+            None => sample,
+        });
+    }
+
+    /// Note the client's decode queue depth reported with an acknowledgement
+    ///
+    /// 0 means unavailable and 0xFFFFFFFF suspends acknowledgements; neither
+    /// is a depth.
+    pub fn record_queue_depth(&self, depth: u32) {
+        if depth != 0 && depth != u32::MAX {
+            self.lock().queue_depth = Some(depth);
+        }
+    }
+
+    /// Note a frame's decode and render time from a QoE report
+    pub fn record_decode_time(&self, decode: Duration) {
+        let mut inner = self.lock();
+        let sample = decode.as_micros() as f64;
+        inner.decode_us = Some(match inner.decode_us {
+            Some(smoothed) => smoothed + SMOOTHING * (sample - smoothed),
+            None => sample,
+        });
+    }
+
+    /// Smoothed decode and render time, None unless the client sends QoE reports
+    pub fn decode_time(&self) -> Option<Duration> {
+        self.lock()
+            .decode_us
+            .map(|us| Duration::from_micros(us as u64))
+    }
+
+    /// Decode queue depth at the last acknowledgement that reported one
+    pub fn queue_depth(&self) -> Option<u32> {
+        self.lock().queue_depth
+    }
+
+    /// Smoothed latency, None until the first acknowledgement
+    pub fn latency(&self) -> Option<Duration> {
+        self.lock()
+            .smoothed_us
+            .map(|us| Duration::from_micros(us as u64))
+    }
+
+    /// Total bytes of acknowledged frames since the last reset
+    pub fn acked_bytes(&self) -> u64 {
+        self.lock().acked_bytes
+    }
+
+    /// Frames sent within `timeout` and not acknowledged yet
+    ///
+    /// 0 until the client acknowledged a frame, so clients that suspend
+    /// acknowledgements aren't considered backed up.
+    pub fn in_flight(&self, timeout: Duration) -> usize {
+        let inner = self.lock();
+        if inner.smoothed_us.is_none() {
+            return 0;
+        }
+        inner
+            .pending
+            .iter()
+            .filter(|(_, sent, _)| sent.elapsed() < timeout)
+            .count()
+    }
+
+    /// Forget all frames and measurements, e.g. when the channel closes
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.pending.clear();
+        inner.smoothed_us = None;
+        inner.acked_bytes = 0;
+        inner.decode_us = None;
+        inner.queue_depth = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ack_latency() {
+        let latency = FrameAckLatency::new();
+        assert_eq!(latency.latency(), None);
+
+        // Unknown frames are ignored
+        latency.frame_acked(7);
+        assert_eq!(latency.latency(), None);
+
+        latency.frame_sent(1, 1000);
+        latency.frame_sent(2, 500);
+        latency.frame_sent(3, 200);
+        assert_eq!(latency.in_flight(Duration::from_secs(5)), 0);
+        std::thread::sleep(Duration::from_millis(5));
+
+        // Acknowledging frame 2 drops frame 1 as well
+        latency.clone().frame_acked(2);
+        assert!(latency.latency().unwrap() >= Duration::from_millis(5));
+        assert_eq!(latency.in_flight(Duration::from_secs(5)), 1);
+        assert_eq!(latency.in_flight(Duration::ZERO), 0);
+        assert_eq!(latency.acked_bytes(), 1500);
+
+        latency.reset();
+        assert_eq!(latency.latency(), None);
+        assert_eq!(latency.acked_bytes(), 0);
+        latency.frame_acked(3);
+        assert_eq!(latency.latency(), None);
+    }
+
+    #[test]
+    fn test_decode_reports() {
+        let latency = FrameAckLatency::new();
+        assert_eq!(latency.decode_time(), None);
+
+        latency.record_queue_depth(0);
+        latency.record_queue_depth(u32::MAX);
+        assert_eq!(latency.queue_depth(), None);
+        latency.record_queue_depth(3);
+        assert_eq!(latency.queue_depth(), Some(3));
+
+        latency.record_decode_time(Duration::from_millis(10));
+        latency.record_decode_time(Duration::from_millis(20));
+        assert_eq!(latency.decode_time(), Some(Duration::from_millis(12)));
+
+        latency.reset();
+        assert_eq!(latency.decode_time(), None);
+        assert_eq!(latency.queue_depth(), None);
+    }
+}
@@ -569,6 +569,7 @@ impl Avc444Encoder {
 
         // Step 2: YUV444 → Dual YUV420
         let (main_yuv420, aux_yuv420) = pack_dual_views(&yuv444);
+        yuv444.recycle();
         let pack_time = start.elapsed() - convert_time;
 
         // Step 3: Encode both views using direct YUV input
@@ -708,6 +709,8 @@ impl Avc444Encoder {
         };
 
         let encode_time = start.elapsed() - convert_time - pack_time;
+        main_yuv420.recycle();
+        aux_yuv420.recycle();
 
         // Convert main bitstream (always present)
         // Aux might be None (omitted for bandwidth optimization)
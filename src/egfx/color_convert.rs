@@ -18,6 +18,8 @@
 //!
 //! Color conversion follows the formulas in MS-RDPEGFX Section 3.3.8.3.
 
+use crate::performance::BufferPool;
+
 /// Color matrix standard for RGB to YUV conversion
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorMatrix {
@@ -127,18 +129,27 @@ pub struct Yuv444Frame {
 }
 
 impl Yuv444Frame {
-    /// Create a new YUV444 frame with allocated buffers
+    /// Create a new YUV444 frame with buffers from the frame buffer pool
     pub fn new(width: usize, height: usize) -> Self {
         let size = width * height;
+        let pool = BufferPool::global();
         Self {
-            y: vec![0u8; size],
-            u: vec![128u8; size], // Neutral chroma
-            v: vec![128u8; size], // Neutral chroma
+            y: pool.take_filled(size, 0),
+            u: pool.take_filled(size, 128), // Neutral chroma
+            v: pool.take_filled(size, 128), // Neutral chroma
             width,
             height,
         }
     }
 
+    /// Return the planes to the frame buffer pool
+    pub fn recycle(self) {
+        let pool = BufferPool::global();
+        pool.recycle(self.y);
+        pool.recycle(self.u);
+        pool.recycle(self.v);
+    }
+
     /// Get the pixel count
     #[inline]
     pub fn pixel_count(&self) -> usize {
@@ -657,7 +668,6 @@ pub fn subsample_chroma_420(chroma_444: &[u8], width: usize, height: usize) -> V
 
     let out_width = width / 2;
     let out_height = height / 2;
-    let mut chroma_420 = Vec::with_capacity(out_width * out_height);
 
     // Dispatch to SIMD if available
     #[cfg(target_arch = "x86_64")]
@@ -679,6 +689,7 @@ pub fn subsample_chroma_420(chroma_444: &[u8], width: usize, height: usize) -> V
     }
 
     // Scalar implementation with proper 2x2 box filter
+    let mut chroma_420 = BufferPool::global().take(out_width * out_height);
     for y in (0..height).step_by(2) {
         for x in (0..width).step_by(2) {
             // 2x2 block indices
@@ -711,7 +722,7 @@ unsafe fn subsample_chroma_420_avx2(chroma_444: &[u8], width: usize, height: usi
 
     let out_width = width / 2;
     let out_height = height / 2;
-    let mut chroma_420 = vec![0u8; out_width * out_height];
+    let mut chroma_420 = BufferPool::global().take_filled(out_width * out_height, 0);
 
     // Process 16 output pixels at a time (32 input pixels per row)
     let simd_width = out_width / 16;
@@ -802,7 +813,7 @@ unsafe fn subsample_chroma_420_neon(chroma_444: &[u8], width: usize, height: usi
 
     let out_width = width / 2;
     let out_height = height / 2;
-    let mut chroma_420 = vec![0u8; out_width * out_height];
+    let mut chroma_420 = BufferPool::global().take_filled(out_width * out_height, 0);
 
     // Process 8 output pixels at a time (16 input pixels per row)
     let simd_width = out_width / 8;
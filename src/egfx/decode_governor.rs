@@ -0,0 +1,193 @@
+//! Client Decode Governor
+//!
+//! A phone or thin client may negotiate AVC444 and accept 4K60, then spend
+//! longer decoding each frame than the frame interval allows. Frames pile up
+//! in its decode queue and the session turns sluggish however good the link
+//! is.
+//!
+//! The governor watches the decode times from the client's QoE reports and
+//! the queue depth from its frame acknowledgements. When either stays over
+//! budget for [`OVERLOAD_HOLD`], it steps the session's operating point down:
+//! first AVC444 to AVC420, then the frame rate from 60 to 30 to 15 FPS. A
+//! client's decoder does not get faster during a session, so steps are never
+//! undone; the next session starts from the top again.
+//!
+//! The resolution is not lowered: the EGFX surface has to match the client's
+//! desktop size.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Share of the frame interval the client may spend decoding a frame
+const DECODE_BUDGET: f64 = 0.8;
+
+/// Decode queue depth at which the client is considered behind
+const QUEUE_DEPTH_LIMIT: u32 = 3;
+
+/// How long the client has to be over budget before stepping down
+pub const OVERLOAD_HOLD: Duration = Duration::from_secs(3);
+
+/// Time for decode reports to reflect a step before judging again
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+
+/// Frame rates stepped down to, highest first
+const FPS_STEPS: [u32; 2] = [30, 15];
+
+/// Codec and frame rate a session runs at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatingPoint {
+    /// Dual-stream 4:4:4 chroma rather than AVC420
+    pub avc444: bool,
+    /// Highest frame rate sent
+    pub max_fps: u32,
+}
+
+impl OperatingPoint {
+    /// Next cheaper point to decode, None at the bottom
+    fn step_down(&self) -> Option<Self> {
+        if self.avc444 {
+            return Some(Self {
+                avc444: false,
+                ..*self
+            });
+        }
+        FPS_STEPS
+            .iter()
+            .find(|&&fps| fps < self.max_fps)
+            .map(|&max_fps| Self { max_fps, ..*self })
+    }
+}
+
+impl fmt::Display for OperatingPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let codec = if self.avc444 { "AVC444" } else { "AVC420" };
+        write!(f, "{} at up to {} FPS", codec, self.max_fps)
+    }
+}
+
+/// Steps a session down to what its client can decode
+#[derive(Debug)]
+pub struct DecodeGovernor {
+    point: OperatingPoint,
+    /// Start of the current spell over budget
+    overloaded_since: Option<Instant>,
+    /// Last step down
+    stepped_at: Option<Instant>,
+}
+
+impl DecodeGovernor {
+    /// Start at the negotiated operating point
+    pub fn new(point: OperatingPoint) -> Self {
+        Self {
+            point,
+            overloaded_since: None,
+            stepped_at: None,
+        }
+    }
+
+    /// Current operating point
+    pub fn point(&self) -> OperatingPoint {
+        self.point
+    }
+
+    /// Judge the client's latest decode time and queue depth
+    ///
+    /// Returns the new operating point when the session has to step down.
+    pub fn evaluate(
+        &mut self,
+        decode_time: Option<Duration>,
+        queue_depth: Option<u32>,
+        now: Instant,
+    ) -> Option<OperatingPoint> {
+        if self
+            .stepped_at
+            .is_some_and(|stepped| now.duration_since(stepped) < SETTLE_TIME)
+        {
+            return None;
+        }
+
+        let budget = Duration::from_secs_f64(DECODE_BUDGET / f64::from(self.point.max_fps.max(1)));
+        let overloaded = decode_time.is_some_and(|decode| decode > budget)
+            || queue_depth.is_some_and(|depth| depth >= QUEUE_DEPTH_LIMIT);
+        if !overloaded {
+            self.overloaded_since = None;
+            return None;
+        }
+
+        let since = *self.overloaded_since.get_or_insert(now);
+        if now.duration_since(since) < OVERLOAD_HOLD {
+            return None;
+        }
+
+        let next = self.point.step_down()?;
+        self.point = next;
+        self.overloaded_since = None;
+        self.stepped_at = Some(now);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_down_when_decoding_falls_behind() {
+        let start = Instant::now();
+        let mut governor = DecodeGovernor::new(OperatingPoint {
+            avc444: true,
+            max_fps: 60,
+        });
+        let slow = Some(Duration::from_millis(20));
+
+        // 20ms fits 30 FPS but not 60; it has to last before stepping down
+        assert_eq!(governor.evaluate(slow, None, start), None);
+        let at = start + OVERLOAD_HOLD;
+        let point = governor.evaluate(slow, None, at).unwrap();
+        assert!(!point.avc444);
+        assert_eq!(point.max_fps, 60);
+
+        // Nothing changes while the step settles
+        assert_eq!(governor.evaluate(slow, None, at + OVERLOAD_HOLD), None);
+        let at = at + SETTLE_TIME;
+        assert_eq!(governor.evaluate(slow, None, at), None);
+        let point = governor.evaluate(slow, None, at + OVERLOAD_HOLD).unwrap();
+        assert_eq!(point.max_fps, 30);
+
+        // Within budget at 30 FPS
+        let at = at + OVERLOAD_HOLD + SETTLE_TIME;
+        assert_eq!(governor.evaluate(slow, None, at), None);
+        assert_eq!(governor.evaluate(slow, None, at + OVERLOAD_HOLD), None);
+        assert_eq!(governor.point().to_string(), "AVC420 at up to 30 FPS");
+    }
+
+    #[test]
+    fn test_queue_depth_and_floor() {
+        let start = Instant::now();
+        let mut governor = DecodeGovernor::new(OperatingPoint {
+            avc444: false,
+            max_fps: 15,
+        });
+
+        // A backed-up queue counts even without QoE reports, but 15 FPS is
+        // the floor
+        assert_eq!(governor.evaluate(None, Some(5), start), None);
+        assert_eq!(
+            governor.evaluate(None, Some(5), start + OVERLOAD_HOLD),
+            None
+        );
+        assert_eq!(governor.point().max_fps, 15);
+
+        // A spell back within budget starts the hold over
+        let mut governor = DecodeGovernor::new(OperatingPoint {
+            avc444: false,
+            max_fps: 60,
+        });
+        governor.evaluate(None, Some(5), start);
+        governor.evaluate(None, Some(1), start + OVERLOAD_HOLD / 2);
+        assert_eq!(
+            governor.evaluate(None, Some(5), start + OVERLOAD_HOLD),
+            None
+        );
+    }
+}
@@ -15,8 +15,11 @@ use ironrdp_egfx::pdu::{CapabilitiesAdvertisePdu, CapabilitiesV81Flags, Capabili
 use ironrdp_egfx::server::{GraphicsPipelineHandler, QoeMetrics, Surface};
 use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, info, trace, warn};
 
+use super::FrameAckLatency;
+use crate::protocol::capture::{Channel, Direction, PduCapture};
 use crate::server::{HandlerState, SharedHandlerState};
 
 /// Handler for EGFX graphics pipeline events
@@ -78,6 +81,12 @@ pub struct LamcoGraphicsHandler {
     /// When true, AVC444 will be disabled even if the client supports it.
     /// This is set based on platform detection (e.g., RHEL 9 has AVC444 blur issues).
     force_avc420_only: bool,
+
+    /// Send-to-acknowledgement latency, fed by frame acknowledgements
+    ack_latency: Option<FrameAckLatency>,
+
+    /// Records the advertised and negotiated capabilities when set
+    capture: Option<PduCapture>,
 }
 
 impl LamcoGraphicsHandler {
@@ -93,7 +102,9 @@ impl LamcoGraphicsHandler {
             primary_surface_id: AtomicU16::new(0),
             negotiated_caps: std::sync::RwLock::new(None),
             shared_state: None,
+            ack_latency: None,
             force_avc420_only: false,
+            capture: None,
         }
     }
 
@@ -118,7 +129,9 @@ impl LamcoGraphicsHandler {
             primary_surface_id: AtomicU16::new(0),
             negotiated_caps: std::sync::RwLock::new(None),
             shared_state: None,
+            ack_latency: None,
             force_avc420_only,
+            capture: None,
         }
     }
 
@@ -139,6 +152,8 @@ impl LamcoGraphicsHandler {
             force_avc420_only: false,
             negotiated_caps: std::sync::RwLock::new(None),
             shared_state: Some(shared_state),
+            ack_latency: None,
+            capture: None,
         }
     }
 
@@ -174,6 +189,8 @@ impl LamcoGraphicsHandler {
             force_avc420_only,
             negotiated_caps: std::sync::RwLock::new(None),
             shared_state: Some(shared_state),
+            ack_latency: None,
+            capture: None,
         }
     }
 
@@ -226,6 +243,11 @@ impl LamcoGraphicsHandler {
         self.avc420_enabled.load(Ordering::Acquire)
     }
 
+    /// Check if AVC444 encoding was negotiated
+    pub fn is_avc444_enabled(&self) -> bool {
+        self.avc444_enabled.load(Ordering::Acquire)
+    }
+
     /// Get the primary surface ID
     pub fn primary_surface_id(&self) -> u16 {
         self.primary_surface_id.load(Ordering::Acquire)
@@ -241,10 +263,25 @@ impl LamcoGraphicsHandler {
     pub fn dimensions(&self) -> (u16, u16) {
         (self.width, self.height)
     }
+
+    /// Measure the latency of frame acknowledgements into `ack_latency`
+    pub fn with_ack_latency(mut self, ack_latency: FrameAckLatency) -> Self {
+        self.ack_latency = Some(ack_latency);
+        self
+    }
+
+    /// Record capability negotiation to a PDU capture
+    pub fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
 }
 
 impl GraphicsPipelineHandler for LamcoGraphicsHandler {
     fn capabilities_advertise(&mut self, pdu: &CapabilitiesAdvertisePdu) {
+        if let Some(capture) = &self.capture {
+            capture.record_pdu(Channel::Egfx, Direction::ClientToServer, pdu);
+        }
         info!("EGFX: Client advertised {} capability sets", pdu.0.len());
         for cap in &pdu.0 {
             debug!("  EGFX capability: {:?}", cap);
@@ -252,6 +289,9 @@ impl GraphicsPipelineHandler for LamcoGraphicsHandler {
     }
 
     fn on_ready(&mut self, negotiated: &CapabilitySet) {
+        if let Some(capture) = &self.capture {
+            capture.record_pdu(Channel::Egfx, Direction::ServerToClient, negotiated);
+        }
         info!("EGFX: Channel ready with {:?}", negotiated);
 
         // Store negotiated caps
@@ -327,6 +367,10 @@ impl GraphicsPipelineHandler for LamcoGraphicsHandler {
             frame_id,
             queue_depth
         );
+        if let Some(ref ack_latency) = self.ack_latency {
+            ack_latency.frame_acked(frame_id);
+            ack_latency.record_queue_depth(queue_depth);
+        }
     }
 
     fn on_qoe_metrics(&mut self, metrics: QoeMetrics) {
@@ -334,7 +378,9 @@ impl GraphicsPipelineHandler for LamcoGraphicsHandler {
             "EGFX: QoE metrics - frame {}, decode+render: {}μs",
             metrics.frame_id, metrics.time_diff_dr
         );
-        // Future: Use metrics to adjust encoding quality dynamically
+        if let Some(ref ack_latency) = self.ack_latency {
+            ack_latency.record_decode_time(Duration::from_micros(u64::from(metrics.time_diff_dr)));
+        }
     }
 
     fn on_surface_created(&mut self, surface: &Surface) {
@@ -369,6 +415,9 @@ impl GraphicsPipelineHandler for LamcoGraphicsHandler {
 
     fn on_close(&mut self) {
         info!("EGFX: Channel closed");
+        if let Some(ref ack_latency) = self.ack_latency {
+            ack_latency.reset();
+        }
         self.ready.store(false, Ordering::Release);
         self.avc420_enabled.store(false, Ordering::Release);
         self.has_surface.store(false, Ordering::Release);
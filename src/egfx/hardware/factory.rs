@@ -146,6 +146,28 @@ pub fn create_hardware_encoder(
     Err(HardwareEncoderError::NoBackendAvailable { reason })
 }
 
+/// Create an encoder of one backend (`"vaapi"` or `"nvenc"`), without fallback
+///
+/// For comparing backends against each other; the server itself uses
+/// [`create_hardware_encoder`].
+pub fn create_backend_encoder(
+    backend: &str,
+    config: &HardwareEncodingConfig,
+    width: u32,
+    height: u32,
+) -> HardwareEncoderResult<Box<dyn HardwareEncoder>> {
+    let preset = QualityPreset::from_str(&config.quality_preset).unwrap_or_default();
+    match backend {
+        #[cfg(feature = "vaapi")]
+        "vaapi" => try_vaapi(config, width, height, preset),
+        #[cfg(feature = "nvenc")]
+        "nvenc" => try_nvenc(config, width, height, preset),
+        _ => Err(HardwareEncoderError::NoBackendAvailable {
+            reason: format!("{} support not built in", backend),
+        }),
+    }
+}
+
 /// Try to create a VA-API encoder
 #[cfg(feature = "vaapi")]
 fn try_vaapi(
@@ -57,7 +57,7 @@ pub mod nvenc;
 
 // Re-exports
 pub use error::{HardwareEncoderError, HardwareEncoderResult};
-pub use factory::create_hardware_encoder;
+pub use factory::{create_backend_encoder, create_hardware_encoder, probe_backends};
 pub use stats::{EncodeTimer, HardwareEncoderStats};
 
 #[cfg(feature = "vaapi")]
@@ -118,6 +118,9 @@ pub struct VaapiEncoder {
 
     /// Color space configuration for conversion and VUI
     color_space: ColorSpaceConfig,
+
+    /// NV12 conversion target, reused across frames
+    nv12_buffer: Vec<u8>,
 }
 
 impl VaapiEncoder {
@@ -290,6 +293,7 @@ impl VaapiEncoder {
             bitrate_bps,
             nv12_format,
             color_space,
+            nv12_buffer: Vec::new(),
         })
     }
 
@@ -424,12 +428,14 @@ impl HardwareEncoder for VaapiEncoder {
         );
 
         // Convert BGRA to NV12 using configured color space
-        let nv12_data = bgra_to_nv12(
+        bgra_to_nv12(
             bgra_data,
             width as usize,
             height as usize,
             &self.color_space,
+            &mut self.nv12_buffer,
         );
+        let nv12_data = &self.nv12_buffer;
 
         // Upload via Image API - create image, write data, image drop calls vaPutImage
         {
@@ -777,10 +783,19 @@ impl VaapiEncoder {
 /// - BT.2020 (UHD content)
 ///
 /// Range is also configurable (limited 16-235 vs full 0-255).
-fn bgra_to_nv12(bgra: &[u8], width: usize, height: usize, config: &ColorSpaceConfig) -> Vec<u8> {
+///
+/// The result is written to `nv12`, which keeps its allocation from frame
+/// to frame; every byte of it is overwritten.
+fn bgra_to_nv12(
+    bgra: &[u8],
+    width: usize,
+    height: usize,
+    config: &ColorSpaceConfig,
+    nv12: &mut Vec<u8>,
+) {
     let y_size = width * height;
     let uv_size = (width / 2) * (height / 2) * 2;
-    let mut nv12 = vec![0u8; y_size + uv_size];
+    nv12.resize(y_size + uv_size, 0);
 
     // Split into Y and UV planes
     let (y_plane, uv_plane) = nv12.split_at_mut(y_size);
@@ -848,8 +863,6 @@ fn bgra_to_nv12(bgra: &[u8], width: usize, height: usize, config: &ColorSpaceCon
             uv_plane[uv_idx + 1] = v_val.clamp(uv_min as f32, uv_max as f32) as u8;
         }
     }
-
-    nv12
 }
 
 #[cfg(test)]
@@ -879,7 +892,8 @@ mod tests {
 
         // Test with BT.709 (default for HD)
         let config = ColorSpaceConfig::from_preset(ColorSpacePreset::BT709);
-        let nv12 = bgra_to_nv12(&bgra, 4, 4, &config);
+        let mut nv12 = Vec::new();
+        bgra_to_nv12(&bgra, 4, 4, &config, &mut nv12);
 
         // Y plane should be 16 bytes, UV plane should be 8 bytes
         assert_eq!(nv12.len(), 24);
@@ -893,6 +907,11 @@ mod tests {
                 y
             );
         }
+
+        // A buffer left over from a larger frame gives the same result
+        let mut reused = vec![0xAA; 64];
+        bgra_to_nv12(&bgra, 4, 4, &config, &mut reused);
+        assert_eq!(reused, nv12);
     }
 
     #[test]
@@ -906,11 +925,13 @@ mod tests {
 
         // BT.709: Kg = 0.7152, so green is brightest
         let bt709 = ColorSpaceConfig::from_preset(ColorSpacePreset::BT709);
-        let nv12_709 = bgra_to_nv12(&bgra, 4, 4, &bt709);
+        let mut nv12_709 = Vec::new();
+        bgra_to_nv12(&bgra, 4, 4, &bt709, &mut nv12_709);
 
         // BT.601: Kg = 0.587, so green is still bright but different
         let bt601 = ColorSpaceConfig::from_preset(ColorSpacePreset::BT601);
-        let nv12_601 = bgra_to_nv12(&bgra, 4, 4, &bt601);
+        let mut nv12_601 = Vec::new();
+        bgra_to_nv12(&bgra, 4, 4, &bt601, &mut nv12_601);
 
         // Both should have high Y values for green
         assert!(nv12_709[0] > 150, "BT.709 green Y should be high");
@@ -52,6 +52,8 @@ mod yuv444_packing;
 #[cfg(any(feature = "vaapi", feature = "nvenc"))]
 pub mod hardware;
 
+mod ack_latency;
+mod decode_governor;
 mod h264_level;
 mod handler;
 mod video_handler;
@@ -78,6 +80,10 @@ pub use yuv444_packing::{
     pack_auxiliary_view, pack_dual_views, pack_main_view, validate_dimensions, Yuv420Frame,
 };
 
+// Re-export frame acknowledgement tracking
+pub use ack_latency::FrameAckLatency;
+pub use decode_governor::{DecodeGovernor, OperatingPoint};
+
 // Re-export H.264 level management
 pub use h264_level::{ConstraintViolation, H264Level, LevelConstraints};
 
@@ -28,6 +28,7 @@
 //! See MS-RDPEGFX Section 3.3.8.3.2 and Figure 7 for the specification.
 
 use super::color_convert::{subsample_chroma_420, ColorMatrix, Yuv444Frame};
+use crate::performance::BufferPool;
 
 /// YUV420 frame (4:2:0 chroma subsampling)
 ///
@@ -48,19 +49,28 @@ pub struct Yuv420Frame {
 }
 
 impl Yuv420Frame {
-    /// Create a new YUV420 frame with allocated buffers
+    /// Create a new YUV420 frame with buffers from the frame buffer pool
     pub fn new(width: usize, height: usize) -> Self {
         let y_size = width * height;
         let uv_size = (width / 2) * (height / 2);
+        let pool = BufferPool::global();
         Self {
-            y: vec![0u8; y_size],
-            u: vec![128u8; uv_size],
-            v: vec![128u8; uv_size],
+            y: pool.take_filled(y_size, 0),
+            u: pool.take_filled(uv_size, 128),
+            v: pool.take_filled(uv_size, 128),
             width,
             height,
         }
     }
 
+    /// Return the planes to the frame buffer pool
+    pub fn recycle(self) {
+        let pool = BufferPool::global();
+        pool.recycle(self.y);
+        pool.recycle(self.u);
+        pool.recycle(self.v);
+    }
+
     /// Get total frame size in bytes
     #[inline]
     pub fn total_size(&self) -> usize {
@@ -227,7 +237,8 @@ pub fn pack_main_view(yuv444: &Yuv444Frame) -> Yuv420Frame {
     let height = yuv444.height;
 
     // Y plane: Copy full luma (no subsampling)
-    let y = yuv444.y.clone();
+    let mut y = BufferPool::global().take(yuv444.y.len());
+    y.extend_from_slice(&yuv444.y);
 
     // U plane: 2×2 box filter subsample
     let mut u = subsample_chroma_420(&yuv444.u, width, height);
@@ -399,9 +410,8 @@ fn pack_auxiliary_view_spec_compliant(yuv444: &Yuv444Frame) -> Yuv420Frame {
     // Pad to 16-row macroblock boundary (required by spec)
     // MS-RDPEGFX: "The auxiliary frame is aligned to multiples of 16×16"
     let padded_height = ((height + 15) / 16) * 16;
-    // Use explicit allocation + fill to ensure deterministic memory state
-    let mut aux_y = vec![0u8; padded_height * width];
-    aux_y.fill(128);
+    // Filled explicitly: pooled buffers hold data of earlier frames
+    let mut aux_y = BufferPool::global().take_filled(padded_height * width, 128);
 
     // B4 and B5 blocks: Pack odd rows from U444 and V444
     //
@@ -471,10 +481,8 @@ fn pack_auxiliary_view_spec_compliant(yuv444: &Yuv444Frame) -> Yuv420Frame {
     // Let OpenH264 handle any padding it needs internally.
 
     // Initialize with neutral chroma (128) for deterministic state
-    let mut aux_u = vec![0u8; chroma_width * chroma_height];
-    let mut aux_v = vec![0u8; chroma_width * chroma_height];
-    aux_u.fill(128);
-    aux_v.fill(128);
+    let mut aux_u = BufferPool::global().take_filled(chroma_width * chroma_height, 128);
+    let mut aux_v = BufferPool::global().take_filled(chroma_width * chroma_height, 128);
 
     // Fill actual data with UNPADDED stride (matches what we tell encoder)
     for cy in 0..chroma_height {
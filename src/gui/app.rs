@@ -3,16 +3,31 @@
 //! Implements the Elm Architecture pattern: State -> View -> Message -> Update -> State
 
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use iced::widget::{button, column, container, row, scrollable, space, text};
-use iced::{Alignment, Element, Length, Subscription, Task};
+use iced::widget::{button, column, container, pick_list, row, scrollable, space, text};
+use iced::{Alignment, Element, Length, Subscription, Task, Theme};
 
 use crate::config::Config;
 use crate::gui::message::{DamageTrackingPreset, EgfxPreset, Message, PerformancePreset};
-use crate::gui::state::{AppState, CertGenState, LogLine, MessageLevel, Tab};
+use crate::gui::monitor_layout;
+use crate::gui::preferences::{self, UI_SCALES};
+use crate::gui::preview;
+use crate::gui::profiles;
+use crate::gui::server_control::ACTIVITY_WINDOW;
+use crate::gui::service::{self, ServiceAction};
+use crate::gui::state::{
+    AppState, CertGenState, EditStrings, LogLine, MessageLevel, ServerStatus, Tab, WizardState,
+    WizardStep,
+};
 use crate::gui::tabs;
-use crate::gui::theme as app_theme;
+use crate::gui::theme::{self as app_theme, ThemeMode};
+use crate::gui::widgets;
+use crate::server::HealthChecker;
+
+/// How often the desktop color scheme is re-read while the theme follows it
+const COLOR_SCHEME_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct ConfigGuiApp {
     pub state: AppState,
@@ -30,12 +45,17 @@ impl Default for ConfigGuiApp {
 
 impl ConfigGuiApp {
     pub fn new() -> (Self, Task<Message>) {
-        let app = Self::default();
+        let mut app = Self::default();
+        app.state.color_scheme_checked = Some(Instant::now());
 
-        // Initial tasks: detect capabilities and GPUs
+        // Initial tasks: detect capabilities, GPUs and the color scheme
         let tasks = Task::batch([
             Task::perform(async {}, |_| Message::RefreshCapabilities),
             Task::perform(async {}, |_| Message::VideoDetectGpus),
+            Task::perform(
+                preferences::system_prefers_dark(),
+                Message::SystemColorSchemeDetected,
+            ),
         ]);
 
         (app, tasks)
@@ -106,12 +126,32 @@ impl ConfigGuiApp {
                 self.state.mark_dirty();
                 Task::none()
             }
+            Message::ServerPresetChanged(name) => {
+                if name == "none" {
+                    self.state.config.profile = None;
+                    self.state.mark_dirty();
+                    return Task::none();
+                }
+                match self.state.config.with_profile(&name) {
+                    Ok(config) => {
+                        self.state.edit_strings = EditStrings::from_config(&config);
+                        self.state.config = config;
+                        self.state.mark_dirty();
+                    }
+                    Err(e) => {
+                        self.state
+                            .add_message(MessageLevel::Error, format!("{:#}", e));
+                    }
+                }
+                Task::none()
+            }
 
             // =================================================================
             // Security Configuration
             // =================================================================
             Message::SecurityCertPathChanged(path) => {
                 self.state.config.security.cert_path = PathBuf::from(path);
+                self.state.cert_check = None;
                 self.state.mark_dirty();
                 Task::none()
             }
@@ -127,13 +167,16 @@ impl ConfigGuiApp {
             ),
             Message::SecurityCertSelected(path) => {
                 if let Some(p) = path {
+                    self.state.edit_strings.cert_path = p.display().to_string();
                     self.state.config.security.cert_path = p;
                     self.state.mark_dirty();
+                    return self.check_certificate();
                 }
                 Task::none()
             }
             Message::SecurityKeyPathChanged(path) => {
                 self.state.config.security.key_path = PathBuf::from(path);
+                self.state.cert_check = None;
                 self.state.mark_dirty();
                 Task::none()
             }
@@ -149,8 +192,10 @@ impl ConfigGuiApp {
             ),
             Message::SecurityKeySelected(path) => {
                 if let Some(p) = path {
+                    self.state.edit_strings.key_path = p.display().to_string();
                     self.state.config.security.key_path = p;
                     self.state.mark_dirty();
+                    return self.check_certificate();
                 }
                 Task::none()
             }
@@ -215,6 +260,7 @@ impl ConfigGuiApp {
                             MessageLevel::Success,
                             "Certificate generated successfully".to_string(),
                         );
+                        return self.check_certificate();
                     }
                     Err(e) => {
                         self.state.add_message(MessageLevel::Error, e);
@@ -222,6 +268,11 @@ impl ConfigGuiApp {
                 }
                 Task::none()
             }
+            Message::SecurityCheckCert => self.check_certificate(),
+            Message::SecurityCertChecked(check) => {
+                self.state.cert_check = Some(check);
+                Task::none()
+            }
             Message::SecurityEnableNlaToggled(val) => {
                 self.state.config.security.enable_nla = val;
                 self.state.mark_dirty();
@@ -420,6 +471,16 @@ impl ConfigGuiApp {
                 self.state.mark_dirty();
                 Task::none()
             }
+            Message::InputFallbackChainChanged(chain) => {
+                self.state.config.input.fallback_chain = chain
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                self.state.edit_strings.fallback_chain = chain;
+                self.state.mark_dirty();
+                Task::none()
+            }
 
             // =================================================================
             // Clipboard Configuration
@@ -471,6 +532,71 @@ impl ConfigGuiApp {
                     self.state.config.multimon.max_monitors = v;
                     self.state.mark_dirty();
                 }
+                self.state.edit_strings.max_monitors = val;
+                Task::none()
+            }
+            Message::MultimonToggleExpanded => {
+                self.state.multimon_expanded = !self.state.multimon_expanded;
+                if self.state.multimon_expanded && self.state.detected_monitors.is_empty() {
+                    return Task::done(Message::DetectMonitors);
+                }
+                Task::none()
+            }
+            Message::DetectMonitors => {
+                self.state.monitors_detecting = true;
+                Task::perform(monitor_layout::detect_monitors(), Message::MonitorsDetected)
+            }
+            Message::MonitorsDetected(result) => {
+                self.state.monitors_detecting = false;
+                match result {
+                    Ok(monitors) => self.state.detected_monitors = monitors,
+                    Err(e) => self.state.add_message(MessageLevel::Warning, e),
+                }
+                Task::none()
+            }
+            Message::MonitorLayoutMoved { index, x, y } => {
+                if let Some(monitor) = self.state.detected_monitors.get(index) {
+                    let layout = &mut self.state.config.multimon.monitors;
+                    let entry = monitor.entry_mut(layout);
+                    (layout[entry].x, layout[entry].y) = (x, y);
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
+            Message::MonitorLayoutDropped(index) => {
+                if let Some(monitor) = self.state.detected_monitors.get(index) {
+                    let layout = &mut self.state.config.multimon.monitors;
+                    let rects =
+                        monitor_layout::Rect::from_layout(&self.state.detected_monitors, layout);
+                    let entry = monitor.entry_mut(layout);
+                    let position = (layout[entry].x, layout[entry].y);
+                    (layout[entry].x, layout[entry].y) =
+                        monitor_layout::snap_position(&rects, index, position);
+                }
+                Task::none()
+            }
+            Message::MonitorSharedToggled(index, shared) => {
+                if let Some(monitor) = self.state.detected_monitors.get(index) {
+                    let layout = &mut self.state.config.multimon.monitors;
+                    let entry = monitor.entry_mut(layout);
+                    layout[entry].shared = shared;
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
+            Message::MonitorFpsSelected(index, fps) => {
+                if let Some(monitor) = self.state.detected_monitors.get(index) {
+                    let layout = &mut self.state.config.multimon.monitors;
+                    let entry = monitor.entry_mut(layout);
+                    // Anything that isn't a number is the pipeline rate
+                    layout[entry].fps = fps.parse().unwrap_or(0);
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
+            Message::MonitorLayoutReset => {
+                self.state.config.multimon.monitors.clear();
+                self.state.mark_dirty();
                 Task::none()
             }
 
@@ -669,6 +795,43 @@ impl ConfigGuiApp {
                 self.state.egfx_expert_mode = !self.state.egfx_expert_mode;
                 Task::none()
             }
+            Message::PreviewToggleExpanded => {
+                self.state.preview_expanded = !self.state.preview_expanded;
+                Task::none()
+            }
+            Message::PreviewResolutionSelected(resolution) => {
+                self.state.preview_resolution = resolution;
+                self.state.preview_sample = None;
+                Task::none()
+            }
+            Message::RenderPreview => {
+                let Some((width, height)) =
+                    preview::parse_resolution(&self.state.preview_resolution)
+                else {
+                    return Task::none();
+                };
+                self.state.preview_rendering = true;
+                let config = self.state.config.clone();
+                Task::perform(
+                    async move {
+                        // Encoding a frame takes a while at 4K; keep it off the UI thread
+                        tokio::task::spawn_blocking(move || {
+                            preview::render_sample(&config, width, height)
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(format!("Preview failed: {}", e)))
+                    },
+                    Message::PreviewRendered,
+                )
+            }
+            Message::PreviewRendered(result) => {
+                self.state.preview_rendering = false;
+                match result {
+                    Ok(sample) => self.state.preview_sample = Some(sample),
+                    Err(e) => self.state.add_message(MessageLevel::Error, e),
+                }
+                Task::none()
+            }
             Message::EgfxH264LevelChanged(level) => {
                 self.state.config.egfx.h264_level = level;
                 self.state.mark_dirty();
@@ -681,6 +844,21 @@ impl ConfigGuiApp {
                 }
                 Task::none()
             }
+            Message::EgfxAdaptiveBitrateToggled(val) => {
+                self.state.config.egfx.adaptive_bitrate = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::EgfxSmallUpdateFastPathToggled(val) => {
+                self.state.config.egfx.small_update_fast_path = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::EgfxDecodeDowngradeToggled(val) => {
+                self.state.config.egfx.decode_downgrade = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
             Message::EgfxZgfxCompressionChanged(mode) => {
                 self.state.config.egfx.zgfx_compression = mode;
                 self.state.mark_dirty();
@@ -953,6 +1131,21 @@ impl ConfigGuiApp {
                 self.state.mark_dirty();
                 Task::none()
             }
+            Message::CursorColorPointersToggled(val) => {
+                self.state.config.cursor.color_pointers = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::CursorDirectTouchToggled(val) => {
+                self.state.config.cursor.direct_touch = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::CursorDirectPenToggled(val) => {
+                self.state.config.cursor.direct_pen = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
             Message::CursorPredictiveThresholdChanged(val) => {
                 if let Ok(v) = val.parse() {
                     self.state.config.cursor.predictive_latency_threshold_ms = v;
@@ -967,6 +1160,13 @@ impl ConfigGuiApp {
                 }
                 Task::none()
             }
+            Message::CursorDecoupleBelowFpsChanged(val) => {
+                if let Ok(v) = val.parse() {
+                    self.state.config.cursor.decouple_below_fps = v;
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
             Message::PredictorHistorySizeChanged(val) => {
                 if let Ok(v) = val.parse() {
                     self.state.config.cursor.predictor.history_size = v;
@@ -1010,6 +1210,30 @@ impl ConfigGuiApp {
                 self.state.mark_dirty();
                 Task::none()
             }
+            Message::PredictorModelChanged(model) => {
+                self.state.config.cursor.predictor.model = model;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::PredictorLookaheadRttFractionChanged(val) => {
+                self.state.config.cursor.predictor.lookahead_rtt_fraction = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::PredictorMaxLookaheadMsChanged(val) => {
+                if let Ok(v) = val.parse() {
+                    self.state.config.cursor.predictor.max_lookahead_ms = v;
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
+            Message::PredictorSnapBackDistanceChanged(val) => {
+                if let Ok(v) = val.parse() {
+                    self.state.config.cursor.predictor.snap_back_distance = v;
+                    self.state.mark_dirty();
+                }
+                Task::none()
+            }
 
             // =================================================================
             // File Operations
@@ -1107,36 +1331,254 @@ impl ConfigGuiApp {
             }
 
             // =================================================================
-            // Server Control
+            // Configuration Profiles
             // =================================================================
-            Message::StartServer => {
-                // TODO: Implement server start via IPC
-                self.state.add_message(
-                    MessageLevel::Info,
-                    "Server start requested (IPC not yet implemented)".to_string(),
-                );
+            Message::ProfilesToggleExpanded => {
+                self.state.profiles_expanded = !self.state.profiles_expanded;
                 Task::none()
             }
-            Message::StopServer => {
-                // TODO: Implement server stop via IPC
-                self.state.add_message(
-                    MessageLevel::Info,
-                    "Server stop requested (IPC not yet implemented)".to_string(),
-                );
+            Message::ProfileSelected(name) => {
+                self.state.selected_profile = Some(name);
                 Task::none()
             }
-            Message::RestartServer => {
-                // TODO: Implement server restart via IPC
-                self.state.add_message(
-                    MessageLevel::Info,
-                    "Server restart requested (IPC not yet implemented)".to_string(),
-                );
+            Message::LoadProfile => {
+                let Some(name) = self.state.selected_profile.clone() else {
+                    return Task::none();
+                };
+                Task::perform(
+                    async move { profiles::load_profile(&profiles::profiles_dir(), &name) },
+                    Message::ProfileLoaded,
+                )
+            }
+            Message::ProfileLoaded(result) => {
+                match result {
+                    Ok(config) => {
+                        self.state.edit_strings = EditStrings::from_config(&config);
+                        self.state.config = config;
+                        self.state.config_diff = None;
+                        self.state.mark_dirty();
+                        self.state.add_message(
+                            MessageLevel::Success,
+                            "Profile loaded - save to apply it".to_string(),
+                        );
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
+                Task::none()
+            }
+            Message::DeleteProfile => {
+                if let Some(name) = self.state.selected_profile.take() {
+                    let dir = profiles::profiles_dir();
+                    if let Err(e) = profiles::delete_profile(&dir, &name) {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                    self.state.profiles = profiles::list_profiles(&dir);
+                }
+                Task::none()
+            }
+            Message::ProfileNameChanged(name) => {
+                self.state.profile_name = name;
+                Task::none()
+            }
+            Message::SaveProfile => {
+                let name = self.state.profile_name.trim().to_string();
+                let config = self.state.config.clone();
+                Task::perform(
+                    async move {
+                        profiles::save_profile(&profiles::profiles_dir(), &name, &config)
+                            .map(|_| name)
+                    },
+                    Message::ProfileSaved,
+                )
+            }
+            Message::ProfileSaved(result) => {
+                match result {
+                    Ok(name) => {
+                        self.state.profiles = profiles::list_profiles(&profiles::profiles_dir());
+                        self.state.add_message(
+                            MessageLevel::Success,
+                            format!("Saved profile '{}'", name),
+                        );
+                        self.state.selected_profile = Some(name);
+                        self.state.profile_name.clear();
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
+                Task::none()
+            }
+            Message::CompareWithRunningConfig => {
+                let config = self.state.config.clone();
+                let path = self.state.config_path.clone();
+                Task::perform(
+                    async move {
+                        let running = crate::gui::file_ops::load_config(&path)?;
+                        Ok(crate::gui::file_ops::diff_configs(&running, &config))
+                    },
+                    Message::ConfigDiffComputed,
+                )
+            }
+            Message::ConfigDiffComputed(result) => {
+                match result {
+                    Ok(diff) => self.state.config_diff = Some(diff),
+                    Err(e) => self.state.add_message(MessageLevel::Error, e),
+                }
+                Task::none()
+            }
+
+            // =================================================================
+            // Server Control
+            // =================================================================
+            Message::StartServer => self.run_service_action(ServiceAction::Start),
+            Message::StopServer => self.run_service_action(ServiceAction::Stop),
+            Message::RestartServer => self.run_service_action(ServiceAction::Restart),
+            Message::ReloadServerConfig => {
+                let config = self.state.config.clone();
+                let path = self.state.config_path.clone();
+                Task::perform(
+                    async move { crate::gui::server_control::save_and_reload(&config, &path) },
+                    Message::ServerConfigReloaded,
+                )
+            }
+            Message::ServerConfigReloaded(result) => {
+                match result {
+                    Ok(outcome) => {
+                        self.state.mark_clean();
+                        if outcome.servers_signalled == 0 {
+                            self.state.add_message(
+                                MessageLevel::Warning,
+                                "Configuration saved, but no running server was found".to_string(),
+                            );
+                        } else {
+                            self.state.add_message(
+                                MessageLevel::Success,
+                                format!(
+                                    "Configuration reloaded ({} settings applied)",
+                                    outcome.report.applied.len()
+                                ),
+                            );
+                        }
+                        if !outcome.report.restart_required.is_empty() {
+                            self.state.add_message(
+                                MessageLevel::Warning,
+                                format!(
+                                    "Restart required for: {}",
+                                    outcome.report.restart_required.join(", ")
+                                ),
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
                 Task::none()
             }
             Message::ServerStatusUpdated(status) => {
                 self.state.server_status = status;
                 Task::none()
             }
+            Message::LiveStatusUpdated(result) => {
+                self.state.live_status_pending = false;
+                match result {
+                    Ok(live) => {
+                        self.state.server_status = live.status;
+                        self.state
+                            .session_history
+                            .record(&live.sessions, Instant::now());
+                        self.state.live_sessions = live.sessions;
+                    }
+                    Err(e) => {
+                        self.state.server_status = ServerStatus::Error(e);
+                        self.state.live_sessions.clear();
+                        self.state.session_history.clear();
+                    }
+                }
+                Task::none()
+            }
+            Message::DisconnectSession(id) => {
+                let config = self.state.config.admin_api.clone();
+                Task::perform(
+                    crate::gui::server_control::disconnect_client(config, id),
+                    Message::SessionDisconnected,
+                )
+            }
+            Message::SetSharingPaused { id, paused } => {
+                let config = self.state.config.admin_api.clone();
+                Task::perform(
+                    crate::gui::server_control::set_sharing_paused(config, id, paused),
+                    Message::SharingPausedSet,
+                )
+            }
+            Message::SharingPausedSet(result) => {
+                match result {
+                    Ok((id, paused)) => {
+                        let session = self.state.live_sessions.iter_mut().find(|s| s.id == id);
+                        if let Some(sharing) = session.and_then(|s| s.sharing.as_mut()) {
+                            sharing.paused = paused;
+                        }
+                        let action = if paused { "paused" } else { "resumed" };
+                        self.state.add_message(
+                            MessageLevel::Info,
+                            format!("Sharing with client {} {}", id, action),
+                        );
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
+                Task::none()
+            }
+            Message::SessionDisconnected(result) => {
+                match result {
+                    Ok(id) => {
+                        self.state.live_sessions.retain(|session| session.id != id);
+                        self.state.add_message(
+                            MessageLevel::Success,
+                            format!("Client {} disconnected", id),
+                        );
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
+                Task::none()
+            }
+
+            Message::ServiceScopeSelected(scope) => {
+                self.state.service_scope = scope;
+                self.state.service_status = None;
+                Task::none()
+            }
+            Message::ServiceActionRequested(action) => self.run_service_action(action),
+            Message::ServiceActionCompleted(action, result) => {
+                self.state.service_busy = false;
+                match result {
+                    Ok(status) => {
+                        self.state.add_message(
+                            MessageLevel::Success,
+                            format!(
+                                "Service {} done: {} is {}",
+                                action, status.unit, status.active_state
+                            ),
+                        );
+                        self.state.service_status = Some(status);
+                    }
+                    Err(e) => {
+                        self.state.add_message(MessageLevel::Error, e);
+                    }
+                }
+                Task::none()
+            }
+            Message::ServiceStatusUpdated(result) => {
+                self.state.service_poll_pending = false;
+                // No systemd (or no D-Bus) leaves the service section empty
+                self.state.service_status = result.ok();
+                Task::none()
+            }
 
             // =================================================================
             // Validation
@@ -1200,6 +1642,116 @@ impl ConfigGuiApp {
                 Task::none()
             }
 
+            // =================================================================
+            // Setup Wizard
+            // =================================================================
+            Message::OpenSetupWizard => {
+                self.state.wizard = Some(WizardState::default());
+                Task::none()
+            }
+            Message::CloseSetupWizard => {
+                self.state.wizard = None;
+                Task::none()
+            }
+            Message::WizardNext => {
+                let Some(wizard) = self.state.wizard.as_mut() else {
+                    return Task::none();
+                };
+                let Some(next) = wizard.step.next() else {
+                    return Task::none();
+                };
+                wizard.step = next;
+                if next == WizardStep::Certificate {
+                    return self.check_certificate();
+                }
+                Task::none()
+            }
+            Message::WizardBack => {
+                if let Some(wizard) = self.state.wizard.as_mut() {
+                    wizard.step = wizard.step.previous().unwrap_or(wizard.step);
+                }
+                Task::none()
+            }
+            Message::WizardNativeCaptureToggled(val) => {
+                self.state.config.mutter.enabled = val;
+                self.state.config.kwin.enabled = val;
+                self.state.mark_dirty();
+                Task::none()
+            }
+            Message::WizardRequestPermissions => {
+                let Some(wizard) = self.state.wizard.as_mut() else {
+                    return Task::none();
+                };
+                wizard.permissions_running = true;
+                let config = self.state.config.clone();
+                Task::perform(
+                    async move {
+                        crate::server::run_setup(&config, false)
+                            .await
+                            .map_err(|e| format!("Permission setup failed: {:#}", e))
+                    },
+                    Message::WizardPermissionsCompleted,
+                )
+            }
+            Message::WizardPermissionsCompleted(result) => {
+                if let Some(wizard) = self.state.wizard.as_mut() {
+                    wizard.permissions_running = false;
+                    match result {
+                        Ok(report) => wizard.permissions = Some(report),
+                        Err(e) => self.state.add_message(MessageLevel::Error, e),
+                    }
+                }
+                Task::none()
+            }
+            Message::WizardGenerateCertificate => {
+                let cert_path = self.state.config.security.cert_path.clone();
+                let key_path = self.state.config.security.key_path.clone();
+                let params = crate::gui::certificates::CertGenParams::default();
+                Task::perform(
+                    async move {
+                        crate::gui::certificates::generate_self_signed_certificate(
+                            cert_path,
+                            key_path,
+                            params.common_name,
+                            params.organization,
+                            params.validity_days,
+                        )
+                    },
+                    Message::CertGenCompleted,
+                )
+            }
+            Message::WizardFinish { start_server } => {
+                self.state.wizard = None;
+                let config = self.state.config.clone();
+                let path = self.state.config_path.clone();
+                let save = Task::perform(
+                    async move { crate::gui::file_ops::save_config(&config, &path) },
+                    Message::ConfigSaved,
+                );
+                if start_server {
+                    save.chain(Task::done(Message::StartServer))
+                } else {
+                    save
+                }
+            }
+
+            // =================================================================
+            // Diagnostics
+            // =================================================================
+            Message::RunDiagnostics => {
+                self.state.diagnostics_running = true;
+                let checker = HealthChecker::new(Arc::new(self.state.config.clone()));
+                Task::perform(
+                    async move { checker.check_before_start().await },
+                    Message::DiagnosticsCompleted,
+                )
+            }
+            Message::DiagnosticsCompleted(report) => {
+                self.state.diagnostics_running = false;
+                self.state.diagnostics = Some(report);
+                Task::none()
+            }
+
             // =================================================================
             // Log Viewer
             // =================================================================
@@ -1226,6 +1778,38 @@ impl ConfigGuiApp {
                 };
                 Task::none()
             }
+            Message::LogModuleFilterChanged(module) => {
+                self.state.log_module_filter = if module == "All" {
+                    String::new()
+                } else {
+                    module
+                };
+                Task::none()
+            }
+            Message::LogSearchChanged(search) => {
+                self.state.log_search = search;
+                Task::none()
+            }
+            Message::LogFilePathChanged(path) => {
+                self.follow_log_file(path);
+                Task::none()
+            }
+            Message::BrowseLogFile => Task::perform(
+                async {
+                    let file = rfd::AsyncFileDialog::new()
+                        .add_filter("Log", &["log", "txt"])
+                        .pick_file()
+                        .await;
+                    file.map(|f| f.path().to_path_buf())
+                },
+                Message::LogFileSelected,
+            ),
+            Message::LogFileSelected(path) => {
+                if let Some(p) = path {
+                    self.follow_log_file(p.display().to_string());
+                }
+                Task::none()
+            }
             Message::ExportLogs => {
                 // TODO: Implement log export
                 self.state.add_message(
@@ -1276,19 +1860,106 @@ impl ConfigGuiApp {
                 self.state.confirm_discard_dialog = false;
                 Task::none()
             }
-            Message::Tick => {
-                // Periodic updates (log refresh, status poll, etc.)
+            Message::ThemeModeSelected(mode) => {
+                self.state.preferences.theme = mode;
+                self.save_preferences()
+            }
+            Message::UiScaleSelected(percent) => {
+                self.state.preferences.scale_percent = percent;
+                self.save_preferences()
+            }
+            Message::SystemColorSchemeDetected(result) => {
+                // Without the Settings portal the light theme stays
+                if let Ok(dark) = result {
+                    self.state.system_prefers_dark = dark;
+                }
                 Task::none()
             }
+            Message::Tick => {
+                let color_scheme = self.poll_color_scheme();
+
+                if let Some(ref mut tail) = self.state.log_tail {
+                    // The file may not exist until the server starts
+                    let lines = tail.read_new_lines().unwrap_or_default();
+                    for line in lines {
+                        self.state.add_log_line(LogLine::parse(&line));
+                    }
+                }
+
+                // Poll the running server on every tab, so the header can show
+                // what clients are doing; its unit only while the Status tab is shown
+                let mut tasks = vec![color_scheme];
+                if self.state.config.admin_api.enabled && !self.state.live_status_pending {
+                    self.state.live_status_pending = true;
+                    let config = self.state.config.admin_api.clone();
+                    let address = self.state.config.server.listen_addr.clone();
+                    tasks.push(Task::perform(
+                        crate::gui::server_control::poll_live_status(config, address),
+                        Message::LiveStatusUpdated,
+                    ));
+                }
+                if self.current_tab == Tab::Status && !self.state.service_poll_pending {
+                    self.state.service_poll_pending = true;
+                    tasks.push(Task::perform(
+                        service::unit_status(self.state.service_scope),
+                        Message::ServiceStatusUpdated,
+                    ));
+                }
+                Task::batch(tasks)
+            }
+        }
+    }
+
+    /// Save the GUI preferences after a change
+    fn save_preferences(&mut self) -> Task<Message> {
+        if let Err(e) = self.state.preferences.save() {
+            self.state.add_message(MessageLevel::Error, e);
         }
+        Task::none()
+    }
+
+    /// Re-read the desktop's color scheme while the theme follows it
+    fn poll_color_scheme(&mut self) -> Task<Message> {
+        if self.state.preferences.theme != ThemeMode::System
+            || self
+                .state
+                .color_scheme_checked
+                .is_some_and(|checked| checked.elapsed() < COLOR_SCHEME_POLL_INTERVAL)
+        {
+            return Task::none();
+        }
+        self.state.color_scheme_checked = Some(Instant::now());
+        Task::perform(
+            preferences::system_prefers_dark(),
+            Message::SystemColorSchemeDetected,
+        )
+    }
+
+    /// The iced theme: light or dark, as chosen or as the desktop prefers
+    pub fn theme(&self) -> Theme {
+        let mode = self.state.preferences.theme;
+        app_theme::iced_theme(mode.is_dark(self.state.system_prefers_dark))
+    }
+
+    /// UI scale chosen in the header
+    pub fn scale_factor(&self) -> f64 {
+        self.state.preferences.scale_factor()
     }
 
     /// Render the main view
     pub fn view(&self) -> Element<'_, Message> {
         let header = self.view_header();
+        let footer = self.view_footer();
+
+        // The first-run wizard takes the place of the tabs while open
+        if let Some(ref wizard) = self.state.wizard {
+            let content =
+                scrollable(tabs::view_setup_wizard(&self.state, wizard)).height(Length::Fill);
+            return column![header, content, footer].spacing(0).into();
+        }
+
         let tab_bar = self.view_tab_bar();
         let content = self.view_tab_content();
-        let footer = self.view_footer();
 
         // Wrap content in scrollable
         let main_content = scrollable(content).height(Length::Fill);
@@ -1305,10 +1976,35 @@ impl ConfigGuiApp {
             row![
                 text("lamco-rdp-server Configuration")
                     .size(24)
-                    .style(|_theme| text::Style {
-                        color: Some(app_theme::colors::PRIMARY),
-                    }),
+                    .style(app_theme::text_color(|p| p.primary)),
                 space().width(Length::Fill),
+                self.view_activity_indicators(),
+                pick_list(
+                    &ThemeMode::ALL[..],
+                    Some(self.state.preferences.theme),
+                    Message::ThemeModeSelected,
+                )
+                .padding([6, 12]),
+                pick_list(
+                    UI_SCALES
+                        .iter()
+                        .map(|percent| format!("{}%", percent))
+                        .collect::<Vec<_>>(),
+                    Some(format!("{}%", self.state.preferences.scale_percent)),
+                    |choice| Message::UiScaleSelected(
+                        choice.trim_end_matches('%').parse().unwrap_or(100)
+                    ),
+                )
+                .padding([6, 12]),
+                button(text("Setup Wizard"))
+                    .on_press_maybe(
+                        self.state
+                            .wizard
+                            .is_none()
+                            .then_some(Message::OpenSetupWizard),
+                    )
+                    .padding([6, 12])
+                    .style(app_theme::secondary_button_style),
                 button(text("Load"))
                     .on_press(Message::LoadConfig)
                     .padding([6, 12])
@@ -1326,19 +2022,45 @@ impl ConfigGuiApp {
             .align_y(Alignment::Center)
             .padding([12, 20]),
         )
-        .style(|_theme| container::Style {
-            background: Some(iced::Background::Color(app_theme::colors::SURFACE)),
-            border: iced::Border {
-                color: app_theme::colors::SURFACE_DARK,
-                width: 0.0,
-                radius: 0.0.into(),
-            },
-            ..Default::default()
-        })
+        .style(app_theme::header_style)
         .width(Length::Fill)
         .into()
     }
 
+    /// What connected clients are doing right now, on every tab
+    fn view_activity_indicators(&self) -> Element<'_, Message> {
+        let sharing: Vec<_> = self
+            .state
+            .live_sessions
+            .iter()
+            .filter_map(|session| session.sharing)
+            .collect();
+        let input = sharing
+            .iter()
+            .any(|s| !s.paused && s.input_active(ACTIVITY_WINDOW));
+        let clipboard = sharing
+            .iter()
+            .any(|s| !s.paused && s.clipboard_active(ACTIVITY_WINDOW));
+        let paused = sharing.iter().filter(|s| s.paused).count();
+
+        let mut indicators = row![].spacing(12).align_y(Alignment::Center);
+        if input {
+            indicators = indicators.push(widgets::activity_indicator("⌨ Remote input", true));
+        }
+        if clipboard {
+            indicators =
+                indicators.push(widgets::activity_indicator("📋 Clipboard transfer", true));
+        }
+        if paused > 0 {
+            indicators = indicators.push(
+                text(format!("⏸ {} paused", paused))
+                    .size(12)
+                    .style(app_theme::text_color(|p| p.warning)),
+            );
+        }
+        indicators.into()
+    }
+
     /// Render the tab bar
     fn view_tab_bar(&self) -> Element<'_, Message> {
         let tabs: Vec<Element<'_, Message>> = Tab::all()
@@ -1363,10 +2085,7 @@ impl ConfigGuiApp {
                 .padding([8, 20])
                 .align_y(Alignment::Center),
         )
-        .style(|_theme| container::Style {
-            background: Some(iced::Background::Color(app_theme::colors::SURFACE_DARK)),
-            ..Default::default()
-        })
+        .style(app_theme::tab_bar_style)
         .width(Length::Fill)
         .into()
     }
@@ -1387,10 +2106,7 @@ impl ConfigGuiApp {
         };
 
         container(content)
-            .style(|_theme| container::Style {
-                background: Some(iced::Background::Color(app_theme::colors::BACKGROUND)),
-                ..Default::default()
-            })
+            .style(app_theme::content_style)
             .width(Length::Fill)
             .height(Length::Fill)
             .into()
@@ -1401,34 +2117,26 @@ impl ConfigGuiApp {
         let dirty_indicator = if self.state.is_dirty {
             text("● Unsaved changes")
                 .size(12)
-                .style(|_theme| text::Style {
-                    color: Some(app_theme::colors::WARNING),
-                })
+                .style(app_theme::text_color(|p| p.warning))
         } else {
-            text("● Saved").size(12).style(|_theme| text::Style {
-                color: Some(app_theme::colors::SUCCESS),
-            })
+            text("● Saved")
+                .size(12)
+                .style(app_theme::text_color(|p| p.success))
         };
 
         let validation_status = if self.state.validation.is_valid {
             text("✓ Valid configuration")
                 .size(12)
-                .style(|_theme| text::Style {
-                    color: Some(app_theme::colors::SUCCESS),
-                })
+                .style(app_theme::text_color(|p| p.success))
         } else {
             text(format!("✗ {} errors", self.state.validation.errors.len()))
                 .size(12)
-                .style(|_theme| text::Style {
-                    color: Some(app_theme::colors::ERROR),
-                })
+                .style(app_theme::text_color(|p| p.error))
         };
 
         let config_path = text(format!("Config: {}", self.state.config_path.display()))
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(app_theme::colors::TEXT_MUTED),
-            });
+            .style(app_theme::text_color(|p| p.text_muted));
 
         container(
             row![
@@ -1442,19 +2150,40 @@ impl ConfigGuiApp {
             .align_y(Alignment::Center)
             .padding([8, 20]),
         )
-        .style(|_theme| container::Style {
-            background: Some(iced::Background::Color(app_theme::colors::SURFACE)),
-            border: iced::Border {
-                color: app_theme::colors::SURFACE_DARK,
-                width: 1.0,
-                radius: 0.0.into(),
-            },
-            ..Default::default()
-        })
+        .style(app_theme::footer_style)
         .width(Length::Fill)
         .into()
     }
 
+    /// Show the lines of `path` in the log viewer, replacing earlier ones
+    fn follow_log_file(&mut self, path: String) {
+        self.state.log_buffer.clear();
+        self.state.log_tail = (!path.trim().is_empty())
+            .then(|| crate::gui::log_tail::LogTail::new(PathBuf::from(path.trim())));
+        self.state.log_file_path = path;
+    }
+
+    /// Run a systemd action on the server's unit in the background
+    fn run_service_action(&mut self, action: ServiceAction) -> Task<Message> {
+        self.state.service_busy = true;
+        let scope = self.state.service_scope;
+        let config_path = self.state.config_path.clone();
+        Task::perform(
+            async move { service::run_action(scope, action, config_path).await },
+            move |result| Message::ServiceActionCompleted(action, result),
+        )
+    }
+
+    /// Check the configured certificate and key in the background
+    fn check_certificate(&self) -> Task<Message> {
+        let cert_path = self.state.config.security.cert_path.clone();
+        let key_path = self.state.config.security.key_path.clone();
+        Task::perform(
+            async move { crate::gui::certificates::check_certificate_pair(&cert_path, &key_path) },
+            Message::SecurityCertChecked,
+        )
+    }
+
     /// Subscriptions for async events
     pub fn subscription(&self) -> Subscription<Message> {
         // Periodic tick for log updates, status polling, etc.
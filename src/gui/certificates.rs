@@ -1,10 +1,12 @@
 //! Certificate Generation Module
 //!
-//! Generates self-signed TLS certificates for RDP server authentication.
+//! Generates self-signed TLS certificates for RDP server authentication,
+//! and checks configured certificate/key pairs before the server uses them.
 
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Utc};
 use rcgen::generate_simple_self_signed;
 use time::{Duration, OffsetDateTime};
 
@@ -235,6 +237,90 @@ pub fn validate_certificate_file(cert_path: &Path) -> Result<CertificateInfo, St
     })
 }
 
+/// Result of checking a certificate and key pair
+#[derive(Debug, Clone, Default)]
+pub struct CertificateCheck {
+    /// SHA-256 fingerprint, for remote users to compare with what their
+    /// client shows
+    pub fingerprint: Option<String>,
+    /// End of the validity period
+    pub valid_until: Option<String>,
+    /// Problems that keep the server from using the pair
+    pub errors: Vec<String>,
+    /// Problems to fix soon (upcoming expiry)
+    pub warnings: Vec<String>,
+}
+
+impl CertificateCheck {
+    /// Whether the server can use the pair
+    pub fn is_usable(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Check a certificate and key the way the server loads them
+///
+/// Reads the validity period (flagging certificates that are expired, not
+/// yet valid or about to expire) and verifies that the key belongs to the
+/// certificate.
+pub fn check_certificate_pair(cert_path: &Path, key_path: &Path) -> CertificateCheck {
+    let mut check = CertificateCheck::default();
+
+    let der = match read_first_certificate(cert_path) {
+        Ok(der) => der,
+        Err(e) => {
+            check.errors.push(e);
+            return check;
+        }
+    };
+    check.fingerprint = Some(calculate_sha256_fingerprint(&der));
+
+    let now = Utc::now();
+    match crate::server::certificate_validity(&der) {
+        Some((not_before, _)) if now < not_before => check
+            .errors
+            .push(format!("Not valid before {}", format_datetime(not_before))),
+        Some((_, not_after)) if now > not_after => check
+            .errors
+            .push(format!("Expired on {}", format_datetime(not_after))),
+        Some((_, not_after)) => {
+            let days_left = (not_after - now).num_days();
+            if days_left < crate::server::CERT_EXPIRY_WARNING_DAYS {
+                check
+                    .warnings
+                    .push(format!("Expires in {} days - replace it soon", days_left));
+            }
+            check.valid_until = Some(format_datetime(not_after));
+        }
+        None => check
+            .warnings
+            .push("The validity period could not be read".to_string()),
+    }
+
+    if let Err(e) = crate::security::TlsConfig::from_files(cert_path, key_path) {
+        check
+            .errors
+            .push(format!("Private key check failed: {:#}", e));
+    }
+
+    check
+}
+
+/// DER of the first certificate in a PEM file
+fn read_first_certificate(cert_path: &Path) -> Result<Vec<u8>, String> {
+    let pem = fs::read(cert_path)
+        .map_err(|e| format!("Failed to read certificate {}: {}", cert_path.display(), e))?;
+    rustls_pemfile::certs(&mut pem.as_slice())
+        .next()
+        .ok_or_else(|| "File does not contain a PEM certificate".to_string())?
+        .map(|cert| cert.to_vec())
+        .map_err(|e| format!("Failed to parse certificate: {}", e))
+}
+
+fn format_datetime(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
 /// Simple base64 decoder
 fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
     const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
@@ -2,10 +2,11 @@
 //!
 //! Handles loading, saving, and exporting configuration files.
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::config::Config;
+use crate::config::{migrate, presets, Config};
 
 /// Default configuration file paths
 pub const DEFAULT_CONFIG_PATHS: &[&str] = &[
@@ -43,12 +44,21 @@ pub fn save_config(config: &Config, path: &Path) -> Result<(), String> {
 }
 
 /// Load configuration from a TOML file
+///
+/// Files of older versions are migrated, so saving writes them back in
+/// the current format. The settings of a selected preset are filled in and
+/// saved as explicit values.
 pub fn load_config(path: &Path) -> Result<Config, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
 
-    let config: Config =
+    let mut document: toml::Table =
         toml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))?;
+    migrate::migrate(&mut document).map_err(|e| format!("Failed to migrate config file: {}", e))?;
+    presets::apply(&mut document).map_err(|e| format!("Invalid config file: {}", e))?;
+    let config: Config = toml::Value::Table(document)
+        .try_into()
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
 
     Ok(config)
 }
@@ -200,156 +210,51 @@ pub fn validate_toml_syntax(path: &Path) -> Result<(), String> {
 }
 
 /// Compare two configs and return list of differences
+///
+/// Every setting is compared, as `key: old -> new` with dotted TOML keys.
 pub fn diff_configs(a: &Config, b: &Config) -> Vec<String> {
-    let mut differences = Vec::new();
-
-    // Server section
-    if a.server.listen_addr != b.server.listen_addr {
-        differences.push(format!(
-            "server.listen_addr: '{}' -> '{}'",
-            a.server.listen_addr, b.server.listen_addr
-        ));
-    }
-    if a.server.max_connections != b.server.max_connections {
-        differences.push(format!(
-            "server.max_connections: {} -> {}",
-            a.server.max_connections, b.server.max_connections
-        ));
-    }
-    if a.server.session_timeout != b.server.session_timeout {
-        differences.push(format!(
-            "server.session_timeout: {} -> {}",
-            a.server.session_timeout, b.server.session_timeout
-        ));
-    }
-    if a.server.use_portals != b.server.use_portals {
-        differences.push(format!(
-            "server.use_portals: {} -> {}",
-            a.server.use_portals, b.server.use_portals
-        ));
-    }
-
-    // Security section
-    if a.security.cert_path != b.security.cert_path {
-        differences.push(format!(
-            "security.cert_path: {:?} -> {:?}",
-            a.security.cert_path, b.security.cert_path
-        ));
-    }
-    if a.security.key_path != b.security.key_path {
-        differences.push(format!(
-            "security.key_path: {:?} -> {:?}",
-            a.security.key_path, b.security.key_path
-        ));
-    }
-    if a.security.enable_nla != b.security.enable_nla {
-        differences.push(format!(
-            "security.enable_nla: {} -> {}",
-            a.security.enable_nla, b.security.enable_nla
-        ));
-    }
-    if a.security.auth_method != b.security.auth_method {
-        differences.push(format!(
-            "security.auth_method: '{}' -> '{}'",
-            a.security.auth_method, b.security.auth_method
-        ));
-    }
-    if a.security.require_tls_13 != b.security.require_tls_13 {
-        differences.push(format!(
-            "security.require_tls_13: {} -> {}",
-            a.security.require_tls_13, b.security.require_tls_13
-        ));
-    }
-
-    // Video section
-    if a.video.encoder != b.video.encoder {
-        differences.push(format!(
-            "video.encoder: '{}' -> '{}'",
-            a.video.encoder, b.video.encoder
-        ));
-    }
-    if a.video.target_fps != b.video.target_fps {
-        differences.push(format!(
-            "video.target_fps: {} -> {}",
-            a.video.target_fps, b.video.target_fps
-        ));
-    }
-    if a.video.bitrate != b.video.bitrate {
-        differences.push(format!(
-            "video.bitrate: {} -> {}",
-            a.video.bitrate, b.video.bitrate
-        ));
-    }
-    if a.video.damage_tracking != b.video.damage_tracking {
-        differences.push(format!(
-            "video.damage_tracking: {} -> {}",
-            a.video.damage_tracking, b.video.damage_tracking
-        ));
-    }
-
-    // EGFX section
-    if a.egfx.enabled != b.egfx.enabled {
-        differences.push(format!(
-            "egfx.enabled: {} -> {}",
-            a.egfx.enabled, b.egfx.enabled
-        ));
-    }
-    if a.egfx.h264_bitrate != b.egfx.h264_bitrate {
-        differences.push(format!(
-            "egfx.h264_bitrate: {} -> {}",
-            a.egfx.h264_bitrate, b.egfx.h264_bitrate
-        ));
-    }
-    if a.egfx.codec != b.egfx.codec {
-        differences.push(format!(
-            "egfx.codec: '{}' -> '{}'",
-            a.egfx.codec, b.egfx.codec
-        ));
-    }
-
-    // Clipboard section
-    if a.clipboard.enabled != b.clipboard.enabled {
-        differences.push(format!(
-            "clipboard.enabled: {} -> {}",
-            a.clipboard.enabled, b.clipboard.enabled
-        ));
-    }
-    if a.clipboard.max_size != b.clipboard.max_size {
-        differences.push(format!(
-            "clipboard.max_size: {} -> {}",
-            a.clipboard.max_size, b.clipboard.max_size
-        ));
-    }
-
-    // Performance section
-    if a.performance.encoder_threads != b.performance.encoder_threads {
-        differences.push(format!(
-            "performance.encoder_threads: {} -> {}",
-            a.performance.encoder_threads, b.performance.encoder_threads
-        ));
-    }
-    if a.performance.zero_copy != b.performance.zero_copy {
-        differences.push(format!(
-            "performance.zero_copy: {} -> {}",
-            a.performance.zero_copy, b.performance.zero_copy
-        ));
-    }
+    let (Ok(a), Ok(b)) = (toml::Value::try_from(a), toml::Value::try_from(b)) else {
+        return Vec::new();
+    };
+    let mut a_settings = BTreeMap::new();
+    flatten_settings("", &a, &mut a_settings);
+    let mut b_settings = BTreeMap::new();
+    flatten_settings("", &b, &mut b_settings);
+
+    let keys: BTreeSet<&String> = a_settings.keys().chain(b_settings.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = a_settings.get(key);
+            let new = b_settings.get(key);
+            (old != new).then(|| {
+                format!(
+                    "{}: {} -> {}",
+                    key,
+                    old.map_or("(unset)", String::as_str),
+                    new.map_or("(unset)", String::as_str)
+                )
+            })
+        })
+        .collect()
+}
 
-    // Logging section
-    if a.logging.level != b.logging.level {
-        differences.push(format!(
-            "logging.level: '{}' -> '{}'",
-            a.logging.level, b.logging.level
-        ));
-    }
-    if a.logging.metrics != b.logging.metrics {
-        differences.push(format!(
-            "logging.metrics: {} -> {}",
-            a.logging.metrics, b.logging.metrics
-        ));
+/// Collect the leaf values of a TOML table under dotted keys
+fn flatten_settings(prefix: &str, value: &toml::Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_settings(&key, value, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
     }
-
-    differences
 }
 
 #[cfg(test)]
@@ -369,6 +274,20 @@ mod tests {
         assert_eq!(config.server.listen_addr, loaded.server.listen_addr);
     }
 
+    #[test]
+    fn test_diff_configs() {
+        let a = Config::default();
+        let mut b = a.clone();
+        assert!(diff_configs(&a, &b).is_empty());
+
+        b.server.listen_addr = "127.0.0.1:3390".to_string();
+        b.clipboard.enabled = !a.clipboard.enabled;
+        let diff = diff_configs(&a, &b);
+        assert_eq!(diff.len(), 2);
+        assert!(diff[0].starts_with("clipboard.enabled: "));
+        assert!(diff[1].ends_with(r#"-> "127.0.0.1:3390""#));
+    }
+
     #[test]
     fn test_expand_path() {
         let expanded = expand_path("~/.config/test");
@@ -0,0 +1,128 @@
+//! Log File Tail
+//!
+//! Follows the server's log file (`--log-file`) for the log viewer in the
+//! Status tab. A file that shrinks is read again from the start, which is
+//! what happens when a restarted server truncates it.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// How much of an existing file to show when following starts
+const INITIAL_BYTES: u64 = 64 * 1024;
+
+/// Upper bound for one read, so a burst of output cannot stall the UI
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// Position in a followed log file
+#[derive(Debug, Clone)]
+pub struct LogTail {
+    path: PathBuf,
+    offset: Option<u64>,
+    /// Start of a line whose newline has not been written yet
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Follow `path`, starting with the end of its current content
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            offset: None,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Complete lines written since the last read
+    pub fn read_new_lines(&mut self) -> Result<Vec<String>, String> {
+        let mut file = File::open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?
+            .len();
+
+        let offset = match self.offset {
+            Some(offset) if offset <= len => offset,
+            // Truncated: the server started over
+            Some(_) => {
+                self.partial.clear();
+                0
+            }
+            None => len.saturating_sub(INITIAL_BYTES),
+        };
+        let skip_first_line = self.offset.is_none() && offset > 0;
+
+        let mut buf = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.take(MAX_READ_BYTES).read_to_end(&mut buf))
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        self.offset = Some(offset + buf.len() as u64);
+
+        self.partial.extend_from_slice(&buf);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+
+        let mut lines = String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        // Reading started mid-file: the first line is cut off
+        if skip_first_line && !lines.is_empty() {
+            lines.remove(0);
+        }
+        lines.retain(|line| !line.trim().is_empty());
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::gui::state::{LogLevel, LogLine};
+
+    #[test]
+    fn test_tail_follows_appends_and_truncation() {
+        let path = std::env::temp_dir().join(format!("lamco-log-tail-{}.log", std::process::id()));
+        std::fs::write(&path, "first\n").unwrap();
+
+        let mut tail = LogTail::new(path.clone());
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["first"]);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"second\nthi").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["second"]);
+        file.write_all(b"rd\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["third"]);
+        assert!(tail.read_new_lines().unwrap().is_empty());
+
+        // Restarted server truncates the file
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["new"]);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(tail.read_new_lines().is_err());
+    }
+
+    #[test]
+    fn test_parse_tracing_line() {
+        let line = LogLine::parse(
+            "2026-01-19T14:23:45.123456Z  WARN session{session_id=3}: lamco_rdp_server::egfx::encoder: Frame dropped",
+        );
+        assert_eq!(line.timestamp, "14:23:45");
+        assert_eq!(line.level, LogLevel::Warn);
+        assert_eq!(line.target, "lamco_rdp_server::egfx::encoder");
+        assert_eq!(line.message, "Frame dropped");
+
+        let line = LogLine::parse("2026-01-19 14:23:45 [ERROR] Server failed");
+        assert_eq!(line.level, LogLevel::Error);
+        assert_eq!(line.message, "Server failed");
+    }
+}
@@ -13,6 +13,8 @@ fn main() -> iced::Result {
         .window_size(Size::new(1200.0, 800.0))
         .centered()
         .antialiasing(true)
+        .theme(ConfigGuiApp::theme)
+        .scale_factor(ConfigGuiApp::scale_factor)
         .subscription(ConfigGuiApp::subscription)
         .run()
 }
@@ -5,6 +5,7 @@
 use std::path::PathBuf;
 
 use crate::config::Config;
+use crate::gui::monitor_layout::DetectedMonitor;
 use crate::gui::state::{DetectedCapabilities, GpuInfo, ServerStatus, Tab, ValidationResult};
 
 /// Main application message type
@@ -17,7 +18,7 @@ pub enum Message {
     TabSelected(Tab),
 
     // =========================================================================
-    // Server Configuration (4 fields)
+    // Server Configuration (5 fields)
     // =========================================================================
     /// Listen address IP changed
     ServerListenAddrChanged(String),
@@ -29,6 +30,8 @@ pub enum Message {
     ServerSessionTimeoutChanged(String),
     /// Use XDG Portals toggled
     ServerUsePortalsToggled(bool),
+    /// Built-in config preset selected ("none" to clear)
+    ServerPresetChanged(String),
 
     // =========================================================================
     // Security Configuration (5 fields)
@@ -59,6 +62,10 @@ pub enum Message {
     CertGenCancel,
     /// Certificate generation completed
     CertGenCompleted(Result<(), String>),
+    /// Check the configured certificate and key
+    SecurityCheckCert,
+    /// Certificate check completed
+    SecurityCertChecked(crate::gui::certificates::CertificateCheck),
     /// Enable NLA toggled
     SecurityEnableNlaToggled(bool),
     /// Auth method changed
@@ -116,7 +123,7 @@ pub enum Message {
     ConverterEnableStatisticsToggled(bool),
 
     // =========================================================================
-    // Input Configuration (3 fields)
+    // Input Configuration (4 fields)
     // =========================================================================
     /// Use libei toggled
     InputUseLibeiToggled(bool),
@@ -124,6 +131,8 @@ pub enum Message {
     InputKeyboardLayoutChanged(String),
     /// Enable touch toggled
     InputEnableTouchToggled(bool),
+    /// Input fallback chain changed (comma-separated)
+    InputFallbackChainChanged(String),
 
     // =========================================================================
     // Clipboard Configuration (4 fields)
@@ -140,12 +149,32 @@ pub enum Message {
     ClipboardPresetSelected(ClipboardPreset),
 
     // =========================================================================
-    // Multi-Monitor Configuration (2 fields)
+    // Multi-Monitor Configuration (3 fields)
     // =========================================================================
     /// Multi-monitor enabled toggled
     MultimonEnabledToggled(bool),
     /// Max monitors changed
     MultimonMaxMonitorsChanged(String),
+    /// Multi-monitor section expanded/collapsed
+    MultimonToggleExpanded,
+    /// Detect the monitors of the local desktop
+    DetectMonitors,
+    /// Monitor detection finished
+    MonitorsDetected(Result<Vec<DetectedMonitor>, String>),
+    /// Detected monitor `index` dragged to a position in the RDP virtual desktop
+    MonitorLayoutMoved {
+        index: usize,
+        x: i32,
+        y: i32,
+    },
+    /// Detected monitor dropped (snaps to its neighbours)
+    MonitorLayoutDropped(usize),
+    /// Detected monitor shared or left out
+    MonitorSharedToggled(usize, bool),
+    /// Capture rate of a detected monitor selected
+    MonitorFpsSelected(usize, String),
+    /// Forget the layout: share every monitor where the compositor has it
+    MonitorLayoutReset,
 
     // =========================================================================
     // Performance Configuration (6 fields + 2 sub-structs = 18 fields total)
@@ -203,9 +232,21 @@ pub enum Message {
     /// Toggle expert mode
     EgfxToggleExpertMode,
 
+    // Bandwidth & quality preview
+    PreviewToggleExpanded,
+    /// Resolution to estimate and render at
+    PreviewResolutionSelected(String),
+    /// Encode the sample image with the current settings
+    RenderPreview,
+    /// Sample encoded
+    PreviewRendered(Result<crate::gui::preview::PreviewSample, String>),
+
     // Basic EGFX
     EgfxH264LevelChanged(String),
     EgfxH264BitrateChanged(String),
+    EgfxAdaptiveBitrateToggled(bool),
+    EgfxSmallUpdateFastPathToggled(bool),
+    EgfxDecodeDowngradeToggled(bool),
     EgfxZgfxCompressionChanged(String),
     EgfxMaxFramesInFlightChanged(String),
     EgfxFrameAckTimeoutChanged(String),
@@ -290,8 +331,12 @@ pub enum Message {
 
     CursorModeChanged(String),
     CursorAutoModeToggled(bool),
+    CursorColorPointersToggled(bool),
+    CursorDirectTouchToggled(bool),
+    CursorDirectPenToggled(bool),
     CursorPredictiveThresholdChanged(String),
     CursorUpdateFpsChanged(String),
+    CursorDecoupleBelowFpsChanged(String),
 
     // Predictor config
     PredictorHistorySizeChanged(String),
@@ -301,6 +346,10 @@ pub enum Message {
     PredictorMaxPredictionDistanceChanged(String),
     PredictorMinVelocityThresholdChanged(String),
     PredictorStopConvergenceRateChanged(f32),
+    PredictorModelChanged(String),
+    PredictorLookaheadRttFractionChanged(f32),
+    PredictorMaxLookaheadMsChanged(String),
+    PredictorSnapBackDistanceChanged(String),
 
     // =========================================================================
     // File Operations
@@ -320,6 +369,30 @@ pub enum Message {
     /// Configuration saved
     ConfigSaved(Result<(), String>),
 
+    // =========================================================================
+    // Configuration Profiles
+    // =========================================================================
+    /// Profiles section expanded/collapsed
+    ProfilesToggleExpanded,
+    /// Profile picked from the list
+    ProfileSelected(String),
+    /// Load the selected profile
+    LoadProfile,
+    /// Profile loaded
+    ProfileLoaded(Result<Config, String>),
+    /// Delete the selected profile
+    DeleteProfile,
+    /// New profile name changed
+    ProfileNameChanged(String),
+    /// Save the current configuration as a profile
+    SaveProfile,
+    /// Profile saved (its name)
+    ProfileSaved(Result<String, String>),
+    /// Compare the current configuration with the server's file
+    CompareWithRunningConfig,
+    /// Comparison done
+    ConfigDiffComputed(Result<Vec<String>, String>),
+
     // =========================================================================
     // Server Control
     // =========================================================================
@@ -329,8 +402,36 @@ pub enum Message {
     StopServer,
     /// Restart server
     RestartServer,
+    /// Save configuration and hot-reload it in the running server
+    ReloadServerConfig,
+    /// Configuration reload requested
+    ServerConfigReloaded(Result<crate::gui::server_control::ApplyOutcome, String>),
     /// Server status updated (from IPC)
     ServerStatusUpdated(ServerStatus),
+    /// Status and clients polled from the admin API
+    LiveStatusUpdated(Result<crate::gui::server_control::LiveStatus, String>),
+    /// Disconnect a client of the running server
+    DisconnectSession(u64),
+    /// Client disconnect finished
+    SessionDisconnected(Result<u64, String>),
+    /// Pause or resume a client's video, input and clipboard
+    SetSharingPaused {
+        id: u64,
+        paused: bool,
+    },
+    /// Pause or resume finished, with the client ID and new state
+    SharingPausedSet(Result<(u64, bool), String>),
+    /// Choose between the user and the system systemd unit
+    ServiceScopeSelected(crate::gui::service::ServiceScope),
+    /// Install, enable, disable, start, stop or restart the systemd unit
+    ServiceActionRequested(crate::gui::service::ServiceAction),
+    /// systemd action finished
+    ServiceActionCompleted(
+        crate::gui::service::ServiceAction,
+        Result<crate::gui::service::UnitStatus, String>,
+    ),
+    /// systemd unit state polled
+    ServiceStatusUpdated(Result<crate::gui::service::UnitStatus, String>),
 
     // =========================================================================
     // Validation
@@ -352,6 +453,38 @@ pub enum Message {
     /// Capabilities exported
     CapabilitiesExported(Result<PathBuf, String>),
 
+    // =========================================================================
+    // Setup Wizard
+    // =========================================================================
+    /// Open the first-run setup wizard
+    OpenSetupWizard,
+    /// Close the wizard, keeping the changes made so far
+    CloseSetupWizard,
+    /// Go to the next wizard step
+    WizardNext,
+    /// Go back one wizard step
+    WizardBack,
+    /// Use the Mutter/KWin capture APIs when available instead of the portal
+    WizardNativeCaptureToggled(bool),
+    /// Show the portal permission dialog and store the restore token
+    WizardRequestPermissions,
+    /// Permission setup finished
+    WizardPermissionsCompleted(Result<crate::server::HealthReport, String>),
+    /// Generate a self-signed certificate at the configured paths
+    WizardGenerateCertificate,
+    /// Save the configuration, then optionally start the server
+    WizardFinish {
+        start_server: bool,
+    },
+
+    // =========================================================================
+    // Diagnostics
+    // =========================================================================
+    /// Run the health checks against the current configuration
+    RunDiagnostics,
+    /// Health checks completed
+    DiagnosticsCompleted(crate::server::HealthReport),
+
     // =========================================================================
     // Log Viewer
     // =========================================================================
@@ -363,6 +496,16 @@ pub enum Message {
     ToggleLogAutoScroll,
     /// Log filter level changed
     LogFilterLevelChanged(String),
+    /// Module filter changed ("All" shows every module)
+    LogModuleFilterChanged(String),
+    /// Search text changed
+    LogSearchChanged(String),
+    /// Followed log file path changed
+    LogFilePathChanged(String),
+    /// Browse for the log file to follow
+    BrowseLogFile,
+    /// Log file selected
+    LogFileSelected(Option<PathBuf>),
     /// Export logs to file
     ExportLogs,
 
@@ -385,6 +528,12 @@ pub enum Message {
     ConfirmDiscardChanges,
     /// Cancel discard changes dialog
     CancelDiscardChanges,
+    /// Theme selected (saved to the GUI preferences)
+    ThemeModeSelected(crate::gui::theme::ThemeMode),
+    /// UI scale selected, in percent (saved to the GUI preferences)
+    UiScaleSelected(u32),
+    /// Desktop color-scheme preference read (true: dark)
+    SystemColorSchemeDetected(Result<bool, String>),
 
     // =========================================================================
     // Tick / Async Updates
@@ -9,7 +9,15 @@ pub mod capabilities;
 pub mod certificates;
 pub mod file_ops;
 pub mod hardware;
+pub mod log_tail;
 pub mod message;
+pub mod monitor_layout;
+pub mod preferences;
+pub mod preview;
+pub mod profiles;
+pub mod server_control;
+pub mod service;
+pub mod session_history;
 pub mod state;
 pub mod tabs;
 pub mod theme;
@@ -0,0 +1,449 @@
+//! Multi-Monitor Layout Editor
+//!
+//! Detects the monitors of the local desktop and lets them be arranged in
+//! the RDP virtual desktop by dragging, shared or left out, and captured at
+//! their own frame rate. The result is written to `multimon.monitors`; a
+//! monitor gets an entry there once it is edited, until then the server
+//! shares it where the compositor has it.
+//!
+//! Monitors are identified by their position on the local desktop, the one
+//! thing every capture source reports; connector names are shown where the
+//! compositor provides them (wl_output version 4).
+
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{mouse, Color, Point, Rectangle, Renderer, Size, Theme, Vector};
+
+use crate::config::types::MonitorLayoutConfig;
+use crate::gui::message::Message;
+use crate::gui::theme::Palette;
+
+/// Distance (desktop pixels) within which a dropped monitor snaps to the
+/// edges of another
+const SNAP_DISTANCE: i32 = 96;
+
+/// Margin around the arrangement in the editor, in canvas pixels
+const CANVAS_PADDING: f32 = 16.0;
+
+/// A monitor of the local desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedMonitor {
+    /// Connector name ("DP-1"), empty when the compositor doesn't say
+    pub name: String,
+    pub desktop_x: i32,
+    pub desktop_y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DetectedMonitor {
+    /// Name to show: the connector, else the position in the list
+    pub fn label(&self, index: usize) -> String {
+        if self.name.is_empty() {
+            format!("Monitor {}", index + 1)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Index of this monitor's entry in `layout`
+    pub fn entry(&self, layout: &[MonitorLayoutConfig]) -> Option<usize> {
+        layout
+            .iter()
+            .position(|m| (m.desktop_x, m.desktop_y) == (self.desktop_x, self.desktop_y))
+    }
+
+    /// Index of this monitor's entry in `layout`, added if missing
+    pub fn entry_mut(&self, layout: &mut Vec<MonitorLayoutConfig>) -> usize {
+        if let Some(index) = self.entry(layout) {
+            layout[index].name.clone_from(&self.name);
+            return index;
+        }
+        layout.push(MonitorLayoutConfig {
+            name: self.name.clone(),
+            desktop_x: self.desktop_x,
+            desktop_y: self.desktop_y,
+            shared: true,
+            x: self.desktop_x,
+            y: self.desktop_y,
+            fps: 0,
+        });
+        layout.len() - 1
+    }
+
+    /// Where the layout puts this monitor, and whether it's shared
+    pub fn placement(&self, layout: &[MonitorLayoutConfig]) -> (i32, i32, bool) {
+        match self.entry(layout).map(|i| &layout[i]) {
+            Some(m) => (m.x, m.y, m.shared),
+            None => (self.desktop_x, self.desktop_y, true),
+        }
+    }
+}
+
+/// Monitors of the local desktop, from the compositor's wl_output globals
+pub async fn detect_monitors() -> Result<Vec<DetectedMonitor>, String> {
+    #[cfg(feature = "wayland")]
+    {
+        use crate::session::strategies::wlr_direct::screencopy;
+
+        tokio::task::spawn_blocking(|| {
+            let conn = wayland_client::Connection::connect_to_env()
+                .map_err(|e| format!("Failed to connect to the Wayland display: {}", e))?;
+            let outputs = screencopy::named_outputs(&conn)
+                .map_err(|e| format!("Failed to list monitors: {:#}", e))?;
+            Ok(outputs
+                .into_iter()
+                .map(|(name, stream)| DetectedMonitor {
+                    name,
+                    desktop_x: stream.position_x,
+                    desktop_y: stream.position_y,
+                    width: stream.width,
+                    height: stream.height,
+                })
+                .collect())
+        })
+        .await
+        .map_err(|e| format!("Monitor detection failed: {}", e))?
+    }
+    #[cfg(not(feature = "wayland"))]
+    {
+        Err("Monitor detection requires the wayland feature".to_string())
+    }
+}
+
+/// Position for monitor `index` dropped at `position`
+///
+/// Edges within [`SNAP_DISTANCE`] of another shared monitor's edges are
+/// pulled onto them, so that monitors end up side by side rather than
+/// with gaps or overlaps of a few pixels.
+pub fn snap_position(rects: &[Rect], index: usize, position: (i32, i32)) -> (i32, i32) {
+    let Some(moving) = rects.get(index) else {
+        return position;
+    };
+    let others: Vec<&Rect> = rects
+        .iter()
+        .enumerate()
+        .filter(|&(i, r)| i != index && r.shared)
+        .map(|(_, r)| r)
+        .collect();
+    let nearest = |value: i32, size: i32, edges: Vec<(i32, i32)>| {
+        edges
+            .into_iter()
+            // Align either edge with either edge of the other monitor
+            .flat_map(|(start, end)| [start, end, start - size, end - size])
+            .min_by_key(|candidate| (candidate - value).abs())
+            .filter(|candidate| (candidate - value).abs() <= SNAP_DISTANCE)
+            .unwrap_or(value)
+    };
+    let x = nearest(
+        position.0,
+        moving.width as i32,
+        others.iter().map(|r| (r.x, r.x + r.width as i32)).collect(),
+    );
+    let y = nearest(
+        position.1,
+        moving.height as i32,
+        others
+            .iter()
+            .map(|r| (r.y, r.y + r.height as i32))
+            .collect(),
+    );
+    (x, y)
+}
+
+/// A monitor as placed in the RDP virtual desktop
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rect {
+    pub label: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub shared: bool,
+    /// Capture rate (0 = pipeline rate)
+    pub fps: u32,
+}
+
+impl Rect {
+    /// Monitors as `layout` places them
+    pub fn from_layout(detected: &[DetectedMonitor], layout: &[MonitorLayoutConfig]) -> Vec<Rect> {
+        detected
+            .iter()
+            .enumerate()
+            .map(|(i, monitor)| {
+                let (x, y, shared) = monitor.placement(layout);
+                Rect {
+                    label: monitor.label(i),
+                    x,
+                    y,
+                    width: monitor.width,
+                    height: monitor.height,
+                    shared,
+                    fps: monitor.entry(layout).map_or(0, |entry| layout[entry].fps),
+                }
+            })
+            .collect()
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        point.x >= self.x as f32
+            && point.y >= self.y as f32
+            && point.x < (self.x + self.width as i32) as f32
+            && point.y < (self.y + self.height as i32) as f32
+    }
+}
+
+/// Drag-and-drop view of the arrangement
+pub struct LayoutEditor {
+    pub rects: Vec<Rect>,
+}
+
+/// Mapping between canvas and desktop coordinates
+#[derive(Debug, Clone, Copy)]
+struct View {
+    /// Desktop point shown at the canvas origin
+    origin: Point,
+    /// Canvas pixels per desktop pixel
+    scale: f32,
+}
+
+impl View {
+    /// View fitting every monitor into `size`
+    fn fit(rects: &[Rect], size: Size) -> Self {
+        let min_x = rects.iter().map(|r| r.x).min().unwrap_or(0) as f32;
+        let min_y = rects.iter().map(|r| r.y).min().unwrap_or(0) as f32;
+        let max_x = rects
+            .iter()
+            .map(|r| r.x + r.width as i32)
+            .max()
+            .unwrap_or(1920) as f32;
+        let max_y = rects
+            .iter()
+            .map(|r| r.y + r.height as i32)
+            .max()
+            .unwrap_or(1080) as f32;
+
+        let (width, height) = ((max_x - min_x).max(1.0), (max_y - min_y).max(1.0));
+        let scale = ((size.width - 2.0 * CANVAS_PADDING) / width)
+            .min((size.height - 2.0 * CANVAS_PADDING) / height)
+            .max(0.01);
+
+        // Center the arrangement
+        let margin_x = (size.width - width * scale) / 2.0;
+        let margin_y = (size.height - height * scale) / 2.0;
+        Self {
+            origin: Point::new(min_x - margin_x / scale, min_y - margin_y / scale),
+            scale,
+        }
+    }
+
+    fn to_desktop(self, point: Point) -> Point {
+        Point::new(
+            self.origin.x + point.x / self.scale,
+            self.origin.y + point.y / self.scale,
+        )
+    }
+
+    fn to_canvas(self, x: i32, y: i32) -> Point {
+        Point::new(
+            (x as f32 - self.origin.x) * self.scale,
+            (y as f32 - self.origin.y) * self.scale,
+        )
+    }
+}
+
+/// Monitor being dragged
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    index: usize,
+    /// Cursor position relative to the monitor's corner, in desktop pixels
+    grab: Vector,
+    /// The view when the drag started, kept so the arrangement doesn't
+    /// rescale under the cursor
+    view: View,
+}
+
+#[derive(Debug, Default)]
+pub struct EditorState {
+    drag: Option<Drag>,
+}
+
+impl LayoutEditor {
+    fn view(&self, state: &EditorState, bounds: Rectangle) -> View {
+        state
+            .drag
+            .map_or_else(|| View::fit(&self.rects, bounds.size()), |drag| drag.view)
+    }
+
+    /// Monitor under `point` (desktop coordinates), topmost first
+    fn hit(&self, point: Point) -> Option<usize> {
+        self.rects.iter().rposition(|r| r.contains(point))
+    }
+}
+
+impl canvas::Program<Message> for LayoutEditor {
+    type State = EditorState;
+
+    fn update(
+        &self,
+        state: &mut EditorState,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let view = View::fit(&self.rects, bounds.size());
+                let point = view.to_desktop(cursor.position_in(bounds)?);
+                let index = self.hit(point)?;
+                let rect = &self.rects[index];
+                state.drag = Some(Drag {
+                    index,
+                    grab: point - Point::new(rect.x as f32, rect.y as f32),
+                    view,
+                });
+                Some(canvas::Action::capture())
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let drag = state.drag?;
+                // Keeps following the cursor outside the canvas
+                let position = Point::ORIGIN + (cursor.position()? - bounds.position());
+                let corner = drag.view.to_desktop(position) - drag.grab;
+                Some(
+                    canvas::Action::publish(Message::MonitorLayoutMoved {
+                        index: drag.index,
+                        x: corner.x.round() as i32,
+                        y: corner.y.round() as i32,
+                    })
+                    .and_capture(),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let drag = state.drag.take()?;
+                Some(
+                    canvas::Action::publish(Message::MonitorLayoutDropped(drag.index))
+                        .and_capture(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &EditorState,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let palette = Palette::of(theme);
+        let view = self.view(state, bounds);
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for (index, rect) in self.rects.iter().enumerate() {
+            let top_left = view.to_canvas(rect.x, rect.y);
+            let size = Size::new(
+                rect.width as f32 * view.scale,
+                rect.height as f32 * view.scale,
+            );
+            let dragged = state.drag.is_some_and(|drag| drag.index == index);
+
+            let color = if rect.shared {
+                palette.primary
+            } else {
+                palette.text_muted
+            };
+            frame.fill_rectangle(
+                top_left,
+                size,
+                Color {
+                    a: if dragged { 0.5 } else { 0.25 },
+                    ..color
+                },
+            );
+            frame.stroke(
+                &Path::rectangle(top_left, size),
+                Stroke::default()
+                    .with_color(color)
+                    .with_width(if dragged { 3.0 } else { 2.0 }),
+            );
+
+            let details = match (rect.shared, rect.fps) {
+                (false, _) => "not shared".to_string(),
+                (true, 0) => format!("{}x{}", rect.width, rect.height),
+                (true, fps) => format!("{}x{} @ {} fps", rect.width, rect.height, fps),
+            };
+            for (line, content) in [rect.label.clone(), details].into_iter().enumerate() {
+                frame.fill_text(canvas::Text {
+                    content,
+                    position: top_left + Vector::new(8.0, 6.0 + line as f32 * 18.0),
+                    color: palette.text_secondary,
+                    size: 14.0.into(),
+                    ..canvas::Text::default()
+                });
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &EditorState,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.drag.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        let view = View::fit(&self.rects, bounds.size());
+        match cursor.position_in(bounds) {
+            Some(point) if self.hit(view.to_desktop(point)).is_some() => mouse::Interaction::Grab,
+            _ => mouse::Interaction::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, x: i32) -> DetectedMonitor {
+        DetectedMonitor {
+            name: name.to_string(),
+            desktop_x: x,
+            desktop_y: 0,
+            width: 1920,
+            height: 1080,
+        }
+    }
+
+    #[test]
+    fn test_entries_created_on_edit() {
+        let detected = vec![monitor("DP-1", 0), monitor("", 1920)];
+        let mut layout = Vec::new();
+
+        assert_eq!(detected[1].placement(&layout), (1920, 0, true));
+        assert_eq!(detected[1].label(1), "Monitor 2");
+
+        let index = detected[1].entry_mut(&mut layout);
+        layout[index].shared = false;
+        assert_eq!(detected[1].entry_mut(&mut layout), index);
+        assert_eq!(layout.len(), 1);
+        assert_eq!(detected[1].placement(&layout), (1920, 0, false));
+        assert_eq!(detected[0].entry(&layout), None);
+    }
+
+    #[test]
+    fn test_snap_position() {
+        let detected = vec![monitor("DP-1", 0), monitor("DP-2", 1920)];
+        let rects = Rect::from_layout(&detected, &[]);
+
+        // Dropped slightly overlapping the right edge of DP-1, a bit low
+        assert_eq!(snap_position(&rects, 1, (1900, 30)), (1920, 0));
+        // Dropped below DP-1
+        assert_eq!(snap_position(&rects, 1, (40, 1100)), (0, 1080));
+        // Far from any edge: stays
+        assert_eq!(snap_position(&rects, 1, (2400, 500)), (2400, 500));
+    }
+}
@@ -0,0 +1,132 @@
+//! GUI Preferences
+//!
+//! Settings of the configuration GUI itself, as opposed to the server's
+//! configuration: the color theme and the UI scale. Stored in
+//! `~/.config/lamco-rdp-server/gui.toml`, next to the user's config file.
+//!
+//! In [`ThemeMode::System`] the theme follows the desktop's color-scheme
+//! preference, read from the XDG Settings portal.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gui::file_ops;
+use crate::gui::theme::ThemeMode;
+
+/// UI scales offered for HiDPI displays, in percent
+pub const UI_SCALES: &[u32] = &[75, 90, 100, 110, 125, 150, 175, 200];
+
+/// Preferences of the configuration GUI
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuiPreferences {
+    /// Color theme, or the desktop's preference
+    pub theme: ThemeMode,
+    /// UI scale in percent, on top of the display's own scale factor
+    pub scale_percent: u32,
+}
+
+impl Default for GuiPreferences {
+    fn default() -> Self {
+        Self {
+            theme: ThemeMode::System,
+            scale_percent: 100,
+        }
+    }
+}
+
+impl GuiPreferences {
+    /// Scale factor for iced, limited to the offered range
+    pub fn scale_factor(&self) -> f64 {
+        f64::from(
+            self.scale_percent
+                .clamp(UI_SCALES[0], UI_SCALES[UI_SCALES.len() - 1]),
+        ) / 100.0
+    }
+
+    /// Load the preferences of this user, defaults if there are none or
+    /// they can't be read
+    pub fn load() -> Self {
+        Self::load_from(&preferences_path()).unwrap_or_default()
+    }
+
+    /// Save the preferences of this user
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&preferences_path())
+    }
+
+    fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize preferences: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// File holding the preferences of this user
+pub fn preferences_path() -> PathBuf {
+    file_ops::get_user_config_path().with_file_name("gui.toml")
+}
+
+/// Whether the desktop prefers a dark color scheme
+///
+/// Desktops without the Settings portal, or without a preference, count
+/// as light.
+pub async fn system_prefers_dark() -> Result<bool, String> {
+    use ashpd::desktop::settings::{ColorScheme, Settings};
+
+    let settings = Settings::new()
+        .await
+        .map_err(|e| format!("Settings portal unavailable: {}", e))?;
+    let scheme = settings
+        .color_scheme()
+        .await
+        .map_err(|e| format!("Failed to read the color scheme: {}", e))?;
+    Ok(scheme == ColorScheme::PreferDark)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_preferences_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gui.toml");
+        assert_eq!(
+            GuiPreferences::load_from(&path).unwrap(),
+            GuiPreferences::default()
+        );
+
+        let preferences = GuiPreferences {
+            theme: ThemeMode::Dark,
+            scale_percent: 150,
+        };
+        preferences.save_to(&path).unwrap();
+        assert_eq!(GuiPreferences::load_from(&path).unwrap(), preferences);
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("theme = \"dark\""));
+
+        // Missing keys take their defaults, the scale stays in range
+        fs::write(&path, "scale_percent = 400\n").unwrap();
+        let loaded = GuiPreferences::load_from(&path).unwrap();
+        assert_eq!(loaded.theme, ThemeMode::System);
+        assert_eq!(loaded.scale_factor(), 2.0);
+    }
+}
@@ -0,0 +1,301 @@
+//! Bandwidth & Quality Preview
+//!
+//! Estimates the bandwidth the EGFX settings need at a given resolution and
+//! frame rate, and renders a synthetic desktop through the server's H.264
+//! encoder so the result can be compared with the original.
+//!
+//! The estimate uses a simple model: full-motion H.264 needs about
+//! [`FULL_MOTION_BPP`] bits per pixel at QP 26, halving for every 6 QP
+//! steps. Typical desktop work changes a small part of the screen, which
+//! P-frames encode almost for free. Both are capped by the configured
+//! bitrate (plus the auxiliary stream for AVC444), past which the encoder
+//! raises QP and quality drops instead.
+
+use iced::widget::image::Handle;
+
+use crate::config::Config;
+
+/// Bits per pixel of full-motion content at QP 26
+const FULL_MOTION_BPP: f64 = 0.1;
+
+/// Share of the screen that changes during typical desktop work
+const TYPICAL_CHANGED_FRACTION: f64 = 0.1;
+
+/// Size of the region shown side by side, at 1:1 scale
+const CROP_WIDTH: u32 = 480;
+const CROP_HEIGHT: u32 = 270;
+
+/// Frames encoded for the sample: one IDR, then P-frames refining it
+#[cfg(feature = "h264")]
+const SAMPLE_FRAMES: u64 = 5;
+
+/// Resolutions offered by the preview
+pub const PREVIEW_RESOLUTIONS: &[&str] = &["1280x720", "1920x1080", "2560x1440", "3840x2160"];
+
+/// Bandwidth needed by a configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandwidthEstimate {
+    /// Whole screen changing every frame (video, scrolling, dragging)
+    pub full_motion_kbps: u32,
+    /// Typical desktop work
+    pub typical_kbps: u32,
+    /// Full motion needs more than the configured bitrate allows
+    pub bitrate_limited: bool,
+}
+
+/// Estimate bandwidth for `config` at `width`x`height`
+pub fn estimate_bandwidth(config: &Config, width: u32, height: u32) -> BandwidthEstimate {
+    let egfx = &config.egfx;
+    let fps = f64::from(config.video.target_fps.max(1));
+    let qp = f64::from(egfx.qp_default);
+
+    // AVC444 adds the auxiliary stream on top of the main one
+    let stream_factor = if uses_avc444(config) {
+        1.0 + f64::from(egfx.avc444_aux_bitrate_ratio)
+    } else {
+        1.0
+    };
+
+    let bpp = FULL_MOTION_BPP * 2f64.powf((26.0 - qp) / 6.0);
+    let demand_kbps = f64::from(width) * f64::from(height) * fps * bpp / 1000.0 * stream_factor;
+    let cap_kbps = f64::from(egfx.h264_bitrate) * stream_factor;
+    let full_motion_kbps = demand_kbps.min(cap_kbps);
+
+    BandwidthEstimate {
+        full_motion_kbps: full_motion_kbps.round() as u32,
+        typical_kbps: (full_motion_kbps * TYPICAL_CHANGED_FRACTION).round() as u32,
+        bitrate_limited: demand_kbps > cap_kbps,
+    }
+}
+
+/// Whether clients that support it get AVC444 with this configuration
+pub fn uses_avc444(config: &Config) -> bool {
+    match config.egfx.codec.as_str() {
+        "avc420" => false,
+        "avc444" => true,
+        _ => config.egfx.avc444_enabled,
+    }
+}
+
+/// Parse a "WIDTHxHEIGHT" resolution
+pub fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (width, height) = resolution.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+/// A synthetic desktop before and after encoding
+#[derive(Debug, Clone)]
+pub struct PreviewSample {
+    /// Center of the original image
+    pub original: Handle,
+    /// The same region, decoded from the encoder's output
+    pub encoded: Handle,
+    /// Size of the IDR frame (a full-screen update)
+    pub keyframe_bytes: usize,
+    /// PSNR of the decoded image against the original, in dB
+    pub psnr_db: f64,
+}
+
+/// Encode a synthetic desktop at `width`x`height` with `config`'s settings
+///
+/// AVC444 is previewed through its main (4:2:0) view; the client restores
+/// full chroma from the auxiliary stream, so text edges look better than
+/// the sample shows.
+#[cfg(feature = "h264")]
+pub fn render_sample(config: &Config, width: u32, height: u32) -> Result<PreviewSample, String> {
+    use openh264::decoder::{Decoder, DecoderConfig};
+    use openh264::formats::YUVSource;
+
+    use crate::egfx::{Avc420Encoder, EncoderConfig};
+
+    if width % 2 != 0 || height % 2 != 0 || width < CROP_WIDTH || height < CROP_HEIGHT {
+        return Err(format!(
+            "Unsupported preview resolution {}x{}",
+            width, height
+        ));
+    }
+
+    let frame = desktop_test_pattern(width, height);
+    let mut encoder = Avc420Encoder::new(EncoderConfig {
+        bitrate_kbps: config.egfx.h264_bitrate,
+        max_fps: config.video.target_fps.max(1) as f32,
+        enable_skip_frame: false,
+        qp_min: config.egfx.qp_min,
+        qp_max: config.egfx.qp_max,
+        ..EncoderConfig::for_resolution(width as u16, height as u16)
+    })
+    .map_err(|e| format!("Failed to create encoder: {}", e))?;
+    let mut decoder =
+        Decoder::with_api_config(openh264::OpenH264API::from_source(), DecoderConfig::new())
+            .map_err(|e| format!("Failed to create decoder: {:?}", e))?;
+
+    let frame_interval_ms = 1000 / u64::from(config.video.target_fps.max(1));
+    let mut keyframe_bytes = 0;
+    let mut decoded = None;
+    for i in 0..SAMPLE_FRAMES {
+        let Some(encoded) = encoder
+            .encode_bgra(&frame, width, height, i * frame_interval_ms)
+            .map_err(|e| format!("Encoding failed: {}", e))?
+        else {
+            continue;
+        };
+        if encoded.is_keyframe {
+            keyframe_bytes = encoded.size;
+        }
+        if let Some(yuv) = decoder
+            .decode(&encoded.data)
+            .map_err(|e| format!("Decoding failed: {:?}", e))?
+        {
+            let (w, h) = yuv.dimensions();
+            if (w, h) != (width as usize, height as usize) {
+                return Err(format!(
+                    "Decoded frame is {}x{}, expected {}x{}",
+                    w, h, width, height
+                ));
+            }
+            let mut rgba = vec![0u8; w * h * 4];
+            yuv.write_rgba8(&mut rgba);
+            decoded = Some(rgba);
+        }
+    }
+    let decoded = decoded.ok_or_else(|| "The encoder produced no frame".to_string())?;
+
+    let original = bgra_to_rgba(&frame);
+    Ok(PreviewSample {
+        psnr_db: psnr(&original, &decoded),
+        original: crop_center(&original, width, height),
+        encoded: crop_center(&decoded, width, height),
+        keyframe_bytes,
+    })
+}
+
+#[cfg(not(feature = "h264"))]
+pub fn render_sample(_config: &Config, _width: u32, _height: u32) -> Result<PreviewSample, String> {
+    Err("H.264 support is not compiled in (h264 feature)".to_string())
+}
+
+/// BGRA image resembling a desktop: a gradient wallpaper tiled with
+/// windows holding dark and colored text-like strokes, the content that
+/// shows chroma subsampling and quantization first
+pub fn desktop_test_pattern(width: u32, height: u32) -> Vec<u8> {
+    const TILE_WIDTH: u32 = 320;
+    const TILE_HEIGHT: u32 = 240;
+
+    let mut data = vec![0u8; (width * height * 4) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let (tx, ty) = (x % TILE_WIDTH, y % TILE_HEIGHT);
+            let in_window =
+                (16..TILE_WIDTH - 16).contains(&tx) && (16..TILE_HEIGHT - 16).contains(&ty);
+
+            // [blue, green, red]
+            let color: [u8; 3] = if !in_window {
+                let shade = (y * 255 / height.max(1)) as u8;
+                [160u8.saturating_add(shade / 3), 90 + shade / 4, 40]
+            } else if ty < 40 {
+                [160, 80, 48] // title bar
+            } else {
+                let line = (ty - 40) / 12;
+                let in_stroke = (ty - 40) % 12 < 7
+                    && tx > 24
+                    && tx < 64 + (line * 37 + (x / TILE_WIDTH) * 53) % 200
+                    && (tx * 7 + ty * 3) % 11 < 6;
+                match (in_stroke, line % 4) {
+                    (false, _) => [255, 255, 255],
+                    (true, 3) => [32, 32, 200], // red text
+                    (true, _) => [40, 40, 40],
+                }
+            };
+
+            let i = ((y * width + x) * 4) as usize;
+            data[i..i + 3].copy_from_slice(&color);
+            data[i + 3] = 255;
+        }
+    }
+    data
+}
+
+fn bgra_to_rgba(bgra: &[u8]) -> Vec<u8> {
+    let mut rgba = bgra.to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    rgba
+}
+
+/// The [`CROP_WIDTH`]x[`CROP_HEIGHT`] center of an RGBA image
+fn crop_center(rgba: &[u8], width: u32, height: u32) -> Handle {
+    let x0 = (width - CROP_WIDTH) / 2;
+    let y0 = (height - CROP_HEIGHT) / 2;
+    let mut pixels = Vec::with_capacity((CROP_WIDTH * CROP_HEIGHT * 4) as usize);
+    for y in y0..y0 + CROP_HEIGHT {
+        let start = ((y * width + x0) * 4) as usize;
+        pixels.extend_from_slice(&rgba[start..start + (CROP_WIDTH * 4) as usize]);
+    }
+    Handle::from_rgba(CROP_WIDTH, CROP_HEIGHT, pixels)
+}
+
+/// Peak signal-to-noise ratio of the color channels of two RGBA images
+pub fn psnr(a: &[u8], b: &[u8]) -> f64 {
+    let (sum, count) = a
+        .chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .flat_map(|(pa, pb)| pa[..3].iter().zip(&pb[..3]))
+        .fold((0f64, 0usize), |(sum, count), (&x, &y)| {
+            let diff = f64::from(x) - f64::from(y);
+            (sum + diff * diff, count + 1)
+        });
+    if sum == 0.0 || count == 0 {
+        return f64::INFINITY;
+    }
+    10.0 * (255.0 * 255.0 / (sum / count as f64)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_bandwidth() {
+        let mut config = Config::default();
+        config.video.target_fps = 30;
+        config.egfx.qp_default = 26;
+        config.egfx.codec = "avc420".to_string();
+        config.egfx.h264_bitrate = 50_000;
+
+        // 1920x1080 x 30 fps x 0.1 bpp
+        let estimate = estimate_bandwidth(&config, 1920, 1080);
+        assert_eq!(estimate.full_motion_kbps, 6221);
+        assert_eq!(estimate.typical_kbps, 622);
+        assert!(!estimate.bitrate_limited);
+
+        // 6 QP steps halve the bitrate
+        config.egfx.qp_default = 32;
+        assert_eq!(
+            estimate_bandwidth(&config, 1920, 1080).full_motion_kbps,
+            3110
+        );
+
+        // The configured bitrate caps it, the auxiliary stream adds to it
+        config.egfx.h264_bitrate = 2000;
+        config.egfx.codec = "avc444".to_string();
+        config.egfx.avc444_aux_bitrate_ratio = 0.5;
+        let estimate = estimate_bandwidth(&config, 1920, 1080);
+        assert_eq!(estimate.full_motion_kbps, 3000);
+        assert!(estimate.bitrate_limited);
+    }
+
+    #[test]
+    fn test_resolution_and_psnr() {
+        assert_eq!(parse_resolution("2560x1440"), Some((2560, 1440)));
+        assert_eq!(parse_resolution("2560"), None);
+
+        let image = bgra_to_rgba(&desktop_test_pattern(64, 32));
+        assert_eq!(image.len(), 64 * 32 * 4);
+        assert_eq!(psnr(&image, &image), f64::INFINITY);
+
+        let mut noisy = image.clone();
+        noisy[0] ^= 0x10;
+        assert!(psnr(&image, &noisy) > 40.0);
+    }
+}
@@ -0,0 +1,98 @@
+//! Configuration Profiles
+//!
+//! Named configurations (e.g. "home-lan", "wan-low-bandwidth") stored as
+//! complete TOML files in `~/.config/lamco-rdp-server/profiles/`. Loading a
+//! profile replaces the configuration being edited; it takes effect once
+//! saved to the server's configuration file. A profile file can be copied
+//! between machines as is, or opened with Load / written with Save As.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::gui::file_ops;
+
+/// Directory holding the profiles of this user
+pub fn profiles_dir() -> PathBuf {
+    file_ops::get_user_config_path().with_file_name("profiles")
+}
+
+/// Names of the profiles in `dir`, sorted
+pub fn list_profiles(dir: &Path) -> Vec<String> {
+    file_ops::list_config_files(dir)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect()
+}
+
+/// Save `config` as profile `name`, replacing an existing one
+pub fn save_profile(dir: &Path, name: &str, config: &Config) -> Result<PathBuf, String> {
+    let path = profile_path(dir, name)?;
+    file_ops::save_config(config, &path)?;
+    Ok(path)
+}
+
+/// Load profile `name`
+pub fn load_profile(dir: &Path, name: &str) -> Result<Config, String> {
+    file_ops::load_config(&profile_path(dir, name)?)
+}
+
+/// Delete profile `name`
+pub fn delete_profile(dir: &Path, name: &str) -> Result<(), String> {
+    let path = profile_path(dir, name)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete profile '{}': {}", name, e))
+}
+
+/// File of profile `name`; names are limited to letters, digits, `-`, `_`
+/// and spaces so they cannot leave the directory
+fn profile_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+    {
+        return Err(format!(
+            "Invalid profile name '{}': use letters, digits, '-', '_' and spaces",
+            name
+        ));
+    }
+    Ok(dir.join(format!("{}.toml", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_profile_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.server.listen_addr = "0.0.0.0:4489".to_string();
+
+        save_profile(dir.path(), "wan-low-bandwidth", &config).unwrap();
+        save_profile(dir.path(), "home lan", &Config::default()).unwrap();
+        assert_eq!(
+            list_profiles(dir.path()),
+            vec!["home lan", "wan-low-bandwidth"]
+        );
+
+        let loaded = load_profile(dir.path(), "wan-low-bandwidth").unwrap();
+        assert_eq!(loaded.server.listen_addr, "0.0.0.0:4489");
+
+        delete_profile(dir.path(), "home lan").unwrap();
+        assert_eq!(list_profiles(dir.path()), vec!["wan-low-bandwidth"]);
+    }
+
+    #[test]
+    fn test_profile_names_stay_in_directory() {
+        let dir = tempdir().unwrap();
+        assert!(save_profile(dir.path(), "../escape", &Config::default()).is_err());
+        assert!(save_profile(dir.path(), "  ", &Config::default()).is_err());
+        assert!(list_profiles(&dir.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Server Control Module
+//!
+//! Controls a running server process from the GUI. Configuration changes
+//! are applied by saving the file and sending SIGHUP, which makes the server
+//! hot-reload its runtime-safe settings. Live status and client disconnects
+//! go through the server's admin API (`[admin_api]`), as does pausing a
+//! client's sharing.
+
+use std::path::Path;
+use std::time::Duration;
+
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use sysinfo::System;
+
+use crate::config::types::AdminApiConfig;
+use crate::config::{Config, ReloadReport};
+use crate::gui::state::ServerStatus;
+use crate::server::{AdminClient, SessionEntry};
+
+/// Server process name
+const SERVER_PROCESS_NAME: &str = "lamco-rdp-server";
+
+/// How recent input or a clipboard transfer must be to show as ongoing
+pub const ACTIVITY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Result of applying the configuration to running servers
+#[derive(Debug, Clone)]
+pub struct ApplyOutcome {
+    /// How the saved settings differ from the previous file
+    pub report: ReloadReport,
+    /// Number of server processes signalled
+    pub servers_signalled: usize,
+}
+
+/// Save `config` to `path` and ask running servers to reload it
+///
+/// The report compares against the file as it was before saving, so
+/// settings listed under `restart_required` will not take effect until the
+/// server is restarted.
+pub fn save_and_reload(config: &Config, path: &Path) -> Result<ApplyOutcome, String> {
+    let previous = crate::gui::file_ops::load_config(path).ok();
+    crate::gui::file_ops::save_config(config, path)?;
+
+    let report = previous
+        .map(|previous| ReloadReport::between(&previous, config))
+        .unwrap_or_default();
+    let servers_signalled = signal_running_servers()?;
+
+    Ok(ApplyOutcome {
+        report,
+        servers_signalled,
+    })
+}
+
+/// Send SIGHUP to every server process owned by this user
+fn signal_running_servers() -> Result<usize, String> {
+    let mut system = System::new();
+    system.refresh_processes();
+
+    let mut signalled = 0;
+    for process in system.processes_by_exact_name(SERVER_PROCESS_NAME) {
+        let pid = Pid::from_raw(process.pid().as_u32() as i32);
+        match kill(pid, Signal::SIGHUP) {
+            Ok(()) => signalled += 1,
+            // Another user's server: not ours to reload
+            Err(nix::errno::Errno::EPERM) => {}
+            Err(e) => return Err(format!("Failed to signal server (pid {}): {}", pid, e)),
+        }
+    }
+
+    Ok(signalled)
+}
+
+/// Running server as seen through its admin API
+#[derive(Debug, Clone)]
+pub struct LiveStatus {
+    pub status: ServerStatus,
+    pub sessions: Vec<SessionEntry>,
+}
+
+/// Query the admin API for server status and connected clients
+///
+/// A server that does not answer is reported as stopped.
+pub async fn poll_live_status(
+    config: AdminApiConfig,
+    address: String,
+) -> Result<LiveStatus, String> {
+    let client = AdminClient::from_config(&config).map_err(|e| format!("{:#}", e))?;
+    let stats = match client.stats().await {
+        Ok(stats) => stats,
+        Err(e) if is_unreachable(&e) => {
+            return Ok(LiveStatus {
+                status: ServerStatus::Stopped,
+                sessions: Vec::new(),
+            })
+        }
+        Err(e) => return Err(format!("{:#}", e)),
+    };
+    let sessions = client.sessions().await.map_err(|e| format!("{:#}", e))?;
+
+    Ok(LiveStatus {
+        status: ServerStatus::Running {
+            connections: stats.clients + stats.observers,
+            uptime: Duration::from_secs(stats.uptime_secs),
+            address,
+        },
+        sessions,
+    })
+}
+
+/// Disconnect a client of the running server, returning its ID
+pub async fn disconnect_client(config: AdminApiConfig, id: u64) -> Result<u64, String> {
+    let client = AdminClient::from_config(&config).map_err(|e| format!("{:#}", e))?;
+    match client.disconnect(id).await {
+        Ok(true) => Ok(id),
+        Ok(false) => Err(format!("Client {} is no longer connected", id)),
+        Err(e) => Err(format!("{:#}", e)),
+    }
+}
+
+/// Pause or resume sharing with a client of the running server
+///
+/// Returns the client ID and whether it is now paused.
+pub async fn set_sharing_paused(
+    config: AdminApiConfig,
+    id: u64,
+    paused: bool,
+) -> Result<(u64, bool), String> {
+    let client = AdminClient::from_config(&config).map_err(|e| format!("{:#}", e))?;
+    match client.set_sharing_paused(id, paused).await {
+        Ok(true) => Ok((id, paused)),
+        Ok(false) => Err(format!("Client {} is no longer connected", id)),
+        Err(e) => Err(format!("{:#}", e)),
+    }
+}
+
+/// Whether the admin API could not be connected to at all
+fn is_unreachable(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect())
+}
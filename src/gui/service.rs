@@ -0,0 +1,332 @@
+//! systemd Service Control
+//!
+//! Installs, enables and starts the server's systemd unit through systemd's
+//! D-Bus API, so a configuration edited here can be put into service
+//! without a terminal.
+//!
+//! - **User service** (`lamco-rdp-server.service` on the session bus): the
+//!   GUI writes the unit to `~/.config/systemd/user/`, pointing at the
+//!   server binary next to the GUI and the configuration file being edited.
+//! - **System service** (`lamco-rdp-server@<user>.service` on the system
+//!   bus): the template unit is shipped by the package; enabling and
+//!   starting it is authorized by polkit, which may ask for a password.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{Connection, MethodFlags};
+
+/// Unit of the per-user service
+const USER_UNIT: &str = "lamco-rdp-server.service";
+
+/// Template shipped in packaging/systemd, rewritten for this installation
+const USER_UNIT_TEMPLATE: &str = include_str!("../../packaging/systemd/lamco-rdp-server.service");
+
+/// Where the server binary is expected when it is not next to the GUI
+const DEFAULT_SERVER_BINARY: &str = "/usr/bin/lamco-rdp-server";
+
+/// Which systemd instance runs the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServiceScope {
+    /// `systemctl --user`: runs in the user's graphical session
+    #[default]
+    User,
+    /// `systemctl`: the per-user template instance of the system manager
+    System,
+}
+
+impl ServiceScope {
+    pub const ALL: [ServiceScope; 2] = [ServiceScope::User, ServiceScope::System];
+
+    /// Name of the unit in this scope
+    pub fn unit_name(&self) -> String {
+        match self {
+            ServiceScope::User => USER_UNIT.to_string(),
+            ServiceScope::System => format!("lamco-rdp-server@{}.service", current_user()),
+        }
+    }
+
+    async fn connection(&self) -> Result<Connection, String> {
+        match self {
+            ServiceScope::User => Connection::session().await,
+            ServiceScope::System => Connection::system().await,
+        }
+        .map_err(|e| format!("Failed to connect to D-Bus: {}", e))
+    }
+}
+
+impl std::fmt::Display for ServiceScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceScope::User => write!(f, "User service"),
+            ServiceScope::System => write!(f, "System service"),
+        }
+    }
+}
+
+/// Operation on the unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    /// Write the user unit file (user scope only)
+    Install,
+    Enable,
+    Disable,
+    Start,
+    Stop,
+    Restart,
+}
+
+impl std::fmt::Display for ServiceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceAction::Install => write!(f, "install"),
+            ServiceAction::Enable => write!(f, "enable"),
+            ServiceAction::Disable => write!(f, "disable"),
+            ServiceAction::Start => write!(f, "start"),
+            ServiceAction::Stop => write!(f, "stop"),
+            ServiceAction::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+/// State of the unit as systemd reports it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitStatus {
+    pub unit: String,
+    /// "loaded", or "not-found" when no unit file is installed
+    pub load_state: String,
+    /// "active", "inactive", "failed", "activating", ...
+    pub active_state: String,
+    /// Finer state, e.g. "running" or "dead"
+    pub sub_state: String,
+    /// "enabled", "disabled", ... (empty when not installed)
+    pub unit_file_state: String,
+}
+
+impl UnitStatus {
+    pub fn is_installed(&self) -> bool {
+        self.load_state != "not-found"
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.active_state.as_str(),
+            "active" | "activating" | "reloading"
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.unit_file_state.starts_with("enabled")
+    }
+}
+
+/// Query the state of the unit in `scope`
+pub async fn unit_status(scope: ServiceScope) -> Result<UnitStatus, String> {
+    let connection = scope.connection().await?;
+    let unit = scope.unit_name();
+    query_unit(&connection, &unit).await
+}
+
+/// Run `action` on the unit in `scope` and report its new state
+///
+/// `config_path` is the configuration a newly installed user unit starts
+/// the server with.
+pub async fn run_action(
+    scope: ServiceScope,
+    action: ServiceAction,
+    config_path: PathBuf,
+) -> Result<UnitStatus, String> {
+    let connection = scope.connection().await?;
+    let manager = manager_proxy(&connection).await?;
+    let unit = scope.unit_name();
+    // Lets polkit ask for a password on the system bus
+    let flags = MethodFlags::AllowInteractiveAuth.into();
+    let failed = |e: zbus::Error| format!("Failed to {} {}: {}", action, unit, e);
+
+    match action {
+        ServiceAction::Install => {
+            if scope == ServiceScope::System {
+                return Err(
+                    "The system unit is installed with the lamco-rdp-server package \
+                     (lamco-rdp-server@.service)"
+                        .to_string(),
+                );
+            }
+            install_user_unit(&manager, &config_path).await?;
+        }
+        ServiceAction::Enable => {
+            manager
+                .call_with_flags::<_, _, (bool, Vec<(String, String, String)>)>(
+                    "EnableUnitFiles",
+                    flags,
+                    &(vec![unit.as_str()], false, false),
+                )
+                .await
+                .map_err(failed)?;
+            manager
+                .call_with_flags::<_, _, ()>("Reload", flags, &())
+                .await
+                .map_err(failed)?;
+        }
+        ServiceAction::Disable => {
+            manager
+                .call_with_flags::<_, _, Vec<(String, String, String)>>(
+                    "DisableUnitFiles",
+                    flags,
+                    &(vec![unit.as_str()], false),
+                )
+                .await
+                .map_err(failed)?;
+            manager
+                .call_with_flags::<_, _, ()>("Reload", flags, &())
+                .await
+                .map_err(failed)?;
+        }
+        ServiceAction::Start | ServiceAction::Stop | ServiceAction::Restart => {
+            // A user service started before it was ever installed
+            if scope == ServiceScope::User
+                && action != ServiceAction::Stop
+                && !query_unit(&connection, &unit).await?.is_installed()
+            {
+                install_user_unit(&manager, &config_path).await?;
+            }
+            let method = match action {
+                ServiceAction::Start => "StartUnit",
+                ServiceAction::Stop => "StopUnit",
+                _ => "RestartUnit",
+            };
+            manager
+                .call_with_flags::<_, _, OwnedObjectPath>(
+                    method,
+                    flags,
+                    &(unit.as_str(), "replace"),
+                )
+                .await
+                .map_err(failed)?;
+        }
+    }
+
+    query_unit(&connection, &unit).await
+}
+
+/// Contents of the user unit for this installation
+///
+/// The packaged unit with `ExecStart` pointing at `server_binary` and
+/// `config_path`. Writable paths are made optional, since a missing one
+/// would keep the unit from starting.
+pub fn user_unit_file(server_binary: &Path, config_path: &Path) -> String {
+    let mut unit = String::new();
+    for line in USER_UNIT_TEMPLATE.lines() {
+        if line.starts_with("ExecStart=") {
+            unit.push_str(&format!(
+                "ExecStart={} -c {}",
+                server_binary.display(),
+                config_path.display()
+            ));
+        } else if let Some(paths) = line.strip_prefix("ReadWritePaths=") {
+            let optional: Vec<String> = paths
+                .split_whitespace()
+                .map(|path| format!("-{}", path.trim_start_matches('-')))
+                .collect();
+            unit.push_str(&format!("ReadWritePaths={}", optional.join(" ")));
+        } else {
+            unit.push_str(line);
+        }
+        unit.push('\n');
+    }
+    unit
+}
+
+/// Write the user unit file and make systemd load it
+async fn install_user_unit(manager: &zbus::Proxy<'_>, config_path: &Path) -> Result<(), String> {
+    let dir = dirs::config_dir()
+        .ok_or_else(|| "Cannot determine the user configuration directory".to_string())?
+        .join("systemd/user");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(USER_UNIT);
+    fs::write(&path, user_unit_file(&server_binary(), config_path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    manager
+        .call::<_, _, ()>("Reload", &())
+        .await
+        .map_err(|e| format!("Failed to reload systemd: {}", e))
+}
+
+/// The server binary installed alongside the GUI, else the packaged one
+fn server_binary() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("lamco-rdp-server")))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SERVER_BINARY))
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_default()
+}
+
+async fn manager_proxy(connection: &Connection) -> Result<zbus::Proxy<'static>, String> {
+    zbus::ProxyBuilder::new(connection)
+        .destination("org.freedesktop.systemd1")
+        .and_then(|b| b.path("/org/freedesktop/systemd1"))
+        .and_then(|b| b.interface("org.freedesktop.systemd1.Manager"))
+        .map_err(|e| format!("Failed to create systemd proxy: {}", e))?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await
+        .map_err(|e| format!("Failed to create systemd proxy: {}", e))
+}
+
+async fn query_unit(connection: &Connection, unit: &str) -> Result<UnitStatus, String> {
+    let manager = manager_proxy(connection).await?;
+    // LoadUnit also answers for units without a unit file ("not-found")
+    let path: OwnedObjectPath = manager
+        .call("LoadUnit", &(unit,))
+        .await
+        .map_err(|e| format!("Failed to query {}: {}", unit, e))?;
+
+    let proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new(connection)
+        .destination("org.freedesktop.systemd1")
+        .and_then(|b| b.path(path))
+        .and_then(|b| b.interface("org.freedesktop.systemd1.Unit"))
+        .map_err(|e| format!("Failed to query {}: {}", unit, e))?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await
+        .map_err(|e| format!("Failed to query {}: {}", unit, e))?;
+
+    let property = |name: &'static str| proxy.get_property::<String>(name);
+    Ok(UnitStatus {
+        unit: unit.to_string(),
+        load_state: property("LoadState").await.unwrap_or_default(),
+        active_state: property("ActiveState").await.unwrap_or_default(),
+        sub_state: property("SubState").await.unwrap_or_default(),
+        unit_file_state: property("UnitFileState").await.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_unit_file() {
+        let unit = user_unit_file(
+            Path::new("/opt/lamco/bin/lamco-rdp-server"),
+            Path::new("/home/alice/.config/lamco-rdp-server/config.toml"),
+        );
+
+        assert!(unit.contains(
+            "\nExecStart=/opt/lamco/bin/lamco-rdp-server -c \
+             /home/alice/.config/lamco-rdp-server/config.toml\n"
+        ));
+        assert!(unit.contains("ReadWritePaths=-/var/log/wrd-server -/run/user/"));
+        assert!(unit.contains("WantedBy=graphical-session.target"));
+        assert_eq!(unit.matches("ExecStart=").count(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Session Resource History
+//!
+//! The admin API reports running totals for each client (CPU time, bytes
+//! sent) plus the current round-trip time. Rates are derived from
+//! consecutive polls and kept for the last [`HISTORY_LEN`] of them, to be
+//! drawn as small graphs next to the session list.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{mouse, Color, Point, Rectangle, Renderer, Theme};
+
+use crate::gui::message::Message;
+use crate::gui::theme::Palette;
+use crate::server::{SessionEntry, SessionUsage};
+
+/// Polls kept per session (one per second)
+pub const HISTORY_LEN: usize = 60;
+
+/// Resource use of a session over one poll interval
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsageSample {
+    pub cpu_percent: f64,
+    pub bandwidth_kbps: f64,
+    /// 0 until the server has measured it
+    pub rtt_ms: f64,
+    /// Frame rate the pipeline aims for
+    pub target_fps: f64,
+    /// EGFX frame acknowledgement latency, 0 until measured
+    pub ack_latency_ms: f64,
+}
+
+/// Recent samples of every connected client with a pipeline of its own
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    tracks: HashMap<u64, Track>,
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    last: SessionUsage,
+    last_at: Instant,
+    samples: VecDeque<UsageSample>,
+}
+
+impl SessionHistory {
+    /// Add the totals of a poll taken at `at`
+    ///
+    /// Clients that are no longer listed are forgotten.
+    pub fn record(&mut self, sessions: &[SessionEntry], at: Instant) {
+        self.tracks
+            .retain(|id, _| sessions.iter().any(|session| session.id == *id));
+
+        for session in sessions {
+            let Some(usage) = session.usage else {
+                continue;
+            };
+            let Some(track) = self.tracks.get_mut(&session.id) else {
+                self.tracks.insert(
+                    session.id,
+                    Track {
+                        last: usage,
+                        last_at: at,
+                        samples: VecDeque::with_capacity(HISTORY_LEN),
+                    },
+                );
+                continue;
+            };
+
+            let secs = at.saturating_duration_since(track.last_at).as_secs_f64();
+            if secs <= 0.0 {
+                continue;
+            }
+            if track.samples.len() == HISTORY_LEN {
+                track.samples.pop_front();
+            }
+            track.samples.push_back(UsageSample {
+                cpu_percent: (usage.cpu_secs - track.last.cpu_secs).max(0.0) / secs * 100.0,
+                bandwidth_kbps: usage.bytes_sent.saturating_sub(track.last.bytes_sent) as f64 * 8.0
+                    / 1000.0
+                    / secs,
+                rtt_ms: usage.rtt_ms,
+                target_fps: f64::from(usage.target_fps),
+                ack_latency_ms: usage.ack_latency_ms,
+            });
+            track.last = usage;
+            track.last_at = at;
+        }
+    }
+
+    /// Samples of client `id`, oldest first
+    pub fn samples(&self, id: u64) -> Option<&VecDeque<UsageSample>> {
+        self.tracks
+            .get(&id)
+            .map(|track| &track.samples)
+            .filter(|samples| !samples.is_empty())
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+/// Line graph of one quantity over the history, scaled to its peak
+pub struct UsageGraph {
+    pub values: Vec<f64>,
+    /// Lowest full-scale value, so idle sessions don't show noise as peaks
+    pub min_scale: f64,
+    pub color: fn(&Palette) -> Color,
+}
+
+impl canvas::Program<Message> for UsageGraph {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let color = (self.color)(Palette::of(theme));
+
+        let scale = self.values.iter().copied().fold(self.min_scale, f64::max);
+        let step = bounds.width / (HISTORY_LEN - 1) as f32;
+        // Newest sample at the right edge
+        let start = HISTORY_LEN.saturating_sub(self.values.len()) as f32 * step;
+        let point = |i: usize, value: f64| {
+            let y = bounds.height - 1.0 - (value / scale) as f32 * (bounds.height - 2.0);
+            Point::new(start + i as f32 * step, y)
+        };
+
+        if self.values.len() > 1 {
+            let line = Path::new(|builder| {
+                for (i, &value) in self.values.iter().enumerate() {
+                    if i == 0 {
+                        builder.move_to(point(i, value));
+                    } else {
+                        builder.line_to(point(i, value));
+                    }
+                }
+            });
+            frame.stroke(&line, Stroke::default().with_color(color).with_width(1.5));
+        }
+        frame.stroke(
+            &Path::line(
+                Point::new(0.0, bounds.height - 0.5),
+                Point::new(bounds.width, bounds.height - 0.5),
+            ),
+            Stroke::default()
+                .with_color(Color { a: 0.3, ..color })
+                .with_width(1.0),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(id: u64, cpu_secs: f64, bytes_sent: u64) -> SessionEntry {
+        SessionEntry {
+            id,
+            peer: "192.0.2.10:50000".to_string(),
+            kind: "primary".to_string(),
+            connected_secs: 0,
+            usage: Some(SessionUsage {
+                cpu_secs,
+                bytes_sent,
+                frame_memory_bytes: 0,
+                throttle_level: 0,
+                rtt_ms: 12.0,
+                capture_stalls: 0,
+                target_fps: 30,
+                activity: None,
+                ack_latency_ms: 0.0,
+                frames_delivered: 0,
+                frames_encoded: 0,
+                encode_secs: 0.0,
+                input_events: 0,
+            }),
+            overlay_visible: None,
+            sharing: None,
+        }
+    }
+
+    #[test]
+    fn test_rates_between_polls() {
+        let start = Instant::now();
+        let mut history = SessionHistory::default();
+
+        history.record(&[entry(1, 10.0, 1_000_000)], start);
+        assert!(history.samples(1).is_none());
+
+        // 0.5 s CPU and 250 kB over two seconds
+        history.record(&[entry(1, 10.5, 1_250_000)], start + Duration::from_secs(2));
+        let sample = history.samples(1).unwrap()[0];
+        assert!((sample.cpu_percent - 25.0).abs() < 1e-9);
+        assert!((sample.bandwidth_kbps - 1000.0).abs() < 1e-9);
+        assert_eq!(sample.rtt_ms, 12.0);
+        assert_eq!(sample.target_fps, 30.0);
+
+        for i in 0..HISTORY_LEN as u64 {
+            history.record(
+                &[entry(1, 11.0, 1_250_000)],
+                start + Duration::from_secs(3 + i),
+            );
+        }
+        assert_eq!(history.samples(1).unwrap().len(), HISTORY_LEN);
+
+        // Disconnected clients are dropped
+        history.record(&[entry(2, 0.0, 0)], start + Duration::from_secs(100));
+        assert!(history.samples(1).is_none());
+    }
+}
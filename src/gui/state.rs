@@ -6,6 +6,9 @@ use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 
 use crate::config::Config;
+use crate::gui::log_tail::LogTail;
+use crate::gui::service::{ServiceScope, UnitStatus};
+use crate::server::{HealthReport, SessionEntry};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Tab {
@@ -69,6 +72,67 @@ impl Tab {
     }
 }
 
+/// Steps of the first-run setup wizard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WizardStep {
+    #[default]
+    Detect,
+    Capture,
+    Permissions,
+    Certificate,
+    Network,
+    Finish,
+}
+
+impl WizardStep {
+    pub fn all() -> &'static [WizardStep] {
+        &[
+            WizardStep::Detect,
+            WizardStep::Capture,
+            WizardStep::Permissions,
+            WizardStep::Certificate,
+            WizardStep::Network,
+            WizardStep::Finish,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            WizardStep::Detect => "Detect",
+            WizardStep::Capture => "Capture",
+            WizardStep::Permissions => "Permissions",
+            WizardStep::Certificate => "Certificate",
+            WizardStep::Network => "Network",
+            WizardStep::Finish => "Finish",
+        }
+    }
+
+    /// Position in [`WizardStep::all`]
+    pub fn index(&self) -> usize {
+        Self::all()
+            .iter()
+            .position(|step| step == self)
+            .unwrap_or(0)
+    }
+
+    pub fn next(&self) -> Option<WizardStep> {
+        Self::all().get(self.index() + 1).copied()
+    }
+
+    pub fn previous(&self) -> Option<WizardStep> {
+        self.index().checked_sub(1).map(|i| Self::all()[i])
+    }
+}
+
+/// Progress through the first-run setup wizard
+#[derive(Debug, Clone, Default)]
+pub struct WizardState {
+    pub step: WizardStep,
+    /// Result of the portal permission setup, once run
+    pub permissions: Option<HealthReport>,
+    pub permissions_running: bool,
+}
+
 /// String buffers for text inputs to avoid iced lifetime issues.
 /// Synced with Config on load/save.
 #[derive(Debug, Clone, Default)]
@@ -87,6 +151,9 @@ pub struct EditStrings {
     // Video tab
     pub vaapi_device: String,
 
+    // Input tab
+    pub fallback_chain: String,
+
     // Clipboard tab
     pub max_size_mb: String,
     pub rate_limit: String,
@@ -128,16 +195,22 @@ pub struct EditStrings {
     // Advanced tab - Display
     pub resolutions_text: String,
 
+    // Advanced tab - Multi-Monitor
+    pub max_monitors: String,
+
     // Advanced tab - Advanced Video
     pub intra_refresh: String,
 
     // Advanced tab - Cursor
     pub cursor_update_fps: String,
+    pub decouple_below_fps: String,
     pub predictive_threshold: String,
     pub history_size: String,
     pub lookahead: String,
     pub max_pred_dist: String,
     pub min_velocity: String,
+    pub max_lookahead: String,
+    pub snap_back_distance: String,
 }
 
 impl EditStrings {
@@ -158,6 +231,9 @@ impl EditStrings {
             // Video
             vaapi_device: config.video.vaapi_device.display().to_string(),
 
+            // Input
+            fallback_chain: config.input.fallback_chain.join(", "),
+
             // Clipboard (convert bytes to MB for display)
             max_size_mb: (config.clipboard.max_size / (1024 * 1024)).to_string(),
             rate_limit: config.clipboard.rate_limit_ms.to_string(),
@@ -215,17 +291,21 @@ impl EditStrings {
 
             // Advanced - Display
             resolutions_text: config.display.allowed_resolutions.join("\n"),
+            max_monitors: config.multimon.max_monitors.to_string(),
 
             // Advanced - Video
             intra_refresh: config.advanced_video.intra_refresh_interval.to_string(),
 
             // Advanced - Cursor
             cursor_update_fps: config.cursor.cursor_update_fps.to_string(),
+            decouple_below_fps: config.cursor.decouple_below_fps.to_string(),
             predictive_threshold: config.cursor.predictive_latency_threshold_ms.to_string(),
             history_size: config.cursor.predictor.history_size.to_string(),
             lookahead: format!("{:.1}", config.cursor.predictor.lookahead_ms),
             max_pred_dist: config.cursor.predictor.max_prediction_distance.to_string(),
             min_velocity: format!("{:.1}", config.cursor.predictor.min_velocity_threshold),
+            max_lookahead: format!("{:.1}", config.cursor.predictor.max_lookahead_ms),
+            snap_back_distance: config.cursor.predictor.snap_back_distance.to_string(),
         }
     }
 
@@ -258,6 +338,17 @@ pub struct AppState {
     // Server state (from IPC)
     pub server_status: ServerStatus,
 
+    // Connected clients of the running server (from the admin API)
+    pub live_sessions: Vec<SessionEntry>,
+    pub live_status_pending: bool,
+    pub session_history: crate::gui::session_history::SessionHistory,
+
+    // systemd unit running the server
+    pub service_scope: ServiceScope,
+    pub service_status: Option<UnitStatus>,
+    pub service_busy: bool,
+    pub service_poll_pending: bool,
+
     // Hardware detection
     pub detected_gpus: Vec<GpuInfo>,
     pub detected_vaapi_devices: Vec<PathBuf>,
@@ -265,6 +356,16 @@ pub struct AppState {
     // Capabilities (from --show-capabilities)
     pub detected_capabilities: Option<DetectedCapabilities>,
 
+    // Diagnostics (health checks of the current configuration)
+    pub diagnostics: Option<HealthReport>,
+    pub diagnostics_running: bool,
+
+    // Appearance of the GUI itself (gui.toml)
+    pub preferences: crate::gui::preferences::GuiPreferences,
+    pub system_prefers_dark: bool,
+    /// When the desktop color scheme was last read
+    pub color_scheme_checked: Option<std::time::Instant>,
+
     // UI state
     pub active_preset: Option<String>,
     pub expert_mode: bool,
@@ -281,14 +382,40 @@ pub struct AppState {
     pub cursor_predictor_expanded: bool,
     pub egfx_expert_mode: bool,
 
+    // Bandwidth & quality preview (EGFX tab)
+    pub preview_expanded: bool,
+    pub preview_resolution: String,
+    pub preview_sample: Option<crate::gui::preview::PreviewSample>,
+    pub preview_rendering: bool,
+
+    // Multi-monitor layout editor (Advanced tab)
+    pub multimon_expanded: bool,
+    pub detected_monitors: Vec<crate::gui::monitor_layout::DetectedMonitor>,
+    pub monitors_detecting: bool,
+
+    // Configuration profiles
+    pub profiles_expanded: bool,
+    pub profiles: Vec<String>,
+    pub selected_profile: Option<String>,
+    pub profile_name: String,
+    /// Differences from the server's configuration file, once compared
+    pub config_diff: Option<Vec<String>>,
+
     // Certificate generation dialog state
     pub cert_gen_dialog: Option<CertGenState>,
 
+    // Last check of the configured certificate and key
+    pub cert_check: Option<crate::gui::certificates::CertificateCheck>,
+
     // Log viewer state
     pub log_buffer: Vec<LogLine>,
     pub log_auto_scroll: bool,
     pub log_filter_level: LogLevel,
+    pub log_module_filter: String,
+    pub log_search: String,
     pub max_log_lines: usize,
+    pub log_file_path: String,
+    pub log_tail: Option<LogTail>,
 
     // User messages (info/warning/error notifications)
     pub messages: Vec<UserMessage>,
@@ -296,12 +423,16 @@ pub struct AppState {
     // Dialog states
     pub confirm_discard_dialog: bool,
     pub pending_action: Option<PendingAction>,
+
+    // First-run setup wizard (replaces the tabs while open)
+    pub wizard: Option<WizardState>,
 }
 
 impl AppState {
     /// Create new state with default or loaded config
     pub fn load_or_default() -> Self {
         let config_path = Self::default_config_path();
+        let first_run = !config_path.exists();
         let config = Config::load(config_path.to_str().unwrap_or_default())
             .unwrap_or_else(|_| Config::default_config().unwrap_or_default());
         let edit_strings = EditStrings::from_config(&config);
@@ -314,9 +445,21 @@ impl AppState {
             last_saved: None,
             validation: ValidationState::default(),
             server_status: ServerStatus::Unknown,
+            live_sessions: Vec::new(),
+            live_status_pending: false,
+            session_history: Default::default(),
+            service_scope: ServiceScope::default(),
+            service_status: None,
+            service_busy: false,
+            service_poll_pending: false,
             detected_gpus: Vec::new(),
             detected_vaapi_devices: Vec::new(),
             detected_capabilities: None,
+            diagnostics: None,
+            diagnostics_running: false,
+            preferences: crate::gui::preferences::GuiPreferences::load(),
+            system_prefers_dark: false,
+            color_scheme_checked: None,
             active_preset: None,
             expert_mode: false,
             video_pipeline_expanded: false,
@@ -329,14 +472,32 @@ impl AppState {
             cursor_expanded: true,
             cursor_predictor_expanded: false,
             egfx_expert_mode: false,
+            preview_expanded: false,
+            preview_resolution: "1920x1080".to_string(),
+            preview_sample: None,
+            preview_rendering: false,
+            multimon_expanded: false,
+            detected_monitors: Vec::new(),
+            monitors_detecting: false,
+            profiles_expanded: false,
+            profiles: crate::gui::profiles::list_profiles(&crate::gui::profiles::profiles_dir()),
+            selected_profile: None,
+            profile_name: String::new(),
+            config_diff: None,
             cert_gen_dialog: None,
+            cert_check: None,
             log_buffer: Vec::new(),
             log_auto_scroll: true,
             log_filter_level: LogLevel::Info,
+            log_module_filter: String::new(),
+            log_search: String::new(),
             max_log_lines: 1000,
+            log_file_path: String::new(),
+            log_tail: None,
             messages: Vec::new(),
             confirm_discard_dialog: false,
             pending_action: None,
+            wizard: first_run.then(WizardState::default),
         }
     }
 
@@ -395,12 +556,16 @@ impl AppState {
         }
     }
 
-    /// Get filtered log lines based on current filter level
+    /// Get filtered log lines based on current level, module and search filters
     pub fn filtered_log_lines(&self) -> impl Iterator<Item = &LogLine> {
         let filter_level = self.log_filter_level;
-        self.log_buffer
-            .iter()
-            .filter(move |line| line.level as u8 >= filter_level as u8)
+        let module = self.log_module_filter.as_str();
+        let search = self.log_search.to_lowercase();
+        self.log_buffer.iter().filter(move |line| {
+            line.level as u8 >= filter_level as u8
+                && (module.is_empty() || line.target.contains(module))
+                && (search.is_empty() || line.raw.to_lowercase().contains(&search))
+        })
     }
 }
 
@@ -634,12 +799,30 @@ impl Default for CertGenState {
 pub struct LogLine {
     pub timestamp: String,
     pub level: LogLevel,
+    /// Module that logged the line (empty if unknown)
+    pub target: String,
     pub message: String,
     pub raw: String,
 }
 
 impl LogLine {
     pub fn parse(raw: &str) -> Self {
+        // tracing's format: "2026-01-19T14:23:45.123456Z  INFO spans: target: message"
+        let (timestamp, rest) = raw.trim().split_once(' ').unwrap_or((raw, ""));
+        let (level, rest) = rest.trim_start().split_once(' ').unwrap_or((rest, ""));
+        if timestamp.contains('T') && timestamp.ends_with('Z') {
+            if let Some(level) = LogLevel::parse(level) {
+                let (target, message) = split_target(rest.trim_start());
+                return Self {
+                    timestamp: timestamp.get(11..19).unwrap_or(timestamp).to_string(),
+                    level,
+                    target,
+                    message,
+                    raw: raw.to_string(),
+                };
+            }
+        }
+
         // Try to parse: "2026-01-19 14:23:45 [INFO] Server listening..."
         let parts: Vec<&str> = raw.splitn(4, ' ').collect();
 
@@ -650,18 +833,8 @@ impl LogLine {
         };
 
         let level = if parts.len() >= 3 {
-            match parts[2]
-                .trim_matches(|c| c == '[' || c == ']')
-                .to_uppercase()
-                .as_str()
-            {
-                "TRACE" => LogLevel::Trace,
-                "DEBUG" => LogLevel::Debug,
-                "INFO" => LogLevel::Info,
-                "WARN" | "WARNING" => LogLevel::Warn,
-                "ERROR" => LogLevel::Error,
-                _ => LogLevel::Info,
-            }
+            LogLevel::parse(parts[2].trim_matches(|c| c == '[' || c == ']'))
+                .unwrap_or(LogLevel::Info)
         } else {
             LogLevel::Info
         };
@@ -671,12 +844,32 @@ impl LogLine {
         Self {
             timestamp,
             level,
+            target: String::new(),
             message,
             raw: raw.to_string(),
         }
     }
 }
 
+/// Split "spans: target: message" into target and message
+fn split_target(rest: &str) -> (String, String) {
+    let mut offset = 0;
+    for token in rest.split(' ') {
+        offset += token.len() + 1;
+        if let Some(target) = token.strip_suffix(':') {
+            if !target.is_empty()
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+            {
+                let message = rest.get(offset..).unwrap_or("").trim_start();
+                return (target.to_string(), message.to_string());
+            }
+        }
+    }
+    (String::new(), rest.to_string())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
     Trace = 0,
@@ -699,6 +892,18 @@ impl std::fmt::Display for LogLevel {
 }
 
 impl LogLevel {
+    /// Parse a level name ("INFO", "warning", ...)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "TRACE" => Some(LogLevel::Trace),
+            "DEBUG" => Some(LogLevel::Debug),
+            "INFO" => Some(LogLevel::Info),
+            "WARN" | "WARNING" => Some(LogLevel::Warn),
+            "ERROR" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
     pub fn all() -> &'static [LogLevel] {
         &[
             LogLevel::Trace,
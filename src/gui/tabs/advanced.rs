@@ -1,11 +1,15 @@
 //! Advanced Configuration Tab
 //!
-//! Combines damage tracking, hardware encoding, display, advanced video, and cursor settings.
+//! Combines damage tracking, hardware encoding, display, multi-monitor, advanced video, and
+//! cursor settings, plus configuration profiles.
 
-use iced::widget::{button, column, pick_list, row, space, text};
+use iced::widget::{
+    button, canvas, column, container, pick_list, row, space, text, text_input, toggler,
+};
 use iced::{Alignment, Element, Length};
 
 use crate::gui::message::{DamageTrackingPreset, Message};
+use crate::gui::monitor_layout::{LayoutEditor, Rect};
 use crate::gui::state::AppState;
 use crate::gui::theme;
 use crate::gui::widgets;
@@ -16,6 +20,11 @@ const HW_QUALITY_PRESETS: &[&str] = &["speed", "balanced", "quality"];
 /// Superset of video.rs modes: adds "painted" and "predictive" for advanced use.
 const CURSOR_MODES: &[&str] = &["metadata", "painted", "hidden", "predictive"];
 
+const PREDICTION_MODELS: &[&str] = &["acceleration", "velocity"];
+
+/// Per-monitor capture rates; "Default" is the pipeline rate
+const MONITOR_FPS_CHOICES: &[&str] = &["Default", "60", "30", "15", "10", "5"];
+
 pub fn view_advanced_tab(state: &AppState) -> Element<'_, Message> {
     column![
         // Section header
@@ -57,6 +66,18 @@ pub fn view_advanced_tab(state: &AppState) -> Element<'_, Message> {
             column![].into()
         },
         space().height(12.0),
+        // Multi-Monitor section
+        widgets::collapsible_header(
+            "Multi-Monitor",
+            state.multimon_expanded,
+            Message::MultimonToggleExpanded,
+        ),
+        if state.multimon_expanded {
+            view_multimon_config(state)
+        } else {
+            column![].into()
+        },
+        space().height(12.0),
         // Advanced Video section
         widgets::collapsible_header(
             "Advanced Video",
@@ -80,12 +101,99 @@ pub fn view_advanced_tab(state: &AppState) -> Element<'_, Message> {
         } else {
             column![].into()
         },
+        space().height(12.0),
+        // Configuration Profiles section
+        widgets::collapsible_header(
+            "Configuration Profiles",
+            state.profiles_expanded,
+            Message::ProfilesToggleExpanded,
+        ),
+        if state.profiles_expanded {
+            view_profiles(state)
+        } else {
+            column![].into()
+        },
     ]
     .spacing(4)
     .padding(20)
     .into()
 }
 
+/// Named configuration profiles and comparison with the server's file
+fn view_profiles(state: &AppState) -> Element<'_, Message> {
+    let has_selection = state.selected_profile.is_some();
+
+    let mut content = column![
+        space().height(8.0),
+        widgets::info_box(
+            "Profiles are complete configurations in ~/.config/lamco-rdp-server/profiles/. \
+             A loaded profile applies once saved.",
+        ),
+        space().height(8.0),
+        row![
+            pick_list(
+                state.profiles.as_slice(),
+                state.selected_profile.as_ref(),
+                Message::ProfileSelected,
+            )
+            .placeholder("Select a profile...")
+            .width(Length::Fixed(250.0)),
+            button(text("Load"))
+                .on_press_maybe(has_selection.then_some(Message::LoadProfile))
+                .padding([6, 12])
+                .style(theme::secondary_button_style),
+            button(text("Delete"))
+                .on_press_maybe(has_selection.then_some(Message::DeleteProfile))
+                .padding([6, 12])
+                .style(theme::danger_button_style),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        space().height(8.0),
+        row![
+            text_input("Profile name", &state.profile_name)
+                .on_input(Message::ProfileNameChanged)
+                .width(Length::Fixed(250.0)),
+            button(text("Save Current as Profile"))
+                .on_press_maybe(
+                    (!state.profile_name.trim().is_empty()).then_some(Message::SaveProfile)
+                )
+                .padding([6, 12])
+                .style(theme::secondary_button_style),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        space().height(12.0),
+        button(text("Compare with Running Configuration"))
+            .on_press(Message::CompareWithRunningConfig)
+            .padding([6, 12])
+            .style(theme::secondary_button_style),
+    ]
+    .spacing(4);
+
+    if let Some(ref diff) = state.config_diff {
+        content = content.push(space().height(8.0));
+        if diff.is_empty() {
+            content = content.push(widgets::success_box(
+                "Identical to the server's configuration file",
+            ));
+        } else {
+            content = content.push(
+                text(format!(
+                    "{} settings differ from the server's configuration file:",
+                    diff.len()
+                ))
+                .size(13),
+            );
+            for line in diff {
+                content = content.push(text(line).size(12).font(iced::Font::MONOSPACE));
+            }
+        }
+    }
+
+    content.into()
+}
+
 /// Damage tracking configuration view
 fn view_damage_tracking_config(state: &AppState) -> Element<'_, Message> {
     let damage = &state.config.damage_tracking;
@@ -111,9 +219,7 @@ fn view_damage_tracking_config(state: &AppState) -> Element<'_, Message> {
         space().height(4.0),
         text("• Diff: CPU pixel comparison | PipeWire: Compositor hints | Hybrid: Both")
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(12.0),
         // Sensitivity presets
         text("Sensitivity Presets:").size(13),
@@ -336,6 +442,146 @@ fn view_display_config(state: &AppState) -> Element<'_, Message> {
     .into()
 }
 
+/// Multi-monitor settings and layout editor
+fn view_multimon_config(state: &AppState) -> Element<'_, Message> {
+    let multimon = &state.config.multimon;
+
+    let mut content = column![
+        space().height(8.0),
+        widgets::toggle_switch(
+            "Multi-Monitor Support",
+            multimon.enabled,
+            Message::MultimonEnabledToggled,
+        ),
+        space().height(8.0),
+        widgets::labeled_row(
+            "Max Monitors:",
+            150.0,
+            widgets::number_input(
+                &state.edit_strings.max_monitors,
+                "4",
+                80.0,
+                Message::MultimonMaxMonitorsChanged,
+            ),
+        ),
+        space().height(12.0),
+        widgets::subsection_header("Layout"),
+        text(
+            "Drag monitors to arrange them in the client's desktop; a dropped monitor \
+             snaps to the edges of its neighbours. Applies to new capture sessions."
+        )
+        .size(13),
+        space().height(8.0),
+    ]
+    .spacing(4);
+
+    if state.monitors_detecting {
+        content = content.push(widgets::info_box("Detecting monitors..."));
+    } else if state.detected_monitors.is_empty() {
+        content = content.push(widgets::warning_box(
+            "No monitors detected. The layout can still be edited in the configuration \
+             file ([[multimon.monitors]]).",
+        ));
+    } else {
+        let rects = Rect::from_layout(&state.detected_monitors, &multimon.monitors);
+        content = content
+            .push(
+                container(
+                    canvas(LayoutEditor { rects })
+                        .width(Length::Fill)
+                        .height(Length::Fixed(240.0)),
+                )
+                .style(theme::section_container_style),
+            )
+            .push(space().height(8.0));
+
+        for (index, monitor) in state.detected_monitors.iter().enumerate() {
+            let (x, y, shared) = monitor.placement(&multimon.monitors);
+            let fps = monitor
+                .entry(&multimon.monitors)
+                .map_or(0, |entry| multimon.monitors[entry].fps);
+            let selected = if fps == 0 {
+                Some(MONITOR_FPS_CHOICES[0])
+            } else {
+                MONITOR_FPS_CHOICES
+                    .iter()
+                    .copied()
+                    .find(|choice| *choice == fps.to_string())
+            };
+
+            content = content.push(
+                row![
+                    text(format!(
+                        "{} ({}x{})",
+                        monitor.label(index),
+                        monitor.width,
+                        monitor.height
+                    ))
+                    .width(Length::Fixed(200.0)),
+                    toggler(shared)
+                        .label("Shared")
+                        .on_toggle(move |shared| Message::MonitorSharedToggled(index, shared))
+                        .width(Length::Fixed(110.0)),
+                    pick_list(MONITOR_FPS_CHOICES.to_vec(), selected, move |fps| {
+                        Message::MonitorFpsSelected(index, fps.to_string())
+                    })
+                    .placeholder(format!("{} fps", fps))
+                    .width(Length::Fixed(110.0)),
+                    text(format!("at {}, {}", x, y))
+                        .size(12)
+                        .style(theme::text_color(|p| p.text_muted)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            );
+        }
+    }
+
+    // Entries for monitors that aren't connected now are kept as they are
+    for entry in multimon.monitors.iter().filter(|entry| {
+        !state
+            .detected_monitors
+            .iter()
+            .any(|m| (m.desktop_x, m.desktop_y) == (entry.desktop_x, entry.desktop_y))
+    }) {
+        content = content.push(
+            text(format!(
+                "Not connected: {} (local {}, {}) - {}",
+                if entry.name.is_empty() {
+                    "monitor"
+                } else {
+                    entry.name.as_str()
+                },
+                entry.desktop_x,
+                entry.desktop_y,
+                if entry.shared { "shared" } else { "not shared" },
+            ))
+            .size(12)
+            .style(theme::text_color(|p| p.text_muted)),
+        );
+    }
+
+    content
+        .push(space().height(8.0))
+        .push(
+            row![
+                button(text("Detect Again"))
+                    .on_press_maybe((!state.monitors_detecting).then_some(Message::DetectMonitors))
+                    .padding([6, 12])
+                    .style(theme::secondary_button_style),
+                button(text("Reset Layout"))
+                    .on_press_maybe(
+                        (!multimon.monitors.is_empty()).then_some(Message::MonitorLayoutReset)
+                    )
+                    .padding([6, 12])
+                    .style(theme::secondary_button_style),
+            ]
+            .spacing(8),
+        )
+        .padding([0, 16])
+        .into()
+}
+
 /// Advanced video configuration view
 fn view_advanced_video_config(state: &AppState) -> Element<'_, Message> {
     let av = &state.config.advanced_video;
@@ -407,9 +653,7 @@ fn view_cursor_config(state: &AppState) -> Element<'_, Message> {
               • Predictive - Physics-based prediction"
         )
         .size(12)
-        .style(|_theme| text::Style {
-            color: Some(theme::colors::TEXT_MUTED),
-        }),
+        .style(theme::text_color(|p| p.text_muted)),
         space().height(12.0),
         widgets::toggle_switch(
             "Auto Mode Selection",
@@ -417,6 +661,27 @@ fn view_cursor_config(state: &AppState) -> Element<'_, Message> {
             Message::CursorAutoModeToggled,
         ),
         space().height(8.0),
+        widgets::toggle_with_help(
+            "Color Pointers (old clients)",
+            cursor.color_pointers,
+            "Send 24-bit cursors with a transparency mask instead of alpha cursors",
+            Message::CursorColorPointersToggled,
+        ),
+        space().height(8.0),
+        widgets::toggle_with_help(
+            "Direct Touch (hidden mode)",
+            cursor.direct_touch,
+            "Keep the wobble of finger taps from turning them into drags",
+            Message::CursorDirectTouchToggled,
+        ),
+        space().height(8.0),
+        widgets::toggle_with_help(
+            "Direct Pen (hidden mode)",
+            cursor.direct_pen,
+            "Keep the wobble of pen taps from turning them into drags",
+            Message::CursorDirectPenToggled,
+        ),
+        space().height(8.0),
         widgets::labeled_row(
             "Predictive Threshold:",
             150.0,
@@ -443,6 +708,22 @@ fn view_cursor_config(state: &AppState) -> Element<'_, Message> {
                 Message::CursorUpdateFpsChanged,
             ),
         ),
+        space().height(8.0),
+        widgets::labeled_row(
+            "Client Cursor Below:",
+            150.0,
+            row![
+                widgets::number_input(
+                    &state.edit_strings.decouple_below_fps,
+                    "15",
+                    60.0,
+                    Message::CursorDecoupleBelowFpsChanged,
+                ),
+                text(" FPS video (0 = never)"),
+            ]
+            .align_y(Alignment::Center)
+            .into(),
+        ),
         space().height(12.0),
         // Predictor sub-section
         widgets::collapsible_header(
@@ -466,6 +747,17 @@ fn view_cursor_predictor_config(state: &AppState) -> Element<'_, Message> {
 
     column![
         space().height(8.0),
+        widgets::labeled_row_with_help(
+            "Model:",
+            180.0,
+            pick_list(PREDICTION_MODELS.to_vec(), Some(pred.model.as_str()), |s| {
+                Message::PredictorModelChanged(s.to_string())
+            },)
+            .width(Length::Fixed(150.0))
+            .into(),
+            "Velocity overshoots less on jerky input such as touchpads",
+        ),
+        space().height(4.0),
         widgets::labeled_row(
             "History Size:",
             180.0,
@@ -488,6 +780,26 @@ fn view_cursor_predictor_config(state: &AppState) -> Element<'_, Message> {
             ),
         ),
         space().height(4.0),
+        widgets::labeled_row(
+            "Lookahead (share of RTT):",
+            180.0,
+            widgets::float_slider(
+                pred.lookahead_rtt_fraction,
+                Message::PredictorLookaheadRttFractionChanged,
+            ),
+        ),
+        space().height(4.0),
+        widgets::labeled_row(
+            "Max Lookahead (ms):",
+            180.0,
+            widgets::number_input(
+                &state.edit_strings.max_lookahead,
+                "150.0",
+                60.0,
+                Message::PredictorMaxLookaheadMsChanged,
+            ),
+        ),
+        space().height(4.0),
         widgets::labeled_row(
             "Velocity Smoothing:",
             180.0,
@@ -541,6 +853,22 @@ fn view_cursor_predictor_config(state: &AppState) -> Element<'_, Message> {
                 Message::PredictorStopConvergenceRateChanged,
             ),
         ),
+        space().height(4.0),
+        widgets::labeled_row(
+            "Snap-Back Distance:",
+            180.0,
+            row![
+                widgets::number_input(
+                    &state.edit_strings.snap_back_distance,
+                    "40",
+                    60.0,
+                    Message::PredictorSnapBackDistanceChanged,
+                ),
+                text(" pixels"),
+            ]
+            .align_y(Alignment::Center)
+            .into(),
+        ),
     ]
     .padding([0, 16])
     .into()
@@ -72,9 +72,7 @@ pub fn view_clipboard_tab(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         text("ⓘ Leave empty to allow all clipboard formats")
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(12.0),
         // Preset buttons
         text("Quick Presets:").size(13),
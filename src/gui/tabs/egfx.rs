@@ -1,11 +1,13 @@
 //! EGFX (Graphics Pipeline Extension) Configuration Tab
 //!
-//! H.264 encoding settings, AVC444 configuration, and quality parameters.
+//! H.264 encoding settings, AVC444 configuration, quality parameters, and a
+//! bandwidth/quality preview of the current settings.
 
-use iced::widget::{button, column, pick_list, row, slider, space, text};
+use iced::widget::{button, column, image, pick_list, row, slider, space, text};
 use iced::{Alignment, Element, Length};
 
 use crate::gui::message::{EgfxPreset, Message};
+use crate::gui::preview;
 use crate::gui::state::AppState;
 use crate::gui::theme;
 use crate::gui::widgets;
@@ -76,6 +78,27 @@ pub fn view_egfx_tab(state: &AppState) -> Element<'_, Message> {
             "Main stream bitrate (3000-15000 recommended)",
         ),
         space().height(12.0),
+        widgets::toggle_with_help(
+            "Adaptive Bitrate",
+            egfx.adaptive_bitrate,
+            "Lower the bitrate while the client's link is congested",
+            Message::EgfxAdaptiveBitrateToggled,
+        ),
+        space().height(12.0),
+        widgets::toggle_with_help(
+            "Small Update Fast Path",
+            egfx.small_update_fast_path,
+            "Send caret blinks and typed characters uncompressed instead of encoding them",
+            Message::EgfxSmallUpdateFastPathToggled,
+        ),
+        space().height(12.0),
+        widgets::toggle_with_help(
+            "Decode Downgrade",
+            egfx.decode_downgrade,
+            "Lower codec and frame rate for clients that can't decode fast enough",
+            Message::EgfxDecodeDowngradeToggled,
+        ),
+        space().height(12.0),
         widgets::labeled_row_with_help(
             "Codec:",
             150.0,
@@ -152,6 +175,18 @@ pub fn view_egfx_tab(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         widgets::info_box("Lower QP = better quality, higher bitrate. Range: 0-51."),
         space().height(20.0),
+        // Bandwidth & Quality Preview
+        widgets::collapsible_header(
+            "Bandwidth & Quality Preview",
+            state.preview_expanded,
+            Message::PreviewToggleExpanded,
+        ),
+        if state.preview_expanded {
+            view_preview(state)
+        } else {
+            column![].into()
+        },
+        space().height(20.0),
         // Expert mode toggle
         button(text(if state.egfx_expert_mode {
             "Hide Expert Settings"
@@ -172,6 +207,105 @@ pub fn view_egfx_tab(state: &AppState) -> Element<'_, Message> {
     .into()
 }
 
+/// Estimated bandwidth and a sample encoded with the current settings
+fn view_preview(state: &AppState) -> Element<'_, Message> {
+    let resolution = preview::parse_resolution(&state.preview_resolution);
+    let mut content = column![
+        space().height(8.0),
+        widgets::labeled_row(
+            "Resolution:",
+            150.0,
+            pick_list(
+                preview::PREVIEW_RESOLUTIONS.to_vec(),
+                Some(state.preview_resolution.as_str()),
+                |s| Message::PreviewResolutionSelected(s.to_string()),
+            )
+            .width(Length::Fixed(150.0))
+            .into(),
+        ),
+    ]
+    .spacing(8);
+
+    if let Some((width, height)) = resolution {
+        let estimate = preview::estimate_bandwidth(&state.config, width, height);
+        let codec = if preview::uses_avc444(&state.config) {
+            "AVC444"
+        } else {
+            "AVC420"
+        };
+        content = content
+            .push(text(format!(
+                "{} at {} FPS, QP {}:",
+                codec, state.config.video.target_fps, state.config.egfx.qp_default
+            )))
+            .push(widgets::labeled_row(
+                "Full motion:",
+                150.0,
+                text(format_kbps(estimate.full_motion_kbps)).into(),
+            ))
+            .push(widgets::labeled_row(
+                "Typical desktop:",
+                150.0,
+                text(format_kbps(estimate.typical_kbps)).into(),
+            ));
+        if estimate.bitrate_limited {
+            content = content.push(widgets::warning_box(
+                "Full motion needs more than the configured bitrate: the encoder will \
+                 lower quality during video and scrolling",
+            ));
+        }
+    }
+
+    content = content.push(
+        button(text(if state.preview_rendering {
+            "Rendering..."
+        } else {
+            "Render Sample"
+        }))
+        .on_press_maybe((!state.preview_rendering).then_some(Message::RenderPreview))
+        .padding([8, 16])
+        .style(theme::secondary_button_style),
+    );
+
+    if let Some(ref sample) = state.preview_sample {
+        let labeled = |label: &'static str, handle: &iced::widget::image::Handle| {
+            column![text(label).size(13), image(handle.clone())].spacing(4)
+        };
+        content = content
+            .push(
+                row![
+                    labeled("Original", &sample.original),
+                    labeled("Encoded", &sample.encoded),
+                ]
+                .spacing(12),
+            )
+            .push(
+                text(format!(
+                    "Full-screen update: {} KB | PSNR: {:.1} dB",
+                    sample.keyframe_bytes / 1024,
+                    sample.psnr_db
+                ))
+                .size(13),
+            );
+        if preview::uses_avc444(&state.config) {
+            content = content.push(widgets::info_box(
+                "The sample shows AVC444's main (4:2:0) view; clients restore full color \
+                 detail from the auxiliary stream.",
+            ));
+        }
+    }
+
+    content.into()
+}
+
+fn format_kbps(kbps: u32) -> String {
+    if kbps >= 1000 {
+        format!("{:.1} Mbit/s", f64::from(kbps) / 1000.0)
+    } else {
+        format!("{} kbit/s", kbps)
+    }
+}
+
 fn view_egfx_expert_settings(state: &AppState) -> Element<'_, Message> {
     let egfx = &state.config.egfx;
 
@@ -261,9 +395,7 @@ fn view_egfx_expert_settings(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         text("Aux stream gets this percentage of main stream's bitrate")
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(12.0),
         widgets::labeled_row(
             "Color Matrix:",
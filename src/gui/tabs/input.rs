@@ -2,7 +2,7 @@
 //!
 //! Keyboard, mouse, and touch input settings.
 
-use iced::widget::{column, pick_list, space};
+use iced::widget::{column, pick_list, space, text_input};
 use iced::{Element, Length};
 
 use crate::gui::message::Message;
@@ -65,6 +65,18 @@ pub fn view_input_tab(state: &AppState) -> Element<'_, Message> {
             "Support touchscreen devices (if available)",
             Message::InputEnableTouchToggled,
         ),
+        space().height(16.0),
+
+        // Input failover
+        widgets::labeled_row_with_help(
+            "Fallback Chain:",
+            150.0,
+            text_input("libei, portal, wlr-direct", &state.edit_strings.fallback_chain)
+                .on_input(Message::InputFallbackChainChanged)
+                .width(Length::Fixed(300.0))
+                .into(),
+            "Backends input moves to when it fails (empty = restart the session)",
+        ),
     ]
     .spacing(8)
     .padding(20)
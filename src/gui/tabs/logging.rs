@@ -56,9 +56,7 @@ pub fn view_logging_tab(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         text("ⓘ Leave empty for console-only logging")
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(20.0),
         // Metrics toggle
         widgets::toggle_with_help(
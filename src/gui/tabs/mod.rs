@@ -13,6 +13,7 @@ mod security;
 mod server;
 mod status;
 mod video;
+mod wizard;
 
 pub use advanced::*;
 pub use clipboard::*;
@@ -24,3 +25,4 @@ pub use security::*;
 pub use server::*;
 pub use status::*;
 pub use video::*;
+pub use wizard::*;
@@ -50,9 +50,7 @@ pub fn view_performance_tab(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         text("Interactive: <50ms latency | Balanced: <100ms | Quality: Best image quality")
             .size(12)
-            .style(|_theme: &iced::Theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(20.0),
         // Threading section
         widgets::collapsible_header(
@@ -260,19 +258,13 @@ fn view_latency_config(state: &AppState) -> Element<'_, Message> {
         space().height(4.0),
         text("• Interactive - <50ms latency (gaming, CAD)")
             .size(12)
-            .style(|_theme: &iced::Theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         text("• Balanced - <100ms latency (general desktop)")
             .size(12)
-            .style(|_theme: &iced::Theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         text("• Quality - <300ms latency (photo/video editing)")
             .size(12)
-            .style(|_theme: &iced::Theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
         space().height(12.0),
         // Advanced tuning (optional, could be hidden in expert mode)
         text("Advanced Tuning:").size(13),
@@ -1,6 +1,7 @@
 //! Security Configuration Tab
 //!
-//! TLS certificates, authentication, NLA settings.
+//! TLS certificates (with validity, key and fingerprint checks),
+//! authentication, NLA settings.
 
 use iced::widget::{button, column, container, pick_list, row, space, text, text_input};
 use iced::{Element, Length};
@@ -42,6 +43,13 @@ pub fn view_security_tab(state: &AppState) -> Element<'_, Message> {
             Message::SecurityKeyPathChanged,
             Message::SecurityBrowseKey,
         ),
+        space().height(8.0),
+        button(text("Check Certificate"))
+            .on_press(Message::SecurityCheckCert)
+            .padding([8, 16])
+            .style(theme::secondary_button_style),
+        space().height(8.0),
+        view_cert_check(state),
         space().height(20.0),
         // Enable NLA
         widgets::toggle_with_help(
@@ -86,6 +94,46 @@ pub fn view_security_tab(state: &AppState) -> Element<'_, Message> {
     }
 }
 
+/// Outcome of the last certificate check
+pub(super) fn view_cert_check(state: &AppState) -> Element<'_, Message> {
+    let Some(ref check) = state.cert_check else {
+        return space().height(0.0).into();
+    };
+
+    let mut details = column![].spacing(6);
+    if let Some(ref fingerprint) = check.fingerprint {
+        details = details.push(
+            column![
+                text("SHA-256 Fingerprint:").size(13),
+                text(fingerprint).size(12).font(iced::Font::MONOSPACE),
+                text("Remote users can compare this with the fingerprint their client shows.")
+                    .size(11)
+                    .style(theme::text_color(|p| p.text_muted)),
+            ]
+            .spacing(2),
+        );
+    }
+    if let Some(ref valid_until) = check.valid_until {
+        details = details.push(text(format!("Valid until: {}", valid_until)).size(13));
+    }
+    for error in &check.errors {
+        details = details.push(widgets::error_box(error));
+    }
+    for warning in &check.warnings {
+        details = details.push(widgets::warning_box(warning));
+    }
+    if check.is_usable() && check.warnings.is_empty() {
+        details = details.push(widgets::success_box(
+            "Certificate is valid and matches the private key",
+        ));
+    }
+
+    container(details)
+        .padding(12)
+        .style(theme::section_container_style)
+        .into()
+}
+
 fn view_cert_gen_dialog(cert_state: &crate::gui::state::CertGenState) -> Element<'_, Message> {
     container(
         column![
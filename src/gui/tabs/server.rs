@@ -1,10 +1,12 @@
 //! Server Configuration Tab
 //!
-//! Basic server settings: listen address, max connections, timeouts, portals.
+//! Basic server settings: listen address, max connections, timeouts, portals,
+//! and the built-in config preset.
 
-use iced::widget::{column, row, space, text};
-use iced::{Alignment, Element};
+use iced::widget::{column, pick_list, row, space, text};
+use iced::{Alignment, Element, Length};
 
+use crate::config::presets::PRESETS;
 use crate::gui::message::Message;
 use crate::gui::state::AppState;
 use crate::gui::widgets;
@@ -67,6 +69,20 @@ pub fn view_server_tab(state: &AppState) -> Element<'_, Message> {
             "Required for Wayland screen capture and input injection",
             Message::ServerUsePortalsToggled,
         ),
+        space().height(16.0),
+        // Built-in preset
+        widgets::labeled_row_with_help(
+            "Preset:",
+            150.0,
+            pick_list(
+                std::iter::once("none").chain(PRESETS).collect::<Vec<_>>(),
+                Some(state.config.profile.as_deref().unwrap_or("none")),
+                |s| Message::ServerPresetChanged(s.to_string()),
+            )
+            .width(Length::Fixed(150.0))
+            .into(),
+            "LAN, WAN, low-power or kiosk settings (replaces the current values)",
+        ),
     ]
     .spacing(8)
     .padding(20)
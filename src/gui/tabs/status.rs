@@ -1,17 +1,34 @@
 //! Status & Monitoring Tab
 //!
-//! Server status, service registry display, and live log viewer.
+//! Server status, connected clients, diagnostics, service registry display,
+//! and live log viewer.
 
-use iced::widget::{button, column, container, pick_list, row, scrollable, space, text};
-use iced::{Alignment, Element, Length};
+use iced::widget::{
+    button, canvas, column, container, pick_list, row, scrollable, space, text, text_input,
+};
+use iced::{Alignment, Color, Element, Length};
 
 use crate::gui::message::Message;
+use crate::gui::server_control::ACTIVITY_WINDOW;
+use crate::gui::service::{ServiceAction, ServiceScope};
+use crate::gui::session_history::{UsageGraph, UsageSample};
 use crate::gui::state::{AppState, LogLevel, ServerStatus, ServiceLevel};
-use crate::gui::theme;
+use crate::gui::theme::{self, Palette};
 use crate::gui::widgets;
+use crate::server::SharingStatus;
 
 const LOG_LEVELS: &[&str] = &["Trace", "Debug", "Info", "Warn", "Error"];
 
+const LOG_MODULES: &[&str] = &[
+    "All",
+    "egfx",
+    "clipboard",
+    "input",
+    "pipewire",
+    "portal",
+    "server",
+];
+
 pub fn view_status_tab(state: &AppState) -> Element<'_, Message> {
     column![
         // Section header
@@ -22,6 +39,21 @@ pub fn view_status_tab(state: &AppState) -> Element<'_, Message> {
         space().height(8.0),
         view_server_status(state),
         space().height(20.0),
+        // systemd Service section
+        widgets::subsection_header("systemd Service"),
+        space().height(8.0),
+        view_service(state),
+        space().height(20.0),
+        // Connected Clients section
+        widgets::subsection_header("Connected Clients"),
+        space().height(8.0),
+        view_live_sessions(state),
+        space().height(20.0),
+        // Diagnostics section
+        widgets::subsection_header("Diagnostics"),
+        space().height(8.0),
+        view_diagnostics(state),
+        space().height(20.0),
         // Detected Capabilities section
         widgets::collapsible_header(
             "Detected Capabilities & Service Registry",
@@ -42,13 +74,20 @@ pub fn view_status_tab(state: &AppState) -> Element<'_, Message> {
 }
 
 fn view_server_status(state: &AppState) -> Element<'_, Message> {
-    let (status_text, status_color, is_running) = match &state.server_status {
-        ServerStatus::Unknown => ("Unknown", theme::colors::TEXT_MUTED, false),
-        ServerStatus::Stopped => ("Stopped", theme::colors::ERROR, false),
-        ServerStatus::Starting => ("Starting...", theme::colors::WARNING, false),
-        ServerStatus::Running { .. } => ("Running", theme::colors::SUCCESS, true),
-        ServerStatus::Error(_) => ("Error", theme::colors::ERROR, false),
-    };
+    let (status_text, status_color, is_running): (_, fn(&Palette) -> Color, _) =
+        match &state.server_status {
+            ServerStatus::Unknown => ("Unknown", |p| p.text_muted, false),
+            ServerStatus::Stopped => ("Stopped", |p| p.error, false),
+            ServerStatus::Starting => ("Starting...", |p| p.warning, false),
+            ServerStatus::Running { .. } => ("Running", |p| p.success, true),
+            ServerStatus::Error(_) => ("Error", |p| p.error, false),
+        };
+    // Without the admin API, the unit tells whether the server runs
+    let is_running = is_running
+        || state
+            .service_status
+            .as_ref()
+            .is_some_and(|unit| unit.is_active());
 
     let status_details = match &state.server_status {
         ServerStatus::Running {
@@ -71,19 +110,17 @@ fn view_server_status(state: &AppState) -> Element<'_, Message> {
     container(
         column![
             row![
-                text("●").size(16).style(move |_theme| text::Style {
-                    color: Some(status_color),
-                }),
+                text("●").size(16).style(theme::text_color(status_color)),
                 text(format!("Status: {}", status_text)).size(16),
             ]
             .spacing(8)
             .align_y(Alignment::Center),
             if !status_details.is_empty() {
-                Element::from(text(status_details).size(13).style(|_theme: &iced::Theme| {
-                    text::Style {
-                        color: Some(theme::colors::TEXT_SECONDARY),
-                    }
-                }))
+                Element::from(
+                    text(status_details)
+                        .size(13)
+                        .style(theme::text_color(|p| p.text_secondary)),
+                )
             } else {
                 Element::from(space().height(0.0))
             },
@@ -109,6 +146,10 @@ fn view_server_status(state: &AppState) -> Element<'_, Message> {
                     .on_press(Message::RestartServer)
                     .padding([8, 16])
                     .style(theme::secondary_button_style),
+                button(text("Reload Config"))
+                    .on_press(Message::ReloadServerConfig)
+                    .padding([8, 16])
+                    .style(theme::secondary_button_style),
             ]
             .spacing(8),
         ]
@@ -119,6 +160,342 @@ fn view_server_status(state: &AppState) -> Element<'_, Message> {
     .into()
 }
 
+/// Clients of the running server, polled from its admin API
+fn view_live_sessions(state: &AppState) -> Element<'_, Message> {
+    if !state.config.admin_api.enabled {
+        return widgets::info_box(
+            "Enable the admin API ([admin_api] with a token) to list connected clients here.",
+        );
+    }
+    if state.live_sessions.is_empty() {
+        return container(text("No clients connected").size(13))
+            .padding(16)
+            .style(theme::section_container_style)
+            .into();
+    }
+
+    let header = row![
+        text("ID").width(Length::FillPortion(1)).size(12),
+        text("Kind").width(Length::FillPortion(2)).size(12),
+        text("Peer").width(Length::FillPortion(4)).size(12),
+        text("Connected").width(Length::FillPortion(2)).size(12),
+        text("CPU").width(Length::FillPortion(2)).size(12),
+        text("Sent").width(Length::FillPortion(2)).size(12),
+        text("RTT").width(Length::FillPortion(2)).size(12),
+        text("FPS").width(Length::FillPortion(2)).size(12),
+        text("Activity").width(Length::FillPortion(4)).size(12),
+        space().width(Length::FillPortion(4)),
+    ]
+    .spacing(8)
+    .padding([4, 8]);
+
+    let rows: Vec<Element<'_, Message>> = state
+        .live_sessions
+        .iter()
+        .map(|session| {
+            let connected = session.connected_secs;
+            let (cpu, sent, rtt, fps) = match session.usage {
+                Some(usage) => (
+                    format!("{:.1}s", usage.cpu_secs),
+                    format!("{:.1} MB", usage.bytes_sent as f64 / 1_000_000.0),
+                    if usage.rtt_ms > 0.0 {
+                        format!("{:.0} ms", usage.rtt_ms)
+                    } else {
+                        "-".to_string()
+                    },
+                    match (usage.target_fps, usage.activity) {
+                        (0, _) => "-".to_string(),
+                        (fps, Some(activity)) => format!("{} ({:?})", fps, activity),
+                        (fps, None) => format!("{} (fixed)", fps),
+                    },
+                ),
+                None => (
+                    "-".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                ),
+            };
+
+            row![
+                text(session.id.to_string())
+                    .width(Length::FillPortion(1))
+                    .size(12),
+                text(&session.kind).width(Length::FillPortion(2)).size(12),
+                text(&session.peer).width(Length::FillPortion(4)).size(12),
+                text(format!(
+                    "{}h {}m {}s",
+                    connected / 3600,
+                    (connected % 3600) / 60,
+                    connected % 60
+                ))
+                .width(Length::FillPortion(2))
+                .size(12),
+                text(cpu).width(Length::FillPortion(2)).size(12),
+                text(sent).width(Length::FillPortion(2)).size(12),
+                text(rtt).width(Length::FillPortion(2)).size(12),
+                text(fps).width(Length::FillPortion(2)).size(12),
+                container(view_session_activity(session.sharing.as_ref()))
+                    .width(Length::FillPortion(4)),
+                button(
+                    text(match session.sharing {
+                        Some(ref sharing) if sharing.paused => "Resume",
+                        _ => "Pause",
+                    })
+                    .size(12),
+                )
+                .on_press_maybe(session.sharing.map(|sharing| {
+                    Message::SetSharingPaused {
+                        id: session.id,
+                        paused: !sharing.paused,
+                    }
+                }))
+                .padding([2, 8])
+                .width(Length::FillPortion(2))
+                .style(theme::secondary_button_style),
+                button(text("Disconnect").size(12))
+                    .on_press(Message::DisconnectSession(session.id))
+                    .padding([2, 8])
+                    .width(Length::FillPortion(2))
+                    .style(theme::danger_button_style),
+            ]
+            .spacing(8)
+            .padding([2, 8])
+            .align_y(Alignment::Center)
+            .into()
+        })
+        .collect();
+
+    let mut content = column![header].extend(rows).spacing(2);
+    let graphs: Vec<Element<'_, Message>> = state
+        .live_sessions
+        .iter()
+        .filter_map(|session| view_session_graphs(state, session.id))
+        .collect();
+    if !graphs.is_empty() {
+        content = content
+            .push(space().height(8.0))
+            .push(
+                text("Resource use (last minute)")
+                    .size(13)
+                    .style(theme::text_color(|p| p.text_secondary)),
+            )
+            .extend(graphs);
+    }
+
+    container(content)
+        .padding(8)
+        .style(theme::section_container_style)
+        .into()
+}
+
+/// Input and clipboard indicators of one client, or its paused state
+fn view_session_activity(sharing: Option<&SharingStatus>) -> Element<'_, Message> {
+    match sharing {
+        Some(sharing) if sharing.paused => text("⏸ Sharing paused")
+            .size(12)
+            .style(theme::text_color(|p| p.warning))
+            .into(),
+        Some(sharing) => row![
+            widgets::activity_indicator("⌨ Input", sharing.input_active(ACTIVITY_WINDOW)),
+            widgets::activity_indicator("📋 Clipboard", sharing.clipboard_active(ACTIVITY_WINDOW)),
+        ]
+        .spacing(8)
+        .into(),
+        None => text("-").size(12).into(),
+    }
+}
+
+/// CPU, bandwidth, round-trip and frame rate graphs of one client
+fn view_session_graphs(state: &AppState, id: u64) -> Option<Element<'_, Message>> {
+    let samples = state.session_history.samples(id)?;
+    let latest = *samples.back()?;
+
+    let values = |f: fn(&UsageSample) -> f64| samples.iter().map(f).collect::<Vec<_>>();
+
+    Some(
+        row![
+            text(format!("Client {}", id))
+                .size(12)
+                .width(Length::FillPortion(1)),
+            usage_graph(
+                format!("CPU {:.1}%", latest.cpu_percent),
+                values(|s| s.cpu_percent),
+                10.0,
+                |p| p.primary,
+            ),
+            usage_graph(
+                format!("Bandwidth {:.0} kbps", latest.bandwidth_kbps),
+                values(|s| s.bandwidth_kbps),
+                100.0,
+                |p| p.success,
+            ),
+            usage_graph(
+                if latest.rtt_ms > 0.0 {
+                    format!("RTT {:.0} ms", latest.rtt_ms)
+                } else {
+                    "RTT -".to_string()
+                },
+                values(|s| s.rtt_ms),
+                20.0,
+                |p| p.warning,
+            ),
+            usage_graph(
+                if latest.ack_latency_ms > 0.0 {
+                    format!(
+                        "{:.0} FPS, ack {:.0} ms",
+                        latest.target_fps, latest.ack_latency_ms
+                    )
+                } else {
+                    format!("{:.0} FPS", latest.target_fps)
+                },
+                values(|s| s.target_fps),
+                30.0,
+                |p| p.primary_light,
+            ),
+        ]
+        .spacing(12)
+        .padding([4, 8])
+        .align_y(Alignment::End)
+        .into(),
+    )
+}
+
+/// A labelled [`UsageGraph`]
+fn usage_graph<'a>(
+    label: String,
+    values: Vec<f64>,
+    min_scale: f64,
+    color: fn(&Palette) -> Color,
+) -> Element<'a, Message> {
+    column![
+        text(label).size(11),
+        canvas(UsageGraph {
+            values,
+            min_scale,
+            color,
+        })
+        .width(Length::Fill)
+        .height(Length::Fixed(36.0)),
+    ]
+    .spacing(2)
+    .width(Length::FillPortion(3))
+    .into()
+}
+
+/// Health checks of the current configuration
+fn view_diagnostics(state: &AppState) -> Element<'_, Message> {
+    let mut content = column![
+        text(
+            "Checks portal availability, PipeWire, encoder creation, the certificate \
+             and the listen address - the same checks as `lamco-rdp-server check`."
+        )
+        .size(13),
+        space().height(8.0),
+        button(text(if state.diagnostics_running {
+            "Running..."
+        } else {
+            "Run Diagnostics"
+        }))
+        .on_press_maybe((!state.diagnostics_running).then_some(Message::RunDiagnostics))
+        .padding([8, 16])
+        .style(theme::primary_button_style),
+    ]
+    .spacing(4);
+
+    if let Some(ref report) = state.diagnostics {
+        content = content.push(space().height(8.0));
+        content = content.push(widgets::health_check_list(&report.checks));
+    }
+
+    container(content)
+        .padding(16)
+        .style(theme::section_container_style)
+        .into()
+}
+
+/// State of the server's systemd unit with install and enable controls
+fn view_service(state: &AppState) -> Element<'_, Message> {
+    let status_text = match state.service_status {
+        Some(ref unit) if !unit.is_installed() => format!("{}: not installed", unit.unit),
+        Some(ref unit) => format!(
+            "{}: {} ({}), {}",
+            unit.unit, unit.active_state, unit.sub_state, unit.unit_file_state
+        ),
+        None => "systemd is not reachable".to_string(),
+    };
+    let installed = state
+        .service_status
+        .as_ref()
+        .is_some_and(|unit| unit.is_installed());
+    let enabled = state
+        .service_status
+        .as_ref()
+        .is_some_and(|unit| unit.is_enabled());
+    let idle = !state.service_busy;
+
+    let mut buttons = row![].spacing(8);
+    if state.service_scope == ServiceScope::User {
+        buttons = buttons.push(
+            button(text(if installed {
+                "Reinstall Unit"
+            } else {
+                "Install Unit"
+            }))
+            .on_press_maybe(idle.then_some(Message::ServiceActionRequested(ServiceAction::Install)))
+            .padding([8, 16])
+            .style(theme::secondary_button_style),
+        );
+    }
+    let toggle = if enabled {
+        ("Disable at Login", ServiceAction::Disable)
+    } else {
+        ("Enable at Login", ServiceAction::Enable)
+    };
+    buttons = buttons.push(
+        button(text(toggle.0))
+            .on_press_maybe(
+                (idle && installed).then_some(Message::ServiceActionRequested(toggle.1)),
+            )
+            .padding([8, 16])
+            .style(theme::secondary_button_style),
+    );
+
+    container(
+        column![
+            row![
+                pick_list(
+                    &ServiceScope::ALL[..],
+                    Some(state.service_scope),
+                    Message::ServiceScopeSelected,
+                )
+                .width(Length::Fixed(180.0)),
+                text(status_text).size(13),
+            ]
+            .spacing(12)
+            .align_y(Alignment::Center),
+            text(match state.service_scope {
+                ServiceScope::User => {
+                    "Runs in your graphical session. Installing writes the unit to \
+                     ~/.config/systemd/user with the configuration file being edited."
+                }
+                ServiceScope::System => {
+                    "The lamco-rdp-server@.service template from the package, for your \
+                     user. Changes are authorized by polkit and may ask for a password."
+                }
+            })
+            .size(12)
+            .style(theme::text_color(|p| p.text_muted)),
+            space().height(4.0),
+            buttons,
+        ]
+        .spacing(6),
+    )
+    .padding(16)
+    .style(theme::section_container_style)
+    .into()
+}
+
 /// Capabilities and service registry view
 fn view_capabilities_section(state: &AppState) -> Element<'_, Message> {
     if let Some(ref caps) = state.detected_capabilities {
@@ -172,9 +549,7 @@ fn view_capabilities_section(state: &AppState) -> Element<'_, Message> {
                                 Element::from(
                                     text(format!("  • {} - {}", q.quirk_id, q.description))
                                         .size(12)
-                                        .style(|_theme: &iced::Theme| text::Style {
-                                            color: Some(theme::colors::WARNING),
-                                        })
+                                        .style(theme::text_color(|p| p.warning))
                                 )
                             }));
                             Element::from(column(quirk_elements))
@@ -185,8 +560,8 @@ fn view_capabilities_section(state: &AppState) -> Element<'_, Message> {
                     .spacing(2)
                     .padding(12),
                 )
-                .style(|_theme| container::Style {
-                    background: Some(iced::Background::Color(theme::colors::SURFACE_DARK)),
+                .style(|t| container::Style {
+                    background: Some(iced::Background::Color(Palette::of(t).surface_dark)),
                     border: iced::Border {
                         radius: 4.0.into(),
                         ..Default::default()
@@ -283,9 +658,7 @@ fn labeled_value(label: impl Into<String>, value: impl Into<String>) -> Element<
         text(label_str)
             .size(13)
             .width(Length::Fixed(140.0))
-            .style(|_theme: &iced::Theme| text::Style {
-                color: Some(theme::colors::TEXT_SECONDARY),
-            }),
+            .style(theme::text_color(|p| p.text_secondary)),
         text(value_str).size(13),
     ]
     .spacing(8)
@@ -312,11 +685,11 @@ fn view_service_registry_table(
     let rows: Vec<Element<'_, Message>> = services
         .iter()
         .map(|service| {
-            let level_color = match service.level {
-                ServiceLevel::Guaranteed => theme::colors::GUARANTEED,
-                ServiceLevel::BestEffort => theme::colors::BEST_EFFORT,
-                ServiceLevel::Degraded => theme::colors::DEGRADED,
-                ServiceLevel::Unavailable => theme::colors::UNAVAILABLE,
+            let level_color: fn(&Palette) -> Color = match service.level {
+                ServiceLevel::Guaranteed => |p| p.guaranteed,
+                ServiceLevel::BestEffort => |p| p.best_effort,
+                ServiceLevel::Degraded => |p| p.degraded,
+                ServiceLevel::Unavailable => |p| p.unavailable,
             };
 
             let service_row: Element<'_, Message> = row![
@@ -326,9 +699,7 @@ fn view_service_registry_table(
                 text(service.level.to_string())
                     .width(Length::FillPortion(2))
                     .size(12)
-                    .style(move |_theme| text::Style {
-                        color: Some(level_color),
-                    }),
+                    .style(theme::text_color(level_color)),
                 text(service.wayland_source.as_deref().unwrap_or("-"))
                     .width(Length::FillPortion(3))
                     .size(12),
@@ -350,9 +721,7 @@ fn view_service_registry_table(
                     .map(|note| {
                         text(format!("    ↳ {}", note))
                             .size(11)
-                            .style(|_theme| text::Style {
-                                color: Some(theme::colors::TEXT_MUTED),
-                            })
+                            .style(theme::text_color(|p| p.text_muted))
                             .into()
                     })
                     .collect();
@@ -363,8 +732,8 @@ fn view_service_registry_table(
         .collect();
 
     container(scrollable(column![header].extend(rows).spacing(2)).height(Length::Fixed(200.0)))
-        .style(|_theme| container::Style {
-            background: Some(iced::Background::Color(theme::colors::SURFACE_DARK)),
+        .style(|t| container::Style {
+            background: Some(iced::Background::Color(Palette::of(t).surface_dark)),
             border: iced::Border {
                 radius: 4.0.into(),
                 ..Default::default()
@@ -384,8 +753,38 @@ fn view_log_viewer(state: &AppState) -> Element<'_, Message> {
         LogLevel::Error => "Error",
     };
 
+    let module_filter = if state.log_module_filter.is_empty() {
+        "All"
+    } else {
+        state.log_module_filter.as_str()
+    };
+
     column![
+        // Followed log file (the server's --log-file)
+        row![
+            text("Log File:").size(13),
+            widgets::path_input(
+                &state.log_file_path,
+                "/path/to/lamco-rdp-server.log",
+                Message::LogFilePathChanged,
+                Message::BrowseLogFile,
+            ),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
         // Log viewer controls
+        row![
+            text("Module:").size(13),
+            pick_list(LOG_MODULES.to_vec(), Some(module_filter), |s| {
+                Message::LogModuleFilterChanged(s.to_string())
+            })
+            .width(Length::Fixed(120.0)),
+            text_input("Search...", &state.log_search)
+                .on_input(Message::LogSearchChanged)
+                .width(Length::Fixed(220.0)),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
         row![
             text("Filter Level:").size(13),
             pick_list(LOG_LEVELS.to_vec(), Some(filter_level), |s| {
@@ -416,30 +815,26 @@ fn view_log_viewer(state: &AppState) -> Element<'_, Message> {
                     state
                         .filtered_log_lines()
                         .map(|line| {
-                            let level_color = match line.level {
-                                LogLevel::Error => theme::colors::LOG_ERROR,
-                                LogLevel::Warn => theme::colors::LOG_WARN,
-                                LogLevel::Info => theme::colors::LOG_INFO,
-                                LogLevel::Debug => theme::colors::LOG_DEBUG,
-                                LogLevel::Trace => theme::colors::LOG_TRACE,
+                            let level_color: fn(&Palette) -> Color = match line.level {
+                                LogLevel::Error => |p| p.log_error,
+                                LogLevel::Warn => |p| p.log_warn,
+                                LogLevel::Info => |p| p.log_info,
+                                LogLevel::Debug => |p| p.log_debug,
+                                LogLevel::Trace => |p| p.log_trace,
                             };
 
                             row![
                                 text(&line.timestamp)
                                     .size(11)
                                     .width(Length::Fixed(80.0))
-                                    .style(|_theme| text::Style {
-                                        color: Some(theme::colors::TEXT_MUTED),
-                                    }),
+                                    .style(theme::text_color(|p| p.text_muted)),
                                 text(line.level.to_string())
                                     .size(11)
                                     .width(Length::Fixed(50.0))
-                                    .style(move |_theme| text::Style {
-                                        color: Some(level_color),
-                                    }),
-                                text(&line.message).size(11).style(|_theme| text::Style {
-                                    color: Some(iced::Color::from_rgb(0.9, 0.9, 0.9)),
-                                }),
+                                    .style(theme::text_color(level_color)),
+                                text(&line.message)
+                                    .size(11)
+                                    .style(theme::text_color(|p| p.terminal_text)),
                             ]
                             .spacing(8)
                             .into()
@@ -459,9 +854,7 @@ fn view_log_viewer(state: &AppState) -> Element<'_, Message> {
             filter_level
         ))
         .size(11)
-        .style(|_theme| text::Style {
-            color: Some(theme::colors::TEXT_MUTED),
-        }),
+        .style(theme::text_color(|p| p.text_muted)),
     ]
     .spacing(4)
     .into()
@@ -0,0 +1,297 @@
+//! First-Run Setup Wizard
+//!
+//! Guides a new installation through capability detection, the capture
+//! strategy, portal permissions, the certificate and the listen address,
+//! then saves the configuration and starts the server. Shown instead of the
+//! tabs while open; every step edits the same configuration the tabs do.
+
+use iced::widget::{button, column, container, row, space, text};
+use iced::{Alignment, Element, Length};
+
+use crate::gui::message::Message;
+use crate::gui::state::{AppState, WizardState, WizardStep};
+use crate::gui::theme;
+use crate::gui::widgets;
+
+pub fn view_setup_wizard<'a>(state: &'a AppState, wizard: &'a WizardState) -> Element<'a, Message> {
+    let step = wizard.step;
+
+    let body = match step {
+        WizardStep::Detect => view_detect(state),
+        WizardStep::Capture => view_capture(state),
+        WizardStep::Permissions => view_permissions(wizard),
+        WizardStep::Certificate => view_certificate(state),
+        WizardStep::Network => view_network(state),
+        WizardStep::Finish => view_finish(state, wizard),
+    };
+
+    let mut navigation = row![
+        button(text("Skip Setup"))
+            .on_press(Message::CloseSetupWizard)
+            .padding([8, 16])
+            .style(theme::secondary_button_style),
+        space().width(Length::Fill),
+        button(text("Back"))
+            .on_press_maybe(step.previous().map(|_| Message::WizardBack))
+            .padding([8, 16])
+            .style(theme::secondary_button_style),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    navigation = if step.next().is_some() {
+        navigation.push(
+            button(text("Next"))
+                .on_press(Message::WizardNext)
+                .padding([8, 16])
+                .style(theme::primary_button_style),
+        )
+    } else {
+        navigation
+            .push(
+                button(text("Save"))
+                    .on_press(Message::WizardFinish {
+                        start_server: false,
+                    })
+                    .padding([8, 16])
+                    .style(theme::secondary_button_style),
+            )
+            .push(
+                button(text("Save and Start Server"))
+                    .on_press(Message::WizardFinish { start_server: true })
+                    .padding([8, 16])
+                    .style(theme::primary_button_style),
+            )
+    };
+
+    column![
+        widgets::section_header("First-Run Setup"),
+        space().height(8.0),
+        view_step_indicator(step),
+        space().height(20.0),
+        container(body)
+            .padding(16)
+            .width(Length::Fill)
+            .style(theme::section_container_style),
+        space().height(20.0),
+        navigation,
+    ]
+    .spacing(0)
+    .padding(20)
+    .into()
+}
+
+/// Step names, the current one highlighted
+fn view_step_indicator(current: WizardStep) -> Element<'static, Message> {
+    let mut steps = row![].spacing(16);
+    for (i, step) in WizardStep::all().iter().enumerate() {
+        let color: fn(&theme::Palette) -> iced::Color = if *step == current {
+            |p| p.primary
+        } else {
+            |p| p.text_muted
+        };
+        steps = steps.push(
+            text(format!("{}. {}", i + 1, step.display_name()))
+                .size(13)
+                .style(theme::text_color(color)),
+        );
+    }
+    steps.into()
+}
+
+fn view_detect(state: &AppState) -> Element<'_, Message> {
+    let mut content = column![
+        widgets::subsection_header("Desktop Capabilities"),
+        text("What the compositor and its XDG portals offer for remote desktop.").size(13),
+        space().height(8.0),
+    ]
+    .spacing(6);
+
+    let Some(ref caps) = state.detected_capabilities else {
+        return content
+            .push(widgets::info_box("Detecting capabilities..."))
+            .into();
+    };
+
+    let version =
+        |v: Option<u32>| v.map_or_else(|| "not available".to_string(), |v| format!("v{}", v));
+    let rows = [
+        ("Compositor:", caps.compositor_name.clone()),
+        (
+            "Portal:",
+            format!("{} (v{})", caps.portal_backend, caps.portal_version),
+        ),
+        ("ScreenCast:", version(caps.screencast_version)),
+        ("RemoteDesktop:", version(caps.remote_desktop_version)),
+        ("Deployment:", caps.deployment_context.to_string()),
+        ("Persistence:", caps.persistence_strategy.clone()),
+    ];
+    for (label, value) in rows {
+        content = content.push(widgets::labeled_row(label, 130.0, text(value).into()));
+    }
+
+    if caps.screencast_version.is_none() {
+        content = content.push(widgets::error_box(
+            "No ScreenCast portal: the screen cannot be captured through the portal",
+        ));
+    }
+    if caps.remote_desktop_version.is_none() {
+        content = content.push(widgets::warning_box(
+            "No RemoteDesktop portal: remote keyboard and mouse input will not work",
+        ));
+    }
+    for quirk in &caps.quirks {
+        content = content.push(widgets::warning_box(&quirk.description));
+    }
+
+    content
+        .push(space().height(8.0))
+        .push(
+            button(text("Detect Again"))
+                .on_press(Message::RefreshCapabilities)
+                .padding([6, 12])
+                .style(theme::secondary_button_style),
+        )
+        .into()
+}
+
+fn view_capture(state: &AppState) -> Element<'_, Message> {
+    let native = state.config.mutter.enabled && state.config.kwin.enabled;
+    column![
+        widgets::subsection_header("Session Strategy"),
+        widgets::toggle_with_help(
+            "Use compositor APIs when available",
+            native,
+            "GNOME (Mutter) and KDE Plasma (KWin) can be captured directly, \
+             without a permission dialog. Off: always use the XDG portal.",
+            Message::WizardNativeCaptureToggled,
+        ),
+        space().height(8.0),
+        widgets::info_box(
+            "wlroots compositors (Sway, Hyprland) are captured with wlr-screencopy \
+             when their portal is missing ([wlr] screencopy in the configuration file).",
+        ),
+    ]
+    .spacing(6)
+    .into()
+}
+
+fn view_permissions(wizard: &WizardState) -> Element<'_, Message> {
+    let mut content = column![
+        widgets::subsection_header("Screen Sharing Permission"),
+        text(
+            "Opens the portal dialog once and stores the restore token, so the \
+             server can start later without anyone at the desktop - the same as \
+             `lamco-rdp-server setup`."
+        )
+        .size(13),
+        space().height(8.0),
+        button(text(if wizard.permissions_running {
+            "Waiting for the dialog..."
+        } else {
+            "Request Permissions"
+        }))
+        .on_press_maybe((!wizard.permissions_running).then_some(Message::WizardRequestPermissions))
+        .padding([8, 16])
+        .style(theme::primary_button_style),
+    ]
+    .spacing(6);
+
+    if let Some(ref report) = wizard.permissions {
+        content = content
+            .push(space().height(8.0))
+            .push(widgets::health_check_list(&report.checks));
+    }
+    content.into()
+}
+
+fn view_certificate(state: &AppState) -> Element<'_, Message> {
+    let usable = state
+        .cert_check
+        .as_ref()
+        .is_some_and(|check| check.is_usable());
+
+    column![
+        widgets::subsection_header("TLS Certificate"),
+        widgets::labeled_row(
+            "Certificate:",
+            130.0,
+            text(state.config.security.cert_path.display().to_string()).into(),
+        ),
+        widgets::labeled_row(
+            "Private key:",
+            130.0,
+            text(state.config.security.key_path.display().to_string()).into(),
+        ),
+        space().height(8.0),
+        super::security::view_cert_check(state),
+        space().height(8.0),
+        button(text(if usable {
+            "Replace with New Self-Signed Certificate"
+        } else {
+            "Generate Self-Signed Certificate"
+        }))
+        .on_press(Message::WizardGenerateCertificate)
+        .padding([8, 16])
+        .style(if usable {
+            theme::secondary_button_style
+        } else {
+            theme::primary_button_style
+        }),
+        text("Other paths or a CA-issued certificate can be set in the Security tab.")
+            .size(12)
+            .style(theme::text_color(|p| p.text_muted)),
+    ]
+    .spacing(6)
+    .into()
+}
+
+fn view_network(state: &AppState) -> Element<'_, Message> {
+    column![
+        widgets::subsection_header("Listen Address"),
+        widgets::labeled_row_with_help(
+            "Address:",
+            130.0,
+            widgets::address_input(
+                &state.edit_strings.server_ip,
+                &state.edit_strings.server_port,
+                Message::ServerListenAddrChanged,
+                Message::ServerPortChanged,
+            ),
+            "0.0.0.0 accepts connections on all interfaces; 3389 is the standard RDP port",
+        ),
+    ]
+    .spacing(6)
+    .into()
+}
+
+fn view_finish<'a>(state: &'a AppState, wizard: &'a WizardState) -> Element<'a, Message> {
+    let capture = if state.config.mutter.enabled && state.config.kwin.enabled {
+        "Compositor APIs when available, portal otherwise"
+    } else {
+        "XDG portal"
+    };
+    let permissions = match wizard.permissions {
+        Some(ref report) if report.is_healthy() => "Granted, restore token stored",
+        Some(_) => "Incomplete - see the Permissions step",
+        None => "Not requested - the dialog appears on first connection",
+    };
+    let certificate = match state.cert_check {
+        Some(ref check) if check.is_usable() => "Valid",
+        Some(_) => "Not usable - see the Certificate step",
+        None => "Not checked",
+    };
+
+    let rows = [
+        ("Configuration:", state.config_path.display().to_string()),
+        ("Listen address:", state.config.server.listen_addr.clone()),
+        ("Capture:", capture.to_string()),
+        ("Permissions:", permissions.to_string()),
+        ("Certificate:", certificate.to_string()),
+    ];
+    let mut content = column![widgets::subsection_header("Summary")].spacing(6);
+    for (label, value) in rows {
+        content = content.push(widgets::labeled_row(label, 130.0, text(value).into()));
+    }
+    content.into()
+}
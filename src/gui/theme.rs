@@ -1,52 +1,234 @@
-//! Visual theme matching the lamco brand palette.
+//! Visual theme matching the lamco brand palette, in light and dark variants.
+//!
+//! Styles look their colors up in the [`Palette`] matching the active iced
+//! theme ([`Palette::of`]), so switching between light and dark restyles
+//! every widget. Which variant is active follows [`ThemeMode`]: the
+//! desktop's color-scheme preference, or a fixed choice.
 
-use iced::widget::{button, container};
+use std::fmt;
+
+use iced::widget::{button, container, text};
 use iced::{Background, Border, Color, Shadow, Theme};
+use serde::{Deserialize, Serialize};
+
+/// Light or dark appearance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Follow the desktop's color-scheme preference
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::System, ThemeMode::Light, ThemeMode::Dark];
+
+    /// Whether to appear dark, given the desktop's preference
+    pub fn is_dark(self, system_prefers_dark: bool) -> bool {
+        match self {
+            ThemeMode::System => system_prefers_dark,
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+        }
+    }
+}
+
+impl fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeMode::System => write!(f, "System theme"),
+            ThemeMode::Light => write!(f, "Light"),
+            ThemeMode::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+/// The iced theme for a light or dark appearance
+///
+/// Widgets without a style of their own (inputs, pick lists, scrollbars)
+/// take their colors from it.
+pub fn iced_theme(dark: bool) -> Theme {
+    if dark {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
 
 /// Colors follow a neutral base with blue accent, matching admin console branding.
-pub mod colors {
-    use iced::Color;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub primary: Color,
+    pub primary_light: Color,
+    pub primary_dark: Color,
 
-    pub const PRIMARY: Color = Color::from_rgb(0.2, 0.4, 0.8);
-    pub const PRIMARY_LIGHT: Color = Color::from_rgb(0.4, 0.6, 0.9);
-    pub const PRIMARY_DARK: Color = Color::from_rgb(0.1, 0.3, 0.6);
+    pub surface: Color,
+    pub surface_dark: Color,
+    pub background: Color,
+    /// Pressed controls on a surface
+    pub surface_pressed: Color,
+    /// Outline of controls
+    pub border: Color,
+    /// Outline of containers, lighter than controls
+    pub border_light: Color,
+    pub shadow: Color,
 
-    pub const SURFACE: Color = Color::from_rgb(1.0, 1.0, 1.0);
-    pub const SURFACE_DARK: Color = Color::from_rgb(0.96, 0.96, 0.98);
-    pub const BACKGROUND: Color = Color::from_rgb(0.94, 0.94, 0.96);
+    pub text_primary: Color,
+    pub text_secondary: Color,
+    pub text_muted: Color,
+    /// Text on primary and danger buttons
+    pub text_on_accent: Color,
 
-    pub const TEXT_PRIMARY: Color = Color::from_rgb(0.1, 0.1, 0.15);
-    pub const TEXT_SECONDARY: Color = Color::from_rgb(0.4, 0.4, 0.5);
-    pub const TEXT_MUTED: Color = Color::from_rgb(0.6, 0.6, 0.65);
+    pub disabled: Color,
+    pub disabled_text: Color,
 
-    pub const SUCCESS: Color = Color::from_rgb(0.2, 0.7, 0.3);
-    pub const WARNING: Color = Color::from_rgb(0.9, 0.6, 0.0);
-    pub const ERROR: Color = Color::from_rgb(0.9, 0.2, 0.2);
-    pub const INFO: Color = Color::from_rgb(0.2, 0.5, 0.9);
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub error_light: Color,
+    pub error_dark: Color,
+    pub info: Color,
 
     /// Service registry levels - green/amber/yellow/gray for at-a-glance status
-    pub const GUARANTEED: Color = Color::from_rgb(0.0, 0.7, 0.3);
-    pub const BEST_EFFORT: Color = Color::from_rgb(1.0, 0.6, 0.0);
-    pub const DEGRADED: Color = Color::from_rgb(0.9, 0.7, 0.0);
-    pub const UNAVAILABLE: Color = Color::from_rgb(0.5, 0.5, 0.5);
-
-    pub const LOG_ERROR: Color = Color::from_rgb(0.9, 0.0, 0.0);
-    pub const LOG_WARN: Color = Color::from_rgb(0.9, 0.7, 0.0);
-    pub const LOG_INFO: Color = Color::from_rgb(0.0, 0.0, 0.0);
-    pub const LOG_DEBUG: Color = Color::from_rgb(0.3, 0.3, 0.8);
-    pub const LOG_TRACE: Color = Color::from_rgb(0.5, 0.5, 0.5);
-
-    pub const TAB_ACTIVE: Color = PRIMARY;
-    pub const TAB_INACTIVE: Color = Color::from_rgb(0.7, 0.7, 0.75);
-    pub const TAB_HOVER: Color = PRIMARY_LIGHT;
+    pub guaranteed: Color,
+    pub best_effort: Color,
+    pub degraded: Color,
+    pub unavailable: Color,
+
+    /// Log viewer, dark in both variants for terminal-like readability
+    pub terminal_background: Color,
+    pub terminal_border: Color,
+    pub terminal_text: Color,
+
+    pub log_error: Color,
+    pub log_warn: Color,
+    pub log_info: Color,
+    pub log_debug: Color,
+    pub log_trace: Color,
+
+    pub tab_inactive: Color,
 }
 
-pub fn primary_button_style(_theme: &Theme, status: button::Status) -> button::Style {
+impl Palette {
+    pub const LIGHT: Palette = Palette {
+        primary: Color::from_rgb(0.2, 0.4, 0.8),
+        primary_light: Color::from_rgb(0.4, 0.6, 0.9),
+        primary_dark: Color::from_rgb(0.1, 0.3, 0.6),
+
+        surface: Color::from_rgb(1.0, 1.0, 1.0),
+        surface_dark: Color::from_rgb(0.96, 0.96, 0.98),
+        background: Color::from_rgb(0.94, 0.94, 0.96),
+        surface_pressed: Color::from_rgb(0.9, 0.9, 0.92),
+        border: Color::from_rgb(0.8, 0.8, 0.85),
+        border_light: Color::from_rgb(0.88, 0.88, 0.9),
+        shadow: Color::from_rgba(0.0, 0.0, 0.0, 0.05),
+
+        text_primary: Color::from_rgb(0.1, 0.1, 0.15),
+        text_secondary: Color::from_rgb(0.4, 0.4, 0.5),
+        text_muted: Color::from_rgb(0.6, 0.6, 0.65),
+        text_on_accent: Color::WHITE,
+
+        disabled: Color::from_rgb(0.7, 0.7, 0.7),
+        disabled_text: Color::from_rgb(0.5, 0.5, 0.5),
+
+        success: Color::from_rgb(0.2, 0.7, 0.3),
+        warning: Color::from_rgb(0.9, 0.6, 0.0),
+        error: Color::from_rgb(0.9, 0.2, 0.2),
+        error_light: Color::from_rgb(1.0, 0.3, 0.3),
+        error_dark: Color::from_rgb(0.7, 0.1, 0.1),
+        info: Color::from_rgb(0.2, 0.5, 0.9),
+
+        guaranteed: Color::from_rgb(0.0, 0.7, 0.3),
+        best_effort: Color::from_rgb(1.0, 0.6, 0.0),
+        degraded: Color::from_rgb(0.9, 0.7, 0.0),
+        unavailable: Color::from_rgb(0.5, 0.5, 0.5),
+
+        terminal_background: Color::from_rgb(0.12, 0.12, 0.14),
+        terminal_border: Color::from_rgb(0.2, 0.2, 0.22),
+        terminal_text: Color::from_rgb(0.9, 0.9, 0.9),
+
+        log_error: Color::from_rgb(0.9, 0.0, 0.0),
+        log_warn: Color::from_rgb(0.9, 0.7, 0.0),
+        log_info: Color::from_rgb(0.88, 0.88, 0.9),
+        log_debug: Color::from_rgb(0.3, 0.3, 0.8),
+        log_trace: Color::from_rgb(0.5, 0.5, 0.5),
+
+        tab_inactive: Color::from_rgb(0.7, 0.7, 0.75),
+    };
+
+    /// Same hues, brightened where they sit on dark surfaces
+    pub const DARK: Palette = Palette {
+        primary: Color::from_rgb(0.3, 0.5, 0.9),
+        primary_light: Color::from_rgb(0.45, 0.63, 0.95),
+        primary_dark: Color::from_rgb(0.18, 0.36, 0.72),
+
+        surface: Color::from_rgb(0.17, 0.18, 0.21),
+        surface_dark: Color::from_rgb(0.14, 0.15, 0.17),
+        background: Color::from_rgb(0.11, 0.11, 0.13),
+        surface_pressed: Color::from_rgb(0.22, 0.23, 0.27),
+        border: Color::from_rgb(0.32, 0.33, 0.38),
+        border_light: Color::from_rgb(0.25, 0.26, 0.3),
+        shadow: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+
+        text_primary: Color::from_rgb(0.9, 0.9, 0.93),
+        text_secondary: Color::from_rgb(0.68, 0.68, 0.75),
+        text_muted: Color::from_rgb(0.5, 0.5, 0.56),
+        text_on_accent: Color::WHITE,
+
+        disabled: Color::from_rgb(0.3, 0.3, 0.33),
+        disabled_text: Color::from_rgb(0.55, 0.55, 0.58),
+
+        success: Color::from_rgb(0.3, 0.78, 0.4),
+        warning: Color::from_rgb(0.95, 0.68, 0.15),
+        error: Color::from_rgb(0.92, 0.33, 0.33),
+        error_light: Color::from_rgb(1.0, 0.45, 0.45),
+        error_dark: Color::from_rgb(0.72, 0.18, 0.18),
+        info: Color::from_rgb(0.35, 0.6, 0.95),
+
+        guaranteed: Color::from_rgb(0.2, 0.8, 0.45),
+        best_effort: Color::from_rgb(1.0, 0.65, 0.15),
+        degraded: Color::from_rgb(0.95, 0.78, 0.2),
+        unavailable: Color::from_rgb(0.55, 0.55, 0.58),
+
+        terminal_background: Color::from_rgb(0.08, 0.08, 0.09),
+        terminal_border: Color::from_rgb(0.25, 0.26, 0.3),
+        terminal_text: Color::from_rgb(0.9, 0.9, 0.9),
+
+        log_error: Color::from_rgb(1.0, 0.35, 0.35),
+        log_warn: Color::from_rgb(0.95, 0.78, 0.2),
+        log_info: Color::from_rgb(0.88, 0.88, 0.9),
+        log_debug: Color::from_rgb(0.55, 0.6, 1.0),
+        log_trace: Color::from_rgb(0.6, 0.6, 0.62),
+
+        tab_inactive: Color::from_rgb(0.4, 0.4, 0.45),
+    };
+
+    /// Palette of the active iced theme
+    pub fn of(theme: &Theme) -> &'static Palette {
+        if theme.extended_palette().is_dark {
+            &Palette::DARK
+        } else {
+            &Palette::LIGHT
+        }
+    }
+}
+
+/// Text in a palette color: `.style(theme::text_color(|p| p.text_muted))`
+pub fn text_color(color: fn(&Palette) -> Color) -> impl Fn(&Theme) -> text::Style {
+    move |theme: &Theme| text::Style {
+        color: Some(color(Palette::of(theme))),
+    }
+}
+
+pub fn primary_button_style(theme: &Theme, status: button::Status) -> button::Style {
+    let palette = Palette::of(theme);
     let base = button::Style {
-        background: Some(Background::Color(colors::PRIMARY)),
-        text_color: Color::WHITE,
+        background: Some(Background::Color(palette.primary)),
+        text_color: palette.text_on_accent,
         border: Border {
-            color: colors::PRIMARY_DARK,
+            color: palette.primary_dark,
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -57,27 +239,28 @@ pub fn primary_button_style(_theme: &Theme, status: button::Status) -> button::S
     match status {
         button::Status::Active => base,
         button::Status::Hovered => button::Style {
-            background: Some(Background::Color(colors::PRIMARY_LIGHT)),
+            background: Some(Background::Color(palette.primary_light)),
             ..base
         },
         button::Status::Pressed => button::Style {
-            background: Some(Background::Color(colors::PRIMARY_DARK)),
+            background: Some(Background::Color(palette.primary_dark)),
             ..base
         },
         button::Status::Disabled => button::Style {
-            background: Some(Background::Color(Color::from_rgb(0.7, 0.7, 0.7))),
-            text_color: Color::from_rgb(0.5, 0.5, 0.5),
+            background: Some(Background::Color(palette.disabled)),
+            text_color: palette.disabled_text,
             ..base
         },
     }
 }
 
-pub fn secondary_button_style(_theme: &Theme, status: button::Status) -> button::Style {
+pub fn secondary_button_style(theme: &Theme, status: button::Status) -> button::Style {
+    let palette = Palette::of(theme);
     let base = button::Style {
-        background: Some(Background::Color(colors::SURFACE)),
-        text_color: colors::TEXT_PRIMARY,
+        background: Some(Background::Color(palette.surface)),
+        text_color: palette.text_primary,
         border: Border {
-            color: Color::from_rgb(0.8, 0.8, 0.85),
+            color: palette.border,
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -88,31 +271,32 @@ pub fn secondary_button_style(_theme: &Theme, status: button::Status) -> button:
     match status {
         button::Status::Active => base,
         button::Status::Hovered => button::Style {
-            background: Some(Background::Color(colors::SURFACE_DARK)),
+            background: Some(Background::Color(palette.surface_dark)),
             border: Border {
-                color: colors::PRIMARY,
+                color: palette.primary,
                 ..base.border
             },
             ..base
         },
         button::Status::Pressed => button::Style {
-            background: Some(Background::Color(Color::from_rgb(0.9, 0.9, 0.92))),
+            background: Some(Background::Color(palette.surface_pressed)),
             ..base
         },
         button::Status::Disabled => button::Style {
-            background: Some(Background::Color(Color::from_rgb(0.95, 0.95, 0.95))),
-            text_color: Color::from_rgb(0.6, 0.6, 0.6),
+            background: Some(Background::Color(palette.surface_dark)),
+            text_color: palette.text_muted,
             ..base
         },
     }
 }
 
-pub fn danger_button_style(_theme: &Theme, status: button::Status) -> button::Style {
+pub fn danger_button_style(theme: &Theme, status: button::Status) -> button::Style {
+    let palette = Palette::of(theme);
     let base = button::Style {
-        background: Some(Background::Color(colors::ERROR)),
-        text_color: Color::WHITE,
+        background: Some(Background::Color(palette.error)),
+        text_color: palette.text_on_accent,
         border: Border {
-            color: Color::from_rgb(0.7, 0.1, 0.1),
+            color: palette.error_dark,
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -123,32 +307,36 @@ pub fn danger_button_style(_theme: &Theme, status: button::Status) -> button::St
     match status {
         button::Status::Active => base,
         button::Status::Hovered => button::Style {
-            background: Some(Background::Color(Color::from_rgb(1.0, 0.3, 0.3))),
+            background: Some(Background::Color(palette.error_light)),
             ..base
         },
         button::Status::Pressed => button::Style {
-            background: Some(Background::Color(Color::from_rgb(0.7, 0.1, 0.1))),
+            background: Some(Background::Color(palette.error_dark)),
             ..base
         },
         button::Status::Disabled => button::Style {
-            background: Some(Background::Color(Color::from_rgb(0.8, 0.5, 0.5))),
-            text_color: Color::from_rgb(0.9, 0.9, 0.9),
+            background: Some(Background::Color(Color {
+                a: 0.5,
+                ..palette.error
+            })),
+            text_color: palette.disabled_text,
             ..base
         },
     }
 }
 
 pub fn tab_button_style(active: bool) -> impl Fn(&Theme, button::Status) -> button::Style {
-    move |_theme: &Theme, status: button::Status| {
+    move |theme: &Theme, status: button::Status| {
+        let palette = Palette::of(theme);
         let base_bg = if active {
-            colors::TAB_ACTIVE
+            palette.primary
         } else {
             Color::TRANSPARENT
         };
         let text_color = if active {
-            Color::WHITE
+            palette.text_on_accent
         } else {
-            colors::TEXT_SECONDARY
+            palette.text_secondary
         };
 
         let base = button::Style {
@@ -167,16 +355,16 @@ pub fn tab_button_style(active: bool) -> impl Fn(&Theme, button::Status) -> butt
             button::Status::Active => base,
             button::Status::Hovered => button::Style {
                 background: Some(Background::Color(if active {
-                    colors::TAB_ACTIVE
+                    palette.primary
                 } else {
-                    colors::TAB_HOVER
+                    palette.primary_light
                 })),
-                text_color: Color::WHITE,
+                text_color: palette.text_on_accent,
                 ..base
             },
             button::Status::Pressed => button::Style {
-                background: Some(Background::Color(colors::PRIMARY_DARK)),
-                text_color: Color::WHITE,
+                background: Some(Background::Color(palette.primary_dark)),
+                text_color: palette.text_on_accent,
                 ..base
             },
             button::Status::Disabled => base,
@@ -184,17 +372,53 @@ pub fn tab_button_style(active: bool) -> impl Fn(&Theme, button::Status) -> butt
     }
 }
 
-pub fn section_container_style(_theme: &Theme) -> container::Style {
+pub fn header_style(theme: &Theme) -> container::Style {
+    container::Style {
+        background: Some(Background::Color(Palette::of(theme).surface)),
+        ..Default::default()
+    }
+}
+
+pub fn tab_bar_style(theme: &Theme) -> container::Style {
+    container::Style {
+        background: Some(Background::Color(Palette::of(theme).surface_dark)),
+        ..Default::default()
+    }
+}
+
+/// Behind the tab content, setting the sections apart
+pub fn content_style(theme: &Theme) -> container::Style {
+    container::Style {
+        background: Some(Background::Color(Palette::of(theme).background)),
+        ..Default::default()
+    }
+}
+
+pub fn footer_style(theme: &Theme) -> container::Style {
+    let palette = Palette::of(theme);
+    container::Style {
+        background: Some(Background::Color(palette.surface)),
+        border: Border {
+            color: palette.surface_dark,
+            width: 1.0,
+            radius: 0.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn section_container_style(theme: &Theme) -> container::Style {
+    let palette = Palette::of(theme);
     container::Style {
-        background: Some(Background::Color(colors::SURFACE)),
+        background: Some(Background::Color(palette.surface)),
         border: Border {
-            color: Color::from_rgb(0.88, 0.88, 0.9),
+            color: palette.border_light,
             width: 1.0,
             radius: 8.0.into(),
         },
-        text_color: Some(colors::TEXT_PRIMARY),
+        text_color: Some(palette.text_primary),
         shadow: Shadow {
-            color: Color::from_rgba(0.0, 0.0, 0.0, 0.05),
+            color: palette.shadow,
             offset: iced::Vector::new(0.0, 2.0),
             blur_radius: 4.0,
         },
@@ -203,29 +427,31 @@ pub fn section_container_style(_theme: &Theme) -> container::Style {
 }
 
 /// Dark background for terminal-like readability.
-pub fn log_viewer_style(_theme: &Theme) -> container::Style {
+pub fn log_viewer_style(theme: &Theme) -> container::Style {
+    let palette = Palette::of(theme);
     container::Style {
-        background: Some(Background::Color(Color::from_rgb(0.12, 0.12, 0.14))),
+        background: Some(Background::Color(palette.terminal_background)),
         border: Border {
-            color: Color::from_rgb(0.2, 0.2, 0.22),
+            color: palette.terminal_border,
             width: 1.0,
             radius: 4.0.into(),
         },
-        text_color: Some(Color::from_rgb(0.9, 0.9, 0.9)),
+        text_color: Some(palette.terminal_text),
         shadow: Shadow::default(),
         snap: false,
     }
 }
 
-pub fn collapsible_header_style(_theme: &Theme) -> container::Style {
+pub fn collapsible_header_style(theme: &Theme) -> container::Style {
+    let palette = Palette::of(theme);
     container::Style {
-        background: Some(Background::Color(colors::SURFACE_DARK)),
+        background: Some(Background::Color(palette.surface_dark)),
         border: Border {
-            color: Color::from_rgb(0.85, 0.85, 0.88),
+            color: palette.border_light,
             width: 1.0,
             radius: 4.0.into(),
         },
-        text_color: Some(colors::TEXT_PRIMARY),
+        text_color: Some(palette.text_primary),
         shadow: Shadow::default(),
         snap: false,
     }
@@ -233,23 +459,24 @@ pub fn collapsible_header_style(_theme: &Theme) -> container::Style {
 
 /// Mutually-exclusive presets highlight the active choice prominently.
 pub fn preset_button_style(selected: bool) -> impl Fn(&Theme, button::Status) -> button::Style {
-    move |_theme: &Theme, status: button::Status| {
+    move |theme: &Theme, status: button::Status| {
+        let palette = Palette::of(theme);
         let base = button::Style {
             background: Some(Background::Color(if selected {
-                colors::PRIMARY
+                palette.primary
             } else {
-                colors::SURFACE
+                palette.surface
             })),
             text_color: if selected {
-                Color::WHITE
+                palette.text_on_accent
             } else {
-                colors::TEXT_PRIMARY
+                palette.text_primary
             },
             border: Border {
                 color: if selected {
-                    colors::PRIMARY_DARK
+                    palette.primary_dark
                 } else {
-                    Color::from_rgb(0.8, 0.8, 0.85)
+                    palette.border
                 },
                 width: 1.0,
                 radius: 4.0.into(),
@@ -262,52 +489,52 @@ pub fn preset_button_style(selected: bool) -> impl Fn(&Theme, button::Status) ->
             button::Status::Active => base,
             button::Status::Hovered => button::Style {
                 background: Some(Background::Color(if selected {
-                    colors::PRIMARY_LIGHT
+                    palette.primary_light
                 } else {
-                    colors::SURFACE_DARK
+                    palette.surface_dark
                 })),
                 ..base
             },
             button::Status::Pressed => button::Style {
-                background: Some(Background::Color(colors::PRIMARY_DARK)),
-                text_color: Color::WHITE,
+                background: Some(Background::Color(palette.primary_dark)),
+                text_color: palette.text_on_accent,
                 ..base
             },
             button::Status::Disabled => button::Style {
-                background: Some(Background::Color(Color::from_rgb(0.9, 0.9, 0.9))),
-                text_color: Color::from_rgb(0.6, 0.6, 0.6),
+                background: Some(Background::Color(palette.surface_pressed)),
+                text_color: palette.text_muted,
                 ..base
             },
         }
     }
 }
 
-pub fn status_indicator_color(running: bool) -> Color {
+pub fn status_indicator_color(palette: &Palette, running: bool) -> Color {
     if running {
-        colors::SUCCESS
+        palette.success
     } else {
-        colors::ERROR
+        palette.error
     }
 }
 
 /// Maps service registry QoS levels to their canonical indicator colors.
-pub fn service_level_color(level: &str) -> Color {
+pub fn service_level_color(palette: &Palette, level: &str) -> Color {
     match level.to_lowercase().as_str() {
-        "guaranteed" => colors::GUARANTEED,
-        "besteffort" | "best_effort" => colors::BEST_EFFORT,
-        "degraded" => colors::DEGRADED,
-        "unavailable" => colors::UNAVAILABLE,
-        _ => colors::TEXT_SECONDARY,
+        "guaranteed" => palette.guaranteed,
+        "besteffort" | "best_effort" => palette.best_effort,
+        "degraded" => palette.degraded,
+        "unavailable" => palette.unavailable,
+        _ => palette.text_secondary,
     }
 }
 
-pub fn log_level_color(level: &str) -> Color {
+pub fn log_level_color(palette: &Palette, level: &str) -> Color {
     match level.to_lowercase().as_str() {
-        "error" => colors::LOG_ERROR,
-        "warn" | "warning" => colors::LOG_WARN,
-        "info" => colors::LOG_INFO,
-        "debug" => colors::LOG_DEBUG,
-        "trace" => colors::LOG_TRACE,
-        _ => colors::TEXT_PRIMARY,
+        "error" => palette.log_error,
+        "warn" | "warning" => palette.log_warn,
+        "info" => palette.log_info,
+        "debug" => palette.log_debug,
+        "trace" => palette.log_trace,
+        _ => palette.text_primary,
     }
 }
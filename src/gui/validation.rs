@@ -265,7 +265,7 @@ fn validate_video_config(
 /// Validate input configuration
 fn validate_input_config(
     config: &Config,
-    _errors: &mut [ValidationError],
+    errors: &mut Vec<ValidationError>,
     warnings: &mut Vec<ValidationWarning>,
 ) {
     // Validate keyboard layout
@@ -293,6 +293,14 @@ fn validate_input_config(
                 .to_string(),
         });
     }
+
+    // Validate fallback chain
+    if let Err(e) = crate::session::failover::parse_chain(&config.input.fallback_chain) {
+        errors.push(ValidationError {
+            field: "input.fallback_chain".to_string(),
+            message: e.to_string(),
+        });
+    }
 }
 
 /// Validate clipboard configuration
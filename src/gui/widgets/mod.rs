@@ -9,6 +9,7 @@ use iced::{Alignment, Element, Length};
 
 use crate::gui::message::Message;
 use crate::gui::theme;
+use crate::server::{HealthCheck, HealthStatus};
 
 pub fn labeled_row<'a>(
     label: &'a str,
@@ -35,9 +36,7 @@ pub fn labeled_row_with_help<'a>(
             space().width(label_width),
             text(format!("ⓘ {}", help_text))
                 .size(12)
-                .style(|_theme| text::Style {
-                    color: Some(theme::colors::TEXT_MUTED),
-                }),
+                .style(theme::text_color(|p| p.text_muted)),
         ],
     ]
     .spacing(4)
@@ -47,18 +46,14 @@ pub fn labeled_row_with_help<'a>(
 pub fn section_header<'a>(title: &'a str) -> Element<'a, Message> {
     text(title)
         .size(20)
-        .style(|_theme| text::Style {
-            color: Some(theme::colors::PRIMARY),
-        })
+        .style(theme::text_color(|p| p.primary))
         .into()
 }
 
 pub fn subsection_header<'a>(title: &'a str) -> Element<'a, Message> {
     text(title)
         .size(16)
-        .style(|_theme| text::Style {
-            color: Some(theme::colors::TEXT_PRIMARY),
-        })
+        .style(theme::text_color(|p| p.text_primary))
         .into()
 }
 
@@ -77,14 +72,15 @@ pub fn collapsible_header<'a>(
     .on_press(on_toggle)
     .padding([8, 12])
     .width(Length::Fill)
-    .style(|theme, status| {
-        let mut style = theme::collapsible_header_style(theme);
+    .style(|t, status| {
+        let palette = theme::Palette::of(t);
+        let mut style = theme::collapsible_header_style(t);
         if matches!(status, button::Status::Hovered) {
-            style.background = Some(iced::Background::Color(theme::colors::SURFACE_DARK));
+            style.background = Some(iced::Background::Color(palette.surface_dark));
         }
         button::Style {
             background: style.background,
-            text_color: theme::colors::TEXT_PRIMARY,
+            text_color: palette.text_primary,
             border: style.border,
             shadow: style.shadow,
             snap: false,
@@ -122,9 +118,7 @@ pub fn toggle_with_help<'a>(
         .align_y(Alignment::Center),
         text(format!("ⓘ {}", help_text))
             .size(12)
-            .style(|_theme| text::Style {
-                color: Some(theme::colors::TEXT_MUTED),
-            }),
+            .style(theme::text_color(|p| p.text_muted)),
     ]
     .spacing(4)
     .into()
@@ -243,12 +237,12 @@ pub fn info_box<'a>(text_content: &'a str) -> Element<'a, Message> {
             .align_y(Alignment::Center),
     )
     .padding([8, 12])
-    .style(|_theme| container::Style {
+    .style(|t| container::Style {
         background: Some(iced::Background::Color(
-            theme::colors::INFO.scale_alpha(0.1),
+            theme::Palette::of(t).info.scale_alpha(0.1),
         )),
         border: iced::Border {
-            color: theme::colors::INFO.scale_alpha(0.3),
+            color: theme::Palette::of(t).info.scale_alpha(0.3),
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -264,12 +258,12 @@ pub fn warning_box<'a>(text_content: &'a str) -> Element<'a, Message> {
             .align_y(Alignment::Center),
     )
     .padding([8, 12])
-    .style(|_theme| container::Style {
+    .style(|t| container::Style {
         background: Some(iced::Background::Color(
-            theme::colors::WARNING.scale_alpha(0.1),
+            theme::Palette::of(t).warning.scale_alpha(0.1),
         )),
         border: iced::Border {
-            color: theme::colors::WARNING.scale_alpha(0.3),
+            color: theme::Palette::of(t).warning.scale_alpha(0.3),
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -285,12 +279,12 @@ pub fn error_box<'a>(text_content: &'a str) -> Element<'a, Message> {
             .align_y(Alignment::Center),
     )
     .padding([8, 12])
-    .style(|_theme| container::Style {
+    .style(|t| container::Style {
         background: Some(iced::Background::Color(
-            theme::colors::ERROR.scale_alpha(0.1),
+            theme::Palette::of(t).error.scale_alpha(0.1),
         )),
         border: iced::Border {
-            color: theme::colors::ERROR.scale_alpha(0.3),
+            color: theme::Palette::of(t).error.scale_alpha(0.3),
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -306,12 +300,12 @@ pub fn success_box<'a>(text_content: &'a str) -> Element<'a, Message> {
             .align_y(Alignment::Center),
     )
     .padding([8, 12])
-    .style(|_theme| container::Style {
+    .style(|t| container::Style {
         background: Some(iced::Background::Color(
-            theme::colors::SUCCESS.scale_alpha(0.1),
+            theme::Palette::of(t).success.scale_alpha(0.1),
         )),
         border: iced::Border {
-            color: theme::colors::SUCCESS.scale_alpha(0.3),
+            color: theme::Palette::of(t).success.scale_alpha(0.3),
             width: 1.0,
             radius: 4.0.into(),
         },
@@ -320,6 +314,27 @@ pub fn success_box<'a>(text_content: &'a str) -> Element<'a, Message> {
     .into()
 }
 
+/// One row per health check: its name and a status-colored detail box
+pub fn health_check_list<'a>(checks: &'a [HealthCheck]) -> Element<'a, Message> {
+    let mut list = column![].spacing(4);
+    for check in checks {
+        let result = match check.status {
+            HealthStatus::Ok => success_box(&check.detail),
+            HealthStatus::Warn => warning_box(&check.detail),
+            HealthStatus::Fail => error_box(&check.detail),
+        };
+        list = list.push(
+            row![
+                text(check.name).size(13).width(Length::Fixed(100.0)),
+                container(result).width(Length::Fill),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        );
+    }
+    list.into()
+}
+
 pub fn section_container<'a>(content: Element<'a, Message>) -> Element<'a, Message> {
     container(content)
         .padding(20)
@@ -370,11 +385,13 @@ pub fn address_input<'a>(
 }
 
 pub fn status_indicator<'a>(running: bool, status_text: &'a str) -> Element<'a, Message> {
-    let color = theme::status_indicator_color(running);
     row![
-        text("●")
-            .size(16)
-            .style(move |_theme| text::Style { color: Some(color) }),
+        text("●").size(16).style(move |t| text::Style {
+            color: Some(theme::status_indicator_color(
+                theme::Palette::of(t),
+                running
+            )),
+        }),
         text(status_text),
     ]
     .spacing(8)
@@ -383,14 +400,25 @@ pub fn status_indicator<'a>(running: bool, status_text: &'a str) -> Element<'a,
 }
 
 pub fn service_level_badge<'a>(level: &'a str, emoji: &'a str) -> Element<'a, Message> {
-    let color = theme::service_level_color(level);
     row![
         text(emoji).size(14),
-        text(level)
-            .size(13)
-            .style(move |_theme| text::Style { color: Some(color) }),
+        text(level).size(13).style(move |t| text::Style {
+            color: Some(theme::service_level_color(theme::Palette::of(t), level)),
+        }),
     ]
     .spacing(4)
     .align_y(Alignment::Center)
     .into()
 }
+
+/// What a remote client is doing: highlighted while `active`, dimmed otherwise
+pub fn activity_indicator<'a>(label: &'a str, active: bool) -> Element<'a, Message> {
+    text(label)
+        .size(12)
+        .style(theme::text_color(if active {
+            |p| p.success
+        } else {
+            |p| p.text_muted
+        }))
+        .into()
+}
@@ -206,6 +206,16 @@ pub mod mutter;
 #[cfg(feature = "gui")]
 pub mod gui;
 
+/// Loopback test client (optional)
+///
+/// Headless RDP client that connects to a running server and checks the
+/// handshake, graphics updates, input and clipboard end to end. Used by
+/// `lamco-rdp-server selftest` and the loopback integration tests.
+///
+/// Requires the `test-client` feature to be enabled.
+#[cfg(feature = "test-client")]
+pub mod test_client;
+
 // =============================================================================
 // Re-exports from published lamco crates (for convenience)
 // =============================================================================
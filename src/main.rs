@@ -2,13 +2,22 @@
 //!
 //! Entry point for the server binary.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
+use std::path::{Path, PathBuf};
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-use lamco_rdp_server::config::Config;
-use lamco_rdp_server::server::LamcoRdpServer;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+use lamco_rdp_server::config::{migrate, tunable, Config, Severity, CONFIG_VERSION, ENV_PREFIX};
+use lamco_rdp_server::performance::{
+    hardware_encoder_available, HardwareProbe, PerformanceProfile,
+};
+use lamco_rdp_server::server::{
+    AdminClient, CaptureLost, HandoffState, HealthChecker, HealthStatus, LamcoRdpServer,
+};
+use lamco_rdp_server::utils::{
+    chaos, crash_report, DiagBundle, RecentLogLayer, RecentLogs, SessionLogDir, SessionLogLayer,
+};
 
 /// Command-line arguments for lamco-rdp-server
 #[derive(Parser, Debug)]
@@ -23,9 +32,16 @@ pub struct Args {
     #[arg(short, long, env = "LAMCO_RDP_LISTEN_ADDR")]
     pub listen: Option<String>,
 
-    /// Listen port
-    #[arg(short, long, env = "LAMCO_RDP_PORT", default_value = "3389")]
-    pub port: u16,
+    /// Listen port (default: the port of `server.listen_addr`, 3389)
+    #[arg(short, long, env = "LAMCO_RDP_PORT")]
+    pub port: Option<u16>,
+
+    /// Dial out to a waiting broker instead of listening (host:port)
+    ///
+    /// Keeps a persistent control connection to the broker and serves RDP
+    /// sessions over connections it requests. Useful behind NAT.
+    #[arg(long, env = "LAMCO_RDP_CONNECT", value_name = "HOST:PORT")]
+    pub connect: Option<String>,
 
     /// Verbose logging (can be specified multiple times)
     #[arg(short, long, action = clap::ArgAction::Count)]
@@ -71,14 +87,263 @@ pub struct Args {
     /// and other components. Helpful for troubleshooting setup issues.
     #[arg(long)]
     pub diagnose: bool,
+
+    /// Measure this machine again instead of using the cached profile
+    ///
+    /// The startup benchmark behind `performance.auto_profile` is cached
+    /// until the CPU or server version changes.
+    #[arg(long)]
+    pub reprofile: bool,
+
+    /// Print the effective configuration and chosen strategies and exit
+    ///
+    /// Resolves the config file, its runtime drop-in, LAMCO_RDP_* variables
+    /// and command-line options the way a start would, probes the
+    /// compositor, portal and GPU, and prints the result as toml (default)
+    /// or json. Secrets are redacted.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "toml"
+    )]
+    pub dry_run: Option<DryRunFormat>,
+
+    /// Subcommand (default: run the server)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Run health checks and exit (status 0 = healthy, 1 = a check failed)
+    ///
+    /// Checks Portal availability, PipeWire connectivity, encoder creation,
+    /// certificate validity and the listening socket, and prints the result
+    /// as JSON (the same report `GET /healthz` serves). An invalid config
+    /// file fails the check before any probe runs.
+    Check,
+
+    /// Grant portal permissions once and report readiness for unattended use
+    ///
+    /// Creates a session the way the server would, so the permission dialog
+    /// appears and the restore token is stored, then checks capture, input
+    /// and clipboard. Run it from the desktop session before enabling the
+    /// service (status 0 = ready, 1 = a check failed).
+    Setup {
+        /// Discard the stored restore token so the dialog appears again
+        #[arg(long)]
+        reset: bool,
+    },
+
+    /// List, message or disconnect the clients of the running server
+    ///
+    /// Talks to the server's admin API, which must be enabled with a token
+    /// in `[admin_api]` of the same configuration file.
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsCommand,
+    },
+
+    /// Check the configuration file or bring it up to date
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Collect a diagnostics bundle for bug reports
+    ///
+    /// Writes system, compositor, portal, GPU and PipeWire information, the
+    /// effective configuration (secrets redacted), self-test results and
+    /// recent logs into one tarball. Logs are included as written; look
+    /// them over before attaching the bundle to a public report.
+    Diag {
+        /// Directory to write the tarball to
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+
+        /// Lines to keep from each log
+        #[arg(long, default_value_t = 2000)]
+        log_lines: usize,
+    },
+
+    /// Time damage detection, color conversion and every encoder built in
+    ///
+    /// Runs synthetic frames through each pipeline stage and prints
+    /// per-frame latency and the frame rate each stage sustains, so hardware
+    /// and software encoding can be compared on this machine. Uses the
+    /// `[hardware_encoding]` settings of the configuration file.
+    Bench {
+        /// Frame size, WIDTHxHEIGHT
+        #[arg(long, default_value = "1920x1080")]
+        resolution: String,
+
+        /// Frames timed per stage
+        #[arg(long, default_value_t = 120)]
+        frames: u32,
+
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List a PDU capture and replay it through the server's handlers
+    ///
+    /// Reads a file recorded with `logging.pdu_capture` and feeds the
+    /// clipboard, input and graphics capability PDUs to fresh handlers,
+    /// without a client or a desktop session, then reports the negotiated
+    /// codec and any keys left pressed.
+    Replay {
+        /// Capture file
+        file: PathBuf,
+
+        /// Keep the gaps between records instead of replaying at once
+        #[arg(long)]
+        realtime: bool,
+    },
+
+    /// Connect to the running server with the built-in test client
+    ///
+    /// Runs a real session against the server and checks the handshake,
+    /// graphics updates, input and clipboard, printing the result as JSON
+    /// (status 0 = every check passed, 1 = a check failed).
+    #[cfg(feature = "test-client")]
+    Selftest {
+        /// Server to connect to (default: `server.listen_addr`, on localhost
+        /// when it listens on all addresses)
+        #[arg(long, value_name = "HOST:PORT")]
+        address: Option<String>,
+
+        /// User name to log in as
+        #[arg(long, default_value = "")]
+        username: String,
+
+        /// Password to log in with
+        #[arg(
+            long,
+            env = "LAMCO_RDP_SELFTEST_PASSWORD",
+            default_value = "",
+            hide_env_values = true
+        )]
+        password: String,
+
+        /// Graphics updates to wait for
+        #[arg(long, default_value_t = 3)]
+        frames: usize,
+
+        /// Seconds to wait for the checks
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
+    },
+}
+
+/// `config` subcommands
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Report syntax errors, unknown keys, out-of-range values and
+    /// conflicting settings with their line (status 0 = usable, 1 = errors)
+    Validate,
+
+    /// Rewrite a file of an older version for this one, keeping the old
+    /// file as `<config>.bak` (comments are not carried over)
+    Migrate {
+        /// Only list the changes
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Output format of `--dry-run`
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum DryRunFormat {
+    Toml,
+    Json,
+}
+
+/// `sessions` subcommands
+#[derive(clap::Subcommand, Debug)]
+pub enum SessionsCommand {
+    /// List connected clients with their resource usage
+    List,
+
+    /// Disconnect a client
+    Kill {
+        /// Client ID, as shown by `sessions list`
+        id: u64,
+    },
+
+    /// Show a message on a client's screen
+    Message {
+        /// Client ID, as shown by `sessions list`
+        id: u64,
+
+        /// Text to show (up to 300 characters)
+        text: String,
+
+        /// Seconds to show it for (server default: 10)
+        #[arg(long)]
+        duration: Option<u64>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    // Keep stdout machine-readable: no logging for health checks
+    if let Some(Command::Check) = args.command {
+        return run_health_check(&args).await;
+    }
+    if let Some(Command::Sessions { ref action }) = args.command {
+        return run_sessions(&args, action).await;
+    }
+    if let Some(Command::Config { ref action }) = args.command {
+        return run_config(&args, action);
+    }
+    if let Some(Command::Diag {
+        ref output,
+        log_lines,
+    }) = args.command
+    {
+        return run_diag(&args, output, log_lines).await;
+    }
+    if let Some(Command::Bench {
+        ref resolution,
+        frames,
+        json,
+    }) = args.command
+    {
+        return run_bench(&args, resolution, frames, json).await;
+    }
+    if let Some(Command::Replay { ref file, realtime }) = args.command {
+        return run_replay(file, realtime);
+    }
+    #[cfg(feature = "test-client")]
+    if let Some(Command::Selftest {
+        ref address,
+        ref username,
+        ref password,
+        frames,
+        timeout,
+    }) = args.command
+    {
+        return run_selftest(
+            &args,
+            address.as_deref(),
+            username,
+            password,
+            frames,
+            timeout,
+        )
+        .await;
+    }
+    if let Some(format) = args.dry_run {
+        return run_dry_run(&args, format).await;
+    }
+
     // Initialize logging
-    init_logging(&args)?;
+    let (log_filter_handle, session_log_dir, recent_logs) = init_logging(&args)?;
 
     info!("════════════════════════════════════════════════════════");
     info!("  lamco-rdp-server v{}", env!("CARGO_PKG_VERSION"));
@@ -110,7 +375,7 @@ async fn main() -> Result<()> {
     }
 
     if args.diagnose {
-        return run_diagnostics().await;
+        return run_diagnostics(&args).await;
     }
 
     if args.clear_tokens {
@@ -121,34 +386,133 @@ async fn main() -> Result<()> {
         return grant_permission_flow().await;
     }
 
+    if let Some(Command::Setup { reset }) = args.command {
+        return run_setup(&args, reset).await;
+    }
+
     // Log startup diagnostics
     lamco_rdp_server::utils::log_startup_diagnostics();
 
-    // Load configuration
-    let config = Config::load(&args.config).or_else(|e| {
-        tracing::warn!("Failed to load config: {}, using defaults", e);
-        Config::default_config()
-    })?;
+    // Load configuration, then LAMCO_RDP_<SECTION>_<KEY> overrides. Only a
+    // missing file falls back to defaults; an invalid one stops startup.
+    let config = if Path::new(&args.config).exists() {
+        Config::load(&args.config)?
+    } else {
+        tracing::warn!("Config file {} not found, using defaults", args.config);
+        Config::default_config()?
+    }
+    .with_env_overrides()?;
+
+    // Keep the file's settings for hot-reload comparisons
+    let file_config = config.clone();
 
     // Override config with CLI args
-    let config = config.with_overrides(args.listen.clone(), args.port);
+    let config = config
+        .with_overrides(args.listen.clone(), args.port)
+        .with_reverse_connect(args.connect.clone());
+
+    // Fit settings left at their defaults to this machine
+    let config = if config.performance.auto_profile {
+        apply_hardware_profile(config, args.reprofile).await?.0
+    } else {
+        config
+    };
 
     info!("Configuration loaded successfully");
-    tracing::debug!("Config: {:?}", config);
 
-    info!("Initializing server");
-    let server = match LamcoRdpServer::new(config).await {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("{}", lamco_rdp_server::utils::format_user_error(&e));
-            return Err(e);
+    match crash_report::install(&config.crash_report, recent_logs) {
+        Ok(Some(dir)) => info!("Crash reports: {}", dir.display()),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("⚠️  Crash reports disabled: {:#}", e),
+    }
+    if let Some(plan) = chaos::install_from_env()? {
+        tracing::warn!("💥 Chaos testing: {}", plan);
+    }
+
+    // One log file per client session (validation guarantees a log_dir)
+    if config.logging.per_session_files {
+        if let Some(ref dir) = config.logging.log_dir {
+            std::fs::create_dir_all(dir)?;
+            session_log_dir.set(Some(dir.clone()));
+            info!("Per-session logs: {}/session-<id>.log", dir.display());
         }
+    }
+    tracing::debug!("Config: {:?}", config);
+
+    // Started by a server handing off for an upgrade?
+    let mut handoff = HandoffState::take_from_env()?;
+
+    // GNOME settings in the way of remote use (inherited sockets stay put)
+    let config = if handoff.is_none() {
+        check_gnome_session(config)
+    } else {
+        config
     };
 
-    info!("Starting server");
-    if let Err(e) = server.run().await {
-        eprintln!("{}", lamco_rdp_server::utils::format_user_error(&e));
-        return Err(e);
+    // Re-creation attempts since the capture session was lost (0 = none)
+    let recovery = config.capture_recovery.clone();
+    let mut attempt = 0;
+
+    loop {
+        if attempt > 0 {
+            info!(
+                "Re-creating the capture session in {}s (attempt {}/{})",
+                recovery.retry_delay_secs, attempt, recovery.max_attempts
+            );
+            tokio::time::sleep(std::time::Duration::from_secs(recovery.retry_delay_secs)).await;
+        }
+
+        info!("Initializing server");
+        let server = match LamcoRdpServer::new(config.clone()).await {
+            Ok(s) => s,
+            Err(e) if attempt > 0 && attempt < recovery.max_attempts => {
+                tracing::warn!("Failed to re-create the capture session: {:#}", e);
+                attempt += 1;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}", lamco_rdp_server::utils::format_user_error(&e));
+                return Err(e);
+            }
+        };
+        attempt = 0;
+        // Only the first server takes over inherited listeners
+        let server = match handoff.take() {
+            Some(ref state) => server.with_handoff(state),
+            None => server,
+        };
+        // Settings changed through the admin API go next to the config file
+        let config_path = std::path::Path::new(&args.config);
+        let server = if config_path.exists() {
+            server.with_settings_dir(tunable::drop_in_dir(config_path))
+        } else {
+            server
+        };
+
+        // Re-read the config file on SIGHUP and apply runtime-safe settings
+        let log_filter_handle = log_filter_handle.clone();
+        let reloader = server
+            .config_reloader(&args.config, file_config.clone())
+            .with_log_level_handler(Box::new(move |level| {
+                log_filter_handle
+                    .reload(log_filter(level))
+                    .map_err(|e| anyhow::anyhow!("Failed to reload log filter: {}", e))
+            }));
+        let reloader = tokio::spawn(reloader.run_on_sighup());
+
+        info!("Starting server");
+        let result = server.run().await;
+        reloader.abort();
+        match result {
+            Ok(()) => break,
+            Err(e) if e.is::<CaptureLost>() && recovery.enabled && recovery.max_attempts > 0 => {
+                attempt = 1;
+            }
+            Err(e) => {
+                eprintln!("{}", lamco_rdp_server::utils::format_user_error(&e));
+                return Err(e);
+            }
+        }
     }
 
     info!("Server shut down");
@@ -259,7 +623,7 @@ async fn show_persistence_status() -> Result<()> {
         println!("✅ Server can start without permission dialog");
     } else {
         println!("⚠️  Server will show permission dialog on next start");
-        println!("   Run with --grant-permission to obtain token");
+        println!("   Run `lamco-rdp-server setup` to obtain token");
     }
 
     Ok(())
@@ -314,8 +678,870 @@ async fn grant_permission_flow() -> Result<()> {
     Ok(())
 }
 
+/// Permission pre-flight (interactive)
+async fn run_setup(args: &Args, reset: bool) -> Result<()> {
+    println!("╔════════════════════════════════════════════════════════╗");
+    println!("║         Permission Setup                               ║");
+    println!("╚════════════════════════════════════════════════════════╝");
+    println!();
+    println!("A permission dialog may appear. Click 'Allow' (and 'Remember'");
+    println!("if offered) so the server can start unattended later.");
+    println!();
+
+    let config = Config::load(&args.config).or_else(|e| {
+        tracing::warn!("Failed to load config: {}, using defaults", e);
+        Config::default_config()
+    })?;
+
+    let report = lamco_rdp_server::server::run_setup(&config, reset).await?;
+
+    println!();
+    println!("READINESS:");
+    for check in &report.checks {
+        let icon = match check.status {
+            HealthStatus::Ok => "✅",
+            HealthStatus::Warn => "⚠️ ",
+            HealthStatus::Fail => "❌",
+        };
+        println!("  {} {:<14} {}", icon, check.name, check.detail);
+    }
+    println!();
+
+    match report.status {
+        HealthStatus::Ok => {
+            println!("✅ Ready for unattended operation:");
+            println!("   • systemctl --user start lamco-rdp-server");
+        }
+        HealthStatus::Warn => {
+            println!("⚠️  The server can start, with the limitations above");
+        }
+        HealthStatus::Fail => {
+            println!("❌ Not ready - fix the failed checks and run setup again");
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply the performance profile for this machine to default settings
+///
+/// Returns the adjusted config and the keys changed.
+async fn apply_hardware_profile(
+    mut config: Config,
+    reprofile: bool,
+) -> Result<(Config, Vec<&'static str>)> {
+    let probe = tokio::task::spawn_blocking(move || HardwareProbe::load_or_run(reprofile)).await?;
+    let profile = PerformanceProfile::select(&probe, hardware_encoder_available());
+    info!(
+        "Hardware profile: {} ({} CPUs), {:.1} ms/frame -> tiles {}px, max {} FPS, preset {}, {} encoding",
+        probe.cpu_model,
+        probe.cpu_count,
+        probe.frame_ms(),
+        profile.tile_size,
+        profile.max_fps,
+        profile.quality_preset,
+        if profile.hardware_encoding { "hardware" } else { "software" }
+    );
+
+    let changed = profile.apply(&mut config, &Config::default_config()?);
+    if !changed.is_empty() {
+        info!("Adjusted to this machine: {}", changed.join(", "));
+    }
+    Ok((config, changed))
+}
+
+/// Run the health checks once and print the JSON report
+async fn run_health_check(args: &Args) -> Result<()> {
+    // As at startup, only a missing file falls back to defaults
+    let config = if Path::new(&args.config).exists() {
+        Config::load(&args.config)?
+    } else {
+        eprintln!("Config file {} not found, using defaults", args.config);
+        Config::default_config()?
+    }
+    .with_env_overrides()?
+    .with_overrides(args.listen.clone(), args.port)
+    .with_reverse_connect(args.connect.clone());
+
+    let report = HealthChecker::new(std::sync::Arc::new(config))
+        .check()
+        .await;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.is_healthy() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// What `--dry-run` prints
+#[derive(serde::Serialize)]
+struct DryRunReport {
+    /// Where the settings came from
+    sources: DryRunSources,
+    /// Detected compositor, portal and hardware
+    environment: DryRunEnvironment,
+    /// What the server would use
+    strategies: DryRunStrategies,
+    /// Effective configuration, secrets redacted
+    config: Config,
+}
+
+#[derive(serde::Serialize)]
+struct DryRunSources {
+    config_file: String,
+    /// The file does not exist, so the server would start on defaults
+    config_missing: bool,
+    runtime_drop_in: Option<String>,
+    /// `LAMCO_RDP_*` variables set
+    environment: Vec<String>,
+    /// Settings adjusted by `performance.auto_profile`
+    hardware_profile: Vec<String>,
+}
+
+#[derive(serde::Serialize, Default)]
+struct DryRunEnvironment {
+    compositor: String,
+    compositor_version: Option<String>,
+    portal_version: Option<u32>,
+    portal_screencast: bool,
+    portal_remote_desktop: bool,
+    portal_clipboard: bool,
+    quirks: Vec<String>,
+    deployment: String,
+    credential_storage: String,
+    restore_token: bool,
+    hardware_encoder: bool,
+    /// Why the compositor and portal could not be probed
+    probe_error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct DryRunStrategies {
+    session: String,
+    capture: String,
+    buffers: String,
+    damage_tracking: String,
+    cursor: String,
+    encoder: String,
+    codec: String,
+}
+
+/// Resolve the configuration and probe the environment like a start
+/// would, print the result and exit
+async fn run_dry_run(args: &Args, format: DryRunFormat) -> Result<()> {
+    use lamco_rdp_server::services::{ServiceId, ServiceLevel, ServiceRegistry};
+    use lamco_rdp_server::session::{SessionStrategy, SessionStrategySelector, TokenManager};
+
+    // As at startup, an invalid file is an error and only a missing one
+    // falls back to defaults
+    let config_path = std::path::Path::new(&args.config);
+    let config_missing = !config_path.exists();
+    let config = if config_missing {
+        Config::default_config()?
+    } else {
+        Config::load(&args.config)?
+    }
+    .with_env_overrides()?
+    .with_overrides(args.listen.clone(), args.port)
+    .with_reverse_connect(args.connect.clone());
+    let (mut config, hardware_profile) = if config.performance.auto_profile {
+        apply_hardware_profile(config, args.reprofile).await?
+    } else {
+        (config, Vec::new())
+    };
+
+    let drop_in = tunable::drop_in_dir(config_path).join(tunable::DROP_IN_FILE);
+    let mut environment_vars: Vec<String> = std::env::vars()
+        .map(|(name, _)| name)
+        .filter(|name| name.starts_with(ENV_PREFIX))
+        .collect();
+    environment_vars.sort();
+    let sources = DryRunSources {
+        config_file: args.config.clone(),
+        runtime_drop_in: (!config_missing && drop_in.exists())
+            .then(|| drop_in.display().to_string()),
+        config_missing,
+        environment: environment_vars,
+        hardware_profile: hardware_profile.iter().map(|key| key.to_string()).collect(),
+    };
+
+    let deployment = lamco_rdp_server::session::detect_deployment_context();
+    let (storage_method, _, _) =
+        lamco_rdp_server::session::detect_credential_storage(&deployment).await;
+    let hardware_encoder = hardware_encoder_available();
+    let mut environment = DryRunEnvironment {
+        deployment: deployment.to_string(),
+        credential_storage: storage_method.to_string(),
+        hardware_encoder,
+        ..Default::default()
+    };
+
+    let unknown = || "unknown".to_string();
+    let mut strategies = DryRunStrategies {
+        session: unknown(),
+        capture: unknown(),
+        buffers: unknown(),
+        damage_tracking: unknown(),
+        cursor: unknown(),
+        encoder: if config.hardware_encoding.enabled && hardware_encoder {
+            "hardware (VA-API/NVENC)".to_string()
+        } else {
+            "software (OpenH264)".to_string()
+        },
+        codec: if config.egfx.avc444_enabled {
+            "AVC444, AVC420 for clients without 4:4:4 support".to_string()
+        } else {
+            "AVC420".to_string()
+        },
+    };
+
+    match lamco_rdp_server::compositor::probe_capabilities().await {
+        Ok(caps) => {
+            environment.compositor = caps.compositor.to_string();
+            environment.compositor_version = caps.compositor.version().map(str::to_string);
+            environment.portal_version = Some(caps.portal.version);
+            environment.portal_screencast = caps.portal.supports_screencast;
+            environment.portal_remote_desktop = caps.portal.supports_remote_desktop;
+            environment.portal_clipboard = caps.portal.supports_clipboard;
+            environment.quirks = caps
+                .profile
+                .quirks
+                .iter()
+                .map(|quirk| quirk.name().to_string())
+                .collect();
+            strategies.capture = format!("{:?}", caps.profile.recommended_capture);
+
+            // Same decisions as server start-up
+            let registry = std::sync::Arc::new(ServiceRegistry::from_compositor(caps));
+            let level = |id| registry.service_level(id);
+            strategies.damage_tracking =
+                if level(ServiceId::DamageTracking) >= ServiceLevel::BestEffort {
+                    "compositor damage, adaptive FPS".to_string()
+                } else {
+                    "frame diff".to_string()
+                };
+            strategies.cursor = if level(ServiceId::MetadataCursor) >= ServiceLevel::BestEffort {
+                "metadata (client-side)".to_string()
+            } else {
+                "painted".to_string()
+            };
+            strategies.buffers = if level(ServiceId::DmaBufZeroCopy) >= ServiceLevel::Guaranteed {
+                "DMA-BUF zero-copy".to_string()
+            } else {
+                "memory copy".to_string()
+            };
+
+            strategies.session = match TokenManager::new(storage_method).await {
+                Ok(token_manager) => {
+                    environment.restore_token =
+                        matches!(token_manager.load_token("default").await, Ok(Some(_)));
+                    let selector = SessionStrategySelector::new(
+                        registry.clone(),
+                        std::sync::Arc::new(token_manager),
+                    )
+                    .with_mutter_config(config.mutter.clone())
+                    .with_kwin_config(config.kwin.clone())
+                    .with_wlr_config(config.wlr.clone());
+                    match selector.select_strategy().await {
+                        Ok(strategy) => strategy.name().to_string(),
+                        Err(e) => format!("none ({:#})", e),
+                    }
+                }
+                Err(e) => format!("none ({:#})", e),
+            };
+        }
+        Err(e) => environment.probe_error = Some(format!("{:#}", e)),
+    }
+
+    // Safe to paste into a support ticket
+    config.redact_secrets();
+
+    let report = DryRunReport {
+        sources,
+        environment,
+        strategies,
+        config,
+    };
+    match format {
+        DryRunFormat::Toml => print!(
+            "{}",
+            toml::to_string_pretty(&report).context("Failed to serialize report")?
+        ),
+        DryRunFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+/// Connect to the running server with the test client and report the checks
+#[cfg(feature = "test-client")]
+async fn run_selftest(
+    args: &Args,
+    address: Option<&str>,
+    username: &str,
+    password: &str,
+    frames: usize,
+    timeout: u64,
+) -> Result<()> {
+    use lamco_rdp_server::test_client::TestClient;
+
+    let config = Config::load(&args.config)
+        .or_else(|e| {
+            eprintln!("Failed to load config: {:#}, using defaults", e);
+            Config::default_config()
+        })?
+        .with_env_overrides()?
+        .with_overrides(args.listen.clone(), args.port);
+
+    let address = match address {
+        Some(address) => address.to_string(),
+        None => {
+            let mut addr: std::net::SocketAddr = config
+                .server
+                .listen_addr
+                .parse()
+                .context("server.listen_addr is not an address to connect to")?;
+            if addr.ip().is_unspecified() {
+                addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+            }
+            addr.to_string()
+        }
+    };
+
+    let report = TestClient::new(address)
+        .with_credentials(username, password)
+        .with_nla(config.security.enable_nla)
+        .with_frames(frames)
+        .with_timeout(std::time::Duration::from_secs(timeout))
+        .run()
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    let problems = report.problems();
+    for problem in &problems {
+        eprintln!("✗ {}", problem);
+    }
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Benchmark the pipeline stages and print the results
+async fn run_bench(args: &Args, resolution: &str, frames: u32, json: bool) -> Result<()> {
+    use lamco_rdp_server::config::types::parse_resolution;
+    use lamco_rdp_server::performance::{bench, BenchOptions};
+
+    let (width, height) = parse_resolution(resolution, "benchmark resolution")
+        .map_err(|e| anyhow::anyhow!(e))?
+        .context("--resolution must not be empty")?;
+    let config = Config::load(&args.config)
+        .or_else(|_| Config::default_config())?
+        .with_env_overrides()?;
+    let options = BenchOptions {
+        width,
+        height,
+        frames,
+        hardware_encoding: config.hardware_encoding,
+    };
+
+    if !json {
+        println!(
+            "Benchmarking {}x{}, {} frames per stage...",
+            width, height, frames
+        );
+    }
+    let report = tokio::task::spawn_blocking(move || bench::run(&options)).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{:<7} {:<24} {:>9} {:>9} {:>9} {:>9} {:>8}",
+        "Stage", "Name", "mean ms", "p50 ms", "p95 ms", "max ms", "fps"
+    );
+    for result in &report.results {
+        match (&result.timing, &result.error) {
+            (Some(t), _) => println!(
+                "{:<7} {:<24} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>8.1}",
+                result.stage, result.name, t.mean_ms, t.p50_ms, t.p95_ms, t.max_ms, t.fps
+            ),
+            (None, error) => println!(
+                "{:<7} {:<24} unavailable: {}",
+                result.stage,
+                result.name,
+                error.as_deref().unwrap_or("no frames timed")
+            ),
+        }
+    }
+    if (report.width, report.height) != (width, height) {
+        println!();
+        println!(
+            "Frames were padded to {}x{} for the encoders.",
+            report.width, report.height
+        );
+    }
+    Ok(())
+}
+
+/// Input target of `replay`: tracks which keys are held down
+#[derive(Default)]
+struct HeldKeys {
+    keys: std::collections::BTreeSet<(u8, bool)>,
+}
+
+impl ironrdp_server::RdpServerInputHandler for HeldKeys {
+    fn keyboard(&mut self, event: ironrdp_server::KeyboardEvent) {
+        use ironrdp_server::KeyboardEvent;
+        match event {
+            KeyboardEvent::Pressed { code, extended } => {
+                self.keys.insert((code, extended));
+            }
+            KeyboardEvent::Released { code, extended } => {
+                self.keys.remove(&(code, extended));
+            }
+            _ => {}
+        }
+    }
+
+    fn mouse(&mut self, _event: ironrdp_server::MouseEvent) {}
+}
+
+/// Print each record of a PDU capture and replay it through fresh handlers
+fn run_replay(file: &Path, realtime: bool) -> Result<()> {
+    use lamco_rdp_server::clipboard::{ClipboardEventSender, RdpCliprdrBackend};
+    use lamco_rdp_server::egfx::LamcoGraphicsHandler;
+    use lamco_rdp_server::protocol::replay::describe;
+    use lamco_rdp_server::protocol::{replay, CaptureReader, Pace, ReplayTargets};
+
+    let reader = CaptureReader::open(file)?;
+
+    let clipboard_events = ClipboardEventSender::new();
+    let clipboard_receiver = clipboard_events.subscribe();
+    let clipboard_dir = std::env::temp_dir().join("lamco-rdp-replay");
+    let mut cliprdr = RdpCliprdrBackend::new(clipboard_dir.display().to_string(), clipboard_events);
+    let mut graphics = LamcoGraphicsHandler::new(1920, 1080);
+    let mut input = HeldKeys::default();
+
+    let stats = replay(
+        reader.inspect(|record| {
+            if let Ok(record) = record {
+                println!("{}", describe(record));
+            }
+        }),
+        &mut ReplayTargets {
+            cliprdr: Some(&mut cliprdr),
+            input: Some(&mut input),
+            egfx: Some(&mut graphics),
+        },
+        if realtime {
+            Pace::Recorded
+        } else {
+            Pace::Immediate
+        },
+    )?;
+
+    let mut clipboard_event_count = 0;
+    while clipboard_receiver.try_recv().is_some() {
+        clipboard_event_count += 1;
+    }
+
+    println!();
+    println!(
+        "Replayed {} records ({} skipped)",
+        stats.delivered, stats.skipped
+    );
+    println!(
+        "Codec: {}",
+        if graphics.is_avc444_enabled() {
+            "AVC444"
+        } else if graphics.is_avc420_enabled() {
+            "AVC420"
+        } else {
+            "none negotiated"
+        }
+    );
+    println!("Clipboard events raised: {}", clipboard_event_count);
+    if !input.keys.is_empty() {
+        let keys: Vec<String> = input
+            .keys
+            .iter()
+            .map(|(code, extended)| {
+                format!(
+                    "0x{:02X}{}",
+                    code,
+                    if *extended { " (extended)" } else { "" }
+                )
+            })
+            .collect();
+        println!("Keys left pressed: {}", keys.join(", "));
+    }
+    Ok(())
+}
+
+/// Collect system information, configuration, self-test results and logs
+/// into a tarball and print its path
+async fn run_diag(args: &Args, output: &Path, log_lines: usize) -> Result<()> {
+    use lamco_rdp_server::utils::diag_bundle::{drm_devices, nvidia_driver_version};
+    use lamco_rdp_server::utils::{detect_portal_backend, get_pipewire_version, SystemInfo};
+    use serde_json::json;
+
+    let mut bundle = DiagBundle::new()?;
+
+    // Effective configuration, resolved the way a start would
+    let mut config_error = None;
+    let mut config = Config::load(&args.config)
+        .or_else(|e| {
+            config_error = Some(format!("{:#}", e));
+            Config::default_config()
+        })?
+        .with_env_overrides()?
+        .with_overrides(args.listen.clone(), args.port)
+        .with_reverse_connect(args.connect.clone());
+    config.redact_secrets();
+    let mut config_toml = String::new();
+    if let Some(e) = config_error {
+        config_toml.push_str(&format!(
+            "# {} not used, showing defaults: {}\n",
+            args.config,
+            e.replace('\n', "\n# ")
+        ));
+    }
+    config_toml.push_str(&toml::to_string_pretty(&config).context("Failed to serialize config")?);
+    bundle.add("config.toml", config_toml)?;
+
+    // Host, compositor, portal, GPU and PipeWire
+    println!("Probing compositor and portal...");
+    let system = SystemInfo::gather();
+    let (capabilities, probe_error) = match lamco_rdp_server::compositor::probe_capabilities().await
+    {
+        Ok(capabilities) => (Some(capabilities), None),
+        Err(e) => (None, Some(format!("{:#}", e))),
+    };
+    let var = |name| std::env::var(name).ok();
+    let system = json!({
+        "server_version": env!("CARGO_PKG_VERSION"),
+        "os": format!("{} {}", system.os_name, system.os_version),
+        "kernel": system.kernel_version,
+        "cpus": system.cpu_count,
+        "memory_mb": system.total_memory_mb,
+        "desktop": var("XDG_CURRENT_DESKTOP"),
+        "session_type": var("XDG_SESSION_TYPE"),
+        "wayland_display": var("WAYLAND_DISPLAY"),
+        "deployment": lamco_rdp_server::session::detect_deployment_context().to_string(),
+        "compositor": capabilities.as_ref().map(|caps| json!({
+            "name": caps.compositor.to_string(),
+            "version": caps.compositor.version(),
+            "quirks": caps.profile.quirks.iter().map(|quirk| quirk.name()).collect::<Vec<_>>(),
+        })),
+        "portal": capabilities.as_ref().map(|caps| json!({
+            "backend": caps.portal.backend,
+            "screencast_version": caps.portal.version,
+            "remote_desktop_version": caps.portal.remote_desktop_version,
+            "clipboard_version": caps.portal.clipboard_version,
+        })),
+        "portal_backend_installed": detect_portal_backend(),
+        "probe_error": probe_error,
+        "pipewire": get_pipewire_version(),
+        "gpus": drm_devices(),
+        "nvidia_driver": nvidia_driver_version(),
+    });
+    bundle.add_json("system.json", &system)?;
+    bundle.add_command("commands/vainfo.txt", "vainfo", &[])?;
+    bundle.add_command("commands/pw-cli-info.txt", "pw-cli", &["info", "0"])?;
+
+    // Self-tests: the health checks (OpenH264 among them) and the hardware encoder
+    println!("Running self-tests...");
+    let health = HealthChecker::new(std::sync::Arc::new(config.clone()))
+        .check()
+        .await;
+    let selftest = json!({
+        "health": health,
+        "hardware_encoder_detected": hardware_encoder_available(),
+        "hardware_encoder": hardware_encoder_self_test(&config),
+    });
+    bundle.add_json("selftest.json", &selftest)?;
+
+    // Recent logs: the systemd units, the log directory and --log-file
+    let lines = log_lines.to_string();
+    bundle.add_command(
+        "logs/journal-user.txt",
+        "journalctl",
+        &[
+            "--user",
+            "-u",
+            "lamco-rdp-server*",
+            "-n",
+            &lines,
+            "--no-pager",
+        ],
+    )?;
+    bundle.add_command(
+        "logs/journal-system.txt",
+        "journalctl",
+        &["-u", "lamco-rdp-server*", "-n", &lines, "--no-pager"],
+    )?;
+    let mut log_files: Vec<PathBuf> = config
+        .logging
+        .log_dir
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    // Newest first, at most a handful of per-session files
+    log_files.sort_by_key(|path| {
+        std::cmp::Reverse(path.metadata().and_then(|meta| meta.modified()).ok())
+    });
+    log_files.truncate(DIAG_MAX_LOG_FILES);
+    log_files.extend(args.log_file.iter().map(PathBuf::from));
+    for path in log_files {
+        if let Some(name) = path.file_name() {
+            let file = format!("logs/{}", name.to_string_lossy());
+            bundle.add_log(&file, &path, log_lines)?;
+        }
+    }
+
+    // The newest crash reports
+    let crash_dir = config
+        .crash_report
+        .dir
+        .clone()
+        .or_else(crash_report::default_dir);
+    let reports = crash_dir
+        .map(|dir| crash_report::list_reports(&dir))
+        .unwrap_or_default();
+    let skip = reports.len().saturating_sub(DIAG_MAX_CRASH_REPORTS);
+    for path in &reports[skip..] {
+        if let (Some(name), Ok(report)) = (path.file_name(), std::fs::read_to_string(path)) {
+            bundle.add(&format!("crashes/{}", name.to_string_lossy()), report)?;
+        }
+    }
+
+    let files = bundle.files().len();
+    let tarball = bundle.write_tarball(output)?;
+    println!("Wrote {} ({} files)", tarball.display(), files);
+    println!("Logs are included as written; review them before sharing the bundle publicly.");
+    Ok(())
+}
+
+/// Log files of `logging.log_dir` put into a diagnostics bundle
+const DIAG_MAX_LOG_FILES: usize = 5;
+
+/// Crash reports put into a diagnostics bundle
+const DIAG_MAX_CRASH_REPORTS: usize = 3;
+
+/// Create the configured hardware encoder and encode one frame
+fn hardware_encoder_self_test(config: &Config) -> String {
+    #[cfg(any(feature = "vaapi", feature = "nvenc"))]
+    {
+        const SIZE: u32 = 256;
+        match lamco_rdp_server::egfx::create_hardware_encoder(&config.hardware_encoding, SIZE, SIZE)
+        {
+            Ok(mut encoder) => {
+                let frame = vec![0u8; (SIZE * SIZE * 4) as usize];
+                match encoder.encode_bgra(&frame, SIZE, SIZE, 0) {
+                    Ok(_) => format!("{} working", encoder.backend_name()),
+                    Err(e) => format!("{} failed to encode: {}", encoder.backend_name(), e),
+                }
+            }
+            Err(e) => format!("unavailable: {}", e),
+        }
+    }
+    #[cfg(not(any(feature = "vaapi", feature = "nvenc")))]
+    {
+        let _ = config;
+        "not built with the vaapi or nvenc feature".to_string()
+    }
+}
+
+/// Check or rewrite the configuration file
+fn run_config(args: &Args, action: &ConfigCommand) -> Result<()> {
+    match action {
+        ConfigCommand::Validate => {
+            let content = std::fs::read_to_string(&args.config)
+                .with_context(|| format!("Failed to read config file: {}", args.config))?;
+            let (_, issues) = lamco_rdp_server::config::schema::check(&content);
+            let errors = issues
+                .iter()
+                .filter(|issue| issue.severity == Severity::Error)
+                .count();
+            for issue in &issues {
+                let icon = match issue.severity {
+                    Severity::Warning => "⚠️ ",
+                    Severity::Error => "❌",
+                };
+                println!("{} {}", icon, issue.describe(&args.config));
+            }
+            if errors > 0 {
+                println!(
+                    "{}: {} error(s), {} warning(s)",
+                    args.config,
+                    errors,
+                    issues.len() - errors
+                );
+                std::process::exit(1);
+            }
+            println!("✅ {}: valid ({} warning(s))", args.config, issues.len());
+        }
+        ConfigCommand::Migrate { dry_run } => {
+            let content = std::fs::read_to_string(&args.config)
+                .with_context(|| format!("Failed to read config file: {}", args.config))?;
+            let mut document: toml::Table = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse config file: {}", args.config))?;
+            let from = migrate::file_version(&document);
+            let migrations = migrate::migrate(&mut document)?;
+            if migrations.is_empty() {
+                println!(
+                    "✅ {}: nothing to migrate (version {})",
+                    args.config, CONFIG_VERSION
+                );
+                return Ok(());
+            }
+            for migration in &migrations {
+                println!("  {}: {}", migration.key, migration.change);
+            }
+            if *dry_run {
+                println!(
+                    "{}: {} change(s) from version {} to {}, file not changed",
+                    args.config,
+                    migrations.len(),
+                    from,
+                    CONFIG_VERSION
+                );
+                return Ok(());
+            }
+
+            let backup = format!("{}.bak", args.config);
+            std::fs::copy(&args.config, &backup)
+                .with_context(|| format!("Failed to back up config file to {}", backup))?;
+            let migrated =
+                toml::to_string_pretty(&document).context("Failed to serialize config")?;
+            std::fs::write(&args.config, migrated)
+                .with_context(|| format!("Failed to write config file: {}", args.config))?;
+            println!(
+                "✅ {}: migrated from version {} to {} (previous file: {})",
+                args.config, from, CONFIG_VERSION, backup
+            );
+        }
+    }
+    Ok(())
+}
+
+/// List or disconnect clients through the admin API
+async fn run_sessions(args: &Args, action: &SessionsCommand) -> Result<()> {
+    let config = Config::load(&args.config)?;
+    let client = AdminClient::from_config(&config.admin_api)?;
+
+    match action {
+        SessionsCommand::List => {
+            let sessions = client.sessions().await?;
+            if sessions.is_empty() {
+                println!("No clients connected");
+                return Ok(());
+            }
+            println!(
+                "{:>6}  {:<9} {:<40} {:>9} {:>8} {:>10}",
+                "ID", "KIND", "PEER", "CONNECTED", "CPU", "SENT"
+            );
+            for session in &sessions {
+                let (cpu, sent) = match session.usage {
+                    Some(usage) => (
+                        format!("{:.1}s", usage.cpu_secs),
+                        format!("{:.1} MB", usage.bytes_sent as f64 / 1_000_000.0),
+                    ),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                println!(
+                    "{:>6}  {:<9} {:<40} {:>9} {:>8} {:>10}",
+                    session.id,
+                    session.kind,
+                    session.peer,
+                    format_uptime(session.connected_secs),
+                    cpu,
+                    sent
+                );
+            }
+        }
+        SessionsCommand::Kill { id } => {
+            if !client.disconnect(*id).await? {
+                eprintln!("No client with ID {}", id);
+                std::process::exit(1);
+            }
+            println!("Disconnecting client {}", id);
+        }
+        SessionsCommand::Message { id, text, duration } => {
+            if !client.send_message(*id, text, *duration).await? {
+                eprintln!("No client with ID {}", id);
+                std::process::exit(1);
+            }
+            println!("Message shown to client {}", id);
+        }
+    }
+    Ok(())
+}
+
+/// `1h02m`, `5m07s` or `42s`
+fn format_uptime(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Log GNOME settings in the way of remote use, and move off a port that
+/// gnome-remote-desktop holds when `server.deconflict_port` is set
+fn check_gnome_session(mut config: Config) -> Config {
+    use lamco_rdp_server::compositor::{gnome, identify_compositor, CompositorType};
+
+    if !matches!(identify_compositor(), CompositorType::Gnome { .. }) {
+        return config;
+    }
+    let Ok(addr) = config.server.listen_addr.parse::<std::net::SocketAddr>() else {
+        return config;
+    };
+
+    let settings = gnome::GnomeSettings::read();
+    for recommendation in gnome::recommendations(&settings, addr.port(), config.clipboard.enabled) {
+        match recommendation.severity {
+            gnome::Severity::Warning => tracing::warn!("⚠️  GNOME: {}", recommendation.problem),
+            gnome::Severity::Info => info!("💡 GNOME: {}", recommendation.problem),
+        }
+        info!("   → {}", recommendation.action);
+    }
+
+    if config.server.deconflict_port
+        && config.server.reverse_connect.is_empty()
+        && settings.grd_holds_port(addr.port())
+        && std::net::TcpListener::bind(addr).is_err()
+    {
+        match gnome::free_port_after(addr) {
+            Some(free) => {
+                tracing::warn!(
+                    "🔀 gnome-remote-desktop listens on port {}, listening on {} instead",
+                    addr.port(),
+                    free
+                );
+                config.server.listen_addr = free.to_string();
+            }
+            None => tracing::warn!(
+                "gnome-remote-desktop listens on port {} and none of the next ports is free",
+                addr.port()
+            ),
+        }
+    }
+    config
+}
+
 /// Run diagnostic checks
-async fn run_diagnostics() -> Result<()> {
+async fn run_diagnostics(args: &Args) -> Result<()> {
     println!("╔════════════════════════════════════════════════════════╗");
     println!("║         Diagnostic Report                              ║");
     println!("╚════════════════════════════════════════════════════════╝");
@@ -395,6 +1621,37 @@ async fn run_diagnostics() -> Result<()> {
         println!("⚠️  Not found (will use hostname)");
     }
 
+    // Test 9: GNOME settings
+    if matches!(
+        compositor,
+        lamco_rdp_server::compositor::CompositorType::Gnome { .. }
+    ) {
+        use lamco_rdp_server::compositor::gnome;
+
+        print!("[  ] GNOME settings... ");
+        let config = Config::load(&args.config).or_else(|_| Config::default_config())?;
+        let port = config
+            .server
+            .listen_addr
+            .parse::<std::net::SocketAddr>()
+            .map_or(3389, |addr| addr.port());
+        let settings = gnome::GnomeSettings::read();
+        let found = gnome::recommendations(&settings, port, config.clipboard.enabled);
+        if found.is_empty() {
+            println!("✅ Ready for remote use");
+        } else {
+            println!();
+            for recommendation in &found {
+                let icon = match recommendation.severity {
+                    gnome::Severity::Warning => "⚠️ ",
+                    gnome::Severity::Info => "💡",
+                };
+                println!("     {} {}", icon, recommendation.problem);
+                println!("        → {}", recommendation.action);
+            }
+        }
+    }
+
     println!();
     println!("SUMMARY:");
     println!("  Run --show-capabilities for detailed capability report");
@@ -403,7 +1660,29 @@ async fn run_diagnostics() -> Result<()> {
     Ok(())
 }
 
-fn init_logging(args: &Args) -> Result<()> {
+/// Log filter for the given level
+///
+/// Enables lamco crates at the requested level, IronRDP protocol at info (debug logs raw packets!),
+/// and ironrdp_cliprdr/egfx/dvc at the requested level for channel troubleshooting.
+fn log_filter(level: &str) -> EnvFilter {
+    EnvFilter::new(format!(
+        "lamco={level},ironrdp_cliprdr={level},ironrdp_egfx={level},ironrdp_dvc={level},ironrdp_server={level},ironrdp=info,ashpd=info,warn",
+        level = level
+    ))
+}
+
+/// Initialize logging
+///
+/// Returns a handle for replacing the log filter at runtime (config reload),
+/// the directory handle for per-session log files (set once the
+/// configuration is loaded) and the recent lines kept for crash reports.
+fn init_logging(
+    args: &Args,
+) -> Result<(
+    tracing_subscriber::reload::Handle<EnvFilter, Registry>,
+    SessionLogDir,
+    RecentLogs,
+)> {
     use std::fs::File;
 
     let log_level = match args.verbose {
@@ -412,14 +1691,12 @@ fn init_logging(args: &Args) -> Result<()> {
         _ => "trace",
     };
 
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        // Enable lamco crates at requested level, IronRDP protocol at info (debug logs raw packets!)
-        // ironrdp_cliprdr/egfx/dvc at debug for channel troubleshooting
-        tracing_subscriber::EnvFilter::new(format!(
-            "lamco={level},ironrdp_cliprdr={level},ironrdp_egfx={level},ironrdp_dvc={level},ironrdp_server={level},ironrdp=info,ashpd=info,warn",
-            level = log_level
-        ))
-    });
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| log_filter(log_level));
+    let (env_filter, filter_handle) = tracing_subscriber::reload::Layer::new(env_filter);
+    let session_log = SessionLogLayer::new();
+    let session_log_dir = session_log.directory();
+    let recent_log = RecentLogLayer::new();
+    let recent_logs = recent_log.logs();
 
     // If log file is specified, write to both stdout and file
     if let Some(log_file_path) = &args.log_file {
@@ -429,6 +1706,8 @@ fn init_logging(args: &Args) -> Result<()> {
             "json" => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(
                         tracing_subscriber::fmt::layer()
                             .json()
@@ -445,6 +1724,8 @@ fn init_logging(args: &Args) -> Result<()> {
             "compact" => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(
                         tracing_subscriber::fmt::layer()
                             .compact()
@@ -461,6 +1742,8 @@ fn init_logging(args: &Args) -> Result<()> {
             _ => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(
                         tracing_subscriber::fmt::layer()
                             .pretty()
@@ -481,23 +1764,29 @@ fn init_logging(args: &Args) -> Result<()> {
             "json" => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(tracing_subscriber::fmt::layer().json())
                     .init();
             }
             "compact" => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(tracing_subscriber::fmt::layer().compact())
                     .init();
             }
             _ => {
                 tracing_subscriber::registry()
                     .with(env_filter)
+                    .with(session_log)
+                    .with(recent_log)
                     .with(tracing_subscriber::fmt::layer().pretty())
                     .init();
             }
         }
     }
 
-    Ok(())
+    Ok((filter_handle, session_log_dir, recent_logs))
 }
@@ -0,0 +1,136 @@
+//! Configured Monitor Arrangement
+//!
+//! Applies `[[multimon.monitors]]` entries to the streams of a capture
+//! session: monitors that aren't shared are left out, and the others are
+//! placed in the RDP virtual desktop where the entries put them instead of
+//! where the compositor has them.
+//!
+//! Entries are matched to streams by local desktop position, which every
+//! capture source (portal, Mutter, wlr-screencopy) reports. Streams without
+//! an entry keep their local position, offset like the configured ones.
+
+use tracing::{info, warn};
+
+use crate::config::types::MonitorLayoutConfig;
+use crate::portal::StreamInfo;
+
+/// Entry configured for `stream`, if any
+pub fn layout_for<'a>(
+    stream: &StreamInfo,
+    layout: &'a [MonitorLayoutConfig],
+) -> Option<&'a MonitorLayoutConfig> {
+    layout
+        .iter()
+        .find(|m| (m.desktop_x, m.desktop_y) == stream.position)
+}
+
+/// Streams of the monitors that are shared
+///
+/// All streams are kept when the layout would share none of them, since a
+/// session without video is not what anyone configured.
+pub fn shared_streams(streams: Vec<StreamInfo>, layout: &[MonitorLayoutConfig]) -> Vec<StreamInfo> {
+    let (shared, excluded): (Vec<_>, Vec<_>) = streams
+        .into_iter()
+        .partition(|s| layout_for(s, layout).map_or(true, |m| m.shared));
+
+    if shared.is_empty() && !excluded.is_empty() {
+        warn!("Monitor layout shares no captured monitor, sharing all of them");
+        return excluded;
+    }
+    for stream in &excluded {
+        info!(
+            "Monitor at {:?} ({}x{}) not shared (multimon.monitors)",
+            stream.position, stream.size.0, stream.size.1
+        );
+    }
+    shared
+}
+
+/// Position of each stream in the RDP virtual desktop
+///
+/// Configured positions where there are entries, local positions
+/// elsewhere, shifted so that the virtual desktop starts at (0, 0).
+pub fn virtual_positions(
+    streams: &[StreamInfo],
+    layout: &[MonitorLayoutConfig],
+) -> Vec<(u32, u32)> {
+    let positions: Vec<(i32, i32)> = streams
+        .iter()
+        .map(|s| layout_for(s, layout).map_or(s.position, |m| (m.x, m.y)))
+        .collect();
+    let min_x = positions.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_y = positions.iter().map(|p| p.1).min().unwrap_or(0);
+    positions
+        .iter()
+        .map(|&(x, y)| ((x - min_x) as u32, (y - min_y) as u32))
+        .collect()
+}
+
+/// Frame rate to capture `stream` at, at most `max_fps`
+pub fn stream_fps(stream: &StreamInfo, layout: &[MonitorLayoutConfig], max_fps: u32) -> u32 {
+    match layout_for(stream, layout) {
+        Some(m) if m.fps > 0 => m.fps.min(max_fps),
+        _ => max_fps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portal::SourceType;
+
+    fn stream(node_id: u32, x: i32, y: i32) -> StreamInfo {
+        StreamInfo {
+            node_id,
+            position: (x, y),
+            size: (1920, 1080),
+            source_type: SourceType::Monitor,
+        }
+    }
+
+    fn entry(desktop_x: i32, shared: bool, x: i32, y: i32, fps: u32) -> MonitorLayoutConfig {
+        MonitorLayoutConfig {
+            name: String::new(),
+            desktop_x,
+            desktop_y: 0,
+            shared,
+            x,
+            y,
+            fps,
+        }
+    }
+
+    #[test]
+    fn test_shared_streams() {
+        let streams = vec![stream(40, 0, 0), stream(41, 1920, 0), stream(42, 3840, 0)];
+        let layout = vec![entry(1920, false, 0, 0, 0)];
+
+        let shared = shared_streams(streams.clone(), &layout);
+        assert_eq!(
+            shared.iter().map(|s| s.node_id).collect::<Vec<_>>(),
+            vec![40, 42]
+        );
+
+        // Sharing nothing falls back to everything
+        let layout: Vec<_> = [0, 1920, 3840]
+            .into_iter()
+            .map(|x| entry(x, false, x, 0, 0))
+            .collect();
+        assert_eq!(shared_streams(streams, &layout).len(), 3);
+    }
+
+    #[test]
+    fn test_virtual_positions_and_fps() {
+        // Second monitor moved above the first; third has no entry
+        let streams = vec![stream(40, 0, 0), stream(41, 1920, 0), stream(42, 3840, 0)];
+        let layout = vec![entry(0, true, 0, 0, 0), entry(1920, true, 0, -1080, 15)];
+
+        assert_eq!(
+            virtual_positions(&streams, &layout),
+            vec![(0, 1080), (0, 0), (3840, 1080)]
+        );
+        assert_eq!(stream_fps(&streams[0], &layout, 30), 30);
+        assert_eq!(stream_fps(&streams[1], &layout, 30), 15);
+        assert_eq!(stream_fps(&streams[1], &layout, 10), 10);
+    }
+}
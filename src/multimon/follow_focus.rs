@@ -0,0 +1,663 @@
+//! Follow-Focus Capture
+//!
+//! Lets a single lower-resolution RDP client work across a large multi-monitor
+//! host by presenting a client-sized viewport that follows the user's focus.
+//!
+//! # Modes
+//!
+//! | Mode | Viewport | Switching |
+//! |------|----------|-----------|
+//! | Off | Whole desktop | - |
+//! | Monitor | One monitor at a time | Push pointer against an edge for `dwell` |
+//! | Pointer | Client-sized window | Pans smoothly when pointer nears an edge |
+//!
+//! # How It Works
+//!
+//! ```text
+//! Desktop (3840x1080)
+//! ┌──────────────────┬──────────────────┐
+//! │    Monitor 0     │    Monitor 1     │
+//! │ ┌──────────────┐ │                  │
+//! │ │  viewport    │─┼─> cropped frame  │──> RDP client (1920x1080)
+//! │ └──────────────┘ │                  │
+//! └──────────────────┴──────────────────┘
+//! ```
+//!
+//! The display pipeline crops each captured frame to the current viewport,
+//! and the input handler maps client coordinates back into desktop space
+//! via [`FollowFocusController::map_client_point`]. Wayland exposes no
+//! portable "focused window" query, so focus is inferred from the pointer:
+//! the monitor under the pointer is the focused monitor.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use crate::multimon::{MultiMonitorError, Result};
+use crate::performance::BufferPool;
+use crate::portal::StreamInfo;
+
+/// Follow-focus mode selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FollowFocusMode {
+    /// Present the whole desktop (follow-focus disabled)
+    #[default]
+    Off,
+
+    /// Present one monitor at a time, switching on edge push
+    Monitor,
+
+    /// Present a client-sized viewport that pans with the pointer
+    Pointer,
+}
+
+impl std::fmt::Display for FollowFocusMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Monitor => write!(f, "monitor"),
+            Self::Pointer => write!(f, "pointer"),
+        }
+    }
+}
+
+impl std::str::FromStr for FollowFocusMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" | "disabled" => Ok(Self::Off),
+            "monitor" | "output" => Ok(Self::Monitor),
+            "pointer" | "pan" => Ok(Self::Pointer),
+            _ => Err(format!("Unknown follow-focus mode: {}", s)),
+        }
+    }
+}
+
+/// Rectangle in desktop coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusRect {
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl FocusRect {
+    /// Create a new rectangle
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    /// Check whether a desktop point lies inside this rectangle
+    pub fn contains(&self, px: i32, py: i32) -> bool {
+        px >= self.x && px < self.right() && py >= self.y && py < self.bottom()
+    }
+
+    fn bounding(rects: &[FocusRect]) -> Option<FocusRect> {
+        let first = rects.first()?;
+        let (mut x0, mut y0, mut x1, mut y1) = (first.x, first.y, first.right(), first.bottom());
+        for r in &rects[1..] {
+            x0 = x0.min(r.x);
+            y0 = y0.min(r.y);
+            x1 = x1.max(r.right());
+            y1 = y1.max(r.bottom());
+        }
+        Some(FocusRect::new(x0, y0, (x1 - x0) as u32, (y1 - y0) as u32))
+    }
+}
+
+/// Direction of an edge push
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A frame cropped to the follow-focus viewport
+#[derive(Debug)]
+pub struct CroppedFrame {
+    /// Tightly packed BGRA pixels
+    pub data: Vec<u8>,
+    /// Cropped width
+    pub width: u32,
+    /// Cropped height
+    pub height: u32,
+}
+
+/// Shared follow-focus controller (display pipeline + input handler)
+pub type SharedFollowFocus = Arc<Mutex<FollowFocusController>>;
+
+/// Follow-focus controller
+///
+/// Owns the current viewport and decides when it moves. All methods are
+/// synchronous and cheap so they can be called from the frame loop and the
+/// input batching task.
+#[derive(Debug)]
+pub struct FollowFocusController {
+    mode: FollowFocusMode,
+    monitors: Vec<FocusRect>,
+    desktop: FocusRect,
+    viewport: FocusRect,
+    active_monitor: usize,
+    dwell: Duration,
+    edge_margin: u32,
+    pending_edge: Option<(Edge, Instant)>,
+    switches: u64,
+}
+
+impl FollowFocusController {
+    /// Default distance from the viewport edge at which pointer mode pans (pixels)
+    pub const DEFAULT_EDGE_MARGIN: u32 = 32;
+
+    /// Create a controller for the given monitor rectangles
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Follow-focus mode (must not be `Off`)
+    /// * `monitors` - Monitor rectangles in desktop coordinates (first = primary)
+    /// * `viewport_size` - Client viewport size, or `None` for the primary monitor size
+    /// * `dwell` - How long an edge push must last before switching monitors
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the mode is `Off` or no monitors are given
+    pub fn new(
+        mode: FollowFocusMode,
+        monitors: Vec<FocusRect>,
+        viewport_size: Option<(u32, u32)>,
+        dwell: Duration,
+    ) -> Result<Self> {
+        if mode == FollowFocusMode::Off {
+            return Err(MultiMonitorError::InvalidConfiguration(
+                "follow-focus controller requires a mode other than off".to_string(),
+            ));
+        }
+        let desktop = FocusRect::bounding(&monitors).ok_or_else(|| {
+            MultiMonitorError::InvalidConfiguration(
+                "follow-focus requires at least one monitor".to_string(),
+            )
+        })?;
+
+        let primary = monitors[0];
+        let (vw, vh) = viewport_size.unwrap_or((primary.width, primary.height));
+        let vw = vw.clamp(1, desktop.width);
+        let vh = vh.clamp(1, desktop.height);
+
+        let mut controller = Self {
+            mode,
+            monitors,
+            desktop,
+            viewport: FocusRect::new(primary.x, primary.y, vw, vh),
+            active_monitor: 0,
+            dwell,
+            edge_margin: Self::DEFAULT_EDGE_MARGIN,
+            pending_edge: None,
+            switches: 0,
+        };
+        controller.clamp_viewport();
+
+        info!(
+            "🎯 Follow-focus {} mode: desktop {}x{}, viewport {}x{}, {} monitors",
+            mode,
+            desktop.width,
+            desktop.height,
+            vw,
+            vh,
+            controller.monitors.len()
+        );
+
+        Ok(controller)
+    }
+
+    /// Create a controller from Portal stream geometry
+    pub fn from_streams(
+        mode: FollowFocusMode,
+        streams: &[StreamInfo],
+        viewport_size: Option<(u32, u32)>,
+        dwell: Duration,
+    ) -> Result<Self> {
+        let monitors = streams
+            .iter()
+            .map(|s| FocusRect::new(s.position.0, s.position.1, s.size.0, s.size.1))
+            .collect();
+        Self::new(mode, monitors, viewport_size, dwell)
+    }
+
+    /// Wrap into a shared handle
+    pub fn into_shared(self) -> SharedFollowFocus {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Override the pointer-mode pan margin
+    pub fn set_edge_margin(&mut self, margin: u32) {
+        self.edge_margin = margin;
+    }
+
+    /// Current mode
+    pub fn mode(&self) -> FollowFocusMode {
+        self.mode
+    }
+
+    /// Current viewport in desktop coordinates
+    pub fn viewport(&self) -> FocusRect {
+        self.viewport
+    }
+
+    /// Size presented to the RDP client
+    pub fn client_size(&self) -> (u16, u16) {
+        (
+            self.viewport.width.min(u16::MAX as u32) as u16,
+            self.viewport.height.min(u16::MAX as u32) as u16,
+        )
+    }
+
+    /// Index of the monitor currently holding focus
+    pub fn active_monitor(&self) -> usize {
+        self.active_monitor
+    }
+
+    /// Number of monitor switches since creation
+    pub fn switch_count(&self) -> u64 {
+        self.switches
+    }
+
+    /// Move the viewport to a monitor
+    ///
+    /// Returns false if the index is out of range.
+    pub fn focus_monitor(&mut self, index: usize) -> bool {
+        let Some(monitor) = self.monitors.get(index).copied() else {
+            return false;
+        };
+        if index != self.active_monitor {
+            self.switches += 1;
+            info!(
+                "🎯 Follow-focus: switching to monitor {} at ({}, {})",
+                index, monitor.x, monitor.y
+            );
+        }
+        self.active_monitor = index;
+        self.viewport.x = monitor.x;
+        self.viewport.y = monitor.y;
+        self.pending_edge = None;
+        self.clamp_viewport();
+        true
+    }
+
+    /// Map a client pointer position to desktop coordinates
+    ///
+    /// Returns coordinates relative to the desktop's top-left corner, which is
+    /// the space the input `CoordinateTransformer` expects. As a side effect
+    /// the viewport may pan (pointer mode) or switch monitors (monitor mode).
+    pub fn map_client_point(&mut self, x: u16, y: u16) -> (u32, u32) {
+        self.map_client_point_at(x, y, Instant::now())
+    }
+
+    fn map_client_point_at(&mut self, x: u16, y: u16, now: Instant) -> (u32, u32) {
+        let cx = (x as i32).min(self.viewport.width as i32 - 1);
+        let cy = (y as i32).min(self.viewport.height as i32 - 1);
+        let px = self.viewport.x + cx;
+        let py = self.viewport.y + cy;
+
+        if let Some(idx) = self.monitors.iter().position(|m| m.contains(px, py)) {
+            if self.mode == FollowFocusMode::Pointer {
+                self.active_monitor = idx;
+            }
+        }
+
+        match self.mode {
+            FollowFocusMode::Pointer => self.pan_towards(cx, cy),
+            FollowFocusMode::Monitor => self.track_edge_push(cx, cy, px, py, now),
+            FollowFocusMode::Off => {}
+        }
+
+        (
+            (px - self.desktop.x).max(0) as u32,
+            (py - self.desktop.y).max(0) as u32,
+        )
+    }
+
+    /// Pointer mode: pan so the pointer stays `edge_margin` inside the viewport
+    fn pan_towards(&mut self, cx: i32, cy: i32) {
+        let margin = self.edge_margin as i32;
+        let (vw, vh) = (self.viewport.width as i32, self.viewport.height as i32);
+        let before = (self.viewport.x, self.viewport.y);
+
+        if cx < margin {
+            self.viewport.x -= margin - cx;
+        } else if cx > vw - 1 - margin {
+            self.viewport.x += cx - (vw - 1 - margin);
+        }
+        if cy < margin {
+            self.viewport.y -= margin - cy;
+        } else if cy > vh - 1 - margin {
+            self.viewport.y += cy - (vh - 1 - margin);
+        }
+
+        self.clamp_viewport();
+        if before != (self.viewport.x, self.viewport.y) {
+            debug!(
+                "Follow-focus pan: ({}, {}) -> ({}, {})",
+                before.0, before.1, self.viewport.x, self.viewport.y
+            );
+        }
+    }
+
+    /// Monitor mode: switch to the neighbouring monitor after a sustained edge push
+    fn track_edge_push(&mut self, cx: i32, cy: i32, px: i32, py: i32, now: Instant) {
+        let edge = if cx <= 0 {
+            Some(Edge::Left)
+        } else if cx >= self.viewport.width as i32 - 1 {
+            Some(Edge::Right)
+        } else if cy <= 0 {
+            Some(Edge::Up)
+        } else if cy >= self.viewport.height as i32 - 1 {
+            Some(Edge::Down)
+        } else {
+            None
+        };
+
+        let Some(edge) = edge else {
+            self.pending_edge = None;
+            return;
+        };
+
+        let Some(neighbor) = self.neighbor(edge, px, py) else {
+            self.pending_edge = None;
+            return;
+        };
+
+        match self.pending_edge {
+            Some((pending, since)) if pending == edge => {
+                if now.duration_since(since) >= self.dwell {
+                    self.focus_monitor(neighbor);
+                }
+            }
+            _ => {
+                if self.dwell.is_zero() {
+                    self.focus_monitor(neighbor);
+                } else {
+                    self.pending_edge = Some((edge, now));
+                }
+            }
+        }
+    }
+
+    /// Find the nearest monitor beyond the active one in the given direction
+    fn neighbor(&self, edge: Edge, px: i32, py: i32) -> Option<usize> {
+        let active = self.monitors[self.active_monitor];
+        self.monitors
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != self.active_monitor)
+            .filter(|(_, m)| match edge {
+                Edge::Left => m.right() <= active.x && py >= m.y && py < m.bottom(),
+                Edge::Right => m.x >= active.right() && py >= m.y && py < m.bottom(),
+                Edge::Up => m.bottom() <= active.y && px >= m.x && px < m.right(),
+                Edge::Down => m.y >= active.bottom() && px >= m.x && px < m.right(),
+            })
+            .min_by_key(|(_, m)| match edge {
+                Edge::Left => active.x - m.right(),
+                Edge::Right => m.x - active.right(),
+                Edge::Up => active.y - m.bottom(),
+                Edge::Down => m.y - active.bottom(),
+            })
+            .map(|(idx, _)| idx)
+    }
+
+    fn clamp_viewport(&mut self) {
+        let max_x = self.desktop.right() - self.viewport.width as i32;
+        let max_y = self.desktop.bottom() - self.viewport.height as i32;
+        self.viewport.x = self.viewport.x.clamp(self.desktop.x, max_x);
+        self.viewport.y = self.viewport.y.clamp(self.desktop.y, max_y);
+    }
+
+    /// Crop a captured BGRA frame to the current viewport
+    ///
+    /// The frame is assumed to cover the whole desktop. If its dimensions
+    /// differ from the desktop (HiDPI scaling), the viewport is scaled
+    /// proportionally. Returns `None` if no crop is needed or the frame is
+    /// too small for its stated dimensions.
+    pub fn crop_frame(&self, data: &[u8], width: u32, height: u32) -> Option<CroppedFrame> {
+        if width == 0 || height == 0 || data.len() < (width * height * 4) as usize {
+            return None;
+        }
+
+        let (fx, fy, fw, fh) = self.frame_viewport(width, height);
+        if fx == 0 && fy == 0 && fw == width && fh == height {
+            return None;
+        }
+
+        let src_stride = (width * 4) as usize;
+        let row_bytes = (fw * 4) as usize;
+        let mut cropped = BufferPool::global().take(row_bytes * fh as usize);
+        for row in fy..fy + fh {
+            let start = row as usize * src_stride + fx as usize * 4;
+            cropped.extend_from_slice(&data[start..start + row_bytes]);
+        }
+
+        Some(CroppedFrame {
+            data: cropped,
+            width: fw,
+            height: fh,
+        })
+    }
+
+    /// Position of a captured frame's pixel within the cropped frame
+    ///
+    /// Returns `None` for pixels outside the viewport.
+    pub fn map_frame_point(&self, x: i32, y: i32, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width == 0 || height == 0 || x < 0 || y < 0 {
+            return None;
+        }
+        let (fx, fy, fw, fh) = self.frame_viewport(width, height);
+        let (x, y) = (x as u32, y as u32);
+        (x >= fx && y >= fy && x < fx + fw && y < fy + fh).then(|| (x - fx, y - fy))
+    }
+
+    /// Viewport in frame pixels (x, y, width, height) for a non-empty frame
+    fn frame_viewport(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let scale_x = width as f64 / self.desktop.width as f64;
+        let scale_y = height as f64 / self.desktop.height as f64;
+        let fx = (((self.viewport.x - self.desktop.x) as f64 * scale_x) as u32).min(width - 1);
+        let fy = (((self.viewport.y - self.desktop.y) as f64 * scale_y) as u32).min(height - 1);
+        let fw = ((self.viewport.width as f64 * scale_x).round() as u32).clamp(1, width - fx);
+        let fh = ((self.viewport.height as f64 * scale_y).round() as u32).clamp(1, height - fy);
+        (fx, fy, fw, fh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dual_monitor() -> Vec<FocusRect> {
+        vec![
+            FocusRect::new(0, 0, 1920, 1080),
+            FocusRect::new(1920, 0, 1920, 1080),
+        ]
+    }
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!(
+            "monitor".parse::<FollowFocusMode>(),
+            Ok(FollowFocusMode::Monitor)
+        );
+        assert_eq!(
+            "Pointer".parse::<FollowFocusMode>(),
+            Ok(FollowFocusMode::Pointer)
+        );
+        assert_eq!("off".parse::<FollowFocusMode>(), Ok(FollowFocusMode::Off));
+        assert!("bogus".parse::<FollowFocusMode>().is_err());
+    }
+
+    #[test]
+    fn test_off_mode_rejected() {
+        let result =
+            FollowFocusController::new(FollowFocusMode::Off, dual_monitor(), None, Duration::ZERO);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_viewport_defaults_to_primary_monitor() {
+        let ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            dual_monitor(),
+            None,
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(ctl.client_size(), (1920, 1080));
+        assert_eq!(ctl.viewport(), FocusRect::new(0, 0, 1920, 1080));
+    }
+
+    #[test]
+    fn test_monitor_switch_after_dwell() {
+        let mut ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            dual_monitor(),
+            None,
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let start = Instant::now();
+
+        // First push only arms the switch
+        ctl.map_client_point_at(1919, 500, start);
+        assert_eq!(ctl.active_monitor(), 0);
+
+        // Still pushing after dwell: switch
+        ctl.map_client_point_at(1919, 500, start + Duration::from_millis(250));
+        assert_eq!(ctl.active_monitor(), 1);
+        assert_eq!(ctl.viewport().x, 1920);
+        assert_eq!(ctl.switch_count(), 1);
+
+        // Client coordinates now map into the second monitor
+        assert_eq!(ctl.map_client_point_at(100, 100, start), (2020, 100));
+    }
+
+    #[test]
+    fn test_leaving_edge_cancels_switch() {
+        let mut ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            dual_monitor(),
+            None,
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let start = Instant::now();
+        ctl.map_client_point_at(1919, 500, start);
+        ctl.map_client_point_at(1000, 500, start + Duration::from_millis(100));
+        ctl.map_client_point_at(1919, 500, start + Duration::from_millis(250));
+        assert_eq!(ctl.active_monitor(), 0);
+    }
+
+    #[test]
+    fn test_no_neighbor_no_switch() {
+        let mut ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            dual_monitor(),
+            None,
+            Duration::ZERO,
+        )
+        .unwrap();
+        ctl.map_client_point_at(0, 500, Instant::now());
+        assert_eq!(ctl.active_monitor(), 0);
+    }
+
+    #[test]
+    fn test_pointer_mode_pans_and_clamps() {
+        let mut ctl = FollowFocusController::new(
+            FollowFocusMode::Pointer,
+            dual_monitor(),
+            Some((1280, 720)),
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(ctl.viewport(), FocusRect::new(0, 0, 1280, 720));
+
+        // Near the right edge: pan right
+        ctl.map_client_point(1279, 300);
+        assert!(ctl.viewport().x > 0);
+
+        // Near the left edge at desktop origin: clamped
+        let mut ctl2 = FollowFocusController::new(
+            FollowFocusMode::Pointer,
+            dual_monitor(),
+            Some((1280, 720)),
+            Duration::ZERO,
+        )
+        .unwrap();
+        ctl2.map_client_point(0, 300);
+        assert_eq!(ctl2.viewport().x, 0);
+    }
+
+    #[test]
+    fn test_crop_frame() {
+        let ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            vec![FocusRect::new(0, 0, 2, 1), FocusRect::new(2, 0, 2, 1)],
+            None,
+            Duration::ZERO,
+        )
+        .unwrap();
+        // 4x1 frame, pixel values = column index
+        let data: Vec<u8> = (0..4u8).flat_map(|i| [i, i, i, 255]).collect();
+        let cropped = ctl.crop_frame(&data, 4, 1).unwrap();
+        assert_eq!((cropped.width, cropped.height), (2, 1));
+        assert_eq!(cropped.data, vec![0, 0, 0, 255, 1, 1, 1, 255]);
+    }
+
+    #[test]
+    fn test_crop_scaled_frame() {
+        let mut ctl = FollowFocusController::new(
+            FollowFocusMode::Monitor,
+            vec![FocusRect::new(0, 0, 2, 1), FocusRect::new(2, 0, 2, 1)],
+            None,
+            Duration::ZERO,
+        )
+        .unwrap();
+        ctl.focus_monitor(1);
+        // Frame captured at 2x scale
+        let data: Vec<u8> = (0..16u8).flat_map(|i| [i, 0, 0, 255]).collect();
+        let cropped = ctl.crop_frame(&data, 8, 2).unwrap();
+        assert_eq!((cropped.width, cropped.height), (4, 2));
+        assert_eq!(cropped.data[0], 4);
+
+        assert_eq!(ctl.map_frame_point(5, 1, 8, 2), Some((1, 1)));
+        assert_eq!(ctl.map_frame_point(3, 1, 8, 2), None);
+    }
+
+    #[test]
+    fn test_crop_not_needed_for_full_viewport() {
+        let ctl = FollowFocusController::new(
+            FollowFocusMode::Pointer,
+            vec![FocusRect::new(0, 0, 4, 4)],
+            None,
+            Duration::ZERO,
+        )
+        .unwrap();
+        let data = vec![0u8; 4 * 4 * 4];
+        assert!(ctl.crop_frame(&data, 4, 4).is_none());
+    }
+}
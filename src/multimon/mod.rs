@@ -121,6 +121,12 @@
 //! # }
 //! ```
 //!
+//! # Follow-Focus
+//!
+//! With `multimon.follow_focus` set to `"monitor"` or `"pointer"`, the client
+//! receives a single viewport that follows the pointer across the desktop
+//! instead of the full bounding box. See [`FollowFocusController`].
+//!
 //! # Performance
 //!
 //! - **Latency:** <1ms for coordinate transformations
@@ -129,9 +135,15 @@
 //!
 //! Implements TASK-P1-09 specification for production-grade multi-monitor handling.
 
+mod arrangement;
+mod follow_focus;
 mod layout;
 mod manager;
 
+pub use arrangement::{layout_for, shared_streams, stream_fps, virtual_positions};
+pub use follow_focus::{
+    CroppedFrame, FocusRect, FollowFocusController, FollowFocusMode, SharedFollowFocus,
+};
 pub use layout::{CoordinateSpace, Layout, LayoutCalculator, MonitorLayout, VirtualDesktop};
 pub use manager::{MonitorEvent, MonitorInfo, MonitorManager, MultiMonitorConfig};
 
@@ -4,14 +4,17 @@
 //! reducing CPU and bandwidth for static content while maintaining
 //! smooth video for active content.
 //!
-//! # Activity Levels (default max_fps=30)
+//! # Activity Levels
+//!
+//! Each level has a fixed FPS target, limited to `min_fps..=max_fps`
+//! (with the default `max_fps = 30`, Medium and High both run at 30):
 //!
 //! | Level | Damage % | FPS | Use Case |
 //! |-------|----------|-----|----------|
 //! | Static | <1% | 5 | Wallpaper, idle desktop |
 //! | Low | 1-10% | 15 | Typing, cursor movement |
-//! | Medium | 10-30% | 20 | Scrolling, menus |
-//! | High | >30% | 30 | Video, window dragging |
+//! | Medium | 10-30% | 30 | Scrolling, menus |
+//! | High | >30% | 60 | Video, window dragging |
 //!
 //! # High Performance Mode (60 FPS)
 //!
@@ -28,13 +31,38 @@
 //!
 //! Uses a rolling window of recent damage ratios to calculate
 //! average activity. This smooths out sudden spikes and provides
-//! stable FPS transitions.
+//! stable FPS transitions. A level is only left downwards once the
+//! average damage falls clearly below the threshold that entered it
+//! (`HYSTERESIS`), so activity near a threshold doesn't flap the rate.
+//!
+//! The client's EGFX frame acknowledgement latency caps the result: while
+//! it exceeds `max_ack_latency_ms` the client can't keep up, and the target
+//! drops one tier until the latency is back under half the limit.
+//!
+//! On a congested link the estimated bandwidth caps it as well: below
+//! `LOW_BANDWIDTH_KBPS` the target is at most 15 FPS, below
+//! `MEDIUM_BANDWIDTH_KBPS` at most 30.
 
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 use tracing::debug;
 
+/// FPS target of each activity level, Static to High
+const FPS_TIERS: [u32; 4] = [5, 15, 30, 60];
+
+/// Bandwidth (kbps) of a congested link below which the target is capped
+/// at the Low tier
+const LOW_BANDWIDTH_KBPS: u32 = 1500;
+
+/// Bandwidth (kbps) of a congested link below which the target is capped
+/// at the Medium tier
+const MEDIUM_BANDWIDTH_KBPS: u32 = 4000;
+
+/// Fraction of a level's threshold the average damage must fall below to
+/// leave the level downwards
+const HYSTERESIS: f32 = 0.7;
+
 /// Configuration for adaptive FPS controller
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdaptiveFpsConfig {
@@ -73,6 +101,11 @@ pub struct AdaptiveFpsConfig {
     /// Ramp-down speed (how fast to decrease FPS on idle)
     #[serde(default = "default_ramp_down_frames")]
     pub ramp_down_frames: usize,
+
+    /// Frame acknowledgement latency above which the target drops a tier
+    /// (0 = ignore acknowledgements)
+    #[serde(default = "default_max_ack_latency_ms")]
+    pub max_ack_latency_ms: u32,
 }
 
 fn default_enabled() -> bool {
@@ -102,6 +135,9 @@ fn default_ramp_up_frames() -> usize {
 fn default_ramp_down_frames() -> usize {
     5
 }
+fn default_max_ack_latency_ms() -> u32 {
+    200
+}
 
 impl Default for AdaptiveFpsConfig {
     fn default() -> Self {
@@ -115,6 +151,7 @@ impl Default for AdaptiveFpsConfig {
             low_activity_threshold: default_low_threshold(),
             ramp_up_frames: default_ramp_up_frames(),
             ramp_down_frames: default_ramp_down_frames(),
+            max_ack_latency_ms: default_max_ack_latency_ms(),
         }
     }
 }
@@ -129,7 +166,8 @@ pub struct DamageRatio {
 }
 
 /// Activity level classification
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActivityLevel {
     /// Screen is static (< low_threshold damage)
     Static,
@@ -142,13 +180,13 @@ pub enum ActivityLevel {
 }
 
 impl ActivityLevel {
-    /// Get FPS multiplier for this activity level
-    pub fn fps_multiplier(&self) -> f32 {
+    /// Position in [`FPS_TIERS`]
+    fn tier(&self) -> usize {
         match self {
-            Self::Static => 0.0, // Use min_fps
-            Self::Low => 0.5,
-            Self::Medium => 0.67,
-            Self::High => 1.0,
+            Self::Static => 0,
+            Self::Low => 1,
+            Self::Medium => 2,
+            Self::High => 3,
         }
     }
 }
@@ -176,6 +214,18 @@ pub struct AdaptiveFpsController {
     /// Frames at current activity level (for ramp smoothing)
     frames_at_level: usize,
 
+    /// Smoothed frame acknowledgement latency of the client
+    ack_latency: Option<Duration>,
+
+    /// Target held a tier down because the client acknowledges too slowly
+    latency_limited: bool,
+
+    /// Bandwidth of the client's link while it is congested
+    congested_bandwidth_kbps: Option<u32>,
+
+    /// Highest frame rate the client can decode, if it fell behind
+    decode_cap: Option<u32>,
+
     /// Statistics
     stats: AdaptiveFpsStats,
 }
@@ -211,6 +261,10 @@ impl AdaptiveFpsController {
             damage_history: VecDeque::with_capacity(config.history_size),
             last_frame_time: Instant::now(),
             frames_at_level: 0,
+            ack_latency: None,
+            latency_limited: false,
+            congested_bandwidth_kbps: None,
+            decode_cap: None,
             stats: AdaptiveFpsStats::default(),
             config,
         }
@@ -240,16 +294,14 @@ impl AdaptiveFpsController {
         // Calculate average damage
         let avg_damage = self.average_damage();
 
-        // Determine target activity level
-        let target_level = if avg_damage > self.config.high_activity_threshold {
-            ActivityLevel::High
-        } else if avg_damage > self.config.medium_activity_threshold {
-            ActivityLevel::Medium
-        } else if avg_damage > self.config.low_activity_threshold {
-            ActivityLevel::Low
-        } else {
-            ActivityLevel::Static
-        };
+        // Determine target activity level, staying at the current one while
+        // damage is within the hysteresis band below its threshold
+        let mut target_level = self.classify(avg_damage);
+        if target_level < self.activity_level
+            && self.classify(avg_damage / HYSTERESIS) >= self.activity_level
+        {
+            target_level = self.activity_level;
+        }
 
         // Apply ramping for smooth transitions
         let new_level = self.apply_ramping(target_level);
@@ -285,13 +337,65 @@ impl AdaptiveFpsController {
         self.stats.frames_processed += 1;
     }
 
+    /// Update with the client's smoothed frame acknowledgement latency
+    pub fn update_ack_latency(&mut self, latency: Duration) {
+        self.ack_latency = Some(latency);
+
+        let limit = Duration::from_millis(u64::from(self.config.max_ack_latency_ms));
+        let limited = if limit.is_zero() {
+            false
+        } else if latency > limit {
+            true
+        } else if latency < limit / 2 {
+            false
+        } else {
+            self.latency_limited
+        };
+        if limited != self.latency_limited {
+            debug!(
+                "Frame acknowledgement latency {:?} (limit {:?}): {}",
+                latency,
+                limit,
+                if limited {
+                    "lowering FPS target"
+                } else {
+                    "FPS target restored"
+                }
+            );
+            self.latency_limited = limited;
+        }
+
+        if self.config.enabled {
+            self.current_fps = self.calculate_target_fps();
+        }
+    }
+
+    /// Update with the estimated bandwidth of a congested link, or None
+    /// while the link is clear
+    pub fn update_link(&mut self, congested_bandwidth_kbps: Option<u32>) {
+        self.congested_bandwidth_kbps = congested_bandwidth_kbps;
+        if self.config.enabled {
+            self.current_fps = self.calculate_target_fps();
+        }
+    }
+
+    /// Cap the frame rate at what the client can decode (None = no cap)
+    pub fn set_decode_cap(&mut self, fps: Option<u32>) {
+        self.decode_cap = fps;
+        self.current_fps = if self.config.enabled {
+            self.calculate_target_fps()
+        } else {
+            self.max_fps()
+        };
+    }
+
     /// Check if we should capture this frame based on current FPS
     ///
     /// Returns `true` if enough time has elapsed since last frame.
     pub fn should_capture_frame(&mut self) -> bool {
         if !self.config.enabled {
             // When disabled, always capture at max FPS timing
-            let frame_interval = Duration::from_secs_f32(1.0 / self.config.max_fps as f32);
+            let frame_interval = Duration::from_secs_f32(1.0 / self.max_fps() as f32);
             let elapsed = self.last_frame_time.elapsed();
             if elapsed >= frame_interval {
                 self.last_frame_time = Instant::now();
@@ -322,6 +426,16 @@ impl AdaptiveFpsController {
         self.activity_level
     }
 
+    /// Last reported frame acknowledgement latency
+    pub fn ack_latency(&self) -> Option<Duration> {
+        self.ack_latency
+    }
+
+    /// Whether the target is held down by the acknowledgement latency
+    pub fn is_latency_limited(&self) -> bool {
+        self.latency_limited
+    }
+
     /// Get statistics
     pub fn stats(&self) -> &AdaptiveFpsStats {
         &self.stats
@@ -341,7 +455,37 @@ impl AdaptiveFpsController {
     pub fn set_enabled(&mut self, enabled: bool) {
         self.config.enabled = enabled;
         if !enabled {
-            self.current_fps = self.config.max_fps;
+            self.current_fps = self.max_fps();
+        }
+    }
+
+    /// Replace the configuration at runtime (e.g. on config reload)
+    ///
+    /// Activity history is kept; the target FPS is re-clamped to the new bounds.
+    pub fn set_config(&mut self, config: AdaptiveFpsConfig) {
+        self.config = config;
+        self.current_fps = if self.config.enabled {
+            self.calculate_target_fps()
+        } else {
+            self.max_fps()
+        };
+    }
+
+    /// Configured maximum, lowered to the decode cap
+    fn max_fps(&self) -> u32 {
+        self.decode_cap
+            .map_or(self.config.max_fps, |cap| cap.min(self.config.max_fps))
+    }
+
+    fn classify(&self, damage: f32) -> ActivityLevel {
+        if damage > self.config.high_activity_threshold {
+            ActivityLevel::High
+        } else if damage > self.config.medium_activity_threshold {
+            ActivityLevel::Medium
+        } else if damage > self.config.low_activity_threshold {
+            ActivityLevel::Low
+        } else {
+            ActivityLevel::Static
         }
     }
 
@@ -387,15 +531,19 @@ impl AdaptiveFpsController {
     }
 
     fn calculate_target_fps(&self) -> u32 {
-        let multiplier = self.activity_level.fps_multiplier();
-
-        if multiplier == 0.0 {
-            self.config.min_fps
-        } else {
-            let range = self.config.max_fps - self.config.min_fps;
-            let fps = self.config.min_fps + (range as f32 * multiplier) as u32;
-            fps.clamp(self.config.min_fps, self.config.max_fps)
+        let mut tier = self.activity_level.tier();
+        if self.latency_limited {
+            tier = tier.saturating_sub(1);
         }
+        match self.congested_bandwidth_kbps {
+            Some(kbps) if kbps < LOW_BANDWIDTH_KBPS => tier = tier.min(1),
+            Some(kbps) if kbps < MEDIUM_BANDWIDTH_KBPS => tier = tier.min(2),
+            _ => {}
+        }
+        FPS_TIERS[tier]
+            .min(self.config.max_fps)
+            .max(self.config.min_fps)
+            .min(self.max_fps())
     }
 }
 
@@ -503,4 +651,126 @@ mod tests {
         // Should be at least Medium after 3 high-activity frames
         assert!(controller.activity_level() >= ActivityLevel::Low);
     }
+
+    #[test]
+    fn test_set_config_applies_new_bounds() {
+        let mut controller = AdaptiveFpsController::new(AdaptiveFpsConfig::default());
+        for _ in 0..50 {
+            controller.update(0.0);
+        }
+        assert_eq!(controller.current_fps(), 5);
+
+        let config = AdaptiveFpsConfig {
+            min_fps: 10,
+            max_fps: 60,
+            ..Default::default()
+        };
+        controller.set_config(config);
+        assert_eq!(controller.activity_level(), ActivityLevel::Static);
+        assert_eq!(controller.current_fps(), 10);
+    }
+
+    #[test]
+    fn test_fps_tiers_with_60_fps_max() {
+        let config = AdaptiveFpsConfig {
+            max_fps: 60,
+            ..Default::default()
+        };
+        let mut controller = AdaptiveFpsController::new(config);
+        for _ in 0..10 {
+            controller.update(0.5);
+        }
+        assert_eq!(controller.current_fps(), 60);
+
+        for _ in 0..50 {
+            controller.update(0.05);
+        }
+        assert_eq!(controller.activity_level(), ActivityLevel::Low);
+        assert_eq!(controller.current_fps(), 15);
+    }
+
+    #[test]
+    fn test_hysteresis_holds_level_near_threshold() {
+        let mut controller = AdaptiveFpsController::new(AdaptiveFpsConfig::default());
+        for _ in 0..10 {
+            controller.update(0.5);
+        }
+        assert_eq!(controller.activity_level(), ActivityLevel::High);
+
+        // Just below the high threshold: stays High
+        for _ in 0..50 {
+            controller.update(0.25);
+        }
+        assert_eq!(controller.activity_level(), ActivityLevel::High);
+
+        // Clearly below: drops to Medium
+        for _ in 0..50 {
+            controller.update(0.15);
+        }
+        assert_eq!(controller.activity_level(), ActivityLevel::Medium);
+    }
+
+    #[test]
+    fn test_ack_latency_lowers_tier() {
+        let config = AdaptiveFpsConfig {
+            max_fps: 60,
+            ..Default::default()
+        };
+        let mut controller = AdaptiveFpsController::new(config);
+        for _ in 0..10 {
+            controller.update(0.5);
+        }
+        assert_eq!(controller.current_fps(), 60);
+
+        controller.update_ack_latency(Duration::from_millis(300));
+        assert!(controller.is_latency_limited());
+        assert_eq!(controller.current_fps(), 30);
+
+        // Within the hysteresis band the limit holds
+        controller.update_ack_latency(Duration::from_millis(150));
+        assert_eq!(controller.current_fps(), 30);
+
+        controller.update_ack_latency(Duration::from_millis(50));
+        assert!(!controller.is_latency_limited());
+        assert_eq!(controller.current_fps(), 60);
+        assert_eq!(controller.ack_latency(), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn test_congested_link_caps_tier() {
+        let config = AdaptiveFpsConfig {
+            max_fps: 60,
+            ..Default::default()
+        };
+        let mut controller = AdaptiveFpsController::new(config);
+        for _ in 0..10 {
+            controller.update(0.5);
+        }
+
+        controller.update_link(Some(3000));
+        assert_eq!(controller.current_fps(), 30);
+        controller.update_link(Some(1000));
+        assert_eq!(controller.current_fps(), 15);
+        controller.update_link(None);
+        assert_eq!(controller.current_fps(), 60);
+    }
+
+    #[test]
+    fn test_decode_cap() {
+        let config = AdaptiveFpsConfig {
+            max_fps: 60,
+            ..Default::default()
+        };
+        let mut controller = AdaptiveFpsController::new(config);
+        for _ in 0..10 {
+            controller.update(0.5);
+        }
+
+        controller.set_decode_cap(Some(30));
+        assert_eq!(controller.current_fps(), 30);
+        controller.set_enabled(false);
+        assert_eq!(controller.current_fps(), 30);
+        controller.set_decode_cap(None);
+        assert_eq!(controller.current_fps(), 60);
+    }
 }
@@ -0,0 +1,306 @@
+//! Pipeline Benchmark
+//!
+//! `lamco-rdp-server bench` runs synthetic desktop frames through the
+//! stages of the video pipeline, without a client or a capture session:
+//!
+//! - damage detection at 16, 32 and 64 pixel tiles,
+//! - BGRA to YUV444 color conversion and 4:2:0 chroma subsampling, and
+//! - every encoder built in: OpenH264 for AVC420 and AVC444, VA-API and
+//!   NVENC.
+//!
+//! Each stage reports its per-frame latency (mean, median, 95th percentile,
+//! worst) and the frame rate it could sustain on its own, so hardware
+//! encoders can be compared with software encoding on the same machine.
+//! The startup [`HardwareProbe`](super::HardwareProbe) times the same
+//! stages briefly to pick defaults; this runs them long enough to compare.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::config::HardwareEncodingConfig;
+use crate::damage::{DamageConfig, DamageDetector};
+use crate::egfx::{
+    align_to_16, bgra_to_yuv444, subsample_chroma_420, Avc420Encoder, Avc444Encoder, ColorMatrix,
+    EncoderConfig,
+};
+
+use super::hardware_profile::test_frame;
+
+/// Distinct frames cycled through, so every frame has changes
+const VARIANTS: u32 = 4;
+
+/// Frames an encoder gets before timing starts (the first is an IDR)
+const WARMUP_FRAMES: usize = 2;
+
+/// What to benchmark
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Frame width (rounded up to a multiple of 16 for the encoders)
+    pub width: u32,
+    /// Frame height (rounded up to a multiple of 16 for the encoders)
+    pub height: u32,
+    /// Frames timed per stage
+    pub frames: u32,
+    /// Settings of the hardware encoders
+    pub hardware_encoding: HardwareEncodingConfig,
+}
+
+/// Per-frame timings of one stage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageTiming {
+    /// Frames timed
+    pub frames: usize,
+    /// Mean time per frame
+    pub mean_ms: f64,
+    /// Median time per frame
+    pub p50_ms: f64,
+    /// 95th percentile time per frame
+    pub p95_ms: f64,
+    /// Slowest frame
+    pub max_ms: f64,
+    /// Frames per second the stage sustains on its own
+    pub fps: f64,
+}
+
+impl StageTiming {
+    fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let total: Duration = samples.iter().sum();
+        let percentile = |p: usize| ms(samples[(samples.len() - 1) * p / 100]);
+        let mean_ms = ms(total) / samples.len() as f64;
+        Some(Self {
+            frames: samples.len(),
+            mean_ms,
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            max_ms: ms(samples[samples.len() - 1]),
+            fps: if mean_ms > 0.0 { 1000.0 / mean_ms } else { 0.0 },
+        })
+    }
+}
+
+/// Result of one stage
+#[derive(Debug, Clone, Serialize)]
+pub struct StageResult {
+    /// Pipeline stage ("damage", "color", "encode")
+    pub stage: &'static str,
+    /// Variant of the stage ("16px tiles", "VA-API", ...)
+    pub name: String,
+    /// None when the stage could not run
+    pub timing: Option<StageTiming>,
+    /// Why the stage could not run
+    pub error: Option<String>,
+}
+
+impl StageResult {
+    fn measured(stage: &'static str, name: impl Into<String>, samples: Vec<Duration>) -> Self {
+        Self {
+            stage,
+            name: name.into(),
+            timing: StageTiming::from_samples(samples),
+            error: None,
+        }
+    }
+
+    fn failed(stage: &'static str, name: impl Into<String>, error: impl ToString) -> Self {
+        Self {
+            stage,
+            name: name.into(),
+            timing: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Benchmark results of all stages
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    /// Frame width the stages ran at
+    pub width: u32,
+    /// Frame height the stages ran at
+    pub height: u32,
+    /// One result per stage, in the order they ran
+    pub results: Vec<StageResult>,
+}
+
+/// Run every stage (blocks for as long as the stages take)
+pub fn run(options: &BenchOptions) -> BenchReport {
+    let width = align_to_16(options.width);
+    let height = align_to_16(options.height);
+    let frames: Vec<Vec<u8>> = (0..VARIANTS)
+        .map(|variant| test_frame(width, height, variant))
+        .collect();
+    let count = options.frames.max(1) as usize;
+    let sequence = || frames.iter().cycle().take(count);
+
+    let mut results = Vec::new();
+
+    for tile_size in [16, 32, 64] {
+        let mut detector = DamageDetector::new(DamageConfig {
+            tile_size,
+            ..Default::default()
+        });
+        // The first frame is a full-screen change without comparison
+        detector.detect(&frames[VARIANTS as usize - 1], width, height);
+        let samples = sequence()
+            .map(|frame| timed(|| detector.detect(frame, width, height)))
+            .collect();
+        results.push(StageResult::measured(
+            "damage",
+            format!("{}px tiles", tile_size),
+            samples,
+        ));
+    }
+
+    let mut yuv444_samples = Vec::with_capacity(count);
+    let mut chroma_samples = Vec::with_capacity(count);
+    for frame in sequence() {
+        let start = Instant::now();
+        let yuv = bgra_to_yuv444(frame, width as usize, height as usize, ColorMatrix::BT709);
+        yuv444_samples.push(start.elapsed());
+
+        let start = Instant::now();
+        std::hint::black_box(subsample_chroma_420(
+            &yuv.u,
+            width as usize,
+            height as usize,
+        ));
+        std::hint::black_box(subsample_chroma_420(
+            &yuv.v,
+            width as usize,
+            height as usize,
+        ));
+        chroma_samples.push(start.elapsed());
+        yuv.recycle();
+    }
+    results.push(StageResult::measured(
+        "color",
+        "BGRA → YUV444 (BT.709)",
+        yuv444_samples,
+    ));
+    results.push(StageResult::measured(
+        "color",
+        "YUV444 → 4:2:0 chroma",
+        chroma_samples,
+    ));
+
+    let config = EncoderConfig {
+        width: Some(width as u16),
+        height: Some(height as u16),
+        ..Default::default()
+    };
+    results.push(match Avc420Encoder::new(config.clone()) {
+        Ok(mut encoder) => encode_stage("OpenH264 AVC420", sequence(), |frame, timestamp| {
+            encoder
+                .encode_bgra(frame, width, height, timestamp)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }),
+        Err(e) => StageResult::failed("encode", "OpenH264 AVC420", e),
+    });
+    results.push(match Avc444Encoder::new(config) {
+        Ok(mut encoder) => encode_stage("OpenH264 AVC444", sequence(), |frame, timestamp| {
+            encoder
+                .encode_bgra(frame, width, height, timestamp)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }),
+        Err(e) => StageResult::failed("encode", "OpenH264 AVC444", e),
+    });
+
+    #[cfg(any(feature = "vaapi", feature = "nvenc"))]
+    for backend in crate::egfx::hardware::available_backends() {
+        let name = match backend {
+            "vaapi" => "VA-API",
+            "nvenc" => "NVENC",
+            other => other,
+        };
+        results.push(
+            match crate::egfx::hardware::create_backend_encoder(
+                backend,
+                &options.hardware_encoding,
+                width,
+                height,
+            ) {
+                Ok(mut encoder) => encode_stage(name, sequence(), |frame, timestamp| {
+                    encoder
+                        .encode_bgra(frame, width, height, timestamp)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }),
+                Err(e) => StageResult::failed("encode", name, e),
+            },
+        );
+    }
+    #[cfg(not(any(feature = "vaapi", feature = "nvenc")))]
+    results.push(StageResult::failed(
+        "encode",
+        "hardware",
+        "not built with the vaapi or nvenc feature",
+    ));
+
+    BenchReport {
+        width,
+        height,
+        results,
+    }
+}
+
+/// Time `encode` over `frames` after a few untimed warm-up frames
+fn encode_stage<'a>(
+    name: &str,
+    frames: impl Iterator<Item = &'a Vec<u8>>,
+    mut encode: impl FnMut(&[u8], u64) -> Result<(), String>,
+) -> StageResult {
+    let mut samples = Vec::new();
+    for (i, frame) in frames.enumerate() {
+        // 30 fps timestamps
+        let timestamp = i as u64 * 33;
+        let start = Instant::now();
+        if let Err(e) = encode(frame, timestamp) {
+            return StageResult::failed("encode", name, e);
+        }
+        if i >= WARMUP_FRAMES {
+            samples.push(start.elapsed());
+        }
+    }
+    StageResult::measured("encode", name, samples)
+}
+
+fn timed<T>(stage: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    std::hint::black_box(stage());
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_timing_percentiles() {
+        let samples = (1..=100).map(Duration::from_millis).collect();
+        let timing = StageTiming::from_samples(samples).unwrap();
+        assert_eq!(timing.frames, 100);
+        assert!((timing.mean_ms - 50.5).abs() < 1e-9);
+        assert!((timing.p50_ms - 50.0).abs() < 1e-9);
+        assert!((timing.p95_ms - 95.0).abs() < 1e-9);
+        assert!((timing.max_ms - 100.0).abs() < 1e-9);
+        assert!(StageTiming::from_samples(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_encode_stage_skips_warmup() {
+        let frames = vec![vec![0u8; 4]; 5];
+        let result = encode_stage("test", frames.iter(), |_, _| Ok(()));
+        assert_eq!(result.timing.unwrap().frames, 5 - WARMUP_FRAMES);
+
+        let result = encode_stage("test", frames.iter(), |_, _| Err("no device".into()));
+        assert_eq!(result.error.as_deref(), Some("no device"));
+    }
+}
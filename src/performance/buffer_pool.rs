@@ -0,0 +1,280 @@
+//! Frame Buffer Pool
+//!
+//! Every frame passes through several frame-sized buffers: cropped and
+//! scaled pixels, the damage detector's copy of the previous frame, the
+//! YUV444 planes and both YUV420 views of AVC444. Allocating them per
+//! frame costs little at 1080p, but at 4K each one is tens of megabytes and
+//! the allocator returns them to the OS and maps them again on every frame.
+//!
+//! The pool keeps returned buffers by size class (steps of 1/16 of a power
+//! of two, so less than 1/8 of a buffer goes unused) and hands them out
+//! again. A class only keeps as many buffers as it has lent out, up to
+//! `performance.buffer_pool_size`, so the pool never grows beyond what the
+//! pipeline actually held at its peak.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+/// Buffers below this size are left to the allocator
+const MIN_CLASS: usize = 4096;
+
+/// Size classes per power of two
+const CLASSES_PER_OCTAVE: usize = 16;
+
+/// Buffers kept per size class unless configured otherwise
+const DEFAULT_MAX_PER_CLASS: usize = 16;
+
+/// Pool usage counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferPoolStats {
+    /// Buffers served from the pool
+    pub reused: u64,
+    /// Buffers the pool had to allocate
+    pub allocated: u64,
+    /// Returned buffers freed because their class was full
+    pub discarded: u64,
+    /// Bytes lent out and not returned yet
+    pub in_use_bytes: usize,
+    /// Bytes held for reuse
+    pub pooled_bytes: usize,
+    /// Most bytes lent out and held at once
+    pub peak_bytes: usize,
+}
+
+impl BufferPoolStats {
+    /// Fraction of requests served without allocating
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.reused + self.allocated;
+        if total == 0 {
+            return 0.0;
+        }
+        self.reused as f64 / total as f64
+    }
+}
+
+/// Size-class pool of byte buffers
+///
+/// The frame path shares [`BufferPool::global`]; separate pools are for tests.
+#[derive(Debug)]
+pub struct BufferPool {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_per_class: usize,
+    classes: HashMap<usize, SizeClass>,
+    stats: BufferPoolStats,
+}
+
+#[derive(Debug, Default)]
+struct SizeClass {
+    idle: Vec<Vec<u8>>,
+    /// Buffers of this class handed out and not returned
+    lent: usize,
+}
+
+impl BufferPool {
+    /// Pool keeping up to `max_per_class` idle buffers of each size
+    pub fn new(max_per_class: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                max_per_class,
+                classes: HashMap::new(),
+                stats: BufferPoolStats::default(),
+            }),
+        }
+    }
+
+    /// Pool shared by the frame path
+    pub fn global() -> &'static BufferPool {
+        static POOL: OnceLock<BufferPool> = OnceLock::new();
+        POOL.get_or_init(|| BufferPool::new(DEFAULT_MAX_PER_CLASS))
+    }
+
+    /// Keep up to `max_per_class` idle buffers of each size (0 = none)
+    pub fn set_max_per_class(&self, max_per_class: usize) {
+        let mut inner = self.lock();
+        inner.max_per_class = max_per_class;
+        let mut freed = 0;
+        for (size, class) in inner.classes.iter_mut() {
+            while class.idle.len() > max_per_class {
+                class.idle.pop();
+                freed += size;
+            }
+        }
+        inner.stats.pooled_bytes -= freed;
+    }
+
+    /// Empty buffer with room for at least `len` bytes
+    pub fn take(&self, len: usize) -> Vec<u8> {
+        if len < MIN_CLASS {
+            return Vec::with_capacity(len);
+        }
+        let size = class_size(len);
+
+        let mut inner = self.lock();
+        let class = inner.classes.entry(size).or_default();
+        class.lent += 1;
+        let reused = class.idle.pop();
+
+        let stats = &mut inner.stats;
+        stats.in_use_bytes += size;
+        let mut buffer = match reused {
+            Some(buffer) => {
+                stats.reused += 1;
+                stats.pooled_bytes -= size;
+                buffer
+            }
+            None => {
+                stats.allocated += 1;
+                stats.peak_bytes = stats
+                    .peak_bytes
+                    .max(stats.in_use_bytes + stats.pooled_bytes);
+                Vec::with_capacity(size)
+            }
+        };
+        buffer.clear();
+        buffer
+    }
+
+    /// Buffer of `len` bytes, all set to `value`
+    pub fn take_filled(&self, len: usize, value: u8) -> Vec<u8> {
+        let mut buffer = self.take(len);
+        buffer.resize(len, value);
+        buffer
+    }
+
+    /// Return a buffer from [`take`](Self::take) for reuse
+    pub fn recycle(&self, buffer: Vec<u8>) {
+        let capacity = buffer.capacity();
+        if capacity < MIN_CLASS {
+            return;
+        }
+        let size = class_floor(capacity);
+
+        let mut inner = self.lock();
+        let max_per_class = inner.max_per_class;
+        let Some(class) = inner.classes.get_mut(&size) else {
+            return;
+        };
+        // Returned more often than lent: not one of ours
+        if class.lent == 0 {
+            return;
+        }
+        class.lent -= 1;
+        let keep = class.idle.len() < max_per_class;
+        if keep {
+            class.idle.push(buffer);
+        }
+
+        let stats = &mut inner.stats;
+        stats.in_use_bytes -= size;
+        if keep {
+            stats.pooled_bytes += size;
+        } else {
+            stats.discarded += 1;
+        }
+    }
+
+    /// Return a shared buffer if nothing else holds it anymore
+    pub fn recycle_shared(&self, buffer: Arc<Vec<u8>>) {
+        if let Ok(buffer) = Arc::try_unwrap(buffer) {
+            self.recycle(buffer);
+        }
+    }
+
+    /// Usage counters
+    pub fn stats(&self) -> BufferPoolStats {
+        self.lock().stats
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Smallest class size holding `len` bytes
+fn class_size(len: usize) -> usize {
+    let step = (len.next_power_of_two() / CLASSES_PER_OCTAVE).max(1);
+    len.div_ceil(step) * step
+}
+
+/// Largest class size that fits in `capacity` bytes
+fn class_floor(capacity: usize) -> usize {
+    let size = class_size(capacity);
+    if size == capacity {
+        return size;
+    }
+    let step = (size.next_power_of_two() / CLASSES_PER_OCTAVE).max(1);
+    size - step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_classes() {
+        assert_eq!(class_size(4096), 4096);
+        assert_eq!(class_size(4097), 4096 + 512);
+        // 4K BGRA frame: less than 1/8 unused
+        let frame = 3840 * 2160 * 4;
+        assert!(class_size(frame) >= frame);
+        assert!(class_size(frame) - frame < frame / 8);
+
+        assert_eq!(class_floor(class_size(5000)), class_size(5000));
+        assert_eq!(class_floor(4097), 4096);
+        assert!(class_floor(1_000_000) <= 1_000_000);
+    }
+
+    #[test]
+    fn test_buffers_reused_by_class() {
+        let pool = BufferPool::new(2);
+        let len = 1920 * 1080 * 4;
+
+        let buffer = pool.take_filled(len, 7);
+        assert_eq!(buffer.len(), len);
+        pool.recycle(buffer);
+
+        // Slightly smaller requests share the class
+        let buffer = pool.take(len - 100);
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= len);
+
+        let stats = pool.stats();
+        assert_eq!(stats.allocated, 1);
+        assert_eq!(stats.reused, 1);
+        assert_eq!(stats.pooled_bytes, 0);
+        assert_eq!(stats.in_use_bytes, class_size(len));
+        assert_eq!(stats.peak_bytes, class_size(len));
+        assert_eq!(stats.hit_rate(), 0.5);
+        pool.recycle(buffer);
+    }
+
+    #[test]
+    fn test_pool_bounded_by_lent_buffers() {
+        let pool = BufferPool::new(1);
+        let len = 64 * 1024;
+
+        // Foreign buffers of a class nothing was lent from are dropped
+        pool.recycle(vec![0; len]);
+        assert_eq!(pool.stats().pooled_bytes, 0);
+
+        let first = pool.take(len);
+        let second = pool.take(len);
+        pool.recycle(first);
+        pool.recycle(second);
+        pool.recycle(vec![0; len]);
+
+        let stats = pool.stats();
+        assert_eq!(stats.pooled_bytes, len);
+        assert_eq!(stats.in_use_bytes, 0);
+        assert_eq!(stats.discarded, 1);
+
+        pool.set_max_per_class(0);
+        assert_eq!(pool.stats().pooled_bytes, 0);
+    }
+}
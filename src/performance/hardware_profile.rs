@@ -0,0 +1,360 @@
+//! Hardware Performance Profile
+//!
+//! Sane defaults differ between a workstation and a thin client. On startup
+//! the server times the per-frame work of its pipeline on a 1080p frame:
+//!
+//! - BGRA to YUV444 color conversion,
+//! - damage detection with 16×16 tiles, and
+//! - a short software H.264 run,
+//!
+//! and picks the damage tile size, the adaptive FPS cap, the hardware
+//! encoder quality preset and software vs hardware encoding to match.
+//! Only settings still at their built-in defaults change, so anything set
+//! in the configuration file wins.
+//!
+//! Measurements are cached in `$XDG_CACHE_HOME/lamco-rdp-server/` and reused
+//! until the CPU or the server version changes; `--reprofile` measures again.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::damage::{DamageConfig, DamageDetector};
+use crate::egfx::{bgra_to_yuv444, Avc420Encoder, ColorMatrix, EncoderConfig};
+
+/// Benchmark frame size (1080p; height aligned to 16 for the encoder)
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1088;
+
+/// Frames timed per benchmark
+const ITERATIONS: u32 = 10;
+
+/// Cache file name below the cache directory
+const CACHE_FILE: &str = "hardware-profile.json";
+
+/// Per-frame cost of the pipeline stages on this machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareProbe {
+    /// CPU model the measurements were taken on
+    pub cpu_model: String,
+    /// Logical CPUs
+    pub cpu_count: usize,
+    /// Server version that measured
+    pub version: String,
+    /// BGRA to YUV444 conversion of one frame (ms)
+    pub color_convert_ms: f64,
+    /// Damage detection of one frame (ms)
+    pub damage_ms: f64,
+    /// Software H.264 encoding of one frame (ms), None without an encoder
+    pub encode_ms: Option<f64>,
+}
+
+impl HardwareProbe {
+    /// Time the pipeline stages (blocks for up to a few seconds)
+    pub fn run() -> Self {
+        let (cpu_model, cpu_count) = cpu_identity();
+        let frames = [test_frame(WIDTH, HEIGHT, 0), test_frame(WIDTH, HEIGHT, 1)];
+
+        let start = Instant::now();
+        for frame in frames.iter().cycle().take(ITERATIONS as usize) {
+            std::hint::black_box(bgra_to_yuv444(
+                frame,
+                WIDTH as usize,
+                HEIGHT as usize,
+                ColorMatrix::BT709,
+            ))
+            .recycle();
+        }
+        let color_convert_ms = per_frame_ms(start);
+
+        let mut detector = DamageDetector::new(DamageConfig {
+            tile_size: 16,
+            ..Default::default()
+        });
+        // The first frame is a full-screen change without comparison
+        detector.detect(&frames[1], WIDTH, HEIGHT);
+        let start = Instant::now();
+        for frame in frames.iter().cycle().take(ITERATIONS as usize) {
+            std::hint::black_box(detector.detect(frame, WIDTH, HEIGHT));
+        }
+        let damage_ms = per_frame_ms(start);
+
+        let encode_ms = match time_encoder(&frames) {
+            Ok(ms) => Some(ms),
+            Err(e) => {
+                debug!("Software encoder benchmark skipped: {:#}", e);
+                None
+            }
+        };
+
+        Self {
+            cpu_model,
+            cpu_count,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            color_convert_ms,
+            damage_ms,
+            encode_ms,
+        }
+    }
+
+    /// Cached measurements for this machine, or new ones
+    ///
+    /// `reprofile` ignores the cache. New measurements are written back;
+    /// failing to do so only costs another probe on the next start.
+    pub fn load_or_run(reprofile: bool) -> Self {
+        let path = cache_path();
+        if !reprofile {
+            if let Some(cached) = path.as_ref().and_then(|path| Self::load(path)) {
+                debug!("Using cached hardware profile");
+                return cached;
+            }
+        }
+
+        info!("Measuring hardware performance");
+        let probe = Self::run();
+        if let Some(path) = path {
+            if let Err(e) = probe.save(&path) {
+                warn!("Failed to cache hardware profile: {:#}", e);
+            }
+        }
+        probe
+    }
+
+    /// Estimated pipeline time of one full-screen frame (ms)
+    ///
+    /// Without a software encoder, color conversion stands in for it at
+    /// the ratio the two usually have.
+    pub fn frame_ms(&self) -> f64 {
+        self.damage_ms + self.encode_ms.unwrap_or(self.color_convert_ms * 4.0)
+    }
+
+    /// Whether the measurements came from this machine and server version
+    fn matches_machine(&self) -> bool {
+        let (cpu_model, cpu_count) = cpu_identity();
+        self.cpu_model == cpu_model
+            && self.cpu_count == cpu_count
+            && self.version == env!("CARGO_PKG_VERSION")
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let probe: Self = serde_json::from_str(&contents).ok()?;
+        probe.matches_machine().then_some(probe)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Defaults chosen for this machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerformanceProfile {
+    /// Damage detection tile size in pixels
+    pub tile_size: usize,
+    /// Adaptive FPS cap
+    pub max_fps: u32,
+    /// Hardware encoder quality preset
+    pub quality_preset: &'static str,
+    /// Encode with VA-API/NVENC instead of OpenH264
+    pub hardware_encoding: bool,
+}
+
+impl PerformanceProfile {
+    /// Pick defaults from measurements and whether a hardware encoder exists
+    pub fn select(probe: &HardwareProbe, hardware_encoder: bool) -> Self {
+        let tile_size = match probe.damage_ms {
+            ms if ms <= 2.0 => 16,
+            ms if ms <= 5.0 => 32,
+            _ => 64,
+        };
+
+        // Leave half of each frame interval for capture, network and the rest
+        let (max_fps, quality_preset) = match probe.frame_ms() {
+            ms if ms <= 8.0 => (60, "quality"),
+            ms if ms <= 16.0 => (30, "balanced"),
+            _ => (15, "speed"),
+        };
+
+        Self {
+            tile_size,
+            max_fps,
+            quality_preset,
+            hardware_encoding: hardware_encoder,
+        }
+    }
+
+    /// Apply to the settings of `config` that still equal `defaults`
+    ///
+    /// Returns the paths of the settings that changed.
+    pub fn apply(&self, config: &mut Config, defaults: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+
+        if config.damage_tracking.tile_size == defaults.damage_tracking.tile_size
+            && config.damage_tracking.tile_size != self.tile_size
+        {
+            config.damage_tracking.tile_size = self.tile_size;
+            changed.push("damage_tracking.tile_size");
+        }
+
+        let fps = &mut config.performance.adaptive_fps;
+        if fps.max_fps == defaults.performance.adaptive_fps.max_fps && fps.max_fps != self.max_fps {
+            fps.max_fps = self.max_fps;
+            fps.min_fps = fps.min_fps.min(self.max_fps);
+            changed.push("performance.adaptive_fps.max_fps");
+        }
+
+        let hardware = &mut config.hardware_encoding;
+        if hardware.quality_preset == defaults.hardware_encoding.quality_preset
+            && hardware.quality_preset != self.quality_preset
+        {
+            hardware.quality_preset = self.quality_preset.to_string();
+            changed.push("hardware_encoding.quality_preset");
+        }
+
+        if hardware.enabled == defaults.hardware_encoding.enabled
+            && hardware.enabled != self.hardware_encoding
+        {
+            hardware.enabled = self.hardware_encoding;
+            changed.push("hardware_encoding.enabled");
+        }
+
+        changed
+    }
+}
+
+/// Whether a VA-API or NVENC device is present
+pub fn hardware_encoder_available() -> bool {
+    #[cfg(any(feature = "vaapi", feature = "nvenc"))]
+    {
+        let (vaapi, nvenc) = crate::egfx::hardware::probe_backends();
+        vaapi || nvenc
+    }
+    #[cfg(not(any(feature = "vaapi", feature = "nvenc")))]
+    {
+        false
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("lamco-rdp-server").join(CACHE_FILE))
+}
+
+fn cpu_identity() -> (String, usize) {
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    let model = sys
+        .cpus()
+        .first()
+        .map(|cpu| cpu.brand().trim().to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+    (model, sys.cpus().len())
+}
+
+fn time_encoder(frames: &[Vec<u8>; 2]) -> Result<f64> {
+    let mut encoder = Avc420Encoder::new(EncoderConfig {
+        width: Some(WIDTH as u16),
+        height: Some(HEIGHT as u16),
+        ..Default::default()
+    })?;
+    // The first frame is an IDR and not representative
+    encoder.encode_bgra(&frames[0], WIDTH, HEIGHT, 0)?;
+
+    let start = Instant::now();
+    for (i, frame) in frames
+        .iter()
+        .cycle()
+        .skip(1)
+        .take(ITERATIONS as usize)
+        .enumerate()
+    {
+        encoder.encode_bgra(frame, WIDTH, HEIGHT, (i as u64 + 1) * 33)?;
+    }
+    Ok(per_frame_ms(start))
+}
+
+fn per_frame_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0 / f64::from(ITERATIONS)
+}
+
+/// Desktop-like test frame: a gradient with a moving block of detail
+pub(super) fn test_frame(width: u32, height: u32, variant: u32) -> Vec<u8> {
+    let mut frame = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let in_block =
+                (400 + variant * 64..800 + variant * 64).contains(&x) && (300..600).contains(&y);
+            let detail = if in_block { ((x ^ y) * 7) as u8 } else { 0 };
+            frame.extend_from_slice(&[
+                (x / 8) as u8 ^ detail,
+                (y / 5) as u8,
+                ((x + y) / 12) as u8,
+                255,
+            ]);
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(damage_ms: f64, encode_ms: Option<f64>) -> HardwareProbe {
+        HardwareProbe {
+            cpu_model: "Test CPU".to_string(),
+            cpu_count: 4,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            color_convert_ms: 3.0,
+            damage_ms,
+            encode_ms,
+        }
+    }
+
+    #[test]
+    fn test_select_scales_with_frame_cost() {
+        let fast = PerformanceProfile::select(&probe(1.0, Some(5.0)), true);
+        assert_eq!(fast.tile_size, 16);
+        assert_eq!(fast.max_fps, 60);
+        assert_eq!(fast.quality_preset, "quality");
+        assert!(fast.hardware_encoding);
+
+        let slow = PerformanceProfile::select(&probe(8.0, Some(40.0)), false);
+        assert_eq!(slow.tile_size, 64);
+        assert_eq!(slow.max_fps, 15);
+        assert_eq!(slow.quality_preset, "speed");
+        assert!(!slow.hardware_encoding);
+
+        // Without an encoder measurement, color conversion stands in
+        let unknown = PerformanceProfile::select(&probe(3.0, None), false);
+        assert_eq!(unknown.tile_size, 32);
+        assert_eq!(unknown.max_fps, 30);
+    }
+
+    #[test]
+    fn test_apply_keeps_configured_settings() {
+        let defaults = Config::default_config().unwrap();
+        let mut config = defaults.clone();
+        config.damage_tracking.tile_size = 32;
+        config.hardware_encoding.quality_preset = "quality".to_string();
+
+        let profile = PerformanceProfile::select(&probe(8.0, Some(40.0)), false);
+        let changed = profile.apply(&mut config, &defaults);
+
+        assert_eq!(changed, vec!["performance.adaptive_fps.max_fps"]);
+        assert_eq!(config.damage_tracking.tile_size, 32);
+        assert_eq!(config.performance.adaptive_fps.max_fps, 15);
+        assert_eq!(config.hardware_encoding.quality_preset, "quality");
+        assert!(!config.hardware_encoding.enabled);
+    }
+}
@@ -17,6 +17,21 @@
 //! 1. Accumulated damage since last encode
 //! 2. Time since first damage (prevents starvation)
 //! 3. Mode-specific thresholds
+//!
+//! # Budget Enforcement
+//!
+//! Each mode also bounds the rest of the pipeline:
+//!
+//! | Mode | Frames in flight | Bitrate cap | Keyframe interval cap | HW preset |
+//! |------|------------------|-------------|-----------------------|-----------|
+//! | Interactive | 1 | 8 Mbps | 2 s | speed |
+//! | Balanced | 2 | - | - | balanced |
+//! | Quality | 3 | - | - | quality |
+//!
+//! A frame is dropped before damage detection when the client still has
+//! the mode's limit of unacknowledged frames, or when the frame already
+//! waited longer than the mode's target latency. A newer frame follows,
+//! so dropping keeps the client current instead of queueing stale frames.
 
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
@@ -47,6 +62,16 @@ impl LatencyMode {
         }
     }
 
+    /// Hardware encoder preset (`hardware_encoding.quality_preset`) that
+    /// matches this mode's tuning
+    pub fn hardware_preset(&self) -> &'static str {
+        match self {
+            Self::Interactive => "speed",
+            Self::Balanced => "balanced",
+            Self::Quality => "quality",
+        }
+    }
+
     /// Get human-readable description
     pub fn description(&self) -> &'static str {
         match self {
@@ -91,6 +116,12 @@ struct ModeSettings {
     use_adaptive_fps: bool,
     /// Encode timeout (how long to wait for encoder)
     encode_timeout_ms: u32,
+    /// Unacknowledged frames allowed at the client
+    max_frames_in_flight: u32,
+    /// Encoder bitrate limit, so frames stay small enough to arrive in time
+    max_bitrate_kbps: Option<u32>,
+    /// Periodic keyframe interval limit, so a damaged picture heals quickly
+    max_idr_interval_secs: Option<u32>,
 }
 
 impl ModeSettings {
@@ -101,18 +132,27 @@ impl ModeSettings {
                 damage_threshold: 0.0,    // Encode ANY change immediately
                 use_adaptive_fps: false,  // Always max FPS
                 encode_timeout_ms: 10,
+                max_frames_in_flight: 1,
+                max_bitrate_kbps: Some(8000),
+                max_idr_interval_secs: Some(2),
             },
             LatencyMode::Balanced => Self {
                 max_frame_delay_ms: 33.0, // ~30fps timing
                 damage_threshold: 0.02,   // 2% damage threshold
                 use_adaptive_fps: true,
                 encode_timeout_ms: 20,
+                max_frames_in_flight: 2,
+                max_bitrate_kbps: None,
+                max_idr_interval_secs: None,
             },
             LatencyMode::Quality => Self {
                 max_frame_delay_ms: 100.0, // Can batch more
                 damage_threshold: 0.05,    // 5% damage threshold
                 use_adaptive_fps: true,
                 encode_timeout_ms: 50,
+                max_frames_in_flight: 3,
+                max_bitrate_kbps: None,
+                max_idr_interval_secs: None,
             },
         }
     }
@@ -198,6 +238,8 @@ pub struct LatencyMetrics {
     pub frames_skipped: u64,
     /// Batches (quality mode)
     pub batches_encoded: u64,
+    /// Frames dropped for exceeding the latency budget
+    pub frames_over_budget: u64,
 }
 
 /// Latency Governor
@@ -229,6 +271,62 @@ impl LatencyGovernor {
         }
     }
 
+    /// Override the mode's frame delay and damage threshold
+    ///
+    /// Used to apply the `[performance.latency]` settings of the mode.
+    pub fn with_frame_delay(mut self, max_frame_delay_ms: u32, damage_threshold: f32) -> Self {
+        self.settings.max_frame_delay_ms = max_frame_delay_ms as f32;
+        self.settings.damage_threshold = damage_threshold;
+        self
+    }
+
+    /// Whether a frame fits the latency budget
+    ///
+    /// `in_flight` is the number of frames the client hasn't acknowledged
+    /// yet, `frame_age` how long ago the frame was captured. Frames over
+    /// budget should be dropped before damage detection.
+    pub fn within_budget(&mut self, in_flight: usize, frame_age: Duration) -> bool {
+        let queue_full = in_flight >= self.settings.max_frames_in_flight as usize;
+        let stale = frame_age > Duration::from_millis(u64::from(self.mode.target_latency_ms()));
+        if !queue_full && !stale {
+            return true;
+        }
+
+        self.metrics.frames_over_budget += 1;
+        if self.metrics.frames_over_budget % 100 == 1 {
+            debug!(
+                "LatencyGovernor ({:?}): dropping frame over budget ({} in flight, {:.1}ms old), {} so far",
+                self.mode,
+                in_flight,
+                frame_age.as_secs_f32() * 1000.0,
+                self.metrics.frames_over_budget
+            );
+        }
+        false
+    }
+
+    /// Unacknowledged frames allowed at the client
+    pub fn max_frames_in_flight(&self) -> u32 {
+        self.settings.max_frames_in_flight
+    }
+
+    /// Limit an encoder bitrate to the mode's cap
+    pub fn cap_bitrate(&self, bitrate_kbps: u32) -> u32 {
+        self.settings
+            .max_bitrate_kbps
+            .map_or(bitrate_kbps, |cap| bitrate_kbps.min(cap))
+    }
+
+    /// Limit a periodic keyframe interval to the mode's cap
+    ///
+    /// A disabled interval (0) stays disabled.
+    pub fn cap_idr_interval(&self, interval_secs: u32) -> u32 {
+        match self.settings.max_idr_interval_secs {
+            Some(cap) if interval_secs > 0 => interval_secs.min(cap),
+            _ => interval_secs,
+        }
+    }
+
     /// Determine if we should encode this frame
     ///
     /// Call this after damage detection to get an encoding decision.
@@ -441,4 +539,32 @@ mod tests {
         assert!(!gov_interactive.should_use_adaptive_fps());
         assert!(gov_balanced.should_use_adaptive_fps());
     }
+
+    #[test]
+    fn test_budget_enforcement() {
+        let mut gov = LatencyGovernor::new(LatencyMode::Interactive);
+        assert!(gov.within_budget(0, Duration::from_millis(10)));
+        // One unacknowledged frame fills the interactive queue
+        assert!(!gov.within_budget(1, Duration::from_millis(10)));
+        // Older than the 50ms target
+        assert!(!gov.within_budget(0, Duration::from_millis(80)));
+        assert_eq!(gov.metrics().frames_over_budget, 2);
+
+        assert_eq!(gov.cap_bitrate(20_000), 8000);
+        assert_eq!(gov.cap_idr_interval(5), 2);
+        assert_eq!(gov.cap_idr_interval(0), 0);
+
+        let mut gov = LatencyGovernor::new(LatencyMode::Quality);
+        assert!(gov.within_budget(2, Duration::from_millis(80)));
+        assert_eq!(gov.cap_bitrate(20_000), 20_000);
+        assert_eq!(gov.cap_idr_interval(5), 5);
+        assert_eq!(LatencyMode::Quality.hardware_preset(), "quality");
+    }
+
+    #[test]
+    fn test_configured_frame_delay() {
+        let mut gov = LatencyGovernor::new(LatencyMode::Balanced).with_frame_delay(33, 0.10);
+        assert_eq!(gov.should_encode_frame(0.05), EncodingDecision::Skip);
+        assert_eq!(gov.should_encode_frame(0.06), EncodingDecision::EncodeNow);
+    }
 }
@@ -2,7 +2,15 @@
 //!
 //! This module contains performance-related features:
 //! - **Adaptive FPS**: Dynamically adjusts frame rate based on screen activity
+//!   and the client's frame acknowledgement latency
 //! - **Latency Governor**: Configurable latency vs quality tradeoffs
+//! - **Thread Scheduling**: CPU pinning and realtime priority of the capture
+//!   and encode threads
+//! - **Buffer Pool**: Reuses frame-sized buffers across frames
+//! - **Hardware Profile**: Startup benchmark that fits default settings to
+//!   the machine
+//! - **Benchmark**: Latency and throughput of each pipeline stage and
+//!   encoder backend (`lamco-rdp-server bench`)
 //!
 //! # Architecture
 //!
@@ -33,7 +41,17 @@
 //! ```
 
 mod adaptive_fps;
+pub mod bench;
+mod buffer_pool;
+mod hardware_profile;
 mod latency_governor;
+mod scheduling;
 
-pub use adaptive_fps::{AdaptiveFpsConfig, AdaptiveFpsController, DamageRatio};
+pub use adaptive_fps::{ActivityLevel, AdaptiveFpsConfig, AdaptiveFpsController, DamageRatio};
+pub use bench::{BenchOptions, BenchReport};
+pub use buffer_pool::{BufferPool, BufferPoolStats};
+pub use hardware_profile::{hardware_encoder_available, HardwareProbe, PerformanceProfile};
 pub use latency_governor::{EncodingDecision, LatencyGovernor, LatencyMode};
+pub use scheduling::{
+    current_thread_id, process_thread_ids, threads_started_since, RealtimePolicy, ThreadScheduling,
+};
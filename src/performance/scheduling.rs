@@ -0,0 +1,317 @@
+//! Thread Scheduling
+//!
+//! Applies `[performance.scheduling]` to the threads on the frame path: the
+//! PipeWire capture thread and the encode thread. Threads can be pinned to a
+//! set of CPUs and given a realtime policy (SCHED_RR or SCHED_FIFO), which
+//! keeps frame pacing steady when other processes compete for the CPU.
+//!
+//! Realtime policies need CAP_SYS_NICE. Without it, the thread is handed to
+//! rtkit (`org.freedesktop.RealtimeKit1` on the system bus), which grants
+//! SCHED_RR up to its configured maximum priority, provided the process has
+//! an `RLIMIT_RTTIME` within rtkit's limit. Failures are logged and the
+//! thread keeps its normal scheduling.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::io;
+use tracing::{info, warn};
+
+use crate::config::types::SchedulingConfig;
+
+/// Realtime scheduling policy of a thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RealtimePolicy {
+    /// Normal (SCHED_OTHER) scheduling
+    #[default]
+    None,
+    /// SCHED_RR
+    RoundRobin,
+    /// SCHED_FIFO
+    Fifo,
+}
+
+impl RealtimePolicy {
+    /// Parse a `realtime` config value
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "rr" => Ok(Self::RoundRobin),
+            "fifo" => Ok(Self::Fifo),
+            other => bail!(
+                "Unknown realtime policy '{}' (expected none, rr or fifo)",
+                other
+            ),
+        }
+    }
+
+    fn libc_policy(self) -> Option<libc::c_int> {
+        match self {
+            Self::None => None,
+            Self::RoundRobin => Some(libc::SCHED_RR),
+            Self::Fifo => Some(libc::SCHED_FIFO),
+        }
+    }
+}
+
+impl std::fmt::Display for RealtimePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::RoundRobin => write!(f, "SCHED_RR"),
+            Self::Fifo => write!(f, "SCHED_FIFO"),
+        }
+    }
+}
+
+/// CPU placement and policy for one kind of thread
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ThreadScheduling {
+    cpus: Vec<usize>,
+    policy: RealtimePolicy,
+    priority: u32,
+}
+
+impl ThreadScheduling {
+    /// Scheduling of the PipeWire capture thread
+    pub fn pipewire(config: &SchedulingConfig) -> Self {
+        Self::from_config(&config.pipewire_cpus, config)
+    }
+
+    /// Scheduling of the encode thread
+    pub fn encoder(config: &SchedulingConfig) -> Self {
+        Self::from_config(&config.encoder_cpus, config)
+    }
+
+    fn from_config(cpus: &[usize], config: &SchedulingConfig) -> Self {
+        let policy = RealtimePolicy::parse(&config.realtime).unwrap_or_else(|e| {
+            warn!("⚠️  {:#}, keeping normal scheduling", e);
+            RealtimePolicy::None
+        });
+        Self {
+            cpus: cpus.to_vec(),
+            policy,
+            priority: config.realtime_priority.clamp(1, 99),
+        }
+    }
+
+    /// Nothing to change: no pinning and no realtime policy
+    pub fn is_default(&self) -> bool {
+        self.cpus.is_empty() && self.policy == RealtimePolicy::None
+    }
+
+    /// Apply to the thread `tid` of this process
+    ///
+    /// Never fails: a setting the system refuses is logged and skipped.
+    pub async fn apply(&self, tid: i32, name: &str) {
+        if !self.cpus.is_empty() {
+            match set_affinity(tid, &self.cpus) {
+                Ok(()) => info!("📋 {} thread {} pinned to CPUs {:?}", name, tid, self.cpus),
+                Err(e) => warn!(
+                    "⚠️  Failed to pin {} thread {} to CPUs {:?}: {}",
+                    name, tid, self.cpus, e
+                ),
+            }
+        }
+
+        let Some(policy) = self.policy.libc_policy() else {
+            return;
+        };
+        match set_scheduler(tid, policy, self.priority) {
+            Ok(()) => info!(
+                "✅ {} thread {} runs {} at priority {}",
+                name, tid, self.policy, self.priority
+            ),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                match rtkit_make_realtime(tid, self.priority).await {
+                    Ok(priority) => {
+                        if self.policy == RealtimePolicy::Fifo {
+                            info!("📋 rtkit grants SCHED_RR only, not SCHED_FIFO");
+                        }
+                        info!(
+                            "✅ {} thread {} runs SCHED_RR at priority {} (rtkit)",
+                            name, tid, priority
+                        );
+                    }
+                    Err(e) => warn!(
+                        "⚠️  No realtime scheduling for {} thread {}: not permitted, and rtkit refused: {:#}",
+                        name, tid, e
+                    ),
+                }
+            }
+            Err(e) => warn!(
+                "⚠️  Failed to set {} for {} thread {}: {}",
+                self.policy, name, tid, e
+            ),
+        }
+    }
+}
+
+/// Kernel id of the calling thread
+pub fn current_thread_id() -> i32 {
+    // SAFETY: gettid has no preconditions
+    unsafe { libc::gettid() }
+}
+
+/// Kernel ids of the threads of this process
+pub fn process_thread_ids() -> HashSet<i32> {
+    std::fs::read_dir("/proc/self/task")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Threads started after `before` was taken, other than tokio's workers
+pub fn threads_started_since(before: &HashSet<i32>) -> Vec<i32> {
+    let mut started: Vec<i32> = process_thread_ids()
+        .difference(before)
+        .copied()
+        .filter(|tid| {
+            let comm = std::fs::read_to_string(format!("/proc/self/task/{}/comm", tid));
+            !comm.is_ok_and(|name| name.starts_with("tokio-"))
+        })
+        .collect();
+    started.sort_unstable();
+    started
+}
+
+fn set_affinity(tid: i32, cpus: &[usize]) -> io::Result<()> {
+    let max_cpu = libc::CPU_SETSIZE as usize;
+    if let Some(cpu) = cpus.iter().find(|&&cpu| cpu >= max_cpu) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU {} out of range", cpu),
+        ));
+    }
+
+    // SAFETY: an all-zero cpu_set_t is the empty set, every CPU index is
+    // below CPU_SETSIZE, and the set outlives the call
+    let ret = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn set_scheduler(tid: i32, policy: libc::c_int, priority: u32) -> io::Result<()> {
+    let param = libc::sched_param {
+        sched_priority: priority as libc::c_int,
+    };
+    // SAFETY: `param` is a valid sched_param for the duration of the call
+    let ret = unsafe { libc::sched_setscheduler(tid, policy, &param) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Ask rtkit for SCHED_RR, returning the priority granted
+async fn rtkit_make_realtime(tid: i32, priority: u32) -> Result<u32> {
+    let connection = zbus::Connection::system().await.context("No system bus")?;
+    let proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new(&connection)
+        .destination("org.freedesktop.RealtimeKit1")?
+        .path("/org/freedesktop/RealtimeKit1")?
+        .interface("org.freedesktop.RealtimeKit1")?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    let max_priority = proxy
+        .get_property::<i32>("MaxRealtimePriority")
+        .await
+        .context("rtkit unavailable")?;
+    let rttime_max = proxy.get_property::<i64>("RTTimeUSecMax").await?;
+    limit_rttime(rttime_max.max(0) as u64)?;
+
+    let priority = priority.min(max_priority.max(1) as u32);
+    proxy
+        .call_method("MakeThreadRealtime", &(tid as u64, priority))
+        .await?;
+    Ok(priority)
+}
+
+/// Lower `RLIMIT_RTTIME` to `max_us`, as rtkit requires of its clients
+fn limit_rttime(max_us: u64) -> Result<()> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid rlimit to write to
+    if unsafe { libc::getrlimit(libc::RLIMIT_RTTIME, &mut limit) } != 0 {
+        return Err(io::Error::last_os_error()).context("Failed to read RLIMIT_RTTIME");
+    }
+    if limit.rlim_max <= max_us as libc::rlim_t {
+        return Ok(());
+    }
+
+    let limit = libc::rlimit {
+        rlim_cur: max_us as libc::rlim_t,
+        rlim_max: max_us as libc::rlim_t,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of the call
+    if unsafe { libc::setrlimit(libc::RLIMIT_RTTIME, &limit) } != 0 {
+        return Err(io::Error::last_os_error()).context("Failed to set RLIMIT_RTTIME");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!(RealtimePolicy::parse("none").unwrap(), RealtimePolicy::None);
+        assert_eq!(
+            RealtimePolicy::parse("RR").unwrap(),
+            RealtimePolicy::RoundRobin
+        );
+        assert_eq!(RealtimePolicy::parse("fifo").unwrap(), RealtimePolicy::Fifo);
+        assert!(RealtimePolicy::parse("deadline").is_err());
+    }
+
+    #[test]
+    fn test_scheduling_from_config() {
+        let config = SchedulingConfig::default();
+        assert!(ThreadScheduling::pipewire(&config).is_default());
+        assert!(ThreadScheduling::encoder(&config).is_default());
+
+        let config = SchedulingConfig {
+            pipewire_cpus: vec![2],
+            encoder_cpus: Vec::new(),
+            realtime: "bogus".to_string(),
+            realtime_priority: 200,
+        };
+        let pipewire = ThreadScheduling::pipewire(&config);
+        assert!(!pipewire.is_default());
+        assert_eq!(pipewire.policy, RealtimePolicy::None);
+        assert_eq!(pipewire.priority, 99);
+        assert!(ThreadScheduling::encoder(&config).is_default());
+    }
+
+    #[test]
+    fn test_new_threads_are_found() {
+        let before = process_thread_ids();
+        assert!(before.contains(&current_thread_id()));
+
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            started_tx.send(current_thread_id()).unwrap();
+            let _ = done_rx.recv();
+        });
+        let tid = started_rx.recv().unwrap();
+
+        assert!(threads_started_since(&before).contains(&tid));
+        drop(done_tx);
+        thread.join().unwrap();
+    }
+}
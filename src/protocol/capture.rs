@@ -0,0 +1,645 @@
+//! PDU Capture
+//!
+//! Records the PDUs of each channel as they reach the server's handlers, so
+//! a client-specific bug can be reproduced offline with
+//! [`replay`](super::replay). TLS is terminated inside IronRDP, so the
+//! capture point is the boundary between IronRDP and our handlers: what is
+//! recorded is the plaintext each handler was given, re-encoded as the PDU
+//! it came from.
+//!
+//! # File Format
+//!
+//! Loosely modelled on pcap-ng: a file header followed by one block per PDU,
+//! all integers little-endian.
+//!
+//! ```text
+//! header  magic "LRDPCAP\n" (8) | version u16 | reserved u16
+//! block   length u32 | channel u8 | direction u8 | reserved u16 |
+//!         timestamp µs since capture start u64 | payload (length bytes)
+//! ```
+//!
+//! # Channels
+//!
+//! | Channel | Payload |
+//! |---------|---------|
+//! | `cliprdr` | CLIPRDR PDUs (MS-RDPECLIP) the clipboard backend received |
+//! | `input` | Keyboard and mouse events in a compact encoding, see [`InputRecord`] |
+//! | `egfx` | Capabilities the client advertised (client → server) and the set negotiated (server → client) |
+//!
+//! Captures contain everything typed and copied during the session. Treat
+//! them like the session itself: the file is created readable by its owner
+//! only, and a symlink at the capture path is refused rather than followed.
+
+use std::fs::{File, OpenOptions, Permissions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    ClipboardFormat, ClipboardGeneralCapabilityFlags, ClipboardPdu, FileContentsRequest,
+    FileContentsResponse, FormatDataRequest, FormatDataResponse, FormatList, LockDataId,
+};
+use ironrdp_core::{AsAny, Encode};
+use ironrdp_pdu::input::fast_path::SynchronizeFlags;
+use ironrdp_server::{KeyboardEvent, MouseEvent};
+use tracing::{info, warn};
+
+/// First bytes of a capture file
+pub const MAGIC: &[u8; 8] = b"LRDPCAP\n";
+
+/// Format version written by this build
+pub const VERSION: u16 = 1;
+
+/// Bytes of a block before its payload
+const BLOCK_HEADER_LEN: usize = 16;
+
+/// Largest payload accepted when reading, to reject corrupt lengths
+const MAX_PAYLOAD: usize = 64 * 1024 * 1024;
+
+/// Channel a PDU belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Clipboard (MS-RDPECLIP)
+    Cliprdr,
+    /// Keyboard and mouse
+    Input,
+    /// Graphics pipeline (MS-RDPEGFX)
+    Egfx,
+}
+
+impl Channel {
+    fn to_u8(self) -> u8 {
+        match self {
+            Channel::Cliprdr => 1,
+            Channel::Input => 2,
+            Channel::Egfx => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Channel::Cliprdr),
+            2 => Some(Channel::Input),
+            3 => Some(Channel::Egfx),
+            _ => None,
+        }
+    }
+
+    /// Name shown in replay listings
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Cliprdr => "cliprdr",
+            Channel::Input => "input",
+            Channel::Egfx => "egfx",
+        }
+    }
+}
+
+/// Which way a PDU travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the client
+    ClientToServer,
+    /// Sent by the server
+    ServerToClient,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::ClientToServer => write!(f, "client → server"),
+            Direction::ServerToClient => write!(f, "server → client"),
+        }
+    }
+}
+
+/// One captured PDU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// Time since the capture started
+    pub timestamp: Duration,
+    /// Channel the PDU belongs to
+    pub channel: Channel,
+    /// Which way it travelled
+    pub direction: Direction,
+    /// The PDU, encoded as described in the module documentation
+    pub payload: Vec<u8>,
+}
+
+/// Capture file being written, shared by the handlers of all connections
+#[derive(Clone)]
+pub struct PduCapture {
+    writer: Arc<Mutex<BufWriter<File>>>,
+    start: Instant,
+    /// Set after a write error, so a full disk is reported once
+    failed: Arc<AtomicBool>,
+}
+
+impl PduCapture {
+    /// Start a capture at `path`, replacing an existing file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)
+            .with_context(|| format!("Failed to create capture file {}", path.display()))?;
+        // The mode only applies to new files; an older capture may be readable
+        file.set_permissions(Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict capture file {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&0u16.to_le_bytes())?;
+        writer.flush()?;
+        info!("📼 Capturing channel PDUs to {}", path.display());
+        Ok(Self {
+            writer: Arc::new(Mutex::new(writer)),
+            start: Instant::now(),
+            failed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Append a PDU
+    pub fn record(&self, channel: Channel, direction: Direction, payload: &[u8]) {
+        if self.failed.load(Ordering::Relaxed) {
+            return;
+        }
+        let timestamp = self.start.elapsed().as_micros() as u64;
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let result = write_block(&mut *writer, timestamp, channel, direction, payload)
+            // Flushed per block so a crash leaves a readable capture
+            .and_then(|()| writer.flush());
+        if let Err(e) = result {
+            warn!("⚠️  PDU capture stopped: {}", e);
+            self.failed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Append an encodable PDU
+    pub fn record_pdu(&self, channel: Channel, direction: Direction, pdu: &dyn Encode) {
+        match ironrdp_core::encode_vec(pdu) {
+            Ok(payload) => self.record(channel, direction, &payload),
+            Err(e) => warn!("⚠️  Could not capture {} PDU: {}", pdu.name(), e),
+        }
+    }
+
+    /// Append a keyboard event of the input channel
+    pub fn record_keyboard(&self, event: &KeyboardEvent) {
+        self.record(
+            Channel::Input,
+            Direction::ClientToServer,
+            &encode_keyboard(event),
+        );
+    }
+
+    /// Append a mouse event of the input channel
+    pub fn record_mouse(&self, event: &MouseEvent) {
+        self.record(
+            Channel::Input,
+            Direction::ClientToServer,
+            &encode_mouse(event),
+        );
+    }
+}
+
+impl std::fmt::Debug for PduCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PduCapture").finish_non_exhaustive()
+    }
+}
+
+fn write_block(
+    writer: &mut impl Write,
+    timestamp: u64,
+    channel: Channel,
+    direction: Direction,
+    payload: &[u8],
+) -> std::io::Result<()> {
+    let mut header = [0u8; BLOCK_HEADER_LEN];
+    header[0..4].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+    header[4] = channel.to_u8();
+    header[5] = match direction {
+        Direction::ClientToServer => 0,
+        Direction::ServerToClient => 1,
+    };
+    header[8..16].copy_from_slice(&timestamp.to_le_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(payload)
+}
+
+/// Reads the records of a capture file
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    /// Open the capture at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open capture file {}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reader over a capture, checking its header
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 12];
+        reader
+            .read_exact(&mut header)
+            .context("Not a capture file (too short)")?;
+        if &header[..8] != MAGIC {
+            bail!("Not a capture file (bad magic)");
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != VERSION {
+            bail!("Unsupported capture version {}", version);
+        }
+        Ok(Self { reader })
+    }
+
+    /// Next record, None at the end of the file
+    pub fn next_record(&mut self) -> Result<Option<CaptureRecord>> {
+        let mut header = [0u8; BLOCK_HEADER_LEN];
+        match self.reader.read_exact(&mut header[..1]) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        self.reader
+            .read_exact(&mut header[1..])
+            .context("Truncated block header")?;
+
+        let length = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        if length > MAX_PAYLOAD {
+            bail!("Block of {} bytes is too large", length);
+        }
+        let channel = Channel::from_u8(header[4])
+            .with_context(|| format!("Unknown channel {}", header[4]))?;
+        let direction = match header[5] {
+            0 => Direction::ClientToServer,
+            1 => Direction::ServerToClient,
+            other => bail!("Unknown direction {}", other),
+        };
+        let timestamp = u64::from_le_bytes(header[8..16].try_into().unwrap());
+
+        let mut payload = vec![0u8; length];
+        self.reader
+            .read_exact(&mut payload)
+            .context("Truncated block payload")?;
+        Ok(Some(CaptureRecord {
+            timestamp: Duration::from_micros(timestamp),
+            channel,
+            direction,
+            payload,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Payload of the input channel
+///
+/// A kind byte followed by the event's fields, little-endian:
+///
+/// | Kind | Event | Fields |
+/// |------|-------|--------|
+/// | 0x01, 0x02 | Key pressed, released | scancode u8, extended u8 |
+/// | 0x03, 0x04 | Unicode pressed, released | code unit u16 |
+/// | 0x05 | Synchronize | lock flags u8 |
+/// | 0x10 | Move | x u16, y u16 |
+/// | 0x11 | Relative move | dx i32, dy i32 |
+/// | 0x12 | Vertical scroll | value i16 |
+/// | 0x13 | Scroll | x i32, y i32 |
+/// | 0x20-0x29 | Left, right, middle, button 4, button 5 pressed/released | |
+#[derive(Debug)]
+pub enum InputRecord {
+    /// Keyboard event
+    Keyboard(KeyboardEvent),
+    /// Mouse event
+    Mouse(MouseEvent),
+}
+
+impl InputRecord {
+    /// Encode for the input channel
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            InputRecord::Keyboard(event) => encode_keyboard(event),
+            InputRecord::Mouse(event) => encode_mouse(event),
+        }
+    }
+
+    /// Decode an input channel payload
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let Some((&kind, fields)) = payload.split_first() else {
+            bail!("Empty input record");
+        };
+        let field = |range: std::ops::Range<usize>| {
+            fields
+                .get(range)
+                .with_context(|| format!("Input record 0x{:02X} is too short", kind))
+        };
+        let u16_at = |at: usize| -> Result<u16> {
+            Ok(u16::from_le_bytes(field(at..at + 2)?.try_into().unwrap()))
+        };
+        let i32_at = |at: usize| -> Result<i32> {
+            Ok(i32::from_le_bytes(field(at..at + 4)?.try_into().unwrap()))
+        };
+
+        let record = match kind {
+            0x01 | 0x02 => {
+                let key = field(0..2)?;
+                let (code, extended) = (key[0], key[1] != 0);
+                InputRecord::Keyboard(if kind == 0x01 {
+                    KeyboardEvent::Pressed { code, extended }
+                } else {
+                    KeyboardEvent::Released { code, extended }
+                })
+            }
+            0x03 => InputRecord::Keyboard(KeyboardEvent::UnicodePressed(u16_at(0)?)),
+            0x04 => InputRecord::Keyboard(KeyboardEvent::UnicodeReleased(u16_at(0)?)),
+            0x05 => InputRecord::Keyboard(KeyboardEvent::Synchronize(
+                SynchronizeFlags::from_bits_truncate(field(0..1)?[0]),
+            )),
+            0x10 => InputRecord::Mouse(MouseEvent::Move {
+                x: u16_at(0)?,
+                y: u16_at(2)?,
+            }),
+            0x11 => InputRecord::Mouse(MouseEvent::RelMove {
+                x: i32_at(0)?,
+                y: i32_at(4)?,
+            }),
+            0x12 => InputRecord::Mouse(MouseEvent::VerticalScroll {
+                value: u16_at(0)? as i16,
+            }),
+            0x13 => InputRecord::Mouse(MouseEvent::Scroll {
+                x: i32_at(0)?,
+                y: i32_at(4)?,
+            }),
+            0x20 => InputRecord::Mouse(MouseEvent::LeftPressed),
+            0x21 => InputRecord::Mouse(MouseEvent::LeftReleased),
+            0x22 => InputRecord::Mouse(MouseEvent::RightPressed),
+            0x23 => InputRecord::Mouse(MouseEvent::RightReleased),
+            0x24 => InputRecord::Mouse(MouseEvent::MiddlePressed),
+            0x25 => InputRecord::Mouse(MouseEvent::MiddleReleased),
+            0x26 => InputRecord::Mouse(MouseEvent::Button4Pressed),
+            0x27 => InputRecord::Mouse(MouseEvent::Button4Released),
+            0x28 => InputRecord::Mouse(MouseEvent::Button5Pressed),
+            0x29 => InputRecord::Mouse(MouseEvent::Button5Released),
+            other => bail!("Unknown input record kind 0x{:02X}", other),
+        };
+        Ok(record)
+    }
+}
+
+/// Input channel payload of a keyboard event
+fn encode_keyboard(event: &KeyboardEvent) -> Vec<u8> {
+    let mut out = Vec::with_capacity(3);
+    match event {
+        KeyboardEvent::Pressed { code, extended } => out.extend([0x01, *code, u8::from(*extended)]),
+        KeyboardEvent::Released { code, extended } => {
+            out.extend([0x02, *code, u8::from(*extended)])
+        }
+        KeyboardEvent::UnicodePressed(unit) => {
+            out.push(0x03);
+            out.extend(unit.to_le_bytes());
+        }
+        KeyboardEvent::UnicodeReleased(unit) => {
+            out.push(0x04);
+            out.extend(unit.to_le_bytes());
+        }
+        KeyboardEvent::Synchronize(flags) => out.extend([0x05, flags.bits()]),
+    }
+    out
+}
+
+/// Input channel payload of a mouse event
+fn encode_mouse(event: &MouseEvent) -> Vec<u8> {
+    let mut out = Vec::with_capacity(9);
+    match event {
+        MouseEvent::Move { x, y } => {
+            out.push(0x10);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        MouseEvent::RelMove { x, y } => {
+            out.push(0x11);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        MouseEvent::VerticalScroll { value } => {
+            out.push(0x12);
+            out.extend(value.to_le_bytes());
+        }
+        MouseEvent::Scroll { x, y } => {
+            out.push(0x13);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        MouseEvent::LeftPressed => out.push(0x20),
+        MouseEvent::LeftReleased => out.push(0x21),
+        MouseEvent::RightPressed => out.push(0x22),
+        MouseEvent::RightReleased => out.push(0x23),
+        MouseEvent::MiddlePressed => out.push(0x24),
+        MouseEvent::MiddleReleased => out.push(0x25),
+        MouseEvent::Button4Pressed => out.push(0x26),
+        MouseEvent::Button4Released => out.push(0x27),
+        MouseEvent::Button5Pressed => out.push(0x28),
+        MouseEvent::Button5Released => out.push(0x29),
+    }
+    out
+}
+
+/// Clipboard backend that captures what it is given before passing it on
+///
+/// Each callback is re-encoded as the CLIPRDR PDU that produced it.
+#[derive(Debug)]
+pub struct CapturingCliprdrBackend {
+    inner: Box<dyn CliprdrBackend>,
+    capture: PduCapture,
+}
+
+impl CapturingCliprdrBackend {
+    /// Capture the PDUs reaching `inner`
+    pub fn new(inner: Box<dyn CliprdrBackend>, capture: PduCapture) -> Self {
+        Self { inner, capture }
+    }
+
+    fn record(&self, pdu: &ClipboardPdu<'_>) {
+        self.capture
+            .record_pdu(Channel::Cliprdr, Direction::ClientToServer, pdu);
+    }
+}
+
+impl AsAny for CapturingCliprdrBackend {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl CliprdrBackend for CapturingCliprdrBackend {
+    fn temporary_directory(&self) -> &str {
+        self.inner.temporary_directory()
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        self.inner.client_capabilities()
+    }
+
+    fn on_ready(&mut self) {
+        self.inner.on_ready();
+    }
+
+    fn on_request_format_list(&mut self) {
+        self.inner.on_request_format_list();
+    }
+
+    fn on_process_negotiated_capabilities(
+        &mut self,
+        capabilities: ClipboardGeneralCapabilityFlags,
+    ) {
+        self.inner.on_process_negotiated_capabilities(capabilities);
+    }
+
+    fn on_remote_copy(&mut self, available_formats: &[ClipboardFormat]) {
+        match FormatList::new_unicode(available_formats, true) {
+            Ok(list) => self.record(&ClipboardPdu::FormatList(list)),
+            Err(e) => warn!("⚠️  Could not capture format list: {}", e),
+        }
+        self.inner.on_remote_copy(available_formats);
+    }
+
+    fn on_format_data_request(&mut self, request: FormatDataRequest) {
+        self.record(&ClipboardPdu::FormatDataRequest(request.clone()));
+        self.inner.on_format_data_request(request);
+    }
+
+    fn on_format_data_response(&mut self, response: FormatDataResponse<'_>) {
+        self.record(&ClipboardPdu::FormatDataResponse(response.clone()));
+        self.inner.on_format_data_response(response);
+    }
+
+    fn on_file_contents_request(&mut self, request: FileContentsRequest) {
+        self.record(&ClipboardPdu::FileContentsRequest(request.clone()));
+        self.inner.on_file_contents_request(request);
+    }
+
+    fn on_file_contents_response(&mut self, response: FileContentsResponse<'_>) {
+        self.record(&ClipboardPdu::FileContentsResponse(response.clone()));
+        self.inner.on_file_contents_response(response);
+    }
+
+    fn on_lock(&mut self, data_id: LockDataId) {
+        self.record(&ClipboardPdu::LockData(data_id.clone()));
+        self.inner.on_lock(data_id);
+    }
+
+    fn on_unlock(&mut self, data_id: LockDataId) {
+        self.record(&ClipboardPdu::UnlockData(data_id.clone()));
+        self.inner.on_unlock(data_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lrdpcap");
+        let capture = PduCapture::create(&path).unwrap();
+        capture.record(Channel::Egfx, Direction::ServerToClient, b"caps");
+        capture.record_mouse(&MouseEvent::Move { x: 640, y: 360 });
+
+        let records: Vec<CaptureRecord> = CaptureReader::open(&path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].channel, Channel::Egfx);
+        assert_eq!(records[0].direction, Direction::ServerToClient);
+        assert_eq!(records[0].payload, b"caps");
+        assert!(matches!(
+            InputRecord::decode(&records[1].payload).unwrap(),
+            InputRecord::Mouse(MouseEvent::Move { x: 640, y: 360 })
+        ));
+    }
+
+    #[test]
+    fn test_capture_file_is_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.lrdpcap");
+        std::fs::write(&path, b"old").unwrap();
+        std::fs::set_permissions(&path, Permissions::from_mode(0o644)).unwrap();
+        PduCapture::create(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // A planted symlink is not followed
+        let target = dir.path().join("target");
+        std::fs::write(&target, b"keep").unwrap();
+        let link = dir.path().join("link.lrdpcap");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        assert!(PduCapture::create(&link).is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"keep");
+    }
+
+    #[test]
+    fn test_truncated_capture_is_an_error() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        write_block(
+            &mut bytes,
+            0,
+            Channel::Input,
+            Direction::ClientToServer,
+            &[0x20],
+        )
+        .unwrap();
+        bytes.pop();
+
+        let mut reader = CaptureReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.next_record().is_err());
+        assert!(CaptureReader::new(&b"PCAPNG\0\0\x01\0\0\0"[..]).is_err());
+    }
+
+    #[test]
+    fn test_input_record_encoding() {
+        let events = [
+            InputRecord::Keyboard(KeyboardEvent::Pressed {
+                code: 0x1E,
+                extended: true,
+            }),
+            InputRecord::Keyboard(KeyboardEvent::UnicodeReleased(0x00E9)),
+            InputRecord::Mouse(MouseEvent::RelMove { x: -5, y: 12 }),
+            InputRecord::Mouse(MouseEvent::VerticalScroll { value: -120 }),
+            InputRecord::Mouse(MouseEvent::Button5Released),
+        ];
+        for event in &events {
+            let decoded = InputRecord::decode(&event.encode()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", event));
+        }
+        assert!(InputRecord::decode(&[0x10, 0x01]).is_err());
+        assert!(InputRecord::decode(&[0x7F]).is_err());
+    }
+}
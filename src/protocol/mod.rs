@@ -12,6 +12,8 @@
 //! - Protocol constant definitions
 //! - Message validation
 //! - Protocol version negotiation utilities
+//! - Capturing the PDUs of each channel ([`capture`]) and replaying them
+//!   through channel handlers ([`replay`]) to reproduce client bugs offline
 //!
 //! ## Protocol Support
 //!
@@ -48,3 +50,9 @@
 //! - Clipboard file transfer methods (PRs #1063-1066 merged upstream)
 //!
 //! See `Cargo.toml` [patch.crates-io] section for fork details.
+
+pub mod capture;
+pub mod replay;
+
+pub use capture::{CaptureReader, PduCapture};
+pub use replay::{replay, Pace, ReplayTargets};
@@ -0,0 +1,318 @@
+//! PDU Replay
+//!
+//! Feeds a [capture](super::capture) back through channel handlers, in the
+//! order and (optionally) at the pace it was recorded. Pointing the records
+//! of a misbehaving client at the server's own handlers reproduces its bug
+//! without the client:
+//!
+//! ```ignore
+//! let mut graphics = LamcoGraphicsHandler::with_quirks(1920, 1080, false);
+//! let mut targets = ReplayTargets {
+//!     egfx: Some(&mut graphics),
+//!     ..Default::default()
+//! };
+//! replay(CaptureReader::open(path)?, &mut targets, Pace::Immediate)?;
+//! assert!(graphics.is_avc420_enabled());
+//! ```
+//!
+//! Records of channels without a target are skipped.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::ClipboardPdu;
+use ironrdp_egfx::pdu::{CapabilitiesAdvertisePdu, CapabilitySet};
+use ironrdp_egfx::server::GraphicsPipelineHandler;
+use ironrdp_server::RdpServerInputHandler;
+
+use super::capture::{CaptureRecord, Channel, Direction, InputRecord};
+
+/// Handlers the records are delivered to
+#[derive(Default)]
+pub struct ReplayTargets<'a> {
+    /// Receives the clipboard PDUs
+    pub cliprdr: Option<&'a mut dyn CliprdrBackend>,
+    /// Receives the keyboard and mouse events
+    pub input: Option<&'a mut dyn RdpServerInputHandler>,
+    /// Receives the graphics pipeline capabilities
+    pub egfx: Option<&'a mut dyn GraphicsPipelineHandler>,
+}
+
+/// How fast records are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// One after the other
+    Immediate,
+    /// With the gaps they were recorded with
+    Recorded,
+}
+
+/// Counts of a replay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Records given to a handler
+    pub delivered: usize,
+    /// Records without a target, or of a PDU no handler method takes
+    pub skipped: usize,
+}
+
+/// Deliver `records` to `targets`
+///
+/// Stops at the first record that cannot be read or decoded.
+pub fn replay(
+    records: impl IntoIterator<Item = Result<CaptureRecord>>,
+    targets: &mut ReplayTargets<'_>,
+    pace: Pace,
+) -> Result<ReplayStats> {
+    let mut stats = ReplayStats::default();
+    let mut last = Duration::ZERO;
+
+    if let Some(cliprdr) = targets.cliprdr.as_deref_mut() {
+        // The capture starts after the channel came up
+        cliprdr.on_ready();
+    }
+
+    for (index, record) in records.into_iter().enumerate() {
+        let record = record.with_context(|| format!("Failed to read record {}", index))?;
+        if pace == Pace::Recorded {
+            std::thread::sleep(record.timestamp.saturating_sub(last));
+        }
+        last = record.timestamp;
+
+        let delivered = deliver(&record, targets).with_context(|| {
+            format!("Failed to replay record {} ({})", index, describe(&record))
+        })?;
+        if delivered {
+            stats.delivered += 1;
+        } else {
+            stats.skipped += 1;
+        }
+    }
+    Ok(stats)
+}
+
+fn deliver(record: &CaptureRecord, targets: &mut ReplayTargets<'_>) -> Result<bool> {
+    match record.channel {
+        Channel::Cliprdr => {
+            let Some(backend) = targets.cliprdr.as_deref_mut() else {
+                return Ok(false);
+            };
+            let pdu = decode_cliprdr(&record.payload)?;
+            deliver_cliprdr(pdu, backend)
+        }
+        Channel::Input => {
+            let Some(handler) = targets.input.as_deref_mut() else {
+                return Ok(false);
+            };
+            match InputRecord::decode(&record.payload)? {
+                InputRecord::Keyboard(event) => handler.keyboard(event),
+                InputRecord::Mouse(event) => handler.mouse(event),
+            }
+            Ok(true)
+        }
+        Channel::Egfx => {
+            let Some(handler) = targets.egfx.as_deref_mut() else {
+                return Ok(false);
+            };
+            match record.direction {
+                Direction::ClientToServer => {
+                    let pdu: CapabilitiesAdvertisePdu = ironrdp_core::decode(&record.payload)
+                        .map_err(|e| anyhow::anyhow!("Invalid capabilities: {}", e))?;
+                    handler.capabilities_advertise(&pdu);
+                }
+                Direction::ServerToClient => {
+                    let negotiated: CapabilitySet = ironrdp_core::decode(&record.payload)
+                        .map_err(|e| anyhow::anyhow!("Invalid capability set: {}", e))?;
+                    handler.on_ready(&negotiated);
+                }
+            }
+            Ok(true)
+        }
+    }
+}
+
+fn decode_cliprdr(payload: &[u8]) -> Result<ClipboardPdu<'_>> {
+    ironrdp_core::decode(payload).map_err(|e| anyhow::anyhow!("Invalid CLIPRDR PDU: {}", e))
+}
+
+fn deliver_cliprdr(pdu: ClipboardPdu<'_>, backend: &mut dyn CliprdrBackend) -> Result<bool> {
+    match pdu {
+        ClipboardPdu::FormatList(list) => {
+            let formats = list
+                .get_formats(true)
+                .map_err(|e| anyhow::anyhow!("Invalid format list: {}", e))?;
+            backend.on_remote_copy(&formats);
+        }
+        ClipboardPdu::FormatDataRequest(request) => backend.on_format_data_request(request),
+        ClipboardPdu::FormatDataResponse(response) => backend.on_format_data_response(response),
+        ClipboardPdu::FileContentsRequest(request) => backend.on_file_contents_request(request),
+        ClipboardPdu::FileContentsResponse(response) => backend.on_file_contents_response(response),
+        ClipboardPdu::LockData(id) => backend.on_lock(id),
+        ClipboardPdu::UnlockData(id) => backend.on_unlock(id),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// One-line summary of a record, for listings
+pub fn describe(record: &CaptureRecord) -> String {
+    let pdu = match record.channel {
+        Channel::Cliprdr => match decode_cliprdr(&record.payload) {
+            Ok(pdu) => cliprdr_summary(&pdu),
+            Err(e) => format!("undecodable: {:#}", e),
+        },
+        Channel::Input => match InputRecord::decode(&record.payload) {
+            Ok(InputRecord::Keyboard(event)) => format!("{:?}", event),
+            Ok(InputRecord::Mouse(event)) => format!("{:?}", event),
+            Err(e) => format!("undecodable: {:#}", e),
+        },
+        Channel::Egfx => match record.direction {
+            Direction::ClientToServer => {
+                match ironrdp_core::decode::<CapabilitiesAdvertisePdu>(&record.payload) {
+                    Ok(pdu) => format!("CapabilitiesAdvertise {:?}", pdu.0),
+                    Err(e) => format!("undecodable: {}", e),
+                }
+            }
+            Direction::ServerToClient => {
+                match ironrdp_core::decode::<CapabilitySet>(&record.payload) {
+                    Ok(set) => format!("Negotiated {:?}", set),
+                    Err(e) => format!("undecodable: {}", e),
+                }
+            }
+        },
+    };
+    format!(
+        "{:>10.3}s {:<8} {} {} ({} bytes)",
+        record.timestamp.as_secs_f64(),
+        record.channel.name(),
+        record.direction,
+        pdu,
+        record.payload.len()
+    )
+}
+
+/// Clipboard PDUs summarized without their data, which may be large
+fn cliprdr_summary(pdu: &ClipboardPdu<'_>) -> String {
+    match pdu {
+        ClipboardPdu::FormatList(list) => match list.get_formats(true) {
+            Ok(formats) => format!(
+                "FormatList [{}]",
+                formats
+                    .iter()
+                    .map(|format| format.id().value().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Err(e) => format!("FormatList (invalid: {})", e),
+        },
+        ClipboardPdu::FormatDataRequest(request) => {
+            format!("FormatDataRequest format={}", request.format.value())
+        }
+        ClipboardPdu::FormatDataResponse(response) => format!(
+            "FormatDataResponse {} bytes{}",
+            response.data().len(),
+            if response.is_error() { " (error)" } else { "" }
+        ),
+        ClipboardPdu::FileContentsRequest(request) => format!(
+            "FileContentsRequest stream={} index={} pos={} size={}",
+            request.stream_id, request.index, request.position, request.requested_size
+        ),
+        ClipboardPdu::FileContentsResponse(response) => format!(
+            "FileContentsResponse stream={} {} bytes",
+            response.stream_id(),
+            response.data().len()
+        ),
+        ClipboardPdu::LockData(id) => format!("LockData {}", id.0),
+        ClipboardPdu::UnlockData(id) => format!("UnlockData {}", id.0),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironrdp_server::{KeyboardEvent, MouseEvent};
+
+    #[derive(Default)]
+    struct RecordingInput {
+        events: Vec<String>,
+    }
+
+    impl RdpServerInputHandler for RecordingInput {
+        fn keyboard(&mut self, event: KeyboardEvent) {
+            self.events.push(format!("{:?}", event));
+        }
+
+        fn mouse(&mut self, event: MouseEvent) {
+            self.events.push(format!("{:?}", event));
+        }
+    }
+
+    fn input_record(timestamp_ms: u64, event: InputRecord) -> Result<CaptureRecord> {
+        Ok(CaptureRecord {
+            timestamp: Duration::from_millis(timestamp_ms),
+            channel: Channel::Input,
+            direction: Direction::ClientToServer,
+            payload: event.encode(),
+        })
+    }
+
+    #[test]
+    fn test_replay_delivers_input_in_order() {
+        let records = vec![
+            input_record(0, InputRecord::Mouse(MouseEvent::Move { x: 10, y: 20 })),
+            input_record(
+                5,
+                InputRecord::Keyboard(KeyboardEvent::Pressed {
+                    code: 0x1E,
+                    extended: false,
+                }),
+            ),
+            Ok(CaptureRecord {
+                timestamp: Duration::from_millis(6),
+                channel: Channel::Egfx,
+                direction: Direction::ClientToServer,
+                payload: Vec::new(),
+            }),
+        ];
+
+        let mut input = RecordingInput::default();
+        let stats = replay(
+            records,
+            &mut ReplayTargets {
+                input: Some(&mut input),
+                ..Default::default()
+            },
+            Pace::Immediate,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stats,
+            ReplayStats {
+                delivered: 2,
+                skipped: 1
+            }
+        );
+        assert_eq!(input.events.len(), 2);
+        assert!(input.events[0].contains("Move"));
+        assert!(input.events[1].contains("Pressed"));
+    }
+
+    #[test]
+    fn test_replay_stops_at_bad_record() {
+        let records = vec![Ok(CaptureRecord {
+            timestamp: Duration::ZERO,
+            channel: Channel::Input,
+            direction: Direction::ClientToServer,
+            payload: vec![0x7F],
+        })];
+        let mut input = RecordingInput::default();
+        let mut targets = ReplayTargets {
+            input: Some(&mut input),
+            ..Default::default()
+        };
+        assert!(replay(records, &mut targets, Pace::Immediate).is_err());
+    }
+}
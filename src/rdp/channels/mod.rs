@@ -2,3 +2,7 @@
 //!
 //! Implementation of RDP virtual channels for clipboard, audio,
 //! and other auxiliary data streams.
+//!
+//! Audio output (RDPSND) needs a capture source first: the PipeWire thread
+//! manager of lamco-pipewire handles video streams only, so there is no
+//! audio channel yet.
@@ -21,7 +21,6 @@ pub struct TlsConfig {
     cert_chain: Vec<CertificateDer<'static>>,
 
     /// Private key (owned for lifetime management)
-    #[allow(dead_code)]
     private_key: PrivateKeyDer<'static>,
 
     /// rustls ServerConfig
@@ -113,6 +112,41 @@ impl TlsConfig {
         Arc::clone(&self.server_config)
     }
 
+    /// Build a ServerConfig that requires client certificates
+    ///
+    /// Clients must present a certificate signed by one of the CAs in
+    /// `client_ca_path` (PEM bundle). Used for mutual TLS on control
+    /// interfaces, not for RDP.
+    pub fn server_config_with_client_auth(
+        &self,
+        client_ca_path: &Path,
+    ) -> Result<Arc<ServerConfig>> {
+        let ca_file = File::open(client_ca_path).context("Failed to open client CA file")?;
+        let mut ca_reader = BufReader::new(ca_file);
+
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut ca_reader) {
+            roots
+                .add(cert.context("Failed to parse client CA certificate")?)
+                .context("Invalid client CA certificate")?;
+        }
+        if roots.is_empty() {
+            anyhow::bail!("No certificates found in client CA file");
+        }
+        debug!("Loaded {} client CA certificate(s)", roots.len());
+
+        let verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(roots))
+            .build()
+            .context("Failed to create client certificate verifier")?;
+
+        let server_config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(self.cert_chain.clone(), self.private_key.clone_key())
+            .context("Failed to configure certificate")?;
+
+        Ok(Arc::new(server_config))
+    }
+
     /// Verify TLS configuration is valid
     ///
     /// Performs basic validation checks on the configuration.
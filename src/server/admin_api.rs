@@ -0,0 +1,1006 @@
+//! HTTP Admin API
+//!
+//! Token-authenticated REST interface for operating a running server.
+//!
+//! # Endpoints
+//!
+//! ```text
+//! GET    /v1/sessions        connected clients and observers, with usage
+//! DELETE /v1/sessions/{id}   disconnect a client (202, or 404 if unknown)
+//! PUT    /v1/sessions/{id}/overlay
+//!                            show/hide the quality overlay ({"visible": bool};
+//!                            204, 404 if unknown, 409 if the overlay is off)
+//! PUT    /v1/sessions/{id}/sharing
+//!                            pause/resume video, input and clipboard
+//!                            ({"paused": bool}; 204, 404 if unknown, 409 if
+//!                            the client has no pipeline yet)
+//! PUT    /v1/sessions/{id}/message
+//!                            show a message on the client's screen
+//!                            ({"text": "...", "duration_secs"?: n}; 204,
+//!                            404 if unknown, 409 if the client has no
+//!                            pipeline yet, 422 if invalid)
+//! GET    /v1/sessions/{id}/stats
+//!                            totals and the latest per-second sample (404
+//!                            if unknown, 409 if the client has no pipeline)
+//! GET    /v1/sessions/{id}/stats/history[?since=<ms>]
+//!                            per-second samples of the last 5 minutes,
+//!                            those after `since` (Unix ms) if given
+//! GET    /v1/stats           uptime, connection counters and buffer pool usage
+//! GET    /v1/policy          runtime policies
+//! PATCH  /v1/policy          update runtime policies (partial JSON body)
+//! GET    /v1/settings        runtime-tunable settings by dotted key
+//! PUT    /v1/settings/{key}  change one ({"value": ..., "actor"?: "name"};
+//!                            404 if not tunable, 422 if invalid)
+//! GET    /v1/settings/audit  settings changes since the server started
+//! POST   /v1/debug/frame-dump
+//!                            write the next frames of every pipeline to
+//!                            disk (202 with the request, 409 if
+//!                            `[frame_dump]` is off, 429 if too soon)
+//! ```
+//!
+//! Every request needs `Authorization: Bearer <token>`. The API listens on
+//! loopback by default; other addresses require mutual TLS
+//! (`admin_api.client_ca_path`) using the server certificate from
+//! `[security]`.
+//!
+//! Policy changes are published the same way as a configuration hot-reload
+//! and last until the next reload or restart. Settings changes are
+//! published the same way, kept in the config file's drop-in and recorded
+//! in its audit trail (see [`crate::config::tunable`]). The actor is
+//! whatever name the client gives, as all clients share the token.
+//!
+//! The API is served over HTTP/JSON only; there is no gRPC transport.
+
+use anyhow::{bail, Context, Result};
+use axum::extract::{Path, RawQuery, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use ironrdp_server::tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use super::frame_dump::{DumpRequest, FrameDump};
+use super::resource_limits::{SessionMeter, SessionUsage};
+use super::session_manager::{ClientSessionInfo, SessionManager};
+use super::session_message::{DEFAULT_DURATION, MAX_DURATION, MAX_MESSAGE_CHARS};
+use super::sharing::SharingStatus;
+use super::stats_history::StatsSample;
+use crate::config::tunable::{self, AuditEntry};
+use crate::config::types::AdminApiConfig;
+use crate::config::{secrets, Config};
+use crate::performance::{BufferPool, BufferPoolStats};
+
+/// Runtime policies exposed by the admin API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RuntimePolicy {
+    /// Clipboard sync enabled
+    pub clipboard_enabled: bool,
+    /// Minimum interval between clipboard transfers (ms)
+    pub clipboard_rate_limit_ms: u64,
+    /// H.264 bitrate cap (kbps)
+    pub h264_bitrate: u32,
+    /// Adaptive frame rate enabled
+    pub adaptive_fps_enabled: bool,
+}
+
+impl RuntimePolicy {
+    /// Current policies of a configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            clipboard_enabled: config.clipboard.enabled,
+            clipboard_rate_limit_ms: config.clipboard.rate_limit_ms,
+            h264_bitrate: config.egfx.h264_bitrate,
+            adaptive_fps_enabled: config.performance.adaptive_fps.enabled,
+        }
+    }
+}
+
+/// Partial policy update (`PATCH /v1/policy`); absent fields are unchanged
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyUpdate {
+    /// Enable or disable clipboard sync
+    #[serde(default)]
+    pub clipboard_enabled: Option<bool>,
+    /// Clipboard rate limit (ms)
+    #[serde(default)]
+    pub clipboard_rate_limit_ms: Option<u64>,
+    /// H.264 bitrate cap (kbps, non-zero)
+    #[serde(default)]
+    pub h264_bitrate: Option<u32>,
+    /// Enable or disable adaptive frame rate
+    #[serde(default)]
+    pub adaptive_fps_enabled: Option<bool>,
+}
+
+impl PolicyUpdate {
+    /// Check the requested values
+    pub fn validate(&self) -> Result<(), String> {
+        if self.h264_bitrate == Some(0) {
+            return Err("h264_bitrate must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Copy of `config` with this update applied
+    pub fn apply_to(&self, config: &Config) -> Config {
+        let mut updated = config.clone();
+        if let Some(enabled) = self.clipboard_enabled {
+            updated.clipboard.enabled = enabled;
+        }
+        if let Some(rate_limit_ms) = self.clipboard_rate_limit_ms {
+            updated.clipboard.rate_limit_ms = rate_limit_ms;
+        }
+        if let Some(bitrate) = self.h264_bitrate {
+            updated.egfx.h264_bitrate = bitrate;
+        }
+        if let Some(enabled) = self.adaptive_fps_enabled {
+            updated.performance.adaptive_fps.enabled = enabled;
+        }
+        updated
+    }
+}
+
+/// Connected client as reported by `GET /v1/sessions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// Client ID
+    pub id: u64,
+    /// Client address
+    pub peer: String,
+    /// Client kind (primary, shared, separate, observer)
+    pub kind: String,
+    /// Time since the client connected
+    pub connected_secs: u64,
+    /// Resource use, for clients with a pipeline of their own
+    pub usage: Option<SessionUsage>,
+    /// Quality overlay state, when the overlay is enabled
+    pub overlay_visible: Option<bool>,
+    /// Input and clipboard activity, once the client has a pipeline
+    #[serde(default)]
+    pub sharing: Option<SharingStatus>,
+}
+
+impl From<ClientSessionInfo> for SessionEntry {
+    fn from(client: ClientSessionInfo) -> Self {
+        Self {
+            id: client.id,
+            peer: client.peer.to_string(),
+            kind: client.kind.to_string(),
+            connected_secs: client.connected_at.elapsed().as_secs(),
+            usage: client.meter.map(|meter| meter.snapshot()),
+            overlay_visible: client.overlay.map(|overlay| overlay.is_visible()),
+            sharing: client.sharing.map(|sharing| sharing.status()),
+        }
+    }
+}
+
+/// Statistics of a client as reported by `GET /v1/sessions/{id}/stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Client ID
+    pub id: u64,
+    /// Totals since the client connected
+    pub usage: SessionUsage,
+    /// Rates over the last second (None until the first sample)
+    pub latest: Option<StatsSample>,
+}
+
+/// Server statistics as reported by `GET /v1/stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStats {
+    /// Server version
+    pub version: String,
+    /// Time since the server started
+    pub uptime_secs: u64,
+    /// Multi-client mode
+    pub multi_client: String,
+    /// Client limit
+    pub max_clients: usize,
+    /// Connected clients
+    pub clients: usize,
+    /// Connected observers
+    pub observers: usize,
+    /// Connections admitted since start
+    pub admitted_total: u64,
+    /// Connections rejected since start
+    pub rejected_total: u64,
+    /// Frame buffer pool usage
+    #[serde(default)]
+    pub buffer_pool: BufferPoolStats,
+}
+
+/// Audit entries kept in memory for `GET /v1/settings/audit`
+const AUDIT_HISTORY: usize = 256;
+
+/// Admin API server
+#[derive(Clone)]
+pub struct AdminApi {
+    token: Arc<str>,
+    clients: SessionManager,
+    observers: SessionManager,
+    live_config: Arc<watch::Sender<Arc<Config>>>,
+    started: Instant,
+    /// Drop-in directory settings changes are kept in
+    settings_dir: Option<Arc<PathBuf>>,
+    audit: Arc<Mutex<VecDeque<AuditEntry>>>,
+    /// Frame dump trigger (None = `[frame_dump]` disabled)
+    frame_dump: Option<FrameDump>,
+}
+
+impl AdminApi {
+    /// Create the API over the server's client registries
+    ///
+    /// Policy updates are published on `live_config`.
+    pub fn new(
+        token: String,
+        clients: SessionManager,
+        observers: SessionManager,
+        live_config: Arc<watch::Sender<Arc<Config>>>,
+    ) -> Self {
+        Self {
+            token: token.into(),
+            clients,
+            observers,
+            live_config,
+            started: Instant::now(),
+            settings_dir: None,
+            audit: Arc::default(),
+            frame_dump: None,
+        }
+    }
+
+    /// Keep settings changes and their audit trail in drop-in directory
+    /// `dir` (see [`tunable::drop_in_dir`])
+    pub fn with_settings_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.settings_dir = dir.map(Arc::new);
+        self
+    }
+
+    /// Accept frame dump requests on `POST /v1/debug/frame-dump`
+    pub fn with_frame_dump(mut self, frame_dump: Option<FrameDump>) -> Self {
+        self.frame_dump = frame_dump;
+        self
+    }
+
+    /// Routes with bearer-token authentication applied
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/v1/sessions", get(list_sessions))
+            .route("/v1/sessions/:id", delete(disconnect_session))
+            .route("/v1/sessions/:id/overlay", put(set_overlay))
+            .route("/v1/sessions/:id/sharing", put(set_sharing))
+            .route("/v1/sessions/:id/message", put(show_message))
+            .route("/v1/sessions/:id/stats", get(session_stats))
+            .route("/v1/sessions/:id/stats/history", get(session_history))
+            .route("/v1/stats", get(stats))
+            .route("/v1/policy", get(get_policy).patch(update_policy))
+            .route("/v1/settings", get(get_settings))
+            .route("/v1/settings/audit", get(get_audit))
+            .route("/v1/settings/:key", put(set_setting))
+            .route("/v1/debug/frame-dump", post(request_frame_dump))
+            .route_layer(middleware::from_fn_with_state(self.clone(), require_token))
+            .with_state(self.clone())
+    }
+
+    /// Serve on `listener` until the process exits
+    ///
+    /// With `tls`, clients must complete a (mutual) TLS handshake first.
+    pub async fn serve(
+        self,
+        listener: tokio::net::TcpListener,
+        tls: Option<Arc<ServerConfig>>,
+    ) -> Result<()> {
+        let listen_addr = listener
+            .local_addr()
+            .context("Admin API listener has no address")?;
+        let app = self.router();
+
+        let Some(tls) = tls else {
+            info!("🛠️ Admin API listening on http://{}", listen_addr);
+            return axum::serve(listener, app)
+                .await
+                .context("Admin API server error");
+        };
+
+        info!(
+            "🛠️ Admin API listening on https://{} (client certificates required)",
+            listen_addr
+        );
+        let acceptor = TlsAcceptor::from(tls);
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Failed to accept admin API connection: {}", e);
+                    continue;
+                }
+            };
+
+            let acceptor = acceptor.clone();
+            let service = TowerToHyperService::new(app.clone());
+            tokio::spawn(async move {
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("Admin API TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                };
+                if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!("Admin API connection from {} ended: {}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+/// Client of a running server's admin API
+///
+/// Used by the `sessions` subcommand. Speaks plain HTTP, so it only reaches
+/// an API without mutual TLS, which listens on loopback.
+pub struct AdminClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl AdminClient {
+    /// Client for the API configured in `[admin_api]`
+    pub fn from_config(config: &AdminApiConfig) -> Result<Self> {
+        if !config.enabled {
+            bail!("The admin API is disabled; set admin_api.enabled = true");
+        }
+        if config.token.is_empty() {
+            bail!("admin_api.token is not set");
+        }
+        if config.client_ca_path.is_some() {
+            bail!("The admin API requires client certificates; use an HTTPS client with one");
+        }
+
+        let mut addr: SocketAddr = config
+            .listen_addr
+            .parse()
+            .with_context(|| format!("Invalid admin_api.listen_addr: {}", config.listen_addr))?;
+        if addr.ip().is_unspecified() {
+            let loopback: IpAddr = if addr.is_ipv4() {
+                Ipv4Addr::LOCALHOST.into()
+            } else {
+                Ipv6Addr::LOCALHOST.into()
+            };
+            addr.set_ip(loopback);
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            http,
+            base_url: format!("http://{}", addr),
+            // The GUI passes the file's settings, references unresolved
+            token: secrets::resolve(&config.token).context("admin_api.token")?,
+        })
+    }
+
+    /// Connected clients and observers
+    pub async fn sessions(&self) -> Result<Vec<SessionEntry>> {
+        let response = self
+            .http
+            .get(format!("{}/v1/sessions", self.base_url))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?
+            .error_for_status()
+            .context("Admin API refused the request")?;
+        response.json().await.context("Invalid session list")
+    }
+
+    /// Uptime and connection counters
+    pub async fn stats(&self) -> Result<AdminStats> {
+        let response = self
+            .http
+            .get(format!("{}/v1/stats", self.base_url))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?
+            .error_for_status()
+            .context("Admin API refused the request")?;
+        response.json().await.context("Invalid server statistics")
+    }
+
+    /// Per-second samples of a client taken after `since_ms` (Unix
+    /// milliseconds; 0 for all); None if no client has this ID
+    pub async fn session_history(
+        &self,
+        id: u64,
+        since_ms: u64,
+    ) -> Result<Option<Vec<StatsSample>>> {
+        let response = self
+            .http
+            .get(format!(
+                "{}/v1/sessions/{}/stats/history",
+                self.base_url, id
+            ))
+            .query(&[("since", since_ms)])
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response
+            .error_for_status()
+            .context("Admin API refused the request")?;
+        response
+            .json()
+            .await
+            .map(Some)
+            .context("Invalid statistics history")
+    }
+
+    /// Disconnect a client; false if no client has this ID
+    pub async fn disconnect(&self, id: u64) -> Result<bool> {
+        let response = self
+            .http
+            .delete(format!("{}/v1/sessions/{}", self.base_url, id))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_status()
+            .context("Admin API refused the request")?;
+        Ok(true)
+    }
+
+    /// Show a message on a client's screen for `duration_secs` (None for
+    /// the server's default); false if no client has this ID
+    pub async fn send_message(
+        &self,
+        id: u64,
+        text: &str,
+        duration_secs: Option<u64>,
+    ) -> Result<bool> {
+        let response = self
+            .http
+            .put(format!("{}/v1/sessions/{}/message", self.base_url, id))
+            .bearer_auth(&self.token)
+            .json(&serde_json::json!({ "text": text, "duration_secs": duration_secs }))
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !status.is_success() {
+            let reason = response.text().await.unwrap_or_default();
+            bail!(
+                "Admin API refused the message ({}): {}",
+                status,
+                reason.trim()
+            );
+        }
+        Ok(true)
+    }
+
+    /// Pause or resume a client's sharing; false if no client has this ID
+    pub async fn set_sharing_paused(&self, id: u64, paused: bool) -> Result<bool> {
+        let response = self
+            .http
+            .put(format!("{}/v1/sessions/{}/sharing", self.base_url, id))
+            .bearer_auth(&self.token)
+            .json(&serde_json::json!({ "paused": paused }))
+            .send()
+            .await
+            .with_context(|| format!("Admin API at {} unreachable", self.base_url))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_status()
+            .context("Admin API refused the request")?;
+        Ok(true)
+    }
+}
+
+/// Compare tokens in time independent of where they differ
+fn token_matches(expected: &str, given: &str) -> bool {
+    let (expected, given) = (expected.as_bytes(), given.as_bytes());
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn require_token(State(api): State<AdminApi>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(&api.token, token.trim()));
+
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+async fn list_sessions(State(api): State<AdminApi>) -> Json<Vec<SessionEntry>> {
+    let mut sessions: Vec<SessionEntry> = api
+        .clients
+        .clients()
+        .into_iter()
+        .chain(api.observers.clients())
+        .map(SessionEntry::from)
+        .collect();
+    sessions.sort_by_key(|s| s.id);
+    Json(sessions)
+}
+
+async fn disconnect_session(State(api): State<AdminApi>, Path(id): Path<u64>) -> StatusCode {
+    if api.clients.disconnect(id) || api.observers.disconnect(id) {
+        info!("🛠️ Admin API: disconnecting client {}", id);
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Body of `PUT /v1/sessions/{id}/overlay`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverlayUpdate {
+    visible: bool,
+}
+
+async fn set_overlay(
+    State(api): State<AdminApi>,
+    Path(id): Path<u64>,
+    Json(update): Json<OverlayUpdate>,
+) -> StatusCode {
+    let client = api
+        .clients
+        .clients()
+        .into_iter()
+        .chain(api.observers.clients())
+        .find(|client| client.id == id);
+    match client.map(|client| client.overlay) {
+        Some(Some(overlay)) => {
+            info!(
+                "🛠️ Admin API: quality overlay of client {} set to visible={}",
+                id, update.visible
+            );
+            overlay.set_visible(update.visible);
+            StatusCode::NO_CONTENT
+        }
+        Some(None) => StatusCode::CONFLICT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+/// Body of `PUT /v1/sessions/{id}/sharing`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SharingUpdate {
+    paused: bool,
+}
+
+async fn set_sharing(
+    State(api): State<AdminApi>,
+    Path(id): Path<u64>,
+    Json(update): Json<SharingUpdate>,
+) -> StatusCode {
+    let client = api
+        .clients
+        .clients()
+        .into_iter()
+        .chain(api.observers.clients())
+        .find(|client| client.id == id);
+    match client.map(|client| client.sharing) {
+        Some(Some(sharing)) => {
+            info!(
+                "🛠️ Admin API: sharing with client {} set to paused={}",
+                id, update.paused
+            );
+            sharing.set_paused(update.paused);
+            StatusCode::NO_CONTENT
+        }
+        Some(None) => StatusCode::CONFLICT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+/// Body of `PUT /v1/sessions/{id}/message`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MessageUpdate {
+    text: String,
+    #[serde(default)]
+    duration_secs: Option<u64>,
+}
+
+impl MessageUpdate {
+    /// How long to show the message, or why it is refused
+    fn duration(&self) -> Result<Duration, String> {
+        if self.text.trim().is_empty() {
+            return Err("The message is empty".to_string());
+        }
+        if self.text.chars().count() > MAX_MESSAGE_CHARS {
+            return Err(format!(
+                "The message is longer than {} characters",
+                MAX_MESSAGE_CHARS
+            ));
+        }
+        let duration = self
+            .duration_secs
+            .map_or(DEFAULT_DURATION, Duration::from_secs);
+        if duration.is_zero() || duration > MAX_DURATION {
+            return Err(format!(
+                "duration_secs must be between 1 and {}",
+                MAX_DURATION.as_secs()
+            ));
+        }
+        Ok(duration)
+    }
+}
+
+async fn show_message(
+    State(api): State<AdminApi>,
+    Path(id): Path<u64>,
+    Json(update): Json<MessageUpdate>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let duration = update
+        .duration()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let client = api
+        .clients
+        .clients()
+        .into_iter()
+        .chain(api.observers.clients())
+        .find(|client| client.id == id);
+    match client.map(|client| client.message) {
+        Some(Some(message)) => {
+            info!(
+                "🛠️ Admin API: showing a message to client {} for {}s",
+                id,
+                duration.as_secs()
+            );
+            message.show(update.text.trim(), duration);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Some(None) => Err((
+            StatusCode::CONFLICT,
+            format!("Client {} has no pipeline yet", id),
+        )),
+        None => Err((StatusCode::NOT_FOUND, format!("No client {}", id))),
+    }
+}
+
+/// Meter of client `id`: 404 if unknown, 409 without a pipeline
+fn session_meter(api: &AdminApi, id: u64) -> Result<SessionMeter, StatusCode> {
+    let client = api
+        .clients
+        .clients()
+        .into_iter()
+        .chain(api.observers.clients())
+        .find(|client| client.id == id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    client.meter.ok_or(StatusCode::CONFLICT)
+}
+
+async fn session_stats(
+    State(api): State<AdminApi>,
+    Path(id): Path<u64>,
+) -> Result<Json<SessionStats>, StatusCode> {
+    let meter = session_meter(&api, id)?;
+    Ok(Json(SessionStats {
+        id,
+        usage: meter.snapshot(),
+        latest: meter.history().latest(),
+    }))
+}
+
+/// `since` of a `GET /v1/sessions/{id}/stats/history` query (0 if absent)
+fn history_since(query: Option<&str>) -> Result<u64, StatusCode> {
+    let since = query
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("since="));
+    match since {
+        Some(value) => value.parse().map_err(|_| StatusCode::BAD_REQUEST),
+        None => Ok(0),
+    }
+}
+
+async fn session_history(
+    State(api): State<AdminApi>,
+    Path(id): Path<u64>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<StatsSample>>, StatusCode> {
+    let since = history_since(query.as_deref())?;
+    let meter = session_meter(&api, id)?;
+    Ok(Json(meter.history().since(since)))
+}
+
+async fn stats(State(api): State<AdminApi>) -> Json<AdminStats> {
+    Json(AdminStats {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_secs: api.started.elapsed().as_secs(),
+        multi_client: api.clients.mode().to_string(),
+        max_clients: api.clients.max_clients(),
+        clients: api.clients.client_count(),
+        observers: api.observers.client_count(),
+        admitted_total: api.clients.admitted_total() + api.observers.admitted_total(),
+        rejected_total: api.clients.rejected_total() + api.observers.rejected_total(),
+        buffer_pool: BufferPool::global().stats(),
+    })
+}
+
+async fn request_frame_dump(
+    State(api): State<AdminApi>,
+) -> Result<(StatusCode, Json<DumpRequest>), Response> {
+    let Some(ref frame_dump) = api.frame_dump else {
+        return Err((StatusCode::CONFLICT, "Frame dumps are disabled").into_response());
+    };
+    match frame_dump.request() {
+        Ok(request) => {
+            info!("🛠️ Admin API: frame dump {} requested", request.id);
+            Ok((StatusCode::ACCEPTED, Json(request)))
+        }
+        Err(limited) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(
+                header::RETRY_AFTER,
+                limited.retry_after.as_secs().max(1).to_string(),
+            )],
+            limited.to_string(),
+        )
+            .into_response()),
+    }
+}
+
+async fn get_policy(State(api): State<AdminApi>) -> Json<RuntimePolicy> {
+    Json(RuntimePolicy::from_config(&api.live_config.borrow()))
+}
+
+async fn update_policy(
+    State(api): State<AdminApi>,
+    Json(update): Json<PolicyUpdate>,
+) -> Result<Json<RuntimePolicy>, (StatusCode, String)> {
+    update
+        .validate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    api.live_config
+        .send_modify(|config| *config = Arc::new(update.apply_to(config)));
+    let policy = RuntimePolicy::from_config(&api.live_config.borrow());
+    info!("🛠️ Admin API: runtime policy updated: {:?}", policy);
+    Ok(Json(policy))
+}
+
+async fn get_settings(State(api): State<AdminApi>) -> Json<BTreeMap<String, toml::Value>> {
+    Json(api.live_config.borrow().tunable_settings())
+}
+
+async fn get_audit(State(api): State<AdminApi>) -> Json<Vec<AuditEntry>> {
+    let audit = api.audit.lock().unwrap_or_else(|e| e.into_inner());
+    Json(audit.iter().cloned().collect())
+}
+
+/// Body of `PUT /v1/settings/{key}`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingUpdate {
+    value: serde_json::Value,
+    /// Who is making the change, for the audit trail
+    #[serde(default)]
+    actor: Option<String>,
+}
+
+/// Result of `PUT /v1/settings/{key}`
+#[derive(Debug, Serialize)]
+struct SettingChanged {
+    key: String,
+    value: toml::Value,
+    /// Kept in the drop-in, so the change survives restarts
+    persisted: bool,
+}
+
+async fn set_setting(
+    State(api): State<AdminApi>,
+    Path(key): Path<String>,
+    Json(update): Json<SettingUpdate>,
+) -> Result<Json<SettingChanged>, (StatusCode, String)> {
+    if !tunable::is_tunable(&key) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} is not a runtime-tunable setting", key),
+        ));
+    }
+    let value = toml::Value::try_from(&update.value).map_err(|e| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid value: {}", e),
+        )
+    })?;
+
+    let mut result = Err(String::new());
+    api.live_config.send_if_modified(|config| {
+        let old = config.tunable_settings().remove(&key);
+        match config.with_tunable_setting(&key, value.clone()) {
+            Ok(updated) => {
+                result = Ok((old, updated.tunable_settings().remove(&key)));
+                *config = Arc::new(updated);
+                true
+            }
+            Err(e) => {
+                result = Err(format!("{:#}", e));
+                false
+            }
+        }
+    });
+    let (Some(old), Some(new)) = result.map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))? else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} is not a runtime-tunable setting", key),
+        ));
+    };
+
+    let entry = AuditEntry {
+        at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        actor: update
+            .actor
+            .filter(|actor| !actor.trim().is_empty())
+            .unwrap_or_else(|| "admin-api".to_string()),
+        key: key.clone(),
+        old,
+        new: new.clone(),
+    };
+    info!(
+        "🛠️ Admin API: {} changed {} from {} to {}",
+        entry.actor, entry.key, entry.old, entry.new
+    );
+
+    let persisted = match api.settings_dir.as_deref() {
+        Some(dir) => {
+            match tunable::persist(dir, &key, &new).and_then(|()| tunable::record(dir, &entry)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Setting change not persisted: {:#}", e);
+                    false
+                }
+            }
+        }
+        None => false,
+    };
+    {
+        let mut audit = api.audit.lock().unwrap_or_else(|e| e.into_inner());
+        if audit.len() == AUDIT_HISTORY {
+            audit.pop_front();
+        }
+        audit.push_back(entry);
+    }
+
+    Ok(Json(SettingChanged {
+        key,
+        value: new,
+        persisted,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cret", "s3creT"));
+        assert!(!token_matches("s3cret", "s3cre"));
+        assert!(!token_matches("s3cret", ""));
+    }
+
+    #[test]
+    fn test_client_targets_loopback() {
+        let mut config = AdminApiConfig {
+            enabled: true,
+            listen_addr: "0.0.0.0:3392".to_string(),
+            token: "s3cret".to_string(),
+            client_ca_path: None,
+        };
+        let client = AdminClient::from_config(&config).unwrap();
+        assert_eq!(client.base_url, "http://127.0.0.1:3392");
+
+        config.client_ca_path = Some("/etc/ca.pem".into());
+        assert!(AdminClient::from_config(&config).is_err());
+        config.client_ca_path = None;
+        config.token.clear();
+        assert!(AdminClient::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_history_since() {
+        assert_eq!(history_since(None), Ok(0));
+        assert_eq!(
+            history_since(Some("since=1700000000000")),
+            Ok(1_700_000_000_000)
+        );
+        assert_eq!(history_since(Some("x=1&since=5")), Ok(5));
+        assert_eq!(
+            history_since(Some("since=soon")),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_policy_update_is_partial() {
+        let config = Config::default_config().unwrap();
+        let update: PolicyUpdate =
+            serde_json::from_str(r#"{"clipboard_enabled":false,"h264_bitrate":2000}"#).unwrap();
+        assert!(update.validate().is_ok());
+
+        let updated = update.apply_to(&config);
+        let policy = RuntimePolicy::from_config(&updated);
+        assert!(!policy.clipboard_enabled);
+        assert_eq!(policy.h264_bitrate, 2000);
+        assert_eq!(
+            policy.clipboard_rate_limit_ms,
+            config.clipboard.rate_limit_ms
+        );
+
+        assert!(serde_json::from_str::<PolicyUpdate>(r#"{"listen_addr":"x"}"#).is_err());
+        let zero: PolicyUpdate = serde_json::from_str(r#"{"h264_bitrate":0}"#).unwrap();
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_message_update() {
+        let parse = |json: &str| serde_json::from_str::<MessageUpdate>(json).unwrap();
+        assert_eq!(
+            parse(r#"{"text":"Restarting at 18:00"}"#).duration(),
+            Ok(DEFAULT_DURATION)
+        );
+        assert_eq!(
+            parse(r#"{"text":"hi","duration_secs":30}"#).duration(),
+            Ok(Duration::from_secs(30))
+        );
+        assert!(parse(r#"{"text":"  "}"#).duration().is_err());
+        let forever = parse(r#"{"text":"hi","duration_secs":86400}"#);
+        assert!(forever.duration().is_err());
+        let long = format!(r#"{{"text":"{}"}}"#, "x".repeat(MAX_MESSAGE_CHARS + 1));
+        assert!(parse(&long).duration().is_err());
+    }
+}
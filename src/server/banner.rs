@@ -0,0 +1,250 @@
+//! Login Banner
+//!
+//! Many corporate and government environments require users to acknowledge
+//! a legal notice before they get access to a system. With `[login_banner]`
+//! enabled, each session starts on a banner screen:
+//!
+//! - the display pipeline replaces every frame with the banner image, fitted
+//!   to the client's desktop
+//! - the input handler withholds the client's input from the host
+//! - pressing Enter or clicking acknowledges the banner and shows the desktop
+//!
+//! Shadowing observers get the banner too. Their Enter or click is taken as
+//! the acknowledgment before their input is discarded as view-only.
+//!
+//! Clients that never acknowledge are disconnected after the configured
+//! timeout. The banner is an image rather than server-rendered text so that
+//! admins control the exact wording, layout and language.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::sync::Notify;
+use tracing::info;
+
+use super::frame_scaler::{FrameScaler, ScalingPolicy};
+use crate::config::types::LoginBannerConfig;
+
+/// Scancode of the Enter key, which acknowledges the banner
+pub const ACKNOWLEDGE_SCANCODE: u8 = 0x1C;
+
+/// Banner image as tightly packed BGRA pixels
+#[derive(Debug)]
+struct BannerImage {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl BannerImage {
+    fn load(path: &Path) -> Result<Self> {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load login banner {:?}", path))?
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        let mut data = image.into_raw();
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+        })
+    }
+
+    /// Banner letterboxed to the given size
+    fn render(&self, width: u32, height: u32) -> Vec<u8> {
+        FrameScaler::new(
+            ScalingPolicy::Fit,
+            (width, height),
+            (self.width, self.height),
+        )
+        .scale(&self.data, self.width, self.height)
+        .map(|scaled| scaled.data)
+        .unwrap_or_else(|| self.data.clone())
+    }
+}
+
+/// Banner rendered for one output size (width, height, pixels)
+type Rendered = (u32, u32, Arc<Vec<u8>>);
+
+#[derive(Debug)]
+struct Inner {
+    /// None = banner disabled
+    image: Option<BannerImage>,
+    timeout: Option<Duration>,
+    acknowledged: AtomicBool,
+    acknowledged_notify: Notify,
+    rendered: Mutex<Option<Rendered>>,
+}
+
+/// Acknowledgment state of the login banner for one pipeline
+///
+/// Shared by the display handler (which shows the banner) and the input
+/// handler (which withholds input and takes the acknowledgment).
+#[derive(Debug, Clone)]
+pub struct LoginBanner {
+    inner: Arc<Inner>,
+}
+
+impl LoginBanner {
+    /// Banner from `[login_banner]`, loading its image if enabled
+    pub fn from_config(config: &LoginBannerConfig) -> Result<Self> {
+        if !config.enabled {
+            return Ok(Self::disabled());
+        }
+        let image = BannerImage::load(&config.image_path)?;
+        info!(
+            "📜 Login banner enabled: {:?} ({}x{})",
+            config.image_path, image.width, image.height
+        );
+        Ok(Self::new(
+            Some(image),
+            (config.timeout_secs > 0).then(|| Duration::from_secs(config.timeout_secs)),
+        ))
+    }
+
+    /// No banner: sessions start on the desktop
+    pub fn disabled() -> Self {
+        Self::new(None, None)
+    }
+
+    fn new(image: Option<BannerImage>, timeout: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                acknowledged: AtomicBool::new(image.is_none()),
+                image,
+                timeout,
+                acknowledged_notify: Notify::new(),
+                rendered: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Require acknowledgment again (for a pipeline reused by a new session)
+    pub fn reset(&self) {
+        if self.inner.image.is_some() {
+            self.inner.acknowledged.store(false, Ordering::Release);
+        }
+    }
+
+    /// Whether the banner is still waiting for acknowledgment
+    pub fn is_pending(&self) -> bool {
+        !self.inner.acknowledged.load(Ordering::Acquire)
+    }
+
+    /// Mark the banner as acknowledged and reveal the desktop
+    pub fn acknowledge(&self) {
+        if !self.inner.acknowledged.swap(true, Ordering::AcqRel) {
+            info!("📜 Login banner acknowledged");
+            self.inner.acknowledged_notify.notify_waiters();
+        }
+    }
+
+    /// Banner pixels to show instead of a `width`x`height` frame
+    ///
+    /// Returns `None` once the banner has been acknowledged.
+    pub fn frame(&self, width: u32, height: u32) -> Option<Arc<Vec<u8>>> {
+        let image = self.inner.image.as_ref().filter(|_| self.is_pending())?;
+        let mut rendered = self
+            .inner
+            .rendered
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match rendered.as_ref() {
+            Some((w, h, data)) if (*w, *h) == (width, height) => Some(Arc::clone(data)),
+            _ => {
+                let data = Arc::new(image.render(width, height));
+                *rendered = Some((width, height, Arc::clone(&data)));
+                Some(data)
+            }
+        }
+    }
+
+    /// Resolve if the banner is not acknowledged within the timeout
+    ///
+    /// Stays pending forever once acknowledged or without a timeout.
+    pub async fn expired(&self) {
+        let Some(timeout) = self.inner.timeout else {
+            return std::future::pending().await;
+        };
+        let acknowledged = self.inner.acknowledged_notify.notified();
+        tokio::pin!(acknowledged);
+        acknowledged.as_mut().enable();
+        if self.is_pending() {
+            tokio::select! {
+                () = tokio::time::sleep(timeout) => {
+                    if self.is_pending() {
+                        return;
+                    }
+                }
+                () = acknowledged => {}
+            }
+        }
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banner(timeout: Option<Duration>) -> LoginBanner {
+        // 2x1 image: red, blue (BGRA)
+        let image = BannerImage {
+            data: vec![0, 0, 255, 255, 255, 0, 0, 255],
+            width: 2,
+            height: 1,
+        };
+        LoginBanner::new(Some(image), timeout)
+    }
+
+    #[test]
+    fn test_disabled_banner_never_pending() {
+        let banner = LoginBanner::disabled();
+        banner.reset();
+        assert!(!banner.is_pending());
+        assert!(banner.frame(4, 4).is_none());
+    }
+
+    #[test]
+    fn test_frame_until_acknowledged() {
+        let banner = banner(None);
+        assert!(banner.is_pending());
+
+        // Fitted and letterboxed: 4x4 output holds a 4x2 band
+        let frame = banner.frame(4, 4).unwrap();
+        assert_eq!(frame.len(), 4 * 4 * 4);
+        assert_eq!(&frame[..4], &[0, 0, 0, 255]);
+        assert!(Arc::ptr_eq(&frame, &banner.frame(4, 4).unwrap()));
+
+        banner.acknowledge();
+        assert!(banner.frame(4, 4).is_none());
+        banner.reset();
+        assert!(banner.is_pending());
+    }
+
+    #[tokio::test]
+    async fn test_expiry() {
+        let timeout = Duration::from_millis(50);
+        let unacknowledged = banner(Some(timeout));
+        tokio::time::timeout(timeout * 10, unacknowledged.expired())
+            .await
+            .expect("banner should expire");
+
+        let acknowledged = banner(Some(timeout));
+        let waiter = {
+            let acknowledged = acknowledged.clone();
+            tokio::spawn(async move { acknowledged.expired().await })
+        };
+        tokio::time::sleep(timeout / 5).await;
+        acknowledged.acknowledge();
+        tokio::time::sleep(timeout * 3).await;
+        assert!(!waiter.is_finished());
+        waiter.abort();
+    }
+}
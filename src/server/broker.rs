@@ -0,0 +1,391 @@
+//! Session Broker Integration
+//!
+//! Lets a fleet controller decide what happens to each incoming connection,
+//! enabling load-balanced VDI-style host pools.
+//!
+//! # Flow
+//!
+//! 1. Peek the client's X.224 Connection Request for the routing cookie
+//!    (`Cookie: mstshash=<username>`), without consuming it
+//! 2. Ask the broker (HTTP POST or D-Bus method call) for a decision
+//! 3. Act on it:
+//!    - `allow` - serve the session on this host
+//!    - `deny` - close the connection
+//!    - `redirect` - relay the connection to the target host verbatim
+//!
+//! # HTTP Broker
+//!
+//! `POST <url>` with JSON `{"username", "client_addr", "host"}`; the response
+//! is `{"action": "allow"|"deny"|"redirect", "target"?, "reason"?}`. With
+//! `broker.token` set, requests carry `Authorization: Bearer <token>`.
+//!
+//! # D-Bus Broker
+//!
+//! Method `Decide(s username, s client_addr, s host) -> (s action, s target, s reason)`
+//! on the configured service, path and interface (session bus).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+use crate::config::types::BrokerConfig;
+
+/// How long to wait for the client's first packet
+const PEEK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Routing cookie prefix in the X.224 Connection Request
+const COOKIE_PREFIX: &[u8] = b"Cookie: mstshash=";
+
+/// Request sent to the broker
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerRequest {
+    /// Username from the routing cookie (empty if the client sent none)
+    pub username: String,
+    /// Client address
+    pub client_addr: String,
+    /// This host's identifier
+    pub host: String,
+}
+
+/// Broker verdict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerAction {
+    /// Serve on this host
+    Allow,
+    /// Refuse the connection
+    Deny,
+    /// Relay to another host
+    Redirect,
+}
+
+impl std::fmt::Display for BrokerAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Deny => write!(f, "deny"),
+            Self::Redirect => write!(f, "redirect"),
+        }
+    }
+}
+
+impl std::str::FromStr for BrokerAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            "redirect" => Ok(Self::Redirect),
+            _ => Err(format!("Unknown broker action: {}", s)),
+        }
+    }
+}
+
+/// Routing/authorization decision from the broker
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BrokerDecision {
+    /// What to do with the connection
+    pub action: BrokerAction,
+    /// Target "host:port" for redirects
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Human-readable reason (logged)
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl BrokerDecision {
+    fn allow(reason: &str) -> Self {
+        Self {
+            action: BrokerAction::Allow,
+            target: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+
+    fn deny(reason: &str) -> Self {
+        Self {
+            action: BrokerAction::Deny,
+            target: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Extract the username from an X.224 Connection Request routing cookie
+///
+/// `data` starts with the TPKT header. Returns `None` when the packet is not
+/// a Connection Request or carries no `mstshash` cookie.
+pub fn parse_routing_cookie(data: &[u8]) -> Option<String> {
+    // TPKT (4) + X.224 CR fixed part (7)
+    const CR_HEADER_LEN: usize = 11;
+    const X224_CONNECTION_REQUEST: u8 = 0xE0;
+
+    if data.len() < CR_HEADER_LEN || data[0] != 0x03 || data[5] & 0xF0 != X224_CONNECTION_REQUEST {
+        return None;
+    }
+
+    let tpkt_len = u16::from_be_bytes([data[2], data[3]]) as usize;
+    let end = tpkt_len.min(data.len());
+    let variable = data.get(CR_HEADER_LEN..end)?;
+    let cookie = variable.strip_prefix(COOKIE_PREFIX)?;
+    let len = cookie.windows(2).position(|w| w == b"\r\n")?;
+    let username = std::str::from_utf8(&cookie[..len]).ok()?.trim();
+
+    if username.is_empty() {
+        None
+    } else {
+        Some(username.to_string())
+    }
+}
+
+/// Peek at the client's first packet for the routing cookie
+pub(super) async fn peek_username(stream: &TcpStream) -> Option<String> {
+    let mut buf = [0u8; 512];
+    let peeked = tokio::time::timeout(PEEK_TIMEOUT, stream.peek(&mut buf))
+        .await
+        .ok()?
+        .ok()?;
+    parse_routing_cookie(&buf[..peeked])
+}
+
+enum Backend {
+    Http {
+        client: reqwest::Client,
+        url: String,
+        token: String,
+    },
+    DBus {
+        proxy: zbus::Proxy<'static>,
+    },
+}
+
+/// Client for the configured session broker
+pub struct SessionBroker {
+    backend: Backend,
+    host: String,
+    fail_open: bool,
+}
+
+impl SessionBroker {
+    /// Create a broker client from configuration
+    pub async fn new(config: &BrokerConfig) -> Result<Self> {
+        let timeout = Duration::from_millis(config.timeout_ms);
+        let backend = match config.kind.as_str() {
+            "dbus" => {
+                let connection = zbus::Connection::session()
+                    .await
+                    .context("Failed to connect to session bus")?;
+                let proxy = zbus::ProxyBuilder::new(&connection)
+                    .destination(config.dbus_destination.clone())?
+                    .path(config.dbus_path.clone())?
+                    .interface(config.dbus_interface.clone())?
+                    .build()
+                    .await
+                    .context("Failed to create broker D-Bus proxy")?;
+                Backend::DBus { proxy }
+            }
+            _ => Backend::Http {
+                client: reqwest::Client::builder()
+                    .timeout(timeout)
+                    .build()
+                    .context("Failed to create broker HTTP client")?,
+                url: config.url.clone(),
+                token: config.token.clone(),
+            },
+        };
+
+        let host = if config.host_id.is_empty() {
+            hostname::get()
+                .ok()
+                .and_then(|h| h.into_string().ok())
+                .unwrap_or_else(|| "unknown".to_string())
+        } else {
+            config.host_id.clone()
+        };
+
+        info!(
+            "🧭 Session broker enabled: {} (host id: {}, fail {})",
+            match &backend {
+                Backend::Http { url, .. } => format!("HTTP {}", url),
+                Backend::DBus { .. } => format!("D-Bus {}", config.dbus_destination),
+            },
+            host,
+            if config.fail_open { "open" } else { "closed" }
+        );
+
+        Ok(Self {
+            backend,
+            host,
+            fail_open: config.fail_open,
+        })
+    }
+
+    /// Ask the broker about a connection
+    ///
+    /// Broker failures resolve to allow or deny depending on `fail_open`.
+    pub async fn decide(&self, username: &str, peer: SocketAddr) -> BrokerDecision {
+        let request = BrokerRequest {
+            username: username.to_string(),
+            client_addr: peer.to_string(),
+            host: self.host.clone(),
+        };
+
+        match self.query(&request).await {
+            Ok(decision) => decision,
+            Err(e) if self.fail_open => {
+                warn!("Broker unavailable, allowing {} (fail open): {:#}", peer, e);
+                BrokerDecision::allow("broker unavailable")
+            }
+            Err(e) => {
+                warn!(
+                    "Broker unavailable, refusing {} (fail closed): {:#}",
+                    peer, e
+                );
+                BrokerDecision::deny("broker unavailable")
+            }
+        }
+    }
+
+    async fn query(&self, request: &BrokerRequest) -> Result<BrokerDecision> {
+        match &self.backend {
+            Backend::Http { client, url, token } => {
+                let mut http = client.post(url).json(request);
+                if !token.is_empty() {
+                    http = http.bearer_auth(token);
+                }
+                http.send()
+                    .await
+                    .context("Broker request failed")?
+                    .error_for_status()
+                    .context("Broker returned an error")?
+                    .json::<BrokerDecision>()
+                    .await
+                    .context("Invalid broker response")
+            }
+            Backend::DBus { proxy } => {
+                let response = proxy
+                    .call_method(
+                        "Decide",
+                        &(
+                            request.username.as_str(),
+                            request.client_addr.as_str(),
+                            request.host.as_str(),
+                        ),
+                    )
+                    .await
+                    .context("Broker Decide call failed")?;
+                let (action, target, reason): (String, String, String) = response
+                    .body()
+                    .deserialize()
+                    .context("Invalid broker Decide response")?;
+                Ok(BrokerDecision {
+                    action: action.parse().map_err(anyhow::Error::msg)?,
+                    target: Some(target).filter(|t| !t.is_empty()),
+                    reason: Some(reason).filter(|r| !r.is_empty()),
+                })
+            }
+        }
+    }
+
+    /// Route a connection according to the broker
+    ///
+    /// Returns the stream when it should be served locally; denied and
+    /// redirected connections are handled here.
+    pub async fn route(&self, stream: TcpStream, peer: SocketAddr) -> Option<TcpStream> {
+        let username = peek_username(&stream).await.unwrap_or_default();
+        let decision = self.decide(&username, peer).await;
+        let reason = decision.reason.as_deref().unwrap_or("-");
+
+        match decision.action {
+            BrokerAction::Allow => {
+                info!(
+                    "🧭 Broker: serving '{}' from {} locally ({})",
+                    username, peer, reason
+                );
+                Some(stream)
+            }
+            BrokerAction::Deny => {
+                info!(
+                    "🧭 Broker: denied '{}' from {} ({})",
+                    username, peer, reason
+                );
+                None
+            }
+            BrokerAction::Redirect => {
+                let Some(target) = decision.target else {
+                    warn!("Broker redirect for {} has no target, refusing", peer);
+                    return None;
+                };
+                info!(
+                    "🧭 Broker: relaying '{}' from {} to {} ({})",
+                    username, peer, target, reason
+                );
+                tokio::spawn(relay(stream, target));
+                None
+            }
+        }
+    }
+}
+
+/// Relay a client connection to another host byte-for-byte
+async fn relay(mut client: TcpStream, target: String) {
+    let mut upstream = match TcpStream::connect(&target).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            warn!("Failed to connect to redirect target {}: {}", target, e);
+            return;
+        }
+    };
+
+    match tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+        Ok((up, down)) => debug!(
+            "Relay to {} closed ({} bytes up, {} bytes down)",
+            target, up, down
+        ),
+        Err(e) => debug!("Relay to {} ended: {}", target, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection_request(cookie: &[u8]) -> Vec<u8> {
+        let mut variable = cookie.to_vec();
+        // RDP negotiation request
+        variable.extend_from_slice(&[0x01, 0x00, 0x08, 0x00, 0x03, 0x00, 0x00, 0x00]);
+        let total = 11 + variable.len();
+        let mut packet = vec![0x03, 0x00, (total >> 8) as u8, total as u8];
+        packet.extend_from_slice(&[(total - 5) as u8, 0xE0, 0, 0, 0, 0, 0]);
+        packet.extend_from_slice(&variable);
+        packet
+    }
+
+    #[test]
+    fn test_parse_routing_cookie() {
+        let packet = connection_request(b"Cookie: mstshash=alice\r\n");
+        assert_eq!(parse_routing_cookie(&packet), Some("alice".to_string()));
+    }
+
+    #[test]
+    fn test_parse_without_cookie() {
+        let packet = connection_request(b"");
+        assert_eq!(parse_routing_cookie(&packet), None);
+        assert_eq!(parse_routing_cookie(b"GET / HTTP/1.1\r\n"), None);
+        assert_eq!(parse_routing_cookie(&[0x03, 0x00]), None);
+    }
+
+    #[test]
+    fn test_decision_json() {
+        let decision: BrokerDecision =
+            serde_json::from_str(r#"{"action":"redirect","target":"10.0.0.5:3389"}"#).unwrap();
+        assert_eq!(decision.action, BrokerAction::Redirect);
+        assert_eq!(decision.target.as_deref(), Some("10.0.0.5:3389"));
+        assert!(serde_json::from_str::<BrokerDecision>(r#"{"action":"maybe"}"#).is_err());
+    }
+}
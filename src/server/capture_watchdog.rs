@@ -0,0 +1,179 @@
+//! Capture Watchdog
+//!
+//! The capture session can stay open while its streams stop delivering
+//! frames: a compositor bug, or a monitor in DPMS standby. The client then
+//! shows the last picture with nothing to tell it is stale.
+//!
+//! With `[capture_watchdog]` enabled, the display pipeline notes when frames
+//! arrive. Once none have come for the timeout while a client is connected,
+//! it counts a stall in the session's [`SessionMeter`](super::SessionMeter),
+//! logs it and, per the configured action, dims the client's last frame or
+//! re-creates the PipeWire streams. It acts again after every further
+//! timeout until frames return.
+
+use std::time::{Duration, Instant};
+
+use crate::config::types::CaptureWatchdogConfig;
+
+/// Reaction to a frozen capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum WatchdogAction {
+    /// Log and count the stall only
+    #[default]
+    Log,
+    /// Re-send the last frame dimmed
+    Indicator,
+    /// Re-create the PipeWire streams on their nodes
+    Renegotiate,
+}
+
+impl std::fmt::Display for WatchdogAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Log => write!(f, "log"),
+            Self::Indicator => write!(f, "indicator"),
+            Self::Renegotiate => write!(f, "renegotiate"),
+        }
+    }
+}
+
+impl std::str::FromStr for WatchdogAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "log" => Ok(Self::Log),
+            "indicator" => Ok(Self::Indicator),
+            "renegotiate" => Ok(Self::Renegotiate),
+            _ => Err(format!("Unknown capture watchdog action: {}", s)),
+        }
+    }
+}
+
+/// Frame arrival tracking of one pipeline
+#[derive(Debug)]
+pub(super) struct CaptureWatchdog {
+    timeout: Duration,
+    action: WatchdogAction,
+    last_frame: Instant,
+    /// Next time to act if no frame arrives
+    deadline: Instant,
+    stalled: bool,
+}
+
+impl CaptureWatchdog {
+    /// Watchdog from `[capture_watchdog]`, or None when disabled
+    pub(super) fn from_config(config: &CaptureWatchdogConfig, now: Instant) -> Option<Self> {
+        if !config.enabled || config.timeout_secs == 0 {
+            return None;
+        }
+        let timeout = Duration::from_secs(config.timeout_secs);
+        Some(Self {
+            timeout,
+            action: config.action.parse().unwrap_or_default(),
+            last_frame: now,
+            deadline: now + timeout,
+            stalled: false,
+        })
+    }
+
+    pub(super) fn action(&self) -> WatchdogAction {
+        self.action
+    }
+
+    /// Time since the last frame
+    pub(super) fn frozen_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_frame)
+    }
+
+    /// Note a frame; returns how long the capture was frozen if it was
+    pub(super) fn frame_arrived(&mut self, now: Instant) -> Option<Duration> {
+        let frozen = self.stalled.then(|| self.frozen_for(now));
+        self.last_frame = now;
+        self.deadline = now + self.timeout;
+        self.stalled = false;
+        frozen
+    }
+
+    /// Whether the capture has been silent past the deadline
+    ///
+    /// True once per timeout while no frames arrive.
+    pub(super) fn check(&mut self, now: Instant) -> bool {
+        if now < self.deadline {
+            return false;
+        }
+        self.deadline = now + self.timeout;
+        self.stalled = true;
+        true
+    }
+
+    /// Restart the timeout without acting (no client to act for)
+    pub(super) fn rearm(&mut self, now: Instant) {
+        self.deadline = now + self.timeout;
+    }
+}
+
+/// Dim a BGRA frame to half brightness, marking it as stale
+pub(super) fn dim_frame(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        for channel in &mut pixel[..3] {
+            *channel /= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(enabled: bool, action: &str) -> CaptureWatchdogConfig {
+        CaptureWatchdogConfig {
+            enabled,
+            timeout_secs: 10,
+            action: action.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fires_once_per_timeout() {
+        let start = Instant::now();
+        assert!(CaptureWatchdog::from_config(&config(false, "log"), start).is_none());
+
+        let mut watchdog = CaptureWatchdog::from_config(&config(true, "indicator"), start).unwrap();
+        assert_eq!(watchdog.action(), WatchdogAction::Indicator);
+
+        let at = |secs| start + Duration::from_secs(secs);
+        assert!(!watchdog.check(at(9)));
+        assert!(watchdog.check(at(10)));
+        assert!(!watchdog.check(at(15)));
+        assert!(watchdog.check(at(20)));
+        assert_eq!(watchdog.frozen_for(at(20)), Duration::from_secs(20));
+
+        // Frames return: the stall is reported once, the timeout restarts
+        assert_eq!(
+            watchdog.frame_arrived(at(22)),
+            Some(Duration::from_secs(22))
+        );
+        assert_eq!(watchdog.frame_arrived(at(23)), None);
+        assert!(!watchdog.check(at(32)));
+        assert!(watchdog.check(at(33)));
+    }
+
+    #[test]
+    fn test_rearm_without_client() {
+        let start = Instant::now();
+        let mut watchdog = CaptureWatchdog::from_config(&config(true, "bogus"), start).unwrap();
+        assert_eq!(watchdog.action(), WatchdogAction::Log);
+
+        watchdog.rearm(start + Duration::from_secs(8));
+        assert!(!watchdog.check(start + Duration::from_secs(12)));
+        assert!(watchdog.check(start + Duration::from_secs(18)));
+    }
+
+    #[test]
+    fn test_dim_frame_keeps_alpha() {
+        let mut data = vec![200, 100, 51, 255, 0, 0, 0, 128];
+        dim_frame(&mut data);
+        assert_eq!(data, vec![100, 50, 25, 255, 0, 0, 0, 128]);
+    }
+}
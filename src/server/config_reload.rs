@@ -0,0 +1,156 @@
+//! Configuration Hot-Reload
+//!
+//! Re-reads the TOML configuration on SIGHUP and publishes the runtime-safe
+//! settings to running pipelines without dropping connections. Settings that
+//! need a restart are logged and keep their current values.
+//!
+//! Pipelines observe changes through a `tokio::sync::watch` channel of the
+//! effective configuration (see [`crate::config::reload`] for what is safe).
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::config::{Config, ReloadReport};
+
+/// Applies a new `logging.level` to the process-wide log filter
+pub type LogLevelHandler = Box<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
+/// Reloads the configuration file and publishes runtime-safe changes
+pub struct ConfigReloader {
+    path: PathBuf,
+    /// Configuration as read from the file and environment (without CLI
+    /// overrides), with runtime changes applied; restart-only changes are
+    /// diffed against it
+    file_config: Mutex<Config>,
+    live: Arc<watch::Sender<Arc<Config>>>,
+    log_level: Option<LogLevelHandler>,
+}
+
+impl ConfigReloader {
+    /// Create a reloader for `path`
+    ///
+    /// `file_config` is the configuration loaded from `path` at startup,
+    /// with environment overrides but before command-line overrides.
+    pub(super) fn new(
+        path: PathBuf,
+        file_config: Config,
+        live: Arc<watch::Sender<Arc<Config>>>,
+    ) -> Self {
+        Self {
+            path,
+            file_config: Mutex::new(file_config),
+            live,
+            log_level: None,
+        }
+    }
+
+    /// Apply `logging.level` changes through `handler`
+    pub fn with_log_level_handler(mut self, handler: LogLevelHandler) -> Self {
+        self.log_level = Some(handler);
+        self
+    }
+
+    /// Re-read the configuration file and apply runtime-safe changes
+    pub fn reload(&self) -> Result<ReloadReport> {
+        // Loading validates; an invalid file leaves the running settings alone
+        let new = Config::load(&self.path.to_string_lossy())
+            .and_then(Config::with_env_overrides)
+            .with_context(|| format!("Failed to reload {}", self.path.display()))?;
+
+        let mut file_config = self.file_config.lock().unwrap_or_else(|e| e.into_inner());
+        let report = ReloadReport::between(&file_config, &new);
+
+        if !report.applied.is_empty() {
+            if report.applied.iter().any(|s| s == "logging.level") {
+                if let Some(ref handler) = self.log_level {
+                    handler(&new.logging.level).context("Failed to apply log level")?;
+                }
+            }
+
+            let effective = self.live.borrow().with_runtime_settings_from(&new);
+            self.live.send_replace(Arc::new(effective));
+            *file_config = file_config.with_runtime_settings_from(&new);
+        }
+
+        Ok(report)
+    }
+
+    /// Reload on every SIGHUP until the process exits
+    pub async fn run_on_sighup(self) {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!(
+                    "Config hot-reload unavailable (cannot handle SIGHUP): {}",
+                    e
+                );
+                return;
+            }
+        };
+        info!(
+            "🔄 Config hot-reload enabled: send SIGHUP to re-read {}",
+            self.path.display()
+        );
+
+        while hangup.recv().await.is_some() {
+            info!("🔄 SIGHUP received, reloading configuration");
+            match self.reload() {
+                Ok(report) => log_report(&report),
+                Err(e) => error!("Configuration not reloaded: {:#}", e),
+            }
+        }
+    }
+}
+
+fn log_report(report: &ReloadReport) {
+    if report.is_empty() {
+        info!("🔄 Configuration unchanged");
+        return;
+    }
+    for setting in &report.applied {
+        info!("🔄 Applied: {}", setting);
+    }
+    for setting in &report.restart_required {
+        warn!("🔄 Requires restart (unchanged): {}", setting);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_applies_runtime_settings_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default_config().unwrap();
+        config.security.cert_path = dir.path().join("cert.pem");
+        config.security.key_path = dir.path().join("key.pem");
+        std::fs::write(&config.security.cert_path, "").unwrap();
+        std::fs::write(&config.security.key_path, "").unwrap();
+
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+        let (live, rx) = watch::channel(Arc::new(config.clone()));
+        let reloader = ConfigReloader::new(path.clone(), config.clone(), Arc::new(live));
+
+        let mut edited = config.clone();
+        edited.egfx.h264_bitrate = 2500;
+        edited.server.listen_addr = "0.0.0.0:4000".to_string();
+        std::fs::write(&path, toml::to_string(&edited).unwrap()).unwrap();
+
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.applied, vec!["egfx.h264_bitrate"]);
+        assert_eq!(report.restart_required, vec!["server.listen_addr"]);
+        assert_eq!(rx.borrow().egfx.h264_bitrate, 2500);
+        assert_eq!(rx.borrow().server.listen_addr, config.server.listen_addr);
+
+        // Restart-only changes keep being reported until the restart
+        let report = reloader.reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.restart_required, vec!["server.listen_addr"]);
+    }
+}
@@ -0,0 +1,638 @@
+//! Client-Side Cursor
+//!
+//! Sessions request the cursor from the portal as metadata: PipeWire leaves
+//! it out of the video and attaches its position, hotspot and bitmap to
+//! buffers as SPA cursor meta. This module turns that metadata into RDP
+//! pointer updates, according to `[cursor] mode`:
+//!
+//! - **metadata** - the client draws the pointer: shape changes are sent as
+//!   RGBA pointers (or, with `color_pointers`, as 24-bit color pointers with
+//!   an AND mask for clients without alpha pointer support), movement as
+//!   pointer positions
+//! - **painted** / **predictive** - the cursor is composited into frames on
+//!   the server (predictive draws it where the pointer is heading) and the
+//!   client's own pointer is hidden
+//! - **hidden** - no cursor at all
+//!
+//! With `auto_mode`, sessions switch to predictive painting while the round
+//! trip time exceeds the configured threshold.
+//!
+//! A painted cursor only moves as often as video frames are sent. When
+//! adaptive FPS drops the video to a few frames a second on a static screen,
+//! the pointer would jump from frame to frame. Below `decouple_below_fps` the
+//! cursor is therefore handed to the client like in metadata mode: its
+//! position updates follow every captured frame, skipped or not, and the
+//! video carries no cursor until the frame rate recovers.
+//!
+//! `app_overrides` force a mode while a given application has focus, e.g.
+//! painted for games that draw a software cursor. The focused window is
+//! polled from the compositor where it can tell (Sway, Hyprland).
+//!
+//! If frames carry no cursor metadata (compositors without metadata cursor
+//! support paint it into the video instead), the client's pointer is hidden
+//! so that the painted cursor is the only one shown. Compositors known to
+//! paint the cursor into frames even with metadata (the
+//! `CursorAlwaysInFrame` quirk) get the same from the first frame, whatever
+//! the configured mode.
+
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use ironrdp_pdu::pointer::PointerPositionAttribute;
+use ironrdp_server::{ColorPointer, DisplayUpdate, RGBAPointer};
+use tracing::{debug, info, warn};
+
+use crate::compositor::{focused_window, supports_focus_query, CompositorType};
+use crate::config::{CursorAppOverride, CursorConfig};
+use crate::cursor::{CursorMode, CursorShape, CursorStrategy, CursorStrategyConfig};
+use crate::pipewire::VideoFrame;
+
+/// Frames without cursor metadata before the cursor is assumed painted
+const PAINTED_FALLBACK_FRAMES: u32 = 60;
+
+/// Largest color pointer (MS-RDPBCGR 2.2.9.1.1.4.4); larger shapes are cropped
+const COLOR_POINTER_MAX_SIZE: u32 = 96;
+
+/// Alpha from which a pixel is opaque in a color pointer's AND mask
+const COLOR_POINTER_ALPHA_THRESHOLD: u8 = 128;
+
+/// Frame rate above `decouple_below_fps` at which painting resumes, so a
+/// rate hovering at the threshold doesn't switch back and forth
+const RECOUPLE_MARGIN_FPS: u32 = 5;
+
+/// How often the focused window is polled for application overrides
+const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cursor state PipeWire attached to a captured frame
+#[derive(Debug, Clone)]
+pub struct CursorMeta {
+    /// Pointer position in capture coordinates
+    pub position: (i32, i32),
+    /// Shape, when it changed with this frame
+    pub shape: Option<CursorShape>,
+}
+
+impl CursorMeta {
+    /// Cursor metadata of a frame (SPA_META_Cursor), if it carries any
+    pub fn from_frame(frame: &VideoFrame) -> Option<Self> {
+        let cursor = frame.cursor.as_ref()?;
+        let shape = cursor.bitmap.as_ref().map(|bitmap| CursorShape {
+            width: cursor.size.0,
+            height: cursor.size.1,
+            hotspot_x: cursor.hotspot.0.max(0) as u32,
+            hotspot_y: cursor.hotspot.1.max(0) as u32,
+            data: bitmap.clone(),
+        });
+        Some(Self {
+            position: cursor.position,
+            shape,
+        })
+    }
+}
+
+struct State {
+    strategy: CursorStrategy,
+    /// Send shapes as color pointers rather than RGBA pointers
+    color_pointers: bool,
+    /// Whether the client has the current shape
+    shape_sent: bool,
+    /// Position last sent to the client
+    position_sent: Option<(u16, u16)>,
+    /// Whether the client's pointer is hidden
+    hidden: bool,
+    /// Pointer position in output coordinates (None = off screen)
+    position: Option<(u32, u32)>,
+    /// Metadata has been seen since the pipeline started
+    seen_meta: bool,
+    frames_without_meta: u32,
+    /// Video frame rate below which a painted cursor is drawn by the client
+    decouple_below_fps: u32,
+    /// Whether a painted cursor is drawn by the client for now
+    decoupled: bool,
+    /// The compositor paints the cursor into every frame
+    cursor_in_frames: bool,
+}
+
+impl State {
+    /// Whether the active mode leaves drawing the pointer to the client
+    fn client_draws(&self) -> bool {
+        if self.cursor_in_frames {
+            return false;
+        }
+        match self.strategy.mode() {
+            CursorMode::Metadata => true,
+            CursorMode::Painted | CursorMode::Predictive => self.decoupled,
+            CursorMode::Hidden => false,
+        }
+    }
+}
+
+/// Cursor state of one pipeline, turned into RDP pointer updates
+///
+/// Cheap to clone; clones share the state, so the session code can make a
+/// reused pipeline send the pointer afresh to its next client.
+#[derive(Clone)]
+pub struct CursorChannel {
+    inner: Arc<Mutex<State>>,
+    /// Application ID patterns (lowercase) and their modes
+    overrides: Arc<[(String, CursorMode)]>,
+}
+
+impl CursorChannel {
+    /// Cursor handling from `[cursor]`
+    pub fn from_config(config: &CursorConfig) -> Self {
+        let strategy = CursorStrategy::new(CursorStrategyConfig::from(config));
+        info!("🖱️ Cursor mode: {}", strategy.mode().description());
+        let overrides = config
+            .app_overrides
+            .iter()
+            .filter_map(|CursorAppOverride { app_id, mode }| match mode.parse() {
+                Ok(mode) => Some((app_id.to_lowercase(), mode)),
+                Err(e) => {
+                    warn!("Ignoring cursor override for '{}': {}", app_id, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            overrides,
+            inner: Arc::new(Mutex::new(State {
+                strategy,
+                color_pointers: config.color_pointers,
+                shape_sent: false,
+                position_sent: None,
+                hidden: false,
+                position: None,
+                seen_meta: false,
+                frames_without_meta: 0,
+                decouple_below_fps: config.decouple_below_fps,
+                decoupled: false,
+                cursor_in_frames: false,
+            })),
+        }
+    }
+
+    /// Leave the cursor to the compositor, which paints it into every frame
+    ///
+    /// The client's pointer is hidden and nothing is painted on top, so the
+    /// cursor in the video is the only one shown.
+    pub fn with_cursor_in_frames(self, cursor_in_frames: bool) -> Self {
+        if cursor_in_frames {
+            info!("🖱️ Compositor paints the cursor into frames: client pointer hidden");
+        }
+        self.state().cursor_in_frames = cursor_in_frames;
+        self
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Send shape, position and visibility again (for a new client), and
+    /// start over from the configured cursor mode
+    pub fn reset(&self) {
+        let mut state = self.state();
+        state.strategy.reset();
+        state.shape_sent = false;
+        state.position_sent = None;
+        state.hidden = false;
+    }
+
+    /// Active cursor mode
+    pub fn mode(&self) -> CursorMode {
+        self.state().strategy.mode()
+    }
+
+    /// Note the frame rate video is currently sent at
+    ///
+    /// Below `decouple_below_fps` a painted cursor is drawn by the client
+    /// until the rate recovers; the switch goes out with the next update.
+    pub fn set_video_fps(&self, fps: u32) {
+        let mut state = self.state();
+        let threshold = state.decouple_below_fps;
+        let decoupled = if state.decoupled {
+            fps < threshold + RECOUPLE_MARGIN_FPS
+        } else {
+            fps < threshold
+        };
+        if decoupled != state.decoupled {
+            state.decoupled = decoupled;
+            if state.strategy.needs_compositing() {
+                if decoupled {
+                    info!("🖱️ Video at {} FPS: client draws the cursor", fps);
+                } else {
+                    info!("🖱️ Video at {} FPS: cursor painted again", fps);
+                }
+            }
+        }
+    }
+
+    /// Apply the override of the focused application, if any has one
+    pub fn set_focused_app(&self, app_id: Option<&str>) {
+        let app_id = app_id.map(str::to_lowercase);
+        let mode = app_id.as_deref().and_then(|app_id| {
+            self.overrides
+                .iter()
+                .find(|(pattern, _)| match pattern.strip_suffix('*') {
+                    Some(prefix) => app_id.starts_with(prefix),
+                    None => app_id == pattern,
+                })
+                .map(|&(_, mode)| mode)
+        });
+        self.state().strategy.set_override(mode);
+    }
+
+    /// Follow the focused window of `compositor` for application overrides
+    ///
+    /// Polls until the channel is dropped; does nothing without overrides.
+    pub fn watch_focus(&self, compositor: CompositorType) {
+        if self.overrides.is_empty() {
+            return;
+        }
+        if !supports_focus_query(&compositor) {
+            warn!(
+                "🖱️ Cursor overrides need the focused window, which {} does not report",
+                compositor.name()
+            );
+            return;
+        }
+
+        let inner: Weak<Mutex<State>> = Arc::downgrade(&self.inner);
+        let overrides = Arc::clone(&self.overrides);
+        tokio::spawn(async move {
+            let mut focused: Option<String> = None;
+            loop {
+                tokio::time::sleep(FOCUS_POLL_INTERVAL).await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let query = compositor.clone();
+                let app_id = tokio::task::spawn_blocking(move || focused_window(&query))
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|window| window.app_id);
+                if app_id != focused {
+                    debug!("Focused application: {:?}", app_id);
+                    let channel = CursorChannel {
+                        inner,
+                        overrides: Arc::clone(&overrides),
+                    };
+                    channel.set_focused_app(app_id.as_deref());
+                    focused = app_id;
+                }
+            }
+        });
+    }
+
+    /// Pointer updates for a captured frame
+    ///
+    /// `map` converts capture coordinates to output coordinates, returning
+    /// `None` for positions the client does not see. `rtt_ms` drives the
+    /// automatic switch to predictive painting.
+    pub fn update(
+        &self,
+        meta: Option<CursorMeta>,
+        map: impl FnOnce(i32, i32) -> Option<(u32, u32)>,
+        rtt_ms: f64,
+    ) -> Vec<DisplayUpdate> {
+        let mut state = self.state();
+        state.strategy.update_latency(rtt_ms.round() as u32);
+
+        match meta {
+            Some(meta) => {
+                if !state.seen_meta {
+                    debug!("Cursor metadata received from PipeWire");
+                }
+                state.seen_meta = true;
+                state.frames_without_meta = 0;
+                if let Some(shape) = meta.shape {
+                    if state.strategy.shape() != Some(&shape) {
+                        state.strategy.update_shape(shape);
+                        state.shape_sent = false;
+                    }
+                }
+                state.position = map(meta.position.0, meta.position.1);
+                if let Some((x, y)) = state.position {
+                    state.strategy.update_position(x as i32, y as i32);
+                }
+            }
+            None if !state.seen_meta => {
+                state.frames_without_meta = state.frames_without_meta.saturating_add(1);
+                if state.frames_without_meta == PAINTED_FALLBACK_FRAMES {
+                    info!("🖱️ No cursor metadata from PipeWire: cursor is painted into the video");
+                }
+            }
+            // Metadata only accompanies frames on which the cursor changed
+            None => {}
+        }
+
+        let painted_by_compositor = state.cursor_in_frames
+            || (!state.seen_meta && state.frames_without_meta >= PAINTED_FALLBACK_FRAMES);
+        let client_draws = state.client_draws()
+            && state.seen_meta
+            && state.position.is_some()
+            && state.strategy.shape().is_some();
+
+        let mut updates = Vec::new();
+        if !client_draws {
+            // Nothing for the client to draw yet: keep its own pointer until
+            // metadata shows up or is known to be missing
+            let undecided = state.strategy.mode() == CursorMode::Metadata
+                && !state.seen_meta
+                && !painted_by_compositor;
+            if !state.hidden && !undecided {
+                state.hidden = true;
+                state.shape_sent = false;
+                state.position_sent = None;
+                updates.push(DisplayUpdate::HidePointer);
+            }
+            return updates;
+        }
+
+        if !state.shape_sent {
+            if let Some(shape) = state.strategy.shape() {
+                updates.push(if state.color_pointers {
+                    DisplayUpdate::ColorPointer(color_pointer(shape))
+                } else {
+                    DisplayUpdate::RGBAPointer(RGBAPointer {
+                        width: shape.width as u16,
+                        height: shape.height as u16,
+                        hot_x: shape.hotspot_x as u16,
+                        hot_y: shape.hotspot_y as u16,
+                        data: shape.data.clone(),
+                    })
+                });
+            }
+            state.shape_sent = true;
+            state.hidden = false;
+        }
+        if let Some((x, y)) = state.position {
+            let position = (x.min(u16::MAX as u32) as u16, y.min(u16::MAX as u32) as u16);
+            if state.position_sent != Some(position) {
+                state.position_sent = Some(position);
+                updates.push(DisplayUpdate::PointerPosition(PointerPositionAttribute {
+                    x: position.0,
+                    y: position.1,
+                }));
+            }
+        }
+        updates
+    }
+
+    /// Composite the cursor into a BGRA frame (painted and predictive modes)
+    pub fn paint(&self, data: &mut [u8], width: u32, height: u32) {
+        let mut state = self.state();
+        if !state.strategy.needs_compositing()
+            || state.decoupled
+            || state.cursor_in_frames
+            || state.position.is_none()
+        {
+            return;
+        }
+        let (x, y) = state.strategy.render_position();
+        let Some(shape) = state.strategy.shape() else {
+            return;
+        };
+        blend_rgba(
+            data,
+            (width, height),
+            shape,
+            x - shape.hotspot_x as i32,
+            y - shape.hotspot_y as i32,
+        );
+    }
+}
+
+/// 24-bit color pointer with an AND mask for an RGBA cursor
+///
+/// Pixels below [`COLOR_POINTER_ALPHA_THRESHOLD`] become transparent, the
+/// others opaque. Both masks are stored bottom-up with rows padded to two
+/// bytes, as in a DIB.
+fn color_pointer(shape: &CursorShape) -> ColorPointer {
+    let width = shape.width.min(COLOR_POINTER_MAX_SIZE);
+    let height = shape.height.min(COLOR_POINTER_MAX_SIZE);
+    let xor_stride = (width as usize * 3).next_multiple_of(2);
+    let and_stride = (width as usize).div_ceil(8).next_multiple_of(2);
+    let mut xor_mask = vec![0u8; xor_stride * height as usize];
+    let mut and_mask = vec![0u8; and_stride * height as usize];
+
+    for y in 0..height as usize {
+        let row = height as usize - 1 - y;
+        for x in 0..width as usize {
+            let src = (y * shape.width as usize + x) * 4;
+            match shape.data.get(src..src + 4) {
+                Some(&[r, g, b, a]) if a >= COLOR_POINTER_ALPHA_THRESHOLD => {
+                    let dst = row * xor_stride + x * 3;
+                    xor_mask[dst..dst + 3].copy_from_slice(&[b, g, r]);
+                }
+                _ => and_mask[row * and_stride + x / 8] |= 0x80 >> (x % 8),
+            }
+        }
+    }
+
+    ColorPointer {
+        width: width as u16,
+        height: height as u16,
+        hot_x: shape.hotspot_x.min(width.saturating_sub(1)) as u16,
+        hot_y: shape.hotspot_y.min(height.saturating_sub(1)) as u16,
+        and_mask,
+        xor_mask,
+    }
+}
+
+/// Alpha-blend an RGBA cursor onto a BGRA frame with its top-left at (x, y)
+fn blend_rgba(data: &mut [u8], (width, height): (u32, u32), shape: &CursorShape, x: i32, y: i32) {
+    if data.len() < (width * height * 4) as usize
+        || shape.data.len() < (shape.width * shape.height * 4) as usize
+    {
+        return;
+    }
+    for row in 0..shape.height as i32 {
+        let fy = y + row;
+        if fy < 0 || fy >= height as i32 {
+            continue;
+        }
+        for col in 0..shape.width as i32 {
+            let fx = x + col;
+            if fx < 0 || fx >= width as i32 {
+                continue;
+            }
+            let s = ((row as u32 * shape.width + col as u32) * 4) as usize;
+            let alpha = shape.data[s + 3] as u32;
+            if alpha == 0 {
+                continue;
+            }
+            let d = ((fy as u32 * width + fx as u32) * 4) as usize;
+            // RGBA source onto BGRA destination
+            for (dst, src) in [(0, 2), (1, 1), (2, 0)] {
+                let blended = (shape.data[s + src] as u32 * alpha
+                    + data[d + dst] as u32 * (255 - alpha))
+                    / 255;
+                data[d + dst] = blended as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mode: &str) -> CursorConfig {
+        CursorConfig {
+            mode: mode.to_string(),
+            auto_mode: false,
+            ..CursorConfig::default()
+        }
+    }
+
+    fn arrow() -> CursorShape {
+        // 2x1: opaque red, transparent
+        CursorShape {
+            width: 2,
+            height: 1,
+            hotspot_x: 0,
+            hotspot_y: 0,
+            data: vec![255, 0, 0, 255, 0, 0, 0, 0],
+        }
+    }
+
+    fn meta(position: (i32, i32), shape: Option<CursorShape>) -> Option<CursorMeta> {
+        Some(CursorMeta { position, shape })
+    }
+
+    fn identity(x: i32, y: i32) -> Option<(u32, u32)> {
+        Some((x as u32, y as u32))
+    }
+
+    #[test]
+    fn test_metadata_sends_shape_then_moves() {
+        let channel = CursorChannel::from_config(&config("metadata"));
+
+        let updates = channel.update(meta((5, 6), Some(arrow())), identity, 0.0);
+        assert!(matches!(
+            updates.as_slice(),
+            [
+                DisplayUpdate::RGBAPointer(_),
+                DisplayUpdate::PointerPosition(_)
+            ]
+        ));
+
+        // Unchanged shape: position only, and nothing when nothing moved
+        let updates = channel.update(meta((7, 6), Some(arrow())), identity, 0.0);
+        assert!(matches!(
+            updates.as_slice(),
+            [DisplayUpdate::PointerPosition(_)]
+        ));
+        assert!(channel.update(None, identity, 0.0).is_empty());
+
+        // A new client gets the shape again
+        channel.reset();
+        assert_eq!(channel.update(None, identity, 0.0).len(), 2);
+    }
+
+    #[test]
+    fn test_missing_metadata_falls_back_to_painted() {
+        let channel = CursorChannel::from_config(&config("metadata"));
+        for _ in 1..PAINTED_FALLBACK_FRAMES {
+            assert!(channel.update(None, identity, 0.0).is_empty());
+        }
+        let updates = channel.update(None, identity, 0.0);
+        assert!(matches!(updates.as_slice(), [DisplayUpdate::HidePointer]));
+        assert!(channel.update(None, identity, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_application_overrides() {
+        let channel = CursorChannel::from_config(&CursorConfig {
+            app_overrides: vec![CursorAppOverride {
+                app_id: "Steam_App_*".to_string(),
+                mode: "painted".to_string(),
+            }],
+            ..config("metadata")
+        });
+
+        channel.set_focused_app(Some("steam_app_570"));
+        assert_eq!(channel.mode(), CursorMode::Painted);
+        channel.set_focused_app(Some("foot"));
+        assert_eq!(channel.mode(), CursorMode::Metadata);
+        channel.set_focused_app(Some("steam_app_570"));
+        channel.set_focused_app(None);
+        assert_eq!(channel.mode(), CursorMode::Metadata);
+    }
+
+    #[test]
+    fn test_color_pointers() {
+        let channel = CursorChannel::from_config(&CursorConfig {
+            color_pointers: true,
+            ..config("metadata")
+        });
+        let updates = channel.update(meta((0, 0), Some(arrow())), identity, 0.0);
+        let [DisplayUpdate::ColorPointer(pointer), _] = updates.as_slice() else {
+            panic!("expected a color pointer");
+        };
+        // Red as BGR, rows padded to two bytes; the transparent pixel is
+        // set in the AND mask
+        assert_eq!(pointer.xor_mask, [0, 0, 255, 0, 0, 0]);
+        assert_eq!(pointer.and_mask, [0x40, 0]);
+    }
+
+    #[test]
+    fn test_painted_mode_composites() {
+        let channel = CursorChannel::from_config(&config("painted"));
+        let updates = channel.update(meta((1, 0), Some(arrow())), identity, 0.0);
+        assert!(matches!(updates.as_slice(), [DisplayUpdate::HidePointer]));
+
+        let mut frame = vec![0u8; 3 * 4];
+        channel.paint(&mut frame, 3, 1);
+        // Opaque red lands at x=1 as BGRA; the transparent pixel leaves x=2
+        assert_eq!(&frame[4..8], &[0, 0, 255, 0]);
+        assert_eq!(&frame[8..12], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_cursor_in_frames_hides_client_pointer() {
+        for mode in ["metadata", "painted"] {
+            let channel = CursorChannel::from_config(&config(mode)).with_cursor_in_frames(true);
+            let updates = channel.update(meta((1, 0), Some(arrow())), identity, 0.0);
+            assert!(matches!(updates.as_slice(), [DisplayUpdate::HidePointer]));
+
+            // Nothing painted over the compositor's cursor
+            let mut frame = vec![0u8; 3 * 4];
+            channel.paint(&mut frame, 3, 1);
+            assert!(frame.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_low_frame_rate_decouples_painted_cursor() {
+        let channel = CursorChannel::from_config(&CursorConfig {
+            decouple_below_fps: 15,
+            ..config("painted")
+        });
+        channel.set_video_fps(30);
+        channel.update(meta((1, 0), Some(arrow())), identity, 0.0);
+
+        // At 5 FPS the client draws the pointer and frames stay clean
+        channel.set_video_fps(5);
+        let updates = channel.update(meta((2, 0), None), identity, 0.0);
+        assert!(matches!(
+            updates.as_slice(),
+            [
+                DisplayUpdate::RGBAPointer(_),
+                DisplayUpdate::PointerPosition(_)
+            ]
+        ));
+        let mut frame = vec![0u8; 3 * 4];
+        channel.paint(&mut frame, 3, 1);
+        assert!(frame.iter().all(|&b| b == 0));
+
+        // Painting resumes only clear of the threshold
+        channel.set_video_fps(15);
+        assert!(channel.update(meta((2, 0), None), identity, 0.0).is_empty());
+        channel.set_video_fps(30);
+        let updates = channel.update(meta((2, 0), None), identity, 0.0);
+        assert!(matches!(updates.as_slice(), [DisplayUpdate::HidePointer]));
+    }
+}
@@ -0,0 +1,173 @@
+//! Direct Manipulation
+//!
+//! The hidden cursor mode is for tablet and phone clients: there is no
+//! pointer to show, and a contact acts where it lands. Their RDP clients
+//! turn touch and pen contacts into mouse events (the server has no
+//! MS-RDPEI touch channel). A tap becomes a move, press and release at the
+//! contact point. A long press becomes a right click, and a two-finger
+//! swipe becomes wheel events.
+//!
+//! A fingertip wobbles a few pixels between press and release, which turns
+//! taps into tiny drags that select text or nudge windows. While a contact
+//! stays within its device type's slop radius of where it landed, its
+//! movement is held back. Once it leaves the radius it is a drag, and its
+//! motion passes through. A pen tip is precise, so it gets a smaller radius
+//! than a finger.
+//!
+//! Touch and pen handling are toggled separately through
+//! [`DirectManipulation::set_enabled`].
+
+use std::sync::{Arc, Mutex};
+
+use ironrdp_server::MouseEvent;
+
+/// Input device type of a client contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchDevice {
+    /// Finger on a touch screen
+    Touch,
+    /// Pen or stylus
+    Pen,
+}
+
+impl TouchDevice {
+    /// Movement (pixels) a tap of the device may wobble and stay a tap
+    pub fn slop(self) -> u32 {
+        match self {
+            Self::Touch => 12,
+            Self::Pen => 4,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    touch: bool,
+    pen: bool,
+    /// Last pointer position from the client
+    position: Option<(u16, u16)>,
+    /// Where the current contact landed, while it is still a tap
+    landed: Option<(u16, u16)>,
+}
+
+/// Touch and pen contacts of a client acting where they land
+///
+/// Cheap to clone; clones share the state, so the session code can toggle
+/// device types while input is flowing.
+#[derive(Debug, Clone, Default)]
+pub struct DirectManipulation {
+    inner: Arc<Mutex<State>>,
+}
+
+impl DirectManipulation {
+    /// Direct manipulation for touch and/or pen contacts
+    pub fn new(touch: bool, pen: bool) -> Self {
+        let manipulation = Self::default();
+        manipulation.set_enabled(TouchDevice::Touch, touch);
+        manipulation.set_enabled(TouchDevice::Pen, pen);
+        manipulation
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Turn handling of a device type's contacts on or off
+    pub fn set_enabled(&self, device: TouchDevice, enabled: bool) {
+        let mut state = self.state();
+        match device {
+            TouchDevice::Touch => state.touch = enabled,
+            TouchDevice::Pen => state.pen = enabled,
+        }
+        if !state.touch && !state.pen {
+            state.landed = None;
+        }
+    }
+
+    /// Whether a device type's contacts are handled
+    pub fn is_enabled(&self, device: TouchDevice) -> bool {
+        let state = self.state();
+        match device {
+            TouchDevice::Touch => state.touch,
+            TouchDevice::Pen => state.pen,
+        }
+    }
+
+    /// Pass `event` on, or None to hold back a tap's wobble
+    pub fn filter(&self, event: MouseEvent) -> Option<MouseEvent> {
+        let mut state = self.state();
+        // Contacts arrive as mouse events of either device type, so the
+        // widest enabled radius applies
+        let Some(slop) = [
+            (state.touch, TouchDevice::Touch),
+            (state.pen, TouchDevice::Pen),
+        ]
+        .into_iter()
+        .filter(|&(enabled, _)| enabled)
+        .map(|(_, device)| device.slop())
+        .max() else {
+            return Some(event);
+        };
+
+        match event {
+            MouseEvent::Move { x, y } => {
+                state.position = Some((x, y));
+                if let Some((lx, ly)) = state.landed {
+                    let (dx, dy) = (u32::from(x.abs_diff(lx)), u32::from(y.abs_diff(ly)));
+                    if dx * dx + dy * dy <= slop * slop {
+                        return None;
+                    }
+                    state.landed = None;
+                }
+            }
+            MouseEvent::LeftPressed => state.landed = state.position,
+            MouseEvent::LeftReleased => state.landed = None,
+            _ => {}
+        }
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(x: u16, y: u16) -> MouseEvent {
+        MouseEvent::Move { x, y }
+    }
+
+    #[test]
+    fn test_tap_wobble_held_back() {
+        let touch = DirectManipulation::new(true, false);
+        assert!(touch.filter(moved(100, 100)).is_some());
+        assert!(touch.filter(MouseEvent::LeftPressed).is_some());
+
+        // Wobble within the radius stays a tap
+        assert!(touch.filter(moved(105, 103)).is_none());
+        assert!(touch.filter(MouseEvent::LeftReleased).is_some());
+
+        // Leaving the radius makes it a drag
+        touch.filter(MouseEvent::LeftPressed);
+        assert!(touch.filter(moved(120, 100)).is_some());
+        assert!(touch.filter(moved(101, 100)).is_some());
+        touch.filter(MouseEvent::LeftReleased);
+
+        // Hover is never held back
+        assert!(touch.filter(moved(102, 100)).is_some());
+    }
+
+    #[test]
+    fn test_device_types_toggle() {
+        let manipulation = DirectManipulation::new(false, true);
+        manipulation.filter(moved(10, 10));
+        manipulation.filter(MouseEvent::LeftPressed);
+        // A pen's radius is tighter than a finger's
+        assert!(manipulation.filter(moved(16, 10)).is_some());
+
+        // Nothing is held back with both device types off
+        manipulation.set_enabled(TouchDevice::Pen, false);
+        assert!(!manipulation.is_enabled(TouchDevice::Pen));
+        manipulation.filter(MouseEvent::LeftPressed);
+        assert!(manipulation.filter(moved(17, 10)).is_some());
+    }
+}
@@ -40,7 +40,8 @@
 //!
 //! # Frame Processing Pipeline
 //!
-//! 1. **Capture:** PipeWire thread extracts frame from buffer
+//! 1. **Capture:** PipeWire thread extracts frame from buffer (or the
+//!    wlr-screencopy thread copies it from the compositor, see [`VideoSource`])
 //! 2. **Transfer:** Frame sent via channel (zero-copy Arc)
 //! 3. **Convert:** BitmapConverter transforms to RDP format
 //! 4. **Map:** Pixel formats mapped to IronRDP types
@@ -57,6 +58,19 @@
 //! - **Rgb16** → IronRDP::XRgb32 (upsample to 32-bit)
 //! - **Rgb15** → IronRDP::XRgb32 (upsample to 32-bit)
 //!
+//! # Multiple Streams
+//!
+//! Frames of all streams of a pipeline share one channel. Each monitor has a
+//! pipeline of its own (see `monitor_pipeline`): its latest frame waits in a
+//! slot, paced at the monitor's frame rate, and is compared against the
+//! monitor's previous frame for damage. A busy monitor can't starve the
+//! others, and the primary monitor goes first when several frames are due.
+//! Encoding and sending share one encoder and EGFX surface. The pipeline
+//! does not hold a frame back to wait for its neighbours. Temporal
+//! alignment of stitched monitors (compositing frames captured within a
+//! tolerance window) belongs to lamco-pipewire's `MultiStreamCoordinator`,
+//! which owns the per-stream timestamps.
+//!
 //! # Performance Characteristics
 //!
 //! - **Frame latency:** <3ms (PipeWire → IronRDP)
@@ -70,23 +84,293 @@ use ironrdp_server::{
     BitmapUpdate as IronBitmapUpdate, DesktopSize, DisplayUpdate, GfxServerHandle,
     PixelFormat as IronPixelFormat, RdpServerDisplay, RdpServerDisplayUpdates, ServerEvent,
 };
+use std::collections::HashMap;
 use std::num::{NonZeroU16, NonZeroUsize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc, Mutex, RwLock};
-use tracing::{debug, error, info, trace, warn};
-
-use crate::damage::{DamageConfig, DamageDetector, DamageRegion};
-use crate::egfx::{Avc420Encoder, Avc444Encoder, EncoderConfig};
-use crate::performance::{AdaptiveFpsController, EncodingDecision, LatencyGovernor, LatencyMode};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Mutex, RwLock};
+use tracing::{debug, error, info, trace, warn, Instrument};
+
+use crate::clipboard::ClipboardPolicy;
+use crate::config::Config;
+use crate::damage::{DamageConfig, DamageRegion};
+use crate::egfx::{
+    Avc420Encoder, Avc444Encoder, DecodeGovernor, EncoderConfig, FrameAckLatency, OperatingPoint,
+};
+use crate::multimon::{stream_fps, SharedFollowFocus};
+use crate::performance::{
+    current_thread_id, process_thread_ids, threads_started_since, AdaptiveFpsController,
+    BufferPool, EncodingDecision, LatencyGovernor, LatencyMode, ThreadScheduling,
+};
 use crate::pipewire::{PipeWireThreadCommand, PipeWireThreadManager, VideoFrame};
 use crate::portal::StreamInfo;
-use crate::server::egfx_sender::EgfxFrameSender;
+use crate::server::banner::LoginBanner;
+use crate::server::capture_watchdog::{dim_frame, CaptureWatchdog, WatchdogAction};
+use crate::server::cursor_channel::{CursorChannel, CursorMeta};
+use crate::server::egfx_sender::{is_small_update, EgfxFrameSender};
 use crate::server::event_multiplexer::GraphicsFrame;
+use crate::server::event_stream::SessionEvents;
+use crate::server::frame_dump::{DumpedFrame, FrameDump, FrameDumpTap};
+use crate::server::frame_scaler::SharedFrameScaler;
 use crate::server::gfx_factory::HandlerState;
-use crate::services::{ServiceId, ServiceRegistry};
+use crate::server::idle_stop::IdleStop;
+use crate::server::link_estimate::{BitrateController, LinkEstimate};
+use crate::server::monitor_pipeline::MonitorPipelines;
+use crate::server::node_watch::{NodeEvent, NodeWatch};
+use crate::server::quality_overlay::QualityOverlay;
+use crate::server::resource_limits::{thread_cpu_time, SessionMeter};
+use crate::server::session_message::SessionMessage;
+use crate::server::sharing::SharingControl;
+use crate::services::{ServiceId, ServiceLevel, ServiceRegistry};
+#[cfg(feature = "wayland")]
+use crate::session::strategies::ScreencopyCapture;
+use crate::session::SessionLiveness;
+use crate::utils::{chaos, spawn_in_current_span, wakeup_audit, CrashState};
 use crate::video::{BitmapConverter, BitmapUpdate, RdpPixelFormat};
 
+/// Where a pipeline's frames come from
+#[derive(Debug, Clone, Copy)]
+pub enum VideoSource {
+    /// PipeWire remote file descriptor, consumed by the pipeline's PipeWire thread
+    PipeWire(i32),
+    /// wlr-screencopy capture of the streams' outputs (wlroots without portal)
+    Screencopy,
+}
+
+impl VideoSource {
+    /// Source for another pipeline on the same capture session
+    pub fn duplicate(&self) -> Result<Self> {
+        match self {
+            Self::PipeWire(fd) => {
+                Ok(Self::PipeWire(nix::unistd::dup(*fd).map_err(|e| {
+                    anyhow::anyhow!("Failed to duplicate PipeWire FD: {}", e)
+                })?))
+            }
+            Self::Screencopy => Ok(Self::Screencopy),
+        }
+    }
+}
+
+/// How long a stream waits for its removed node to be replaced before the
+/// capture session counts as lost
+const NODE_RETURN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Highest frame rate asked of the compositor
+const MAX_STREAM_FRAMERATE: u32 = 144;
+
+/// Delay before polling PipeWire again right after a frame
+const FRAME_POLL_MIN: Duration = Duration::from_millis(5);
+
+/// Longest delay between polls while no frames arrive
+///
+/// The first frame after a quiet spell waits at most this long, well below
+/// what a user notices, and an idle desktop wakes 50 times a second instead
+/// of 200.
+const FRAME_POLL_MAX: Duration = Duration::from_millis(20);
+
+/// Most frames taken off the channel per poll, so one flooding stream can't
+/// hold the loop
+const FRAME_DRAIN_MAX: usize = 64;
+
+/// How often per-monitor stats are logged
+const MONITOR_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Frame producer of a pipeline
+#[derive(Clone)]
+enum FrameSource {
+    PipeWire(Arc<Mutex<PipeWireThreadManager>>),
+    #[cfg(feature = "wayland")]
+    Screencopy(Arc<ScreencopyCapture>),
+}
+
+impl FrameSource {
+    /// Start capturing `stream_info` from `video`
+    ///
+    /// Dropping the source stops capture.
+    async fn start(
+        video: VideoSource,
+        stream_info: &[StreamInfo],
+        config: &Config,
+        service_registry: &ServiceRegistry,
+    ) -> Result<Self> {
+        let source = match video {
+            VideoSource::PipeWire(pipewire_fd) => {
+                // Create PipeWire thread manager (handles all PipeWire operations)
+                let threads_before = process_thread_ids();
+                let pipewire_thread = Arc::new(Mutex::new(
+                    PipeWireThreadManager::new(pipewire_fd)
+                        .map_err(|e| anyhow::anyhow!("Failed to create PipeWire thread: {}", e))?,
+                ));
+
+                // The manager's thread is not ours to configure before it
+                // starts, so find it among the threads it just started
+                let scheduling = ThreadScheduling::pipewire(&config.performance.scheduling);
+                if !scheduling.is_default() {
+                    for tid in threads_started_since(&threads_before) {
+                        scheduling.apply(tid, "PipeWire").await;
+                    }
+                }
+
+                // Offer DMA-BUF only where the compositor handles it, and
+                // no more frames than the pipeline sends
+                let params =
+                    StreamParams::new(service_registry, config.performance.adaptive_fps.max_fps);
+                if !params.use_dmabuf {
+                    info!("📋 Requesting MemFd buffers (DMA-BUF unavailable on this compositor)");
+                }
+                info!(
+                    "Negotiating PipeWire streams at up to {} fps with {} buffers",
+                    params.framerate, params.buffer_count
+                );
+
+                // Create streams on the PipeWire thread
+                for (idx, stream) in stream_info.iter().enumerate() {
+                    create_pipewire_stream(
+                        &pipewire_thread,
+                        idx,
+                        stream.node_id,
+                        stream.node_id,
+                        stream.size,
+                        params.for_stream(stream, config),
+                    )
+                    .await?;
+                }
+
+                FrameSource::PipeWire(pipewire_thread)
+            }
+            #[cfg(feature = "wayland")]
+            VideoSource::Screencopy => {
+                let outputs: Vec<u32> = stream_info.iter().map(|s| s.node_id).collect();
+                FrameSource::Screencopy(Arc::new(ScreencopyCapture::start(
+                    &outputs,
+                    config.video.target_fps,
+                )?))
+            }
+            #[cfg(not(feature = "wayland"))]
+            VideoSource::Screencopy => {
+                anyhow::bail!("wlr-screencopy capture requires the \"wayland\" feature")
+            }
+        };
+        Ok(source)
+    }
+
+    /// Next captured frame, if one is waiting (non-blocking)
+    async fn try_recv_frame(&self) -> Option<VideoFrame> {
+        match self {
+            Self::PipeWire(thread) => thread.lock().await.try_recv_frame(),
+            #[cfg(feature = "wayland")]
+            Self::Screencopy(capture) => capture.try_recv_frame(),
+        }
+    }
+
+    /// Re-create a PipeWire stream on the node that replaced its own
+    async fn recreate_stream(
+        &self,
+        idx: usize,
+        stream_id: u32,
+        node_id: u32,
+        size: (u32, u32),
+        params: StreamParams,
+    ) -> Result<()> {
+        match self {
+            Self::PipeWire(thread) => {
+                create_pipewire_stream(thread, idx, stream_id, node_id, size, params).await
+            }
+            #[cfg(feature = "wayland")]
+            Self::Screencopy(_) => anyhow::bail!("Screencopy pipelines have no PipeWire streams"),
+        }
+    }
+}
+
+/// Format parameters a pipeline negotiates for its PipeWire streams
+#[derive(Debug, Clone, Copy)]
+struct StreamParams {
+    /// Offer DMA-BUF buffers (not where the DmaBufZeroCopy service is
+    /// unavailable, e.g. PoorDmaBufSupport or MemFd-preferring profiles)
+    use_dmabuf: bool,
+    /// Maximum frame rate asked of the compositor
+    framerate: u32,
+    /// Buffers in the stream's pool
+    buffer_count: u32,
+}
+
+impl StreamParams {
+    /// Parameters for a pipeline sending at most `max_fps` frames per second
+    ///
+    /// Adaptive FPS and the fixed-rate regulator both stay at or below
+    /// `performance.adaptive_fps.max_fps`, so frames the compositor renders
+    /// beyond it would only be dropped.
+    fn new(service_registry: &ServiceRegistry, max_fps: u32) -> Self {
+        let framerate = max_fps.clamp(1, MAX_STREAM_FRAMERATE);
+        Self {
+            use_dmabuf: service_registry.service_level(ServiceId::DmaBufZeroCopy)
+                >= ServiceLevel::BestEffort,
+            framerate,
+            buffer_count: service_registry.recommended_buffer_count(framerate),
+        }
+    }
+
+    /// The same parameters at the capture rate configured for `stream`
+    /// (`multimon.monitors`), never above the pipeline's
+    fn for_stream(self, stream: &StreamInfo, config: &crate::config::Config) -> Self {
+        Self {
+            framerate: stream_fps(stream, &config.multimon.monitors, self.framerate),
+            ..self
+        }
+    }
+}
+
+/// Create a stream for `node_id` on a pipeline's PipeWire thread
+///
+/// Frames of the stream are tagged with `stream_id`; a stream re-created
+/// on a replacement node keeps the id of the one it replaces.
+async fn create_pipewire_stream(
+    pipewire_thread: &Mutex<PipeWireThreadManager>,
+    idx: usize,
+    stream_id: u32,
+    node_id: u32,
+    size: (u32, u32),
+    params: StreamParams,
+) -> Result<()> {
+    let config = lamco_pipewire::StreamConfig {
+        name: format!("monitor-{}", idx),
+        width: size.0,
+        height: size.1,
+        framerate: params.framerate,
+        use_dmabuf: params.use_dmabuf,
+        buffer_count: params.buffer_count,
+        preferred_format: Some(lamco_pipewire::PixelFormat::BGRx),
+    };
+
+    // Send create stream command to PipeWire thread
+    let (response_tx, response_rx) = std::sync::mpsc::sync_channel(1);
+    let cmd = PipeWireThreadCommand::CreateStream {
+        stream_id,
+        node_id,
+        config,
+        response_tx,
+    };
+
+    pipewire_thread
+        .lock()
+        .await
+        .send_command(cmd)
+        .map_err(|e| anyhow::anyhow!("Failed to send create stream command: {}", e))?;
+
+    // Wait for response
+    response_rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .map_err(|_| anyhow::anyhow!("Timeout creating stream"))?
+        .map_err(|e| anyhow::anyhow!("Stream creation failed: {}", e))?;
+
+    debug!(
+        "Stream {} created successfully on node {}",
+        stream_id, node_id
+    );
+    Ok(())
+}
+
 /// Video encoder abstraction for codec-agnostic frame encoding
 ///
 /// Supports both AVC420 (standard H.264 4:2:0) and AVC444 (premium H.264 4:4:4).
@@ -110,6 +394,22 @@ enum EncodedVideoFrame {
     },
 }
 
+impl EncodedVideoFrame {
+    /// Copies of the bitstreams by file suffix, for frame dumps
+    fn streams(&self) -> Vec<(&'static str, Vec<u8>)> {
+        match self {
+            EncodedVideoFrame::Single(data) => vec![("h264", data.clone())],
+            EncodedVideoFrame::Dual { main, aux } => {
+                let mut streams = vec![("main.h264", main.clone())];
+                if let Some(aux) = aux {
+                    streams.push(("aux.h264", aux.clone()));
+                }
+                streams
+            }
+        }
+    }
+}
+
 impl VideoEncoder {
     /// Encode a BGRA frame to H.264
     ///
@@ -121,6 +421,11 @@ impl VideoEncoder {
         height: u32,
         timestamp_ms: u64,
     ) -> Result<Option<EncodedVideoFrame>, crate::egfx::EncoderError> {
+        if chaos::fail_encode() {
+            return Err(crate::egfx::EncoderError::EncodeFailed(
+                "injected fault".to_string(),
+            ));
+        }
         match self {
             VideoEncoder::Avc420(encoder) => encoder
                 .encode_bgra(bgra_data, width, height, timestamp_ms)
@@ -155,6 +460,61 @@ impl VideoEncoder {
         }
     }
 
+    /// Create a fresh encoder of the same codec with new settings
+    ///
+    /// Used to change the bitrate mid-session; the new encoder starts with
+    /// an IDR frame, so the client resynchronizes without renegotiation.
+    fn rebuild(
+        &self,
+        config: EncoderConfig,
+        egfx: &crate::config::types::EgfxConfig,
+        governor: &LatencyGovernor,
+    ) -> Result<Self, crate::egfx::EncoderError> {
+        match self {
+            VideoEncoder::Avc420(_) => Avc420Encoder::new(config).map(VideoEncoder::Avc420),
+            VideoEncoder::Avc444(_) => {
+                let mut encoder = Avc444Encoder::new(config)?;
+                configure_avc444(&mut encoder, egfx, governor);
+                Ok(VideoEncoder::Avc444(encoder))
+            }
+        }
+    }
+
+    /// Switch to a new bitrate by rebuilding the encoder
+    ///
+    /// `config` is updated to the new bitrate; on failure the current
+    /// encoder and bitrate are kept.
+    fn apply_bitrate(
+        &mut self,
+        config: &mut EncoderConfig,
+        bitrate_kbps: u32,
+        egfx: &crate::config::types::EgfxConfig,
+        governor: &LatencyGovernor,
+    ) {
+        if config.bitrate_kbps == bitrate_kbps {
+            return;
+        }
+        let rebuilt_config = EncoderConfig {
+            bitrate_kbps,
+            ..config.clone()
+        };
+        match self.rebuild(rebuilt_config.clone(), egfx, governor) {
+            Ok(rebuilt) => {
+                *self = rebuilt;
+                *config = rebuilt_config;
+                info!(
+                    "🔄 {} encoder rebuilt at {}kbps",
+                    self.codec_name(),
+                    bitrate_kbps
+                );
+            }
+            Err(e) => warn!(
+                "Failed to apply bitrate {}kbps, keeping current encoder: {:?}",
+                bitrate_kbps, e
+            ),
+        }
+    }
+
     /// Check if periodic IDR is due (non-consuming)
     /// Used to bypass damage detection and send full frame when IDR fires
     fn is_periodic_idr_due(&self) -> bool {
@@ -165,56 +525,82 @@ impl VideoEncoder {
     }
 }
 
-/// Frame rate regulator using token bucket algorithm
+/// Apply the AVC444 tuning options from the EGFX configuration
 ///
-/// Ensures smooth video delivery by limiting frame rate to target FPS.
-/// Uses token bucket to allow brief bursts while maintaining average rate.
-struct FrameRateRegulator {
-    /// Target frames per second
-    target_fps: u32,
-    /// Interval between frames
-    frame_interval: std::time::Duration,
-    /// Last frame send time
-    last_frame_time: Instant,
-    /// Token budget for burst handling (allows brief spikes)
-    token_budget: f32,
-    /// Maximum tokens that can accumulate
-    max_tokens: f32,
+/// The periodic IDR interval is limited by the latency mode.
+fn configure_avc444(
+    encoder: &mut Avc444Encoder,
+    egfx: &crate::config::types::EgfxConfig,
+    governor: &LatencyGovernor,
+) {
+    // Wire aux omission config from EgfxConfig
+    encoder.configure_aux_omission(
+        egfx.avc444_enable_aux_omission,
+        egfx.avc444_max_aux_interval,
+        egfx.avc444_aux_change_threshold,
+        egfx.avc444_force_aux_idr_on_return,
+    );
+    // Wire periodic IDR config for artifact recovery
+    encoder.configure_periodic_idr(governor.cap_idr_interval(egfx.periodic_idr_interval));
 }
 
-impl FrameRateRegulator {
-    fn new(target_fps: u32) -> Self {
-        Self {
-            target_fps,
-            frame_interval: std::time::Duration::from_micros(1_000_000 / target_fps as u64),
-            last_frame_time: Instant::now(),
-            token_budget: 1.0,
-            max_tokens: 2.0, // Allow 2-frame burst
+/// Encoder state published for crash reports
+fn encoder_crash_state(
+    encoder: Option<&VideoEncoder>,
+    config: Option<&EncoderConfig>,
+    frames_sent: u64,
+    egfx_frames_sent: u64,
+    frames_dropped: u64,
+) -> serde_json::Value {
+    serde_json::json!({
+        "codec": encoder.map_or("none", VideoEncoder::codec_name),
+        "width": config.and_then(|c| c.width),
+        "height": config.and_then(|c| c.height),
+        "bitrate_kbps": config.map(|c| c.bitrate_kbps),
+        "frames_sent": frames_sent,
+        "egfx_frames_sent": egfx_frames_sent,
+        "frames_dropped": frames_dropped,
+    })
+}
+
+/// Latency governor for the `[performance.latency]` settings
+fn latency_governor(config: &Config) -> LatencyGovernor {
+    let latency = &config.performance.latency;
+    let mode: LatencyMode = latency.mode.parse().unwrap_or_default();
+    let governor = LatencyGovernor::new(mode);
+    match mode {
+        LatencyMode::Interactive => {
+            governor.with_frame_delay(latency.interactive_max_delay_ms, 0.0)
         }
+        LatencyMode::Balanced => governor.with_frame_delay(
+            latency.balanced_max_delay_ms,
+            latency.balanced_damage_threshold,
+        ),
+        LatencyMode::Quality => governor.with_frame_delay(
+            latency.quality_max_delay_ms,
+            latency.quality_damage_threshold,
+        ),
     }
+}
 
-    /// Check if a frame should be sent based on rate limiting
-    /// Returns true if frame should be sent, false if it should be dropped
-    fn should_send_frame(&mut self) -> bool {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_frame_time);
-
-        // CRITICAL: Update last_frame_time on EVERY call, not just when sending
-        // Otherwise dropped frames cause time to accumulate and earn too many tokens
-        self.last_frame_time = now;
-
-        // Add tokens based on elapsed time
-        let tokens_earned = elapsed.as_secs_f32() * self.target_fps as f32;
-        self.token_budget = (self.token_budget + tokens_earned).min(self.max_tokens);
-
-        // Check if we have budget to send this frame
-        if self.token_budget >= 1.0 {
-            self.token_budget -= 1.0;
-            true
-        } else {
-            // Drop frame - too fast
-            false
-        }
+/// Adaptive FPS settings for the pipeline
+///
+/// Adaptive FPS needs the damage tracking service as its activity signal, so
+/// it stays disabled without it regardless of configuration.
+fn adaptive_fps_config(
+    config: &Config,
+    service_supported: bool,
+) -> crate::performance::AdaptiveFpsConfig {
+    let adaptive_fps = &config.performance.adaptive_fps;
+    crate::performance::AdaptiveFpsConfig {
+        enabled: adaptive_fps.enabled && service_supported,
+        min_fps: adaptive_fps.min_fps,
+        max_fps: adaptive_fps.max_fps,
+        high_activity_threshold: adaptive_fps.high_activity_threshold,
+        medium_activity_threshold: adaptive_fps.medium_activity_threshold,
+        low_activity_threshold: adaptive_fps.low_activity_threshold,
+        max_ack_latency_ms: adaptive_fps.max_ack_latency_ms,
+        ..Default::default()
     }
 }
 
@@ -232,8 +618,8 @@ pub struct LamcoDisplayHandler {
     /// Current desktop size
     size: Arc<RwLock<DesktopSize>>,
 
-    /// PipeWire thread manager
-    pipewire_thread: Arc<Mutex<PipeWireThreadManager>>,
+    /// PipeWire thread or screencopy capture (None = stopped while idle)
+    frame_source: Arc<RwLock<Option<FrameSource>>>,
 
     /// Bitmap converter for RDP format conversion
     bitmap_converter: Arc<Mutex<BitmapConverter>>,
@@ -267,6 +653,68 @@ pub struct LamcoDisplayHandler {
 
     /// Service registry for compositor-aware feature decisions
     service_registry: Arc<ServiceRegistry>,
+
+    /// Follow-focus viewport (None = send the whole desktop)
+    follow_focus: Option<SharedFollowFocus>,
+
+    /// Server-side scaler (None = frames sent at capture size)
+    frame_scaler: Option<SharedFrameScaler>,
+
+    /// Set when the pipeline task should exit (client pipeline torn down)
+    stopped: Arc<AtomicBool>,
+
+    /// Hot-reloaded settings (None = settings fixed at startup)
+    live_config: Option<watch::Receiver<Arc<Config>>>,
+
+    /// Clipboard policy of this pipeline's clipboard manager
+    /// Set after the clipboard manager is built (via set_clipboard_policy)
+    clipboard_policy: Arc<RwLock<Option<ClipboardPolicy>>>,
+
+    /// Resource accounting and throttling for the session served
+    session_meter: SessionMeter,
+
+    /// Login banner shown instead of the desktop until acknowledged
+    login_banner: LoginBanner,
+
+    /// Connection quality badge drawn into frames (None = disabled)
+    quality_overlay: Option<QualityOverlay>,
+
+    /// Client's EGFX frame acknowledgement latency, timed from the frames
+    /// sent here
+    ack_latency: FrameAckLatency,
+
+    /// Bandwidth estimate of the client's link, fed by the resource
+    /// monitor's TCP samples and the acknowledgements timed here
+    link_estimate: LinkEstimate,
+
+    /// Frames are held back while the local user has paused sharing
+    sharing: SharingControl,
+
+    /// Admin message drawn into the primary monitor's frames
+    session_message: SessionMessage,
+
+    /// Client-side cursor from PipeWire cursor metadata (None = cursor left
+    /// to the compositor)
+    cursor_channel: Option<CursorChannel>,
+
+    /// Removal and replacement of the streams' PipeWire nodes (None =
+    /// screencopy, or the PipeWire daemon isn't reachable); taken by the
+    /// pipeline task
+    node_watch: std::sync::Mutex<Option<NodeWatch>>,
+
+    /// Liveness of the capture session, closed when a stream's node is gone
+    /// for good (None = not tied to a capture session)
+    capture_liveness: Option<SessionLiveness>,
+
+    /// Capture stops without clients; restarted from the spare source
+    /// (None = capture runs for the pipeline's lifetime)
+    idle_stop: Option<(IdleStop, VideoSource)>,
+
+    /// Frame dump requests (None = `[frame_dump]` disabled)
+    frame_dump: Option<FrameDump>,
+
+    /// Lifecycle events of the client being served
+    events: SessionEvents,
 }
 
 impl LamcoDisplayHandler {
@@ -276,7 +724,7 @@ impl LamcoDisplayHandler {
     ///
     /// * `initial_width` - Initial desktop width
     /// * `initial_height` - Initial desktop height
-    /// * `pipewire_fd` - PipeWire file descriptor from portal
+    /// * `video` - PipeWire file descriptor from portal, or screencopy
     /// * `stream_info` - Stream information from portal
     /// * `graphics_tx` - Optional graphics queue sender for priority multiplexing
     /// * `gfx_server_handle` - Optional handle to GFX server for EGFX support
@@ -294,7 +742,7 @@ impl LamcoDisplayHandler {
     pub async fn new(
         initial_width: u16,
         initial_height: u16,
-        pipewire_fd: i32,
+        video: VideoSource,
         stream_info: Vec<StreamInfo>,
         graphics_tx: Option<mpsc::Sender<GraphicsFrame>>,
         gfx_server_handle: Option<Arc<RwLock<Option<GfxServerHandle>>>>,
@@ -307,47 +755,19 @@ impl LamcoDisplayHandler {
             height: initial_height,
         }));
 
-        // Create PipeWire thread manager (handles all PipeWire operations)
-        let pipewire_thread = Arc::new(Mutex::new(
-            PipeWireThreadManager::new(pipewire_fd)
-                .map_err(|e| anyhow::anyhow!("Failed to create PipeWire thread: {}", e))?,
-        ));
-
-        // Create streams on the PipeWire thread
-        for (idx, stream) in stream_info.iter().enumerate() {
-            let config = lamco_pipewire::StreamConfig {
-                name: format!("monitor-{}", idx),
-                width: stream.size.0,
-                height: stream.size.1,
-                framerate: 60,
-                use_dmabuf: true,
-                buffer_count: 3,
-                preferred_format: Some(lamco_pipewire::PixelFormat::BGRx),
-            };
-
-            // Send create stream command to PipeWire thread
-            let (response_tx, response_rx) = std::sync::mpsc::sync_channel(1);
-            let cmd = PipeWireThreadCommand::CreateStream {
-                stream_id: stream.node_id,
-                node_id: stream.node_id,
-                config,
-                response_tx,
-            };
-
-            pipewire_thread
-                .lock()
-                .await
-                .send_command(cmd)
-                .map_err(|e| anyhow::anyhow!("Failed to send create stream command: {}", e))?;
-
-            // Wait for response
-            response_rx
-                .recv_timeout(std::time::Duration::from_secs(5))
-                .map_err(|_| anyhow::anyhow!("Timeout creating stream"))?
-                .map_err(|e| anyhow::anyhow!("Stream creation failed: {}", e))?;
+        let frame_source =
+            FrameSource::start(video, &stream_info, &config, &service_registry).await?;
 
-            debug!("Stream {} created successfully", stream.node_id);
-        }
+        // Follow the nodes, so streams can move to replacement nodes
+        let node_watch = match video {
+            VideoSource::PipeWire(_) => {
+                let node_ids: Vec<u32> = stream_info.iter().map(|s| s.node_id).collect();
+                NodeWatch::start(&node_ids)
+                    .map_err(|e| debug!("PipeWire node watch unavailable: {:#}", e))
+                    .ok()
+            }
+            VideoSource::Screencopy => None,
+        };
 
         // Create bitmap converter
         let bitmap_converter = Arc::new(Mutex::new(BitmapConverter::new(
@@ -376,7 +796,7 @@ impl LamcoDisplayHandler {
 
         Ok(Self {
             size,
-            pipewire_thread,
+            frame_source: Arc::new(RwLock::new(Some(frame_source))),
             bitmap_converter,
             update_sender,
             update_receiver,
@@ -387,9 +807,289 @@ impl LamcoDisplayHandler {
             server_event_tx: Arc::new(RwLock::new(None)),
             config,           // Store config for feature flags
             service_registry, // Service-aware feature decisions
+            follow_focus: None,
+            frame_scaler: None,
+            stopped: Arc::new(AtomicBool::new(false)),
+            live_config: None,
+            clipboard_policy: Arc::new(RwLock::new(None)),
+            session_meter: SessionMeter::new(),
+            login_banner: LoginBanner::disabled(),
+            quality_overlay: None,
+            ack_latency: FrameAckLatency::new(),
+            link_estimate: LinkEstimate::new(),
+            sharing: SharingControl::new(),
+            session_message: SessionMessage::new(),
+            cursor_channel: None,
+            node_watch: std::sync::Mutex::new(node_watch),
+            capture_liveness: None,
+            idle_stop: None,
+            frame_dump: None,
+            events: SessionEvents::default(),
         })
     }
 
+    /// Enable follow-focus cropping
+    ///
+    /// When set, every captured frame is cropped to the controller's current
+    /// viewport before damage detection and encoding.
+    pub fn with_follow_focus(mut self, follow_focus: Option<SharedFollowFocus>) -> Self {
+        if follow_focus.is_some() {
+            info!("Follow-focus cropping enabled in display pipeline");
+        }
+        self.follow_focus = follow_focus;
+        self
+    }
+
+    /// Enable server-side scaling
+    ///
+    /// Applied after follow-focus cropping, so the scaler sees the same frame
+    /// size the client would otherwise receive.
+    pub fn with_frame_scaler(mut self, frame_scaler: Option<SharedFrameScaler>) -> Self {
+        if let Some(ref scaler) = frame_scaler {
+            if let Ok(scaler) = scaler.lock() {
+                info!(
+                    "Server-side scaling enabled in display pipeline ({} policy)",
+                    scaler.policy()
+                );
+            }
+        }
+        self.frame_scaler = frame_scaler;
+        self
+    }
+
+    /// Follow hot-reloaded settings
+    ///
+    /// The pipeline applies bitrate, adaptive FPS and clipboard policy changes
+    /// published on `live_config` without interrupting the connection.
+    pub fn with_live_config(mut self, live_config: watch::Receiver<Arc<Config>>) -> Self {
+        self.live_config = Some(live_config);
+        self
+    }
+
+    /// Show a login banner instead of the desktop until it is acknowledged
+    pub fn with_login_banner(mut self, login_banner: LoginBanner) -> Self {
+        self.login_banner = login_banner;
+        self
+    }
+
+    /// Draw a connection quality badge into frames while it is shown
+    pub fn with_quality_overlay(mut self, quality_overlay: Option<QualityOverlay>) -> Self {
+        self.quality_overlay = quality_overlay;
+        self
+    }
+
+    /// Time the client's frame acknowledgements, for adaptive FPS
+    ///
+    /// Pass the tracker of the EGFX factory whose handler receives the
+    /// acknowledgements.
+    pub fn with_ack_latency(mut self, ack_latency: FrameAckLatency) -> Self {
+        self.ack_latency = ack_latency;
+        self
+    }
+
+    /// Drive the client's pointer from PipeWire cursor metadata
+    pub fn with_cursor_channel(mut self, cursor_channel: Option<CursorChannel>) -> Self {
+        self.cursor_channel = cursor_channel;
+        self
+    }
+
+    /// Report lost screencast nodes as a lost capture session
+    ///
+    /// When a stream's node is removed and no replacement appears, the
+    /// liveness is closed, so the server recovers the session per
+    /// `[capture_recovery]` instead of serving a frozen desktop.
+    pub fn with_capture_liveness(mut self, liveness: SessionLiveness) -> Self {
+        self.capture_liveness = Some(liveness);
+        self
+    }
+
+    /// Stop capture and encoding while no client is connected
+    ///
+    /// Pass the idle stop with a spare of the pipeline's video source,
+    /// duplicated to restart capture when the next client connects.
+    pub fn with_idle_stop(mut self, idle_stop: Option<(IdleStop, VideoSource)>) -> Self {
+        self.idle_stop = idle_stop;
+        self
+    }
+
+    /// Write encoded frames to disk when a dump is requested
+    pub fn with_frame_dump(mut self, frame_dump: Option<FrameDump>) -> Self {
+        self.frame_dump = frame_dump;
+        self
+    }
+
+    /// Publish the login, codec and resolution of served clients
+    pub fn with_events(mut self, events: SessionEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Lifecycle events of this pipeline (attached to each client it serves)
+    pub fn events(&self) -> SessionEvents {
+        self.events.clone()
+    }
+
+    /// Close the capture session after losing a stream for good
+    fn capture_lost(&self, reason: &str) {
+        error!("❌ {}", reason);
+        match self.capture_liveness {
+            Some(ref liveness) => liveness.close(reason),
+            None => warn!("Pipeline not tied to a capture session, frames stay frozen"),
+        }
+    }
+
+    /// Next captured frame, if capture runs and one is waiting
+    async fn try_recv_frame(&self) -> Option<VideoFrame> {
+        let source = self.frame_source.read().await;
+        source.as_ref()?.try_recv_frame().await
+    }
+
+    /// Re-create a PipeWire stream of the running capture
+    async fn recreate_stream(
+        &self,
+        idx: usize,
+        stream_id: u32,
+        node_id: u32,
+        size: (u32, u32),
+        params: StreamParams,
+    ) -> Result<()> {
+        match self.frame_source.read().await.as_ref() {
+            Some(source) => {
+                source
+                    .recreate_stream(idx, stream_id, node_id, size, params)
+                    .await
+            }
+            None => anyhow::bail!("Capture is stopped"),
+        }
+    }
+
+    /// Stop capture until [`Self::restart_capture`]
+    async fn stop_capture(&self) {
+        self.frame_source.write().await.take();
+    }
+
+    /// Start capture again from a duplicate of `video`
+    async fn restart_capture(&self, video: VideoSource) -> Result<()> {
+        let source = FrameSource::start(
+            video.duplicate()?,
+            &self.stream_info,
+            &self.config,
+            &self.service_registry,
+        )
+        .await?;
+        *self.frame_source.write().await = Some(source);
+        Ok(())
+    }
+
+    /// Idle stop of this pipeline (None = capture never stops)
+    pub fn idle_stop(&self) -> Option<IdleStop> {
+        self.idle_stop
+            .as_ref()
+            .map(|(idle_stop, _)| idle_stop.clone())
+    }
+
+    /// Re-create every PipeWire stream on its current node
+    ///
+    /// Returns whether any stream was re-created.
+    async fn renegotiate_streams(&self, stream_nodes: &HashMap<u32, u32>, fps: u32) -> bool {
+        let mut renegotiated = false;
+        for (&node_id, &stream_id) in stream_nodes {
+            let Some((idx, stream)) = self
+                .stream_info
+                .iter()
+                .enumerate()
+                .find(|(_, s)| s.node_id == stream_id)
+            else {
+                continue;
+            };
+            let params =
+                StreamParams::new(&self.service_registry, fps).for_stream(stream, &self.config);
+            match self
+                .recreate_stream(idx, stream_id, node_id, stream.size, params)
+                .await
+            {
+                Ok(()) => {
+                    info!("🔄 Stream {} renegotiated on node {}", stream_id, node_id);
+                    renegotiated = true;
+                }
+                Err(e) => warn!("Failed to renegotiate stream {}: {:#}", stream_id, e),
+            }
+        }
+        renegotiated
+    }
+
+    /// Set the clipboard policy updated on configuration reload
+    pub async fn set_clipboard_policy(&self, policy: ClipboardPolicy) {
+        *self.clipboard_policy.write().await = Some(policy);
+    }
+
+    /// Resource meter of this pipeline
+    ///
+    /// The pipeline records its CPU time and frame memory here and drops
+    /// frames while the meter is throttled.
+    pub fn session_meter(&self) -> SessionMeter {
+        self.session_meter.clone()
+    }
+
+    /// Login banner of this pipeline
+    pub fn login_banner(&self) -> LoginBanner {
+        self.login_banner.clone()
+    }
+
+    /// Connection quality overlay of this pipeline
+    pub fn quality_overlay(&self) -> Option<QualityOverlay> {
+        self.quality_overlay.clone()
+    }
+
+    /// Link estimate of this pipeline
+    ///
+    /// The session's resource monitor adds its `TCP_INFO` samples here.
+    pub fn link_estimate(&self) -> LinkEstimate {
+        self.link_estimate.clone()
+    }
+
+    /// Activity and pause state of this pipeline
+    pub fn sharing(&self) -> SharingControl {
+        self.sharing.clone()
+    }
+
+    /// On-screen message of this pipeline
+    pub fn session_message(&self) -> SessionMessage {
+        self.session_message.clone()
+    }
+
+    /// Client-side cursor of this pipeline
+    pub fn cursor_channel(&self) -> Option<CursorChannel> {
+        self.cursor_channel.clone()
+    }
+
+    /// Position of a captured frame's pixel in the frames sent to the client
+    ///
+    /// Applies follow-focus cropping and server-side scaling; `None` if the
+    /// client does not see that pixel.
+    fn map_capture_point(&self, x: i32, y: i32, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (x, y) = match self.follow_focus {
+            Some(ref follow_focus) => follow_focus
+                .lock()
+                .ok()?
+                .map_frame_point(x, y, width, height)?,
+            None if x >= 0 && y >= 0 => (x as u32, y as u32),
+            None => return None,
+        };
+        match self.frame_scaler {
+            Some(ref frame_scaler) => frame_scaler.lock().ok()?.map_source_point(x, y),
+            None => Some((x, y)),
+        }
+    }
+
+    /// Stop the display pipeline task
+    ///
+    /// Used when a per-client pipeline is torn down; the task exits on its
+    /// next iteration and releases its PipeWire consumer.
+    pub fn stop_pipeline(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
     /// Set graphics queue sender for priority multiplexing
     ///
     /// When set, frames will be routed through the graphics queue instead of
@@ -411,18 +1111,25 @@ impl LamcoDisplayHandler {
     /// Pad frame to aligned dimensions (16-pixel boundary)
     ///
     /// MS-RDPEGFX requires surface dimensions to be multiples of 16.
-    /// This function pads the frame by replicating edge pixels.
+    /// This function pads the frame by replicating edge pixels into `padded`,
+    /// which is reused across frames so the pipeline doesn't allocate a
+    /// frame-sized buffer for each one.
     fn pad_frame_to_aligned(
         data: &[u8],
         width: u32,
         height: u32,
         aligned_width: u32,
         aligned_height: u32,
-    ) -> Vec<u8> {
+        padded: &mut Vec<u8>,
+    ) {
         let bytes_per_pixel = 4; // BGRA
         let src_stride = width * bytes_per_pixel;
         let dst_stride = aligned_width * bytes_per_pixel;
-        let mut padded = vec![0u8; (aligned_width * aligned_height * bytes_per_pixel) as usize];
+        // Every byte is overwritten below
+        padded.resize(
+            (aligned_width * aligned_height * bytes_per_pixel) as usize,
+            0,
+        );
 
         // Copy existing rows
         for y in 0..height {
@@ -446,15 +1153,14 @@ impl LamcoDisplayHandler {
         // Replicate last row to fill height padding
         if aligned_height > height {
             let last_row_offset = ((height - 1) * dst_stride) as usize;
-            // Create a copy of the last row to avoid borrow checker issues
-            let last_row = padded[last_row_offset..last_row_offset + dst_stride as usize].to_vec();
             for y in height..aligned_height {
                 let dst_offset = (y * dst_stride) as usize;
-                padded[dst_offset..dst_offset + dst_stride as usize].copy_from_slice(&last_row);
+                padded.copy_within(
+                    last_row_offset..last_row_offset + dst_stride as usize,
+                    dst_offset,
+                );
             }
         }
-
-        padded
     }
 
     /// Check if EGFX is ready for frame sending
@@ -549,43 +1255,32 @@ impl LamcoDisplayHandler {
     ///   bitmap and sends through standard display update channel.
     pub fn start_pipeline(self: Arc<Self>) {
         let handler = Arc::clone(&self);
+        let scheduling = ThreadScheduling::encoder(&self.config.performance.scheduling);
 
-        tokio::spawn(async move {
+        let pipeline = async move {
             info!("🎬 Starting display update pipeline task");
 
             // === ADAPTIVE FPS CONTROLLER (Premium Feature) ===
             // Dynamically adjusts frame rate based on screen activity:
             // - Static screen: 5 FPS (saves CPU/bandwidth)
             // - Low activity (typing): 15 FPS
-            // - Medium activity (scrolling): 20 FPS
-            // - High activity (video): 30 FPS
+            // - Medium activity (scrolling): 30 FPS
+            // - High activity (video): 60 FPS
+            // each limited to min_fps..=max_fps. Levels are left downwards with
+            // hysteresis, and a high client ack latency holds the rate one tier
+            // down until it recovers (see AdaptiveFpsController).
             //
             // SERVICE-AWARE: Only enable when damage tracking service is available
             // (without it, adaptive FPS has no activity detection signal)
             let service_supports_adaptive_fps = self.service_registry.should_enable_adaptive_fps();
-            let adaptive_fps_enabled =
-                self.config.performance.adaptive_fps.enabled && service_supports_adaptive_fps;
             if self.config.performance.adaptive_fps.enabled && !service_supports_adaptive_fps {
                 info!("⚠️ Adaptive FPS disabled: damage tracking service unavailable");
             }
-            let adaptive_fps_config = crate::performance::AdaptiveFpsConfig {
-                enabled: adaptive_fps_enabled,
-                min_fps: self.config.performance.adaptive_fps.min_fps,
-                max_fps: self.config.performance.adaptive_fps.max_fps,
-                high_activity_threshold: self
-                    .config
-                    .performance
-                    .adaptive_fps
-                    .high_activity_threshold,
-                medium_activity_threshold: self
-                    .config
-                    .performance
-                    .adaptive_fps
-                    .medium_activity_threshold,
-                low_activity_threshold: self.config.performance.adaptive_fps.low_activity_threshold,
-                ..Default::default()
-            };
-            let mut adaptive_fps = AdaptiveFpsController::new(adaptive_fps_config);
+            let mut adaptive_fps = AdaptiveFpsController::new(adaptive_fps_config(
+                &self.config,
+                service_supports_adaptive_fps,
+            ));
+            let mut adaptive_fps_enabled = adaptive_fps.is_enabled();
 
             // === LATENCY GOVERNOR (Premium Feature) ===
             // Controls encoding latency vs quality trade-off:
@@ -594,13 +1289,14 @@ impl LamcoDisplayHandler {
             // - Quality (<300ms): Photo/video editing - accumulate for quality
             //
             // SERVICE-AWARE: ExplicitSync service affects frame pacing accuracy
+            //
+            // Each mode also bounds the frames in flight at the client, caps the
+            // encoder's bitrate and keyframe interval, and drops frames that
+            // miss its latency budget
             let explicit_sync_level = self.service_registry.service_level(ServiceId::ExplicitSync);
-            let latency_mode = match self.config.performance.latency.mode.as_str() {
-                "interactive" => LatencyMode::Interactive,
-                "quality" => LatencyMode::Quality,
-                _ => LatencyMode::Balanced,
-            };
-            let mut latency_governor = LatencyGovernor::new(latency_mode);
+            let mut latency_governor = latency_governor(&self.config);
+            let latency_mode = latency_governor.mode();
+            let frame_ack_timeout = Duration::from_millis(self.config.egfx.frame_ack_timeout);
 
             // Log service-aware performance feature status
             let damage_level = self
@@ -617,16 +1313,26 @@ impl LamcoDisplayHandler {
                 "   Services: damage_tracking={}, explicit_sync={}, dmabuf={}",
                 damage_level, explicit_sync_level, dmabuf_level
             );
+            info!(
+                "   Latency budget: {}ms, {} frame(s) in flight, hardware preset '{}'",
+                latency_mode.target_latency_ms(),
+                latency_governor.max_frames_in_flight(),
+                latency_mode.hardware_preset()
+            );
 
-            // Legacy frame regulator (fallback when adaptive FPS disabled)
+            // Frame rate when adaptive FPS is disabled
             // Uses configured max_fps (default: 30, can be 60 for high-performance mode)
-            let legacy_fps = self.config.performance.adaptive_fps.max_fps;
-            let mut frame_regulator = FrameRateRegulator::new(legacy_fps);
+            let mut legacy_fps = self.config.performance.adaptive_fps.max_fps;
             let mut frames_sent = 0u64;
             let mut frames_dropped = 0u64;
             let mut egfx_frames_sent = 0u64;
+            let encoder_state = CrashState::new("encoder");
 
             let mut loop_iterations = 0u64;
+            let mut poll_delay = FRAME_POLL_MIN;
+            let small_update_fast_path = self.config.egfx.small_update_fast_path;
+            let mut small_updates_sent = 0u64;
+            let mut frame_dump_tap = handler.frame_dump.as_ref().map(FrameDump::tap);
 
             // EGFX/H.264 encoder - created lazily when EGFX becomes ready
             // Supports both AVC420 (4:2:0) and AVC444 (4:4:4) based on client negotiation
@@ -634,6 +1340,21 @@ impl LamcoDisplayHandler {
             let mut egfx_sender: Option<EgfxFrameSender> = None;
             let mut egfx_checked = false;
             let mut use_avc444 = false; // Track which codec is active for sending
+            let mut encoder_config: Option<EncoderConfig> = None; // For in-place rebuilds
+
+            // Padding target for unaligned frames, kept across frames
+            let mut padded_frame: Vec<u8> = Vec::new();
+            // Steps the session down when the client can't decode in time
+            let mut decode_governor: Option<DecodeGovernor> = None;
+            let mut decode_fps_cap = u32::MAX;
+            let mut h264_bitrate = self.config.egfx.h264_bitrate;
+            // Bitrate following the client's link (None = fixed bitrate)
+            let mut bitrate_controller = self.config.egfx.adaptive_bitrate.then(|| {
+                BitrateController::new(latency_governor.cap_bitrate(h264_bitrate), Instant::now())
+            });
+
+            // Hot-reloaded settings (bitrate, adaptive FPS, clipboard policy)
+            let mut live_config = handler.live_config.clone();
 
             // === DAMAGE DETECTION (Config-controlled) ===
             // Detects changed screen regions to skip unchanged frames (90%+ bandwidth reduction for static content)
@@ -647,70 +1368,369 @@ impl LamcoDisplayHandler {
                 min_region_area: self.config.damage_tracking.min_region_area,
             };
 
-            let mut damage_detector_opt = if self.config.damage_tracking.enabled {
+            let damage_config = if self.config.damage_tracking.enabled {
                 debug!("Damage tracking ENABLED: tile_size={}, threshold={:.2}, pixel_threshold={}, merge_distance={}, min_region_area={}",
                     damage_config.tile_size, damage_config.diff_threshold, damage_config.pixel_threshold,
                     damage_config.merge_distance, damage_config.min_region_area);
-                Some(DamageDetector::new(damage_config))
+                Some(damage_config)
             } else {
                 debug!("🎯 Damage tracking DISABLED via config");
                 None
             };
 
+            // === PER-MONITOR PIPELINES ===
+            // Each monitor keeps its latest frame, paced at its own frame
+            // rate, with a damage detector of its own
+            let mut monitors = MonitorPipelines::new(
+                handler
+                    .stream_info
+                    .iter()
+                    .enumerate()
+                    .map(|(index, stream)| {
+                        let fps = stream_fps(stream, &self.config.multimon.monitors, legacy_fps);
+                        (index as u32, fps)
+                    }),
+                legacy_fps,
+                damage_config,
+            );
+            let mut monitor_stats_logged = Instant::now();
+
             let mut frames_skipped_damage = 0u64; // Frames skipped due to no damage
 
+            // Last desktop frame hidden behind the login banner, repainted as
+            // soon as the banner is acknowledged
+            let mut frame_behind_banner = None;
+            // Last primary monitor frame, repainted when a message appears
+            // or goes away
+            let mut frame_behind_message: Option<VideoFrame> = None;
+
+            // === NODE RE-RESOLUTION ===
+            // Current node of each stream (node → stream id) and the nodes
+            // removed underneath a stream, waiting for a replacement
+            let mut node_watch = handler.node_watch.lock().ok().and_then(|mut w| w.take());
+            let mut stream_nodes: HashMap<u32, u32> = handler
+                .stream_info
+                .iter()
+                .map(|s| (s.node_id, s.node_id))
+                .collect();
+            let mut lost_nodes: HashMap<u32, Instant> = HashMap::new();
+
+            // === CAPTURE WATCHDOG ===
+            // Notice streams going silent; the indicator action re-sends the
+            // last captured frame dimmed
+            let mut capture_watchdog =
+                CaptureWatchdog::from_config(&self.config.capture_watchdog, Instant::now());
+            let mut last_captured: Option<VideoFrame> = None;
+            // Pool-backed crop/scale outputs, recycled once their frame is done
+            let mut stage_buffers: Vec<Arc<Vec<u8>>> = Vec::new();
+
+            // === IDLE FULL STOP ===
+            // When the last client left (None = a client is connected)
+            let mut idle_since: Option<Instant> = None;
+
             loop {
+                if handler.stopped.load(Ordering::Relaxed) {
+                    info!("🛑 Display pipeline stopped after {} frames", frames_sent);
+                    break;
+                }
+
+                // === IDLE FULL STOP ===
+                // Nobody to capture for: stop the streams and encoder, sleep
+                // until the next client connects, then re-arm capture
+                if let Some((ref idle_stop, video)) = handler.idle_stop {
+                    if idle_stop.has_clients() {
+                        idle_since = None;
+                    } else if idle_since.get_or_insert_with(Instant::now).elapsed()
+                        >= idle_stop.delay()
+                    {
+                        info!(
+                            "💤 No clients for {}s: stopping capture and encoding",
+                            idle_stop.delay().as_secs()
+                        );
+                        handler.stop_capture().await;
+                        // The next client gets a new encoder and EGFX surface
+                        video_encoder = None;
+                        encoder_config = None;
+                        egfx_sender = None;
+                        egfx_checked = false;
+                        padded_frame = Vec::new();
+                        monitors.invalidate_damage();
+                        monitors.clear();
+                        last_captured = None;
+                        frame_behind_banner = None;
+                        frame_behind_message = None;
+
+                        idle_stop.wait_for_client().await;
+                        idle_since = None;
+                        match handler.restart_capture(video).await {
+                            Ok(()) => info!("▶️ Client connected: capture re-armed"),
+                            Err(e) => handler
+                                .capture_lost(&format!("Capture could not be restarted: {:#}", e)),
+                        }
+                        if let Some(ref mut watchdog) = capture_watchdog {
+                            watchdog.rearm(Instant::now());
+                        }
+                        continue;
+                    }
+                }
+
+                // === HOT-RELOADED SETTINGS ===
+                if let Some(live) = live_config.as_mut() {
+                    if live.has_changed().unwrap_or(false) {
+                        let live = Arc::clone(&live.borrow_and_update());
+
+                        adaptive_fps
+                            .set_config(adaptive_fps_config(&live, service_supports_adaptive_fps));
+                        adaptive_fps_enabled = adaptive_fps.is_enabled();
+                        if live.performance.adaptive_fps.max_fps != legacy_fps {
+                            legacy_fps = live.performance.adaptive_fps.max_fps;
+                        }
+
+                        if live.egfx.h264_bitrate != h264_bitrate {
+                            h264_bitrate = live.egfx.h264_bitrate;
+                            let mut bitrate = latency_governor.cap_bitrate(h264_bitrate);
+                            if let Some(controller) = bitrate_controller.as_mut() {
+                                controller.set_max(bitrate, Instant::now());
+                                bitrate = controller.current_kbps();
+                            }
+                            if let (Some(encoder), Some(config)) =
+                                (video_encoder.as_mut(), encoder_config.as_mut())
+                            {
+                                encoder.apply_bitrate(
+                                    config,
+                                    bitrate,
+                                    &self.config.egfx,
+                                    &latency_governor,
+                                );
+                            }
+                        }
+
+                        if let Some(policy) = handler.clipboard_policy.read().await.as_ref() {
+                            policy.set_enabled(live.clipboard.enabled);
+                            policy.set_rate_limit_ms(live.clipboard.rate_limit_ms);
+                        }
+                    }
+                }
+
                 loop_iterations += 1;
                 if loop_iterations % 1000 == 0 {
                     debug!(
                         "Display pipeline heartbeat: {} iterations, sent {} (egfx: {}), dropped {}, skipped_damage {}",
                         loop_iterations, frames_sent, egfx_frames_sent, frames_dropped, frames_skipped_damage
                     );
+                    encoder_state.set(encoder_crash_state(
+                        video_encoder.as_ref(),
+                        encoder_config.as_ref(),
+                        frames_sent,
+                        egfx_frames_sent,
+                        frames_dropped,
+                    ));
                 }
 
-                // Try to get frame from PipeWire thread (non-blocking)
-                let frame = {
-                    let thread_mgr = handler.pipewire_thread.lock().await;
-                    thread_mgr.try_recv_frame()
-                };
-
-                let frame = match frame {
-                    Some(f) => {
-                        debug!("Received frame from PipeWire");
-                        f
+                // === NODE RE-RESOLUTION ===
+                // Move streams to the nodes replacing removed ones; the new
+                // node starts over, so damage history and reference frames go
+                while let Some(event) = node_watch.as_mut().and_then(NodeWatch::try_recv) {
+                    match event {
+                        NodeEvent::Removed { node_id } => {
+                            warn!(
+                                "⚠️  Screencast node {} removed, waiting {}s for a replacement",
+                                node_id,
+                                NODE_RETURN_TIMEOUT.as_secs()
+                            );
+                            lost_nodes.insert(node_id, Instant::now());
+                        }
+                        NodeEvent::Replaced {
+                            old_node_id,
+                            new_node_id,
+                        } => {
+                            lost_nodes.remove(&old_node_id);
+                            let Some(stream_id) = stream_nodes.remove(&old_node_id) else {
+                                continue;
+                            };
+                            let Some((idx, stream)) = handler
+                                .stream_info
+                                .iter()
+                                .enumerate()
+                                .find(|(_, s)| s.node_id == stream_id)
+                            else {
+                                continue;
+                            };
+                            let result = handler
+                                .recreate_stream(
+                                    idx,
+                                    stream_id,
+                                    new_node_id,
+                                    stream.size,
+                                    StreamParams::new(&handler.service_registry, legacy_fps)
+                                        .for_stream(stream, &handler.config),
+                                )
+                                .await;
+                            match result {
+                                Ok(()) => {
+                                    info!(
+                                        "🔄 Stream {} reconnected: node {} → {}",
+                                        stream_id, old_node_id, new_node_id
+                                    );
+                                    stream_nodes.insert(new_node_id, stream_id);
+                                    monitors.invalidate_damage();
+                                    if let Some(encoder) = video_encoder.as_mut() {
+                                        encoder.request_idr();
+                                    }
+                                }
+                                Err(e) => handler.capture_lost(&format!(
+                                    "Screencast node {} could not be opened: {:#}",
+                                    new_node_id, e
+                                )),
+                            }
+                        }
                     }
-                    None => {
-                        // No frame available, sleep briefly and retry
-                        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+                }
+                if lost_nodes
+                    .values()
+                    .any(|removed| removed.elapsed() >= NODE_RETURN_TIMEOUT)
+                {
+                    handler.capture_lost("Screencast node removed and not replaced");
+                    lost_nodes.clear();
+                }
+
+                // Drain captured frames into their monitors' slots (non-blocking)
+                let mut received = 0;
+                while received < FRAME_DRAIN_MAX {
+                    let Some(f) = handler.try_recv_frame().await else {
+                        break;
+                    };
+                    received += 1;
+                    trace!("Received frame from monitor {}", f.monitor_index);
+                    if chaos::drop_frame() {
                         continue;
                     }
-                };
+                    if let Some(ref mut watchdog) = capture_watchdog {
+                        if let Some(frozen) = watchdog.frame_arrived(Instant::now()) {
+                            info!("✅ Capture resumed after {:.1}s", frozen.as_secs_f64());
+                        }
+                        if watchdog.action() == WatchdogAction::Indicator {
+                            last_captured = Some(f.clone());
+                        }
+                    }
+                    // === CURSOR ===
+                    // Pointer updates track every frame, including those
+                    // superseded in their monitor's slot
+                    if let Some(ref cursor) = handler.cursor_channel {
+                        let updates = cursor.update(
+                            CursorMeta::from_frame(&f),
+                            |x, y| handler.map_capture_point(x, y, f.width, f.height),
+                            handler.session_meter.snapshot().rtt_ms,
+                        );
+                        for update in updates {
+                            if let Err(e) = handler.update_sender.send(update).await {
+                                error!("Failed to send cursor update: {}", e);
+                            }
+                        }
+                    }
+                    monitors.queue(f, Instant::now());
+                }
+                if received > 0 {
+                    poll_delay = FRAME_POLL_MIN;
+                }
 
                 // === FRAME RATE REGULATION ===
-                // Use adaptive FPS if enabled, otherwise fall back to fixed 30 FPS
-                let should_process = if adaptive_fps_enabled {
-                    // Adaptive FPS: check based on current activity-driven target
-                    adaptive_fps.should_capture_frame()
+                // Each monitor is paced at its own rate, capped by adaptive FPS
+                // (or the configured rate) and the client's decode capacity
+                let video_fps = if adaptive_fps_enabled {
+                    adaptive_fps.current_fps()
                 } else {
-                    // Fixed FPS: use legacy regulator
-                    frame_regulator.should_send_frame()
+                    legacy_fps.min(decode_fps_cap)
                 };
+                monitors.set_ceiling(video_fps);
+                if let Some(ref cursor) = handler.cursor_channel {
+                    cursor.set_video_fps(video_fps);
+                }
+                let now = Instant::now();
+                let frame = monitors.next_frame(now).or_else(|| {
+                    // A static desktop sends no new frames to show or remove
+                    // a message on
+                    frame_behind_message
+                        .clone()
+                        .filter(|_| handler.session_message.take_repaint())
+                        .map(|f| (f, now))
+                });
+
+                wakeup_audit::wakeup("frame-poll", received == 0 && frame.is_none());
+
+                let (frame, frame_received) = match frame {
+                    Some(queued) => queued,
+                    None => match frame_behind_banner.take() {
+                        // A static desktop sends no new frames after the banner
+                        Some(f) if !handler.login_banner.is_pending() => (f, now),
+                        held => {
+                            frame_behind_banner = held;
+
+                            let frozen = capture_watchdog.as_mut().is_some_and(|w| w.check(now));
+                            let stale = match capture_watchdog.as_mut() {
+                                Some(watchdog) if frozen => {
+                                    if handler.is_egfx_ready().await {
+                                        handler.session_meter.record_capture_stall();
+                                        warn!(
+                                            "⚠️  No frames captured for {}s ({})",
+                                            watchdog.frozen_for(now).as_secs(),
+                                            watchdog.action()
+                                        );
+                                        match watchdog.action() {
+                                            WatchdogAction::Log => None,
+                                            WatchdogAction::Indicator => {
+                                                last_captured.take().map(|mut f| {
+                                                    dim_frame(Arc::make_mut(&mut f.data));
+                                                    f
+                                                })
+                                            }
+                                            WatchdogAction::Renegotiate => {
+                                                if handler
+                                                    .renegotiate_streams(&stream_nodes, legacy_fps)
+                                                    .await
+                                                {
+                                                    monitors.invalidate_damage();
+                                                    if let Some(encoder) = video_encoder.as_mut() {
+                                                        encoder.request_idr();
+                                                    }
+                                                }
+                                                None
+                                            }
+                                        }
+                                    } else {
+                                        // No client to show a frozen screen to
+                                        watchdog.rearm(now);
+                                        None
+                                    }
+                                }
+                                _ => None,
+                            };
 
-                if !should_process {
+                            match stale {
+                                Some(f) => (f, now),
+                                None => {
+                                    // No frame due: sleep until a waiting frame
+                                    // is, backing off while the screen is still
+                                    let due = monitors.next_due(now).unwrap_or(poll_delay);
+                                    tokio::time::sleep(due.min(poll_delay)).await;
+                                    if received == 0 {
+                                        poll_delay = (poll_delay * 2).min(FRAME_POLL_MAX);
+                                    }
+                                    continue;
+                                }
+                            }
+                        }
+                    },
+                };
+
+                let monitor_index = frame.monitor_index;
+
+                // Session over its resource limits: run at a fraction of the rate
+                if handler.session_meter.skip_frame() {
                     frames_dropped += 1;
-                    if frames_dropped % 30 == 0 {
-                        let current_fps = if adaptive_fps_enabled {
-                            adaptive_fps.current_fps()
-                        } else {
-                            30
-                        };
-                        info!(
-                            "Frame rate regulation: dropped {} frames, sent {}, target_fps={}",
-                            frames_dropped, frames_sent, current_fps
-                        );
-                    }
                     continue;
                 }
+                handler.session_meter.record_frame_memory(frame.data.len());
 
                 frames_sent += 1;
                 if frames_sent % 30 == 0 || frames_sent < 10 {
@@ -750,6 +1770,98 @@ impl LamcoDisplayHandler {
                     continue;
                 }
 
+                // === PAUSED SHARING ===
+                // The client keeps showing the last frame it was sent
+                if handler.sharing.is_paused() {
+                    continue;
+                }
+
+                // Held as captured: cropping and scaling apply again on repaint
+                let captured = handler.login_banner.is_pending().then(|| frame.clone());
+                if frame.monitor_index == 0 {
+                    frame_behind_message = Some(frame.clone());
+                }
+
+                // The previous frame is gone, so its stage outputs are free
+                let pool = BufferPool::global();
+                for data in stage_buffers.drain(..) {
+                    pool.recycle_shared(data);
+                }
+                // Whether frame.data is a stage output from the pool
+                let mut pooled_data = false;
+
+                // === FOLLOW-FOCUS CROP ===
+                // Present only the focused viewport to the client
+                let mut frame = frame;
+                if let Some(ref follow_focus) = handler.follow_focus {
+                    let cropped = follow_focus
+                        .lock()
+                        .map(|ctl| ctl.crop_frame(&frame.data, frame.width, frame.height))
+                        .unwrap_or(None);
+                    if let Some(cropped) = cropped {
+                        frame.width = cropped.width;
+                        frame.height = cropped.height;
+                        frame.data = Arc::new(cropped.data);
+                        pooled_data = true;
+                    }
+                }
+
+                // === SERVER-SIDE SCALING ===
+                // Fit/fill/pan the frame into the client's desktop size
+                if let Some(ref frame_scaler) = handler.frame_scaler {
+                    let scaled = frame_scaler
+                        .lock()
+                        .map(|mut scaler| scaler.scale(&frame.data, frame.width, frame.height))
+                        .unwrap_or(None);
+                    if let Some(scaled) = scaled {
+                        frame.width = scaled.width;
+                        frame.height = scaled.height;
+                        let replaced = std::mem::replace(&mut frame.data, Arc::new(scaled.data));
+                        if pooled_data {
+                            pool.recycle_shared(replaced);
+                        }
+                        pooled_data = true;
+                    }
+                }
+                handler.events.resolution(frame.width, frame.height);
+
+                // === LOGIN BANNER ===
+                // Show the banner instead of the desktop until acknowledged
+                if let Some(banner) = handler.login_banner.frame(frame.width, frame.height) {
+                    frame_behind_banner = captured;
+                    let replaced = std::mem::replace(&mut frame.data, banner);
+                    if pooled_data {
+                        pool.recycle_shared(replaced);
+                    }
+                    pooled_data = false;
+                } else {
+                    // === PAINTED CURSOR ===
+                    if let Some(ref cursor) = handler.cursor_channel {
+                        if cursor.mode().requires_compositing() {
+                            let data = Arc::make_mut(&mut frame.data);
+                            cursor.paint(data, frame.width, frame.height);
+                        }
+                    }
+                    // === QUALITY OVERLAY ===
+                    if let Some(ref overlay) = handler.quality_overlay {
+                        if overlay.is_visible() {
+                            let usage = handler.session_meter.snapshot();
+                            let data = Arc::make_mut(&mut frame.data);
+                            overlay.draw(data, frame.width, frame.height, &usage);
+                        }
+                    }
+                    // === SESSION MESSAGE ===
+                    if frame.monitor_index == 0 && handler.session_message.is_active() {
+                        let data = Arc::make_mut(&mut frame.data);
+                        handler
+                            .session_message
+                            .draw(data, frame.width, frame.height);
+                    }
+                }
+                if pooled_data {
+                    stage_buffers.push(Arc::clone(&frame.data));
+                }
+
                 // === EGFX/H.264 PATH ===
                 // EGFX is ready - process frame
                 if true {
@@ -766,7 +1878,10 @@ impl LamcoDisplayHandler {
                         // Create H.264 encoder with resolution-appropriate level
                         // Use config values for quality settings
                         let config = EncoderConfig {
-                            bitrate_kbps: self.config.egfx.h264_bitrate,
+                            bitrate_kbps: bitrate_controller.as_ref().map_or(
+                                latency_governor.cap_bitrate(h264_bitrate),
+                                BitrateController::current_kbps,
+                            ),
                             max_fps: self.config.video.target_fps as f32,
                             enable_skip_frame: true,
                             width: Some(aligned_width),
@@ -775,9 +1890,10 @@ impl LamcoDisplayHandler {
                             qp_min: self.config.egfx.qp_min,
                             qp_max: self.config.egfx.qp_max,
                         };
+                        encoder_config = Some(config.clone());
                         info!(
                             "🎬 H.264 encoder config: {}kbps, {}fps, QP[{}-{}]",
-                            self.config.egfx.h264_bitrate,
+                            config.bitrate_kbps,
                             self.config.video.target_fps,
                             self.config.egfx.qp_min,
                             self.config.egfx.qp_max
@@ -804,16 +1920,10 @@ impl LamcoDisplayHandler {
                             // Try AVC444 first (premium 4:4:4 chroma)
                             match Avc444Encoder::new(config.clone()) {
                                 Ok(mut encoder) => {
-                                    // Wire aux omission config from EgfxConfig
-                                    encoder.configure_aux_omission(
-                                        self.config.egfx.avc444_enable_aux_omission,
-                                        self.config.egfx.avc444_max_aux_interval,
-                                        self.config.egfx.avc444_aux_change_threshold,
-                                        self.config.egfx.avc444_force_aux_idr_on_return,
-                                    );
-                                    // Wire periodic IDR config for artifact recovery
-                                    encoder.configure_periodic_idr(
-                                        self.config.egfx.periodic_idr_interval,
+                                    configure_avc444(
+                                        &mut encoder,
+                                        &self.config.egfx,
+                                        &latency_governor,
                                     );
 
                                     video_encoder = Some(VideoEncoder::Avc444(encoder));
@@ -853,6 +1963,32 @@ impl LamcoDisplayHandler {
                             }
                         }
 
+                        // Each client starts from the negotiated operating point
+                        decode_fps_cap = u32::MAX;
+                        adaptive_fps.set_decode_cap(None);
+                        let point = OperatingPoint {
+                            avc444: matches!(video_encoder, Some(VideoEncoder::Avc444(_))),
+                            max_fps: legacy_fps,
+                        };
+                        info!("🎚️ Negotiated operating point: {}", point);
+                        handler.events.codec(
+                            video_encoder
+                                .as_ref()
+                                .map_or("RemoteFX", VideoEncoder::codec_name),
+                        );
+                        encoder_state.set(encoder_crash_state(
+                            video_encoder.as_ref(),
+                            encoder_config.as_ref(),
+                            frames_sent,
+                            egfx_frames_sent,
+                            frames_dropped,
+                        ));
+                        decode_governor = self
+                            .config
+                            .egfx
+                            .decode_downgrade
+                            .then(|| DecodeGovernor::new(point));
+
                         // Create EGFX sender and surface
                         if let (Some(gfx_handle), Some(event_tx)) = (
                             handler.gfx_server_handle.read().await.clone(),
@@ -977,6 +2113,16 @@ impl LamcoDisplayHandler {
                             continue;
                         }
 
+                        // === LATENCY BUDGET ===
+                        // Drop frames the client has no room for, or that waited
+                        // too long, before damage detection, so their changes
+                        // carry over to the next frame
+                        let in_flight = handler.ack_latency.in_flight(frame_ack_timeout);
+                        if !latency_governor.within_budget(in_flight, frame_received.elapsed()) {
+                            frames_dropped += 1;
+                            continue;
+                        }
+
                         // === DAMAGE DETECTION (Config-controlled) ===
                         // Detect which regions changed since the last frame
                         // Skip encoding entirely if nothing changed (huge bandwidth savings)
@@ -993,8 +2139,9 @@ impl LamcoDisplayHandler {
                                 "Forcing full frame for periodic IDR (bypassing damage detection)"
                             );
                             vec![DamageRegion::full_frame(frame.width, frame.height)]
-                        } else if let Some(ref mut detector) = damage_detector_opt {
-                            // Damage tracking enabled - detect changed regions
+                        } else if let Some(detector) = monitors.damage_detector(monitor_index) {
+                            // Damage tracking enabled - detect changes since the
+                            // monitor's previous frame
                             detector.detect(&frame.data, frame.width, frame.height)
                         } else {
                             // Damage tracking disabled - use full frame
@@ -1010,10 +2157,80 @@ impl LamcoDisplayHandler {
                             0.0
                         };
 
+                        // === LINK ESTIMATE ===
+                        // Add the client's ack pacing to the resource monitor's
+                        // TCP samples; a congested link lowers the bitrate and
+                        // caps the frame rate
+                        let now = Instant::now();
+                        handler.link_estimate.record_acks(
+                            handler.ack_latency.acked_bytes(),
+                            handler.ack_latency.latency(),
+                            now,
+                        );
+                        let link = handler.link_estimate.snapshot(now);
+                        adaptive_fps.update_link(link.bandwidth_kbps.filter(|_| link.congested));
+                        if let Some(bitrate) = bitrate_controller
+                            .as_mut()
+                            .and_then(|controller| controller.update(&link, now))
+                        {
+                            if let Some(config) = encoder_config.as_mut() {
+                                encoder.apply_bitrate(
+                                    config,
+                                    bitrate,
+                                    &self.config.egfx,
+                                    &latency_governor,
+                                );
+                            }
+                        }
+
+                        // === CLIENT DECODE CAPABILITY ===
+                        // Step down codec, then frame rate, for a client whose
+                        // decoder can't keep up with what we send
+                        if let Some(point) = decode_governor.as_mut().and_then(|governor| {
+                            governor.evaluate(
+                                handler.ack_latency.decode_time(),
+                                handler.ack_latency.queue_depth(),
+                                now,
+                            )
+                        }) {
+                            warn!(
+                                "📉 Client can't decode in time (decode {:?}, queue {:?}): operating point now {}",
+                                handler.ack_latency.decode_time(),
+                                handler.ack_latency.queue_depth(),
+                                point
+                            );
+                            if !point.avc444 && matches!(encoder, VideoEncoder::Avc444(_)) {
+                                match encoder_config.clone().map(Avc420Encoder::new) {
+                                    Some(Ok(avc420)) => {
+                                        *encoder = VideoEncoder::Avc420(avc420);
+                                        use_avc444 = false;
+                                    }
+                                    Some(Err(e)) => {
+                                        warn!("Failed to create AVC420 encoder: {:?}", e)
+                                    }
+                                    None => {}
+                                }
+                            }
+                            decode_fps_cap = point.max_fps;
+                            adaptive_fps.set_decode_cap(Some(decode_fps_cap));
+                        }
+
                         // === UPDATE ADAPTIVE FPS (Premium Feature) ===
-                        // Feed damage ratio to update activity level and target FPS
+                        // Feed damage ratio to update activity level and target FPS,
+                        // and the client's ack latency to hold it down a tier when
+                        // the client falls behind
+                        if let Some(latency) = handler.ack_latency.latency() {
+                            handler.session_meter.record_ack_latency(latency);
+                            adaptive_fps.update_ack_latency(latency);
+                        }
                         if adaptive_fps_enabled {
                             adaptive_fps.update(damage_ratio);
+                            handler.session_meter.record_frame_rate(
+                                adaptive_fps.current_fps(),
+                                Some(adaptive_fps.activity_level()),
+                            );
+                        } else {
+                            handler.session_meter.record_frame_rate(legacy_fps, None);
                         }
 
                         // === LATENCY GOVERNOR DECISION (Premium Feature) ===
@@ -1042,7 +2259,7 @@ impl LamcoDisplayHandler {
                             // No changes detected - skip this frame entirely
                             frames_skipped_damage += 1;
                             if frames_skipped_damage % 100 == 0 {
-                                if let Some(ref detector) = damage_detector_opt {
+                                if let Some(detector) = monitors.damage_detector(monitor_index) {
                                     let stats = detector.stats();
                                     debug!(
                                         "🎯 Damage tracking: {} frames skipped (no change), {:.1}% bandwidth saved",
@@ -1054,13 +2271,17 @@ impl LamcoDisplayHandler {
                             // Update adaptive FPS with zero damage
                             if adaptive_fps_enabled {
                                 adaptive_fps.update(0.0);
+                                handler.session_meter.record_frame_rate(
+                                    adaptive_fps.current_fps(),
+                                    Some(adaptive_fps.activity_level()),
+                                );
                             }
                             continue;
                         }
 
                         // Log damage stats periodically
                         if frames_sent % 60 == 0 {
-                            if let Some(ref detector) = damage_detector_opt {
+                            if let Some(detector) = monitors.damage_detector(monitor_index) {
                                 let stats = detector.stats();
                                 debug!(
                                     "🎯 Damage: {} regions, {:.1}% of frame, avg {:.1}ms detection",
@@ -1079,14 +2300,55 @@ impl LamcoDisplayHandler {
                             }
                         }
 
+                        // === SMALL UPDATE FAST PATH ===
+                        // A caret blink or typed character goes out as raw
+                        // pixels; the encoder stays asleep and picks the change
+                        // up with the next frame it encodes
+                        if small_update_fast_path
+                            && !force_full_frame
+                            && is_small_update(&damage_regions)
+                        {
+                            let fast_path_start = Instant::now();
+                            match sender
+                                .send_uncompressed_regions(
+                                    &frame.data,
+                                    frame.width,
+                                    frame.height,
+                                    &damage_regions,
+                                    timestamp_ms as u32,
+                                )
+                                .await
+                            {
+                                Ok((frame_id, bytes)) => {
+                                    handler.ack_latency.frame_sent(frame_id, bytes);
+                                    monitors.record_sent(monitor_index, fast_path_start.elapsed());
+                                    handler.session_meter.record_frame_delivered();
+                                    egfx_frames_sent += 1;
+                                    small_updates_sent += 1;
+                                    if small_updates_sent % 100 == 0 {
+                                        debug!(
+                                            "⚡ Small updates: {} sent without encoding",
+                                            small_updates_sent
+                                        );
+                                    }
+                                }
+                                Err(e) => {
+                                    trace!("Uncompressed update failed: {} - dropping frame", e);
+                                    frames_dropped += 1;
+                                }
+                            }
+                            continue;
+                        }
+
                         // MS-RDPEGFX REQUIRES 16-pixel alignment
                         // Frame from PipeWire may not be aligned (e.g., 800×600)
                         // Must align dimensions AND pad frame data
                         let aligned_width = align_to_16(frame.width as u32);
                         let aligned_height = align_to_16(frame.height as u32);
 
-                        // Pad frame data if needed
-                        let frame_data = if aligned_width != frame.width as u32
+                        // Pad frame data if needed; aligned frames are
+                        // encoded straight from the captured buffer
+                        let frame_data: &[u8] = if aligned_width != frame.width as u32
                             || aligned_height != frame.height as u32
                         {
                             Self::pad_frame_to_aligned(
@@ -1095,23 +2357,58 @@ impl LamcoDisplayHandler {
                                 frame.height,
                                 aligned_width,
                                 aligned_height,
-                            )
+                                &mut padded_frame,
+                            );
+                            &padded_frame
                         } else {
-                            (*frame.data).clone()
+                            &frame.data
                         };
 
                         // Encode frame to H.264 with ALIGNED dimensions
                         // VideoEncoder handles both AVC420 and AVC444 transparently
-                        match encoder.encode_bgra(
-                            &frame_data,
+                        let encode_cpu_start = thread_cpu_time();
+                        let encode_start = Instant::now();
+                        let encoded = encoder.encode_bgra(
+                            frame_data,
                             aligned_width,
                             aligned_height,
                             timestamp_ms,
-                        ) {
+                        );
+                        latency_governor.record_encode_timing(
+                            encode_start.duration_since(frame_received).as_secs_f32() * 1000.0,
+                            encode_start.elapsed().as_secs_f32() * 1000.0,
+                        );
+                        handler
+                            .session_meter
+                            .record_cpu(thread_cpu_time().saturating_sub(encode_cpu_start));
+                        handler.session_meter.record_encode(encode_start.elapsed());
+                        match encoded {
                             Ok(Some(encoded_frame)) => {
                                 // Send via EGFX - method varies by codec
                                 // - encoded dimensions: aligned (for H.264 macroblock requirements)
                                 // - display dimensions: actual (for visible region, crops padding)
+                                let encoded_bytes = match &encoded_frame {
+                                    EncodedVideoFrame::Single(data) => data.len(),
+                                    EncodedVideoFrame::Dual { main, aux } => {
+                                        main.len() + aux.as_ref().map_or(0, Vec::len)
+                                    }
+                                };
+                                // === FRAME DUMP ===
+                                if let Some(slot) =
+                                    frame_dump_tap.as_mut().and_then(FrameDumpTap::next_frame)
+                                {
+                                    slot.write(DumpedFrame {
+                                        monitor: monitor_index,
+                                        width: frame.width,
+                                        height: frame.height,
+                                        data: Arc::clone(&frame.data),
+                                        damage: damage_regions.clone(),
+                                        codec: encoder.codec_name(),
+                                        streams: encoded_frame.streams(),
+                                        color_matrix: self.config.egfx.color_matrix.clone(),
+                                        color_range: self.config.egfx.color_range.clone(),
+                                    });
+                                }
                                 let send_result = match encoded_frame {
                                     EncodedVideoFrame::Single(data) => {
                                         // AVC420: Single stream with damage regions
@@ -1146,7 +2443,10 @@ impl LamcoDisplayHandler {
                                 };
 
                                 match send_result {
-                                    Ok(_frame_id) => {
+                                    Ok(frame_id) => {
+                                        handler.ack_latency.frame_sent(frame_id, encoded_bytes);
+                                        monitors.record_sent(monitor_index, encode_start.elapsed());
+                                        handler.session_meter.record_frame_delivered();
                                         egfx_frames_sent += 1;
                                         if egfx_frames_sent % 30 == 0 {
                                             let codec = encoder.codec_name();
@@ -1155,6 +2455,20 @@ impl LamcoDisplayHandler {
                                                 egfx_frames_sent, codec
                                             );
                                         }
+                                        if monitor_stats_logged.elapsed() >= MONITOR_STATS_INTERVAL
+                                        {
+                                            monitor_stats_logged = Instant::now();
+                                            for (index, stats) in monitors.stats() {
+                                                debug!(
+                                                    "🖥️ Monitor {}: {} received, {} superseded, {} sent, {:.1}ms encode",
+                                                    index,
+                                                    stats.received,
+                                                    stats.superseded,
+                                                    stats.sent,
+                                                    stats.encode_ms
+                                                );
+                                            }
+                                        }
                                         continue; // Frame sent via EGFX, skip RemoteFX path
                                     }
                                     Err(e) => {
@@ -1222,6 +2536,11 @@ impl LamcoDisplayHandler {
                     }
                 };
                 let iron_elapsed = iron_start.elapsed();
+                // Conversion awaits locks, so thread CPU time would not be
+                // reliable here; it is CPU-bound, so wall time is close
+                handler
+                    .session_meter
+                    .record_cpu(convert_elapsed + iron_elapsed);
 
                 // Log conversion performance every 30 frames
                 if frames_sent % 30 == 0 {
@@ -1251,6 +2570,7 @@ impl LamcoDisplayHandler {
                             warn!("Graphics queue full - frame dropped (QoS policy)");
                         }
                     }
+                    handler.session_meter.record_frame_delivered();
                 } else {
                     // Fallback: Send directly to IronRDP (no multiplexer)
                     for iron_bitmap in iron_updates {
@@ -1262,9 +2582,31 @@ impl LamcoDisplayHandler {
                             return;
                         }
                     }
+                    handler.session_meter.record_frame_delivered();
                 }
             }
-        });
+        };
+
+        if scheduling.is_default() {
+            spawn_in_current_span(pipeline);
+            return;
+        }
+
+        // Tasks move between runtime workers, so a pinned or realtime
+        // pipeline runs on a thread of its own
+        let runtime = tokio::runtime::Handle::current();
+        let pipeline = pipeline.in_current_span();
+        let spawned = std::thread::Builder::new()
+            .name("lamco-encode".to_string())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    scheduling.apply(current_thread_id(), "Encode").await;
+                    pipeline.await;
+                })
+            });
+        if let Err(e) = spawned {
+            error!("Failed to spawn encode thread: {}", e);
+        }
     }
 
     /// Convert video frame to RDP bitmap
@@ -1355,6 +2697,8 @@ impl RdpServerDisplay for LamcoDisplayHandler {
             .take()
             .ok_or_else(|| anyhow::anyhow!("Display updates already claimed"))?;
 
+        // Only clients past the connection sequence get this far
+        self.events.logged_in();
         Ok(Box::new(DisplayUpdatesStream::new(receiver)))
     }
 
@@ -1376,7 +2720,7 @@ impl Clone for LamcoDisplayHandler {
     fn clone(&self) -> Self {
         Self {
             size: Arc::clone(&self.size),
-            pipewire_thread: Arc::clone(&self.pipewire_thread),
+            frame_source: Arc::clone(&self.frame_source),
             bitmap_converter: Arc::clone(&self.bitmap_converter),
             update_sender: self.update_sender.clone(),
             update_receiver: Arc::clone(&self.update_receiver),
@@ -1388,6 +2732,25 @@ impl Clone for LamcoDisplayHandler {
             server_event_tx: Arc::clone(&self.server_event_tx),
             config: Arc::clone(&self.config), // Clone config Arc
             service_registry: Arc::clone(&self.service_registry), // Clone service registry Arc
+            follow_focus: self.follow_focus.clone(),
+            frame_scaler: self.frame_scaler.clone(),
+            stopped: Arc::clone(&self.stopped),
+            live_config: self.live_config.clone(),
+            clipboard_policy: Arc::clone(&self.clipboard_policy),
+            session_meter: self.session_meter.clone(),
+            login_banner: self.login_banner.clone(),
+            quality_overlay: self.quality_overlay.clone(),
+            ack_latency: self.ack_latency.clone(),
+            link_estimate: self.link_estimate.clone(),
+            sharing: self.sharing.clone(),
+            session_message: self.session_message.clone(),
+            cursor_channel: self.cursor_channel.clone(),
+            // Taken by the pipeline task of the original
+            node_watch: std::sync::Mutex::new(None),
+            capture_liveness: self.capture_liveness.clone(),
+            idle_stop: self.idle_stop.clone(),
+            frame_dump: self.frame_dump.clone(),
+            events: self.events.clone(),
         }
     }
 }
@@ -1473,4 +2836,21 @@ mod tests {
         assert_eq!(data.rectangle.bottom, 100);
         assert_eq!(data.data.len(), 100 * 100 * 4);
     }
+
+    #[test]
+    fn test_pad_frame_reuses_buffer() {
+        // 2×1 frame: pixels 1 and 2
+        let data = [1, 1, 1, 1, 2, 2, 2, 2];
+        // A previous, larger frame left stale bytes behind
+        let mut padded = vec![0xAA; 32 * 32 * 4];
+
+        LamcoDisplayHandler::pad_frame_to_aligned(&data, 2, 1, 16, 16, &mut padded);
+
+        assert_eq!(padded.len(), 16 * 16 * 4);
+        for row in padded.chunks_exact(16 * 4) {
+            assert_eq!(&row[..8], &data);
+            // Last pixel replicated across the width padding
+            assert!(row[8..].iter().all(|&b| b == 2));
+        }
+    }
 }
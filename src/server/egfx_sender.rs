@@ -21,6 +21,9 @@
 //! IronRDP Server event loop → Wire → RDP Client
 //! ```
 //!
+//! Damage small enough for the [`SMALL_UPDATE_MAX_SIZE`] fast path skips
+//! the encoder and goes out as uncompressed `WireToSurface1` rectangles.
+//!
 //! # API Boundaries
 //!
 //! This module uses IronRDP types internally but exposes a clean API.
@@ -33,12 +36,32 @@ use tracing::{debug, trace, warn};
 // IronRDP types - used internally only
 use ironrdp_dvc::encode_dvc_messages;
 use ironrdp_egfx::pdu::Avc420Region;
+use ironrdp_pdu::geometry::InclusiveRectangle;
 use ironrdp_server::{EgfxServerMessage, GfxServerHandle, ServerEvent};
 use ironrdp_svc::ChannelFlags;
 
 use crate::damage::DamageRegion;
 use crate::server::gfx_factory::HandlerState;
 
+/// Largest width and height of a damage region sent uncompressed
+///
+/// Caret blinks, cursor trails and typed characters fit in a few regions
+/// this size; sending their pixels as they are is faster than an H.264
+/// encode and keeps a GPU encoder asleep while typing.
+pub const SMALL_UPDATE_MAX_SIZE: u32 = 64;
+
+/// Most regions sent uncompressed in one frame (64 KiB of pixels at most)
+const SMALL_UPDATE_MAX_REGIONS: usize = 4;
+
+/// Whether `regions` are few and small enough to send uncompressed
+pub fn is_small_update(regions: &[DamageRegion]) -> bool {
+    !regions.is_empty()
+        && regions.len() <= SMALL_UPDATE_MAX_REGIONS
+        && regions
+            .iter()
+            .all(|r| r.width <= SMALL_UPDATE_MAX_SIZE && r.height <= SMALL_UPDATE_MAX_SIZE)
+}
+
 /// Result type for frame sending operations
 pub type SendResult<T> = Result<T, SendError>;
 
@@ -780,6 +803,125 @@ impl EgfxFrameSender {
 
         Ok(frame_id)
     }
+
+    /// Send damage regions as uncompressed pixels, without the encoder
+    ///
+    /// For updates passing [`is_small_update`]. The pixels are BGRA, which
+    /// is the byte order of the EGFX XRGB format.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - BGRA frame the regions were detected in
+    /// * `width` / `height` - Frame dimensions (unaligned)
+    /// * `damage_regions` - Changed regions
+    /// * `timestamp_ms` - Frame timestamp
+    ///
+    /// # Returns
+    ///
+    /// `Ok((frame_id, bytes))` on success, or an error.
+    pub async fn send_uncompressed_regions(
+        &self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        damage_regions: &[DamageRegion],
+        timestamp_ms: u32,
+    ) -> SendResult<(u32, usize)> {
+        let state = self
+            .handler_state
+            .read()
+            .await
+            .as_ref()
+            .cloned()
+            .ok_or(SendError::NotReady)?;
+
+        if !state.is_ready {
+            return Err(SendError::NotReady);
+        }
+
+        let surface_id = state.primary_surface_id.ok_or(SendError::NoSurface)?;
+
+        let rects: Vec<(InclusiveRectangle, Vec<u8>)> = damage_regions
+            .iter()
+            .filter_map(|r| region_pixels(data, width, height, r))
+            .collect();
+        if rects.is_empty() {
+            return Err(SendError::EncodingFailed("empty regions".to_string()));
+        }
+        let bytes = rects.iter().map(|(_, pixels)| pixels.len()).sum();
+
+        let (frame_id, dvc_messages, channel_id) = {
+            let mut server = self.gfx_server.lock().map_err(|_| SendError::LockFailed)?;
+            let channel_id = server.channel_id().ok_or(SendError::NotReady)?;
+
+            let frame_id = server
+                .send_uncompressed_frame(surface_id, &rects, timestamp_ms)
+                .ok_or(SendError::Backpressure)?;
+
+            let messages = server.drain_output();
+            (frame_id, messages, channel_id)
+        };
+
+        if !dvc_messages.is_empty() {
+            let svc_messages =
+                encode_dvc_messages(channel_id, dvc_messages, ChannelFlags::SHOW_PROTOCOL)
+                    .map_err(|e| SendError::EncodingFailed(e.to_string()))?;
+
+            let event = ServerEvent::Egfx(EgfxServerMessage::SendMessages {
+                channel_id,
+                messages: svc_messages,
+            });
+
+            self.event_tx
+                .send(event)
+                .map_err(|_| SendError::ChannelClosed)?;
+        }
+
+        trace!(
+            "EGFX: Sent {} uncompressed regions ({} bytes) as frame {}",
+            rects.len(),
+            bytes,
+            frame_id
+        );
+        self.frame_count
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Ok((frame_id, bytes))
+    }
+}
+
+/// Copy a region's pixels out of a BGRA frame, clamped to the frame
+///
+/// Returns the inclusive destination rectangle and the region's rows,
+/// packed without padding.
+fn region_pixels(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    region: &DamageRegion,
+) -> Option<(InclusiveRectangle, Vec<u8>)> {
+    let right = (region.x + region.width).min(width);
+    let bottom = (region.y + region.height).min(height);
+    if region.x >= right || region.y >= bottom {
+        return None;
+    }
+
+    let stride = width as usize * 4;
+    let row_start = region.x as usize * 4;
+    let row_end = right as usize * 4;
+    let mut pixels = Vec::with_capacity((row_end - row_start) * (bottom - region.y) as usize);
+    for y in region.y as usize..bottom as usize {
+        let row = data.get(y * stride + row_start..y * stride + row_end)?;
+        pixels.extend_from_slice(row);
+    }
+
+    let rect = InclusiveRectangle {
+        left: region.x as u16,
+        top: region.y as u16,
+        right: (right - 1) as u16,
+        bottom: (bottom - 1) as u16,
+    };
+    Some((rect, pixels))
 }
 
 /// Convert DamageRegion list to Avc420Region list
@@ -839,4 +981,27 @@ mod tests {
             "Frame dropped due to backpressure"
         );
     }
+
+    #[test]
+    fn test_small_update_regions() {
+        // 4×3 frame, pixel value = index
+        let data: Vec<u8> = (0..4 * 3 * 4).map(|i| i as u8).collect();
+
+        let (rect, pixels) = region_pixels(&data, 4, 3, &DamageRegion::new(1, 1, 2, 2)).unwrap();
+        assert_eq!((rect.left, rect.top, rect.right, rect.bottom), (1, 1, 2, 2));
+        assert_eq!(pixels.len(), 2 * 2 * 4);
+        assert_eq!(&pixels[..4], &data[20..24]);
+        assert_eq!(&pixels[8..12], &data[36..40]);
+
+        // Clamped to the frame
+        let (rect, pixels) = region_pixels(&data, 4, 3, &DamageRegion::new(3, 2, 8, 8)).unwrap();
+        assert_eq!((rect.right, rect.bottom), (3, 2));
+        assert_eq!(pixels, &data[44..48]);
+        assert!(region_pixels(&data, 4, 3, &DamageRegion::new(4, 0, 1, 1)).is_none());
+
+        assert!(is_small_update(&[DamageRegion::new(0, 0, 64, 64)]));
+        assert!(!is_small_update(&[DamageRegion::new(0, 0, 65, 16)]));
+        assert!(!is_small_update(&[]));
+        assert!(!is_small_update(&[DamageRegion::new(0, 0, 8, 8); 5]));
+    }
 }
@@ -0,0 +1,452 @@
+//! Lifecycle Event Stream
+//!
+//! With `[event_stream]` enabled, the server publishes what happens to its
+//! sessions as newline-delimited JSON on a Unix socket, for desktop
+//! indicators and automation that should not parse logs:
+//!
+//! ```text
+//! $ socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/lamco-rdp-server/events.sock
+//! {"time":"2026-10-18T09:12:03.114+02:00","event":"session_started","session_id":4,"peer":"192.0.2.7:50123","kind":"primary"}
+//! {"time":"2026-10-18T09:12:04.870+02:00","event":"auth_result","session_id":4,"success":true}
+//! ```
+//!
+//! Each line holds `time`, `event` and the event's fields (see
+//! [`LifecycleEvent`]). Listeners receive the events from their connection
+//! on; nothing is replayed. One that falls more than [`BUFFER`] events
+//! behind gets a `lagged` line with the number it missed. The socket is
+//! only accessible to the server's user.
+
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use super::hooks::is_auth_failure;
+use super::session_manager::ClientKind;
+use crate::clipboard::ClipboardTransfer;
+use crate::config::types::EventStreamConfig;
+
+/// Events kept for a listener that falls behind
+pub const BUFFER: usize = 256;
+
+/// Session lifecycle event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    /// A client was admitted
+    SessionStarted {
+        /// Client ID
+        session_id: u64,
+        /// Client address
+        peer: String,
+        /// Client kind (primary, shared, separate, observer)
+        kind: String,
+    },
+    /// A session ended
+    SessionEnded {
+        /// Client ID
+        session_id: u64,
+        /// Time since the client was admitted
+        duration_secs: u64,
+        /// Error the session ended with
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// A client logged in, or failed to
+    AuthResult {
+        /// Client ID
+        session_id: u64,
+        /// Whether the client got to its desktop
+        success: bool,
+        /// Why the login failed
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// The size of the desktop sent to a client changed (or was first set)
+    ResolutionChanged {
+        /// Client ID
+        session_id: u64,
+        /// Width in pixels
+        width: u32,
+        /// Height in pixels
+        height: u32,
+    },
+    /// The video codec of a client was chosen
+    CodecNegotiated {
+        /// Client ID
+        session_id: u64,
+        /// AVC444, AVC420 or RemoteFX
+        codec: String,
+    },
+    /// Clipboard data moved between a client and the host
+    ClipboardTransfer {
+        /// Client ID
+        session_id: u64,
+        /// `to_client` or `to_host`
+        direction: String,
+        /// Format the client asked for (to the client)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        format_id: Option<u32>,
+        /// Size of the data (to the host)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bytes: Option<usize>,
+    },
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: &'a LifecycleEvent,
+}
+
+/// Publisher of lifecycle events
+///
+/// Cloned handles publish to the same listeners.
+#[derive(Debug, Clone)]
+pub struct EventStream {
+    tx: broadcast::Sender<Arc<str>>,
+}
+
+impl EventStream {
+    /// Stream from `[event_stream]`, or None when disabled
+    pub fn from_config(config: &EventStreamConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            tx: broadcast::channel(BUFFER).0,
+        })
+    }
+
+    /// Socket the events are served on
+    pub fn socket_path(config: &EventStreamConfig) -> Option<PathBuf> {
+        config.socket_path.clone().or_else(|| {
+            std::env::var_os("XDG_RUNTIME_DIR")
+                .map(|dir| Path::new(&dir).join("lamco-rdp-server").join("events.sock"))
+        })
+    }
+
+    /// Publish `event` to the current listeners
+    pub fn emit(&self, event: LifecycleEvent) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        match line(&event) {
+            Ok(line) => {
+                let _ = self.tx.send(line.into());
+            }
+            Err(e) => warn!("Failed to serialize {:?}: {}", event, e),
+        }
+    }
+
+    /// Publish the admission of a client
+    pub fn session_started(&self, session_id: u64, peer: SocketAddr, kind: ClientKind) {
+        self.emit(LifecycleEvent::SessionStarted {
+            session_id,
+            peer: peer.to_string(),
+            kind: kind.to_string(),
+        });
+    }
+
+    /// Publish the end of a session admitted at `started`
+    ///
+    /// A session that failed to log in is also reported as a failed login.
+    pub fn session_ended(&self, session_id: u64, started: Instant, result: &Result<()>) {
+        let reason = result.as_ref().err().map(|e| format!("{:#}", e));
+        if let Err(e) = result {
+            if is_auth_failure(e) {
+                self.emit(LifecycleEvent::AuthResult {
+                    session_id,
+                    success: false,
+                    reason: reason.clone(),
+                });
+            }
+        }
+        self.emit(LifecycleEvent::SessionEnded {
+            session_id,
+            duration_secs: started.elapsed().as_secs(),
+            reason,
+        });
+    }
+
+    /// Receive the events published from now on, one line each
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<str>> {
+        self.tx.subscribe()
+    }
+
+    /// Serve the events on a Unix socket at `path`
+    ///
+    /// A stale socket at the path is replaced.
+    pub async fn serve(self, path: PathBuf) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => debug!("Removed stale event socket {}", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context(format!("Failed to remove {}", path.display())),
+        }
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to bind {}", path.display()))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict {}", path.display()))?;
+        info!("📣 Event stream on {}", path.display());
+
+        loop {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept event stream listener: {}", e);
+                    continue;
+                }
+            };
+            let mut events = self.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    let line = match events.recv().await {
+                        Ok(line) => line.to_string(),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            format!("{{\"event\":\"lagged\",\"missed\":{}}}", missed)
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    };
+                    if socket.write_all(line.as_bytes()).await.is_err()
+                        || socket.write_all(b"\n").await.is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// JSON line of `event`, stamped with the current time
+fn line(event: &LifecycleEvent) -> serde_json::Result<String> {
+    serde_json::to_string(&EventLine {
+        time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        event,
+    })
+}
+
+/// Events of the client a pipeline serves
+///
+/// The primary pipeline serves successive clients, so the client is set on
+/// admission. Cloned handles share it. Without a stream, nothing is
+/// published.
+#[derive(Debug, Clone, Default)]
+pub struct SessionEvents {
+    stream: Option<EventStream>,
+    /// Client ID (0 = none)
+    session_id: Arc<AtomicU64>,
+    /// Last size published, with the client it was published for
+    resolution: Arc<Mutex<Option<(u64, u32, u32)>>>,
+}
+
+impl SessionEvents {
+    /// Events published to `stream`
+    pub fn new(stream: Option<EventStream>) -> Self {
+        Self {
+            stream,
+            ..Self::default()
+        }
+    }
+
+    /// Publish for client `session_id` from now on
+    pub fn attach(&self, session_id: u64) {
+        self.session_id.store(session_id, Ordering::Relaxed);
+    }
+
+    /// Stop publishing until the next client is attached
+    pub fn detach(&self) {
+        self.session_id.store(0, Ordering::Relaxed);
+    }
+
+    /// Note that the client completed its login
+    pub fn logged_in(&self) {
+        self.emit(|session_id| LifecycleEvent::AuthResult {
+            session_id,
+            success: true,
+            reason: None,
+        });
+    }
+
+    /// Note the size of the frames sent to the client
+    ///
+    /// Published when it differs from the last size of the same client.
+    pub fn resolution(&self, width: u32, height: u32) {
+        let session_id = self.session_id.load(Ordering::Relaxed);
+        if self.stream.is_none() || session_id == 0 {
+            return;
+        }
+        let current = Some((session_id, width, height));
+        let mut last = self.resolution.lock().unwrap_or_else(|e| e.into_inner());
+        if *last != current {
+            *last = current;
+            self.emit(|session_id| LifecycleEvent::ResolutionChanged {
+                session_id,
+                width,
+                height,
+            });
+        }
+    }
+
+    /// Note the codec chosen for the client
+    pub fn codec(&self, codec: &str) {
+        self.emit(|session_id| LifecycleEvent::CodecNegotiated {
+            session_id,
+            codec: codec.to_string(),
+        });
+    }
+
+    /// Note clipboard data moving between the client and the host
+    pub fn clipboard_transfer(&self, transfer: ClipboardTransfer) {
+        self.emit(|session_id| match transfer {
+            ClipboardTransfer::ToClient { format_id } => LifecycleEvent::ClipboardTransfer {
+                session_id,
+                direction: "to_client".to_string(),
+                format_id: Some(format_id),
+                bytes: None,
+            },
+            ClipboardTransfer::ToHost { bytes } => LifecycleEvent::ClipboardTransfer {
+                session_id,
+                direction: "to_host".to_string(),
+                format_id: None,
+                bytes: Some(bytes),
+            },
+        });
+    }
+
+    fn emit(&self, event: impl FnOnce(u64) -> LifecycleEvent) {
+        let Some(ref stream) = self.stream else {
+            return;
+        };
+        match self.session_id.load(Ordering::Relaxed) {
+            0 => {}
+            session_id => stream.emit(event(session_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> EventStream {
+        EventStream::from_config(&EventStreamConfig {
+            enabled: true,
+            socket_path: None,
+        })
+        .unwrap()
+    }
+
+    fn parse(line: &str) -> serde_json::Value {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn test_event_lines() {
+        let value = parse(
+            &line(&LifecycleEvent::SessionEnded {
+                session_id: 3,
+                duration_secs: 42,
+                reason: None,
+            })
+            .unwrap(),
+        );
+        assert_eq!(value["event"], "session_ended");
+        assert_eq!(value["session_id"], 3);
+        assert_eq!(value["duration_secs"], 42);
+        assert!(value.get("reason").is_none());
+        assert!(value["time"].as_str().is_some());
+    }
+
+    #[test]
+    fn test_failed_login_ends_session() {
+        let stream = enabled();
+        let mut rx = stream.subscribe();
+        let result = Err(anyhow::anyhow!("CredSSP: logon failure"));
+        stream.session_ended(5, Instant::now(), &result);
+
+        let auth = parse(&rx.try_recv().unwrap());
+        assert_eq!(auth["event"], "auth_result");
+        assert_eq!(auth["success"], false);
+        let ended = parse(&rx.try_recv().unwrap());
+        assert_eq!(ended["event"], "session_ended");
+        assert_eq!(ended["reason"], "CredSSP: logon failure");
+    }
+
+    #[test]
+    fn test_session_events_follow_attached_client() {
+        let stream = enabled();
+        let mut rx = stream.subscribe();
+        let events = SessionEvents::new(Some(stream));
+
+        // Nothing without a client
+        events.codec("AVC420");
+        assert!(rx.try_recv().is_err());
+
+        events.attach(7);
+        events.resolution(1920, 1080);
+        events.resolution(1920, 1080);
+        events.clipboard_transfer(ClipboardTransfer::ToHost { bytes: 12 });
+        let resolution = parse(&rx.try_recv().unwrap());
+        assert_eq!(resolution["event"], "resolution_changed");
+        assert_eq!(resolution["session_id"], 7);
+        assert_eq!(resolution["width"], 1920);
+        let clipboard = parse(&rx.try_recv().unwrap());
+        assert_eq!(clipboard["direction"], "to_host");
+        assert_eq!(clipboard["bytes"], 12);
+        assert!(rx.try_recv().is_err());
+
+        // The next client hears of the size again
+        events.attach(8);
+        events.resolution(1920, 1080);
+        assert_eq!(parse(&rx.try_recv().unwrap())["session_id"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_serve_on_socket() {
+        use tokio::io::AsyncBufReadExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.sock");
+        let stream = enabled();
+        tokio::spawn(stream.clone().serve(path.clone()));
+
+        let mut socket = None;
+        for _ in 0..50 {
+            if let Ok(connected) = tokio::net::UnixStream::connect(&path).await {
+                socket = Some(connected);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let mut lines = tokio::io::BufReader::new(socket.expect("socket not served")).lines();
+        // The listener subscribes once accepted
+        while stream.tx.receiver_count() == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        stream.emit(LifecycleEvent::AuthResult {
+            session_id: 1,
+            success: false,
+            reason: Some("logon failure".to_string()),
+        });
+        let value = parse(&lines.next_line().await.unwrap().unwrap());
+        assert_eq!(value["event"], "auth_result");
+        assert_eq!(value["success"], false);
+        assert_eq!(value["reason"], "logon failure");
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
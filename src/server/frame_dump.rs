@@ -0,0 +1,433 @@
+//! Frame Dumps
+//!
+//! Writes what a display pipeline encodes to disk on request, for color
+//! space and corruption reports that can't be judged from a screenshot of
+//! the client. `POST /v1/debug/frame-dump` on the admin API or SIGUSR1 asks
+//! every running pipeline for its next `frame_dump.frames` H.264 frames:
+//!
+//! ```text
+//! <dir>/0003-p1-m0-f0.raw.ppm      captured frame as given to the encoder
+//! <dir>/0003-p1-m0-f0.damage.ppm   the same with its damage regions outlined
+//! <dir>/0003-p1-m0-f0.h264         encoded bitstream (AVC420)
+//! <dir>/0003-p1-m0-f0.main.h264    encoded bitstreams (AVC444)
+//! <dir>/0003-p1-m0-f0.aux.h264
+//! <dir>/0003-p1-m0-f0.json         size, damage regions, codec, color settings
+//!   │     │  │  └ frame of the request
+//!   │     │  └ monitor
+//!   │     └ pipeline (one per client pipeline)
+//!   └ request
+//! ```
+//!
+//! The bitstreams are Annex B, so `ffplay` or `ffmpeg -i` read them as is.
+//! Requests closer together than `frame_dump.min_interval_secs` are
+//! refused, as each frame is several full-size images. Files are written
+//! off the pipeline task.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{info, warn};
+
+use crate::config::types::FrameDumpConfig;
+use crate::damage::DamageRegion;
+
+/// Outline colour of damage regions (RGB)
+const DAMAGE_COLOR: [u8; 3] = [255, 0, 255];
+
+/// Outline width in pixels
+const DAMAGE_OUTLINE: u32 = 2;
+
+/// Accepted frame dump request
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpRequest {
+    /// Request number, the first part of the file names
+    pub id: u64,
+    /// Directory the frames are written to
+    pub dir: PathBuf,
+    /// Frames each pipeline writes
+    pub frames: u32,
+}
+
+/// A request came too soon after the previous one
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("frame dump requested too soon, retry in {}s", .retry_after.as_secs().max(1))]
+pub struct RateLimited {
+    /// Time until requests are accepted again
+    pub retry_after: Duration,
+}
+
+struct Shared {
+    dir: PathBuf,
+    frames: u32,
+    min_interval: Duration,
+    /// Number of the latest request (0 = none yet)
+    request: AtomicU64,
+    last_request: Mutex<Option<Instant>>,
+    pipelines: AtomicU64,
+}
+
+/// Server-wide frame dump trigger
+///
+/// Cheap to clone; every pipeline gets a [`FrameDumpTap`] of its own.
+#[derive(Clone)]
+pub struct FrameDump {
+    shared: Arc<Shared>,
+}
+
+impl FrameDump {
+    /// Trigger for `[frame_dump]` (None when disabled)
+    pub fn from_config(config: &FrameDumpConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            shared: Arc::new(Shared {
+                dir: config.dir.clone(),
+                frames: config.frames.max(1),
+                min_interval: Duration::from_secs(config.min_interval_secs),
+                request: AtomicU64::new(0),
+                last_request: Mutex::new(None),
+                pipelines: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Ask every pipeline for its next frames
+    pub fn request(&self) -> Result<DumpRequest, RateLimited> {
+        let mut last = self
+            .shared
+            .last_request
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = *last {
+            let elapsed = last.elapsed();
+            if elapsed < self.shared.min_interval {
+                return Err(RateLimited {
+                    retry_after: self.shared.min_interval - elapsed,
+                });
+            }
+        }
+        *last = Some(Instant::now());
+
+        let id = self.shared.request.fetch_add(1, Ordering::SeqCst) + 1;
+        info!(
+            "📸 Frame dump {} requested: next {} frames of each pipeline to {}",
+            id,
+            self.shared.frames,
+            self.shared.dir.display()
+        );
+        Ok(DumpRequest {
+            id,
+            dir: self.shared.dir.clone(),
+            frames: self.shared.frames,
+        })
+    }
+
+    /// Tap for a new pipeline, ignoring requests made before it
+    pub fn tap(&self) -> FrameDumpTap {
+        FrameDumpTap {
+            shared: Arc::clone(&self.shared),
+            pipeline: self.shared.pipelines.fetch_add(1, Ordering::Relaxed) + 1,
+            seen: self.shared.request.load(Ordering::SeqCst),
+            remaining: 0,
+            index: 0,
+        }
+    }
+
+    /// Request a dump on every SIGUSR1 until the process exits
+    pub async fn run_on_sigusr1(self) {
+        let mut user1 = match signal(SignalKind::user_defined1()) {
+            Ok(user1) => user1,
+            Err(e) => {
+                warn!("Frame dumps unavailable on SIGUSR1: {}", e);
+                return;
+            }
+        };
+        info!(
+            "📸 Frame dumps enabled: send SIGUSR1 to write frames to {}",
+            self.shared.dir.display()
+        );
+        while user1.recv().await.is_some() {
+            if let Err(e) = self.request() {
+                warn!("📸 SIGUSR1 ignored: {}", e);
+            }
+        }
+    }
+}
+
+/// A pipeline's view of the dump requests
+pub struct FrameDumpTap {
+    shared: Arc<Shared>,
+    pipeline: u64,
+    /// Latest request this pipeline started on
+    seen: u64,
+    remaining: u32,
+    index: u32,
+}
+
+impl FrameDumpTap {
+    /// Where to dump the frame about to be encoded, if a request wants it
+    pub fn next_frame(&mut self) -> Option<DumpSlot> {
+        let request = self.shared.request.load(Ordering::SeqCst);
+        if request != self.seen {
+            self.seen = request;
+            self.remaining = self.shared.frames;
+            self.index = 0;
+        }
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = DumpSlot {
+            dir: self.shared.dir.clone(),
+            request,
+            pipeline: self.pipeline,
+            index: self.index,
+        };
+        self.index += 1;
+        Some(slot)
+    }
+}
+
+/// One frame of a dump request
+#[derive(Debug, Clone)]
+pub struct DumpSlot {
+    dir: PathBuf,
+    request: u64,
+    pipeline: u64,
+    index: u32,
+}
+
+/// A frame and what the pipeline made of it
+#[derive(Debug, Clone)]
+pub struct DumpedFrame {
+    /// Monitor the frame was captured from
+    pub monitor: u32,
+    /// Frame width
+    pub width: u32,
+    /// Frame height
+    pub height: u32,
+    /// BGRA pixels, `width * 4` bytes per row
+    pub data: Arc<Vec<u8>>,
+    /// Damage regions sent with the frame
+    pub damage: Vec<DamageRegion>,
+    /// "AVC420" or "AVC444"
+    pub codec: &'static str,
+    /// Encoded streams by file suffix ("h264", or "main.h264" and "aux.h264")
+    pub streams: Vec<(&'static str, Vec<u8>)>,
+    /// Configured color matrix (`egfx.color_matrix`)
+    pub color_matrix: String,
+    /// Configured color range (`egfx.color_range`)
+    pub color_range: String,
+}
+
+/// Sidecar of a dumped frame
+#[derive(Serialize)]
+struct FrameMetadata<'a> {
+    request: u64,
+    pipeline: u64,
+    monitor: u32,
+    frame: u32,
+    width: u32,
+    height: u32,
+    codec: &'a str,
+    color_matrix: &'a str,
+    color_range: &'a str,
+    /// [x, y, width, height]
+    damage: Vec<[u32; 4]>,
+    streams: Vec<(&'a str, usize)>,
+}
+
+impl DumpSlot {
+    /// Write `frame` in the background, logging failures
+    pub fn write(self, frame: DumpedFrame) {
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = self.write_files(&frame) {
+                warn!("📸 Frame dump {} incomplete: {:#}", self.request, e);
+            }
+        });
+    }
+
+    fn write_files(&self, frame: &DumpedFrame) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let stem = format!(
+            "{:04}-p{}-m{}-f{}",
+            self.request, self.pipeline, frame.monitor, self.index
+        );
+        let path = |suffix: &str| self.dir.join(format!("{}.{}", stem, suffix));
+
+        let mut rgb = bgra_to_rgb(&frame.data, frame.width, frame.height);
+        write_ppm(&path("raw.ppm"), frame.width, frame.height, &rgb)?;
+        for region in &frame.damage {
+            outline(&mut rgb, frame.width, frame.height, region);
+        }
+        write_ppm(&path("damage.ppm"), frame.width, frame.height, &rgb)?;
+
+        for (suffix, stream) in &frame.streams {
+            write_file(&path(suffix), stream)?;
+        }
+
+        let metadata = FrameMetadata {
+            request: self.request,
+            pipeline: self.pipeline,
+            monitor: frame.monitor,
+            frame: self.index,
+            width: frame.width,
+            height: frame.height,
+            codec: frame.codec,
+            color_matrix: &frame.color_matrix,
+            color_range: &frame.color_range,
+            damage: frame
+                .damage
+                .iter()
+                .map(|r| [r.x, r.y, r.width, r.height])
+                .collect(),
+            streams: frame
+                .streams
+                .iter()
+                .map(|(suffix, stream)| (*suffix, stream.len()))
+                .collect(),
+        };
+        write_file(&path("json"), &serde_json::to_vec_pretty(&metadata)?)?;
+
+        info!("📸 Frame dump {}: wrote {}", self.request, stem);
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Binary PPM (P6), readable by about every image viewer
+fn write_ppm(path: &Path, width: u32, height: u32, rgb: &[u8]) -> Result<()> {
+    let mut contents = Vec::with_capacity(rgb.len() + 32);
+    write!(contents, "P6\n{} {}\n255\n", width, height)?;
+    contents.extend_from_slice(rgb);
+    write_file(path, &contents)
+}
+
+fn bgra_to_rgb(bgra: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let pixels = (width * height) as usize;
+    bgra.chunks_exact(4)
+        .take(pixels)
+        .flat_map(|px| [px[2], px[1], px[0]])
+        .collect()
+}
+
+/// Draw the border of `region` into an RGB image
+fn outline(rgb: &mut [u8], width: u32, height: u32, region: &DamageRegion) {
+    let right = (region.x + region.width).min(width);
+    let bottom = (region.y + region.height).min(height);
+    for y in region.y..bottom {
+        for x in region.x..right {
+            let border = x < region.x + DAMAGE_OUTLINE
+                || y < region.y + DAMAGE_OUTLINE
+                || x + DAMAGE_OUTLINE >= right
+                || y + DAMAGE_OUTLINE >= bottom;
+            if border {
+                let offset = ((y * width + x) * 3) as usize;
+                rgb[offset..offset + 3].copy_from_slice(&DAMAGE_COLOR);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(frames: u32, min_interval_secs: u64) -> FrameDump {
+        FrameDump::from_config(&FrameDumpConfig {
+            enabled: true,
+            dir: PathBuf::from("/nonexistent"),
+            frames,
+            min_interval_secs,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_requests_are_rate_limited() {
+        assert!(FrameDump::from_config(&FrameDumpConfig::default()).is_none());
+
+        let limited = dump(1, 60);
+        assert_eq!(limited.request().unwrap().id, 1);
+        let refused = limited.request().unwrap_err();
+        assert!(refused.retry_after > Duration::from_secs(59));
+
+        let unlimited = dump(1, 0);
+        assert_eq!(unlimited.request().unwrap().id, 1);
+        assert_eq!(unlimited.request().unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_tap_yields_frames_per_request() {
+        let dump = dump(2, 0);
+        dump.request().unwrap();
+
+        // Requests before the pipeline started are not its concern
+        let mut tap = dump.tap();
+        assert!(tap.next_frame().is_none());
+
+        dump.request().unwrap();
+        let first = tap.next_frame().unwrap();
+        let second = tap.next_frame().unwrap();
+        assert!(tap.next_frame().is_none());
+        assert_eq!((first.request, first.index), (2, 0));
+        assert_eq!((second.request, second.index), (2, 1));
+
+        // Other pipelines dump the same request, under their own number
+        let mut other = dump.tap();
+        dump.request().unwrap();
+        assert_ne!(other.next_frame().unwrap().pipeline, first.pipeline);
+    }
+
+    #[test]
+    fn test_write_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let slot = DumpSlot {
+            dir: dir.path().to_path_buf(),
+            request: 7,
+            pipeline: 1,
+            index: 0,
+        };
+        let frame = DumpedFrame {
+            monitor: 0,
+            width: 8,
+            height: 8,
+            data: Arc::new(vec![0x10; 8 * 8 * 4]),
+            damage: vec![DamageRegion::new(2, 2, 4, 4)],
+            codec: "AVC420",
+            streams: vec![("h264", vec![0, 0, 0, 1, 0x67])],
+            color_matrix: "bt709".into(),
+            color_range: "limited".into(),
+        };
+        slot.write_files(&frame).unwrap();
+
+        let raw = std::fs::read(dir.path().join("0007-p1-m0-f0.raw.ppm")).unwrap();
+        assert!(raw.starts_with(b"P6\n8 8\n255\n"));
+        assert_eq!(raw.len(), 11 + 8 * 8 * 3);
+
+        let damage = std::fs::read(dir.path().join("0007-p1-m0-f0.damage.ppm")).unwrap();
+        let pixel = |image: &[u8], x: usize, y: usize| {
+            let offset = 11 + (y * 8 + x) * 3;
+            image[offset..offset + 3].to_vec()
+        };
+        assert_eq!(pixel(&damage, 2, 2), DAMAGE_COLOR);
+        assert_eq!(pixel(&damage, 0, 0), [0x10; 3]);
+        assert_eq!(pixel(&damage, 4, 4), [0x10; 3]);
+
+        let h264 = std::fs::read(dir.path().join("0007-p1-m0-f0.h264")).unwrap();
+        assert_eq!(h264, [0, 0, 0, 1, 0x67]);
+        let metadata: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("0007-p1-m0-f0.json")).unwrap())
+                .unwrap();
+        assert_eq!(metadata["damage"][0], serde_json::json!([2, 2, 4, 4]));
+        assert_eq!(metadata["codec"], "AVC420");
+    }
+}
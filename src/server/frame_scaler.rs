@@ -0,0 +1,477 @@
+//! Server-Side Frame Scaling
+//!
+//! Portal mode cannot resize the host desktop, so when the client's desktop
+//! size differs from the captured size the server has to adapt frames itself.
+//!
+//! # Policies
+//!
+//! | Policy | Aspect | Whole desktop visible | Notes |
+//! |--------|--------|-----------------------|-------|
+//! | Off | - | - | Frames sent unchanged (default) |
+//! | Fit | Preserved | Yes | Letterboxed with black bars |
+//! | Fill | Preserved | No | Edges cropped to cover the output |
+//! | Pan | 1:1 | No | Output-sized window pans with the pointer |
+//!
+//! # Quality
+//!
+//! Downscaling uses area averaging (box filter) so text stays legible and
+//! moiré is avoided; upscaling uses bilinear interpolation. Both operate on
+//! tightly packed BGRA frames in a single pass per output pixel.
+//!
+//! # Input Mapping
+//!
+//! [`FrameScaler::map_output_point`] inverts the active policy so pointer
+//! events from the client land on the right host pixel.
+
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+use crate::performance::BufferPool;
+
+/// Opaque black BGRA pixel used for letterbox bars
+const LETTERBOX_PIXEL: [u8; 4] = [0, 0, 0, 255];
+
+/// Scaling policy for mismatched client/host sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScalingPolicy {
+    /// No server-side scaling
+    #[default]
+    Off,
+    /// Scale to fit inside the output, letterboxing the remainder
+    Fit,
+    /// Scale to cover the output, cropping the overflow
+    Fill,
+    /// 1:1 pixels with an output-sized window that pans with the pointer
+    Pan,
+}
+
+impl std::fmt::Display for ScalingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Fit => write!(f, "fit"),
+            Self::Fill => write!(f, "fill"),
+            Self::Pan => write!(f, "pan"),
+        }
+    }
+}
+
+impl std::str::FromStr for ScalingPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "fit" | "letterbox" => Ok(Self::Fit),
+            "fill" | "crop" => Ok(Self::Fill),
+            "pan" | "1:1" | "native" => Ok(Self::Pan),
+            _ => Err(format!("Unknown scaling policy: {}", s)),
+        }
+    }
+}
+
+/// Where the source lands in the output for the current frame size
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    /// Source region used (x, y, width, height)
+    src: (u32, u32, u32, u32),
+    /// Destination region filled (x, y, width, height)
+    dst: (u32, u32, u32, u32),
+}
+
+/// A frame produced by the scaler
+#[derive(Debug)]
+pub struct ScaledFrame {
+    /// Tightly packed BGRA pixels
+    pub data: Vec<u8>,
+    /// Output width
+    pub width: u32,
+    /// Output height
+    pub height: u32,
+}
+
+/// Shared scaler (display pipeline + input handler)
+pub type SharedFrameScaler = Arc<Mutex<FrameScaler>>;
+
+/// Server-side frame scaler
+#[derive(Debug)]
+pub struct FrameScaler {
+    policy: ScalingPolicy,
+    output: (u32, u32),
+    /// Last source size seen (for input mapping between frames)
+    source: (u32, u32),
+    /// Pan window origin in source coordinates
+    pan: (u32, u32),
+    /// Distance from the output edge at which panning starts
+    pan_margin: u32,
+}
+
+impl FrameScaler {
+    /// Default pan margin in output pixels
+    pub const DEFAULT_PAN_MARGIN: u32 = 32;
+
+    /// Create a scaler for the given output size
+    ///
+    /// `source` is the initial capture size; it is updated from each frame.
+    pub fn new(policy: ScalingPolicy, output: (u32, u32), source: (u32, u32)) -> Self {
+        info!(
+            "📐 Server-side scaling: {} policy, {}x{} capture → {}x{} output",
+            policy, source.0, source.1, output.0, output.1
+        );
+        Self {
+            policy,
+            output: (output.0.max(1), output.1.max(1)),
+            source,
+            pan: (0, 0),
+            pan_margin: Self::DEFAULT_PAN_MARGIN,
+        }
+    }
+
+    /// Wrap into a shared handle
+    pub fn into_shared(self) -> SharedFrameScaler {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Active policy
+    pub fn policy(&self) -> ScalingPolicy {
+        self.policy
+    }
+
+    /// Output size presented to the client
+    pub fn output_size(&self) -> (u32, u32) {
+        self.output
+    }
+
+    fn placement(&self, sw: u32, sh: u32) -> Placement {
+        let (ow, oh) = self.output;
+        match self.policy {
+            ScalingPolicy::Off => Placement {
+                src: (0, 0, sw, sh),
+                dst: (0, 0, sw, sh),
+            },
+            ScalingPolicy::Fit => {
+                let scale = (ow as f64 / sw as f64).min(oh as f64 / sh as f64);
+                let dw = ((sw as f64 * scale).round() as u32).clamp(1, ow);
+                let dh = ((sh as f64 * scale).round() as u32).clamp(1, oh);
+                Placement {
+                    src: (0, 0, sw, sh),
+                    dst: ((ow - dw) / 2, (oh - dh) / 2, dw, dh),
+                }
+            }
+            ScalingPolicy::Fill => {
+                let scale = (ow as f64 / sw as f64).max(oh as f64 / sh as f64);
+                let cw = ((ow as f64 / scale).round() as u32).clamp(1, sw);
+                let ch = ((oh as f64 / scale).round() as u32).clamp(1, sh);
+                Placement {
+                    src: ((sw - cw) / 2, (sh - ch) / 2, cw, ch),
+                    dst: (0, 0, ow, oh),
+                }
+            }
+            ScalingPolicy::Pan => {
+                let cw = ow.min(sw);
+                let ch = oh.min(sh);
+                let px = self.pan.0.min(sw - cw);
+                let py = self.pan.1.min(sh - ch);
+                Placement {
+                    src: (px, py, cw, ch),
+                    dst: ((ow - cw) / 2, (oh - ch) / 2, cw, ch),
+                }
+            }
+        }
+    }
+
+    /// Scale a BGRA frame to the output size
+    ///
+    /// Returns `None` when no work is needed (policy off, or the frame already
+    /// matches the output) or when the buffer is smaller than its stated size.
+    pub fn scale(&mut self, data: &[u8], width: u32, height: u32) -> Option<ScaledFrame> {
+        if self.policy == ScalingPolicy::Off
+            || width == 0
+            || height == 0
+            || data.len() < (width * height * 4) as usize
+        {
+            return None;
+        }
+        if self.source != (width, height) {
+            debug!(
+                "Scaler source size changed: {}x{} → {}x{}",
+                self.source.0, self.source.1, width, height
+            );
+            self.source = (width, height);
+        }
+        if (width, height) == self.output {
+            return None;
+        }
+
+        let (ow, oh) = self.output;
+        let placement = self.placement(width, height);
+        let mut out = BufferPool::global().take((ow * oh * 4) as usize);
+        for _ in 0..ow * oh {
+            out.extend_from_slice(&LETTERBOX_PIXEL);
+        }
+
+        let (sx, sy, sw, sh) = placement.src;
+        let (dx, dy, dw, dh) = placement.dst;
+        if sw == dw && sh == dh {
+            copy_region(data, width, (sx, sy), &mut out, ow, (dx, dy), (dw, dh));
+        } else if dw <= sw && dh <= sh {
+            resample_area(data, width, placement.src, &mut out, ow, placement.dst);
+        } else {
+            resample_bilinear(data, width, placement.src, &mut out, ow, placement.dst);
+        }
+
+        Some(ScaledFrame {
+            data: out,
+            width: ow,
+            height: oh,
+        })
+    }
+
+    /// Map a client pointer position to capture coordinates
+    ///
+    /// In pan mode this also moves the pan window when the pointer comes
+    /// within the pan margin of the output edge.
+    pub fn map_output_point(&mut self, x: u32, y: u32) -> (u32, u32) {
+        let (sw, sh) = self.source;
+        if self.policy == ScalingPolicy::Off || sw == 0 || sh == 0 {
+            return (x, y);
+        }
+
+        let placement = self.placement(sw, sh);
+        let (sx, sy, srw, srh) = placement.src;
+        let (dx, dy, dw, dh) = placement.dst;
+        let rx = x.saturating_sub(dx).min(dw - 1);
+        let ry = y.saturating_sub(dy).min(dh - 1);
+        let mapped = (
+            sx + (rx as u64 * srw as u64 / dw as u64) as u32,
+            sy + (ry as u64 * srh as u64 / dh as u64) as u32,
+        );
+
+        if self.policy == ScalingPolicy::Pan {
+            self.pan_towards(rx, ry, dw, dh, sw - srw, sh - srh);
+        }
+
+        mapped
+    }
+
+    /// Map a capture position to output coordinates
+    ///
+    /// The inverse of [`Self::map_output_point`], for drawing the cursor.
+    /// Returns `None` for positions outside the part of the capture shown.
+    pub fn map_source_point(&self, x: u32, y: u32) -> Option<(u32, u32)> {
+        let (sw, sh) = self.source;
+        if self.policy == ScalingPolicy::Off || sw == 0 || sh == 0 {
+            return Some((x, y));
+        }
+
+        let placement = self.placement(sw, sh);
+        let (sx, sy, srw, srh) = placement.src;
+        let (dx, dy, dw, dh) = placement.dst;
+        if x < sx || y < sy || x >= sx + srw || y >= sy + srh {
+            return None;
+        }
+        Some((
+            dx + ((x - sx) as u64 * dw as u64 / srw as u64) as u32,
+            dy + ((y - sy) as u64 * dh as u64 / srh as u64) as u32,
+        ))
+    }
+
+    fn pan_towards(&mut self, rx: u32, ry: u32, dw: u32, dh: u32, max_x: u32, max_y: u32) {
+        let margin = self.pan_margin;
+        if rx < margin {
+            self.pan.0 = self.pan.0.saturating_sub(margin - rx);
+        } else if rx + margin >= dw {
+            self.pan.0 = (self.pan.0 + (rx + margin + 1 - dw)).min(max_x);
+        }
+        if ry < margin {
+            self.pan.1 = self.pan.1.saturating_sub(margin - ry);
+        } else if ry + margin >= dh {
+            self.pan.1 = (self.pan.1 + (ry + margin + 1 - dh)).min(max_y);
+        }
+    }
+}
+
+/// Copy a same-size region between BGRA buffers
+fn copy_region(
+    src: &[u8],
+    src_width: u32,
+    src_origin: (u32, u32),
+    dst: &mut [u8],
+    dst_width: u32,
+    dst_origin: (u32, u32),
+    size: (u32, u32),
+) {
+    let row_bytes = (size.0 * 4) as usize;
+    for row in 0..size.1 {
+        let s = (((src_origin.1 + row) * src_width + src_origin.0) * 4) as usize;
+        let d = (((dst_origin.1 + row) * dst_width + dst_origin.0) * 4) as usize;
+        dst[d..d + row_bytes].copy_from_slice(&src[s..s + row_bytes]);
+    }
+}
+
+/// Downscale with area averaging (box filter)
+fn resample_area(
+    src: &[u8],
+    src_width: u32,
+    src_rect: (u32, u32, u32, u32),
+    dst: &mut [u8],
+    dst_width: u32,
+    dst_rect: (u32, u32, u32, u32),
+) {
+    let (sx, sy, sw, sh) = src_rect;
+    let (dx, dy, dw, dh) = dst_rect;
+    for oy in 0..dh {
+        let y0 = sy + oy * sh / dh;
+        let y1 = (sy + (oy + 1) * sh / dh).max(y0 + 1);
+        for ox in 0..dw {
+            let x0 = sx + ox * sw / dw;
+            let x1 = (sx + (ox + 1) * sw / dw).max(x0 + 1);
+            let mut acc = [0u32; 4];
+            for y in y0..y1 {
+                let row = (y * src_width) as usize * 4;
+                for x in x0..x1 {
+                    let p = row + x as usize * 4;
+                    acc[0] += src[p] as u32;
+                    acc[1] += src[p + 1] as u32;
+                    acc[2] += src[p + 2] as u32;
+                    acc[3] += src[p + 3] as u32;
+                }
+            }
+            let n = (y1 - y0) * (x1 - x0);
+            let d = (((dy + oy) * dst_width + dx + ox) * 4) as usize;
+            for c in 0..4 {
+                dst[d + c] = ((acc[c] + n / 2) / n) as u8;
+            }
+        }
+    }
+}
+
+/// Upscale with bilinear interpolation
+fn resample_bilinear(
+    src: &[u8],
+    src_width: u32,
+    src_rect: (u32, u32, u32, u32),
+    dst: &mut [u8],
+    dst_width: u32,
+    dst_rect: (u32, u32, u32, u32),
+) {
+    let (sx, sy, sw, sh) = src_rect;
+    let (dx, dy, dw, dh) = dst_rect;
+    let step_x = sw as f32 / dw as f32;
+    let step_y = sh as f32 / dh as f32;
+    for oy in 0..dh {
+        let fy = ((oy as f32 + 0.5) * step_y - 0.5).clamp(0.0, (sh - 1) as f32);
+        let y0 = fy as u32;
+        let y1 = (y0 + 1).min(sh - 1);
+        let wy = fy - y0 as f32;
+        for ox in 0..dw {
+            let fx = ((ox as f32 + 0.5) * step_x - 0.5).clamp(0.0, (sw - 1) as f32);
+            let x0 = fx as u32;
+            let x1 = (x0 + 1).min(sw - 1);
+            let wx = fx - x0 as f32;
+
+            let px = |x: u32, y: u32| (((sy + y) * src_width + sx + x) * 4) as usize;
+            let (p00, p10, p01, p11) = (px(x0, y0), px(x1, y0), px(x0, y1), px(x1, y1));
+            let d = (((dy + oy) * dst_width + dx + ox) * 4) as usize;
+            for c in 0..4 {
+                let top = src[p00 + c] as f32 * (1.0 - wx) + src[p10 + c] as f32 * wx;
+                let bottom = src[p01 + c] as f32 * (1.0 - wx) + src[p11 + c] as f32 * wx;
+                dst[d + c] = (top * (1.0 - wy) + bottom * wy).round() as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, value: u8) -> Vec<u8> {
+        vec![value; (width * height * 4) as usize]
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        assert_eq!("fit".parse::<ScalingPolicy>(), Ok(ScalingPolicy::Fit));
+        assert_eq!("letterbox".parse::<ScalingPolicy>(), Ok(ScalingPolicy::Fit));
+        assert_eq!("FILL".parse::<ScalingPolicy>(), Ok(ScalingPolicy::Fill));
+        assert_eq!("1:1".parse::<ScalingPolicy>(), Ok(ScalingPolicy::Pan));
+        assert!("stretch".parse::<ScalingPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_off_is_passthrough() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Off, (100, 100), (200, 200));
+        assert!(scaler.scale(&solid(200, 200, 9), 200, 200).is_none());
+        assert_eq!(scaler.map_output_point(50, 50), (50, 50));
+    }
+
+    #[test]
+    fn test_fit_letterboxes() {
+        // 4:1 source into square output → bars top and bottom
+        let mut scaler = FrameScaler::new(ScalingPolicy::Fit, (8, 8), (16, 4));
+        let out = scaler.scale(&solid(16, 4, 200), 16, 4).unwrap();
+        assert_eq!((out.width, out.height), (8, 8));
+        // Row 0 is letterbox, row 4 is content
+        assert_eq!(&out.data[0..4], &LETTERBOX_PIXEL);
+        let mid = (4 * 8 * 4) as usize;
+        assert_eq!(out.data[mid], 200);
+    }
+
+    #[test]
+    fn test_fill_crops_center() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Fill, (4, 4), (8, 4));
+        // Left half 10, right half 20; center crop spans both
+        let data: Vec<u8> = (0..4)
+            .flat_map(|_| (0..8u8).flat_map(|x| if x < 4 { [10; 4] } else { [20; 4] }))
+            .collect();
+        let out = scaler.scale(&data, 8, 4).unwrap();
+        assert_eq!((out.width, out.height), (4, 4));
+        assert_eq!(out.data[0], 10);
+        assert_eq!(out.data[(3 * 4) as usize], 20);
+        // No letterbox in fill mode
+        assert!(out.data.chunks(4).all(|p| p[0] == 10 || p[0] == 20));
+    }
+
+    #[test]
+    fn test_area_downscale_averages() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Fit, (1, 1), (2, 2));
+        let data = vec![
+            0, 0, 0, 255, 100, 100, 100, 255, 100, 100, 100, 255, 200, 200, 200, 255,
+        ];
+        let out = scaler.scale(&data, 2, 2).unwrap();
+        assert_eq!(out.data, vec![100, 100, 100, 255]);
+    }
+
+    #[test]
+    fn test_bilinear_upscale_preserves_solid() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Fit, (8, 8), (2, 2));
+        let out = scaler.scale(&solid(2, 2, 77), 2, 2).unwrap();
+        assert!(out.data.iter().all(|&v| v == 77));
+    }
+
+    #[test]
+    fn test_pan_maps_and_moves() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Pan, (100, 100), (400, 400));
+        scaler.scale(&solid(400, 400, 1), 400, 400);
+        assert_eq!(scaler.map_output_point(50, 50), (50, 50));
+
+        // Push against the right edge: window pans right
+        scaler.map_output_point(99, 50);
+        assert!(scaler.map_output_point(50, 50).0 > 50);
+    }
+
+    #[test]
+    fn test_fit_input_mapping() {
+        let mut scaler = FrameScaler::new(ScalingPolicy::Fit, (100, 100), (200, 100));
+        scaler.scale(&solid(200, 100, 1), 200, 100);
+        // Content occupies rows 25..75 at half scale
+        assert_eq!(scaler.map_output_point(50, 50), (100, 50));
+        assert_eq!(scaler.map_output_point(0, 25), (0, 0));
+
+        // And back, for the cursor
+        assert_eq!(scaler.map_source_point(100, 50), Some((50, 50)));
+        assert_eq!(scaler.map_source_point(0, 0), Some((0, 25)));
+        assert_eq!(scaler.map_source_point(200, 50), None);
+    }
+}
@@ -34,7 +34,8 @@ use tokio::sync::RwLock;
 use ironrdp_egfx::server::{GraphicsPipelineHandler, GraphicsPipelineServer};
 use ironrdp_server::{GfxDvcBridge, GfxServerFactory, GfxServerHandle};
 
-use crate::egfx::LamcoGraphicsHandler;
+use crate::egfx::{FrameAckLatency, LamcoGraphicsHandler};
+use crate::protocol::PduCapture;
 
 /// Factory for creating EGFX graphics pipeline handlers
 ///
@@ -81,6 +82,12 @@ pub struct LamcoGfxFactory {
 
     /// Force AVC420-only mode due to platform quirks (e.g., RHEL 9)
     force_avc420_only: bool,
+
+    /// Frame acknowledgement latency, measured by the handler
+    ack_latency: FrameAckLatency,
+
+    /// PDU capture the handlers record capability negotiation to
+    capture: Option<PduCapture>,
 }
 
 /// Shared handler state accessible from display handler
@@ -119,6 +126,8 @@ impl LamcoGfxFactory {
             handler_state: Arc::new(RwLock::new(None)),
             server_handle: Arc::new(RwLock::new(None)),
             force_avc420_only: false,
+            ack_latency: FrameAckLatency::new(),
+            capture: None,
         }
     }
 
@@ -139,6 +148,8 @@ impl LamcoGfxFactory {
             handler_state: Arc::new(RwLock::new(None)),
             server_handle: Arc::new(RwLock::new(None)),
             force_avc420_only,
+            ack_latency: FrameAckLatency::new(),
+            capture: None,
         }
     }
 
@@ -160,6 +171,20 @@ impl LamcoGfxFactory {
     pub fn server_handle(&self) -> Arc<RwLock<Option<GfxServerHandle>>> {
         Arc::clone(&self.server_handle)
     }
+
+    /// Get the frame acknowledgement latency of the handler
+    ///
+    /// The display handler records the frames it sends here, so the
+    /// handler can time their acknowledgements.
+    pub fn ack_latency(&self) -> FrameAckLatency {
+        self.ack_latency.clone()
+    }
+
+    /// Record the capability negotiation of each connection to `capture`
+    pub fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
 }
 
 impl GfxServerFactory for LamcoGfxFactory {
@@ -167,7 +192,8 @@ impl GfxServerFactory for LamcoGfxFactory {
         // Basic mode: just return the handler without shared access
         // Note: This method is called when build_server_with_handle() returns None
         let handler =
-            LamcoGraphicsHandler::with_quirks(self.width, self.height, self.force_avc420_only);
+            LamcoGraphicsHandler::with_quirks(self.width, self.height, self.force_avc420_only)
+                .with_capture(self.capture.clone());
         Box::new(handler)
     }
 
@@ -180,7 +206,9 @@ impl GfxServerFactory for LamcoGfxFactory {
             self.height,
             Arc::clone(&self.handler_state),
             self.force_avc420_only,
-        );
+        )
+        .with_ack_latency(self.ack_latency.clone())
+        .with_capture(self.capture.clone());
 
         // Create the GraphicsPipelineServer wrapped in Arc<std::sync::Mutex<>>
         // Note: Using std::sync::Mutex (not tokio) because DvcProcessor trait
@@ -34,6 +34,7 @@ use tokio::sync::mpsc;
 use tracing::{debug, info, trace, warn};
 
 use crate::server::event_multiplexer::GraphicsFrame;
+use crate::utils::spawn_in_current_span;
 
 /// Statistics for graphics drain task
 #[derive(Debug, Clone, Default)]
@@ -63,7 +64,7 @@ pub(super) fn start_graphics_drain_task(
     mut graphics_rx: mpsc::Receiver<GraphicsFrame>,
     update_sender: mpsc::Sender<DisplayUpdate>,
 ) -> tokio::task::JoinHandle<()> {
-    tokio::spawn(async move {
+    spawn_in_current_span(async move {
         info!("🎬 Graphics drain task started (Phase 1 multiplexer)");
         let mut stats = GraphicsDrainStats::default();
 
@@ -0,0 +1,392 @@
+//! Upgrade Handoff
+//!
+//! Replaces the running server binary without refusing connections or
+//! dropping connected clients (`server.upgrade_handoff`).
+//!
+//! On SIGUSR2 the server starts the executable it was launched from (the
+//! upgraded binary, once the package manager has replaced it) with the same
+//! arguments, passes it the listening sockets and a little state, and waits
+//! for it to report that it is accepting:
+//!
+//! ```text
+//! old process                              new process
+//!   SIGUSR2
+//!   dup listeners, spawn ───── env ──────>  inherit listeners, resume IDs
+//!                                           Portal session (restore token)
+//!   stop accepting     <───── ready ──────  accepting
+//!   drain clients, exit
+//! ```
+//!
+//! The TLS and RDP protocol state of a live connection lives inside IronRDP
+//! and cannot be serialized, so established sessions are not migrated: they
+//! keep running in the old process until they disconnect, and reconnecting
+//! clients land on the new one. Until it exits, the old process keeps
+//! answering on the admin API and health sockets alongside the new one; only
+//! the client listeners stop accepting.
+//!
+//! If the new process exits or is not ready within [`READY_TIMEOUT`], it is
+//! killed and the old process carries on as before.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use super::session_manager::{next_client_id, resume_client_ids};
+
+/// Environment variable carrying [`HandoffState`] to the new process
+pub const HANDOFF_ENV: &str = "LAMCO_RDP_HANDOFF";
+
+/// How long the new process may take to start accepting
+pub const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// State passed from the old server process to its successor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandoffState {
+    /// Version of the process handing off
+    pub version: String,
+    /// PID of the process handing off
+    pub parent_pid: u32,
+    /// Inherited listening sockets by name
+    pub listeners: BTreeMap<String, RawFd>,
+    /// Socket to report readiness on
+    pub ready_fd: RawFd,
+    /// Next client ID, so IDs stay unique across the upgrade
+    pub next_client_id: u64,
+}
+
+impl HandoffState {
+    /// Take the handoff state passed by a previous server process, if any
+    ///
+    /// Removes the variable so it is not passed on to other children.
+    pub fn take_from_env() -> Result<Option<Self>> {
+        let Some(value) = std::env::var_os(HANDOFF_ENV) else {
+            return Ok(None);
+        };
+        std::env::remove_var(HANDOFF_ENV);
+        let state = serde_json::from_str(&value.to_string_lossy())
+            .with_context(|| format!("Invalid {}", HANDOFF_ENV))?;
+        Ok(Some(state))
+    }
+}
+
+/// Listening sockets that can be handed to a successor process
+///
+/// Every listener the server accepts on is bound through [`Listeners::bind`],
+/// which reuses an inherited socket for the same name and address.
+#[derive(Debug, Clone)]
+pub struct Listeners {
+    sockets: Arc<Mutex<ListenerSet>>,
+    handed_off: Arc<watch::Sender<bool>>,
+}
+
+#[derive(Debug, Default)]
+struct ListenerSet {
+    /// Inherited sockets not yet claimed
+    inherited: HashMap<String, OwnedFd>,
+    /// Sockets in use, by name
+    bound: BTreeMap<String, RawFd>,
+    /// Where to report readiness to the previous process
+    ready: Option<OwnedFd>,
+}
+
+impl Default for Listeners {
+    fn default() -> Self {
+        Self {
+            sockets: Arc::default(),
+            handed_off: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl Listeners {
+    /// No inherited sockets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take ownership of the sockets passed by a previous process
+    ///
+    /// Also continues its client ID numbering.
+    pub fn inherit(state: &HandoffState) -> Self {
+        resume_client_ids(state.next_client_id);
+        info!(
+            "♻️ Taking over from lamco-rdp-server v{} (pid {})",
+            state.version, state.parent_pid
+        );
+
+        let listeners = Self::new();
+        {
+            let mut sockets = listeners.lock();
+            for (name, &fd) in &state.listeners {
+                // SAFETY: the previous process passed these descriptors to us
+                // alone and nothing else in this process refers to them
+                let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+                sockets.inherited.insert(name.clone(), fd);
+            }
+            // SAFETY: as above
+            sockets.ready = Some(unsafe { OwnedFd::from_raw_fd(state.ready_fd) });
+        }
+        listeners
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ListenerSet> {
+        self.sockets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Listen on `addr`, reusing the inherited socket called `name` if any
+    pub fn bind(&self, name: &str, addr: SocketAddr) -> io::Result<TcpListener> {
+        let mut sockets = self.lock();
+        let listener = match sockets.inherited.remove(name) {
+            Some(fd) => {
+                let inherited = std::net::TcpListener::from(fd);
+                if inherited.local_addr()? == addr {
+                    info!("♻️ Inherited {} listener on {}", name, addr);
+                    inherited
+                } else {
+                    warn!(
+                        "Inherited {} listener is on {}, configured {}; binding anew",
+                        name,
+                        inherited.local_addr()?,
+                        addr
+                    );
+                    std::net::TcpListener::bind(addr)?
+                }
+            }
+            None => std::net::TcpListener::bind(addr)?,
+        };
+        listener.set_nonblocking(true)?;
+        sockets.bound.insert(name.to_string(), listener.as_raw_fd());
+        TcpListener::from_std(listener)
+    }
+
+    /// Report readiness to the previous process, if there is one
+    ///
+    /// Call once every listener is bound. Inherited sockets that were not
+    /// claimed (listener disabled since) are closed.
+    pub fn finish_inheritance(&self) {
+        let mut sockets = self.lock();
+        for (name, _) in sockets.inherited.drain() {
+            info!("♻️ Closing inherited {} listener (no longer used)", name);
+        }
+        if let Some(ready) = sockets.ready.take() {
+            match UnixStream::from(ready).write_all(b"R") {
+                Ok(()) => info!("♻️ Upgrade handoff complete, now accepting connections"),
+                Err(e) => warn!("Failed to report readiness to previous process: {}", e),
+            }
+        }
+    }
+
+    /// Resolve once the sockets have been handed to a successor
+    ///
+    /// Accept loops for client connections stop at this point.
+    pub async fn handed_off(&self) {
+        let mut handed_off = self.handed_off.subscribe();
+        let _ = handed_off.wait_for(|handed_off| *handed_off).await;
+    }
+
+    fn bound(&self) -> BTreeMap<String, RawFd> {
+        self.lock().bound.clone()
+    }
+}
+
+/// Starts the upgraded binary and hands the listeners over on SIGUSR2
+pub struct Upgrader {
+    exe: PathBuf,
+    args: Vec<OsString>,
+    listeners: Listeners,
+}
+
+impl Upgrader {
+    /// Prepare to re-execute the current binary with the current arguments
+    ///
+    /// Create this at startup: the executable path is resolved now, before
+    /// an upgrade replaces the file.
+    pub fn new(listeners: Listeners) -> Result<Self> {
+        let exe = std::env::current_exe().context("Cannot determine server executable")?;
+        Ok(Self {
+            exe,
+            args: std::env::args_os().skip(1).collect(),
+            listeners,
+        })
+    }
+
+    /// Start the new process and wait until it accepts connections
+    ///
+    /// Returns its PID. On success this process stops accepting clients.
+    pub async fn upgrade(&self) -> Result<u32> {
+        let bound = self.listeners.bound();
+        if bound.is_empty() {
+            anyhow::bail!("No listening sockets to hand off");
+        }
+
+        let (ready, ready_child) = UnixStream::pair().context("Failed to create ready socket")?;
+
+        // Duplicates lack close-on-exec, so only they reach the new process
+        let mut passed = Vec::new();
+        let result = (|| {
+            let mut listeners = BTreeMap::new();
+            for (name, fd) in bound {
+                let dup = nix::unistd::dup(fd).context("Failed to duplicate listener")?;
+                passed.push(dup);
+                listeners.insert(name, dup);
+            }
+            let ready_fd =
+                nix::unistd::dup(ready_child.as_raw_fd()).context("Failed to duplicate socket")?;
+            passed.push(ready_fd);
+
+            let state = HandoffState {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                parent_pid: std::process::id(),
+                listeners,
+                ready_fd,
+                next_client_id: next_client_id(),
+            };
+            tokio::process::Command::new(&self.exe)
+                .args(&self.args)
+                .env(HANDOFF_ENV, serde_json::to_string(&state)?)
+                .spawn()
+                .with_context(|| format!("Failed to start {}", self.exe.display()))
+        })();
+        for fd in passed {
+            let _ = nix::unistd::close(fd);
+        }
+        drop(ready_child);
+        let mut child = result?;
+        let pid = child.id().unwrap_or_default();
+        info!(
+            "♻️ Started {} (pid {}), waiting for it",
+            self.exe.display(),
+            pid
+        );
+
+        ready.set_nonblocking(true)?;
+        let mut ready = tokio::net::UnixStream::from_std(ready)?;
+        let mut byte = [0u8; 1];
+        let outcome = tokio::select! {
+            read = tokio::time::timeout(READY_TIMEOUT, ready.read(&mut byte)) => match read {
+                Ok(Ok(1)) => Ok(()),
+                Ok(Ok(_)) => Err(anyhow::anyhow!("new process closed the ready socket")),
+                Ok(Err(e)) => Err(anyhow::Error::new(e).context("Failed to read readiness")),
+                Err(_) => Err(anyhow::anyhow!("new process not ready within {:?}", READY_TIMEOUT)),
+            },
+            status = child.wait() => Err(anyhow::anyhow!("new process exited ({:?})", status?)),
+        };
+
+        if let Err(e) = outcome {
+            let _ = child.kill().await;
+            return Err(e);
+        }
+        self.listeners.handed_off.send_replace(true);
+        Ok(pid)
+    }
+
+    /// Hand off on SIGUSR2 until a handoff succeeds
+    pub async fn run_on_sigusr2(self) {
+        let mut user2 = match signal(SignalKind::user_defined2()) {
+            Ok(user2) => user2,
+            Err(e) => {
+                warn!("Upgrade handoff unavailable (cannot handle SIGUSR2): {}", e);
+                return;
+            }
+        };
+        info!(
+            "♻️ Upgrade handoff enabled: send SIGUSR2 to restart {} in place",
+            self.exe.display()
+        );
+
+        while user2.recv().await.is_some() {
+            info!("♻️ SIGUSR2 received, handing off to a new server process");
+            match self.upgrade().await {
+                Ok(pid) => {
+                    info!(
+                        "♻️ Handed off to pid {}; serving connected clients until they leave",
+                        pid
+                    );
+                    return;
+                }
+                Err(e) => error!("Upgrade handoff failed, still serving: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::os::fd::IntoRawFd;
+
+    #[test]
+    fn test_state_roundtrip() {
+        let state = HandoffState {
+            version: "1.0.0".to_string(),
+            parent_pid: 42,
+            listeners: BTreeMap::from([("rdp".to_string(), 5), ("shadow".to_string(), 6)]),
+            ready_fd: 7,
+            next_client_id: 12,
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<HandoffState>(&json).unwrap(), state);
+    }
+
+    #[tokio::test]
+    async fn test_inherited_listener_is_reused() {
+        let original = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = original.local_addr().unwrap();
+        let unused = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let (mut parent, child) = UnixStream::pair().unwrap();
+
+        let state = HandoffState {
+            version: "test".to_string(),
+            parent_pid: std::process::id(),
+            listeners: BTreeMap::from([
+                ("rdp".to_string(), original.into_raw_fd()),
+                ("shadow".to_string(), unused.into_raw_fd()),
+            ]),
+            ready_fd: child.into_raw_fd(),
+            next_client_id: 1,
+        };
+        let listeners = Listeners::inherit(&state);
+
+        let listener = listeners.bind("rdp", addr).unwrap();
+        assert_eq!(listener.local_addr().unwrap(), addr);
+        assert_eq!(listeners.bound().len(), 1);
+
+        listeners.finish_inheritance();
+        let mut byte = [0u8; 1];
+        parent.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"R");
+
+        // Still a working listener
+        let client = tokio::net::TcpStream::connect(addr);
+        let (accepted, _) = tokio::join!(listener.accept(), client);
+        assert!(accepted.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handed_off_resolves() {
+        let listeners = Listeners::new();
+        let waiter = tokio::spawn({
+            let listeners = listeners.clone();
+            async move { listeners.handed_off().await }
+        });
+        listeners.handed_off.send_replace(true);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
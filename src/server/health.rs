@@ -0,0 +1,587 @@
+//! Health Checks
+//!
+//! Verifies that everything a session needs is in place, for load balancers,
+//! container health checks and monitoring:
+//!
+//! - `portal` - ScreenCast and RemoteDesktop portals reachable on the session bus
+//! - `pipewire` - PipeWire daemon socket accepts connections
+//! - `encoder` - an H.264 encoder can be created and encodes a frame
+//! - `certificate` - TLS certificate and key load and the certificate is
+//!   currently valid (warns when it expires within 14 days)
+//! - `listener` - something listens on `server.listen_addr`
+//!
+//! Served as `GET /healthz` on `health.listen_addr` (200 unless a check
+//! fails, 503 otherwise) and run once by `lamco-rdp-server check`. The GUI's
+//! diagnostics run them before the server starts, where a free listen
+//! address passes the `listener` check.
+//!
+//! ```json
+//! {"status": "warn", "version": "0.1.0", "checks": [
+//!   {"name": "portal", "status": "ok", "detail": "ScreenCast v5, RemoteDesktop v2"},
+//!   {"name": "encoder", "status": "warn", "detail": "H.264 unavailable: ..."}
+//! ]}
+//! ```
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::config::Config;
+use crate::egfx::{Avc420Encoder, EncoderConfig, EncoderError};
+
+/// Reports are reused for this long so frequent probes stay cheap
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Upper bound for each check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Certificates expiring sooner than this produce a warning
+pub(crate) const CERT_EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// Outcome of a check (ordered from best to worst)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Working
+    Ok,
+    /// Working with reduced functionality or about to break
+    Warn,
+    /// Sessions cannot be served
+    Fail,
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Warn => write!(f, "warn"),
+            Self::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    /// Check name
+    pub name: &'static str,
+    /// Outcome
+    pub status: HealthStatus,
+    /// Human-readable detail
+    pub detail: String,
+}
+
+impl HealthCheck {
+    pub(super) fn new(name: &'static str, status: HealthStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Machine-readable health report
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// Worst status of all checks
+    pub status: HealthStatus,
+    /// Server version
+    pub version: &'static str,
+    /// Individual checks
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Summarize individual checks
+    pub fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        Self {
+            status: checks
+                .iter()
+                .map(|c| c.status)
+                .max()
+                .unwrap_or(HealthStatus::Ok),
+            version: env!("CARGO_PKG_VERSION"),
+            checks,
+        }
+    }
+
+    /// No check failed (warnings are still healthy)
+    pub fn is_healthy(&self) -> bool {
+        self.status != HealthStatus::Fail
+    }
+}
+
+/// Runs health checks for a configuration
+pub struct HealthChecker {
+    config: Arc<Config>,
+    cached: Mutex<Option<(Instant, HealthReport)>>,
+}
+
+impl HealthChecker {
+    /// Create a checker for `config`
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Run all checks now
+    pub async fn check(&self) -> HealthReport {
+        let (portal, pipewire, encoder) =
+            tokio::join!(check_portal(), check_pipewire(), check_encoder());
+        let certificate = check_certificate(
+            &self.config.security.cert_path,
+            &self.config.security.key_path,
+            Utc::now(),
+        );
+        let listener = check_listener(&self.config);
+
+        HealthReport::from_checks(vec![portal, pipewire, encoder, certificate, listener])
+    }
+
+    /// Run all checks for a server that may not be started yet
+    ///
+    /// Same as [`check`](Self::check), except that a listen address nobody
+    /// listens on passes as long as it can be bound.
+    pub async fn check_before_start(&self) -> HealthReport {
+        let mut report = self.check().await;
+        for check in &mut report.checks {
+            if check.name == "listener" && check.status == HealthStatus::Fail {
+                *check = check_bindable(&self.config);
+            }
+        }
+        HealthReport::from_checks(report.checks)
+    }
+
+    /// Latest report, re-running the checks when it is older than 5s
+    pub async fn cached_check(&self) -> HealthReport {
+        let mut cached = self.cached.lock().await;
+        if let Some((at, ref report)) = *cached {
+            if at.elapsed() < CACHE_TTL {
+                return report.clone();
+            }
+        }
+        let report = self.check().await;
+        *cached = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    /// Serve `GET /healthz` on `listener` until the process exits
+    pub async fn serve(self: Arc<Self>, listener: tokio::net::TcpListener) -> Result<()> {
+        let listen_addr = listener
+            .local_addr()
+            .context("Health endpoint listener has no address")?;
+        let app = Router::new()
+            .route("/healthz", get(healthz))
+            .with_state(self);
+
+        info!("🩺 Health endpoint: http://{}/healthz", listen_addr);
+        axum::serve(listener, app)
+            .await
+            .context("Health endpoint error")
+    }
+}
+
+async fn healthz(State(checker): State<Arc<HealthChecker>>) -> (StatusCode, Json<HealthReport>) {
+    let report = checker.cached_check().await;
+    let status = if report.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+async fn check_portal() -> HealthCheck {
+    const NAME: &str = "portal";
+
+    let versions = tokio::time::timeout(CHECK_TIMEOUT, async {
+        let connection = zbus::Connection::session()
+            .await
+            .context("No session bus")?;
+        let screencast = portal_version(&connection, "org.freedesktop.portal.ScreenCast").await?;
+        let remote_desktop =
+            portal_version(&connection, "org.freedesktop.portal.RemoteDesktop").await?;
+        anyhow::Ok((screencast, remote_desktop))
+    })
+    .await;
+
+    match versions {
+        Ok(Ok((screencast, remote_desktop))) => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!(
+                "ScreenCast v{}, RemoteDesktop v{}",
+                screencast, remote_desktop
+            ),
+        ),
+        Ok(Err(e)) => HealthCheck::new(NAME, HealthStatus::Fail, format!("{:#}", e)),
+        Err(_) => HealthCheck::new(NAME, HealthStatus::Fail, "portal did not respond"),
+    }
+}
+
+async fn portal_version(connection: &zbus::Connection, interface: &'static str) -> Result<u32> {
+    let proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new(connection)
+        .destination("org.freedesktop.portal.Desktop")?
+        .path("/org/freedesktop/portal/desktop")?
+        .interface(interface)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    proxy
+        .get_property::<u32>("version")
+        .await
+        .with_context(|| format!("{} unavailable", interface))
+}
+
+/// PipeWire daemon socket, following libpipewire's lookup rules
+fn pipewire_socket_path() -> Option<PathBuf> {
+    let remote = std::env::var("PIPEWIRE_REMOTE").unwrap_or_else(|_| "pipewire-0".to_string());
+    if remote.starts_with('/') {
+        return Some(PathBuf::from(remote));
+    }
+    let dir =
+        std::env::var_os("PIPEWIRE_RUNTIME_DIR").or_else(|| std::env::var_os("XDG_RUNTIME_DIR"))?;
+    Some(PathBuf::from(dir).join(remote))
+}
+
+async fn check_pipewire() -> HealthCheck {
+    const NAME: &str = "pipewire";
+
+    let Some(path) = pipewire_socket_path() else {
+        return HealthCheck::new(NAME, HealthStatus::Fail, "XDG_RUNTIME_DIR not set");
+    };
+    match tokio::time::timeout(CHECK_TIMEOUT, tokio::net::UnixStream::connect(&path)).await {
+        Ok(Ok(_)) => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!("connected to {}", path.display()),
+        ),
+        Ok(Err(e)) => HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("{}: {}", path.display(), e),
+        ),
+        Err(_) => HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("{}: connection timed out", path.display()),
+        ),
+    }
+}
+
+async fn check_encoder() -> HealthCheck {
+    const NAME: &str = "encoder";
+    const SIZE: u32 = 64;
+
+    let result = tokio::task::spawn_blocking(|| {
+        let mut encoder = Avc420Encoder::new(EncoderConfig::default())?;
+        let frame = vec![0u8; (SIZE * SIZE * 4) as usize];
+        encoder.encode_bgra(&frame, SIZE, SIZE, 0).map(|_| ())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => HealthCheck::new(NAME, HealthStatus::Ok, "H.264 (OpenH264) encoder working"),
+        // RemoteFX still works without H.264
+        Ok(Err(EncoderError::FeatureDisabled)) => HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            "H.264 unavailable: built without the h264 feature (RemoteFX only)",
+        ),
+        Ok(Err(e)) => HealthCheck::new(NAME, HealthStatus::Fail, e.to_string()),
+        Err(e) => HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("encoder check panicked: {}", e),
+        ),
+    }
+}
+
+fn check_certificate(cert_path: &Path, key_path: &Path, now: DateTime<Utc>) -> HealthCheck {
+    const NAME: &str = "certificate";
+
+    let cert = match load_certificate(cert_path, key_path) {
+        Ok(cert) => cert,
+        Err(e) => return HealthCheck::new(NAME, HealthStatus::Fail, format!("{:#}", e)),
+    };
+
+    match certificate_validity(&cert) {
+        None => HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            "loaded, but the validity period could not be read",
+        ),
+        Some((not_before, _)) if now < not_before => HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("not valid before {}", not_before),
+        ),
+        Some((_, not_after)) if now > not_after => {
+            HealthCheck::new(NAME, HealthStatus::Fail, format!("expired {}", not_after))
+        }
+        Some((_, not_after))
+            if not_after - now < chrono::Duration::days(CERT_EXPIRY_WARNING_DAYS) =>
+        {
+            HealthCheck::new(NAME, HealthStatus::Warn, format!("expires {}", not_after))
+        }
+        Some((_, not_after)) => {
+            HealthCheck::new(NAME, HealthStatus::Ok, format!("valid until {}", not_after))
+        }
+    }
+}
+
+/// Load the leaf certificate (DER) and make sure the key parses
+fn load_certificate(cert_path: &Path, key_path: &Path) -> Result<Vec<u8>> {
+    let cert_file = File::open(cert_path)
+        .with_context(|| format!("Cannot open certificate {}", cert_path.display()))?;
+    let cert = rustls_pemfile::certs(&mut BufReader::new(cert_file))
+        .next()
+        .context("No certificate found")?
+        .context("Failed to parse certificate")?;
+
+    let key_file = File::open(key_path)
+        .with_context(|| format!("Cannot open private key {}", key_path.display()))?;
+    rustls_pemfile::private_key(&mut BufReader::new(key_file))
+        .context("Failed to parse private key")?
+        .context("No private key found")?;
+
+    Ok(cert.to_vec())
+}
+
+/// Read one DER element: (tag, contents, remaining input)
+fn der_next(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let len = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let octets = (first & 0x7F) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets]
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        rest = &rest[octets..];
+        len
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+/// Validity period (notBefore, notAfter) of a DER X.509 certificate
+pub(crate) fn certificate_validity(der: &[u8]) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    const EXPLICIT_VERSION: u8 = 0xA0;
+
+    let (_, certificate, _) = der_next(der)?;
+    let (_, tbs, _) = der_next(certificate)?;
+
+    // [0] version (optional), serialNumber, signature, issuer, validity
+    let (tag, _, rest) = der_next(tbs)?;
+    let rest = if tag == EXPLICIT_VERSION {
+        der_next(rest)?.2
+    } else {
+        rest
+    };
+    let rest = der_next(rest)?.2;
+    let rest = der_next(rest)?.2;
+    let (_, validity, _) = der_next(rest)?;
+
+    let (tag, not_before, rest) = der_next(validity)?;
+    let not_before = parse_der_time(tag, not_before)?;
+    let (tag, not_after, _) = der_next(rest)?;
+    let not_after = parse_der_time(tag, not_after)?;
+    Some((not_before, not_after))
+}
+
+/// Parse UTCTime (`YYMMDDHHMMSSZ`) or GeneralizedTime (`YYYYMMDDHHMMSSZ`)
+fn parse_der_time(tag: u8, value: &[u8]) -> Option<DateTime<Utc>> {
+    const UTC_TIME: u8 = 0x17;
+    const GENERALIZED_TIME: u8 = 0x18;
+
+    let text = std::str::from_utf8(value).ok()?;
+    let full = match tag {
+        UTC_TIME => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            format!("{}{}", if year < 50 { "20" } else { "19" }, text)
+        }
+        GENERALIZED_TIME => text.to_string(),
+        _ => return None,
+    };
+    NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+fn check_listener(config: &Config) -> HealthCheck {
+    const NAME: &str = "listener";
+
+    if !config.server.reverse_connect.is_empty() {
+        return HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!(
+                "reverse connection mode (dialing {})",
+                config.server.reverse_connect
+            ),
+        );
+    }
+
+    let port = match config.server.listen_addr.parse::<SocketAddr>() {
+        Ok(addr) => addr.port(),
+        Err(e) => return HealthCheck::new(NAME, HealthStatus::Fail, e.to_string()),
+    };
+
+    // Inspect the socket table rather than connecting, which would start
+    // (and abort) an RDP session
+    let listening = ["/proc/net/tcp", "/proc/net/tcp6"].iter().any(|table| {
+        std::fs::read_to_string(table)
+            .map(|content| is_listening(&content, port))
+            .unwrap_or(false)
+    });
+
+    if listening {
+        HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!("listening on {}", config.server.listen_addr),
+        )
+    } else {
+        HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("nothing listening on {}", config.server.listen_addr),
+        )
+    }
+}
+
+/// Whether the server could listen on `server.listen_addr`
+fn check_bindable(config: &Config) -> HealthCheck {
+    const NAME: &str = "listener";
+
+    match std::net::TcpListener::bind(&config.server.listen_addr) {
+        Ok(_) => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!("{} is free to listen on", config.server.listen_addr),
+        ),
+        Err(e) => HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("cannot listen on {}: {}", config.server.listen_addr, e),
+        ),
+    }
+}
+
+/// Whether a `/proc/net/tcp{,6}` table has a listening socket on `port`
+fn is_listening(table: &str, port: u16) -> bool {
+    const TCP_LISTEN: &str = "0A";
+
+    table.lines().skip(1).any(|line| {
+        let mut fields = line.split_whitespace();
+        let local = fields.nth(1);
+        let state = fields.nth(1);
+        state == Some(TCP_LISTEN)
+            && local
+                .and_then(|local| local.rsplit_once(':'))
+                .and_then(|(_, hex_port)| u16::from_str_radix(hex_port, 16).ok())
+                == Some(port)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag, content.len() as u8];
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn certificate(not_before: &[u8], not_after: &[u8]) -> Vec<u8> {
+        let mut validity = der(0x17, not_before);
+        validity.extend(der(0x18, not_after));
+
+        let mut tbs = der(0xA0, &der(0x02, &[2]));
+        tbs.extend(der(0x02, &[0x01, 0x23]));
+        tbs.extend(der(0x30, &der(0x06, &[0x2A])));
+        tbs.extend(der(0x30, &[]));
+        tbs.extend(der(0x30, &validity));
+
+        der(0x30, &der(0x30, &tbs))
+    }
+
+    #[test]
+    fn test_certificate_validity() {
+        let cert = certificate(b"240101000000Z", b"20350101000000Z");
+        let (not_before, not_after) = certificate_validity(&cert).unwrap();
+        assert_eq!(not_before.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(not_after.to_rfc3339(), "2035-01-01T00:00:00+00:00");
+
+        assert!(certificate_validity(&cert[..cert.len() - 3]).is_none());
+        assert!(certificate_validity(b"not a certificate").is_none());
+    }
+
+    #[test]
+    fn test_is_listening() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue\n   \
+            0: 00000000:0D3D 00000000:0000 0A 00000000:00000000\n   \
+            1: 0100007F:1F90 0100007F:9C40 01 00000000:00000000\n";
+        assert!(is_listening(table, 3389));
+        // Established, not listening
+        assert!(!is_listening(table, 8080));
+        assert!(!is_listening(table, 22));
+    }
+
+    #[test]
+    fn test_check_bindable() {
+        let mut config = Config::default_config().unwrap();
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        config.server.listen_addr = taken.local_addr().unwrap().to_string();
+        assert_eq!(check_bindable(&config).status, HealthStatus::Fail);
+
+        drop(taken);
+        assert_eq!(check_bindable(&config).status, HealthStatus::Ok);
+
+        config.server.listen_addr = "not an address".to_string();
+        assert_eq!(check_bindable(&config).status, HealthStatus::Fail);
+    }
+
+    #[test]
+    fn test_report_status_is_worst_check() {
+        let report = HealthReport::from_checks(vec![
+            HealthCheck::new("a", HealthStatus::Ok, ""),
+            HealthCheck::new("b", HealthStatus::Warn, ""),
+        ]);
+        assert_eq!(report.status, HealthStatus::Warn);
+        assert!(report.is_healthy());
+
+        let report = HealthReport::from_checks(vec![
+            HealthCheck::new("a", HealthStatus::Fail, ""),
+            HealthCheck::new("b", HealthStatus::Warn, ""),
+        ]);
+        assert!(!report.is_healthy());
+        assert_eq!(
+            serde_json::to_value(&report).unwrap()["status"],
+            serde_json::json!("fail")
+        );
+    }
+}
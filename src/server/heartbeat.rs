@@ -0,0 +1,293 @@
+//! Heartbeat Status File
+//!
+//! With `[heartbeat]` enabled, the server replaces a small JSON file every
+//! `interval_secs` with its session counts and health flags, for monitoring
+//! agents that can read files but not reach the health endpoint or admin
+//! API:
+//!
+//! ```json
+//! {"updated": "2026-10-18T09:12:03+02:00", "updated_unix": 1792307523,
+//!  "interval_secs": 5, "pid": 4242, "version": "0.1.0", "uptime_secs": 3600,
+//!  "status": "ok", "healthy": true, "capture_alive": true, "shutting_down": false,
+//!  "sessions": {"clients": 1, "observers": 0, "max_clients": 5,
+//!               "admitted_total": 12, "rejected_total": 0},
+//!  "checks": {"portal": "ok", "pipewire": "ok", "encoder": "ok",
+//!             "certificate": "ok", "listener": "ok"}}
+//! ```
+//!
+//! The file is written next to its final path and renamed over it, so
+//! readers never see a partial file. It is not removed when the server
+//! stops: a file older than a few intervals means the server is gone or
+//! hung.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::health::{HealthChecker, HealthReport, HealthStatus};
+use super::housekeeping::Housekeeping;
+use super::session_manager::SessionManager;
+use super::shutdown::Shutdown;
+use crate::config::types::HeartbeatConfig;
+use crate::config::Config;
+use crate::session::SessionLiveness;
+
+/// Client counts in a heartbeat
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionCounts {
+    /// Connected clients
+    pub clients: usize,
+    /// Connected observers
+    pub observers: usize,
+    /// Client limit
+    pub max_clients: usize,
+    /// Connections admitted since start
+    pub admitted_total: u64,
+    /// Connections rejected since start
+    pub rejected_total: u64,
+}
+
+/// Content of the status file
+#[derive(Debug, Clone, Serialize)]
+pub struct HeartbeatStatus {
+    /// Time of writing (RFC 3339)
+    pub updated: String,
+    /// Time of writing (seconds since the Unix epoch)
+    pub updated_unix: u64,
+    /// Seconds until the next update
+    pub interval_secs: u64,
+    /// Server process
+    pub pid: u32,
+    /// Server version
+    pub version: &'static str,
+    /// Time since the server started
+    pub uptime_secs: u64,
+    /// Worst status of the health checks
+    pub status: HealthStatus,
+    /// No health check failed and the screen is still captured
+    pub healthy: bool,
+    /// The screen capture session is open
+    pub capture_alive: bool,
+    /// The server is ending its sessions to exit
+    pub shutting_down: bool,
+    /// Client counts
+    pub sessions: SessionCounts,
+    /// Status of each health check by name
+    pub checks: BTreeMap<&'static str, HealthStatus>,
+}
+
+impl HeartbeatStatus {
+    fn new(
+        report: &HealthReport,
+        sessions: SessionCounts,
+        capture_alive: bool,
+        shutting_down: bool,
+        uptime: Duration,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            updated: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            updated_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            interval_secs: interval.as_secs(),
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_secs: uptime.as_secs(),
+            status: report.status,
+            healthy: report.is_healthy() && capture_alive,
+            capture_alive,
+            shutting_down,
+            sessions,
+            checks: report
+                .checks
+                .iter()
+                .map(|check| (check.name, check.status))
+                .collect(),
+        }
+    }
+}
+
+/// Writes the heartbeat status file
+pub struct Heartbeat {
+    path: PathBuf,
+    interval: Duration,
+    checker: HealthChecker,
+    clients: SessionManager,
+    observers: SessionManager,
+    capture: SessionLiveness,
+    shutdown: Shutdown,
+    started: Instant,
+}
+
+impl Heartbeat {
+    /// Heartbeat per `[heartbeat]`, or None when disabled
+    ///
+    /// Warns and returns None when no path is configured and
+    /// `XDG_RUNTIME_DIR` is not set.
+    pub fn from_config(
+        config: &Arc<Config>,
+        clients: SessionManager,
+        observers: SessionManager,
+        capture: SessionLiveness,
+        shutdown: Shutdown,
+    ) -> Option<Self> {
+        let heartbeat = &config.heartbeat;
+        if !heartbeat.enabled {
+            return None;
+        }
+        let Some(path) = Self::path(heartbeat) else {
+            warn!("Heartbeat disabled: no heartbeat.path and no XDG_RUNTIME_DIR");
+            return None;
+        };
+        Some(Self {
+            path,
+            interval: Duration::from_secs(heartbeat.interval_secs),
+            checker: HealthChecker::new(Arc::clone(config)),
+            clients,
+            observers,
+            capture,
+            shutdown,
+            started: Instant::now(),
+        })
+    }
+
+    /// Status file of `[heartbeat]`
+    pub fn path(config: &HeartbeatConfig) -> Option<PathBuf> {
+        config.path.clone().or_else(|| {
+            std::env::var_os("XDG_RUNTIME_DIR")
+                .map(|dir| Path::new(&dir).join("lamco-rdp-server").join("status.json"))
+        })
+    }
+
+    /// Current status
+    pub async fn status(&self) -> HeartbeatStatus {
+        let report = self.checker.cached_check().await;
+        let sessions = SessionCounts {
+            clients: self.clients.client_count(),
+            observers: self.observers.client_count(),
+            max_clients: self.clients.max_clients(),
+            admitted_total: self.clients.admitted_total() + self.observers.admitted_total(),
+            rejected_total: self.clients.rejected_total() + self.observers.rejected_total(),
+        };
+        HeartbeatStatus::new(
+            &report,
+            sessions,
+            !self.capture.is_closed(),
+            self.shutdown.is_requested(),
+            self.started.elapsed(),
+            self.interval,
+        )
+    }
+
+    /// Update the file every interval until the task is aborted
+    pub async fn run(self, housekeeping: Housekeeping) {
+        info!(
+            "💓 Heartbeat: {} every {}s",
+            self.path.display(),
+            self.interval.as_secs()
+        );
+        let mut tick = housekeeping.ticker(self.interval);
+        let mut failing = false;
+        loop {
+            let status = self.status().await;
+            match write_atomically(&self.path, &status) {
+                Ok(()) if failing => {
+                    info!("Heartbeat file written again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Warn once per outage rather than every interval
+                Err(e) if !failing => {
+                    warn!("Failed to write heartbeat: {:#}", e);
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+            tick.tick().await;
+        }
+    }
+}
+
+/// Replace `path` with `status` without exposing a partial file
+fn write_atomically(path: &Path, status: &HeartbeatStatus) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let name = path
+        .file_name()
+        .context("Heartbeat path has no file name")?
+        .to_string_lossy();
+    let temp = dir.join(format!(".{}.tmp", name));
+    let content = serde_json::to_vec_pretty(status).context("Failed to serialize heartbeat")?;
+    std::fs::write(&temp, content)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::health::HealthCheck;
+
+    fn status(checks: Vec<HealthCheck>, capture_alive: bool) -> HeartbeatStatus {
+        HeartbeatStatus::new(
+            &HealthReport::from_checks(checks),
+            SessionCounts {
+                clients: 2,
+                observers: 1,
+                max_clients: 5,
+                admitted_total: 7,
+                rejected_total: 1,
+            },
+            capture_alive,
+            false,
+            Duration::from_secs(90),
+            Duration::from_secs(5),
+        )
+    }
+
+    #[test]
+    fn test_status_flags() {
+        let ok = status(
+            vec![
+                HealthCheck::new("portal", HealthStatus::Ok, ""),
+                HealthCheck::new("encoder", HealthStatus::Warn, "RemoteFX only"),
+            ],
+            true,
+        );
+        assert_eq!(ok.status, HealthStatus::Warn);
+        assert!(ok.healthy);
+        assert_eq!(ok.checks["encoder"], HealthStatus::Warn);
+
+        // A lost capture is unhealthy whatever the checks say
+        assert!(!status(vec![], false).healthy);
+        let failed = HealthCheck::new("portal", HealthStatus::Fail, "no session bus");
+        assert!(!status(vec![failed], true).healthy);
+    }
+
+    #[test]
+    fn test_write_replaces_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("status.json");
+
+        write_atomically(&path, &status(vec![], true)).unwrap();
+        write_atomically(&path, &status(vec![], false)).unwrap();
+
+        let value: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(value["capture_alive"], false);
+        assert_eq!(value["sessions"]["clients"], 2);
+        assert_eq!(value["interval_secs"], 5);
+        // Only the status file remains
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+}
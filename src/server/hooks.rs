@@ -0,0 +1,314 @@
+//! Session Hook Scripts
+//!
+//! Runs the `[hooks]` commands on session events so admins can plug in
+//! custom logging, VPN setup or desktop notifications:
+//!
+//! - `on_connect` when a client is admitted
+//! - `on_disconnect` when its session ends
+//! - `on_auth_failure` instead of `on_disconnect` when it failed to log in
+//!
+//! Commands run through `/bin/sh -c` in the background, never delaying the
+//! session, with the session details in `LAMCO_*` environment variables.
+//! The user is taken from the client's routing cookie, as sent before TLS.
+//!
+//! IronRDP reports a failed logon as an ordinary connection error, so auth
+//! failures are recognized from the error message.
+
+use std::net::SocketAddr;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+use super::broker::peek_username;
+use super::session_manager::ClientKind;
+use crate::config::types::HooksConfig;
+
+/// Error message fragments that mean the client failed to log in
+const AUTH_FAILURE_MARKERS: &[&str] = &[
+    "credssp",
+    "authentication",
+    "logon failure",
+    "invalid credentials",
+    "access denied",
+];
+
+/// Session event a hook runs for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// Client admitted
+    Connect,
+    /// Session ended
+    Disconnect,
+    /// Client failed to log in
+    AuthFailure,
+}
+
+impl std::fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect => write!(f, "connect"),
+            Self::Disconnect => write!(f, "disconnect"),
+            Self::AuthFailure => write!(f, "auth_failure"),
+        }
+    }
+}
+
+/// Runs the configured hook commands
+#[derive(Debug, Clone)]
+pub struct SessionHooks {
+    config: Arc<HooksConfig>,
+}
+
+impl SessionHooks {
+    /// Hooks from `[hooks]`
+    pub fn from_config(config: &HooksConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+        }
+    }
+
+    fn command(&self, event: HookEvent) -> Option<&str> {
+        let command = match event {
+            HookEvent::Connect => &self.config.on_connect,
+            HookEvent::Disconnect => &self.config.on_disconnect,
+            HookEvent::AuthFailure => &self.config.on_auth_failure,
+        };
+        Some(command.trim()).filter(|command| !command.is_empty())
+    }
+
+    /// Whether any hook command is set
+    pub fn is_configured(&self) -> bool {
+        [
+            HookEvent::Connect,
+            HookEvent::Disconnect,
+            HookEvent::AuthFailure,
+        ]
+        .into_iter()
+        .any(|event| self.command(event).is_some())
+    }
+
+    /// Run `on_connect` for an admitted client
+    ///
+    /// The returned handle runs the matching end-of-session hook.
+    pub async fn connected(
+        &self,
+        stream: &TcpStream,
+        session_id: u64,
+        peer: SocketAddr,
+        kind: ClientKind,
+    ) -> HookSession {
+        // Peeking waits for the client's first packet; skip it if unused
+        let user = if self.is_configured() {
+            peek_username(stream).await.unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let session = HookSession {
+            hooks: self.clone(),
+            session_id,
+            peer,
+            kind,
+            user,
+            started: Instant::now(),
+        };
+        session.run(HookEvent::Connect, None);
+        session
+    }
+
+    fn spawn(&self, event: HookEvent, env: Vec<(&'static str, String)>) {
+        let Some(command) = self.command(event) else {
+            return;
+        };
+        let mut child = match tokio::process::Command::new("/bin/sh")
+            .arg("-c")
+            .arg(command)
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Failed to run {} hook: {}", event, e);
+                return;
+            }
+        };
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        tokio::spawn(async move {
+            // Read stderr until the hook exits, so one writing more than the
+            // pipe holds doesn't block until it is killed
+            let finished = async move {
+                let mut output = String::new();
+                if let Some(mut stderr) = child.stderr.take() {
+                    use tokio::io::AsyncReadExt;
+                    let _ = stderr.read_to_string(&mut output).await;
+                }
+                child.wait().await.map(|status| (status, output))
+            };
+            match tokio::time::timeout(timeout, finished).await {
+                Ok(Ok((status, _))) if status.success() => debug!("🪝 {} hook finished", event),
+                Ok(Ok((status, output))) => {
+                    warn!("🪝 {} hook failed ({}): {}", event, status, output.trim());
+                }
+                Ok(Err(e)) => warn!("🪝 {} hook failed: {}", event, e),
+                // Dropping the unfinished future drops and kills the child
+                Err(_) => warn!("🪝 {} hook killed after {:?}", event, timeout),
+            }
+        });
+    }
+}
+
+/// Hook state for one session
+#[derive(Debug)]
+pub struct HookSession {
+    hooks: SessionHooks,
+    session_id: u64,
+    peer: SocketAddr,
+    kind: ClientKind,
+    user: String,
+    started: Instant,
+}
+
+impl HookSession {
+    /// Run `on_disconnect`, or `on_auth_failure` if the client failed to log in
+    pub fn finished(&self, result: &anyhow::Result<()>) {
+        match result {
+            Ok(()) => self.run(HookEvent::Disconnect, None),
+            Err(e) if is_auth_failure(e) => {
+                self.run(HookEvent::AuthFailure, Some(format!("{:#}", e)))
+            }
+            Err(e) => self.run(HookEvent::Disconnect, Some(format!("{:#}", e))),
+        }
+    }
+
+    fn run(&self, event: HookEvent, reason: Option<String>) {
+        self.hooks.spawn(event, self.environment(event, reason));
+    }
+
+    fn environment(&self, event: HookEvent, reason: Option<String>) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("LAMCO_EVENT", event.to_string()),
+            ("LAMCO_SESSION_ID", self.session_id.to_string()),
+            ("LAMCO_USER", self.user.clone()),
+            ("LAMCO_PEER_IP", self.peer.ip().to_string()),
+            ("LAMCO_PEER_PORT", self.peer.port().to_string()),
+            ("LAMCO_SESSION_KIND", self.kind.to_string()),
+        ];
+        if event != HookEvent::Connect {
+            let duration = self.started.elapsed().as_secs();
+            env.push(("LAMCO_DURATION_SECS", duration.to_string()));
+        }
+        if let Some(reason) = reason {
+            env.push(("LAMCO_REASON", reason));
+        }
+        env
+    }
+}
+
+/// Whether a connection error means the client failed to log in
+pub(super) fn is_auth_failure(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let message = cause.to_string().to_lowercase();
+        AUTH_FAILURE_MARKERS
+            .iter()
+            .any(|marker| message.contains(marker))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(hooks: &HooksConfig) -> HookSession {
+        HookSession {
+            hooks: SessionHooks::from_config(hooks),
+            session_id: 9,
+            peer: SocketAddr::from(([192, 0, 2, 7], 50123)),
+            kind: ClientKind::Primary,
+            user: "alice".to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_environment() {
+        let session = session(&HooksConfig::default());
+        let env = session.environment(HookEvent::AuthFailure, Some("bad".to_string()));
+        let get = |key| env.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("LAMCO_EVENT"), Some("auth_failure"));
+        assert_eq!(get("LAMCO_SESSION_ID"), Some("9"));
+        assert_eq!(get("LAMCO_USER"), Some("alice"));
+        assert_eq!(get("LAMCO_PEER_IP"), Some("192.0.2.7"));
+        assert_eq!(get("LAMCO_SESSION_KIND"), Some("primary"));
+        assert_eq!(get("LAMCO_REASON"), Some("bad"));
+        assert!(get("LAMCO_DURATION_SECS").is_some());
+
+        let env = session.environment(HookEvent::Connect, None);
+        assert!(!env.iter().any(|(k, _)| *k == "LAMCO_DURATION_SECS"));
+    }
+
+    #[test]
+    fn test_auth_failure_detection() {
+        let auth = anyhow::anyhow!("CredSSP: logon failure").context("connection failed");
+        assert!(is_auth_failure(&auth));
+        assert!(!is_auth_failure(&anyhow::anyhow!(
+            "connection reset by peer"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_hook_runs_with_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("hook.out");
+        let hooks = HooksConfig {
+            on_disconnect: format!(
+                "echo \"$LAMCO_EVENT $LAMCO_USER $LAMCO_PEER_IP\" > {}",
+                out.display()
+            ),
+            timeout_secs: 5,
+            ..HooksConfig::default()
+        };
+        session(&hooks).finished(&Ok(()));
+
+        for _ in 0..50 {
+            if let Ok(written) = std::fs::read_to_string(&out) {
+                if !written.is_empty() {
+                    assert_eq!(written.trim(), "disconnect alice 192.0.2.7");
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("hook did not run");
+    }
+
+    #[tokio::test]
+    async fn test_hook_stderr_beyond_pipe_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("hook.out");
+        // More stderr than a pipe holds, then a marker
+        let hooks = HooksConfig {
+            on_connect: format!(
+                "head -c 262144 /dev/zero >&2; echo done > {}",
+                out.display()
+            ),
+            timeout_secs: 30,
+            ..HooksConfig::default()
+        };
+        session(&hooks).run(HookEvent::Connect, None);
+
+        for _ in 0..100 {
+            if std::fs::read_to_string(&out).is_ok_and(|written| !written.is_empty()) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("hook blocked on stderr");
+    }
+}
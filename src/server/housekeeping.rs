@@ -0,0 +1,181 @@
+//! Housekeeping Tick
+//!
+//! Each connection runs periodic checks: the dead-peer watchdog samples
+//! `TCP_INFO` every second, the resource monitor every two. On timers of
+//! their own, every connected client woke the process at different moments.
+//! They now share one low-frequency tick, so the server wakes once per
+//! [`HOUSEKEEPING_PERIOD`] however many clients are connected.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::utils::wakeup_audit;
+
+/// Period of the shared tick
+pub const HOUSEKEEPING_PERIOD: Duration = Duration::from_secs(1);
+
+/// Server-wide tick that periodic work is coalesced onto
+///
+/// Cloned handles share the tick, which stops once every handle and
+/// [`Ticker`] is dropped.
+#[derive(Debug, Clone)]
+pub struct Housekeeping {
+    period: Duration,
+    ticks: watch::Receiver<u64>,
+    /// Tickers alive on the shared tick
+    tickers: Arc<AtomicUsize>,
+}
+
+impl Housekeeping {
+    /// Start ticking every `period` (requires a Tokio runtime)
+    pub fn start(period: Duration) -> Self {
+        let (tx, ticks) = watch::channel(0u64);
+        let tickers = Arc::new(AtomicUsize::new(0));
+        let waiting = Arc::clone(&tickers);
+        let report_every =
+            (wakeup_audit::REPORT_INTERVAL.as_millis() / period.as_millis().max(1)).max(1) as u64;
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    () = tx.closed() => break,
+                }
+                wakeup_audit::wakeup("housekeeping", waiting.load(Ordering::Relaxed) == 0);
+                tx.send_modify(|tick| *tick += 1);
+                if *tx.borrow() % report_every == 0 {
+                    wakeup_audit::log_report();
+                }
+            }
+        });
+
+        Self {
+            period,
+            ticks,
+            tickers,
+        }
+    }
+
+    /// Ticker firing about every `period`, on the shared tick
+    pub fn ticker(&self, period: Duration) -> Ticker {
+        let every = (period.as_millis() / self.period.as_millis().max(1)).max(1) as u64;
+        let next = *self.ticks.borrow() + every;
+        self.tickers.fetch_add(1, Ordering::Relaxed);
+        Ticker {
+            source: TickSource::Shared {
+                ticks: self.ticks.clone(),
+                every,
+                next,
+                tickers: Arc::clone(&self.tickers),
+            },
+        }
+    }
+}
+
+/// Periodic wakeup for one task
+#[derive(Debug)]
+pub struct Ticker {
+    source: TickSource,
+}
+
+#[derive(Debug)]
+enum TickSource {
+    Shared {
+        ticks: watch::Receiver<u64>,
+        every: u64,
+        next: u64,
+        tickers: Arc<AtomicUsize>,
+    },
+    Own {
+        period: Duration,
+        interval: Option<Interval>,
+    },
+}
+
+impl Ticker {
+    /// Ticker on a timer of its own, for use without a [`Housekeeping`]
+    pub fn interval(period: Duration) -> Self {
+        Self {
+            source: TickSource::Own {
+                period,
+                interval: None,
+            },
+        }
+    }
+
+    /// Wait for the next tick, one period after the previous one
+    pub async fn tick(&mut self) {
+        match &mut self.source {
+            TickSource::Shared {
+                ticks, every, next, ..
+            } => {
+                let target = *next;
+                let reached = ticks
+                    .wait_for(|tick| *tick >= target)
+                    .await
+                    .map(|tick| *tick);
+                match reached {
+                    // Ticks missed while busy are skipped, not made up for
+                    Ok(tick) => *next = tick + *every,
+                    // The tick stopped with the server
+                    Err(_) => std::future::pending().await,
+                }
+            }
+            TickSource::Own { period, interval } => {
+                let period = *period;
+                interval
+                    .get_or_insert_with(|| {
+                        let start = tokio::time::Instant::now() + period;
+                        let mut interval = tokio::time::interval_at(start, period);
+                        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                        interval
+                    })
+                    .tick()
+                    .await;
+            }
+        }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        if let TickSource::Shared { ref tickers, .. } = self.source {
+            tickers.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tickers_share_the_tick() {
+        let housekeeping = Housekeeping::start(Duration::from_millis(10));
+        let mut every = housekeeping.ticker(Duration::from_millis(10));
+        let mut second = housekeeping.ticker(Duration::from_millis(20));
+
+        let timeout = Duration::from_secs(2);
+        tokio::time::timeout(timeout, every.tick()).await.unwrap();
+        tokio::time::timeout(timeout, second.tick()).await.unwrap();
+        // The shared tick is past the first ticker's next target by now
+        let tick = *housekeeping.ticks.borrow();
+        assert!(tick >= 2);
+        tokio::time::timeout(timeout, every.tick()).await.unwrap();
+        assert!(*housekeeping.ticks.borrow() >= tick);
+
+        assert_eq!(housekeeping.tickers.load(Ordering::Relaxed), 2);
+        drop(second);
+        assert_eq!(housekeeping.tickers.load(Ordering::Relaxed), 1);
+
+        let mut own = Ticker::interval(Duration::from_millis(5));
+        tokio::time::timeout(timeout, own.tick()).await.unwrap();
+    }
+}
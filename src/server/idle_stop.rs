@@ -0,0 +1,109 @@
+//! Idle Full Stop
+//!
+//! The primary pipeline outlives its sessions, and with nobody connected
+//! its capture and encoding only burn CPU. Once no client has been
+//! connected for `server.idle_stop_secs`, the pipeline:
+//!
+//! - stops its PipeWire streams (or screencopy capture),
+//! - drops its H.264 encoder and damage history, and
+//! - sleeps until the next client connects, without polling.
+//!
+//! The Portal session stays open, so the next connection re-arms capture
+//! on the same screencast nodes without a new permission dialog. Its first
+//! frame comes from freshly negotiated streams and a new encoder.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use crate::config::types::ServerConfig;
+
+/// Connected clients of a pipeline, and how long it may run without any
+///
+/// Cloned handles share state.
+#[derive(Debug, Clone)]
+pub struct IdleStop {
+    delay: Duration,
+    clients: Arc<watch::Sender<usize>>,
+}
+
+impl IdleStop {
+    /// Stop after `delay` without clients
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            clients: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Idle stop from `server.idle_stop_secs` (None = disabled)
+    pub fn from_config(config: &ServerConfig) -> Option<Self> {
+        (config.idle_stop_secs > 0).then(|| Self::new(Duration::from_secs(config.idle_stop_secs)))
+    }
+
+    /// Time without clients before capture stops
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Count a client as connected until the returned guard is dropped
+    pub fn client_connected(&self) -> ClientGuard {
+        self.clients.send_modify(|count| *count += 1);
+        ClientGuard {
+            clients: Arc::clone(&self.clients),
+        }
+    }
+
+    /// Whether any client is connected
+    pub fn has_clients(&self) -> bool {
+        *self.clients.borrow() > 0
+    }
+
+    /// Resolve once a client is connected
+    pub async fn wait_for_client(&self) {
+        let mut clients = self.clients.subscribe();
+        // The sender lives in self, so the channel cannot close here
+        let _ = clients.wait_for(|count| *count > 0).await;
+    }
+}
+
+/// A connected client, counted by its [`IdleStop`] while held
+#[derive(Debug)]
+pub struct ClientGuard {
+    clients: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.clients
+            .send_modify(|count| *count = count.saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clients_counted_until_guard_dropped() {
+        let idle = IdleStop::new(Duration::from_secs(30));
+        assert!(!idle.has_clients());
+
+        let waiter = tokio::spawn({
+            let idle = idle.clone();
+            async move { idle.wait_for_client().await }
+        });
+        let first = idle.client_connected();
+        let second = idle.clone().client_connected();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("connection wakes the idle pipeline")
+            .unwrap();
+
+        drop(first);
+        assert!(idle.has_clients());
+        drop(second);
+        assert!(!idle.has_clients());
+    }
+}
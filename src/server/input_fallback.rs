@@ -0,0 +1,106 @@
+//! Input Fallback Backends
+//!
+//! Input handles for the backends of `[input] fallback_chain`, created while
+//! the capture session stays up. The portal backend is a companion
+//! RemoteDesktop session like the one of hybrid sessions, libei connects to
+//! EIS through a RemoteDesktop session of its own, and wlr-direct creates
+//! virtual devices scaled to the captured streams.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::portal::PortalManager;
+use crate::session::failover::InputProvider;
+use crate::session::strategies::PortalSessionHandleImpl;
+#[cfg(any(feature = "libei", feature = "wayland"))]
+use crate::session::strategy::SessionStrategy;
+use crate::session::strategy::{ClipboardComponents, SessionHandle, SessionType, StreamInfo};
+
+/// Companion portal session for input, and for clipboard with `clipboard`
+///
+/// Not persistent: the portal asks for permission each time.
+pub(super) async fn companion_portal_session(
+    portal_manager: &PortalManager,
+    clipboard: bool,
+) -> Result<(PortalSessionHandleImpl, ClipboardComponents)> {
+    let session_id = format!("lamco-rdp-input-clipboard-{}", uuid::Uuid::new_v4());
+    let (portal_handle, _) = portal_manager
+        .create_session(session_id, None)
+        .await
+        .context("Failed to create Portal session for input+clipboard")?;
+
+    let clipboard_mgr = if clipboard {
+        Some(Arc::new(
+            lamco_portal::ClipboardManager::new()
+                .await
+                .context("Failed to create Portal clipboard manager")?,
+        ))
+    } else {
+        None
+    };
+
+    info!("Separate Portal session created for input+clipboard (non-persistent)");
+
+    let portal_session = Arc::new(RwLock::new(portal_handle.session));
+
+    // Portal input handle over the companion session, regardless of clipboard availability
+    let input_handle = PortalSessionHandleImpl::from_portal_session(
+        Arc::clone(&portal_session),
+        portal_manager.remote_desktop().clone(),
+        clipboard_mgr.clone(),
+    );
+
+    Ok((
+        input_handle,
+        ClipboardComponents {
+            manager: clipboard_mgr,
+            session: portal_session,
+        },
+    ))
+}
+
+/// Creates fallback input handles next to a running capture session
+pub(super) struct SessionInputProvider {
+    portal_manager: Arc<PortalManager>,
+}
+
+impl SessionInputProvider {
+    pub(super) fn new(portal_manager: Arc<PortalManager>) -> Self {
+        Self { portal_manager }
+    }
+}
+
+#[async_trait]
+impl InputProvider for SessionInputProvider {
+    async fn create_input(
+        &self,
+        backend: SessionType,
+        #[cfg_attr(not(feature = "wayland"), allow(unused_variables))] streams: &[StreamInfo],
+    ) -> Result<Arc<dyn SessionHandle>> {
+        match backend {
+            SessionType::Portal => {
+                // The clipboard keeps the session it started with
+                let (handle, _) = companion_portal_session(&self.portal_manager, false).await?;
+                Ok(Arc::new(handle))
+            }
+            #[cfg(feature = "libei")]
+            SessionType::Libei => {
+                crate::session::strategies::LibeiStrategy::new(None)
+                    .create_session()
+                    .await
+            }
+            #[cfg(feature = "wayland")]
+            SessionType::WlrDirect => {
+                crate::session::strategies::WlrDirectStrategy::new()
+                    .with_input_streams(streams.to_vec())
+                    .create_session()
+                    .await
+            }
+            other => bail!("{} input is not available in this build", other),
+        }
+    }
+}
@@ -85,6 +85,16 @@ use tracing::{debug, error, info, trace, warn};
 use crate::input::{
     CoordinateTransformer, InputError, KeyboardHandler, MonitorInfo, MouseButton, MouseHandler,
 };
+use crate::multimon::SharedFollowFocus;
+use crate::protocol::PduCapture;
+use crate::server::banner::{LoginBanner, ACKNOWLEDGE_SCANCODE};
+use crate::server::direct_manipulation::DirectManipulation;
+use crate::server::frame_scaler::SharedFrameScaler;
+use crate::server::quality_overlay::{OverlayHotkey, QualityOverlay};
+use crate::server::resource_limits::SessionMeter;
+use crate::server::sharing::SharingControl;
+use crate::session::{InputCapability, LockKeys};
+use crate::utils::{spawn_in_current_span, wakeup_audit};
 
 /// WRD Input Handler
 ///
@@ -108,8 +118,8 @@ pub enum InputEvent {
 /// Receives keyboard and mouse events from RDP clients and injects them
 /// into the Wayland compositor via the Portal RemoteDesktop API.
 pub struct LamcoInputHandler {
-    /// Session handle for input injection (abstraction over Portal/Mutter)
-    session_handle: Arc<dyn crate::session::SessionHandle>,
+    /// Input injection side of the shared session (abstraction over Portal/Mutter)
+    session_handle: InputCapability,
 
     /// Keyboard event handler (pub for multiplexer access)
     pub keyboard_handler: Arc<Mutex<KeyboardHandler>>,
@@ -125,6 +135,31 @@ pub struct LamcoInputHandler {
 
     /// Input event queue sender (for multiplexer - bounded with drop policy)
     input_tx: mpsc::Sender<InputEvent>,
+
+    /// Follow-focus viewport mapping (None = client sees the whole desktop)
+    follow_focus: Option<SharedFollowFocus>,
+
+    /// Server-side scaling (None = client sees capture-sized frames)
+    frame_scaler: Option<SharedFrameScaler>,
+
+    /// Discard all client input (shadowing observers)
+    view_only: bool,
+
+    /// Login banner; input is withheld from the host until it is acknowledged
+    login_banner: LoginBanner,
+
+    /// Quality overlay toggled by the Ctrl+Alt+O hotkey (None = disabled)
+    quality_overlay: Option<(QualityOverlay, OverlayHotkey)>,
+    /// Input is discarded while sharing is paused, and recorded otherwise
+    sharing: SharingControl,
+    /// Counts the input forwarded, for the session's statistics
+    session_meter: SessionMeter,
+
+    /// Touch and pen contacts acting where they land (hidden cursor mode)
+    direct_manipulation: Option<DirectManipulation>,
+
+    /// Records every event as the client sent it (None = not capturing)
+    capture: Option<PduCapture>,
 }
 
 impl LamcoInputHandler {
@@ -132,9 +167,10 @@ impl LamcoInputHandler {
     ///
     /// # Arguments
     ///
-    /// * `portal` - RemoteDesktop portal manager
-    /// * `session` - Portal session handle (must remain alive)
+    /// * `session_handle` - Input capability of the shared session
     /// * `monitors` - Monitor configuration for coordinate transformation
+    /// * `follow_focus` - Optional follow-focus viewport for client → desktop mapping
+    /// * `frame_scaler` - Optional server-side scaler for output → capture mapping
     ///
     /// # Returns
     ///
@@ -144,11 +180,13 @@ impl LamcoInputHandler {
     ///
     /// Returns error if coordinate transformer initialization fails
     pub fn new(
-        session_handle: Arc<dyn crate::session::SessionHandle>,
+        session_handle: InputCapability,
         monitors: Vec<MonitorInfo>,
         primary_stream_id: u32,
         input_tx: mpsc::Sender<InputEvent>,
         mut input_rx: mpsc::Receiver<InputEvent>,
+        follow_focus: Option<SharedFollowFocus>,
+        frame_scaler: Option<SharedFrameScaler>,
     ) -> Result<Self, InputError> {
         let keyboard_handler = Arc::new(Mutex::new(KeyboardHandler::new()));
         let mouse_handler = Arc::new(Mutex::new(MouseHandler::new()));
@@ -163,12 +201,14 @@ impl LamcoInputHandler {
 
         // Start input batching task (10ms windows for responsive typing)
         // Receives from multiplexer input queue, batches, and sends to Portal
-        let session_handle_clone = Arc::clone(&session_handle);
+        let session_handle_clone = session_handle.clone();
         let keyboard_clone = Arc::clone(&keyboard_handler);
         let mouse_clone = Arc::clone(&mouse_handler);
         let coord_clone = Arc::clone(&coordinate_transformer);
+        let follow_focus_clone = follow_focus.clone();
+        let frame_scaler_clone = frame_scaler.clone();
 
-        tokio::spawn(async move {
+        spawn_in_current_span(async move {
             let mut keyboard_batch = Vec::with_capacity(16);
             let mut mouse_batch = Vec::with_capacity(16);
             let mut last_flush = Instant::now();
@@ -176,20 +216,28 @@ impl LamcoInputHandler {
 
             loop {
                 tokio::select! {
-                    Some(event) = input_rx.recv() => {
+                    event = input_rx.recv() => {
                         match event {
-                            InputEvent::Keyboard(kbd) => {
+                            Some(InputEvent::Keyboard(kbd)) => {
                                 trace!("📥 Input queue: received keyboard event");
                                 keyboard_batch.push(kbd);
                             }
-                            InputEvent::Mouse(mouse) => {
+                            Some(InputEvent::Mouse(mouse)) => {
                                 trace!("📥 Input queue: received mouse event");
                                 mouse_batch.push(mouse);
                             }
+                            None => {
+                                // All senders dropped: the client pipeline is gone
+                                debug!("Input queue closed - stopping batching task");
+                                break;
+                            }
                         }
                     }
 
-                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(last_flush + batch_interval)) => {
+                    // Only armed with events pending, so an idle session doesn't wake every 10ms
+                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(last_flush + batch_interval)),
+                        if !keyboard_batch.is_empty() || !mouse_batch.is_empty() => {
+                        wakeup_audit::wakeup("input-flush", false);
                         // Process keyboard batch
                         if !keyboard_batch.is_empty() {
                             trace!("🔄 Input batching: flushing {} keyboard events", keyboard_batch.len());
@@ -213,6 +261,8 @@ impl LamcoInputHandler {
                                 &session_handle_clone,
                                 &mouse_clone,
                                 &coord_clone,
+                                follow_focus_clone.as_ref(),
+                                frame_scaler_clone.as_ref(),
                                 mouse_event,
                                 primary_stream_id
                             ).await {
@@ -235,9 +285,72 @@ impl LamcoInputHandler {
             coordinate_transformer,
             primary_stream_id,
             input_tx,
+            follow_focus,
+            frame_scaler,
+            view_only: false,
+            login_banner: LoginBanner::disabled(),
+            quality_overlay: None,
+            sharing: SharingControl::new(),
+            session_meter: SessionMeter::new(),
+            direct_manipulation: None,
+            capture: None,
         })
     }
 
+    /// Discard all keyboard and mouse input from this client
+    ///
+    /// Used for shadowing observers, which may watch but not control. A
+    /// pending login banner still takes their acknowledgment.
+    pub fn with_view_only(mut self, view_only: bool) -> Self {
+        if view_only {
+            info!("Input handler is view-only - client input will be discarded");
+        }
+        self.view_only = view_only;
+        self
+    }
+
+    /// Withhold input until the login banner is acknowledged
+    ///
+    /// Releasing Enter or the left mouse button acknowledges the banner, so
+    /// the whole press/release pair stays with the server.
+    pub fn with_login_banner(mut self, login_banner: LoginBanner) -> Self {
+        self.login_banner = login_banner;
+        self
+    }
+
+    /// Toggle the connection quality overlay with Ctrl+Alt+O
+    pub fn with_quality_overlay(mut self, quality_overlay: Option<QualityOverlay>) -> Self {
+        self.quality_overlay = quality_overlay.map(|overlay| (overlay, OverlayHotkey::default()));
+        self
+    }
+
+    /// Report input activity to, and stop input while paused by, `sharing`
+    pub fn with_sharing(mut self, sharing: SharingControl) -> Self {
+        self.sharing = sharing;
+        self
+    }
+
+    /// Count forwarded input on the pipeline's `meter`
+    pub fn with_session_meter(mut self, meter: SessionMeter) -> Self {
+        self.session_meter = meter;
+        self
+    }
+
+    /// Treat pointer input as touch and pen contacts (hidden cursor mode)
+    pub fn with_direct_manipulation(
+        mut self,
+        direct_manipulation: Option<DirectManipulation>,
+    ) -> Self {
+        self.direct_manipulation = direct_manipulation;
+        self
+    }
+
+    /// Record client input to a PDU capture
+    pub fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
     /// Update coordinate transformer when monitor configuration changes
     ///
     /// This should be called when the RDP client requests a different resolution
@@ -251,7 +364,7 @@ impl LamcoInputHandler {
 
     /// Handle keyboard event implementation (static for batching task)
     async fn handle_keyboard_event_impl(
-        session_handle: &Arc<dyn crate::session::SessionHandle>,
+        session_handle: &InputCapability,
         keyboard_handler: &Arc<Mutex<KeyboardHandler>>,
         event: IronKeyboardEvent,
     ) -> Result<(), InputError> {
@@ -373,10 +486,18 @@ impl LamcoInputHandler {
 
             IronKeyboardEvent::Synchronize(flags) => {
                 debug!("Keyboard synchronize: {:?}", flags);
-                // Update toggle key states based on sync flags
-                // The flags tell us the client's current Caps/Num/Scroll lock states
-                // We should sync our local state but portal doesn't have direct sync API
-                // This is handled implicitly when keys are pressed
+                // The flags carry the client's Caps/Num/Scroll Lock state (TS_SYNC_EVENT).
+                // Strategies that track the compositor's locks toggle mismatches;
+                // the others rely on the client's own lock key presses.
+                let bits = flags.bits();
+                let locks = LockKeys {
+                    scroll_lock: bits & 0x01 != 0,
+                    num_lock: bits & 0x02 != 0,
+                    caps_lock: bits & 0x04 != 0,
+                };
+                session_handle.sync_lock_keys(locks).await.map_err(|e| {
+                    InputError::PortalError(format!("Failed to sync lock keys: {}", e))
+                })?;
             }
         }
 
@@ -386,9 +507,11 @@ impl LamcoInputHandler {
     /// Handle mouse event with full error handling and logging
     /// Handle mouse event implementation (static for batching task)
     async fn handle_mouse_event_impl(
-        session_handle: &Arc<dyn crate::session::SessionHandle>,
+        session_handle: &InputCapability,
         mouse_handler: &Arc<Mutex<MouseHandler>>,
         coordinate_transformer: &Arc<Mutex<CoordinateTransformer>>,
+        follow_focus: Option<&SharedFollowFocus>,
+        frame_scaler: Option<&SharedFrameScaler>,
         event: IronMouseEvent,
         stream_id: u32,
     ) -> Result<(), InputError> {
@@ -399,9 +522,32 @@ impl LamcoInputHandler {
             IronMouseEvent::Move { x, y } => {
                 debug!("Mouse move: x={}, y={}", x, y);
 
+                // Scaling: undo fit/fill/pan so coordinates match the captured frame
+                let (x, y) = match frame_scaler {
+                    Some(frame_scaler) => frame_scaler
+                        .lock()
+                        .map(|mut scaler| {
+                            let (sx, sy) = scaler.map_output_point(x as u32, y as u32);
+                            (
+                                sx.min(u16::MAX as u32) as u16,
+                                sy.min(u16::MAX as u32) as u16,
+                            )
+                        })
+                        .unwrap_or((x, y)),
+                    None => (x, y),
+                };
+
+                // Follow-focus: client coordinates are relative to the viewport
+                let (x, y) = match follow_focus {
+                    Some(follow_focus) => follow_focus
+                        .lock()
+                        .map(|mut ctl| ctl.map_client_point(x, y))
+                        .unwrap_or((x as u32, y as u32)),
+                    None => (x as u32, y as u32),
+                };
+
                 // Process absolute move through mouse handler
-                let mouse_event =
-                    mouse.handle_absolute_move(x as u32, y as u32, &mut transformer)?;
+                let mouse_event = mouse.handle_absolute_move(x, y, &mut transformer)?;
 
                 // Extract coordinates from our event
                 let (stream_x, stream_y) = match mouse_event {
@@ -600,6 +746,42 @@ impl LamcoInputHandler {
 /// trait to async execution.
 impl RdpServerInputHandler for LamcoInputHandler {
     fn keyboard(&mut self, event: IronKeyboardEvent) {
+        if let Some(capture) = &self.capture {
+            capture.record_keyboard(&event);
+        }
+        if self.login_banner.is_pending() {
+            if matches!(
+                event,
+                IronKeyboardEvent::Released {
+                    code: ACKNOWLEDGE_SCANCODE,
+                    ..
+                }
+            ) {
+                self.login_banner.acknowledge();
+            }
+            return;
+        }
+        if self.view_only {
+            trace!("⌨️  View-only client: keyboard event discarded");
+            return;
+        }
+        if let Some((overlay, hotkey)) = self.quality_overlay.as_mut() {
+            let consumed = match event {
+                IronKeyboardEvent::Pressed { code, .. } => hotkey.key(code, true, overlay),
+                IronKeyboardEvent::Released { code, .. } => hotkey.key(code, false, overlay),
+                _ => false,
+            };
+            if consumed {
+                return;
+            }
+        }
+        if self.sharing.is_paused() {
+            trace!("⌨️  Sharing paused: keyboard event discarded");
+            return;
+        }
+        self.sharing.record_input();
+        self.session_meter.record_input_event();
+
         // Send to batching queue (processed every 10ms)
         // Use try_send (non-blocking, bounded queue)
         trace!("⌨️  Input multiplexer: routing keyboard to queue");
@@ -609,6 +791,37 @@ impl RdpServerInputHandler for LamcoInputHandler {
     }
 
     fn mouse(&mut self, event: IronMouseEvent) {
+        if let Some(capture) = &self.capture {
+            capture.record_mouse(&event);
+        }
+        if self.login_banner.is_pending() {
+            if matches!(event, IronMouseEvent::LeftReleased) {
+                self.login_banner.acknowledge();
+            }
+            return;
+        }
+        if self.view_only {
+            trace!("🖱️  View-only client: mouse event discarded");
+            return;
+        }
+        if self.sharing.is_paused() {
+            trace!("🖱️  Sharing paused: mouse event discarded");
+            return;
+        }
+        self.sharing.record_input();
+        self.session_meter.record_input_event();
+
+        let event = match self.direct_manipulation.as_ref() {
+            Some(direct) => match direct.filter(event) {
+                Some(event) => event,
+                None => {
+                    trace!("🖱️  Tap wobble held back");
+                    return;
+                }
+            },
+            None => event,
+        };
+
         // Send to batching queue (processed every 10ms)
         // Use try_send (non-blocking, bounded queue)
         trace!("🖱️  Input multiplexer: routing mouse to queue");
@@ -623,12 +836,21 @@ impl RdpServerInputHandler for LamcoInputHandler {
 impl Clone for LamcoInputHandler {
     fn clone(&self) -> Self {
         Self {
-            session_handle: Arc::clone(&self.session_handle),
+            session_handle: self.session_handle.clone(),
             keyboard_handler: Arc::clone(&self.keyboard_handler),
             mouse_handler: Arc::clone(&self.mouse_handler),
             coordinate_transformer: Arc::clone(&self.coordinate_transformer),
             primary_stream_id: self.primary_stream_id,
             input_tx: self.input_tx.clone(),
+            follow_focus: self.follow_focus.clone(),
+            frame_scaler: self.frame_scaler.clone(),
+            view_only: self.view_only,
+            login_banner: self.login_banner.clone(),
+            quality_overlay: self.quality_overlay.clone(),
+            sharing: self.sharing.clone(),
+            session_meter: self.session_meter.clone(),
+            direct_manipulation: self.direct_manipulation.clone(),
+            capture: self.capture.clone(),
         }
     }
 }
@@ -0,0 +1,238 @@
+//! Dead-Peer Detection
+//!
+//! A client that crashes or loses its NAT mapping leaves a half-open TCP
+//! connection: nothing arrives and nothing fails, so the session would keep
+//! its Portal capture and encoder until the OS gives up many minutes later.
+//!
+//! Two checks tear such connections down within seconds:
+//!
+//! - **TCP keepalive** probes idle connections (nothing in flight) and
+//!   resets them after the configured number of unanswered probes.
+//! - **Acknowledgement watchdog** while streaming: `TCP_USER_TIMEOUT` makes
+//!   the kernel abort a connection whose sent data stays unacknowledged, and
+//!   [`PeerWatch`] polls `TCP_INFO` so the session is ended (and logged) as
+//!   soon as the peer stops acknowledging.
+//!
+//! IronRDP does not let the server inject RDP heartbeat PDUs into a running
+//! connection, so liveness is judged from TCP acknowledgements rather than
+//! from application-level heartbeats.
+
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::TcpStream;
+
+use super::housekeeping::{Housekeeping, Ticker};
+use crate::config::types::ServerConfig;
+
+/// How often the watchdog samples `TCP_INFO`
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Linux `TCP_CLOSE` connection state
+const TCP_STATE_CLOSE: u8 = 7;
+
+/// Keepalive and dead-peer settings for client connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveSettings {
+    /// Idle time before keepalive probes start (None = keepalive off)
+    pub idle: Option<Duration>,
+    /// Interval between keepalive probes
+    pub interval: Duration,
+    /// Unanswered probes before the connection is reset
+    pub probes: u32,
+    /// Maximum time sent data may stay unacknowledged (None = no watchdog)
+    pub dead_peer_timeout: Option<Duration>,
+}
+
+impl KeepaliveSettings {
+    /// Settings from the `[server]` section
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let secs = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        Self {
+            idle: secs(config.tcp_keepalive_secs),
+            interval: Duration::from_secs(config.tcp_keepalive_interval_secs),
+            probes: config.tcp_keepalive_probes,
+            dead_peer_timeout: secs(config.dead_peer_timeout_secs),
+        }
+    }
+
+    /// Whether any check is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.idle.is_some() || self.dead_peer_timeout.is_some()
+    }
+
+    /// Configure keepalive and the user timeout on a client socket
+    pub fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        let socket = SockRef::from(stream);
+        if let Some(idle) = self.idle {
+            let keepalive = TcpKeepalive::new()
+                .with_time(idle)
+                .with_interval(self.interval)
+                .with_retries(self.probes);
+            socket.set_tcp_keepalive(&keepalive)?;
+        }
+        if let Some(timeout) = self.dead_peer_timeout {
+            socket.set_tcp_user_timeout(Some(timeout))?;
+        }
+        Ok(())
+    }
+
+    /// Watchdog for a client socket
+    ///
+    /// The returned watch must not outlive `stream`; poll it alongside the
+    /// future serving the connection.
+    pub fn watch(&self, stream: &TcpStream) -> PeerWatch {
+        PeerWatch {
+            fd: stream.as_raw_fd(),
+            timeout: self.dead_peer_timeout,
+            tick: Ticker::interval(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Watches a connection for a peer that stopped acknowledging data
+#[derive(Debug)]
+pub struct PeerWatch {
+    fd: RawFd,
+    timeout: Option<Duration>,
+    tick: Ticker,
+}
+
+impl PeerWatch {
+    /// Poll on the server's shared tick instead of a timer of its own
+    pub fn with_housekeeping(mut self, housekeeping: &Housekeeping) -> Self {
+        self.tick = housekeeping.ticker(POLL_INTERVAL);
+        self
+    }
+
+    /// Resolve with the reason once the peer is considered dead
+    ///
+    /// Stays pending forever when the watchdog is disabled.
+    pub async fn dead(mut self) -> String {
+        let Some(timeout) = self.timeout else {
+            return std::future::pending().await;
+        };
+
+        loop {
+            self.tick.tick().await;
+            let info = match tcp_info(self.fd) {
+                Ok(info) => info,
+                // Socket already gone: the connection future will notice
+                Err(_) => return std::future::pending().await,
+            };
+            if let Some(reason) = dead_peer_reason(
+                info.tcpi_state,
+                info.tcpi_unacked,
+                Duration::from_millis(u64::from(info.tcpi_last_ack_recv)),
+                timeout,
+            ) {
+                return reason;
+            }
+        }
+    }
+}
+
+/// Read `TCP_INFO` for a socket
+pub(super) fn tcp_info(fd: RawFd) -> io::Result<libc::tcp_info> {
+    // SAFETY: tcp_info is plain old data; all-zero is a valid value
+    let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+    // SAFETY: `info` and `len` are valid for writes of the sizes passed, and
+    // getsockopt fails with EBADF/ENOTSOCK rather than misbehaving on a stale fd
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            (&mut info as *mut libc::tcp_info).cast(),
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(info)
+}
+
+/// Decide from a `TCP_INFO` sample whether the peer is dead
+fn dead_peer_reason(
+    state: u8,
+    unacked: u32,
+    since_last_ack: Duration,
+    timeout: Duration,
+) -> Option<String> {
+    if state == TCP_STATE_CLOSE {
+        return Some("connection closed by the kernel".to_string());
+    }
+    if unacked > 0 && since_last_ack >= timeout {
+        return Some(format!(
+            "{} segment(s) unacknowledged for {}s",
+            unacked,
+            since_last_ack.as_secs()
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP_STATE_ESTABLISHED: u8 = 1;
+
+    #[test]
+    fn test_dead_peer_reason() {
+        let timeout = Duration::from_secs(20);
+
+        // Idle connection: nothing in flight, left to keepalive
+        assert!(
+            dead_peer_reason(TCP_STATE_ESTABLISHED, 0, Duration::from_secs(60), timeout).is_none()
+        );
+        // Streaming, peer acknowledging
+        assert!(dead_peer_reason(
+            TCP_STATE_ESTABLISHED,
+            12,
+            Duration::from_millis(30),
+            timeout
+        )
+        .is_none());
+        // Streaming, peer silent
+        assert!(
+            dead_peer_reason(TCP_STATE_ESTABLISHED, 12, Duration::from_secs(21), timeout).is_some()
+        );
+        assert!(dead_peer_reason(TCP_STATE_CLOSE, 0, Duration::ZERO, timeout).is_some());
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let mut config = crate::config::Config::default_config().unwrap().server;
+        let settings = KeepaliveSettings::from_config(&config);
+        assert_eq!(settings.idle, Some(Duration::from_secs(10)));
+        assert_eq!(settings.dead_peer_timeout, Some(Duration::from_secs(20)));
+
+        config.tcp_keepalive_secs = 0;
+        config.dead_peer_timeout_secs = 0;
+        assert!(!KeepaliveSettings::from_config(&config).is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_connected_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        let config = crate::config::Config::default_config().unwrap().server;
+        KeepaliveSettings::from_config(&config)
+            .apply(&server)
+            .unwrap();
+        assert!(SockRef::from(&server).keepalive().unwrap());
+
+        let info = tcp_info(server.as_raw_fd()).unwrap();
+        assert_eq!(info.tcpi_state, TCP_STATE_ESTABLISHED);
+        drop(client);
+    }
+}
@@ -0,0 +1,413 @@
+//! Link Estimation
+//!
+//! A passive estimate of what the network to a client can carry, shared by
+//! the parts of its pipeline that adapt to it. Nothing is sent to probe the
+//! link; the estimate is built from what the connection already reports:
+//!
+//! - **`TCP_INFO`**: the kernel's delivery rate, round-trip time, bytes
+//!   retransmitted and bytes still queued in the socket, sampled by the
+//!   session's [`ResourceMonitor`](super::ResourceMonitor)
+//! - **Ack pacing**: the bytes of EGFX frames the client acknowledged per
+//!   second, and how long those acknowledgements take
+//!
+//! While the pipeline sends less than the link could carry, both rates only
+//! show what was sent, so the bandwidth is the highest rate seen over the
+//! last [`MAX_FILTER_SAMPLES`] samples. A link is **congested** when data
+//! is retransmitted, piles up in the socket, or acknowledgements slow down;
+//! the bandwidth is then what actually got through, and stays so for
+//! [`CONGESTION_HOLD`].
+//!
+//! The [`BitrateController`] lowers the encoder bitrate to the estimate on
+//! congestion and raises it step by step towards `egfx.h264_bitrate` once
+//! the link is clear. Adaptive FPS caps its frame rate tier by the estimate
+//! while the link is congested.
+//!
+//! RDP's active bandwidth measurement (`RDP_BW_START`/`RDP_BW_STOP`
+//! auto-detect PDUs) is not used: the RDP stack does not expose it to the
+//! server.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info};
+
+/// Rate samples the bandwidth is the maximum of
+pub const MAX_FILTER_SAMPLES: usize = 10;
+
+/// How long a link counts as congested after the last sign of it
+pub const CONGESTION_HOLD: Duration = Duration::from_secs(5);
+
+/// Shortest interval ack pacing measures a rate over
+const ACK_RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Data queued in the socket, in time at the delivery rate, that counts as
+/// congestion
+const MAX_QUEUED: Duration = Duration::from_millis(100);
+
+/// Slack over the lowest acknowledgement latency before growth counts as
+/// queueing
+const ACK_LATENCY_SLACK: Duration = Duration::from_millis(50);
+
+/// Lowest bitrate the controller sets
+const MIN_BITRATE_KBPS: u32 = 500;
+
+/// Share of the estimated bandwidth given to video
+const BITRATE_HEADROOM: f64 = 0.8;
+
+/// Time between bitrate decreases, so each can take effect
+const DECREASE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time the link must stay clear before each bitrate increase
+const INCREASE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Factor of each bitrate increase
+const INCREASE_STEP: f64 = 1.15;
+
+/// One `TCP_INFO` sample, reduced to what the estimate needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpSample {
+    /// Kernel's most recent delivery rate (bytes/s)
+    pub delivery_rate: u64,
+    /// Smoothed round-trip time
+    pub rtt: Duration,
+    /// Total bytes sent, including retransmissions
+    pub bytes_sent: u64,
+    /// Total bytes retransmitted
+    pub bytes_retrans: u64,
+    /// Bytes written to the socket but not sent yet
+    pub notsent_bytes: u32,
+}
+
+impl TcpSample {
+    /// Reduce a `TCP_INFO` reading
+    pub(super) fn from_tcp_info(info: &libc::tcp_info) -> Self {
+        Self {
+            delivery_rate: info.tcpi_delivery_rate,
+            rtt: Duration::from_micros(u64::from(info.tcpi_rtt)),
+            bytes_sent: info.tcpi_bytes_sent,
+            bytes_retrans: info.tcpi_bytes_retrans,
+            notsent_bytes: info.tcpi_notsent_bytes,
+        }
+    }
+}
+
+/// State of the estimate at one point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkSnapshot {
+    /// Estimated bandwidth (None = nothing measured yet)
+    pub bandwidth_kbps: Option<u32>,
+    /// Smoothed round-trip time (zero = not measured yet)
+    pub rtt: Duration,
+    /// Whether the link showed congestion recently
+    pub congested: bool,
+}
+
+/// Bandwidth and round-trip estimate of one client's link
+///
+/// Cloned handles share state.
+#[derive(Debug, Clone, Default)]
+pub struct LinkEstimate {
+    inner: Arc<Mutex<LinkState>>,
+}
+
+#[derive(Debug, Default)]
+struct LinkState {
+    /// Recent delivery rates (kbps), newest last
+    rates: VecDeque<u32>,
+    rtt: Duration,
+    last_tcp: Option<TcpSample>,
+    /// Acknowledged EGFX bytes and when they were counted
+    last_acks: Option<(u64, Instant)>,
+    min_ack_latency: Option<Duration>,
+    congested_until: Option<Instant>,
+}
+
+impl LinkEstimate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the link of the previous session on a reused pipeline
+    pub fn reset(&self) {
+        *self.lock() = LinkState::default();
+    }
+
+    /// Add a `TCP_INFO` sample taken at `at`
+    pub fn record_tcp(&self, sample: TcpSample, at: Instant) {
+        let mut state = self.lock();
+        state.rtt = sample.rtt;
+        if sample.delivery_rate > 0 {
+            state.add_rate((sample.delivery_rate * 8 / 1000) as u32);
+        }
+
+        let retransmitted = state.last_tcp.is_some_and(|last| {
+            let sent = sample.bytes_sent.saturating_sub(last.bytes_sent);
+            let retrans = sample.bytes_retrans.saturating_sub(last.bytes_retrans);
+            // More than 1% of the data sent since the last sample
+            sent > 0 && retrans * 100 > sent
+        });
+        let queued = sample.delivery_rate > 0
+            && u64::from(sample.notsent_bytes)
+                > (sample.delivery_rate as f64 * MAX_QUEUED.as_secs_f64()) as u64;
+        if retransmitted || queued {
+            state.congest(
+                at,
+                if retransmitted {
+                    "retransmissions"
+                } else {
+                    "data queued in the socket"
+                },
+            );
+        }
+        state.last_tcp = Some(sample);
+    }
+
+    /// Add the client's EGFX acknowledgements: the total bytes of the
+    /// frames it acknowledged so far and the smoothed acknowledgement latency
+    pub fn record_acks(&self, acked_bytes: u64, latency: Option<Duration>, at: Instant) {
+        let mut state = self.lock();
+
+        match state.last_acks {
+            Some((bytes, since)) if at.duration_since(since) >= ACK_RATE_INTERVAL => {
+                let secs = at.duration_since(since).as_secs_f64();
+                let kbps = acked_bytes.saturating_sub(bytes) as f64 * 8.0 / 1000.0 / secs;
+                if kbps > 0.0 {
+                    state.add_rate(kbps as u32);
+                }
+                state.last_acks = Some((acked_bytes, at));
+            }
+            Some(_) => {}
+            None => state.last_acks = Some((acked_bytes, at)),
+        }
+
+        if let Some(latency) = latency {
+            let min = state
+                .min_ack_latency
+                .map_or(latency, |min| min.min(latency));
+            state.min_ack_latency = Some(min);
+            if latency > min * 2 + ACK_LATENCY_SLACK {
+                state.congest(at, "acknowledgements slowing down");
+            }
+        }
+    }
+
+    /// Current estimate as of `at`
+    pub fn snapshot(&self, at: Instant) -> LinkSnapshot {
+        let state = self.lock();
+        let congested = state.is_congested(at);
+        let bandwidth_kbps = if congested {
+            // What got through lately, not what the link carried at its best
+            state.rates.back().copied()
+        } else {
+            state.rates.iter().copied().max()
+        };
+        LinkSnapshot {
+            bandwidth_kbps,
+            rtt: state.rtt,
+            congested,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LinkState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl LinkState {
+    fn add_rate(&mut self, kbps: u32) {
+        if self.rates.len() == MAX_FILTER_SAMPLES {
+            self.rates.pop_front();
+        }
+        self.rates.push_back(kbps);
+    }
+
+    fn congest(&mut self, at: Instant, reason: &str) {
+        if !self.is_congested(at) {
+            debug!("Link congested: {}", reason);
+            // Rates from before the congestion overstate the link now
+            let latest = self.rates.back().copied();
+            self.rates.clear();
+            self.rates.extend(latest);
+        }
+        self.congested_until = Some(at + CONGESTION_HOLD);
+    }
+
+    fn is_congested(&self, at: Instant) -> bool {
+        self.congested_until.is_some_and(|until| at < until)
+    }
+}
+
+/// Encoder bitrate following the link estimate
+///
+/// Only congestion lowers the bitrate: while the link is clear the
+/// estimate only shows what was sent, which for a quiet desktop is far
+/// below what the link can carry.
+#[derive(Debug, Clone)]
+pub struct BitrateController {
+    max_kbps: u32,
+    current_kbps: u32,
+    last_change: Instant,
+}
+
+impl BitrateController {
+    /// Start at the configured bitrate, which stays the maximum
+    pub fn new(max_kbps: u32, now: Instant) -> Self {
+        Self {
+            max_kbps,
+            current_kbps: max_kbps,
+            last_change: now,
+        }
+    }
+
+    /// Bitrate to encode at
+    pub fn current_kbps(&self) -> u32 {
+        self.current_kbps
+    }
+
+    /// Change the maximum, e.g. on config reload
+    ///
+    /// A bitrate at the old maximum follows the new one; a lowered bitrate
+    /// stays unless it's above the new maximum. Returns the new bitrate if
+    /// it changed.
+    pub fn set_max(&mut self, max_kbps: u32, now: Instant) -> Option<u32> {
+        let at_max = self.current_kbps == self.max_kbps;
+        self.max_kbps = max_kbps;
+        if self.current_kbps == max_kbps || (!at_max && self.current_kbps < max_kbps) {
+            return None;
+        }
+        self.current_kbps = max_kbps;
+        self.last_change = now;
+        Some(max_kbps)
+    }
+
+    /// Follow the link; returns the new bitrate when it should change
+    pub fn update(&mut self, link: &LinkSnapshot, now: Instant) -> Option<u32> {
+        let since_change = now.duration_since(self.last_change);
+
+        let target = if link.congested {
+            let bandwidth = link.bandwidth_kbps?;
+            let target = ((f64::from(bandwidth) * BITRATE_HEADROOM) as u32)
+                .clamp(MIN_BITRATE_KBPS.min(self.max_kbps), self.max_kbps);
+            if since_change < DECREASE_INTERVAL || target >= self.current_kbps {
+                return None;
+            }
+            target
+        } else {
+            if since_change < INCREASE_INTERVAL || self.current_kbps >= self.max_kbps {
+                return None;
+            }
+            ((f64::from(self.current_kbps) * INCREASE_STEP) as u32).min(self.max_kbps)
+        };
+
+        info!(
+            "📶 Bitrate {} → {}kbps (link {}, estimate {:?}kbps)",
+            self.current_kbps,
+            target,
+            if link.congested { "congested" } else { "clear" },
+            link.bandwidth_kbps
+        );
+        self.current_kbps = target;
+        self.last_change = now;
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(delivery_kbps: u64, bytes_sent: u64, bytes_retrans: u64) -> TcpSample {
+        TcpSample {
+            delivery_rate: delivery_kbps * 1000 / 8,
+            rtt: Duration::from_millis(20),
+            bytes_sent,
+            bytes_retrans,
+            notsent_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_estimate_and_congestion() {
+        let start = Instant::now();
+        let link = LinkEstimate::new();
+        assert_eq!(link.snapshot(start).bandwidth_kbps, None);
+
+        link.record_tcp(tcp(8000, 1_000_000, 0), start);
+        link.record_tcp(tcp(2000, 2_000_000, 0), start + Duration::from_secs(2));
+        let snapshot = link.snapshot(start + Duration::from_secs(2));
+        assert_eq!(snapshot.bandwidth_kbps, Some(8000));
+        assert_eq!(snapshot.rtt, Duration::from_millis(20));
+        assert!(!snapshot.congested);
+
+        // 5% retransmitted: congested, estimate is the latest rate
+        let at = start + Duration::from_secs(4);
+        link.record_tcp(tcp(3000, 3_000_000, 50_000), at);
+        let snapshot = link.snapshot(at);
+        assert!(snapshot.congested);
+        assert_eq!(snapshot.bandwidth_kbps, Some(3000));
+        assert!(!link.snapshot(at + CONGESTION_HOLD).congested);
+
+        link.clone().reset();
+        assert_eq!(link.snapshot(at).bandwidth_kbps, None);
+    }
+
+    #[test]
+    fn test_ack_pacing() {
+        let start = Instant::now();
+        let link = LinkEstimate::new();
+        link.record_acks(0, Some(Duration::from_millis(30)), start);
+        // 500 kB acknowledged over two seconds
+        link.record_acks(
+            500_000,
+            Some(Duration::from_millis(40)),
+            start + Duration::from_secs(2),
+        );
+        let snapshot = link.snapshot(start + Duration::from_secs(2));
+        assert_eq!(snapshot.bandwidth_kbps, Some(2000));
+        assert!(!snapshot.congested);
+
+        // Latency well over twice its minimum
+        link.record_acks(
+            500_000,
+            Some(Duration::from_millis(200)),
+            start + Duration::from_secs(3),
+        );
+        assert!(link.snapshot(start + Duration::from_secs(3)).congested);
+    }
+
+    #[test]
+    fn test_bitrate_controller() {
+        let start = Instant::now();
+        let mut controller = BitrateController::new(5000, start);
+        let congested = LinkSnapshot {
+            bandwidth_kbps: Some(2000),
+            rtt: Duration::from_millis(20),
+            congested: true,
+        };
+        let clear = LinkSnapshot {
+            congested: false,
+            ..congested
+        };
+
+        // A clear link at the maximum changes nothing
+        assert_eq!(controller.update(&clear, start + INCREASE_INTERVAL), None);
+
+        let at = start + DECREASE_INTERVAL;
+        assert_eq!(controller.update(&congested, at), Some(1600));
+        // Not again before the previous change took effect
+        assert_eq!(
+            controller.update(&congested, at + Duration::from_secs(1)),
+            None
+        );
+
+        let at = at + INCREASE_INTERVAL;
+        assert_eq!(controller.update(&clear, at), Some(1840));
+        assert_eq!(controller.current_kbps(), 1840);
+
+        // A lowered bitrate stays below a raised maximum
+        assert_eq!(controller.set_max(6000, at), None);
+        assert_eq!(controller.set_max(1000, at), Some(1000));
+        assert_eq!(controller.set_max(3000, at), Some(3000));
+    }
+}
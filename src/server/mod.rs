@@ -25,6 +25,96 @@
 //!
 //! **Clipboard Path:** Client ↔ IronRDP ↔ Clipboard Manager ↔ Portal ↔ Compositor
 //!
+//! # Multiple Clients
+//!
+//! With `server.multi_client = "single"` IronRDP's accept loop serves one
+//! client at a time. In `"shared"` or `"separate"` mode the server runs its
+//! own accept loop: the first client uses the primary pipeline, and each
+//! further client gets a pipeline of its own (display handler, encoder, input
+//! and clipboard) built on either the primary capture or a new Portal session.
+//! See [`SessionManager`] for admission control.
+//!
+//! With `server.reverse_connect` (or `--connect host:port`) the server dials
+//! out to a broker instead of listening; see [`ReverseConnector`].
+//!
+//! With `broker.enabled`, an external session broker decides whether each
+//! connection is served here, refused, or relayed to another host; see
+//! [`SessionBroker`].
+//!
+//! With `shadow.enabled`, admins can also attach view-only observers on a
+//! separate listener after the local user consents (see [`HostNotifier`]).
+//!
+//! With `admin_api.enabled`, a token-authenticated HTTP API lists and
+//! disconnects clients and changes runtime policies; see [`AdminApi`].
+//! `health.enabled` serves `GET /healthz` for load balancers; see
+//! [`HealthChecker`].
+//!
+//! Connections served by our own accept loop get TCP keepalive and a
+//! dead-peer watchdog (`server.tcp_keepalive_secs`,
+//! `server.dead_peer_timeout_secs`), so a crashed client releases its Portal
+//! session and encoder within seconds; see [`KeepaliveSettings`].
+//!
+//! With `[login_banner]`, each session starts on a consent banner that must
+//! be acknowledged before the desktop is shown; see [`LoginBanner`].
+//!
+//! `[quality_overlay]` draws a frame rate, bitrate and round-trip time badge
+//! into the client's frames on request; see [`QualityOverlay`].
+//!
+//! Each client's input and clipboard activity is tracked, and the local user
+//! can pause a client's video, input and clipboard without disconnecting it;
+//! see [`SharingControl`].
+//!
+//! Admins can put a short message on a client's screen through the admin
+//! API; see [`SessionMessage`].
+//!
+//! With the portal cursor in metadata mode, the client's pointer follows the
+//! cursor bitmap and position PipeWire attaches to frames, or the cursor is
+//! painted into frames per `[cursor] mode`; see [`CursorChannel`].
+//!
+//! On wlroots compositors with `[wlr] screencopy`, each pipeline copies the
+//! outputs with wlr-screencopy instead of reading PipeWire, and input goes
+//! through the virtual keyboard and pointer: no portal is involved, and
+//! there is no clipboard sync. See [`VideoSource`].
+//!
+//! `[hooks]` commands run on connect, disconnect and failed logons with the
+//! session details in the environment; see [`SessionHooks`].
+//!
+//! Each session's CPU time, data sent and frame memory are accounted, and
+//! `[resource_limits]` throttles or terminates sessions over their limits;
+//! see [`ResourceMonitor`].
+//!
+//! SIGTERM/SIGINT end client sessions with a notification and a grace period
+//! before the server exits; see [`Shutdown`].
+//!
+//! If the capture session ends underneath the server (the user stops sharing,
+//! the portal restarts), its clients are disconnected the same way and
+//! [`LamcoRdpServer::run`] returns [`CaptureLost`], after which the binary
+//! re-creates the server per `[capture_recovery]`. A screencast node that
+//! the compositor replaces while the session lives on (GNOME Shell reload)
+//! is followed in place: pipelines re-create their stream on the new node
+//! and send a full frame, and only count the session as lost when no
+//! replacement appears within 5s.
+//!
+//! Compositor capabilities are probed again when the portal restarts and
+//! every `[capture_recovery] reprobe_interval_secs`: changes apply to
+//! pipelines created afterwards, and a changed set of outputs re-creates a
+//! wlr-screencopy capture like a lost one.
+//!
+//! With `server.upgrade_handoff`, SIGUSR2 starts the (upgraded) binary and
+//! hands it the listening sockets; connected clients stay on the old process
+//! until they disconnect. See [`Upgrader`].
+//!
+//! With `[frame_dump]`, SIGUSR1 or the admin API has every pipeline write
+//! its next frames, their damage regions and H.264 bitstreams to disk; see
+//! [`FrameDump`].
+//!
+//! With `[event_stream]`, session starts and ends, logins, resolution and
+//! codec changes and clipboard transfers are published as JSON lines on a
+//! Unix socket; see [`EventStream`].
+//!
+//! `[heartbeat]` keeps a JSON status file with session counts and health
+//! flags up to date for monitoring agents; see [`Heartbeat`].
+//!
 //! # Threading Model
 //!
 //! - **Tokio async runtime:** Main server logic, Portal API calls, frame processing
@@ -59,34 +149,122 @@
 //! - Target: 30-60 FPS video streaming
 //! - RemoteFX compression for efficient bandwidth usage
 
+mod admin_api;
+mod banner;
+mod broker;
+mod capture_watchdog;
+mod config_reload;
+mod cursor_channel;
+mod direct_manipulation;
 mod display_handler;
 mod egfx_sender;
 mod event_multiplexer;
+mod event_stream;
+mod frame_dump;
+mod frame_scaler;
 mod gfx_factory;
 mod graphics_drain;
+mod handoff;
+mod health;
+mod heartbeat;
+mod hooks;
+mod housekeeping;
+mod idle_stop;
+mod input_fallback;
 mod input_handler;
+mod keepalive;
+mod link_estimate;
+mod monitor_pipeline;
 mod multiplexer_loop;
-
-pub use display_handler::LamcoDisplayHandler;
+mod node_watch;
+mod osd;
+mod quality_overlay;
+mod reprobe;
+mod resource_limits;
+mod reverse;
+mod session_manager;
+mod session_message;
+mod setup;
+mod shadow;
+mod sharing;
+mod shutdown;
+mod stats_history;
+
+pub use admin_api::{
+    AdminApi, AdminClient, AdminStats, PolicyUpdate, RuntimePolicy, SessionEntry, SessionStats,
+};
+pub use banner::{LoginBanner, ACKNOWLEDGE_SCANCODE};
+pub use broker::{
+    parse_routing_cookie, BrokerAction, BrokerDecision, BrokerRequest, SessionBroker,
+};
+pub use config_reload::{ConfigReloader, LogLevelHandler};
+pub use cursor_channel::{CursorChannel, CursorMeta};
+pub use direct_manipulation::{DirectManipulation, TouchDevice};
+pub use display_handler::{LamcoDisplayHandler, VideoSource};
 pub use egfx_sender::{EgfxFrameSender, SendError};
+pub use event_stream::{EventStream, LifecycleEvent, SessionEvents};
+pub use frame_dump::{DumpRequest, DumpSlot, DumpedFrame, FrameDump, FrameDumpTap, RateLimited};
+pub use frame_scaler::{FrameScaler, ScaledFrame, ScalingPolicy, SharedFrameScaler};
 pub use gfx_factory::{HandlerState, LamcoGfxFactory, SharedHandlerState};
+pub use handoff::{HandoffState, Listeners, Upgrader, HANDOFF_ENV, READY_TIMEOUT};
+pub(crate) use health::{certificate_validity, CERT_EXPIRY_WARNING_DAYS};
+pub use health::{HealthCheck, HealthChecker, HealthReport, HealthStatus};
+pub use heartbeat::{Heartbeat, HeartbeatStatus, SessionCounts};
+pub use hooks::{HookEvent, HookSession, SessionHooks};
+pub use housekeeping::{Housekeeping, Ticker, HOUSEKEEPING_PERIOD};
+pub use idle_stop::{ClientGuard, IdleStop};
 pub use input_handler::LamcoInputHandler;
+pub use keepalive::{KeepaliveSettings, PeerWatch};
+pub use link_estimate::{BitrateController, LinkEstimate, LinkSnapshot, TcpSample};
+pub use quality_overlay::{OverlayHotkey, QualityOverlay};
+pub use resource_limits::{
+    thread_cpu_time, LimitAction, ResourceLimits, ResourceMonitor, SessionMeter, SessionUsage,
+};
+pub use reverse::{ControlMessage, ReverseConnector};
+pub use session_manager::{
+    AdmissionError, ClientKind, ClientSessionInfo, ClientSlot, MultiClientMode, SessionManager,
+};
+pub use session_message::{SessionMessage, DEFAULT_DURATION, MAX_DURATION, MAX_MESSAGE_CHARS};
+pub use setup::run_setup;
+pub use shadow::{admit_observer, ConsentDecision, HostNotifier};
+pub use sharing::{SharingControl, SharingStatus};
+pub use shutdown::Shutdown;
+pub use stats_history::{StatsHistory, StatsSample, HISTORY_LEN};
 
 use anyhow::{Context, Result};
 use ironrdp_pdu::rdp::capability_sets::server_codecs_capabilities;
-use ironrdp_server::{Credentials, RdpServer};
+use ironrdp_server::{Credentials, RdpServer, ServerEvent};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
-use tracing::{debug, error, info, warn};
+use tokio::sync::{watch, Mutex};
+use tracing::{debug, error, info, warn, Instrument};
 
 use crate::clipboard::{ClipboardConfig, ClipboardManager, LamcoCliprdrFactory};
 use crate::config::Config;
 use crate::input::MonitorInfo as InputMonitorInfo;
+use crate::multimon::{shared_streams, virtual_positions, FollowFocusController, FollowFocusMode};
 use crate::portal::PortalManager;
+use crate::protocol::PduCapture;
 use crate::security::TlsConfig;
 use crate::services::{ServiceId, ServiceLevel, ServiceRegistry};
-use crate::session::{PipeWireAccess, SessionStrategySelector, SessionType};
+use crate::session::failover::{parse_chain, FailoverChain};
+use crate::session::{PipeWireAccess, SessionStrategySelector, SessionType, SharedSession};
+use crate::utils::{chaos, crash_report, session_span, spawn_in_current_span};
+use input_fallback::SessionInputProvider;
+use reprobe::{CapabilityReprober, ProbedEnvironment};
+
+/// The primary capture session ended underneath the server
+///
+/// Returned by [`LamcoRdpServer::run`] after connected clients have been
+/// disconnected. The caller may create a new server to re-establish the
+/// session (see `[capture_recovery]`).
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("screen capture session lost: {reason}")]
+pub struct CaptureLost {
+    /// Why the session ended
+    pub reason: String,
+}
 
 /// WRD Server
 ///
@@ -107,6 +285,85 @@ pub struct LamcoRdpServer {
     /// Display handler (kept for lifecycle management)
     #[allow(dead_code)]
     display_handler: Arc<LamcoDisplayHandler>,
+
+    /// Clipboard manager of the primary pipeline (closed on shutdown)
+    primary_clipboard: Arc<Mutex<ClipboardManager>>,
+
+    /// Context for building additional client pipelines
+    context: SessionContext,
+
+    /// Capture session backing the primary pipeline (shared-view clients reuse it)
+    primary_capture: Arc<CaptureSession>,
+
+    /// Connected client tracking and admission control
+    session_manager: SessionManager,
+
+    /// Shadowing observers (separate limit)
+    observer_manager: SessionManager,
+
+    /// Effective configuration published to running pipelines (hot-reload)
+    live_config: Arc<watch::Sender<Arc<Config>>>,
+
+    /// Publishes re-probed capabilities to the context (taken by `run`)
+    reprober: Option<CapabilityReprober>,
+
+    /// Listening sockets, possibly inherited from a previous process
+    listeners: Listeners,
+
+    /// Drop-in directory of the config file, for settings changed at runtime
+    settings_dir: Option<PathBuf>,
+}
+
+/// Captured desktop that one or more client pipelines draw from
+struct CaptureSession {
+    /// Session shared by screencast, input injection and clipboard
+    session: SharedSession,
+
+    /// PipeWire remote file descriptor, or screencopy
+    video: VideoSource,
+
+    /// Captured streams
+    stream_info: Vec<crate::portal::StreamInfo>,
+}
+
+/// Per-client pipeline: encoder, input, clipboard and IronRDP server
+struct ClientPipeline {
+    rdp_server: RdpServer,
+    display_handler: Arc<LamcoDisplayHandler>,
+    clipboard_manager: Arc<Mutex<ClipboardManager>>,
+}
+
+/// What a client pipeline may do with the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PipelineRole {
+    /// Input and host clipboard (primary and separate clients)
+    Full,
+    /// Input, isolated clipboard (shared-view clients)
+    SharedView,
+    /// No input, isolated clipboard (shadowing observers)
+    Observer,
+}
+
+/// Everything needed to create capture sessions and client pipelines
+#[derive(Clone)]
+struct SessionContext {
+    config: Arc<Config>,
+    /// Compositor capabilities and services as of the last probe
+    environment: watch::Receiver<ProbedEnvironment>,
+    strategy: Arc<dyn crate::session::SessionStrategy>,
+    portal_manager: Arc<PortalManager>,
+    /// Runtime-safe settings as of the last reload
+    live_config: watch::Receiver<Arc<Config>>,
+    /// Server-wide shutdown request
+    shutdown: Shutdown,
+    /// Shared tick for per-connection periodic checks
+    housekeeping: Housekeeping,
+    /// Channel PDU recording (`logging.pdu_capture`)
+    pdu_capture: Option<PduCapture>,
+    /// Frame dump trigger (None = `[frame_dump]` disabled)
+    frame_dump: Option<FrameDump>,
+    /// Lifecycle event publisher (None = `[event_stream]` disabled)
+    events: Option<EventStream>,
 }
 
 impl LamcoRdpServer {
@@ -122,6 +379,8 @@ impl LamcoRdpServer {
     pub async fn new(config: Config) -> Result<Self> {
         info!("Initializing server");
         let config = Arc::new(config);
+        crate::performance::BufferPool::global()
+            .set_max_per_class(config.performance.buffer_pool_size);
 
         // === CAPABILITY PROBING ===
         // Detect compositor and adapt configuration automatically
@@ -148,6 +407,9 @@ impl LamcoRdpServer {
                 crate::compositor::Quirk::NeedsExplicitCursorComposite => {
                     info!("📋 Cursor compositing may be needed (no metadata cursor)");
                 }
+                crate::compositor::Quirk::CursorAlwaysInFrame => {
+                    info!("📋 Cursor is always in the video, client pointer will be hidden");
+                }
                 crate::compositor::Quirk::RestartCaptureOnResize => {
                     info!("📋 Capture will restart on resolution changes");
                 }
@@ -160,6 +422,12 @@ impl LamcoRdpServer {
                 crate::compositor::Quirk::ClipboardUnavailable => {
                     info!("📋 Clipboard sync unavailable (Portal v1 limitation)");
                 }
+                crate::compositor::Quirk::UnreliableClipboardOwnerSignals => {
+                    info!("📋 Local clipboard changes also read from the GNOME extension");
+                }
+                crate::compositor::Quirk::DmaBufNeedsExplicitSync => {
+                    info!("📋 DMA-BUF frames need explicit sync, using MemFd buffers");
+                }
                 _ => {
                     debug!("Applying quirk: {:?}", quirk);
                 }
@@ -252,7 +520,10 @@ impl LamcoRdpServer {
         info!("Selecting session strategy based on detected capabilities");
 
         let strategy_selector =
-            SessionStrategySelector::new(service_registry.clone(), Arc::new(token_manager));
+            SessionStrategySelector::new(service_registry.clone(), Arc::new(token_manager))
+                .with_mutter_config(config.mutter.clone())
+                .with_kwin_config(config.kwin.clone())
+                .with_wlr_config(config.wlr.clone());
 
         let strategy = strategy_selector
             .select_strategy()
@@ -261,7 +532,810 @@ impl LamcoRdpServer {
 
         info!("🎯 Selected strategy: {}", strategy.name());
 
+        // Create Portal manager for input+clipboard (needed for both strategies)
+        let mut portal_config = config.to_portal_config();
+        portal_config.persist_mode = ashpd::desktop::PersistMode::DoNot; // Don't persist (causes errors)
+        portal_config.restore_token = None;
+
+        let portal_manager = Arc::new(
+            PortalManager::new(portal_config)
+                .await
+                .context("Failed to create Portal manager for input+clipboard")?,
+        );
+
+        let (live_config, live_config_rx) = watch::channel(Arc::clone(&config));
+        let (reprober, environment) = CapabilityReprober::new(ProbedEnvironment::new(
+            Arc::new(capabilities),
+            service_registry,
+        ));
+        let pdu_capture = config
+            .logging
+            .pdu_capture
+            .as_deref()
+            .map(PduCapture::create)
+            .transpose()?;
+        let context = SessionContext {
+            config: Arc::clone(&config),
+            environment,
+            strategy: Arc::from(strategy),
+            portal_manager: Arc::clone(&portal_manager),
+            live_config: live_config_rx,
+            shutdown: Shutdown::new(std::time::Duration::from_secs(
+                config.server.shutdown_grace_secs,
+            )),
+            housekeeping: Housekeeping::start(HOUSEKEEPING_PERIOD),
+            pdu_capture,
+            frame_dump: FrameDump::from_config(&config.frame_dump),
+            events: EventStream::from_config(&config.event_stream),
+        };
+
+        // Primary capture session and client pipeline
+        let primary_capture = Arc::new(context.create_capture_session().await?);
+        // Its tasks serve successive clients, so they are tagged by pipeline
+        // rather than by session
+        let primary = context
+            .build_pipeline(
+                &primary_capture,
+                primary_capture.video,
+                PipelineRole::Full,
+                IdleStop::from_config(&config.server),
+            )
+            .instrument(tracing::info_span!("pipeline", kind = %ClientKind::Primary))
+            .await?;
+
+        let multi_client = config
+            .server
+            .multi_client
+            .parse::<MultiClientMode>()
+            .unwrap_or_default();
+        let session_manager = SessionManager::new(multi_client, config.server.max_connections);
+        let observer_manager =
+            SessionManager::new(MultiClientMode::Shared, config.shadow.max_observers);
+
+        // Outputs are fixed when a screencopy capture is created
+        let reprober = reprober
+            .with_interval(
+                &context.housekeeping,
+                std::time::Duration::from_secs(config.capture_recovery.reprobe_interval_secs),
+            )
+            .with_restart_on_output_change(
+                config.capture_recovery.enabled
+                    && matches!(primary_capture.video, VideoSource::Screencopy),
+            );
+
+        info!("Server initialized successfully");
+
+        Ok(Self {
+            config,
+            rdp_server: primary.rdp_server,
+            portal_manager,
+            display_handler: primary.display_handler,
+            primary_clipboard: primary.clipboard_manager,
+            context,
+            primary_capture,
+            session_manager,
+            observer_manager,
+            live_config: Arc::new(live_config),
+            reprober: Some(reprober),
+            listeners: Listeners::new(),
+            settings_dir: None,
+        })
+    }
+
+    /// Take over the listeners of the server process that started this one
+    ///
+    /// See [`Upgrader`]; the state comes from [`HandoffState::take_from_env`].
+    pub fn with_handoff(mut self, state: &HandoffState) -> Self {
+        self.listeners = Listeners::inherit(state);
+        self
+    }
+
+    /// Keep settings changed through the admin API in drop-in directory
+    /// `dir` (see [`crate::config::tunable`])
+    pub fn with_settings_dir(mut self, dir: PathBuf) -> Self {
+        self.settings_dir = Some(dir);
+        self
+    }
+
+    /// Create a reloader that applies runtime-safe changes to this server
+    ///
+    /// `file_config` is the configuration as loaded from `path`, before
+    /// command-line overrides. Run [`ConfigReloader::run_on_sighup`] to reload
+    /// on SIGHUP.
+    pub fn config_reloader(&self, path: impl Into<PathBuf>, file_config: Config) -> ConfigReloader {
+        ConfigReloader::new(path.into(), file_config, Arc::clone(&self.live_config))
+    }
+
+    /// Run the server
+    ///
+    /// This starts the RDP server and handles incoming connections.
+    /// Blocks until the server is shut down.
+    pub async fn run(mut self) -> Result<()> {
+        info!("╔════════════════════════════════════════════════════════════╗");
+        info!("║          Server Starting                                   ║");
+        info!("╚════════════════════════════════════════════════════════════╝");
+        info!("  Listen Address: {}", self.config.server.listen_addr);
+        info!("  TLS: Enabled (rustls 0.23)");
+        info!("  Codec: RemoteFX");
+        info!("  Max Connections: {}", self.config.server.max_connections);
+        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+        info!("Server is ready and listening for RDP connections");
+        info!("Waiting for clients to connect...");
+
+        // Set credentials for RDP authentication
+        self.rdp_server
+            .set_credentials(rdp_credentials(&self.config));
+        info!(
+            "Authentication configured: {}",
+            self.config.security.auth_method
+        );
+
+        // Background tasks end with this server instance, freeing their
+        // ports for a server re-created after capture loss
+        let mut background = vec![tokio::spawn(self.context.shutdown.clone().run_on_signals())];
+        if self.config.shadow.enabled {
+            self.spawn_observer_listener();
+        }
+        if self.config.admin_api.enabled {
+            background.push(self.spawn_admin_api()?);
+        }
+        if self.config.health.enabled {
+            background.push(self.spawn_health_endpoint()?);
+        }
+        let primary_clipboard = Arc::clone(&self.primary_clipboard);
+
+        // Connected clients for crash reports (replaces a previous instance's)
+        crash_report::add_section("sessions", {
+            let clients = self.session_manager.clone();
+            let observers = self.observer_manager.clone();
+            move || {
+                let sessions: Vec<SessionEntry> = clients
+                    .clients()
+                    .into_iter()
+                    .chain(observers.clients())
+                    .map(SessionEntry::from)
+                    .collect();
+                serde_json::to_value(sessions).unwrap_or_default()
+            }
+        });
+
+        // Losing the primary capture ends this server instance like a shutdown,
+        // as does an environment change the capture cannot follow
+        let capture_watch = tokio::spawn({
+            let session = self.primary_capture.session.clone();
+            let shutdown = self.context.shutdown.clone();
+            let reprober = self.reprober.take();
+            async move {
+                let reason = match reprober {
+                    Some(reprober) => tokio::select! {
+                        reason = session.closed() => reason,
+                        reason = reprober.run() => reason,
+                    },
+                    None => session.closed().await,
+                };
+                if shutdown.is_requested() {
+                    return None;
+                }
+                warn!("🖥️ Screen capture session lost: {}", reason);
+                shutdown.request(&format!("Screen sharing ended on the host ({})", reason));
+                Some(reason)
+            }
+        });
+
+        if self.config.server.upgrade_handoff && self.config.server.reverse_connect.is_empty() {
+            match Upgrader::new(self.listeners.clone()) {
+                Ok(upgrader) => {
+                    background.push(tokio::spawn(upgrader.run_on_sigusr2()));
+                }
+                Err(e) => warn!("Upgrade handoff unavailable: {:#}", e),
+            }
+        }
+        if let Some(frame_dump) = self.context.frame_dump.clone() {
+            background.push(tokio::spawn(frame_dump.run_on_sigusr1()));
+        }
+        if let Some(events) = self.context.events.clone() {
+            match EventStream::socket_path(&self.config.event_stream) {
+                Some(path) => background.push(tokio::spawn(async move {
+                    if let Err(e) = events.serve(path).await {
+                        warn!("Event stream unavailable: {:#}", e);
+                    }
+                })),
+                None => warn!("Event stream unavailable: no socket_path and no XDG_RUNTIME_DIR"),
+            }
+        }
+        if let Some(heartbeat) = Heartbeat::from_config(
+            &self.config,
+            self.session_manager.clone(),
+            self.observer_manager.clone(),
+            self.primary_capture.session.liveness().clone(),
+            self.context.shutdown.clone(),
+        ) {
+            background.push(tokio::spawn(
+                heartbeat.run(self.context.housekeeping.clone()),
+            ));
+        }
+
+        // Run the IronRDP server (one client at a time) or our own accept loop
+        let result = if !self.config.server.reverse_connect.is_empty() {
+            self.run_reverse().await
+        } else if !self.needs_accept_loop() {
+            self.run_single_client().await
+        } else {
+            self.run_multi_client().await
+        };
+        close_clipboard(&primary_clipboard).await;
+        for task in &background {
+            task.abort();
+        }
+
+        let lost = if capture_watch.is_finished() {
+            capture_watch.await.ok().flatten()
+        } else {
+            capture_watch.abort();
+            None
+        };
+        if let (Ok(()), Some(reason)) = (&result, lost) {
+            info!("Server stopped: screen capture session lost");
+            return Err(CaptureLost { reason }.into());
+        }
+
+        if let Err(ref e) = result {
+            error!("Server stopped with error: {:#}", e);
+        } else {
+            info!("Server stopped gracefully");
+        }
+
+        info!("Server shutdown complete");
+        result
+    }
+
+    /// Whether connections must go through our own accept loop
+    ///
+    /// IronRDP's loop serves one client at a time and knows nothing about
+    /// brokers, client tracking (admin API), per-session logging, dead-peer
+    /// detection, upgrade handoff, idle stop, resource limits, hooks, a
+    /// login banner for every connection or the quality overlay's meter.
+    fn needs_accept_loop(&self) -> bool {
+        self.session_manager.mode() != MultiClientMode::Single
+            || self.config.login_banner.enabled
+            || self.config.quality_overlay.enabled
+            || self.config.broker.enabled
+            || self.config.resource_limits.enabled
+            || SessionHooks::from_config(&self.config.hooks).is_configured()
+            || self.config.admin_api.enabled
+            || self.config.logging.per_session_files
+            || KeepaliveSettings::from_config(&self.config.server).is_enabled()
+            || self.config.server.upgrade_handoff
+            || self.config.server.idle_stop_secs > 0
+            || self.config.event_stream.enabled
+            || chaos::severs_connections()
+    }
+
+    /// IronRDP's own accept loop, ended gracefully on shutdown
+    async fn run_single_client(mut self) -> Result<()> {
+        let shutdown = self.context.shutdown.clone();
+        let events = self.rdp_server.event_sender().clone();
+        let server = self.rdp_server.run();
+        tokio::pin!(server);
+        tokio::select! {
+            result = &mut server => return result.context("RDP server error"),
+            () = shutdown.requested() => {}
+        }
+
+        let reason = shutdown.reason().unwrap_or_default();
+        if events.send(ServerEvent::Quit(reason)).is_err() {
+            return Ok(());
+        }
+        match tokio::time::timeout(shutdown.grace(), server).await {
+            Ok(result) => result.context("RDP server error"),
+            Err(_) => {
+                warn!(
+                    "🛑 Client did not disconnect within {}s; closing",
+                    shutdown.grace().as_secs()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Accept loop for concurrent clients and broker-routed connections
+    async fn run_multi_client(self) -> Result<()> {
+        let listen_addr: SocketAddr = self
+            .config
+            .server
+            .listen_addr
+            .parse()
+            .context("Invalid listen address")?;
+        let listener = self
+            .listeners
+            .bind("rdp", listen_addr)
+            .context("Failed to bind listen address")?;
+        self.listeners.finish_inheritance();
+
+        let (incoming_tx, incoming_rx) = tokio::sync::mpsc::channel(4);
+        let listeners = self.listeners.clone();
+        let shutdown = self.context.shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                let accepted = tokio::select! {
+                    () = listeners.handed_off() => return,
+                    () = shutdown.requested() => return,
+                    accepted = listener.accept() => accepted,
+                };
+                match accepted {
+                    Ok(conn) => {
+                        if incoming_tx.send(conn).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => warn!("Failed to accept connection: {}", e),
+                }
+            }
+        });
+
+        self.serve_connections(incoming_rx).await
+    }
+
+    /// Dial out to a broker and serve the sessions it hands us
+    async fn run_reverse(self) -> Result<()> {
+        let connector = ReverseConnector::new(
+            self.config.server.reverse_connect.clone(),
+            std::time::Duration::from_secs(self.config.server.reverse_heartbeat_secs),
+            std::time::Duration::from_secs(self.config.server.reverse_retry_secs),
+        );
+        info!(
+            "🔁 Reverse connection mode: dialing {} (not listening)",
+            self.config.server.reverse_connect
+        );
+
+        let (incoming_tx, incoming_rx) = tokio::sync::mpsc::channel(4);
+        tokio::spawn(connector.run(incoming_tx));
+
+        self.serve_connections(incoming_rx).await
+    }
+
+    /// Serve incoming connections with per-client pipelines
+    ///
+    /// The first client to connect while the primary pipeline is idle uses it;
+    /// further clients get their own pipeline according to the multi-client
+    /// mode (shared view of the primary capture, or a separate capture).
+    /// When a session broker is configured it decides each connection first.
+    async fn serve_connections(
+        self,
+        mut incoming: tokio::sync::mpsc::Receiver<(tokio::net::TcpStream, SocketAddr)>,
+    ) -> Result<()> {
+        info!(
+            "👥 Multi-client mode: {} (max {} concurrent clients)",
+            self.session_manager.mode(),
+            self.session_manager.max_clients()
+        );
+
+        let broker = if self.config.broker.enabled {
+            Some(Arc::new(SessionBroker::new(&self.config.broker).await?))
+        } else {
+            None
+        };
+        let primary = Arc::new(Mutex::new(self.rdp_server));
+        let keepalive = KeepaliveSettings::from_config(&self.config.server);
+        let resource_limits = ResourceLimits::from_config(&self.config.resource_limits);
+        let primary_meter = self.display_handler.session_meter();
+        let primary_banner = self.display_handler.login_banner();
+        let primary_overlay = self.display_handler.quality_overlay();
+        let primary_sharing = self.display_handler.sharing();
+        let primary_message = self.display_handler.session_message();
+        let primary_link = self.display_handler.link_estimate();
+        let primary_cursor = self.display_handler.cursor_channel();
+        let primary_idle = self.display_handler.idle_stop();
+        let primary_events = self.display_handler.events();
+        let hooks = SessionHooks::from_config(&self.config.hooks);
+        let shutdown = self.context.shutdown.clone();
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                () = shutdown.requested() => break,
+                next = incoming.recv() => match next {
+                    Some(connection) => connection,
+                    None => break,
+                },
+            };
+            let broker = broker.clone();
+            let primary = Arc::clone(&primary);
+            let session_manager = self.session_manager.clone();
+            let context = self.context.clone();
+            let primary_capture = Arc::clone(&self.primary_capture);
+            let primary_meter = primary_meter.clone();
+            let primary_banner = primary_banner.clone();
+            let primary_overlay = primary_overlay.clone();
+            let primary_sharing = primary_sharing.clone();
+            let primary_message = primary_message.clone();
+            let primary_link = primary_link.clone();
+            let primary_cursor = primary_cursor.clone();
+            let primary_idle = primary_idle.clone();
+            let primary_events = primary_events.clone();
+            let hooks = hooks.clone();
+            let shutdown = shutdown.clone();
+
+            tokio::spawn(async move {
+                // Let the broker allow, refuse or relay the connection first
+                let stream = match broker {
+                    Some(broker) => match broker.route(stream, peer).await {
+                        Some(stream) => stream,
+                        None => return,
+                    },
+                    None => stream,
+                };
+                if let Err(e) = keepalive.apply(&stream) {
+                    warn!("Failed to enable keepalive for {}: {}", peer, e);
+                }
+                let peer_watch = keepalive
+                    .watch(&stream)
+                    .with_housekeeping(&context.housekeeping);
+
+                // The primary pipeline is free whenever nobody holds its lock
+                let primary_server = primary.try_lock_owned().ok();
+                let kind = match primary_server {
+                    Some(_) => ClientKind::Primary,
+                    None => session_manager.additional_client_kind(),
+                };
+
+                let slot = match session_manager.admit(peer, kind) {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        warn!("Rejecting client {}: {}", peer, e);
+                        return;
+                    }
+                };
+
+                // Everything done for this client carries its session ID
+                let span = session_span(slot.id(), peer, kind);
+                async move {
+                    let _sever = chaos::sever_later(&stream);
+                    let hook_session = hooks.connected(&stream, slot.id(), peer, kind).await;
+                    let started = std::time::Instant::now();
+                    if let Some(ref events) = context.events {
+                        events.session_started(slot.id(), peer, kind);
+                    }
+                    let result = match primary_server {
+                        Some(mut server) => {
+                            // The primary pipeline outlives sessions: start afresh
+                            primary_meter.reset();
+                            primary_banner.reset();
+                            if let Some(ref cursor) = primary_cursor {
+                                cursor.reset();
+                            }
+                            slot.set_meter(primary_meter.clone());
+                            if let Some(overlay) = primary_overlay {
+                                overlay.reset();
+                                slot.set_overlay(overlay);
+                            }
+                            primary_sharing.reset();
+                            slot.set_sharing(primary_sharing);
+                            primary_message.clear();
+                            slot.set_message(primary_message);
+                            primary_link.reset();
+                            primary_events.attach(slot.id());
+                            // Wakes the pipeline if capture stopped while idle
+                            let _connected = primary_idle.as_ref().map(IdleStop::client_connected);
+                            let monitor =
+                                ResourceMonitor::new(primary_meter, &stream, resource_limits)
+                                    .with_link_estimate(primary_link)
+                                    .with_housekeeping(&context.housekeeping);
+                            let events = server.event_sender().clone();
+
+                            tokio::select! {
+                                result = serve_until_disconnected(
+                                    server.run_connection(stream),
+                                    &events,
+                                    &slot,
+                                    &shutdown,
+                                    &primary_capture.session,
+                                ) => result,
+                                reason = peer_watch.dead() => {
+                                    warn!(
+                                        "💀 Client {} ({}) is unresponsive: {}",
+                                        slot.id(),
+                                        peer,
+                                        reason
+                                    );
+                                    Ok(())
+                                }
+                                reason = monitor.run() => {
+                                    warn!(
+                                        "⛔ Client {} ({}) terminated: {}",
+                                        slot.id(),
+                                        peer,
+                                        reason
+                                    );
+                                    Ok(())
+                                }
+                                () = primary_banner.expired() => {
+                                    warn!(
+                                        "📜 Client {} ({}) did not acknowledge the login banner",
+                                        slot.id(),
+                                        peer
+                                    );
+                                    Ok(())
+                                }
+                            }
+                        }
+                        None => {
+                            context
+                                .serve_additional_client(
+                                    kind,
+                                    &primary_capture,
+                                    stream,
+                                    peer_watch,
+                                    &slot,
+                                )
+                                .await
+                        }
+                    };
+
+                    hook_session.finished(&result);
+                    if kind == ClientKind::Primary {
+                        primary_events.detach();
+                    }
+                    if let Some(ref events) = context.events {
+                        events.session_ended(slot.id(), started, &result);
+                    }
+                    match result {
+                        Ok(()) => info!("Client {} ({}) disconnected", slot.id(), peer),
+                        Err(e) => {
+                            warn!("Client {} ({}) ended with error: {:#}", slot.id(), peer, e)
+                        }
+                    }
+                }
+                .instrument(span)
+                .await;
+            });
+        }
+
+        if shutdown.is_requested() {
+            shutdown
+                .drain(&[&self.session_manager, &self.observer_manager])
+                .await;
+            return Ok(());
+        }
+
+        // Accepting stopped after an upgrade handoff: let clients finish here
+        let remaining = self.session_manager.client_count() + self.observer_manager.client_count();
+        if remaining > 0 {
+            info!("♻️ Waiting for {} connected client(s) to leave", remaining);
+            self.session_manager.wait_idle().await;
+            self.observer_manager.wait_idle().await;
+        }
+
+        Ok(())
+    }
+
+    /// Start the shadowing listener for view-only observers
+    ///
+    /// Each observer is gated on local consent, gets a view-only pipeline on
+    /// the primary capture, and is announced on the host while attached.
+    fn spawn_observer_listener(&self) {
+        let shadow_config = self.config.shadow.clone();
+        let context = self.context.clone();
+        let primary_capture = Arc::clone(&self.primary_capture);
+        let observers = self.observer_manager.clone();
+        let keepalive = KeepaliveSettings::from_config(&self.config.server);
+        let listeners = self.listeners.clone();
+        let hooks = SessionHooks::from_config(&self.config.hooks);
+        let shutdown = self.context.shutdown.clone();
+
+        let listener = match shadow_config.listen_addr.parse::<SocketAddr>() {
+            Ok(addr) => match listeners.bind("shadow", addr) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Failed to bind shadow listener on {}: {}", addr, e);
+                    return;
+                }
+            },
+            Err(e) => {
+                error!("Invalid shadow listen address: {}", e);
+                return;
+            }
+        };
+
+        tokio::spawn(async move {
+            info!(
+                "👁️ Shadowing enabled: observers connect on {} (consent: {}, max {})",
+                shadow_config.listen_addr,
+                if shadow_config.require_consent {
+                    "required"
+                } else {
+                    "not required"
+                },
+                observers.max_clients()
+            );
+
+            loop {
+                let accepted = tokio::select! {
+                    () = listeners.handed_off() => return,
+                    () = shutdown.requested() => return,
+                    accepted = listener.accept() => accepted,
+                };
+                let (stream, peer) = match accepted {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!("Failed to accept observer connection: {}", e);
+                        continue;
+                    }
+                };
+
+                if let Err(e) = keepalive.apply(&stream) {
+                    warn!("Failed to enable keepalive for observer {}: {}", peer, e);
+                }
+
+                let slot = match observers.admit(peer, ClientKind::Observer) {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        warn!("Rejecting observer {}: {}", peer, e);
+                        continue;
+                    }
+                };
+
+                let context = context.clone();
+                let primary_capture = Arc::clone(&primary_capture);
+                let shadow_config = shadow_config.clone();
+                let hooks = hooks.clone();
+                // Everything done for this observer carries its session ID
+                let _session = session_span(slot.id(), peer, ClientKind::Observer).entered();
+                spawn_in_current_span(async move {
+                    let notifier = match admit_observer(
+                        peer,
+                        shadow_config.require_consent,
+                        std::time::Duration::from_secs(shadow_config.consent_timeout_secs),
+                    )
+                    .await
+                    {
+                        Ok(notifier) => notifier,
+                        Err(decision) => {
+                            warn!("Observer {} refused: consent {}", peer, decision);
+                            return;
+                        }
+                    };
+
+                    let indicator = match notifier {
+                        Some(ref notifier) if shadow_config.show_indicator => {
+                            notifier.show_indicator(peer).await.ok()
+                        }
+                        _ => None,
+                    };
+
+                    let peer_watch = keepalive
+                        .watch(&stream)
+                        .with_housekeeping(&context.housekeeping);
+                    let hook_session = hooks
+                        .connected(&stream, slot.id(), peer, ClientKind::Observer)
+                        .await;
+                    let started = std::time::Instant::now();
+                    if let Some(ref events) = context.events {
+                        events.session_started(slot.id(), peer, ClientKind::Observer);
+                    }
+                    let result = context
+                        .serve_additional_client(
+                            ClientKind::Observer,
+                            &primary_capture,
+                            stream,
+                            peer_watch,
+                            &slot,
+                        )
+                        .await;
+
+                    hook_session.finished(&result);
+                    if let Some(ref events) = context.events {
+                        events.session_ended(slot.id(), started, &result);
+                    }
+                    if let (Some(notifier), Some(id)) = (notifier.as_ref(), indicator) {
+                        notifier.close(id).await;
+                    }
+
+                    match result {
+                        Ok(()) => info!("Observer {} ({}) disconnected", slot.id(), peer),
+                        Err(e) => {
+                            warn!(
+                                "Observer {} ({}) ended with error: {:#}",
+                                slot.id(),
+                                peer,
+                                e
+                            )
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// Start the HTTP admin API
+    ///
+    /// Serves plain HTTP on loopback, or mutual TLS when a client CA is
+    /// configured.
+    fn spawn_admin_api(&self) -> Result<tokio::task::JoinHandle<()>> {
+        let admin_config = &self.config.admin_api;
+        let listen_addr: SocketAddr = admin_config
+            .listen_addr
+            .parse()
+            .context("Invalid admin API listen address")?;
+        let tls = match admin_config.client_ca_path {
+            Some(ref ca_path) => Some(
+                TlsConfig::from_files(
+                    &self.config.security.cert_path,
+                    &self.config.security.key_path,
+                )?
+                .server_config_with_client_auth(ca_path)
+                .context("Failed to configure admin API mutual TLS")?,
+            ),
+            None => None,
+        };
+
+        let api = AdminApi::new(
+            admin_config.token.clone(),
+            self.session_manager.clone(),
+            self.observer_manager.clone(),
+            Arc::clone(&self.live_config),
+        )
+        .with_settings_dir(self.settings_dir.clone())
+        .with_frame_dump(self.context.frame_dump.clone());
+        let listener = self
+            .listeners
+            .bind("admin_api", listen_addr)
+            .with_context(|| format!("Failed to bind admin API on {}", listen_addr))?;
+        Ok(tokio::spawn(async move {
+            if let Err(e) = api.serve(listener, tls).await {
+                error!("Admin API stopped: {:#}", e);
+            }
+        }))
+    }
+
+    /// Start the `GET /healthz` endpoint
+    fn spawn_health_endpoint(&self) -> Result<tokio::task::JoinHandle<()>> {
+        let listen_addr: SocketAddr = self
+            .config
+            .health
+            .listen_addr
+            .parse()
+            .context("Invalid health endpoint listen address")?;
+        let checker = Arc::new(HealthChecker::new(Arc::clone(&self.config)));
+        let listener = self
+            .listeners
+            .bind("health", listen_addr)
+            .with_context(|| format!("Failed to bind health endpoint on {}", listen_addr))?;
+
+        Ok(tokio::spawn(async move {
+            if let Err(e) = checker.serve(listener).await {
+                error!("Health endpoint stopped: {:#}", e);
+            }
+        }))
+    }
+
+    /// Handle for shutting the running server down
+    ///
+    /// Requesting shutdown has the same effect as SIGTERM.
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.context.shutdown.clone()
+    }
+
+    /// Graceful shutdown
+    ///
+    /// Ends client sessions with a notification and stops the server.
+    pub fn shutdown(&self) {
+        self.context.shutdown.request("Shutdown requested");
+    }
+}
+
+impl SessionContext {
+    /// Create a capture session via the selected strategy
+    ///
+    /// Each call may show a permission dialog unless a restore token applies.
+    async fn create_capture_session(&self) -> Result<CaptureSession> {
+        let strategy = &self.strategy;
+        let probed = self.environment.borrow().clone();
+        let capabilities = &probed.capabilities;
+        let portal_manager = Arc::clone(&self.portal_manager);
+
         // Create session via selected strategy
+        chaos::delay_portal().await;
         info!("Creating session via selected strategy");
         let session_handle = strategy
             .create_session()
@@ -271,7 +1345,7 @@ impl LamcoRdpServer {
         info!("✅ Session created successfully via {}", strategy.name());
 
         // Extract session details and handle different PipeWire access methods
-        let (pipewire_fd, stream_info) = match session_handle.pipewire_access() {
+        let (video, stream_info) = match session_handle.pipewire_access() {
             PipeWireAccess::FileDescriptor(fd) => {
                 // Portal path: FD directly provided
                 info!("Using Portal-provided PipeWire file descriptor: {}", fd);
@@ -288,7 +1362,7 @@ impl LamcoRdpServer {
                     })
                     .collect();
 
-                (fd, portal_streams)
+                (VideoSource::PipeWire(fd), portal_streams)
             }
             PipeWireAccess::NodeId(node_id) => {
                 // Mutter path: Node ID provided, need to connect to PipeWire daemon
@@ -311,97 +1385,231 @@ impl LamcoRdpServer {
                     })
                     .collect();
 
-                (fd, portal_streams)
+                (VideoSource::PipeWire(fd), portal_streams)
             }
-        };
+            PipeWireAccess::Screencopy => {
+                // wlr-direct path: each pipeline copies the outputs itself
+                info!("Using wlr-screencopy for video (no PipeWire)");
 
-        // Create Portal manager for input+clipboard (needed for both strategies)
-        let mut portal_config = config.to_portal_config();
-        portal_config.persist_mode = ashpd::desktop::PersistMode::DoNot; // Don't persist (causes errors)
-        portal_config.restore_token = None;
+                // Streams are outputs, identified by wl_output global name
+                let strategy_streams = session_handle.streams();
+                let portal_streams: Vec<crate::portal::StreamInfo> = strategy_streams
+                    .iter()
+                    .map(|s| crate::portal::StreamInfo {
+                        node_id: s.node_id,
+                        position: (s.position_x, s.position_y),
+                        size: (s.width, s.height),
+                        source_type: crate::portal::SourceType::Monitor,
+                    })
+                    .collect();
 
-        let portal_manager = Arc::new(
-            PortalManager::new(portal_config)
-                .await
-                .context("Failed to create Portal manager for input+clipboard")?,
-        );
+                (VideoSource::Screencopy, portal_streams)
+            }
+        };
+
+        // Leave out the monitors the configured layout doesn't share
+        let stream_info = shared_streams(stream_info, &self.config.multimon.monitors);
 
-        // Get clipboard components from session handle, or create fallback Portal session
+        // Share the session's input and clipboard, or create a companion Portal session
         // HYBRID STRATEGY: For Mutter, we also use Portal session for input (Mutter input broken on GNOME 46)
-        let (portal_clipboard_manager, portal_clipboard_session, portal_input_handle) =
-            if session_handle.session_type() == SessionType::Portal {
-                // Portal strategy: use session_handle directly (no duplicate sessions)
-                info!("Portal strategy: using session_handle directly");
-
-                // Portal strategy always provides ClipboardComponents
-                // manager may be None on Portal v1, but session is always present
-                let clipboard_components = session_handle
-                    .portal_clipboard()
-                    .expect("Portal strategy always provides ClipboardComponents");
-
-                let clipboard_mgr = clipboard_components.manager; // Option<Arc<...>>
-                let session = clipboard_components.session; // Always present
-
-                (clipboard_mgr, session, session_handle)
-            } else {
-                // Mutter strategy: Need separate Portal session for input AND clipboard (one dialog)
-                // HYBRID: Mutter provides video (zero dialogs), Portal provides input+clipboard (one dialog)
-                info!("Strategy doesn't provide clipboard, creating separate Portal session for input+clipboard");
-                info!("HYBRID MODE: Mutter for video (zero dialogs), Portal for input+clipboard (one dialog)");
-
-                let session_id = format!("lamco-rdp-input-clipboard-{}", uuid::Uuid::new_v4());
-                let (portal_handle, _) = portal_manager
-                    .create_session(session_id, None)
-                    .await
-                    .context("Failed to create Portal session for input+clipboard")?;
-
-                // Only create clipboard if Portal supports it (v2+)
-                let clipboard_mgr = if capabilities.portal.supports_clipboard {
-                    Some(Arc::new(
-                        lamco_portal::ClipboardManager::new()
-                            .await
-                            .context("Failed to create Portal clipboard manager")?,
-                    ))
-                } else {
-                    info!(
-                        "Skipping clipboard creation - Portal v{} doesn't support clipboard",
-                        capabilities.portal.version
-                    );
-                    None
-                };
+        let session = if session_handle.session_type() == SessionType::Portal {
+            // Portal strategy: one session serves video, input and clipboard
+            info!("Portal strategy: sharing one session for video, input and clipboard");
+
+            // Portal strategy always provides ClipboardComponents
+            // manager may be None on Portal v1, but session is always present
+            let clipboard_components = session_handle
+                .portal_clipboard()
+                .expect("Portal strategy always provides ClipboardComponents");
+
+            SharedSession::new(
+                Arc::clone(&session_handle),
+                Arc::clone(&session_handle),
+                Some(clipboard_components),
+            )
+        } else if matches!(video, VideoSource::Screencopy) {
+            // wlr-direct with screencopy: video and input without any portal
+            // (xdg-desktop-portal-wlr has no RemoteDesktop), so no clipboard
+            info!("wlr-direct: native protocols for video and input, no Portal session");
+            info!("Clipboard sync unavailable without a Portal session");
 
-                info!("Separate Portal session created for input+clipboard (non-persistent)");
+            SharedSession::new(Arc::clone(&session_handle), session_handle, None)
+        } else {
+            // Mutter/KWin strategies: Need separate Portal session for input AND clipboard (one dialog)
+            // HYBRID: the compositor provides video (zero dialogs), Portal provides input+clipboard (one dialog)
+            info!("Strategy doesn't provide clipboard, creating separate Portal session for input+clipboard");
+            info!(
+                "HYBRID MODE: {} for video (zero dialogs), Portal for input+clipboard (one dialog)",
+                session_handle.session_type()
+            );
 
-                let session = Arc::new(RwLock::new(portal_handle.session));
+            // Only create clipboard if the portal matrix has it
+            let supports_clipboard = capabilities.portal.supports_clipboard;
+            if !supports_clipboard {
+                info!(
+                    "Skipping clipboard creation - Portal has no clipboard (RemoteDesktop v{}, Clipboard v{})",
+                    capabilities.portal.remote_desktop_version, capabilities.portal.clipboard_version
+                );
+            }
+            let (input_handle, clipboard_components) =
+                input_fallback::companion_portal_session(&portal_manager, supports_clipboard)
+                    .await?;
+
+            SharedSession::new(
+                session_handle,
+                Arc::new(input_handle),
+                Some(clipboard_components),
+            )
+        };
 
-                // Create PortalSessionHandleImpl for input
-                // If no clipboard (Portal v1), just use Mutter session_handle for input instead
-                // Create Portal input handle regardless of clipboard availability
-                let input_handle =
-                    crate::session::strategies::PortalSessionHandleImpl::from_portal_session(
-                        session.clone(),
-                        portal_manager.remote_desktop().clone(),
-                        clipboard_mgr.clone(), // Pass Option directly
-                    );
+        // Move input to another backend rather than dropping a working capture
+        let session = if self.config.input.fallback_chain.is_empty() {
+            session
+        } else {
+            let links = parse_chain(&self.config.input.fallback_chain)?;
+            let provider = SessionInputProvider::new(Arc::clone(&portal_manager));
+            session.with_input_failover(FailoverChain::new(links, Arc::new(provider)))
+        };
 
-                (
-                    clipboard_mgr,
-                    session,
-                    Arc::new(input_handle) as Arc<dyn crate::session::SessionHandle>,
+        // Notice the user stopping the share or the portal going away
+        session.spawn_closed_watch();
+        // ... or input that can't be delivered any more
+        session.spawn_input_watch();
+
+        Ok(CaptureSession {
+            session,
+            video,
+            stream_info,
+        })
+    }
+
+    /// Build a client pipeline on top of a capture session
+    ///
+    /// A PipeWire `video` source is consumed by the pipeline's PipeWire
+    /// thread, so shared pipelines must pass a duplicate. Only [`PipelineRole::Full`] pipelines
+    /// sync the clipboard with the host; the others get an isolated one.
+    /// With `idle_stop` (the primary pipeline), capture stops while no
+    /// client is connected.
+    async fn build_pipeline(
+        &self,
+        capture: &CaptureSession,
+        video: VideoSource,
+        role: PipelineRole,
+        idle_stop: Option<IdleStop>,
+    ) -> Result<ClientPipeline> {
+        let with_clipboard = role == PipelineRole::Full;
+        // New pipelines start with the latest runtime-safe settings and
+        // probed capabilities
+        let config = Arc::clone(&self.live_config.borrow());
+        let probed = self.environment.borrow().clone();
+        let capabilities = &probed.capabilities;
+        let service_registry = Arc::clone(&probed.service_registry);
+        let portal_manager = Arc::clone(&self.portal_manager);
+        let stream_info = capture.stream_info.clone();
+        // Observers acknowledge the banner with the input view-only discards
+        let login_banner = LoginBanner::from_config(&config.login_banner)?;
+        let quality_overlay = config
+            .quality_overlay
+            .enabled
+            .then(|| QualityOverlay::new(config.quality_overlay.show_on_connect));
+        // Without metadata the portal paints the cursor into the video
+        let cursor_channel = config
+            .video
+            .cursor_mode
+            .eq_ignore_ascii_case("metadata")
+            .then(|| {
+                CursorChannel::from_config(&config.cursor).with_cursor_in_frames(
+                    capabilities
+                        .profile
+                        .has_quirk(&crate::compositor::Quirk::CursorAlwaysInFrame),
                 )
-            };
+            });
+        if let Some(ref cursor) = cursor_channel {
+            cursor.watch_focus(capabilities.compositor.clone());
+        }
+        // Without a pointer to show, touch and pen contacts act where they land
+        let direct_manipulation =
+            config.cursor.mode.eq_ignore_ascii_case("hidden").then(|| {
+                DirectManipulation::new(config.cursor.direct_touch, config.cursor.direct_pen)
+            });
+        let portal_input = capture.session.input();
+        let portal_clipboard = capture.session.clipboard();
 
         info!(
-            "Session started with {} streams, PipeWire FD: {}",
+            "Session started with {} streams, video: {:?}",
             stream_info.len(),
-            pipewire_fd
+            video
         );
 
-        // Determine initial desktop size from first stream
-        let initial_size = stream_info
-            .first()
-            .map(|s| (s.size.0 as u16, s.size.1 as u16))
-            .unwrap_or((1920, 1080)); // Default fallback
+        // === FOLLOW-FOCUS ===
+        // Present a single viewport that follows the pointer across monitors
+        let follow_focus_mode = config
+            .multimon
+            .follow_focus
+            .parse::<FollowFocusMode>()
+            .unwrap_or_default();
+        let follow_focus = if follow_focus_mode == FollowFocusMode::Off {
+            None
+        } else {
+            let viewport = config
+                .multimon
+                .follow_focus_viewport_size()
+                .map_err(anyhow::Error::msg)?;
+            Some(
+                FollowFocusController::from_streams(
+                    follow_focus_mode,
+                    &stream_info,
+                    viewport,
+                    std::time::Duration::from_millis(config.multimon.follow_focus_dwell_ms),
+                )
+                .context("Failed to create follow-focus controller")?
+                .into_shared(),
+            )
+        };
+
+        // Determine captured size from first stream (or follow-focus viewport)
+        let capture_size = match follow_focus {
+            Some(ref ff) => ff
+                .lock()
+                .map(|ctl| ctl.client_size())
+                .unwrap_or((1920, 1080)),
+            None => stream_info
+                .first()
+                .map(|s| (s.size.0 as u16, s.size.1 as u16))
+                .unwrap_or((1920, 1080)), // Default fallback
+        };
+
+        // === SERVER-SIDE SCALING ===
+        // Portal mode can't resize the host, so adapt frames to the configured size
+        let scaling_policy = config
+            .display
+            .scaling_policy
+            .parse::<ScalingPolicy>()
+            .unwrap_or_default();
+        let frame_scaler = match config
+            .display
+            .scaling_output_dimensions()
+            .map_err(anyhow::Error::msg)?
+        {
+            Some(output) if scaling_policy != ScalingPolicy::Off => Some(
+                FrameScaler::new(
+                    scaling_policy,
+                    output,
+                    (capture_size.0 as u32, capture_size.1 as u32),
+                )
+                .into_shared(),
+            ),
+            _ => None,
+        };
+
+        // Client sees the scaler output when scaling is active
+        let initial_size = match frame_scaler {
+            Some(ref scaler) => scaler
+                .lock()
+                .map(|s| (s.output_size().0 as u16, s.output_size().1 as u16))
+                .unwrap_or(capture_size),
+            None => capture_size,
+        };
 
         info!(
             "Initial desktop size: {}x{}",
@@ -431,22 +1639,30 @@ impl LamcoRdpServer {
             initial_size.0 as u16,
             initial_size.1 as u16,
             force_avc420_only,
-        );
+        )
+        .with_capture(self.pdu_capture.clone());
         // Get shared references BEFORE passing factory to builder
         let gfx_handler_state = gfx_factory.handler_state();
         let gfx_server_handle = gfx_factory.server_handle();
+        let gfx_ack_latency = gfx_factory.ack_latency();
         if force_avc420_only {
             info!("EGFX factory created for H.264/AVC420 streaming (AVC444 disabled by platform quirk)");
         } else {
             info!("EGFX factory created for H.264/AVC420+AVC444 streaming");
         }
 
-        // Create display handler with PipeWire FD, stream info, graphics queue, and EGFX references
+        // Capture restarts after an idle stop from a spare of the source
+        let idle_stop = match idle_stop {
+            Some(idle_stop) => Some((idle_stop, video.duplicate()?)),
+            None => None,
+        };
+
+        // Create display handler with video source, stream info, graphics queue, and EGFX references
         let display_handler = Arc::new(
             LamcoDisplayHandler::new(
                 initial_size.0,
                 initial_size.1,
-                pipewire_fd,
+                video,
                 stream_info.to_vec(), // streams() returns &[StreamInfo], convert to Vec
                 Some(graphics_tx),    // Graphics queue for multiplexer
                 Some(gfx_server_handle), // EGFX server handle for H.264 frame sending
@@ -455,7 +1671,18 @@ impl LamcoRdpServer {
                 Arc::clone(&service_registry), // Service registry for feature decisions
             )
             .await
-            .context("Failed to create display handler")?,
+            .context("Failed to create display handler")?
+            .with_follow_focus(follow_focus.clone())
+            .with_frame_scaler(frame_scaler.clone())
+            .with_live_config(self.live_config.clone())
+            .with_login_banner(login_banner.clone())
+            .with_quality_overlay(quality_overlay.clone())
+            .with_ack_latency(gfx_ack_latency)
+            .with_cursor_channel(cursor_channel)
+            .with_capture_liveness(capture.session.liveness().clone())
+            .with_idle_stop(idle_stop)
+            .with_frame_dump(self.frame_dump.clone())
+            .with_events(SessionEvents::new(self.events.clone())),
         );
 
         // Start the graphics drain task
@@ -470,11 +1697,14 @@ impl LamcoRdpServer {
         // Create input handler for mouse and keyboard injection
         info!("Creating input handler for mouse/keyboard control");
 
-        // Convert stream info to monitor info for coordinate transformation
+        // Convert stream info to monitor info for coordinate transformation,
+        // placing the monitors where the configured layout has them
+        let rdp_positions = virtual_positions(&stream_info, &config.multimon.monitors);
         let monitors: Vec<InputMonitorInfo> = stream_info
             .iter()
+            .zip(&rdp_positions)
             .enumerate()
-            .map(|(idx, stream)| InputMonitorInfo {
+            .map(|(idx, (stream, &(stream_x, stream_y)))| InputMonitorInfo {
                 id: idx as u32,
                 name: format!("Monitor {}", idx),
                 x: stream.position.0 as i32,
@@ -483,8 +1713,8 @@ impl LamcoRdpServer {
                 height: stream.size.1 as u32,
                 dpi: 96.0,         // Default DPI
                 scale_factor: 1.0, // Default scale, Portal doesn't provide this
-                stream_x: stream.position.0 as u32,
-                stream_y: stream.position.1 as u32,
+                stream_x,
+                stream_y,
                 stream_width: stream.size.0 as u32,
                 stream_height: stream.size.1 as u32,
                 is_primary: idx == 0, // First monitor is primary
@@ -502,13 +1732,22 @@ impl LamcoRdpServer {
         // Create input handler using Portal session handle (works correctly)
         // HYBRID: For Mutter strategy, uses Portal for input while Mutter handles video
         let input_handler = LamcoInputHandler::new(
-            portal_input_handle, // Use Portal session for input (works on all DEs)
+            portal_input, // Use Portal session for input (works on all DEs)
             monitors.clone(),
             primary_stream_id,
             input_tx.clone(), // Multiplexer input queue sender (for handler callbacks)
             input_rx,         // Multiplexer input queue receiver (for batching task)
+            follow_focus,     // Viewport mapping when follow-focus is enabled
+            frame_scaler,     // Output → capture mapping when scaling is enabled
         )
-        .context("Failed to create input handler")?;
+        .context("Failed to create input handler")?
+        .with_view_only(role == PipelineRole::Observer)
+        .with_login_banner(login_banner)
+        .with_quality_overlay(quality_overlay)
+        .with_sharing(display_handler.sharing())
+        .with_session_meter(display_handler.session_meter())
+        .with_direct_manipulation(direct_manipulation)
+        .with_capture(self.pdu_capture.clone());
 
         info!("Input handler created successfully - mouse/keyboard enabled via Portal");
 
@@ -519,17 +1758,14 @@ impl LamcoRdpServer {
         let keyboard_handler = input_handler.keyboard_handler.clone();
         let mouse_handler = input_handler.mouse_handler.clone();
         let coord_transformer = input_handler.coordinate_transformer.clone();
-        // On Portal v1, portal_clipboard_session may be placeholder - but multiplexer only uses it if clipboard_mgr exists
-        let session_for_mux = Arc::clone(&portal_clipboard_session);
 
-        tokio::spawn(multiplexer_loop::run_multiplexer_drain_loop(
+        spawn_in_current_span(multiplexer_loop::run_multiplexer_drain_loop(
             control_rx,
             clipboard_rx,
             portal_for_mux,
             keyboard_handler,
             mouse_handler,
             coord_transformer,
-            session_for_mux,
             primary_stream_id,
         ));
         info!("🚀 Full multiplexer drain loop started (control + clipboard priorities)");
@@ -550,15 +1786,28 @@ impl LamcoRdpServer {
 
         // Create clipboard manager
         info!("Initializing clipboard manager");
-        let clipboard_config = ClipboardConfig::default();
+        let clipboard_config = ClipboardConfig {
+            owner_signals_reliable: !capabilities
+                .profile
+                .has_quirk(&crate::compositor::Quirk::UnreliableClipboardOwnerSignals),
+            ..ClipboardConfig::default()
+        };
         let mut clipboard_mgr = ClipboardManager::new(clipboard_config)
             .await
             .context("Failed to create clipboard manager")?;
 
         // Set Portal clipboard reference if available (from session or fallback)
-        if let Some(clipboard_mgr_arc) = portal_clipboard_manager {
+        // Shared-view clients get an isolated clipboard with no host backing
+        let portal_clipboard_manager = if with_clipboard {
+            portal_clipboard.manager()
+        } else {
+            None
+        };
+        if let (Some(clipboard_mgr_arc), Some(portal_session)) =
+            (portal_clipboard_manager, portal_clipboard.session())
+        {
             clipboard_mgr
-                .set_portal_clipboard(clipboard_mgr_arc, Arc::clone(&portal_clipboard_session))
+                .set_portal_clipboard(clipboard_mgr_arc, portal_session)
                 .await;
             // Note: Success message logged inside set_portal_clipboard
         } else {
@@ -567,16 +1816,34 @@ impl LamcoRdpServer {
 
         // Mount FUSE filesystem for clipboard file transfer
         // This enables on-demand file streaming for Windows → Linux file copy
-        if let Err(e) = clipboard_mgr.mount_fuse().await {
-            warn!("Failed to mount FUSE clipboard filesystem: {:?}", e);
-            warn!("File clipboard will use staging fallback (download files upfront)");
+        if with_clipboard {
+            if let Err(e) = clipboard_mgr.mount_fuse().await {
+                warn!("Failed to mount FUSE clipboard filesystem: {:?}", e);
+                warn!("File clipboard will use staging fallback (download files upfront)");
+            }
+        }
+
+        // Runtime policy follows [clipboard] and is updated on config reload
+        let clipboard_policy = clipboard_mgr.policy();
+        clipboard_policy.set_enabled(config.clipboard.enabled);
+        clipboard_policy.set_rate_limit_ms(config.clipboard.rate_limit_ms);
+        display_handler
+            .sharing()
+            .attach_clipboard(clipboard_policy.clone());
+        if self.events.is_some() {
+            let events = display_handler.events();
+            clipboard_policy.set_transfer_observer(Arc::new(move |transfer| {
+                events.clipboard_transfer(transfer)
+            }));
         }
+        display_handler.set_clipboard_policy(clipboard_policy).await;
 
         let clipboard_manager = Arc::new(Mutex::new(clipboard_mgr));
 
         // Create clipboard factory for IronRDP
         // Factory automatically starts event bridge task internally
-        let clipboard_factory = LamcoCliprdrFactory::new(Arc::clone(&clipboard_manager));
+        let clipboard_factory = LamcoCliprdrFactory::new(Arc::clone(&clipboard_manager))
+            .with_capture(self.pdu_capture.clone());
 
         // Note: gfx_factory was created earlier (before display handler)
         // to share references with display handler
@@ -606,84 +1873,173 @@ impl LamcoRdpServer {
             .await;
         info!("Server event sender configured in display handler");
 
-        info!("Server initialized successfully");
-
-        Ok(Self {
-            config,
+        Ok(ClientPipeline {
             rdp_server,
-            portal_manager,
             display_handler,
+            clipboard_manager,
         })
     }
 
-    /// Run the server
+    /// Build a pipeline for an additional client and serve its connection
     ///
-    /// This starts the RDP server and handles incoming connections.
-    /// Blocks until the server is shut down.
-    pub async fn run(mut self) -> Result<()> {
-        info!("╔════════════════════════════════════════════════════════════╗");
-        info!("║          Server Starting                                   ║");
-        info!("╚════════════════════════════════════════════════════════════╝");
-        info!("  Listen Address: {}", self.config.server.listen_addr);
-        info!("  TLS: Enabled (rustls 0.23)");
-        info!("  Codec: RemoteFX");
-        info!("  Max Connections: {}", self.config.server.max_connections);
-        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-
-        info!("Server is ready and listening for RDP connections");
-        info!("Waiting for clients to connect...");
-
-        // Set credentials for RDP authentication
-        // Even with auth_method="none", we need to set empty/test credentials
-        // for IronRDP to complete the protocol handshake properly
-        let credentials = if self.config.security.auth_method == "none" {
-            Some(Credentials {
-                username: String::new(),
-                password: String::new(),
-                domain: None,
-            })
-        } else {
-            // For future authentication support
-            None
+    /// `peer_watch` must belong to `stream`.
+    async fn serve_additional_client(
+        &self,
+        kind: ClientKind,
+        primary_capture: &CaptureSession,
+        stream: tokio::net::TcpStream,
+        peer_watch: PeerWatch,
+        slot: &ClientSlot,
+    ) -> Result<()> {
+        // Separate clients own a capture session for the connection lifetime
+        let separate_capture;
+        let capture = match kind {
+            ClientKind::Separate => {
+                separate_capture = self.create_capture_session().await?;
+                &separate_capture
+            }
+            _ => primary_capture,
+        };
+        let mut pipeline = match kind {
+            ClientKind::Separate => {
+                self.build_pipeline(capture, capture.video, PipelineRole::Full, None)
+                    .await?
+            }
+            _ => {
+                let role = if kind == ClientKind::Observer {
+                    PipelineRole::Observer
+                } else {
+                    PipelineRole::SharedView
+                };
+                let video = primary_capture.video.duplicate()?;
+                self.build_pipeline(primary_capture, video, role, None)
+                    .await?
+            }
         };
 
-        self.rdp_server.set_credentials(credentials);
-        info!(
-            "Authentication configured: {}",
-            self.config.security.auth_method
-        );
-
-        // Run the IronRDP server
-        let result = self.rdp_server.run().await.context("RDP server error");
+        pipeline
+            .rdp_server
+            .set_credentials(rdp_credentials(&self.config));
+        let meter = pipeline.display_handler.session_meter();
+        let banner = pipeline.display_handler.login_banner();
+        slot.set_meter(meter.clone());
+        if let Some(overlay) = pipeline.display_handler.quality_overlay() {
+            slot.set_overlay(overlay);
+        }
+        slot.set_sharing(pipeline.display_handler.sharing());
+        slot.set_message(pipeline.display_handler.session_message());
+        pipeline.display_handler.events().attach(slot.id());
+        let monitor = ResourceMonitor::new(
+            meter,
+            &stream,
+            ResourceLimits::from_config(&self.config.resource_limits),
+        )
+        .with_link_estimate(pipeline.display_handler.link_estimate())
+        .with_housekeeping(&self.housekeeping);
+
+        let events = pipeline.rdp_server.event_sender().clone();
+
+        let result = tokio::select! {
+            result = serve_until_disconnected(
+                pipeline.rdp_server.run_connection(stream),
+                &events,
+                slot,
+                &self.shutdown,
+                &capture.session,
+            ) => result,
+            reason = peer_watch.dead() => {
+                warn!("💀 Client {} is unresponsive: {}", slot.id(), reason);
+                Ok(())
+            }
+            reason = monitor.run() => {
+                warn!("⛔ Client {} terminated: {}", slot.id(), reason);
+                Ok(())
+            }
+            () = banner.expired() => {
+                warn!("📜 Client {} did not acknowledge the login banner", slot.id());
+                Ok(())
+            }
+        };
+        pipeline.display_handler.stop_pipeline();
+        close_clipboard(&pipeline.clipboard_manager).await;
+        result
+    }
+}
 
-        if let Err(ref e) = result {
-            error!("Server stopped with error: {:#}", e);
-        } else {
-            info!("Server stopped gracefully");
+/// Serve a connection until it ends, its slot is asked to disconnect or its
+/// capture session is lost
+///
+/// In the latter cases the client is told the server is ending its session
+/// and gets the shutdown grace period to leave; the connection is closed if
+/// it is still open afterwards.
+async fn serve_until_disconnected(
+    connection: impl std::future::Future<Output = Result<()>>,
+    events: &tokio::sync::mpsc::UnboundedSender<ServerEvent>,
+    slot: &ClientSlot,
+    shutdown: &Shutdown,
+    capture: &SharedSession,
+) -> Result<()> {
+    tokio::pin!(connection);
+    let reason = tokio::select! {
+        result = &mut connection => return result,
+        () = slot.disconnect_requested() => shutdown
+            .reason()
+            .unwrap_or_else(|| "Disconnected by the server".to_string()),
+        reason = capture.closed() => {
+            format!("Screen sharing ended on the host ({})", reason)
+        }
+    };
+    info!("Ending session of client {}: {}", slot.id(), reason);
+    if events.send(ServerEvent::Quit(reason)).is_err() {
+        return Ok(());
+    }
+    match tokio::time::timeout(shutdown.grace(), connection).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                "Client {} did not disconnect within {}s; closing",
+                slot.id(),
+                shutdown.grace().as_secs()
+            );
+            Ok(())
         }
+    }
+}
 
-        info!("Server shutdown complete");
-        result
+/// Stop a pipeline's clipboard manager and release its FUSE mount
+async fn close_clipboard(clipboard: &Mutex<ClipboardManager>) {
+    let mut clipboard = clipboard.lock().await;
+    if let Err(e) = clipboard.shutdown().await {
+        debug!("Clipboard manager already stopped: {}", e);
+    }
+    if let Err(e) = clipboard.unmount_fuse().await {
+        warn!("Failed to unmount clipboard filesystem: {}", e);
     }
+}
 
-    /// Graceful shutdown
-    ///
-    /// Sends a quit event to stop the server gracefully.
-    pub fn shutdown(&self) {
-        info!("Initiating graceful shutdown");
-        let _ = self
-            .rdp_server
-            .event_sender()
-            .send(ironrdp_server::ServerEvent::Quit(
-                "Shutdown requested".to_string(),
-            ));
+/// Credentials handed to IronRDP for the configured auth method
+fn rdp_credentials(config: &Config) -> Option<Credentials> {
+    // Even with auth_method="none", we need to set empty/test credentials
+    // for IronRDP to complete the protocol handshake properly
+    if config.security.auth_method == "none" {
+        Some(Credentials {
+            username: String::new(),
+            password: String::new(),
+            domain: None,
+        })
+    } else {
+        // For future authentication support
+        None
     }
 }
 
-impl Drop for LamcoRdpServer {
+impl Drop for CaptureSession {
     fn drop(&mut self) {
-        debug!("LamcoRdpServer dropped - cleaning up resources");
-        // Resources are automatically cleaned up through Arc<Mutex<>> drops
+        debug!("Capture session dropped - cleaning up resources");
+        // Pipelines may outlive the capture briefly; stop their input
+        // injection against the released session
+        self.session.close("capture session released");
+        // Remaining resources are cleaned up through Arc drops
         // and tokio task cancellation
     }
 }
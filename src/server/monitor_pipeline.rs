@@ -0,0 +1,299 @@
+//! Per-Monitor Pipelines
+//!
+//! Frames of all monitors of a pipeline arrive on one channel. Taken in
+//! arrival order under one shared frame rate, a monitor playing video sends
+//! dozens of frames a second that all count against that rate, and the
+//! primary monitor's next frame waits behind them or is dropped.
+//!
+//! Each monitor here has a pipeline of its own: a slot holding its latest
+//! frame (a newer frame replaces one still waiting, so a busy monitor never
+//! builds a backlog), its own pacing at its configured frame rate, its own
+//! damage detector (comparing a monitor's frame against another monitor's
+//! would mark everything changed), and its own stats. Whenever several
+//! monitors have a frame due, the primary goes first and the others take
+//! turns.
+//!
+//! Encoding and sending still share the session's encoder and EGFX surface,
+//! one frame at a time.
+
+use std::time::{Duration, Instant};
+
+use crate::damage::{DamageConfig, DamageDetector};
+
+/// How early a waiting frame may be and still count as due, so capture
+/// jitter doesn't push every other frame to the next poll
+const PACING_SLACK: Duration = Duration::from_millis(2);
+
+/// A frame belonging to one of a pipeline's monitors
+pub(super) trait MonitorFrame {
+    /// Index of the monitor (stream or output) the frame was captured from
+    fn monitor_index(&self) -> u32;
+}
+
+impl MonitorFrame for crate::pipewire::VideoFrame {
+    fn monitor_index(&self) -> u32 {
+        self.monitor_index
+    }
+}
+
+/// Counters of one monitor's pipeline
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct MonitorStats {
+    /// Frames captured
+    pub received: u64,
+    /// Frames replaced by a newer one before their turn
+    pub superseded: u64,
+    /// Frames encoded and sent
+    pub sent: u64,
+    /// Smoothed time to encode and send a frame
+    pub encode_ms: f64,
+}
+
+struct Monitor<F> {
+    index: u32,
+    /// Configured frame rate of the monitor
+    max_fps: u32,
+    /// Latest frame and when it arrived
+    pending: Option<(F, Instant)>,
+    last_taken: Option<Instant>,
+    damage: Option<DamageDetector>,
+    stats: MonitorStats,
+}
+
+impl<F> Monitor<F> {
+    fn new(index: u32, max_fps: u32) -> Self {
+        Self {
+            index,
+            max_fps,
+            pending: None,
+            last_taken: None,
+            damage: None,
+            stats: MonitorStats::default(),
+        }
+    }
+
+    /// Time until the pending frame may go, zero when due
+    fn wait(&self, ceiling_fps: u32, now: Instant) -> Option<Duration> {
+        self.pending.as_ref()?;
+        let Some(last) = self.last_taken else {
+            return Some(Duration::ZERO);
+        };
+        let fps = self.max_fps.min(ceiling_fps).max(1);
+        let interval = Duration::from_secs(1) / fps;
+        let due = last + interval.saturating_sub(PACING_SLACK);
+        Some(due.saturating_duration_since(now))
+    }
+}
+
+/// The monitors of one pipeline, each paced on its own
+pub(super) struct MonitorPipelines<F> {
+    /// Primary monitor first
+    monitors: Vec<Monitor<F>>,
+    /// Frame rate of monitors the pipeline was not created with
+    default_fps: u32,
+    /// Pipeline-wide frame rate (adaptive FPS, decode cap) no monitor exceeds
+    ceiling_fps: u32,
+    damage_config: Option<DamageConfig>,
+    /// Monitor after which the next turn among secondary monitors starts
+    turn: usize,
+}
+
+impl<F: MonitorFrame> MonitorPipelines<F> {
+    /// Pipelines for `monitors` (monitor index and frame rate, primary first)
+    ///
+    /// Monitors get a damage detector each when `damage_config` is given.
+    pub fn new(
+        monitors: impl IntoIterator<Item = (u32, u32)>,
+        default_fps: u32,
+        damage_config: Option<DamageConfig>,
+    ) -> Self {
+        Self {
+            monitors: monitors
+                .into_iter()
+                .map(|(monitor_index, fps)| Monitor::new(monitor_index, fps))
+                .collect(),
+            default_fps,
+            ceiling_fps: default_fps,
+            damage_config,
+            turn: 0,
+        }
+    }
+
+    /// Keep `frame` as its monitor's latest, replacing one still waiting
+    pub fn queue(&mut self, frame: F, now: Instant) {
+        let idx = self.slot(frame.monitor_index());
+        let monitor = &mut self.monitors[idx];
+        monitor.stats.received += 1;
+        if monitor.pending.replace((frame, now)).is_some() {
+            monitor.stats.superseded += 1;
+        }
+    }
+
+    /// Cap every monitor's frame rate at `fps`
+    pub fn set_ceiling(&mut self, fps: u32) {
+        self.ceiling_fps = fps;
+    }
+
+    /// Next frame due and when it arrived
+    ///
+    /// The primary monitor goes first; the others take turns.
+    pub fn next_frame(&mut self, now: Instant) -> Option<(F, Instant)> {
+        let ceiling = self.ceiling_fps;
+        let count = self.monitors.len();
+        let idx = (0..count)
+            .map(|i| match i {
+                0 => 0,
+                i => 1 + (self.turn + i - 1) % count.saturating_sub(1).max(1),
+            })
+            .find(|&idx| self.monitors[idx].wait(ceiling, now) == Some(Duration::ZERO))?;
+
+        if idx > 0 {
+            self.turn = idx;
+        }
+        let monitor = &mut self.monitors[idx];
+        monitor.last_taken = Some(now);
+        monitor.pending.take()
+    }
+
+    /// Time until the next waiting frame is due (None without one)
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        self.monitors
+            .iter()
+            .filter_map(|monitor| monitor.wait(self.ceiling_fps, now))
+            .min()
+    }
+
+    /// Damage detector of a monitor (None with damage tracking disabled)
+    pub fn damage_detector(&mut self, monitor_index: u32) -> Option<&mut DamageDetector> {
+        let config = self.damage_config.clone()?;
+        let idx = self.slot(monitor_index);
+        Some(
+            self.monitors[idx]
+                .damage
+                .get_or_insert_with(|| DamageDetector::new(config)),
+        )
+    }
+
+    /// Make every monitor's next frame count as fully damaged
+    pub fn invalidate_damage(&mut self) {
+        for detector in self.monitors.iter_mut().filter_map(|m| m.damage.as_mut()) {
+            detector.invalidate();
+        }
+    }
+
+    /// Note a monitor's frame sent after `encode` spent encoding and sending it
+    pub fn record_sent(&mut self, monitor_index: u32, encode: Duration) {
+        let idx = self.slot(monitor_index);
+        let stats = &mut self.monitors[idx].stats;
+        let sample = encode.as_secs_f64() * 1000.0;
+        stats.encode_ms = if stats.sent == 0 {
+            sample
+        } else {
+            stats.encode_ms + 0.2 * (sample - stats.encode_ms)
+        };
+        stats.sent += 1;
+    }
+
+    /// Stats of each monitor, primary first
+    pub fn stats(&self) -> impl Iterator<Item = (u32, MonitorStats)> + '_ {
+        self.monitors.iter().map(|m| (m.index, m.stats))
+    }
+
+    /// Drop waiting frames, e.g. when capture stops
+    pub fn clear(&mut self) {
+        for monitor in &mut self.monitors {
+            monitor.pending = None;
+        }
+    }
+
+    fn slot(&mut self, monitor_index: u32) -> usize {
+        match self.monitors.iter().position(|m| m.index == monitor_index) {
+            Some(idx) => idx,
+            None => {
+                self.monitors
+                    .push(Monitor::new(monitor_index, self.default_fps));
+                self.monitors.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Frame(u32, u32);
+
+    impl MonitorFrame for Frame {
+        fn monitor_index(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_latest_frame_per_monitor() {
+        let now = Instant::now();
+        let mut monitors = MonitorPipelines::new([(1, 30), (2, 30)], 30, None);
+
+        // A busy secondary keeps only its latest frame
+        for n in 0..5 {
+            monitors.queue(Frame(2, n), now);
+        }
+        monitors.queue(Frame(1, 0), now);
+
+        // Primary first, then the secondary's latest
+        assert_eq!(monitors.next_frame(now).unwrap().0, Frame(1, 0));
+        assert_eq!(monitors.next_frame(now).unwrap().0, Frame(2, 4));
+        assert!(monitors.next_frame(now).is_none());
+
+        let stats: Vec<_> = monitors.stats().collect();
+        assert_eq!(stats[1].1.received, 5);
+        assert_eq!(stats[1].1.superseded, 4);
+    }
+
+    #[test]
+    fn test_independent_pacing() {
+        let start = Instant::now();
+        let mut monitors = MonitorPipelines::new([(1, 60), (2, 15)], 60, None);
+        monitors.queue(Frame(1, 0), start);
+        monitors.queue(Frame(2, 0), start);
+        assert!(monitors.next_frame(start).is_some());
+        assert!(monitors.next_frame(start).is_some());
+
+        // 20ms later the 60 FPS primary is due again, the 15 FPS secondary not
+        let at = start + Duration::from_millis(20);
+        monitors.queue(Frame(1, 1), at);
+        monitors.queue(Frame(2, 1), at);
+        assert_eq!(monitors.next_frame(at).unwrap().0, Frame(1, 1));
+        assert!(monitors.next_frame(at).is_none());
+        let wait = monitors.next_due(at).unwrap();
+        assert!(wait > Duration::from_millis(40) && wait <= Duration::from_millis(47));
+
+        // The ceiling holds the primary down as well
+        monitors.set_ceiling(10);
+        let at = start + Duration::from_millis(40);
+        monitors.queue(Frame(1, 2), at);
+        assert!(monitors.next_frame(at).is_none());
+
+        // Frames of monitors the pipeline did not start with get a slot
+        monitors.queue(Frame(7, 0), at);
+        assert_eq!(monitors.next_frame(at).unwrap().0, Frame(7, 0));
+    }
+
+    #[test]
+    fn test_secondaries_take_turns() {
+        let now = Instant::now();
+        let mut monitors = MonitorPipelines::new([(1, 30), (2, 30), (3, 30)], 30, None);
+        monitors.set_ceiling(1000);
+
+        let mut order = Vec::new();
+        for n in 0..4 {
+            let at = now + Duration::from_secs(n);
+            monitors.queue(Frame(2, 0), at);
+            monitors.queue(Frame(3, 0), at);
+            order.push(monitors.next_frame(at).unwrap().0 .0);
+        }
+        assert_eq!(order, [2, 3, 2, 3]);
+    }
+}
@@ -11,6 +11,7 @@ use tracing::{debug, info};
 
 use crate::input::{CoordinateTransformer, KeyboardHandler, MouseHandler};
 use crate::portal::RemoteDesktopManager;
+use crate::utils::wakeup_audit;
 
 /// Control event for session management
 #[derive(Debug)]
@@ -37,14 +38,6 @@ pub(super) async fn run_multiplexer_drain_loop(
     _keyboard_handler: Arc<Mutex<KeyboardHandler>>,
     _mouse_handler: Arc<Mutex<MouseHandler>>,
     _coord_transformer: Arc<Mutex<CoordinateTransformer>>,
-    _session: Arc<
-        RwLock<
-            ashpd::desktop::Session<
-                'static,
-                ashpd::desktop::remote_desktop::RemoteDesktop<'static>,
-            >,
-        >,
-    >,
     _primary_stream_id: u32,
 ) {
     info!("🚀 Multiplexer drain loop started - control + clipboard priority handling");
@@ -52,30 +45,45 @@ pub(super) async fn run_multiplexer_drain_loop(
     let mut stats_control = 0u64;
     let mut stats_clipboard = 0u64;
 
-    loop {
-        // Small sleep to prevent busy-loop
-        tokio::time::sleep(tokio::time::Duration::from_micros(100)).await;
-
-        // PRIORITY 1: Control events (session management)
-        if let Ok(control) = control_rx.try_recv() {
-            stats_control += 1;
-            match control {
-                ControlEvent::Quit(reason) => {
-                    info!("🛑 Quit event received: {}", reason);
-                    break;
-                }
-                ControlEvent::SetCredentials(creds) => {
-                    info!("🔑 Credentials updated: {}", creds.username);
+    let mut control_open = true;
+    let mut clipboard_open = true;
+
+    // Sleeps until an event arrives instead of polling both queues
+    while control_open || clipboard_open {
+        tokio::select! {
+            biased;
+
+            // PRIORITY 1: Control events (session management)
+            control = control_rx.recv(), if control_open => {
+                let Some(control) = control else {
+                    control_open = false;
+                    continue;
+                };
+                wakeup_audit::wakeup("multiplexer", false);
+                stats_control += 1;
+                match control {
+                    ControlEvent::Quit(reason) => {
+                        info!("🛑 Quit event received: {}", reason);
+                        break;
+                    }
+                    ControlEvent::SetCredentials(creds) => {
+                        info!("🔑 Credentials updated: {}", creds.username);
+                    }
                 }
             }
-        }
 
-        // PRIORITY 2: Clipboard events
-        if let Ok(clipboard) = clipboard_rx.try_recv() {
-            stats_clipboard += 1;
-            match clipboard {
-                ClipboardEvent::Message(_msg) => {
-                    debug!("📋 Clipboard event processed via multiplexer");
+            // PRIORITY 2: Clipboard events
+            clipboard = clipboard_rx.recv(), if clipboard_open => {
+                let Some(clipboard) = clipboard else {
+                    clipboard_open = false;
+                    continue;
+                };
+                wakeup_audit::wakeup("multiplexer", false);
+                stats_clipboard += 1;
+                match clipboard {
+                    ClipboardEvent::Message(_msg) => {
+                        debug!("📋 Clipboard event processed via multiplexer");
+                    }
                 }
             }
         }
@@ -0,0 +1,260 @@
+//! PipeWire Node Watch
+//!
+//! A compositor may tear down a screencast node and publish a new one while
+//! the capture session itself stays alive (e.g. a GNOME Shell reload). The
+//! pipeline's PipeWire stream then stops delivering frames without an error.
+//!
+//! The watch follows the PipeWire registry on its own thread, reports when a
+//! node of the session goes away, and which video source node replaces it:
+//! a new `Video/Source` node with the same `node.name` (or, when the name of
+//! the removed node was never seen, the only node waiting for a replacement).
+//! The display pipeline then re-creates its stream on the new node, or
+//! treats the capture session as lost when no replacement shows up.
+//!
+//! The registry is read over a connection to the PipeWire daemon socket,
+//! like the Mutter path; where that is not reachable (Flatpak), there is no
+//! watch and pipelines behave as before.
+
+use anyhow::{Context, Result};
+use pipewire as pw;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::thread::JoinHandle;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Change to one of the watched nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum NodeEvent {
+    /// The node of a stream was removed
+    Removed { node_id: u32 },
+    /// A new video source took the place of a removed node
+    Replaced { old_node_id: u32, new_node_id: u32 },
+}
+
+/// Registry bookkeeping, independent of the PipeWire loop
+#[derive(Debug, Default)]
+struct NodeTracker {
+    /// Watched node id → `node.name` (None until its global is seen)
+    watched: HashMap<u32, Option<String>>,
+    /// Removed nodes waiting for a replacement, oldest first
+    lost: Vec<(u32, Option<String>)>,
+}
+
+impl NodeTracker {
+    fn new(node_ids: &[u32]) -> Self {
+        Self {
+            watched: node_ids.iter().map(|&id| (id, None)).collect(),
+            lost: Vec::new(),
+        }
+    }
+
+    /// A node global appeared (including the initial enumeration)
+    fn added(&mut self, id: u32, name: Option<&str>, video_source: bool) -> Option<NodeEvent> {
+        if let Some(known) = self.watched.get_mut(&id) {
+            *known = name.map(str::to_string);
+            return None;
+        }
+        if !video_source || self.lost.is_empty() {
+            return None;
+        }
+
+        let index = match name {
+            Some(name) => self
+                .lost
+                .iter()
+                .position(|(_, lost)| lost.as_deref() == Some(name)),
+            None => None,
+        }
+        .or_else(|| (self.lost.len() == 1 && self.lost[0].1.is_none()).then_some(0))?;
+
+        let (old_node_id, old_name) = self.lost.remove(index);
+        self.watched
+            .insert(id, name.map(str::to_string).or(old_name));
+        Some(NodeEvent::Replaced {
+            old_node_id,
+            new_node_id: id,
+        })
+    }
+
+    /// A global was removed
+    fn removed(&mut self, id: u32) -> Option<NodeEvent> {
+        let name = self.watched.remove(&id)?;
+        self.lost.push((id, name));
+        Some(NodeEvent::Removed { node_id: id })
+    }
+}
+
+/// Registry watcher for the nodes of one pipeline
+pub(super) struct NodeWatch {
+    events: mpsc::UnboundedReceiver<NodeEvent>,
+    quit: pw::channel::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NodeWatch {
+    /// Start watching `node_ids` on the PipeWire daemon
+    pub(super) fn start(node_ids: &[u32]) -> Result<Self> {
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (quit, quit_rx) = pw::channel::channel();
+        let (ready_tx, ready_rx) = std::sync::mpsc::sync_channel(1);
+        let tracker = NodeTracker::new(node_ids);
+
+        let thread = std::thread::Builder::new()
+            .name("pw-node-watch".to_string())
+            .spawn(move || {
+                if let Err(e) = run_watch(tracker, events_tx, quit_rx, &ready_tx) {
+                    // Reaches the caller unless the loop was already running
+                    let _ = ready_tx.try_send(Err(e));
+                }
+            })
+            .context("Failed to spawn node watch thread")?;
+
+        ready_rx
+            .recv()
+            .context("Node watch thread exited during startup")??;
+        info!("Watching PipeWire nodes {:?} for removal", node_ids);
+
+        Ok(Self {
+            events,
+            quit,
+            thread: Some(thread),
+        })
+    }
+
+    /// Next node change, if one is waiting (non-blocking)
+    pub(super) fn try_recv(&mut self) -> Option<NodeEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+impl Drop for NodeWatch {
+    fn drop(&mut self) {
+        let _ = self.quit.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// PipeWire loop of the watch thread
+fn run_watch(
+    tracker: NodeTracker,
+    events: mpsc::UnboundedSender<NodeEvent>,
+    quit: pw::channel::Receiver<()>,
+    ready: &std::sync::mpsc::SyncSender<Result<()>>,
+) -> Result<()> {
+    pw::init();
+    let mainloop = pw::main_loop::MainLoop::new(None).context("Failed to create PipeWire loop")?;
+    let context =
+        pw::context::Context::new(&mainloop).context("Failed to create PipeWire context")?;
+    let core = context
+        .connect(None)
+        .context("Failed to connect to the PipeWire daemon")?;
+    let registry = core
+        .get_registry()
+        .context("Failed to get the PipeWire registry")?;
+
+    let tracker = Rc::new(RefCell::new(tracker));
+    let _listener = registry
+        .add_listener_local()
+        .global({
+            let tracker = Rc::clone(&tracker);
+            let events = events.clone();
+            move |global| {
+                if global.type_ != pw::types::ObjectType::Node {
+                    return;
+                }
+                let props = global.props;
+                let name = props.and_then(|p| p.get(*pw::keys::NODE_NAME));
+                let video_source =
+                    props.and_then(|p| p.get(*pw::keys::MEDIA_CLASS)) == Some("Video/Source");
+                if let Some(event) = tracker.borrow_mut().added(global.id, name, video_source) {
+                    debug!(
+                        "PipeWire node {} ({:?}) appeared: {:?}",
+                        global.id, name, event
+                    );
+                    let _ = events.send(event);
+                }
+            }
+        })
+        .global_remove({
+            let tracker = Rc::clone(&tracker);
+            move |id| {
+                if let Some(event) = tracker.borrow_mut().removed(id) {
+                    warn!("⚠️  PipeWire node {} removed", id);
+                    let _ = events.send(event);
+                }
+            }
+        })
+        .register();
+
+    let _quit = quit.attach(mainloop.loop_(), {
+        let mainloop = mainloop.clone();
+        move |()| mainloop.quit()
+    });
+
+    let _ = ready.send(Ok(()));
+    mainloop.run();
+    debug!("PipeWire node watch stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement_by_name() {
+        let mut tracker = NodeTracker::new(&[40, 41]);
+        assert_eq!(tracker.added(40, Some("screencast-a"), true), None);
+        assert_eq!(tracker.added(41, Some("screencast-b"), true), None);
+
+        assert_eq!(tracker.removed(99), None);
+        assert_eq!(
+            tracker.removed(41),
+            Some(NodeEvent::Removed { node_id: 41 })
+        );
+        assert_eq!(
+            tracker.removed(40),
+            Some(NodeEvent::Removed { node_id: 40 })
+        );
+
+        // Unrelated nodes don't take a stream's place
+        assert_eq!(tracker.added(50, Some("webcam"), true), None);
+        assert_eq!(tracker.added(51, Some("screencast-b"), false), None);
+
+        assert_eq!(
+            tracker.added(52, Some("screencast-b"), true),
+            Some(NodeEvent::Replaced {
+                old_node_id: 41,
+                new_node_id: 52
+            })
+        );
+        // The replacement is watched in turn
+        assert_eq!(
+            tracker.removed(52),
+            Some(NodeEvent::Removed { node_id: 52 })
+        );
+    }
+
+    #[test]
+    fn test_unnamed_node_needs_single_candidate() {
+        // Removed before its global was seen: no name to match
+        let mut tracker = NodeTracker::new(&[40, 41]);
+        tracker.removed(40);
+        tracker.removed(41);
+        assert_eq!(tracker.added(60, Some("screencast"), true), None);
+
+        let mut tracker = NodeTracker::new(&[40]);
+        tracker.removed(40);
+        assert_eq!(
+            tracker.added(60, None, true),
+            Some(NodeEvent::Replaced {
+                old_node_id: 40,
+                new_node_id: 60
+            })
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! On-Screen Text
+//!
+//! Text boxes the server draws into the client's frames: the quality
+//! overlay's badge and messages sent to a session through the admin API.
+//!
+//! Text is drawn with a built-in 5x7 pixel font covering uppercase letters,
+//! digits and common punctuation, so no font files or rendering libraries
+//! are involved. Lowercase letters are drawn in uppercase and characters
+//! without a glyph as `?`.
+
+/// Glyph size in font pixels
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// Screen pixels per font pixel
+const SCALE: u32 = 2;
+
+/// Horizontal advance and line height in screen pixels
+pub const ADVANCE: u32 = (GLYPH_WIDTH + 1) * SCALE;
+const LINE_HEIGHT: u32 = (GLYPH_HEIGHT + 2) * SCALE;
+
+/// Padding inside the box
+const PADDING: u32 = 6;
+
+/// 5x7 glyph rows, most significant of the low five bits leftmost
+pub fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; 7],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        ';' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '"' => [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        '*' => [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00],
+        '@' => [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    }
+}
+
+/// Lines of text on a darkened backdrop
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBox {
+    /// Text, one entry per line
+    pub lines: Vec<String>,
+    /// Text colour (BGRA)
+    pub color: [u8; 4],
+}
+
+impl TextBox {
+    /// Box size in screen pixels
+    pub fn size(&self) -> (u32, u32) {
+        let chars = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count() as u32)
+            .max()
+            .unwrap_or(0);
+        (
+            (chars * ADVANCE).saturating_sub(SCALE) + 2 * PADDING,
+            (self.lines.len() as u32 * LINE_HEIGHT).saturating_sub(2 * SCALE) + 2 * PADDING,
+        )
+    }
+
+    /// Draw with the top-left corner at (`left`, `top`) into a tightly
+    /// packed BGRA frame
+    ///
+    /// Nothing is drawn unless the whole box fits.
+    pub fn draw(&self, data: &mut [u8], width: u32, height: u32, left: u32, top: u32) {
+        let (box_width, box_height) = self.size();
+        if left + box_width > width || top + box_height > height {
+            return;
+        }
+        if data.len() < (width * height * 4) as usize {
+            return;
+        }
+        let pixel = |x: u32, y: u32| ((y * width + x) * 4) as usize;
+
+        // Darkened backdrop keeps the text readable on any content
+        for y in top..top + box_height {
+            for x in left..left + box_width {
+                let i = pixel(x, y);
+                for channel in &mut data[i..i + 3] {
+                    *channel /= 4;
+                }
+            }
+        }
+
+        for (row, line) in self.lines.iter().enumerate() {
+            let line_top = top + PADDING + row as u32 * LINE_HEIGHT;
+            for (column, c) in line.chars().enumerate() {
+                let glyph_left = left + PADDING + column as u32 * ADVANCE;
+                for (gy, bits) in glyph(c).iter().enumerate() {
+                    for gx in 0..GLYPH_WIDTH {
+                        if bits & (0x10 >> gx) == 0 {
+                            continue;
+                        }
+                        for sy in 0..SCALE {
+                            for sx in 0..SCALE {
+                                let i = pixel(
+                                    glyph_left + gx * SCALE + sx,
+                                    line_top + gy as u32 * SCALE + sy,
+                                );
+                                data[i..i + 4].copy_from_slice(&self.color);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyphs() {
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph(' '), [0; 7]);
+        // No glyph: drawn as a question mark
+        assert_eq!(glyph('ü'), glyph('?'));
+        for c in ('A'..='Z').chain('0'..='9') {
+            assert_ne!(glyph(c), glyph('?'), "{}", c);
+        }
+    }
+
+    #[test]
+    fn test_draw_inside_frame_only() {
+        let text = TextBox {
+            lines: vec!["HI".to_string()],
+            color: [255, 255, 255, 255],
+        };
+        let (box_width, box_height) = text.size();
+        let (width, height) = (box_width + 4, box_height + 4);
+        let blank = vec![200u8; (width * height * 4) as usize];
+
+        let mut frame = blank.clone();
+        text.draw(&mut frame, width, height, 4, 4);
+        // Backdrop darkened from the corner, pixels above and left untouched
+        let corner = ((4 * width + 4) * 4) as usize;
+        assert_eq!(frame[corner], 50);
+        assert_eq!(&frame[..4], &blank[..4]);
+
+        // Would cross the right edge
+        let mut frame = blank.clone();
+        text.draw(&mut frame, width, height, 5, 0);
+        assert_eq!(frame, blank);
+    }
+}
@@ -0,0 +1,328 @@
+//! Connection Quality Overlay
+//!
+//! An optional badge drawn into the top-right corner of the client's frames
+//! showing the frame rate, bitrate and round-trip time of the connection, so
+//! users can tell at a glance whether sluggishness comes from the network.
+//!
+//! ```text
+//! ┌──────────────┐
+//! │ 30 FPS       │
+//! │ 2.4 MBPS     │
+//! │ RTT 12 MS    │   text colour: green < 50ms, yellow < 150ms, red beyond
+//! └──────────────┘
+//! ```
+//!
+//! Numbers are refreshed every two seconds from the session's
+//! [`SessionMeter`](super::SessionMeter). The badge is toggled with
+//! Ctrl+Alt+O on the client ([`OverlayHotkey`]) or through the admin API.
+//! Ctrl and Alt still reach the host; only the O key is held back.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use super::osd::TextBox;
+use super::resource_limits::SessionUsage;
+
+/// How often the numbers are refreshed (matches the meter's sample rate)
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Distance of the badge from the frame edges
+const MARGIN: u32 = 8;
+
+/// Text colours (BGRA) by round-trip time
+const COLOR_GOOD: [u8; 4] = [80, 220, 80, 255];
+const COLOR_FAIR: [u8; 4] = [60, 210, 240, 255];
+const COLOR_POOR: [u8; 4] = [70, 70, 240, 255];
+const COLOR_UNKNOWN: [u8; 4] = [220, 220, 220, 255];
+
+/// Scancodes of the toggle hotkey (Ctrl+Alt+O)
+const SCANCODE_CTRL: u8 = 0x1D;
+const SCANCODE_ALT: u8 = 0x38;
+const SCANCODE_TOGGLE: u8 = 0x18;
+
+/// Badge before the first numbers are in
+fn placeholder_badge() -> TextBox {
+    badge(None, None, 0.0)
+}
+
+/// Badge text, coloured by round-trip time
+fn badge(fps: Option<f64>, bitrate_kbps: Option<f64>, rtt_ms: f64) -> TextBox {
+    let fps = fps.map_or_else(|| "--".to_string(), |fps| format!("{:.0}", fps));
+    let bitrate = match bitrate_kbps {
+        None => "-- KBPS".to_string(),
+        Some(kbps) if kbps >= 1000.0 => format!("{:.1} MBPS", kbps / 1000.0),
+        Some(kbps) => format!("{:.0} KBPS", kbps),
+    };
+    let (rtt, color) = if rtt_ms <= 0.0 {
+        ("--".to_string(), COLOR_UNKNOWN)
+    } else if rtt_ms < 50.0 {
+        (format!("{:.0}", rtt_ms), COLOR_GOOD)
+    } else if rtt_ms < 150.0 {
+        (format!("{:.0}", rtt_ms), COLOR_FAIR)
+    } else {
+        (format!("{:.0}", rtt_ms), COLOR_POOR)
+    };
+    TextBox {
+        lines: vec![format!("{} FPS", fps), bitrate, format!("RTT {} MS", rtt)],
+        color,
+    }
+}
+
+#[derive(Debug)]
+struct OverlayState {
+    badge: TextBox,
+    frames: u32,
+    window_start: Instant,
+    /// Bytes sent at the start of the window
+    window_bytes: u64,
+}
+
+impl OverlayState {
+    fn new() -> Self {
+        Self {
+            badge: placeholder_badge(),
+            frames: 0,
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    show_on_connect: bool,
+    visible: AtomicBool,
+    state: Mutex<OverlayState>,
+}
+
+/// Connection quality badge of one pipeline
+///
+/// Shared by the display pipeline (which draws it), the input handler
+/// (hotkey) and the session registry (admin API).
+#[derive(Debug, Clone)]
+pub struct QualityOverlay {
+    inner: Arc<Inner>,
+}
+
+impl QualityOverlay {
+    /// Overlay, initially shown if `show_on_connect`
+    pub fn new(show_on_connect: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                show_on_connect,
+                visible: AtomicBool::new(show_on_connect),
+                state: Mutex::new(OverlayState::new()),
+            }),
+        }
+    }
+
+    /// Restore the initial visibility for a new session on a reused pipeline
+    pub fn reset(&self) {
+        self.inner
+            .visible
+            .store(self.inner.show_on_connect, Ordering::Relaxed);
+        *self.state() = OverlayState::new();
+    }
+
+    /// Whether the badge is drawn
+    pub fn is_visible(&self) -> bool {
+        self.inner.visible.load(Ordering::Relaxed)
+    }
+
+    /// Show or hide the badge
+    ///
+    /// Frames are only counted while shown, so showing it starts a new
+    /// measuring window rather than averaging over the hidden time.
+    pub fn set_visible(&self, visible: bool) {
+        if self.inner.visible.swap(visible, Ordering::Relaxed) != visible {
+            if visible {
+                *self.state() = OverlayState::new();
+            }
+            info!(
+                "📶 Quality overlay {}",
+                if visible { "shown" } else { "hidden" }
+            );
+        }
+    }
+
+    /// Flip the badge's visibility
+    pub fn toggle(&self) {
+        self.set_visible(!self.is_visible());
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, OverlayState> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Count a frame sent to the client and draw the badge into it if shown
+    ///
+    /// `data` is a tightly packed BGRA frame; `usage` is the session's
+    /// current meter reading.
+    pub fn draw(&self, data: &mut [u8], width: u32, height: u32, usage: &SessionUsage) {
+        if !self.is_visible() {
+            return;
+        }
+        let mut state = self.state();
+        state.frames += 1;
+
+        let elapsed = state.window_start.elapsed();
+        if elapsed >= REFRESH_INTERVAL {
+            let secs = elapsed.as_secs_f64();
+            let kbps =
+                usage.bytes_sent.saturating_sub(state.window_bytes) as f64 * 8.0 / 1000.0 / secs;
+            // The first window starts before any bytes were counted
+            let bitrate = (state.window_bytes > 0).then_some(kbps);
+            state.badge = badge(Some(f64::from(state.frames) / secs), bitrate, usage.rtt_ms);
+            state.frames = 0;
+            state.window_start = Instant::now();
+            state.window_bytes = usage.bytes_sent;
+        }
+        let (badge_width, _) = state.badge.size();
+        if let Some(left) = width.checked_sub(badge_width + MARGIN) {
+            state.badge.draw(data, width, height, left, MARGIN);
+        }
+    }
+}
+
+/// Detects the Ctrl+Alt+O overlay hotkey in a client's key events
+#[derive(Debug, Clone, Default)]
+pub struct OverlayHotkey {
+    ctrl: bool,
+    alt: bool,
+    /// Toggle key pressed as part of the hotkey; its release is held back too
+    toggle_down: bool,
+}
+
+impl OverlayHotkey {
+    /// Track a key event; returns `true` if it belongs to the hotkey and must
+    /// not be forwarded to the host
+    ///
+    /// Left and right Ctrl/Alt share their scancodes, so `extended` is not
+    /// needed.
+    pub fn key(&mut self, code: u8, pressed: bool, overlay: &QualityOverlay) -> bool {
+        match code {
+            SCANCODE_CTRL => self.ctrl = pressed,
+            SCANCODE_ALT => self.alt = pressed,
+            SCANCODE_TOGGLE if pressed && self.ctrl && self.alt => {
+                if !self.toggle_down {
+                    overlay.toggle();
+                }
+                self.toggle_down = true;
+                return true;
+            }
+            SCANCODE_TOGGLE if !pressed && self.toggle_down => {
+                self.toggle_down = false;
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(bytes_sent: u64, rtt_ms: f64) -> SessionUsage {
+        SessionUsage {
+            cpu_secs: 0.0,
+            bytes_sent,
+            frame_memory_bytes: 0,
+            throttle_level: 0,
+            rtt_ms,
+            capture_stalls: 0,
+            target_fps: 0,
+            activity: None,
+            ack_latency_ms: 0.0,
+            frames_delivered: 0,
+            frames_encoded: 0,
+            encode_secs: 0.0,
+            input_events: 0,
+        }
+    }
+
+    #[test]
+    fn test_badge_text_and_color() {
+        let badge = badge(Some(29.6), Some(2400.0), 12.0);
+        assert_eq!(badge.lines, ["30 FPS", "2.4 MBPS", "RTT 12 MS"]);
+        assert_eq!(badge.color, COLOR_GOOD);
+
+        let badge = super::badge(Some(5.0), Some(850.0), 200.0);
+        assert_eq!(badge.lines[1], "850 KBPS");
+        assert_eq!(badge.color, COLOR_POOR);
+
+        let placeholder = placeholder_badge();
+        assert_eq!(placeholder.lines, ["-- FPS", "-- KBPS", "RTT -- MS"]);
+    }
+
+    #[test]
+    fn test_draw_only_when_visible() {
+        let (width, height) = (200, 100);
+        let blank = vec![200u8; (width * height * 4) as usize];
+        let overlay = QualityOverlay::new(false);
+
+        let mut frame = blank.clone();
+        overlay.draw(&mut frame, width, height, &usage(0, 0.0));
+        assert_eq!(frame, blank);
+
+        overlay.toggle();
+        overlay.draw(&mut frame, width, height, &usage(0, 0.0));
+        // Top-right corner darkened, top-left untouched
+        let corner = ((MARGIN * width + width - MARGIN - 1) * 4) as usize;
+        assert_eq!(frame[corner], 50);
+        assert_eq!(&frame[..4], &blank[..4]);
+
+        // Too small for the badge: left alone
+        let mut tiny = vec![200u8; 20 * 20 * 4];
+        overlay.draw(&mut tiny, 20, 20, &usage(0, 0.0));
+        assert!(tiny.iter().all(|&b| b == 200));
+
+        overlay.set_visible(false);
+        overlay.reset();
+        assert!(!overlay.is_visible());
+    }
+
+    #[test]
+    fn test_showing_restarts_window() {
+        let (width, height) = (200, 100);
+        let mut frame = vec![0u8; (width * height * 4) as usize];
+        let overlay = QualityOverlay::new(true);
+        overlay.draw(&mut frame, width, height, &usage(1_000_000, 10.0));
+        overlay.set_visible(false);
+
+        // Hidden for longer than a window
+        overlay.state().window_start -= REFRESH_INTERVAL * 5;
+        overlay.set_visible(true);
+        overlay.draw(&mut frame, width, height, &usage(9_000_000, 10.0));
+        let state = overlay.state();
+        assert_eq!(state.frames, 1);
+        assert!(state.window_start.elapsed() < REFRESH_INTERVAL);
+        assert_eq!(state.badge, placeholder_badge());
+    }
+
+    #[test]
+    fn test_hotkey() {
+        let overlay = QualityOverlay::new(false);
+        let mut hotkey = OverlayHotkey::default();
+
+        // O alone is typed normally
+        assert!(!hotkey.key(SCANCODE_TOGGLE, true, &overlay));
+        assert!(!hotkey.key(SCANCODE_TOGGLE, false, &overlay));
+
+        assert!(!hotkey.key(SCANCODE_CTRL, true, &overlay));
+        assert!(!hotkey.key(SCANCODE_ALT, true, &overlay));
+        assert!(hotkey.key(SCANCODE_TOGGLE, true, &overlay));
+        // Auto-repeat does not toggle again
+        assert!(hotkey.key(SCANCODE_TOGGLE, true, &overlay));
+        assert!(overlay.is_visible());
+
+        // Release held back even after the modifiers went up
+        assert!(!hotkey.key(SCANCODE_ALT, false, &overlay));
+        assert!(hotkey.key(SCANCODE_TOGGLE, false, &overlay));
+        assert!(!hotkey.key(SCANCODE_CTRL, false, &overlay));
+    }
+}
@@ -0,0 +1,355 @@
+//! Capability Re-Probing
+//!
+//! Compositor capabilities are probed when the server is created, but the
+//! session can change underneath a long-running server: the portal backend
+//! crashes and D-Bus activates it again, a package upgrade installs another
+//! compositor version, monitors are plugged in or out, or an administrator
+//! edits the quirk database.
+//!
+//! The re-prober probes again whenever one of the portal D-Bus names gets a
+//! new owner, and every `[capture_recovery] reprobe_interval_secs` on the
+//! housekeeping tick. A probe that differs from the previous one is logged
+//! change by change and published with its service registry, like reloaded
+//! settings: pipelines created from then on (new clients, observers)
+//! take their quirks and services from it, running ones keep theirs.
+//!
+//! With wlr-screencopy the captured outputs are fixed when the capture
+//! session is created, so a new monitor would never be shown. There a
+//! changed set of outputs ends the capture like a lost session, and
+//! `[capture_recovery]` re-creates it with the current layout.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::compositor::CompositorCapabilities;
+use crate::services::ServiceRegistry;
+
+use super::housekeeping::{Housekeeping, Ticker};
+
+/// D-Bus names of the portal frontend and its backends
+const PORTAL_NAMES: [&str; 5] = [
+    "org.freedesktop.portal.Desktop",
+    "org.freedesktop.impl.portal.desktop.gnome",
+    "org.freedesktop.impl.portal.desktop.kde",
+    "org.freedesktop.impl.portal.desktop.wlr",
+    "org.freedesktop.impl.portal.desktop.hyprland",
+];
+
+/// Wait after a portal restart before probing it
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// Compositor capabilities with the services derived from them
+#[derive(Clone)]
+pub(super) struct ProbedEnvironment {
+    pub(super) capabilities: Arc<CompositorCapabilities>,
+    pub(super) service_registry: Arc<ServiceRegistry>,
+}
+
+impl ProbedEnvironment {
+    pub(super) fn new(
+        capabilities: Arc<CompositorCapabilities>,
+        service_registry: Arc<ServiceRegistry>,
+    ) -> Self {
+        Self {
+            capabilities,
+            service_registry,
+        }
+    }
+}
+
+/// Probes the session again and publishes the capabilities when they change
+pub(super) struct CapabilityReprober {
+    environment: watch::Sender<ProbedEnvironment>,
+    /// Periodic re-probe (None: only on portal restarts)
+    ticker: Option<Ticker>,
+    /// End the capture when the set of outputs changes
+    restart_on_output_change: bool,
+}
+
+impl CapabilityReprober {
+    /// Re-prober publishing to the returned receiver, starting from `initial`
+    pub(super) fn new(initial: ProbedEnvironment) -> (Self, watch::Receiver<ProbedEnvironment>) {
+        let (environment, receiver) = watch::channel(initial);
+        let reprober = Self {
+            environment,
+            ticker: None,
+            restart_on_output_change: false,
+        };
+        (reprober, receiver)
+    }
+
+    /// Also re-probe every `period` on the shared tick (zero: never)
+    pub(super) fn with_interval(mut self, housekeeping: &Housekeeping, period: Duration) -> Self {
+        self.ticker = (!period.is_zero()).then(|| housekeeping.ticker(period));
+        self
+    }
+
+    /// Ask for the capture to be re-created when outputs come or go
+    pub(super) fn with_restart_on_output_change(mut self, enabled: bool) -> Self {
+        self.restart_on_output_change = enabled;
+        self
+    }
+
+    /// Re-probe on portal restarts and on the timer
+    ///
+    /// Only returns when the capture session has to be re-created, with the
+    /// reason.
+    pub(super) async fn run(mut self) -> String {
+        let mut restarts = match portal_restarts().await {
+            Ok(restarts) => restarts,
+            Err(e) => {
+                warn!("Not watching for portal restarts: {:#}", e);
+                stream::pending().boxed()
+            }
+        };
+        loop {
+            tokio::select! {
+                () = next_tick(&mut self.ticker) => {}
+                Some(name) = restarts.next() => {
+                    info!("🔄 {} restarted, probing compositor capabilities again", name);
+                    tokio::time::sleep(SETTLE_DELAY).await;
+                }
+            }
+            if let Some(reason) = self.reprobe().await {
+                return reason;
+            }
+        }
+    }
+
+    /// Probe once and publish the result if it differs
+    async fn reprobe(&self) -> Option<String> {
+        let previous = Arc::clone(&self.environment.borrow().capabilities);
+        let current = crate::compositor::reprobe_capabilities(&previous).await;
+        let changes = describe_changes(&previous, &current);
+        if changes.is_empty() {
+            debug!("Compositor capabilities unchanged");
+            return None;
+        }
+
+        info!("🔄 Compositor capabilities changed:");
+        for change in &changes {
+            info!("   {}", change);
+        }
+        let outputs_changed = output_names(&previous) != output_names(&current);
+        let service_registry = Arc::new(ServiceRegistry::from_compositor(current.clone()));
+        service_registry.log_summary();
+        self.environment
+            .send_replace(ProbedEnvironment::new(Arc::new(current), service_registry));
+
+        (outputs_changed && self.restart_on_output_change)
+            .then(|| "display outputs changed".to_string())
+    }
+}
+
+async fn next_tick(ticker: &mut Option<Ticker>) {
+    match ticker {
+        Some(ticker) => ticker.tick().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Names of the portal services as they get a new owner
+async fn portal_restarts() -> Result<BoxStream<'static, String>> {
+    let connection = zbus::Connection::session()
+        .await
+        .context("Failed to connect to D-Bus session bus")?;
+    let proxy = zbus::fdo::DBusProxy::new(&connection).await?;
+
+    let mut restarts = Vec::new();
+    for name in PORTAL_NAMES {
+        let owner_changes = proxy
+            .receive_name_owner_changed_with_args(&[(0, name)])
+            .await
+            .with_context(|| format!("Failed to watch {}", name))?;
+        // A name losing its owner is followed by the restart taking it
+        restarts.push(
+            owner_changes
+                .filter_map(|signal| async move {
+                    let args = signal.args().ok()?;
+                    args.new_owner().is_some().then(|| args.name().to_string())
+                })
+                .boxed(),
+        );
+    }
+    Ok(stream::select_all(restarts).boxed())
+}
+
+/// Registry names of the wl_output globals, in order
+fn output_names(capabilities: &CompositorCapabilities) -> Vec<u32> {
+    let mut names: Vec<u32> = capabilities
+        .wayland_globals
+        .iter()
+        .filter(|global| global.interface == "wl_output")
+        .map(|global| global.name)
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// What differs between two probes, one line per change
+fn describe_changes(old: &CompositorCapabilities, new: &CompositorCapabilities) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if old.compositor != new.compositor {
+        changes.push(format!(
+            "compositor: {} → {}",
+            old.compositor, new.compositor
+        ));
+    }
+    if old.portal.backend != new.portal.backend {
+        changes.push(format!(
+            "portal backend: {} → {}",
+            old.portal.backend.as_deref().unwrap_or("none"),
+            new.portal.backend.as_deref().unwrap_or("none")
+        ));
+    }
+    let interfaces = [
+        ("ScreenCast", old.portal.version, new.portal.version),
+        (
+            "RemoteDesktop",
+            old.portal.remote_desktop_version,
+            new.portal.remote_desktop_version,
+        ),
+        (
+            "Clipboard",
+            old.portal.clipboard_version,
+            new.portal.clipboard_version,
+        ),
+    ];
+    for (interface, before, after) in interfaces {
+        if before != after {
+            changes.push(format!(
+                "portal {}: {} → {}",
+                interface,
+                interface_version(before),
+                interface_version(after)
+            ));
+        }
+    }
+
+    for quirk in &new.profile.quirks {
+        if !old.profile.has_quirk(quirk) {
+            changes.push(format!("quirk added: {}", quirk.name()));
+        }
+    }
+    for quirk in &old.profile.quirks {
+        if !new.profile.has_quirk(quirk) {
+            changes.push(format!("quirk removed: {}", quirk.name()));
+        }
+    }
+    let features = [
+        (
+            "damage hints",
+            old.profile.supports_damage_hints,
+            new.profile.supports_damage_hints,
+        ),
+        (
+            "explicit sync",
+            old.profile.supports_explicit_sync,
+            new.profile.supports_explicit_sync,
+        ),
+        (
+            "restore tokens",
+            old.profile.supports_restore_tokens,
+            new.profile.supports_restore_tokens,
+        ),
+        ("EIS", old.profile.supports_eis, new.profile.supports_eis),
+    ];
+    for (feature, before, after) in features {
+        if before != after {
+            let state = |supported| {
+                if supported {
+                    "supported"
+                } else {
+                    "unsupported"
+                }
+            };
+            changes.push(format!("{}: {} → {}", feature, state(before), state(after)));
+        }
+    }
+
+    let (old_outputs, new_outputs) = (output_names(old), output_names(new));
+    if old_outputs != new_outputs {
+        changes.push(format!(
+            "outputs: {} → {} (wl_output globals {:?} → {:?})",
+            old_outputs.len(),
+            new_outputs.len(),
+            old_outputs,
+            new_outputs
+        ));
+    }
+
+    changes
+}
+
+fn interface_version(version: u32) -> String {
+    if version == 0 {
+        "unavailable".to_string()
+    } else {
+        format!("v{}", version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::{CompositorType, PortalCapabilities, Quirk, WaylandGlobal};
+
+    fn caps(version: &str, outputs: &[u32]) -> CompositorCapabilities {
+        let portal = PortalCapabilities {
+            version: 5,
+            remote_desktop_version: 2,
+            backend: Some("gnome".to_string()),
+            ..Default::default()
+        };
+        let globals = outputs
+            .iter()
+            .map(|&name| WaylandGlobal {
+                interface: "wl_output".to_string(),
+                version: 4,
+                name,
+            })
+            .collect();
+        let compositor = CompositorType::Gnome {
+            version: Some(version.to_string()),
+        };
+        CompositorCapabilities::new(compositor, portal, globals)
+    }
+
+    #[test]
+    fn test_unchanged_probe_has_no_changes() {
+        assert!(describe_changes(&caps("46.0", &[40]), &caps("46.0", &[40])).is_empty());
+    }
+
+    #[test]
+    fn test_describe_changes() {
+        let old = caps("46.0", &[40]);
+        let mut new = caps("47.1", &[40, 52]);
+        new.portal.clipboard_version = 1;
+        new.profile.quirks.push(Quirk::Avc444Unreliable);
+
+        let changes = describe_changes(&old, &new);
+        assert!(changes.contains(&"compositor: GNOME 46.0 → GNOME 47.1".to_string()));
+        assert!(changes.contains(&"portal Clipboard: unavailable → v1".to_string()));
+        assert!(changes.contains(&"quirk added: avc444_unreliable".to_string()));
+        assert!(changes
+            .iter()
+            .any(|change| change.starts_with("outputs: 1 → 2")));
+    }
+
+    #[test]
+    fn test_output_names_ignore_other_globals() {
+        let mut capabilities = caps("46.0", &[52, 40]);
+        capabilities.wayland_globals.push(WaylandGlobal {
+            interface: "wl_seat".to_string(),
+            version: 9,
+            name: 7,
+        });
+        assert_eq!(output_names(&capabilities), vec![40, 52]);
+    }
+}
@@ -0,0 +1,649 @@
+//! Per-Session Resource Accounting
+//!
+//! Every client pipeline has a [`SessionMeter`] recording what the session
+//! costs:
+//!
+//! - **CPU time** spent converting and encoding its frames (the bulk of a
+//!   session's CPU use), measured on the pipeline thread
+//! - **Data sent**, read from the kernel's byte counters for the connection
+//!   (`TCP_INFO`), so every channel and the TLS overhead is included
+//! - **Frame memory**, the size of the frame buffers its pipeline works on
+//!
+//! The meter also carries the connection's round-trip time, the state of
+//! the pipeline's adaptive frame rate, and frame, encode and input counters
+//! for display.
+//!
+//! A [`ResourceMonitor`] runs next to each connection, samples the meter
+//! every second into its [`StatsHistory`] and, with `[resource_limits]`
+//! enabled, throttles (halves the frame rate, down to 1/8) or terminates
+//! sessions that stay over a limit for the grace period. Throttled sessions
+//! get their frame rate back step by step once they stay under their
+//! limits. Its `TCP_INFO` samples also feed the pipeline's [`LinkEstimate`].
+
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use super::housekeeping::{Housekeeping, Ticker};
+use super::keepalive::tcp_info;
+use super::link_estimate::{LinkEstimate, TcpSample};
+use super::stats_history::{StatsHistory, StatsSample};
+use crate::config::types::ResourceLimitsConfig;
+use crate::performance::ActivityLevel;
+
+/// How often usage is sampled (one history sample each)
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Deepest throttle level (frame rate divided by 2^level)
+const MAX_THROTTLE_LEVEL: u8 = 3;
+
+/// CPU time consumed by the calling thread
+pub fn thread_cpu_time() -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid timespec to write to; the clock ID is supported
+    // on every Linux version we run on
+    let ret = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    if ret != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// What to do with a session over its limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitAction {
+    /// Lower the session's frame rate
+    #[default]
+    Throttle,
+    /// Disconnect the session
+    Terminate,
+}
+
+impl std::fmt::Display for LimitAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Throttle => write!(f, "throttle"),
+            Self::Terminate => write!(f, "terminate"),
+        }
+    }
+}
+
+impl std::str::FromStr for LimitAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "throttle" => Ok(Self::Throttle),
+            "terminate" => Ok(Self::Terminate),
+            _ => Err(format!("Unknown resource limit action: {}", s)),
+        }
+    }
+}
+
+/// Enforced per-session limits (None = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+    /// CPU in percent of one core
+    pub max_cpu_percent: Option<f64>,
+    /// Data sent (kbit/s)
+    pub max_bandwidth_kbps: Option<f64>,
+    /// Frame memory (bytes)
+    pub max_memory_bytes: Option<u64>,
+    /// Action for CPU and bandwidth violations
+    pub action: LimitAction,
+    /// Time over a limit before acting
+    pub grace: Duration,
+}
+
+impl ResourceLimits {
+    /// Limits from `[resource_limits]`, or None when enforcement is off
+    pub fn from_config(config: &ResourceLimitsConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let limit = |value: u32| (value > 0).then_some(value);
+        Some(Self {
+            max_cpu_percent: limit(config.max_cpu_percent).map(f64::from),
+            max_bandwidth_kbps: limit(config.max_bandwidth_kbps).map(f64::from),
+            max_memory_bytes: limit(config.max_memory_mb).map(|mb| u64::from(mb) << 20),
+            action: config.action.parse().unwrap_or_default(),
+            grace: Duration::from_secs(config.grace_secs),
+        })
+    }
+
+    /// First limit `usage` exceeds, and whether throttling can relieve it
+    fn violation(&self, usage: &ResourceUsage) -> Option<(String, bool)> {
+        if let Some(max) = self.max_memory_bytes {
+            if usage.frame_memory_bytes > max {
+                let reason = format!(
+                    "frame memory {} MB over limit {} MB",
+                    usage.frame_memory_bytes >> 20,
+                    max >> 20
+                );
+                return Some((reason, false));
+            }
+        }
+        if let Some(max) = self.max_cpu_percent {
+            if usage.cpu_percent > max {
+                let reason = format!("CPU {:.0}% over limit {:.0}%", usage.cpu_percent, max);
+                return Some((reason, true));
+            }
+        }
+        if let Some(max) = self.max_bandwidth_kbps {
+            if usage.bandwidth_kbps > max {
+                let reason = format!(
+                    "bandwidth {:.0} kbit/s over limit {:.0} kbit/s",
+                    usage.bandwidth_kbps, max
+                );
+                return Some((reason, true));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct MeterInner {
+    cpu_ns: AtomicU64,
+    bytes_sent: AtomicU64,
+    frame_memory_bytes: AtomicU64,
+    throttle_level: AtomicU8,
+    frames_seen: AtomicU64,
+    rtt_us: AtomicU64,
+    capture_stalls: AtomicU64,
+    target_fps: AtomicU32,
+    /// 0 = adaptive FPS off, else 1 + the activity level
+    activity: AtomicU8,
+    ack_latency_us: AtomicU64,
+    frames_delivered: AtomicU64,
+    frames_encoded: AtomicU64,
+    encode_ns: AtomicU64,
+    input_events: AtomicU64,
+    history: StatsHistory,
+}
+
+/// Resource accounting for one session's pipeline
+///
+/// Cheap to clone; clones share the counters.
+#[derive(Debug, Clone, Default)]
+pub struct SessionMeter {
+    inner: Arc<MeterInner>,
+}
+
+impl SessionMeter {
+    /// Meter with all counters at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Zero the counters for a new session on a reused pipeline
+    pub fn reset(&self) {
+        let inner = &self.inner;
+        inner.cpu_ns.store(0, Ordering::Relaxed);
+        inner.bytes_sent.store(0, Ordering::Relaxed);
+        inner.frame_memory_bytes.store(0, Ordering::Relaxed);
+        inner.throttle_level.store(0, Ordering::Relaxed);
+        inner.frames_seen.store(0, Ordering::Relaxed);
+        inner.rtt_us.store(0, Ordering::Relaxed);
+        inner.capture_stalls.store(0, Ordering::Relaxed);
+        inner.target_fps.store(0, Ordering::Relaxed);
+        inner.activity.store(0, Ordering::Relaxed);
+        inner.ack_latency_us.store(0, Ordering::Relaxed);
+        inner.frames_delivered.store(0, Ordering::Relaxed);
+        inner.frames_encoded.store(0, Ordering::Relaxed);
+        inner.encode_ns.store(0, Ordering::Relaxed);
+        inner.input_events.store(0, Ordering::Relaxed);
+        inner.history.clear();
+    }
+
+    /// Add CPU time spent on the session's frames
+    pub fn record_cpu(&self, cpu: Duration) {
+        self.inner
+            .cpu_ns
+            .fetch_add(cpu.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Set the frame buffer size the pipeline currently works on
+    pub fn record_frame_memory(&self, bytes: usize) {
+        self.inner
+            .frame_memory_bytes
+            .store(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a period in which the capture delivered no frames
+    pub fn record_capture_stall(&self) {
+        self.inner.capture_stalls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the frame rate the pipeline aims for and the screen activity
+    /// behind it (None = fixed frame rate)
+    pub fn record_frame_rate(&self, target_fps: u32, activity: Option<ActivityLevel>) {
+        let activity = match activity {
+            None => 0,
+            Some(ActivityLevel::Static) => 1,
+            Some(ActivityLevel::Low) => 2,
+            Some(ActivityLevel::Medium) => 3,
+            Some(ActivityLevel::High) => 4,
+        };
+        self.inner.target_fps.store(target_fps, Ordering::Relaxed);
+        self.inner.activity.store(activity, Ordering::Relaxed);
+    }
+
+    /// Set the client's smoothed frame acknowledgement latency
+    pub fn record_ack_latency(&self, latency: Duration) {
+        self.inner
+            .ack_latency_us
+            .store(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Count a frame delivered to the client
+    pub fn record_frame_delivered(&self) {
+        self.inner.frames_delivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add the time taken to encode a frame
+    pub fn record_encode(&self, elapsed: Duration) {
+        let inner = &self.inner;
+        inner.frames_encoded.fetch_add(1, Ordering::Relaxed);
+        inner
+            .encode_ns
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Count a keyboard or mouse event forwarded from the client
+    pub fn record_input_event(&self) {
+        self.inner.input_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Per-second samples of the session
+    pub fn history(&self) -> StatsHistory {
+        self.inner.history.clone()
+    }
+
+    fn record_bytes_sent(&self, bytes: u64) {
+        self.inner.bytes_sent.store(bytes, Ordering::Relaxed);
+    }
+
+    fn record_rtt(&self, rtt: Duration) {
+        self.inner
+            .rtt_us
+            .store(rtt.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn set_throttle_level(&self, level: u8) {
+        self.inner.throttle_level.store(level, Ordering::Relaxed);
+    }
+
+    /// Whether the pipeline should drop this frame to honour throttling
+    pub fn skip_frame(&self) -> bool {
+        let level = self.inner.throttle_level.load(Ordering::Relaxed);
+        if level == 0 {
+            return false;
+        }
+        let seen = self.inner.frames_seen.fetch_add(1, Ordering::Relaxed);
+        // Pass one frame in 2^level
+        seen & ((1u64 << level) - 1) != 0
+    }
+
+    /// Current totals
+    pub fn snapshot(&self) -> SessionUsage {
+        let inner = &self.inner;
+        SessionUsage {
+            cpu_secs: Duration::from_nanos(inner.cpu_ns.load(Ordering::Relaxed)).as_secs_f64(),
+            bytes_sent: inner.bytes_sent.load(Ordering::Relaxed),
+            frame_memory_bytes: inner.frame_memory_bytes.load(Ordering::Relaxed),
+            throttle_level: inner.throttle_level.load(Ordering::Relaxed),
+            rtt_ms: inner.rtt_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            capture_stalls: inner.capture_stalls.load(Ordering::Relaxed),
+            target_fps: inner.target_fps.load(Ordering::Relaxed),
+            activity: match inner.activity.load(Ordering::Relaxed) {
+                1 => Some(ActivityLevel::Static),
+                2 => Some(ActivityLevel::Low),
+                3 => Some(ActivityLevel::Medium),
+                4 => Some(ActivityLevel::High),
+                _ => None,
+            },
+            ack_latency_ms: inner.ack_latency_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            frames_delivered: inner.frames_delivered.load(Ordering::Relaxed),
+            frames_encoded: inner.frames_encoded.load(Ordering::Relaxed),
+            encode_secs: Duration::from_nanos(inner.encode_ns.load(Ordering::Relaxed))
+                .as_secs_f64(),
+            input_events: inner.input_events.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Totals of a session's resource use
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    /// CPU time spent on the session's frames
+    pub cpu_secs: f64,
+    /// Bytes delivered to the client
+    pub bytes_sent: u64,
+    /// Frame memory held by the pipeline
+    pub frame_memory_bytes: u64,
+    /// Current throttle level (frame rate divided by 2^level)
+    pub throttle_level: u8,
+    /// Smoothed round-trip time to the client (0 = not measured yet)
+    pub rtt_ms: f64,
+    /// Times the capture watchdog found the capture frozen
+    pub capture_stalls: u64,
+    /// Frame rate the pipeline aims for (0 = no frames yet)
+    pub target_fps: u32,
+    /// Screen activity driving adaptive FPS (None = fixed frame rate)
+    pub activity: Option<ActivityLevel>,
+    /// Smoothed EGFX frame acknowledgement latency (0 = not measured yet)
+    pub ack_latency_ms: f64,
+    /// Frames delivered to the client
+    #[serde(default)]
+    pub frames_delivered: u64,
+    /// Frames encoded to H.264
+    #[serde(default)]
+    pub frames_encoded: u64,
+    /// Wall time spent encoding them
+    #[serde(default)]
+    pub encode_secs: f64,
+    /// Keyboard and mouse events forwarded from the client
+    #[serde(default)]
+    pub input_events: u64,
+}
+
+/// Usage rates over one sample interval
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ResourceUsage {
+    cpu_percent: f64,
+    bandwidth_kbps: f64,
+    frame_memory_bytes: u64,
+}
+
+impl ResourceUsage {
+    fn between(previous: &SessionUsage, current: &SessionUsage, elapsed: Duration) -> Self {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        Self {
+            cpu_percent: (current.cpu_secs - previous.cpu_secs).max(0.0) / secs * 100.0,
+            bandwidth_kbps: current.bytes_sent.saturating_sub(previous.bytes_sent) as f64 * 8.0
+                / 1000.0
+                / secs,
+            frame_memory_bytes: current.frame_memory_bytes,
+        }
+    }
+}
+
+/// Outcome of evaluating one sample
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Verdict {
+    /// Nothing to change
+    Keep,
+    /// Switch to this throttle level
+    Throttle(u8),
+    /// End the session
+    Terminate(String),
+}
+
+/// Applies limits with a grace period and stepwise throttling
+#[derive(Debug)]
+struct Enforcer {
+    limits: ResourceLimits,
+    level: u8,
+    over_since: Option<Instant>,
+    under_since: Option<Instant>,
+}
+
+impl Enforcer {
+    fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            level: 0,
+            over_since: None,
+            under_since: None,
+        }
+    }
+
+    fn evaluate(&mut self, usage: &ResourceUsage, now: Instant) -> Verdict {
+        let grace = self.limits.grace;
+        match self.limits.violation(usage) {
+            Some((reason, throttleable)) => {
+                self.under_since = None;
+                let since = *self.over_since.get_or_insert(now);
+                if now.duration_since(since) < grace {
+                    return Verdict::Keep;
+                }
+                if !throttleable || self.limits.action == LimitAction::Terminate {
+                    return Verdict::Terminate(reason);
+                }
+                if self.level == MAX_THROTTLE_LEVEL {
+                    return Verdict::Keep;
+                }
+                // Give each step a full grace period to take effect
+                self.level += 1;
+                self.over_since = Some(now);
+                warn!("⏬ Throttling session to 1/{}: {}", 1 << self.level, reason);
+                Verdict::Throttle(self.level)
+            }
+            None => {
+                self.over_since = None;
+                if self.level == 0 {
+                    return Verdict::Keep;
+                }
+                let since = *self.under_since.get_or_insert(now);
+                if now.duration_since(since) < grace {
+                    return Verdict::Keep;
+                }
+                self.level -= 1;
+                self.under_since = Some(now);
+                info!("⏫ Easing session throttle to 1/{}", 1 << self.level);
+                Verdict::Throttle(self.level)
+            }
+        }
+    }
+}
+
+/// Samples a session's usage and enforces its limits
+///
+/// Poll [`ResourceMonitor::run`] alongside the future serving the
+/// connection; it must not outlive the stream it was created for.
+#[derive(Debug)]
+pub struct ResourceMonitor {
+    meter: SessionMeter,
+    fd: RawFd,
+    limits: Option<ResourceLimits>,
+    link: Option<LinkEstimate>,
+    tick: Ticker,
+}
+
+impl ResourceMonitor {
+    /// Monitor `stream`, accounting to `meter`
+    pub fn new(meter: SessionMeter, stream: &TcpStream, limits: Option<ResourceLimits>) -> Self {
+        Self {
+            meter,
+            fd: stream.as_raw_fd(),
+            limits,
+            link: None,
+            tick: Ticker::interval(SAMPLE_INTERVAL),
+        }
+    }
+
+    /// Add the connection's `TCP_INFO` samples to `link`
+    pub fn with_link_estimate(mut self, link: LinkEstimate) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    /// Sample on the server's shared tick instead of a timer of its own
+    pub fn with_housekeeping(mut self, housekeeping: &Housekeeping) -> Self {
+        self.tick = housekeeping.ticker(SAMPLE_INTERVAL);
+        self
+    }
+
+    /// Resolve with the reason once the session has to be terminated
+    ///
+    /// Without limits this only keeps the accounting up to date.
+    pub async fn run(mut self) -> String {
+        let mut enforcer = self.limits.map(Enforcer::new);
+        let mut previous = self.meter.snapshot();
+        let mut previous_at = Instant::now();
+
+        loop {
+            self.tick.tick().await;
+            match tcp_info(self.fd) {
+                Ok(info) => {
+                    self.meter.record_bytes_sent(info.tcpi_bytes_acked);
+                    self.meter
+                        .record_rtt(Duration::from_micros(u64::from(info.tcpi_rtt)));
+                    if let Some(ref link) = self.link {
+                        link.record_tcp(TcpSample::from_tcp_info(&info), Instant::now());
+                    }
+                }
+                // Socket already gone: the connection future will notice
+                Err(_) => return std::future::pending().await,
+            }
+
+            let now = Instant::now();
+            let current = self.meter.snapshot();
+            let usage = ResourceUsage::between(&previous, &current, now - previous_at);
+            self.meter.history().push(StatsSample::between(
+                &previous,
+                &current,
+                now - previous_at,
+                SystemTime::now(),
+            ));
+            (previous, previous_at) = (current, now);
+
+            let Some(ref mut enforcer) = enforcer else {
+                continue;
+            };
+            match enforcer.evaluate(&usage, now) {
+                Verdict::Keep => {}
+                Verdict::Throttle(level) => self.meter.set_throttle_level(level),
+                Verdict::Terminate(reason) => return reason,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(action: LimitAction) -> ResourceLimits {
+        ResourceLimits {
+            max_cpu_percent: Some(50.0),
+            max_bandwidth_kbps: None,
+            max_memory_bytes: Some(64 << 20),
+            action,
+            grace: Duration::from_secs(10),
+        }
+    }
+
+    fn usage(cpu_percent: f64) -> ResourceUsage {
+        ResourceUsage {
+            cpu_percent,
+            bandwidth_kbps: 0.0,
+            frame_memory_bytes: 8 << 20,
+        }
+    }
+
+    #[test]
+    fn test_throttle_steps_after_grace_and_recovers() {
+        let mut enforcer = Enforcer::new(limits(LimitAction::Throttle));
+        let start = Instant::now();
+        let mut sample =
+            |cpu, secs| enforcer.evaluate(&usage(cpu), start + Duration::from_secs(secs));
+
+        assert_eq!(sample(90.0, 0), Verdict::Keep);
+        assert_eq!(sample(90.0, 5), Verdict::Keep);
+        assert_eq!(sample(90.0, 10), Verdict::Throttle(1));
+        assert_eq!(sample(90.0, 12), Verdict::Keep);
+        assert_eq!(sample(90.0, 20), Verdict::Throttle(2));
+
+        assert_eq!(sample(10.0, 22), Verdict::Keep);
+        assert_eq!(sample(10.0, 32), Verdict::Throttle(1));
+        assert_eq!(sample(10.0, 42), Verdict::Throttle(0));
+        assert_eq!(sample(10.0, 60), Verdict::Keep);
+    }
+
+    #[test]
+    fn test_terminate_and_memory_limit() {
+        let start = Instant::now();
+        let mut enforcer = Enforcer::new(limits(LimitAction::Terminate));
+        enforcer.evaluate(&usage(90.0), start);
+        assert!(matches!(
+            enforcer.evaluate(&usage(90.0), start + Duration::from_secs(10)),
+            Verdict::Terminate(_)
+        ));
+
+        // Memory is never throttled
+        let mut enforcer = Enforcer::new(limits(LimitAction::Throttle));
+        let big = ResourceUsage {
+            frame_memory_bytes: 128 << 20,
+            ..usage(0.0)
+        };
+        enforcer.evaluate(&big, start);
+        assert!(matches!(
+            enforcer.evaluate(&big, start + Duration::from_secs(10)),
+            Verdict::Terminate(reason) if reason.contains("frame memory")
+        ));
+    }
+
+    #[test]
+    fn test_meter_rates_and_frame_skipping() {
+        let meter = SessionMeter::new();
+        let before = meter.snapshot();
+        meter.record_cpu(Duration::from_millis(500));
+        meter.record_bytes_sent(250_000);
+        meter.record_rtt(Duration::from_micros(12_500));
+        assert_eq!(meter.snapshot().rtt_ms, 12.5);
+        let usage = ResourceUsage::between(&before, &meter.snapshot(), Duration::from_secs(2));
+        assert!((usage.cpu_percent - 25.0).abs() < 0.01);
+        assert!((usage.bandwidth_kbps - 1000.0).abs() < 0.01);
+
+        assert!(!meter.skip_frame());
+        meter.set_throttle_level(2);
+        let sent = (0..8).filter(|_| !meter.skip_frame()).count();
+        assert_eq!(sent, 2);
+
+        meter.record_frame_rate(15, Some(ActivityLevel::Low));
+        meter.record_ack_latency(Duration::from_millis(40));
+        let usage = meter.snapshot();
+        assert_eq!(usage.target_fps, 15);
+        assert_eq!(usage.activity, Some(ActivityLevel::Low));
+        assert_eq!(usage.ack_latency_ms, 40.0);
+
+        meter.record_frame_delivered();
+        meter.record_encode(Duration::from_millis(8));
+        meter.record_input_event();
+        let usage = meter.snapshot();
+        assert_eq!(usage.frames_delivered, 1);
+        assert_eq!(usage.frames_encoded, 1);
+        assert!((usage.encode_secs - 0.008).abs() < 1e-9);
+        assert_eq!(usage.input_events, 1);
+
+        meter.reset();
+        assert_eq!(meter.snapshot().frames_delivered, 0);
+        assert_eq!(meter.snapshot().activity, None);
+        assert_eq!(meter.snapshot().throttle_level, 0);
+        assert_eq!(meter.snapshot().bytes_sent, 0);
+    }
+
+    #[test]
+    fn test_limits_from_config() {
+        let mut config = ResourceLimitsConfig::default();
+        assert!(ResourceLimits::from_config(&config).is_none());
+
+        config.enabled = true;
+        config.max_memory_mb = 256;
+        config.action = "terminate".to_string();
+        let limits = ResourceLimits::from_config(&config).unwrap();
+        assert_eq!(limits.max_cpu_percent, None);
+        assert_eq!(limits.max_memory_bytes, Some(256 << 20));
+        assert_eq!(limits.action, LimitAction::Terminate);
+    }
+}
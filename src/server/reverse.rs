@@ -0,0 +1,272 @@
+//! Reverse Connection Mode
+//!
+//! Instead of listening, the server dials out to a waiting broker and keeps a
+//! persistent control connection open. Useful behind NAT where no port can be
+//! forwarded to the host.
+//!
+//! # Protocol
+//!
+//! Line-based text on the control connection:
+//!
+//! ```text
+//! server → broker   HELLO lamco-rdp-server/<version> <hostname>
+//! broker → server   CONNECT <token>        (a client is waiting)
+//! either → other    PING / PONG            (keepalive)
+//! ```
+//!
+//! For each `CONNECT`, the server opens a new data connection to the broker,
+//! writes `ACCEPT <token>`, and then speaks plain RDP on that connection; the
+//! broker splices it to the waiting client.
+//!
+//! The control connection is re-established with exponential backoff when it
+//! drops. A broker that stays silent for three heartbeat intervals is treated
+//! as gone.
+
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Maximum reconnect backoff
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Maximum accepted control line length
+const MAX_LINE: usize = 512;
+
+/// Message received on the control connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlMessage {
+    /// A client is waiting; open a data connection with this token
+    Connect(String),
+    /// Keepalive request
+    Ping,
+    /// Keepalive response
+    Pong,
+}
+
+impl ControlMessage {
+    /// Parse a control line (without the trailing newline)
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        match (parts.next()?, parts.next(), parts.next()) {
+            ("CONNECT", Some(token), None) if is_valid_token(token) => {
+                Some(Self::Connect(token.to_string()))
+            }
+            ("PING", None, None) => Some(Self::Ping),
+            ("PONG", None, None) => Some(Self::Pong),
+            _ => None,
+        }
+    }
+}
+
+/// Tokens are opaque to the server but must be printable and short
+fn is_valid_token(token: &str) -> bool {
+    !token.is_empty()
+        && token.len() <= 128
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Reverse connection settings
+#[derive(Debug, Clone)]
+pub struct ReverseConnector {
+    target: String,
+    hostname: String,
+    heartbeat: Duration,
+    retry: Duration,
+}
+
+impl ReverseConnector {
+    /// Create a connector for `target` ("host:port")
+    pub fn new(target: String, heartbeat: Duration, retry: Duration) -> Self {
+        let hostname = hostname::get()
+            .ok()
+            .and_then(|h| h.into_string().ok())
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            target,
+            hostname,
+            heartbeat: heartbeat.max(Duration::from_secs(1)),
+            retry: retry.max(Duration::from_secs(1)),
+        }
+    }
+
+    /// Keep the control connection alive and deliver data connections
+    ///
+    /// Runs until `sessions` is closed. Each delivered stream has already
+    /// been claimed with `ACCEPT <token>` and is ready for RDP.
+    pub async fn run(self, sessions: mpsc::Sender<(TcpStream, SocketAddr)>) {
+        let mut backoff = self.retry;
+        loop {
+            let started = tokio::time::Instant::now();
+            match self.run_control(&sessions).await {
+                Ok(()) => {
+                    info!("Reverse connection closed: server shutting down");
+                    return;
+                }
+                Err(e) => {
+                    // A connection that stayed up for a while starts a fresh backoff
+                    if started.elapsed() > MAX_BACKOFF {
+                        backoff = self.retry;
+                    }
+                    warn!(
+                        "🔁 Reverse control connection to {} lost: {:#} (retry in {}s)",
+                        self.target,
+                        e,
+                        backoff.as_secs()
+                    );
+                }
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            if sessions.is_closed() {
+                return;
+            }
+        }
+    }
+
+    /// One control connection lifetime
+    ///
+    /// Returns `Ok(())` only when the session channel is closed.
+    async fn run_control(&self, sessions: &mpsc::Sender<(TcpStream, SocketAddr)>) -> Result<()> {
+        let stream = TcpStream::connect(&self.target)
+            .await
+            .with_context(|| format!("Failed to connect to broker {}", self.target))?;
+        let _ = stream.set_nodelay(true);
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer
+            .write_all(
+                format!(
+                    "HELLO lamco-rdp-server/{} {}\n",
+                    env!("CARGO_PKG_VERSION"),
+                    self.hostname
+                )
+                .as_bytes(),
+            )
+            .await
+            .context("Failed to send HELLO")?;
+        info!(
+            "🔁 Reverse control connection established to {}",
+            self.target
+        );
+
+        let mut heartbeat = tokio::time::interval(self.heartbeat);
+        heartbeat.tick().await;
+        let mut last_seen = tokio::time::Instant::now();
+
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    let line = line
+                        .context("Control connection read failed")?
+                        .ok_or_else(|| anyhow::anyhow!("Broker closed control connection"))?;
+                    last_seen = tokio::time::Instant::now();
+                    if line.len() > MAX_LINE {
+                        anyhow::bail!("Control line too long ({} bytes)", line.len());
+                    }
+
+                    match ControlMessage::parse(line.trim()) {
+                        Some(ControlMessage::Connect(token)) => {
+                            match self.open_data_connection(&token).await {
+                                Ok(conn) => {
+                                    if sessions.send(conn).await.is_err() {
+                                        return Ok(());
+                                    }
+                                }
+                                Err(e) => warn!("Failed to open data connection: {:#}", e),
+                            }
+                        }
+                        Some(ControlMessage::Ping) => {
+                            writer.write_all(b"PONG\n").await.context("Failed to send PONG")?;
+                        }
+                        Some(ControlMessage::Pong) => {}
+                        None => debug!("Ignoring unknown control line: {:?}", line),
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    if last_seen.elapsed() > self.heartbeat * 3 {
+                        anyhow::bail!("Broker unresponsive for {}s", last_seen.elapsed().as_secs());
+                    }
+                    writer.write_all(b"PING\n").await.context("Failed to send PING")?;
+                }
+                _ = sessions.closed() => return Ok(()),
+            }
+        }
+    }
+
+    /// Open and claim a data connection for `token`
+    async fn open_data_connection(&self, token: &str) -> Result<(TcpStream, SocketAddr)> {
+        let mut stream = TcpStream::connect(&self.target)
+            .await
+            .with_context(|| format!("Failed to connect to broker {}", self.target))?;
+        let _ = stream.set_nodelay(true);
+        let peer = stream.peer_addr().context("Data connection has no peer")?;
+        stream
+            .write_all(format!("ACCEPT {}\n", token).as_bytes())
+            .await
+            .context("Failed to send ACCEPT")?;
+        debug!("Reverse data connection opened for token {}", token);
+        Ok((stream, peer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_control_lines() {
+        assert_eq!(
+            ControlMessage::parse("CONNECT abc-123"),
+            Some(ControlMessage::Connect("abc-123".to_string()))
+        );
+        assert_eq!(ControlMessage::parse("PING"), Some(ControlMessage::Ping));
+        assert_eq!(ControlMessage::parse("PONG"), Some(ControlMessage::Pong));
+        assert_eq!(ControlMessage::parse("CONNECT"), None);
+        assert_eq!(ControlMessage::parse("CONNECT a b"), None);
+        assert_eq!(ControlMessage::parse("CONNECT bad/token"), None);
+        assert_eq!(ControlMessage::parse("HELLO"), None);
+    }
+
+    #[tokio::test]
+    async fn test_connect_opens_claimed_data_connection() {
+        let broker = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = broker.local_addr().unwrap();
+        let connector = ReverseConnector::new(
+            addr.to_string(),
+            Duration::from_secs(30),
+            Duration::from_secs(1),
+        );
+        let (tx, mut rx) = mpsc::channel(1);
+        let task = tokio::spawn(connector.run(tx));
+
+        // Control connection: expect HELLO, request a session
+        let (control, _) = broker.accept().await.unwrap();
+        let (read, mut write) = control.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let hello = lines.next_line().await.unwrap().unwrap();
+        assert!(hello.starts_with("HELLO lamco-rdp-server/"));
+        write.write_all(b"CONNECT tok1\n").await.unwrap();
+
+        // Data connection is claimed with the token
+        let (data, _) = broker.accept().await.unwrap();
+        let mut data_lines = BufReader::new(data).lines();
+        assert_eq!(
+            data_lines.next_line().await.unwrap().unwrap(),
+            "ACCEPT tok1"
+        );
+
+        let (_stream, peer) = rx.recv().await.unwrap();
+        assert_eq!(peer, addr);
+
+        drop(rx);
+        task.await.unwrap();
+    }
+}
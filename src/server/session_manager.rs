@@ -0,0 +1,525 @@
+//! Client Session Manager
+//!
+//! Tracks connected RDP clients and decides how additional clients are served
+//! when more than one connects at a time.
+//!
+//! # Modes
+//!
+//! - **Single:** One client at a time (IronRDP's own accept loop)
+//! - **Shared:** Additional clients view the primary capture; each gets its own
+//!   PipeWire consumer, encoder and an isolated clipboard
+//! - **Separate:** Additional clients get their own Portal session (and
+//!   permission grant), encoder, input and clipboard
+//!
+//! Admission is bounded by `server.max_connections`. A [`ClientSlot`] is held
+//! for the lifetime of each connection and releases its place on drop.
+//! Client IDs are unique across all managers in the process, so a client can
+//! be addressed by ID alone (e.g. from the admin API), and continue across
+//! an upgrade handoff (see [`resume_client_ids`]).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::Notify;
+use tracing::info;
+
+use super::quality_overlay::QualityOverlay;
+use super::resource_limits::SessionMeter;
+use super::session_message::SessionMessage;
+use super::sharing::SharingControl;
+
+/// Next client ID, shared by all managers
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// ID the next admitted client will get
+pub fn next_client_id() -> u64 {
+    NEXT_CLIENT_ID.load(Ordering::Relaxed)
+}
+
+/// Continue numbering after the IDs handed out by a previous server process
+pub fn resume_client_ids(next: u64) {
+    NEXT_CLIENT_ID.fetch_max(next, Ordering::Relaxed);
+}
+
+/// How concurrent clients are served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultiClientMode {
+    /// One client at a time
+    #[default]
+    Single,
+    /// Additional clients share the primary capture
+    Shared,
+    /// Additional clients get their own capture session
+    Separate,
+}
+
+impl std::fmt::Display for MultiClientMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Single => write!(f, "single"),
+            Self::Shared => write!(f, "shared"),
+            Self::Separate => write!(f, "separate"),
+        }
+    }
+}
+
+impl std::str::FromStr for MultiClientMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "single" => Ok(Self::Single),
+            "shared" => Ok(Self::Shared),
+            "separate" => Ok(Self::Separate),
+            _ => Err(format!("Unknown multi-client mode: {}", s)),
+        }
+    }
+}
+
+/// Pipeline a client is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientKind {
+    /// The server's primary pipeline
+    Primary,
+    /// Extra pipeline viewing the primary capture
+    Shared,
+    /// Extra pipeline with its own capture session
+    Separate,
+    /// View-only observer of the primary capture (shadowing)
+    Observer,
+}
+
+impl std::fmt::Display for ClientKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Primary => write!(f, "primary"),
+            Self::Shared => write!(f, "shared"),
+            Self::Separate => write!(f, "separate"),
+            Self::Observer => write!(f, "observer"),
+        }
+    }
+}
+
+/// Snapshot of a connected client
+#[derive(Debug, Clone)]
+pub struct ClientSessionInfo {
+    /// Server-assigned client ID
+    pub id: u64,
+    /// Remote address
+    pub peer: SocketAddr,
+    /// Pipeline kind
+    pub kind: ClientKind,
+    /// Connection time
+    pub connected_at: Instant,
+    /// Resource accounting (set once the client has a pipeline)
+    pub meter: Option<SessionMeter>,
+    /// Connection quality overlay (set once the client has a pipeline)
+    pub overlay: Option<QualityOverlay>,
+    /// Activity and pause state (set once the client has a pipeline)
+    pub sharing: Option<SharingControl>,
+    /// On-screen message (set once the client has a pipeline)
+    pub message: Option<SessionMessage>,
+}
+
+/// Why a client was refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AdmissionError {
+    /// `max_connections` reached
+    #[error("connection limit reached ({0} clients)")]
+    LimitReached(usize),
+    /// Primary pipeline busy and multi-client is disabled
+    #[error("a client is already connected (multi_client = \"single\")")]
+    SingleClientBusy,
+}
+
+#[derive(Debug)]
+struct ClientEntry {
+    info: ClientSessionInfo,
+    disconnect: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    mode: MultiClientMode,
+    max_clients: usize,
+    clients: Mutex<HashMap<u64, ClientEntry>>,
+    admitted_total: AtomicU64,
+    rejected_total: AtomicU64,
+    /// Woken when the last client is released
+    idle: Notify,
+}
+
+/// Registry of connected clients with admission control
+#[derive(Debug, Clone)]
+pub struct SessionManager {
+    inner: Arc<Inner>,
+}
+
+impl SessionManager {
+    /// Create a manager for the given mode and connection limit
+    ///
+    /// A limit of 0 is treated as 1.
+    pub fn new(mode: MultiClientMode, max_clients: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                mode,
+                max_clients: max_clients.max(1),
+                clients: Mutex::new(HashMap::new()),
+                admitted_total: AtomicU64::new(0),
+                rejected_total: AtomicU64::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    /// Configured mode
+    pub fn mode(&self) -> MultiClientMode {
+        self.inner.mode
+    }
+
+    /// Maximum concurrent clients
+    pub fn max_clients(&self) -> usize {
+        self.inner.max_clients
+    }
+
+    /// Pipeline kind for a client arriving while the primary is busy
+    pub fn additional_client_kind(&self) -> ClientKind {
+        match self.inner.mode {
+            MultiClientMode::Separate => ClientKind::Separate,
+            _ => ClientKind::Shared,
+        }
+    }
+
+    /// Register a client, enforcing mode and connection limit
+    pub fn admit(&self, peer: SocketAddr, kind: ClientKind) -> Result<ClientSlot, AdmissionError> {
+        // Observers are governed by their own manager and limit
+        if self.inner.mode == MultiClientMode::Single
+            && !matches!(kind, ClientKind::Primary | ClientKind::Observer)
+        {
+            self.inner.rejected_total.fetch_add(1, Ordering::Relaxed);
+            return Err(AdmissionError::SingleClientBusy);
+        }
+
+        let mut clients = self.inner.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.len() >= self.inner.max_clients {
+            self.inner.rejected_total.fetch_add(1, Ordering::Relaxed);
+            return Err(AdmissionError::LimitReached(self.inner.max_clients));
+        }
+
+        let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
+        let disconnect = Arc::new(Notify::new());
+        clients.insert(
+            id,
+            ClientEntry {
+                info: ClientSessionInfo {
+                    id,
+                    peer,
+                    kind,
+                    connected_at: Instant::now(),
+                    meter: None,
+                    overlay: None,
+                    sharing: None,
+                    message: None,
+                },
+                disconnect: Arc::clone(&disconnect),
+            },
+        );
+        self.inner.admitted_total.fetch_add(1, Ordering::Relaxed);
+        info!(
+            "👤 Client {} connected from {} ({} pipeline, {}/{} active)",
+            id,
+            peer,
+            kind,
+            clients.len(),
+            self.inner.max_clients
+        );
+
+        Ok(ClientSlot {
+            id,
+            disconnect,
+            inner: Arc::clone(&self.inner),
+        })
+    }
+
+    /// Ask a connected client's connection to end
+    ///
+    /// Returns `false` if no client with this ID is registered here. The
+    /// slot is released once the connection task has shut down.
+    pub fn disconnect(&self, id: u64) -> bool {
+        let clients = self.inner.clients.lock().unwrap_or_else(|e| e.into_inner());
+        match clients.get(&id) {
+            Some(client) => {
+                info!("👤 Disconnect requested for client {}", id);
+                client.disconnect.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask every connected client's connection to end
+    ///
+    /// Returns the number of clients asked.
+    pub fn disconnect_all(&self) -> usize {
+        let clients = self.inner.clients.lock().unwrap_or_else(|e| e.into_inner());
+        for client in clients.values() {
+            client.disconnect.notify_one();
+        }
+        clients.len()
+    }
+
+    /// Clients admitted since startup
+    pub fn admitted_total(&self) -> u64 {
+        self.inner.admitted_total.load(Ordering::Relaxed)
+    }
+
+    /// Clients refused since startup
+    pub fn rejected_total(&self) -> u64 {
+        self.inner.rejected_total.load(Ordering::Relaxed)
+    }
+
+    /// Number of connected clients
+    pub fn client_count(&self) -> usize {
+        self.inner
+            .clients
+            .lock()
+            .map(|clients| clients.len())
+            .unwrap_or(0)
+    }
+
+    /// Resolve once no client is connected
+    pub async fn wait_idle(&self) {
+        loop {
+            // Registered before checking, so a release in between is not missed
+            let idle = self.inner.idle.notified();
+            if self.client_count() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Snapshot of connected clients, ordered by ID
+    pub fn clients(&self) -> Vec<ClientSessionInfo> {
+        let mut clients: Vec<_> = self
+            .inner
+            .clients
+            .lock()
+            .map(|clients| clients.values().map(|c| c.info.clone()).collect())
+            .unwrap_or_default();
+        clients.sort_by_key(|c| c.id);
+        clients
+    }
+}
+
+/// Registration held for the lifetime of a connection
+#[derive(Debug)]
+pub struct ClientSlot {
+    id: u64,
+    disconnect: Arc<Notify>,
+    inner: Arc<Inner>,
+}
+
+impl ClientSlot {
+    /// Server-assigned client ID
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Resolves when [`SessionManager::disconnect`] is called for this client
+    pub async fn disconnect_requested(&self) {
+        self.disconnect.notified().await;
+    }
+
+    /// Report the resource use of the pipeline serving this client
+    pub fn set_meter(&self, meter: SessionMeter) {
+        if let Ok(mut clients) = self.inner.clients.lock() {
+            if let Some(client) = clients.get_mut(&self.id) {
+                client.info.meter = Some(meter);
+            }
+        }
+    }
+
+    /// Make the quality overlay of this client's pipeline reachable
+    pub fn set_overlay(&self, overlay: QualityOverlay) {
+        if let Ok(mut clients) = self.inner.clients.lock() {
+            if let Some(client) = clients.get_mut(&self.id) {
+                client.info.overlay = Some(overlay);
+            }
+        }
+    }
+
+    /// Make the activity and pause state of this client's pipeline reachable
+    pub fn set_sharing(&self, sharing: SharingControl) {
+        if let Ok(mut clients) = self.inner.clients.lock() {
+            if let Some(client) = clients.get_mut(&self.id) {
+                client.info.sharing = Some(sharing);
+            }
+        }
+    }
+
+    /// Make the on-screen message of this client's pipeline reachable
+    pub fn set_message(&self, message: SessionMessage) {
+        if let Ok(mut clients) = self.inner.clients.lock() {
+            if let Some(client) = clients.get_mut(&self.id) {
+                client.info.message = Some(message);
+            }
+        }
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        if let Ok(mut clients) = self.inner.clients.lock() {
+            if let Some(client) = clients.remove(&self.id) {
+                info!(
+                    "👤 Client {} released after {:.1}s ({} active)",
+                    self.id,
+                    client.info.connected_at.elapsed().as_secs_f64(),
+                    clients.len()
+                );
+            }
+            if clients.is_empty() {
+                self.inner.idle.notify_waiters();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!(
+            "shared".parse::<MultiClientMode>(),
+            Ok(MultiClientMode::Shared)
+        );
+        assert_eq!(
+            "Separate".parse::<MultiClientMode>(),
+            Ok(MultiClientMode::Separate)
+        );
+        assert!("many".parse::<MultiClientMode>().is_err());
+    }
+
+    #[test]
+    fn test_limit_and_release() {
+        let manager = SessionManager::new(MultiClientMode::Shared, 2);
+        let a = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        let _b = manager.admit(peer(2), ClientKind::Shared).unwrap();
+        assert_eq!(
+            manager.admit(peer(3), ClientKind::Shared).unwrap_err(),
+            AdmissionError::LimitReached(2)
+        );
+
+        drop(a);
+        assert_eq!(manager.client_count(), 1);
+        assert!(manager.admit(peer(3), ClientKind::Shared).is_ok());
+    }
+
+    #[test]
+    fn test_single_mode_rejects_additional() {
+        let manager = SessionManager::new(MultiClientMode::Single, 10);
+        let _primary = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        assert_eq!(
+            manager.admit(peer(2), ClientKind::Shared).unwrap_err(),
+            AdmissionError::SingleClientBusy
+        );
+    }
+
+    #[test]
+    fn test_additional_kind_follows_mode() {
+        let shared = SessionManager::new(MultiClientMode::Shared, 4);
+        assert_eq!(shared.additional_client_kind(), ClientKind::Shared);
+        let separate = SessionManager::new(MultiClientMode::Separate, 4);
+        assert_eq!(separate.additional_client_kind(), ClientKind::Separate);
+    }
+
+    #[test]
+    fn test_clients_snapshot_ordered() {
+        let manager = SessionManager::new(MultiClientMode::Separate, 4);
+        let _a = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        let _b = manager.admit(peer(2), ClientKind::Separate).unwrap();
+        let clients = manager.clients();
+        assert_eq!(clients.len(), 2);
+        assert!(clients[0].id < clients[1].id);
+        assert_eq!(clients[1].kind, ClientKind::Separate);
+    }
+
+    #[test]
+    fn test_ids_unique_across_managers() {
+        let primary = SessionManager::new(MultiClientMode::Shared, 4);
+        let observers = SessionManager::new(MultiClientMode::Shared, 4);
+        let a = primary.admit(peer(1), ClientKind::Primary).unwrap();
+        let b = observers.admit(peer(2), ClientKind::Observer).unwrap();
+        assert_ne!(a.id(), b.id());
+        assert!(!primary.disconnect(b.id()));
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_wakes_slot() {
+        let manager = SessionManager::new(MultiClientMode::Single, 1);
+        let slot = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        assert!(manager.admit(peer(2), ClientKind::Primary).is_err());
+
+        // Notify stores the permit, so requesting before awaiting still wakes
+        assert!(manager.disconnect(slot.id()));
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            slot.disconnect_requested(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(manager.admitted_total(), 1);
+        assert_eq!(manager.rejected_total(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle_and_resumed_ids() {
+        let manager = SessionManager::new(MultiClientMode::Shared, 4);
+        manager.wait_idle().await;
+
+        // Other tests admit clients concurrently, so only lower bounds hold
+        let resumed = next_client_id() + 100;
+        resume_client_ids(resumed);
+        let slot = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        assert!(slot.id() >= resumed);
+
+        let waiter = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.wait_idle().await }
+        });
+        drop(slot);
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_all() {
+        let manager = SessionManager::new(MultiClientMode::Shared, 4);
+        assert_eq!(manager.disconnect_all(), 0);
+
+        let first = manager.admit(peer(1), ClientKind::Primary).unwrap();
+        let second = manager.admit(peer(2), ClientKind::Shared).unwrap();
+        assert_eq!(manager.disconnect_all(), 2);
+        // Requests made before anyone waits are not lost
+        for slot in [&first, &second] {
+            tokio::time::timeout(
+                std::time::Duration::from_secs(1),
+                slot.disconnect_requested(),
+            )
+            .await
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Session Messages
+//!
+//! Lets an admin put a short notice on a client's screen, such as a
+//! maintenance warning, through the admin API
+//! (`PUT /v1/sessions/{id}/message`) or `lamco-rdp-server sessions message`.
+//!
+//! The display pipeline draws the message centred at the top of the primary
+//! monitor's frames until it expires or is replaced, with the pixel font of
+//! the quality overlay. Long text is wrapped at word boundaries. A static
+//! desktop sends no new frames, so the pipeline repaints its last frame when
+//! a message appears or goes away.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use super::osd::TextBox;
+
+/// Longest message accepted (characters)
+pub const MAX_MESSAGE_CHARS: usize = 300;
+
+/// Display time when none is given
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+
+/// Longest display time accepted
+pub const MAX_DURATION: Duration = Duration::from_secs(600);
+
+/// Characters per line before wrapping
+const COLUMNS: usize = 48;
+
+/// Distance from the top of the frame
+const MARGIN: u32 = 24;
+
+/// Text colour (BGRA)
+const COLOR: [u8; 4] = [245, 245, 245, 255];
+
+#[derive(Debug, Default)]
+struct MessageState {
+    /// Message drawn and when it expires
+    shown: Option<(TextBox, Instant)>,
+    /// Frames must be repainted to show or remove the message
+    repaint: bool,
+}
+
+/// On-screen message of one pipeline
+///
+/// Shared by the display pipeline (which draws it) and the session registry
+/// (admin API). Cloned handles share the message.
+#[derive(Debug, Clone, Default)]
+pub struct SessionMessage {
+    state: Arc<Mutex<MessageState>>,
+}
+
+impl SessionMessage {
+    /// No message shown
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MessageState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// State with an expired message removed
+    fn current(&self) -> MutexGuard<'_, MessageState> {
+        let mut state = self.state();
+        if state
+            .shown
+            .as_ref()
+            .is_some_and(|(_, until)| *until <= Instant::now())
+        {
+            state.shown = None;
+            state.repaint = true;
+        }
+        state
+    }
+
+    /// Show `text` for `duration`, replacing any message shown
+    pub fn show(&self, text: &str, duration: Duration) {
+        info!("💬 Showing a message for {}s", duration.as_secs());
+        let mut state = self.state();
+        state.shown = Some((
+            TextBox {
+                lines: wrap(text),
+                color: COLOR,
+            },
+            Instant::now() + duration,
+        ));
+        state.repaint = true;
+    }
+
+    /// Remove the message (for a new session on a reused pipeline)
+    pub fn clear(&self) {
+        let mut state = self.state();
+        if state.shown.take().is_some() {
+            state.repaint = true;
+        }
+    }
+
+    /// Whether frames carry a message or still have to lose one
+    pub fn is_active(&self) -> bool {
+        let state = self.current();
+        state.shown.is_some() || state.repaint
+    }
+
+    /// Whether the message appeared, changed or went away since it was
+    /// last drawn; the next call returns false
+    pub fn take_repaint(&self) -> bool {
+        std::mem::take(&mut self.current().repaint)
+    }
+
+    /// Draw the message centred at the top of a tightly packed BGRA frame
+    pub fn draw(&self, data: &mut [u8], width: u32, height: u32) {
+        let mut state = self.current();
+        state.repaint = false;
+        if let Some((text, _)) = &state.shown {
+            let (text_width, _) = text.size();
+            if let Some(space) = width.checked_sub(text_width) {
+                text.draw(data, width, height, space / 2, MARGIN);
+            }
+        }
+    }
+}
+
+/// Split `text` into lines of at most [`COLUMNS`] characters, at spaces
+/// where possible
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            // Words longer than a line are broken
+            while word.len() > COLUMNS {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                lines.push(word.drain(..COLUMNS).collect());
+            }
+            let length = line.chars().count();
+            if length > 0 && length + 1 + word.len() > COLUMNS {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Server restarts at 18:00"),
+            ["Server restarts at 18:00"]
+        );
+        assert_eq!(wrap("one\n\ntwo"), ["one", "", "two"]);
+
+        let long = "word ".repeat(20);
+        let lines = wrap(&long);
+        assert!(lines.iter().all(|line| line.chars().count() <= COLUMNS));
+        assert_eq!(lines.join(" "), long.trim());
+
+        let unbroken = "x".repeat(COLUMNS + 5);
+        assert_eq!(wrap(&format!("a {}", unbroken)).len(), 3);
+    }
+
+    #[test]
+    fn test_show_and_expire() {
+        let (width, height) = (800, 200);
+        let blank = vec![200u8; (width * height * 4) as usize];
+        let message = SessionMessage::new();
+        assert!(!message.is_active());
+        assert!(!message.take_repaint());
+
+        message.show("Maintenance in 5 minutes", Duration::from_millis(50));
+        assert!(message.is_active());
+        let mut frame = blank.clone();
+        message.draw(&mut frame, width, height);
+        assert_ne!(frame, blank);
+        // Drawn: nothing to repaint
+        assert!(!message.take_repaint());
+
+        std::thread::sleep(Duration::from_millis(80));
+        // Expired: one repaint to remove it
+        assert!(message.is_active());
+        assert!(message.take_repaint());
+        assert!(!message.is_active());
+        let mut frame = blank.clone();
+        message.draw(&mut frame, width, height);
+        assert_eq!(frame, blank);
+    }
+
+    #[test]
+    fn test_clear() {
+        let message = SessionMessage::new();
+        message.clear();
+        assert!(!message.take_repaint());
+
+        message.show("hello", DEFAULT_DURATION);
+        assert!(message.take_repaint());
+        message.clear();
+        assert!(message.take_repaint());
+        assert!(!message.is_active());
+    }
+}
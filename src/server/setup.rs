@@ -0,0 +1,407 @@
+//! Permission Pre-flight
+//!
+//! `lamco-rdp-server setup` walks through the permission dialogs once, from
+//! an interactive desktop session, so the service can later start headless:
+//!
+//! 1. Probe the compositor and portal feature matrix
+//! 2. Create a session through the selected strategy (the dialog appears
+//!    unless a restore token applies; `--reset` discards the stored one)
+//! 3. Check that the session delivers capture streams, input and clipboard
+//!    the way the server would use them, and that a restore token was stored
+//!
+//! The result is a [`HealthReport`] with one check per step, so it prints
+//! and serializes like `lamco-rdp-server check`. Warnings mark things that
+//! work but will need attention at every start (e.g. a dialog for input).
+
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tracing::info;
+
+use super::health::{HealthCheck, HealthReport, HealthStatus};
+use crate::compositor::{CompositorCapabilities, PortalFeature};
+use crate::config::Config;
+use crate::services::ServiceRegistry;
+use crate::session::{
+    InputStatus, PipeWireAccess, SessionHandle, SessionStrategySelector, SessionType, TokenManager,
+};
+
+/// Run the pre-flight and report what is ready for unattended operation
+///
+/// Only fails when the compositor cannot be probed at all; every later
+/// problem, including a declined dialog, becomes a failed check.
+pub async fn run_setup(config: &Config, reset: bool) -> Result<HealthReport> {
+    let capabilities = crate::compositor::probe_capabilities()
+        .await
+        .context("Failed to probe compositor capabilities")?;
+    capabilities.portal.log_feature_matrix();
+
+    let mut checks = vec![check_portal(&capabilities)];
+
+    let deployment = crate::session::detect_deployment_context();
+    let (storage_method, encryption, accessible) =
+        crate::session::detect_credential_storage(&deployment).await;
+    checks.push(HealthCheck::new(
+        "credentials",
+        if accessible {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Warn
+        },
+        format!(
+            "{} ({}){}",
+            storage_method,
+            encryption,
+            if accessible { "" } else { ", locked" }
+        ),
+    ));
+
+    let token_manager = Arc::new(
+        TokenManager::new(storage_method)
+            .await
+            .context("Failed to create TokenManager")?,
+    );
+    if reset {
+        info!("Discarding stored restore token");
+        token_manager.delete_token("default").await?;
+    }
+
+    let service_registry = Arc::new(ServiceRegistry::from_compositor(capabilities.clone()));
+    let strategy = match SessionStrategySelector::new(service_registry, Arc::clone(&token_manager))
+        .with_mutter_config(config.mutter.clone())
+        .with_kwin_config(config.kwin.clone())
+        .with_wlr_config(config.wlr.clone())
+        .select_strategy()
+        .await
+    {
+        Ok(strategy) => strategy,
+        Err(e) => {
+            checks.push(HealthCheck::new(
+                "capture",
+                HealthStatus::Fail,
+                format!("No session strategy: {:#}", e),
+            ));
+            return Ok(HealthReport::from_checks(checks));
+        }
+    };
+
+    info!(
+        "Creating session via {} (approve the permission dialog if one appears)",
+        strategy.name()
+    );
+    let session = match strategy.create_session().await {
+        Ok(session) => session,
+        Err(e) => {
+            checks.push(HealthCheck::new(
+                "capture",
+                HealthStatus::Fail,
+                format!("{}: {:#}", strategy.name(), e),
+            ));
+            return Ok(HealthReport::from_checks(checks));
+        }
+    };
+
+    checks.push(check_capture(strategy.name(), session.as_ref()));
+    checks.push(check_restore_token(&capabilities, session.as_ref(), &token_manager).await);
+    checks.push(check_input(session.as_ref()));
+    checks.push(check_clipboard(&capabilities, session.as_ref()));
+
+    if let Err(e) = strategy.cleanup(session.as_ref()).await {
+        tracing::warn!("Failed to close setup session: {:#}", e);
+    }
+
+    Ok(HealthReport::from_checks(checks))
+}
+
+fn check_portal(capabilities: &CompositorCapabilities) -> HealthCheck {
+    const NAME: &str = "portal";
+
+    let portal = &capabilities.portal;
+    let versions = format!(
+        "ScreenCast v{}, RemoteDesktop v{}, Clipboard v{}",
+        portal.version, portal.remote_desktop_version, portal.clipboard_version
+    );
+    if !portal.supports(PortalFeature::ScreenCast) {
+        HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            format!("{}; no ScreenCast portal", versions),
+        )
+    } else {
+        HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!("{} on {}", versions, capabilities.compositor),
+        )
+    }
+}
+
+/// The session delivers at least one stream with a usable size
+fn check_capture(strategy: &str, session: &dyn SessionHandle) -> HealthCheck {
+    const NAME: &str = "capture";
+
+    let streams = session.streams();
+    if streams.is_empty() {
+        return HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("{} returned no streams", strategy),
+        );
+    }
+    if let Some(stream) = streams.iter().find(|s| s.width == 0 || s.height == 0) {
+        return HealthCheck::new(
+            NAME,
+            HealthStatus::Fail,
+            format!("stream {} has no size", stream.node_id),
+        );
+    }
+
+    let source = match session.pipewire_access() {
+        PipeWireAccess::FileDescriptor(_) => "PipeWire (portal fd)",
+        PipeWireAccess::NodeId(_) => "PipeWire (daemon node)",
+        PipeWireAccess::Screencopy => "wlr-screencopy",
+    };
+    let sizes = streams
+        .iter()
+        .map(|s| format!("{}x{}", s.width, s.height))
+        .collect::<Vec<_>>()
+        .join(", ");
+    HealthCheck::new(
+        NAME,
+        HealthStatus::Ok,
+        format!(
+            "{} via {}: {} stream(s) {}",
+            strategy,
+            source,
+            streams.len(),
+            sizes
+        ),
+    )
+}
+
+/// The next start can skip the dialog
+async fn check_restore_token(
+    capabilities: &CompositorCapabilities,
+    session: &dyn SessionHandle,
+    token_manager: &TokenManager,
+) -> HealthCheck {
+    const NAME: &str = "restore_token";
+
+    match session.session_type() {
+        SessionType::Portal => {
+            if !capabilities.portal.supports(PortalFeature::RestoreTokens) {
+                return HealthCheck::new(
+                    NAME,
+                    HealthStatus::Warn,
+                    format!(
+                        "ScreenCast v{} has no restore tokens; the dialog appears at every start",
+                        capabilities.portal.version
+                    ),
+                );
+            }
+            match token_manager.load_token("default").await {
+                Ok(Some(token)) => HealthCheck::new(
+                    NAME,
+                    HealthStatus::Ok,
+                    format!("stored ({} chars)", token.len()),
+                ),
+                Ok(None) => {
+                    HealthCheck::new(NAME, HealthStatus::Fail, "portal returned no restore token")
+                }
+                Err(e) => HealthCheck::new(
+                    NAME,
+                    HealthStatus::Fail,
+                    format!("token not readable: {:#}", e),
+                ),
+            }
+        }
+        SessionType::Libei => HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            "libei sessions are not persisted; the dialog appears at every start",
+        ),
+        session_type => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            format!("not needed ({} starts without a dialog)", session_type),
+        ),
+    }
+}
+
+/// Input is injected the way the server will do it
+///
+/// Mirrors the server's session assembly: Portal and wlr-direct sessions
+/// inject themselves, every other strategy needs a companion Portal
+/// session, which is not persisted.
+fn check_input(session: &dyn SessionHandle) -> HealthCheck {
+    const NAME: &str = "input";
+
+    if let Some(status) = session.input_status() {
+        match &*status.borrow() {
+            InputStatus::Ready => {}
+            InputStatus::Suspended(reason) => {
+                return HealthCheck::new(NAME, HealthStatus::Warn, format!("suspended: {}", reason))
+            }
+            InputStatus::Lost(reason) => {
+                return HealthCheck::new(NAME, HealthStatus::Fail, format!("lost: {}", reason))
+            }
+        }
+    }
+
+    match (session.session_type(), session.pipewire_access()) {
+        (SessionType::Portal, _) => {
+            HealthCheck::new(NAME, HealthStatus::Ok, "RemoteDesktop portal (same session)")
+        }
+        (_, PipeWireAccess::Screencopy) => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            "wlr virtual keyboard and pointer",
+        ),
+        (session_type, _) => HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            format!(
+                "{} video uses a companion RemoteDesktop session; its dialog appears at every start",
+                session_type
+            ),
+        ),
+    }
+}
+
+fn check_clipboard(
+    capabilities: &CompositorCapabilities,
+    session: &dyn SessionHandle,
+) -> HealthCheck {
+    const NAME: &str = "clipboard";
+
+    if !capabilities.portal.supports(PortalFeature::Clipboard) {
+        return HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            format!(
+                "unavailable (RemoteDesktop v{}, Clipboard v{})",
+                capabilities.portal.remote_desktop_version, capabilities.portal.clipboard_version
+            ),
+        );
+    }
+
+    match session.portal_clipboard() {
+        Some(components) if components.manager.is_some() => {
+            HealthCheck::new(NAME, HealthStatus::Ok, "Clipboard portal enabled")
+        }
+        Some(_) => HealthCheck::new(
+            NAME,
+            HealthStatus::Warn,
+            "session has no clipboard; sync will be unavailable",
+        ),
+        None if matches!(session.pipewire_access(), PipeWireAccess::Screencopy) => {
+            HealthCheck::new(
+                NAME,
+                HealthStatus::Warn,
+                "unavailable without a Portal session",
+            )
+        }
+        None => HealthCheck::new(
+            NAME,
+            HealthStatus::Ok,
+            "provided by the companion RemoteDesktop session",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::strategy::{ClipboardComponents, StreamInfo};
+    use async_trait::async_trait;
+    use tokio::sync::watch;
+
+    /// Video-only session handle, as the Mutter and KWin strategies return
+    struct VideoHandle {
+        streams: Vec<StreamInfo>,
+        status: Option<watch::Receiver<InputStatus>>,
+    }
+
+    #[async_trait]
+    impl SessionHandle for VideoHandle {
+        fn pipewire_access(&self) -> PipeWireAccess {
+            PipeWireAccess::NodeId(42)
+        }
+
+        fn streams(&self) -> Vec<StreamInfo> {
+            self.streams.clone()
+        }
+
+        fn session_type(&self) -> SessionType {
+            SessionType::MutterDirect
+        }
+
+        async fn notify_keyboard_keycode(&self, _keycode: i32, _pressed: bool) -> Result<()> {
+            Ok(())
+        }
+
+        async fn notify_pointer_motion_absolute(&self, _: u32, _: f64, _: f64) -> Result<()> {
+            Ok(())
+        }
+
+        async fn notify_pointer_button(&self, _button: i32, _pressed: bool) -> Result<()> {
+            Ok(())
+        }
+
+        async fn notify_pointer_axis(&self, _dx: f64, _dy: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn input_status(&self) -> Option<watch::Receiver<InputStatus>> {
+            self.status.clone()
+        }
+
+        fn portal_clipboard(&self) -> Option<ClipboardComponents> {
+            None
+        }
+    }
+
+    fn stream(width: u32, height: u32) -> StreamInfo {
+        StreamInfo {
+            node_id: 42,
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_capture_needs_sized_streams() {
+        let mut handle = VideoHandle {
+            streams: vec![],
+            status: None,
+        };
+        assert_eq!(check_capture("mutter", &handle).status, HealthStatus::Fail);
+
+        handle.streams = vec![stream(1920, 1080), stream(0, 0)];
+        assert_eq!(check_capture("mutter", &handle).status, HealthStatus::Fail);
+
+        handle.streams = vec![stream(1920, 1080)];
+        let check = check_capture("mutter", &handle);
+        assert_eq!(check.status, HealthStatus::Ok);
+        assert!(check.detail.contains("1920x1080"));
+    }
+
+    #[test]
+    fn test_input_follows_session_assembly() {
+        // Video-only strategies fall back to a non-persistent companion session
+        let mut handle = VideoHandle {
+            streams: vec![stream(1920, 1080)],
+            status: None,
+        };
+        assert_eq!(check_input(&handle).status, HealthStatus::Warn);
+
+        let (tx, rx) = watch::channel(InputStatus::Lost("EIS disconnected".into()));
+        handle.status = Some(rx);
+        let check = check_input(&handle);
+        assert_eq!(check.status, HealthStatus::Fail);
+        assert!(check.detail.contains("EIS disconnected"));
+
+        tx.send_replace(InputStatus::Ready);
+        assert_eq!(check_input(&handle).status, HealthStatus::Warn);
+    }
+}
@@ -0,0 +1,259 @@
+//! Session Shadowing
+//!
+//! Lets an admin attach to the running session in view-only mode for support
+//! and training. Observers connect on `shadow.listen_addr` and get a pipeline
+//! on the primary capture with input discarded and no clipboard channel.
+//!
+//! # Consent and Indicator
+//!
+//! The local user is asked through a desktop notification with Allow/Deny
+//! actions (`org.freedesktop.Notifications`). No answer within the timeout, a
+//! dismissed notification, or no notification daemon all refuse the observer.
+//! While an observer is attached a resident notification tells the user the
+//! session is being viewed; it is withdrawn when the observer leaves.
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use zbus::zvariant::Value;
+use zbus::Connection;
+
+const APP_NAME: &str = "lamco-rdp-server";
+const ICON: &str = "video-display";
+const ACTION_ALLOW: &str = "allow";
+const ACTION_DENY: &str = "deny";
+
+/// Outcome of a consent request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentDecision {
+    /// The user allowed the observer
+    Allowed,
+    /// The user denied or dismissed the request
+    Denied,
+    /// No answer before the timeout
+    TimedOut,
+}
+
+impl ConsentDecision {
+    /// Map a notification action key to a decision
+    fn from_action(action: &str) -> Self {
+        if action == ACTION_ALLOW {
+            Self::Allowed
+        } else {
+            Self::Denied
+        }
+    }
+}
+
+impl std::fmt::Display for ConsentDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allowed => write!(f, "allowed"),
+            Self::Denied => write!(f, "denied"),
+            Self::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+/// Desktop notification client for consent prompts and the viewing indicator
+///
+/// Service: org.freedesktop.Notifications
+/// Path: /org/freedesktop/Notifications
+#[derive(Debug)]
+pub struct HostNotifier {
+    proxy: zbus::Proxy<'static>,
+}
+
+impl HostNotifier {
+    /// Connect to the notification daemon on the session bus
+    pub async fn new() -> Result<Self> {
+        let connection = Connection::session()
+            .await
+            .context("Failed to connect to session bus")?;
+
+        let proxy = zbus::ProxyBuilder::new(&connection)
+            .interface("org.freedesktop.Notifications")?
+            .path("/org/freedesktop/Notifications")?
+            .destination("org.freedesktop.Notifications")?
+            .build()
+            .await
+            .context("Failed to create Notifications proxy")?;
+
+        Ok(Self { proxy })
+    }
+
+    async fn notify(
+        &self,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        resident: bool,
+    ) -> Result<u32> {
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        hints.insert("urgency", Value::from(2u8)); // critical: stays until acted on
+        if resident {
+            hints.insert("resident", Value::from(true));
+        }
+
+        let response = self
+            .proxy
+            .call_method(
+                "Notify",
+                &(APP_NAME, 0u32, ICON, summary, body, actions, hints, 0i32),
+            )
+            .await
+            .context("Failed to call Notify")?;
+
+        let id: u32 = response
+            .body()
+            .deserialize()
+            .context("Failed to deserialize Notify response")?;
+        Ok(id)
+    }
+
+    /// Withdraw a notification
+    pub async fn close(&self, id: u32) {
+        if let Err(e) = self.proxy.call_method("CloseNotification", &(id,)).await {
+            debug!("Failed to close notification {}: {}", id, e);
+        }
+    }
+
+    /// Ask the local user whether `peer` may view the session
+    pub async fn request_consent(&self, peer: SocketAddr, timeout: Duration) -> ConsentDecision {
+        match self.request_consent_inner(peer, timeout).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!("Consent prompt failed, refusing observer: {:#}", e);
+                ConsentDecision::Denied
+            }
+        }
+    }
+
+    async fn request_consent_inner(
+        &self,
+        peer: SocketAddr,
+        timeout: Duration,
+    ) -> Result<ConsentDecision> {
+        // Subscribe before showing the prompt so no answer is missed
+        let mut actions = self
+            .proxy
+            .receive_signal("ActionInvoked")
+            .await
+            .context("Failed to subscribe to ActionInvoked")?;
+        let mut closed = self
+            .proxy
+            .receive_signal("NotificationClosed")
+            .await
+            .context("Failed to subscribe to NotificationClosed")?;
+
+        let id = self
+            .notify(
+                "Remote viewing request",
+                &format!(
+                    "{} wants to view this session (view-only). Allow?",
+                    peer.ip()
+                ),
+                &[ACTION_ALLOW, "Allow", ACTION_DENY, "Deny"],
+                false,
+            )
+            .await?;
+
+        let wait = async {
+            loop {
+                tokio::select! {
+                    Some(msg) = actions.next() => {
+                        if let Ok((nid, action)) = msg.body().deserialize::<(u32, String)>() {
+                            if nid == id {
+                                return ConsentDecision::from_action(&action);
+                            }
+                        }
+                    }
+                    Some(msg) = closed.next() => {
+                        if let Ok((nid, _reason)) = msg.body().deserialize::<(u32, u32)>() {
+                            if nid == id {
+                                return ConsentDecision::Denied;
+                            }
+                        }
+                    }
+                    else => return ConsentDecision::Denied,
+                }
+            }
+        };
+
+        let decision = tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(ConsentDecision::TimedOut);
+        if decision == ConsentDecision::TimedOut {
+            self.close(id).await;
+        }
+        Ok(decision)
+    }
+
+    /// Show the "session is being viewed" indicator
+    pub async fn show_indicator(&self, peer: SocketAddr) -> Result<u32> {
+        self.notify(
+            "Session is being viewed",
+            &format!("{} is viewing this session (view-only)", peer.ip()),
+            &[],
+            true,
+        )
+        .await
+    }
+}
+
+/// Gate an incoming observer on local consent
+///
+/// Returns the notifier (for the indicator) when the observer may proceed.
+pub async fn admit_observer(
+    peer: SocketAddr,
+    require_consent: bool,
+    consent_timeout: Duration,
+) -> Result<Option<HostNotifier>, ConsentDecision> {
+    let notifier = match HostNotifier::new().await {
+        Ok(notifier) => Some(notifier),
+        Err(e) if require_consent => {
+            warn!("Notifications unavailable, cannot ask for consent: {:#}", e);
+            return Err(ConsentDecision::Denied);
+        }
+        Err(e) => {
+            warn!("Notifications unavailable, no viewing indicator: {:#}", e);
+            None
+        }
+    };
+
+    if let Some(notifier) = notifier.as_ref().filter(|_| require_consent) {
+        info!("👁️ Asking local user to allow observer {}", peer);
+        let decision = notifier.request_consent(peer, consent_timeout).await;
+        if decision != ConsentDecision::Allowed {
+            return Err(decision);
+        }
+        info!("👁️ Observer {} allowed by local user", peer);
+    }
+
+    Ok(notifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_mapping() {
+        assert_eq!(
+            ConsentDecision::from_action("allow"),
+            ConsentDecision::Allowed
+        );
+        assert_eq!(
+            ConsentDecision::from_action("deny"),
+            ConsentDecision::Denied
+        );
+        // Default action (body click) is not consent
+        assert_eq!(
+            ConsentDecision::from_action("default"),
+            ConsentDecision::Denied
+        );
+    }
+}
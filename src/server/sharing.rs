@@ -0,0 +1,181 @@
+//! Sharing Activity and Pause
+//!
+//! Lets the local user see what a connected client is doing with the
+//! desktop, and stop it for a while without disconnecting it:
+//!
+//! - **Activity:** when the client last injected keyboard or mouse input,
+//!   and when clipboard data last moved between it and the host
+//! - **Pause:** while paused, the client's view stays on its last frame,
+//!   its input is discarded, and no new clipboard contents are offered in
+//!   either direction
+//!
+//! Both are reported and controlled per client through the admin API
+//! (`GET /v1/sessions`, `PUT /v1/sessions/{id}/sharing`), which the
+//! configuration GUI uses for its privacy indicators.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::clipboard::ClipboardPolicy;
+
+/// Sharing state of a client as reported by the admin API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharingStatus {
+    /// Video, input and clipboard paused by the local user
+    pub paused: bool,
+    /// Time since the client last injected input (None = never)
+    pub input_idle_ms: Option<u64>,
+    /// Time since clipboard data last moved (None = never)
+    pub clipboard_idle_ms: Option<u64>,
+}
+
+/// Activity and pause state of one client pipeline
+///
+/// Cloned handles share state.
+#[derive(Debug, Clone)]
+pub struct SharingControl {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    paused: AtomicBool,
+    epoch: Instant,
+    /// Milliseconds after `epoch` plus one (0 = never)
+    last_input: AtomicU64,
+    /// Set once the pipeline's clipboard manager exists
+    clipboard: Mutex<Option<ClipboardPolicy>>,
+}
+
+impl Default for SharingControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharingControl {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                paused: AtomicBool::new(false),
+                epoch: Instant::now(),
+                last_input: AtomicU64::new(0),
+                clipboard: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Resume and forget the activity of the previous session on a reused
+    /// pipeline
+    pub fn reset(&self) {
+        self.set_paused(false);
+        self.inner.last_input.store(0, Ordering::Relaxed);
+        if let Some(policy) = self.clipboard() {
+            policy.clear_last_transfer();
+        }
+    }
+
+    /// Track (and pause) the transfers of the pipeline's clipboard
+    pub fn attach_clipboard(&self, policy: ClipboardPolicy) {
+        policy.set_paused(self.is_paused());
+        *self
+            .inner
+            .clipboard
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(policy);
+    }
+
+    /// Whether sharing is paused
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume video, input and clipboard
+    pub fn set_paused(&self, paused: bool) {
+        if self.inner.paused.swap(paused, Ordering::Relaxed) != paused {
+            info!("⏸️ Sharing {}", if paused { "paused" } else { "resumed" });
+        }
+        if let Some(policy) = self.clipboard() {
+            policy.set_paused(paused);
+        }
+    }
+
+    /// Note input forwarded from the client
+    pub fn record_input(&self) {
+        let now = self.inner.epoch.elapsed().as_millis() as u64 + 1;
+        self.inner.last_input.store(now, Ordering::Relaxed);
+    }
+
+    /// Current state
+    pub fn status(&self) -> SharingStatus {
+        let input_idle_ms = match self.inner.last_input.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some((self.inner.epoch.elapsed().as_millis() as u64 + 1).saturating_sub(at)),
+        };
+        let clipboard_idle_ms = self
+            .clipboard()
+            .and_then(|policy| policy.last_transfer())
+            .map(|at| at.elapsed().as_millis() as u64);
+        SharingStatus {
+            paused: self.is_paused(),
+            input_idle_ms,
+            clipboard_idle_ms,
+        }
+    }
+
+    fn clipboard(&self) -> Option<ClipboardPolicy> {
+        self.inner
+            .clipboard
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl SharingStatus {
+    /// Whether the client injected input within `window`
+    pub fn input_active(&self, window: Duration) -> bool {
+        self.input_idle_ms
+            .is_some_and(|idle| idle < window.as_millis() as u64)
+    }
+
+    /// Whether clipboard data moved within `window`
+    pub fn clipboard_active(&self, window: Duration) -> bool {
+        self.clipboard_idle_ms
+            .is_some_and(|idle| idle < window.as_millis() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_and_reset() {
+        let sharing = SharingControl::new();
+        let status = sharing.status();
+        assert_eq!(status.input_idle_ms, None);
+        assert_eq!(status.clipboard_idle_ms, None);
+        assert!(!status.input_active(Duration::from_secs(2)));
+
+        sharing.record_input();
+        assert!(sharing.status().input_active(Duration::from_secs(2)));
+
+        sharing.set_paused(true);
+        assert!(sharing.clone().status().paused);
+
+        sharing.reset();
+        assert_eq!(
+            sharing.status(),
+            SharingStatus {
+                paused: false,
+                input_idle_ms: None,
+                clipboard_idle_ms: None,
+            }
+        );
+    }
+}
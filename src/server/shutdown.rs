@@ -0,0 +1,192 @@
+//! Graceful Shutdown
+//!
+//! On SIGTERM or SIGINT the server winds down in order instead of dropping
+//! every socket at once:
+//!
+//! 1. accept loops stop taking new connections
+//! 2. every connected client is told that the server is ending its session
+//!    (IronRDP sends the MCS disconnect ultimatum, which clients report as a
+//!    server-initiated disconnect)
+//! 3. clients get `server.shutdown_grace_secs` to go; connections still open
+//!    afterwards are closed
+//! 4. clipboard managers are shut down and their FUSE mounts released
+//!
+//! A second signal during this sequence exits immediately.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use super::session_manager::SessionManager;
+
+/// Exit status for a forced exit on a second signal (128 + SIGINT)
+const FORCED_EXIT_STATUS: i32 = 130;
+
+#[derive(Debug)]
+struct Inner {
+    grace: Duration,
+    requested: watch::Sender<bool>,
+    reason: Mutex<Option<String>>,
+}
+
+/// Server-wide shutdown request
+///
+/// Cheap to clone; clones observe the same request.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+impl Shutdown {
+    /// Shutdown state giving clients `grace` to disconnect
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                grace,
+                requested: watch::channel(false).0,
+                reason: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Time clients get to disconnect once told to
+    pub fn grace(&self) -> Duration {
+        self.inner.grace
+    }
+
+    /// Start shutting down; later requests keep the first reason
+    pub fn request(&self, reason: &str) {
+        let mut current = self.inner.reason.lock().unwrap_or_else(|e| e.into_inner());
+        if current.is_none() {
+            info!("🛑 Shutdown requested: {}", reason);
+            *current = Some(reason.to_string());
+            self.inner.requested.send_replace(true);
+        }
+    }
+
+    /// Whether shutdown has been requested
+    pub fn is_requested(&self) -> bool {
+        *self.inner.requested.borrow()
+    }
+
+    /// Why shutdown was requested
+    pub fn reason(&self) -> Option<String> {
+        self.inner
+            .reason
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Resolve once shutdown has been requested
+    pub async fn requested(&self) {
+        let mut requested = self.inner.requested.subscribe();
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+
+    /// Request shutdown on SIGTERM or SIGINT; exit at once on a second signal
+    pub async fn run_on_signals(self) {
+        let (mut sigterm, mut sigint) = match (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) {
+            (Ok(sigterm), Ok(sigint)) => (sigterm, sigint),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Failed to install shutdown signal handlers: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            let name = tokio::select! {
+                _ = sigterm.recv() => "SIGTERM",
+                _ = sigint.recv() => "SIGINT",
+            };
+            if self.is_requested() {
+                warn!("🛑 {} during shutdown: exiting immediately", name);
+                std::process::exit(FORCED_EXIT_STATUS);
+            }
+            self.request(&format!("{} received", name));
+        }
+    }
+
+    /// Tell every client to disconnect and wait for them, up to the grace
+    /// period
+    ///
+    /// Returns the number of clients still connected afterwards.
+    pub async fn drain(&self, managers: &[&SessionManager]) -> usize {
+        let asked: usize = managers
+            .iter()
+            .map(|manager| manager.disconnect_all())
+            .sum();
+        if asked > 0 {
+            info!(
+                "🛑 Ending {} client session(s), allowing {}s to disconnect",
+                asked,
+                self.grace().as_secs()
+            );
+        }
+
+        let all_idle = async {
+            for manager in managers {
+                manager.wait_idle().await;
+            }
+        };
+        if tokio::time::timeout(self.grace(), all_idle).await.is_ok() {
+            return 0;
+        }
+        let remaining = managers.iter().map(|manager| manager.client_count()).sum();
+        warn!(
+            "🛑 {} client(s) still connected after {}s",
+            remaining,
+            self.grace().as_secs()
+        );
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::session_manager::{ClientKind, MultiClientMode};
+
+    #[tokio::test]
+    async fn test_request_keeps_first_reason() {
+        let shutdown = Shutdown::new(Duration::from_secs(1));
+        assert!(!shutdown.is_requested());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.requested().await }
+        });
+        shutdown.request("SIGTERM received");
+        shutdown.request("SIGINT received");
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(shutdown.reason().as_deref(), Some("SIGTERM received"));
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let shutdown = Shutdown::new(Duration::from_millis(200));
+        let manager = SessionManager::new(MultiClientMode::Shared, 4);
+        let peer = "192.0.2.1:50000".parse().unwrap();
+
+        // A client that leaves when asked
+        let slot = manager.admit(peer, ClientKind::Primary).unwrap();
+        let leaving = tokio::spawn(async move {
+            slot.disconnect_requested().await;
+        });
+        assert_eq!(shutdown.drain(&[&manager]).await, 0);
+        leaving.await.unwrap();
+
+        // A client that ignores the request
+        let _stuck = manager.admit(peer, ClientKind::Primary).unwrap();
+        assert_eq!(shutdown.drain(&[&manager]).await, 1);
+    }
+}
@@ -0,0 +1,188 @@
+//! Per-Session Statistics History
+//!
+//! Each pipeline's [`SessionMeter`](super::SessionMeter) keeps the last
+//! [`HISTORY_LEN`] seconds of its session as [`StatsSample`]s: frames
+//! delivered, bitrate, encode time and input events, one sample per second.
+//! Samples are taken by the connection's
+//! [`ResourceMonitor`](super::ResourceMonitor) from consecutive meter
+//! readings, so a session without a pipeline of its own has no history.
+//!
+//! The admin API serves the latest sample and the history
+//! (`GET /v1/sessions/{id}/stats`, `GET /v1/sessions/{id}/stats/history`),
+//! so dashboards can draw charts without a metrics server.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::resource_limits::SessionUsage;
+
+/// Samples kept per session (one per second)
+pub const HISTORY_LEN: usize = 300;
+
+/// A session's activity over one second
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatsSample {
+    /// End of the interval (milliseconds since the Unix epoch)
+    pub at_ms: u64,
+    /// Frames delivered to the client per second
+    pub fps: f64,
+    /// Data sent (kbit/s)
+    pub bitrate_kbps: f64,
+    /// Mean encode time of the frames encoded (0 = none encoded)
+    pub encode_ms: f64,
+    /// Keyboard and mouse events from the client per second
+    pub input_events: f64,
+}
+
+impl StatsSample {
+    /// Rates between two meter readings taken `elapsed` apart, ending at `at`
+    pub fn between(
+        previous: &SessionUsage,
+        current: &SessionUsage,
+        elapsed: Duration,
+        at: SystemTime,
+    ) -> Self {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let per_sec = |current: u64, previous: u64| current.saturating_sub(previous) as f64 / secs;
+        let encoded = current
+            .frames_encoded
+            .saturating_sub(previous.frames_encoded);
+        let encode_secs = (current.encode_secs - previous.encode_secs).max(0.0);
+        Self {
+            at_ms: at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            fps: per_sec(current.frames_delivered, previous.frames_delivered),
+            bitrate_kbps: per_sec(current.bytes_sent, previous.bytes_sent) * 8.0 / 1000.0,
+            encode_ms: if encoded > 0 {
+                encode_secs * 1000.0 / encoded as f64
+            } else {
+                0.0
+            },
+            input_events: per_sec(current.input_events, previous.input_events),
+        }
+    }
+}
+
+/// Ring buffer of a session's last [`HISTORY_LEN`] samples
+///
+/// Cloned handles share the samples.
+#[derive(Debug, Clone, Default)]
+pub struct StatsHistory {
+    samples: Arc<Mutex<VecDeque<StatsSample>>>,
+}
+
+impl StatsHistory {
+    /// Empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn samples(&self) -> std::sync::MutexGuard<'_, VecDeque<StatsSample>> {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Add a sample, dropping the oldest once full
+    pub fn push(&self, sample: StatsSample) {
+        let mut samples = self.samples();
+        if samples.len() == HISTORY_LEN {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Most recent sample
+    pub fn latest(&self) -> Option<StatsSample> {
+        self.samples().back().copied()
+    }
+
+    /// Samples taken after `after_ms` (milliseconds since the Unix epoch),
+    /// oldest first
+    pub fn since(&self, after_ms: u64) -> Vec<StatsSample> {
+        self.samples()
+            .iter()
+            .filter(|sample| sample.at_ms > after_ms)
+            .copied()
+            .collect()
+    }
+
+    /// Forget all samples
+    pub fn clear(&self) {
+        self.samples().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(frames: u64, bytes_sent: u64, encoded: u64, encode_secs: f64) -> SessionUsage {
+        SessionUsage {
+            cpu_secs: 0.0,
+            bytes_sent,
+            frame_memory_bytes: 0,
+            throttle_level: 0,
+            rtt_ms: 0.0,
+            capture_stalls: 0,
+            target_fps: 30,
+            activity: None,
+            ack_latency_ms: 0.0,
+            frames_delivered: frames,
+            frames_encoded: encoded,
+            encode_secs,
+            input_events: frames / 2,
+        }
+    }
+
+    #[test]
+    fn test_sample_rates() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let sample = StatsSample::between(
+            &usage(100, 1_000_000, 90, 0.5),
+            &usage(130, 1_250_000, 120, 0.8),
+            Duration::from_secs(1),
+            at,
+        );
+        assert_eq!(sample.at_ms, 1_700_000_000_000);
+        assert_eq!(sample.fps, 30.0);
+        assert_eq!(sample.bitrate_kbps, 2000.0);
+        assert!((sample.encode_ms - 10.0).abs() < 1e-9);
+        assert_eq!(sample.input_events, 15.0);
+
+        // No frames encoded: no encode time rather than a division by zero
+        let idle = StatsSample::between(
+            &usage(130, 1_250_000, 120, 0.8),
+            &usage(130, 1_250_000, 120, 0.8),
+            Duration::from_secs(1),
+            at,
+        );
+        assert_eq!(idle.encode_ms, 0.0);
+        assert_eq!(idle.fps, 0.0);
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_filtered() {
+        let history = StatsHistory::new();
+        assert!(history.latest().is_none());
+        for at_ms in 0..(HISTORY_LEN as u64 + 10) {
+            history.push(StatsSample {
+                at_ms,
+                fps: 30.0,
+                bitrate_kbps: 0.0,
+                encode_ms: 0.0,
+                input_events: 0.0,
+            });
+        }
+        assert_eq!(history.since(0).len(), HISTORY_LEN);
+        assert_eq!(history.since(0)[0].at_ms, 10);
+        assert_eq!(history.latest().unwrap().at_ms, HISTORY_LEN as u64 + 9);
+        assert_eq!(history.since(HISTORY_LEN as u64 + 7).len(), 2);
+
+        history.clear();
+        assert!(history.since(0).is_empty());
+    }
+}
@@ -12,6 +12,9 @@ use super::{
     translation::translate_capabilities,
 };
 
+/// Upper bound for [`ServiceRegistry::recommended_buffer_count`]
+const MAX_BUFFER_COUNT: u32 = 8;
+
 /// Central service registry
 ///
 /// Holds the translated services from compositor capabilities and
@@ -214,6 +217,21 @@ impl ServiceRegistry {
         self.compositor_caps.profile.recommended_fps_cap
     }
 
+    /// Recommended PipeWire buffer count for a stream at `framerate`
+    ///
+    /// Starts from the DMA-BUF service's pipelining hint (2 for zero-copy,
+    /// 3 when frames are copied) and adds a buffer per 60 fps above 60, so
+    /// the compositor has a free buffer at high refresh rates while we
+    /// still read the previous frame.
+    pub fn recommended_buffer_count(&self, framerate: u32) -> u32 {
+        let base = self
+            .get_service(ServiceId::DmaBufZeroCopy)
+            .filter(|s| s.level >= ServiceLevel::BestEffort)
+            .and_then(|s| s.performance.buffer_count)
+            .unwrap_or(3);
+        (base + framerate.saturating_sub(1) / 60).min(MAX_BUFFER_COUNT)
+    }
+
     /// Check if adaptive FPS should be enabled
     pub fn should_enable_adaptive_fps(&self) -> bool {
         self.service_level(ServiceId::DamageTracking) >= ServiceLevel::BestEffort
@@ -331,6 +349,8 @@ mod tests {
         portal.supports_remote_desktop = true;
         portal.supports_clipboard = true;
         portal.version = 5;
+        portal.remote_desktop_version = 2;
+        portal.clipboard_version = 1;
         portal.available_cursor_modes = vec![CursorMode::Metadata, CursorMode::Embedded];
         portal.available_source_types = vec![SourceType::Monitor, SourceType::Window];
 
@@ -382,6 +402,17 @@ mod tests {
         assert_eq!(total, registry.all_services().len());
     }
 
+    #[test]
+    fn test_recommended_buffer_count() {
+        // GNOME prefers MemFd: copied frames, three buffers up to 60 fps
+        let registry = ServiceRegistry::from_compositor(make_test_caps());
+        assert_eq!(registry.recommended_buffer_count(30), 3);
+        assert_eq!(registry.recommended_buffer_count(60), 3);
+        assert_eq!(registry.recommended_buffer_count(120), 4);
+        assert_eq!(registry.recommended_buffer_count(144), 5);
+        assert_eq!(registry.recommended_buffer_count(1000), MAX_BUFFER_COUNT);
+    }
+
     #[test]
     fn test_services_at_level() {
         let caps = make_test_caps();
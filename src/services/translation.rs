@@ -133,6 +133,10 @@ fn translate_dmabuf(caps: &CompositorCapabilities) -> AdvertisedService {
         return AdvertisedService::unavailable(ServiceId::DmaBufZeroCopy)
             .with_note("Compositor has unreliable DMA-BUF support");
     }
+    if profile.has_quirk(&Quirk::DmaBufNeedsExplicitSync) {
+        return AdvertisedService::unavailable(ServiceId::DmaBufZeroCopy)
+            .with_note("Frames need explicit sync, which capture does not wait on");
+    }
 
     match profile.recommended_buffer_type {
         BufferType::DmaBuf => {
@@ -197,7 +201,20 @@ fn translate_metadata_cursor(caps: &CompositorCapabilities) -> AdvertisedService
     // Check for cursor quirks
     let needs_composite = profile.has_quirk(&Quirk::NeedsExplicitCursorComposite);
 
-    if has_metadata && !needs_composite {
+    if has_metadata && profile.has_quirk(&Quirk::CursorAlwaysInFrame) {
+        // The client must not draw a second cursor over the video's
+        let feature = WaylandFeature::MetadataCursor {
+            has_hotspot: true,
+            has_shape_updates: false,
+        };
+
+        AdvertisedService::degraded(
+            ServiceId::MetadataCursor,
+            feature,
+            "Compositor paints the cursor into frames",
+        )
+        .with_rdp_capability(RdpCapability::cursor_painted())
+    } else if has_metadata && !needs_composite {
         let feature = WaylandFeature::MetadataCursor {
             has_hotspot: true,
             has_shape_updates: true,
@@ -294,7 +311,7 @@ fn translate_clipboard(caps: &CompositorCapabilities) -> AdvertisedService {
 
     if portal.supports_clipboard {
         let feature = WaylandFeature::Clipboard {
-            portal_version: portal.version,
+            portal_version: portal.clipboard_version,
         };
 
         let has_extra_handshake = caps.profile.has_quirk(&Quirk::ClipboardExtraHandshake);
@@ -380,15 +397,18 @@ fn translate_session_persistence(caps: &CompositorCapabilities) -> AdvertisedSer
         CredentialStorageMethod::None => TokenStorageMethod::None,
     };
 
+    // The portal interface can advertise tokens its backend ignores
+    let restore_tokens = portal.supports_restore_tokens && caps.profile.supports_restore_tokens;
+
     let feature = WaylandFeature::SessionPersistence {
-        restore_token_supported: portal.supports_restore_tokens,
+        restore_token_supported: restore_tokens,
         max_persist_mode: portal.max_persist_mode,
         token_storage,
         portal_version: portal.version,
     };
 
     // Determine service level based on token support + storage
-    let level = match (portal.supports_restore_tokens, accessible, token_storage) {
+    let level = match (restore_tokens, accessible, token_storage) {
         // Portal v4+ with working storage
         (true, true, TokenStorageMethod::Tpm2SystemdCreds) => ServiceLevel::Guaranteed,
         (true, true, TokenStorageMethod::SecretService) => ServiceLevel::Guaranteed,
@@ -410,7 +430,7 @@ fn translate_session_persistence(caps: &CompositorCapabilities) -> AdvertisedSer
             AdvertisedService::best_effort(ServiceId::SessionPersistence, feature)
         }
         ServiceLevel::Degraded => {
-            let note = if !portal.supports_restore_tokens {
+            let note = if !restore_tokens {
                 format!(
                     "Portal v{} does not support restore tokens (requires v4+)",
                     portal.version
@@ -423,9 +443,15 @@ fn translate_session_persistence(caps: &CompositorCapabilities) -> AdvertisedSer
             AdvertisedService::degraded(ServiceId::SessionPersistence, feature, &note)
         }
         ServiceLevel::Unavailable => {
-            return AdvertisedService::unavailable(ServiceId::SessionPersistence).with_note(
-                &format!("Portal v{} does not support restore tokens", portal.version),
-            );
+            let note = if portal.supports_restore_tokens {
+                format!(
+                    "{} portal backend does not honor restore tokens",
+                    caps.compositor
+                )
+            } else {
+                format!("Portal v{} does not support restore tokens", portal.version)
+            };
+            return AdvertisedService::unavailable(ServiceId::SessionPersistence).with_note(&note);
         }
     };
 
@@ -622,20 +648,27 @@ fn translate_libei_input(caps: &CompositorCapabilities) -> AdvertisedService {
             .with_note("Portal RemoteDesktop not available");
     }
 
-    // ConnectToEIS was added in Portal v2
-    // Older portals don't have this method
-    let has_connect_to_eis = portal.version >= 2;
+    // ConnectToEIS was added in RemoteDesktop v2
+    let has_connect_to_eis = portal.supports_connect_to_eis();
 
     if !has_connect_to_eis {
         return AdvertisedService::unavailable(ServiceId::LibeiInput).with_note(&format!(
-            "Portal v{} does not support ConnectToEIS (requires v2+)",
-            portal.version
+            "RemoteDesktop v{} does not support ConnectToEIS (requires v2+)",
+            portal.remote_desktop_version
+        ));
+    }
+
+    // Interface v2 comes with the portal frontend, the method with the backend
+    if !caps.profile.supports_eis {
+        return AdvertisedService::unavailable(ServiceId::LibeiInput).with_note(&format!(
+            "{} portal backend does not implement ConnectToEIS",
+            caps.compositor
         ));
     }
 
     // libei supports keyboard, pointer, and potentially touch
     let feature = WaylandFeature::LibeiInput {
-        portal_version: portal.version,
+        portal_version: portal.remote_desktop_version,
         has_connect_to_eis,
         keyboard: true,
         pointer: true,
@@ -644,10 +677,10 @@ fn translate_libei_input(caps: &CompositorCapabilities) -> AdvertisedService {
 
     // libei is Guaranteed when:
     // 1. Portal RemoteDesktop v2+ is available
-    // 2. Portal backend implements ConnectToEIS (can't detect without trying)
+    // 2. Portal backend implements ConnectToEIS (implied by the interface version)
     //
-    // Note: This assumes the portal backend supports ConnectToEIS.
-    // If the backend doesn't support it, session creation will fail gracefully.
+    // A backend that advertises v2 without implementing the method still
+    // fails at session creation, which falls back gracefully.
     AdvertisedService::guaranteed(ServiceId::LibeiInput, feature)
         .with_rdp_capability(RdpCapability::input_full())
         .with_note("EIS protocol via Portal RemoteDesktop (Flatpak-compatible)")
@@ -763,6 +796,8 @@ mod tests {
         portal.supports_remote_desktop = true;
         portal.supports_clipboard = true;
         portal.version = 5;
+        portal.remote_desktop_version = 2;
+        portal.clipboard_version = 1;
         portal.available_cursor_modes = vec![CursorMode::Metadata, CursorMode::Embedded];
         portal.available_source_types = vec![SourceType::Monitor, SourceType::Window];
 
@@ -788,6 +823,55 @@ mod tests {
         let clipboard = services.iter().find(|s| s.id == ServiceId::Clipboard);
         assert!(clipboard.is_some());
         assert!(clipboard.unwrap().level.is_usable());
+
+        // RemoteDesktop v2 offers ConnectToEIS
+        let libei = services.iter().find(|s| s.id == ServiceId::LibeiInput);
+        assert!(libei.unwrap().level.is_usable());
+    }
+
+    #[test]
+    fn test_libei_requires_remote_desktop_v2() {
+        let mut caps = make_gnome_caps();
+        caps.portal.remote_desktop_version = 1;
+
+        let libei = translate_libei_input(&caps);
+        assert_eq!(libei.level, ServiceLevel::Unavailable);
+    }
+
+    #[test]
+    fn test_backend_quirks_limit_portal_services() {
+        let mut caps = make_gnome_caps();
+        caps.profile.supports_eis = false;
+        assert_eq!(
+            translate_libei_input(&caps).level,
+            ServiceLevel::Unavailable
+        );
+
+        caps.profile.supports_restore_tokens = false;
+        assert_eq!(
+            translate_session_persistence(&caps).level,
+            ServiceLevel::Unavailable
+        );
+    }
+
+    #[test]
+    fn test_explicit_sync_only_dmabuf_unavailable() {
+        let mut caps = make_gnome_caps();
+        caps.profile.recommended_buffer_type = BufferType::DmaBuf;
+        assert_eq!(translate_dmabuf(&caps).level, ServiceLevel::Guaranteed);
+
+        caps.profile.quirks.push(Quirk::DmaBufNeedsExplicitSync);
+        assert_eq!(translate_dmabuf(&caps).level, ServiceLevel::Unavailable);
+    }
+
+    #[test]
+    fn test_cursor_in_frames_degrades_metadata_cursor() {
+        let mut caps = make_gnome_caps();
+        caps.profile.quirks.push(Quirk::CursorAlwaysInFrame);
+        assert_eq!(
+            translate_metadata_cursor(&caps).level,
+            ServiceLevel::Degraded
+        );
     }
 
     #[test]
@@ -0,0 +1,272 @@
+//! Input Failover
+//!
+//! Video and input can come from different backends (see the hybrid sessions
+//! in [`crate::session::shared`]), and input is the part that tends to break
+//! while the video keeps flowing: the EIS connection of libei drops its
+//! devices, the companion portal session is revoked, a virtual device stops
+//! accepting events.
+//!
+//! Rather than closing the whole session and asking the user to share the
+//! screen again, the input side moves along `[input] fallback_chain`
+//! (libei → portal → wlr-direct by default). The video session is kept, and
+//! the stream layout it captured is handed to the new input backend so
+//! absolute pointer motion keeps landing on the right monitor. Only when no
+//! backend after the current one can be created is the session closed and
+//! re-created as a lost capture session.
+//!
+//! Each input handle is scored by [`InputHealth`]: failed injections and
+//! suspensions cost points, delivered events earn some back. A handle that
+//! reports [`InputStatus::Lost`] or whose score drops to zero is failed over.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::session::strategy::{InputStatus, SessionHandle, SessionType, StreamInfo};
+
+/// Backends tried in order when `[input] fallback_chain` is not set
+pub const DEFAULT_CHAIN: [&str; 3] = ["libei", "portal", "wlr-direct"];
+
+/// Score of a healthy input handle
+const FULL_HEALTH: u8 = 100;
+
+/// Points lost per failed injection
+const FAILURE_COST: u8 = 20;
+
+/// Points lost each time the handle reports suspended input
+const SUSPEND_COST: u8 = 10;
+
+/// Points earned back per delivered event
+const SUCCESS_CREDIT: u8 = 1;
+
+/// Session type of a `fallback_chain` entry
+pub fn parse_backend(name: &str) -> Option<SessionType> {
+    match name {
+        "libei" => Some(SessionType::Libei),
+        "portal" => Some(SessionType::Portal),
+        "wlr-direct" => Some(SessionType::WlrDirect),
+        _ => None,
+    }
+}
+
+/// Session types of a `fallback_chain`, in order
+pub fn parse_chain(names: &[String]) -> Result<Vec<SessionType>> {
+    let mut links = Vec::with_capacity(names.len());
+    for name in names {
+        let Some(link) = parse_backend(name) else {
+            bail!(
+                "Unknown input backend '{}' (expected libei, portal or wlr-direct)",
+                name
+            );
+        };
+        if links.contains(&link) {
+            bail!("Input backend '{}' is listed twice", name);
+        }
+        links.push(link);
+    }
+    Ok(links)
+}
+
+/// Health score of the current input handle, from 0 (failed) to 100
+#[derive(Debug, Clone)]
+pub struct InputHealth {
+    score: Arc<watch::Sender<u8>>,
+}
+
+impl InputHealth {
+    /// Health of a newly created handle
+    pub fn new() -> Self {
+        Self {
+            score: Arc::new(watch::channel(FULL_HEALTH).0),
+        }
+    }
+
+    /// Current score
+    pub fn score(&self) -> u8 {
+        *self.score.borrow()
+    }
+
+    /// Account for the outcome of one injection
+    pub fn record<T>(&self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.adjust(|score| score.saturating_add(SUCCESS_CREDIT).min(FULL_HEALTH)),
+            Err(_) => self.adjust(|score| score.saturating_sub(FAILURE_COST)),
+        }
+    }
+
+    /// Account for a status reported by the handle
+    pub fn record_status(&self, status: &InputStatus) {
+        match status {
+            InputStatus::Ready => {}
+            InputStatus::Suspended(_) => self.adjust(|score| score.saturating_sub(SUSPEND_COST)),
+            InputStatus::Lost(_) => self.fail(),
+        }
+    }
+
+    /// Mark the handle as failed
+    pub fn fail(&self) {
+        self.adjust(|_| 0);
+    }
+
+    /// Start over with a new handle
+    pub fn reset(&self) {
+        self.adjust(|_| FULL_HEALTH);
+    }
+
+    /// Resolve once the score has dropped to zero
+    pub async fn failed(&self) {
+        let mut score = self.score.subscribe();
+        // The sender lives in `self`, so the channel cannot close
+        let _ = score.wait_for(|score| *score == 0).await;
+    }
+
+    fn adjust(&self, update: impl FnOnce(u8) -> u8) {
+        self.score.send_if_modified(|score| {
+            let updated = update(*score);
+            let changed = updated != *score;
+            *score = updated;
+            changed
+        });
+    }
+}
+
+impl Default for InputHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates input-only handles for the backends of a fallback chain
+#[async_trait]
+pub trait InputProvider: Send + Sync {
+    /// Input handle of `backend` for a session showing `streams`
+    async fn create_input(
+        &self,
+        backend: SessionType,
+        streams: &[StreamInfo],
+    ) -> Result<Arc<dyn SessionHandle>>;
+}
+
+/// Input backends to fail over to, in order of preference
+pub struct FailoverChain {
+    links: Vec<SessionType>,
+    provider: Arc<dyn InputProvider>,
+}
+
+impl FailoverChain {
+    /// Chain over `links`, creating handles with `provider`
+    pub fn new(links: Vec<SessionType>, provider: Arc<dyn InputProvider>) -> Self {
+        Self { links, provider }
+    }
+
+    /// Backends of the chain, in order
+    pub fn links(&self) -> &[SessionType] {
+        &self.links
+    }
+
+    /// Backends to try once `current` has failed
+    ///
+    /// Those after `current` in the chain; all others when `current` is not
+    /// part of it. Never going back keeps a flapping backend from being
+    /// retried over and over.
+    fn candidates(&self, current: SessionType) -> Vec<SessionType> {
+        match self.links.iter().position(|link| *link == current) {
+            Some(position) => self.links[position + 1..].to_vec(),
+            None => self.links.clone(),
+        }
+    }
+
+    /// Input handle of the first backend after `current` that can be created
+    pub async fn fail_over(
+        &self,
+        current: SessionType,
+        streams: &[StreamInfo],
+    ) -> Option<Arc<dyn SessionHandle>> {
+        for backend in self.candidates(current) {
+            info!("🔀 Trying {} for input", backend);
+            match self.provider.create_input(backend, streams).await {
+                Ok(handle) => return Some(handle),
+                Err(e) => warn!("⚠️  {} input unavailable: {:#}", backend, e),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_parse_chain() {
+        let names: Vec<String> = DEFAULT_CHAIN.iter().map(|name| name.to_string()).collect();
+        assert_eq!(
+            parse_chain(&names).unwrap(),
+            vec![
+                SessionType::Libei,
+                SessionType::Portal,
+                SessionType::WlrDirect
+            ]
+        );
+        assert!(parse_chain(&["mutter".to_string()]).is_err());
+        assert!(parse_chain(&["portal".to_string(), "portal".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_health_scoring() {
+        let health = InputHealth::new();
+        health.record(&Err::<(), _>(anyhow!("EIS device gone")));
+        health.record_status(&InputStatus::Suspended("devices paused".into()));
+        assert_eq!(health.score(), 70);
+
+        for _ in 0..50 {
+            health.record(&Ok(()));
+        }
+        assert_eq!(health.score(), 100);
+
+        for _ in 0..5 {
+            health.record(&Err::<(), _>(anyhow!("portal call failed")));
+        }
+        assert_eq!(health.score(), 0);
+
+        health.reset();
+        health.record_status(&InputStatus::Lost("EIS reconnect failed".into()));
+        assert_eq!(health.score(), 0);
+    }
+
+    struct NoInput;
+
+    #[async_trait]
+    impl InputProvider for NoInput {
+        async fn create_input(
+            &self,
+            backend: SessionType,
+            _streams: &[StreamInfo],
+        ) -> Result<Arc<dyn SessionHandle>> {
+            bail!("{} not available", backend)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_candidates_follow_chain() {
+        let chain = FailoverChain::new(
+            vec![
+                SessionType::Libei,
+                SessionType::Portal,
+                SessionType::WlrDirect,
+            ],
+            Arc::new(NoInput),
+        );
+        assert_eq!(
+            chain.candidates(SessionType::Libei),
+            vec![SessionType::Portal, SessionType::WlrDirect]
+        );
+        assert!(chain.candidates(SessionType::WlrDirect).is_empty());
+        assert_eq!(chain.candidates(SessionType::MutterDirect).len(), 3);
+        assert!(chain.fail_over(SessionType::Libei, &[]).await.is_none());
+    }
+}
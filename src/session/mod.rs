@@ -15,8 +15,9 @@
 //! SessionStrategySelector
 //!   ├─> Portal + Token Strategy (universal, portal v4+)
 //!   ├─> Mutter Direct API (GNOME only, no dialog)
+//!   ├─> KWin ScreenCast (KDE Plasma, no dialog for video)
 //!   ├─> libei/EIS (wlroots via Portal, Flatpak-compatible)
-//!   └─> wlr-direct (wlroots native, no Flatpak)
+//!   └─> wlr-direct (wlroots native, screencopy video, no Flatpak)
 //!
 //! TokenManager
 //!   ├─> Flatpak Secret Portal (Flatpak deployment)
@@ -65,8 +66,10 @@
 //! See: docs/architecture/SESSION-PERSISTENCE-ARCHITECTURE.md
 
 pub mod credentials;
+pub mod failover;
 pub mod flatpak_secret;
 pub mod secret_service;
+pub mod shared;
 pub mod strategy;
 pub mod token_manager;
 pub mod tpm_store;
@@ -77,6 +80,10 @@ pub mod strategies {
     pub mod portal_token;
     pub mod selector;
 
+    #[cfg(feature = "wayland")]
+    pub mod hyprland;
+    #[cfg(feature = "wayland")]
+    pub mod kwin_screencast;
     #[cfg(feature = "wayland")]
     pub mod wlr_direct;
 
@@ -88,7 +95,13 @@ pub mod strategies {
     pub use selector::SessionStrategySelector;
 
     #[cfg(feature = "wayland")]
-    pub use wlr_direct::{WlrDirectStrategy, WlrSessionHandleImpl};
+    pub use hyprland::HyprlandStrategy;
+    #[cfg(feature = "wayland")]
+    pub use kwin_screencast::{KwinScreencastStrategy, KwinSessionHandleImpl};
+    #[cfg(feature = "wayland")]
+    pub use wlr_direct::{
+        OutputPlacement, ScreencopyCapture, WlrDirectStrategy, WlrSessionHandleImpl,
+    };
 
     #[cfg(feature = "libei")]
     pub use libei::{LibeiSessionHandleImpl, LibeiStrategy};
@@ -99,9 +112,17 @@ pub use credentials::{
     detect_credential_storage, detect_deployment_context, CredentialStorageMethod,
     DeploymentContext, EncryptionType,
 };
+pub use failover::{FailoverChain, InputHealth, InputProvider};
 pub use flatpak_secret::FlatpakSecretManager;
 pub use secret_service::AsyncSecretServiceClient;
+pub use shared::{
+    ClipboardCapability, InputCapability, RemoteDesktopSession, ScreencastCapability,
+    SessionLiveness, SharedSession,
+};
 pub use strategies::SessionStrategySelector;
-pub use strategy::{PipeWireAccess, SessionConfig, SessionHandle, SessionStrategy, SessionType};
+pub use strategy::{
+    InputStatus, LockKeys, PipeWireAccess, SessionConfig, SessionHandle, SessionStrategy,
+    SessionType,
+};
 pub use token_manager::TokenManager;
 pub use tpm_store::AsyncTpmCredentialStore;
@@ -0,0 +1,678 @@
+//! Shared Session Capabilities
+//!
+//! A single RemoteDesktop session serves three consumers: the PipeWire
+//! screencast, input injection and the clipboard. Rather than passing the
+//! strategy's [`SessionHandle`] and the raw portal session around separately,
+//! [`SharedSession`] owns them and hands out typed capabilities:
+//!
+//! - [`ScreencastCapability`] - PipeWire access and stream layout
+//! - [`InputCapability`] - keyboard and pointer injection
+//! - [`ClipboardCapability`] - portal clipboard manager and session (absent
+//!   for sessions without a portal)
+//!
+//! Capabilities are cheap to clone and keep the session alive. All of them
+//! observe the same [`SessionLiveness`], so once the session is closed (by
+//! the compositor, the user or the server) input injection fails fast
+//! instead of issuing D-Bus calls against a dead session, and owners can
+//! await [`SharedSession::closed`] to tear down or re-create their pipeline.
+//! [`SharedSession::spawn_closed_watch`] closes it when the portal signals
+//! that the session has ended, [`SharedSession::spawn_input_watch`] when the
+//! input handle reports [`InputStatus::Lost`].
+//!
+//! With an input [`FailoverChain`] attached, failing input moves to the next
+//! backend of the chain instead, and only closes the session once the chain
+//! is exhausted (see [`crate::session::failover`]).
+//!
+//! # Hybrid Sessions
+//!
+//! With the Mutter strategy, video comes from Mutter while input and
+//! clipboard go through a separate portal session. Both sessions are owned
+//! by the same [`SharedSession`] and share one liveness state.
+//!
+//! wlr-direct with screencopy provides video and input itself and runs
+//! without any portal session, and so without clipboard.
+
+use anyhow::{bail, Result};
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::session::failover::{FailoverChain, InputHealth};
+use crate::session::strategy::{
+    ClipboardComponents, InputStatus, LockKeys, PipeWireAccess, SessionHandle, SessionType,
+    StreamInfo,
+};
+
+/// Portal RemoteDesktop session shared by input injection and clipboard
+pub type RemoteDesktopSession = Arc<
+    RwLock<
+        ashpd::desktop::Session<'static, ashpd::desktop::remote_desktop::RemoteDesktop<'static>>,
+    >,
+>;
+
+/// Open/closed state of a session, shared by all of its capabilities
+#[derive(Debug, Clone)]
+pub struct SessionLiveness {
+    /// None while open, Some(reason) once closed
+    closed: Arc<watch::Sender<Option<String>>>,
+}
+
+impl SessionLiveness {
+    /// Liveness of a newly opened session
+    pub fn new() -> Self {
+        Self {
+            closed: Arc::new(watch::channel(None).0),
+        }
+    }
+
+    /// Mark the session closed; later calls keep the first reason
+    pub fn close(&self, reason: &str) {
+        self.closed.send_if_modified(|closed| {
+            if closed.is_some() {
+                return false;
+            }
+            info!("Session closed: {}", reason);
+            *closed = Some(reason.to_string());
+            true
+        });
+    }
+
+    /// Whether the session has been closed
+    pub fn is_closed(&self) -> bool {
+        self.closed.borrow().is_some()
+    }
+
+    /// Why the session was closed
+    pub fn close_reason(&self) -> Option<String> {
+        self.closed.borrow().clone()
+    }
+
+    /// Resolve once the session has been closed, with the reason
+    pub async fn closed(&self) -> String {
+        let mut closed = self.closed.subscribe();
+        let reason = match closed.wait_for(Option::is_some).await {
+            Ok(reason) => reason.clone(),
+            // The sender lives in `self`, so the channel cannot close
+            Err(_) => None,
+        };
+        reason.unwrap_or_default()
+    }
+
+    fn ensure_open(&self) -> Result<()> {
+        match self.close_reason() {
+            Some(reason) => bail!("Session closed: {}", reason),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for SessionLiveness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Video capture side of a session
+#[derive(Clone)]
+pub struct ScreencastCapability {
+    handle: Arc<dyn SessionHandle>,
+    liveness: SessionLiveness,
+}
+
+impl ScreencastCapability {
+    /// PipeWire node ID or file descriptor for video capture
+    pub fn pipewire_access(&self) -> PipeWireAccess {
+        self.handle.pipewire_access()
+    }
+
+    /// Captured streams
+    pub fn streams(&self) -> Vec<StreamInfo> {
+        self.handle.streams()
+    }
+
+    /// Strategy that created the capture session
+    pub fn session_type(&self) -> SessionType {
+        self.handle.session_type()
+    }
+
+    /// Whether the session has been closed
+    pub fn is_closed(&self) -> bool {
+        self.liveness.is_closed()
+    }
+}
+
+/// Input injection side of a session
+///
+/// Injection fails immediately once the session is closed. The handle
+/// behind it can be replaced when input fails over to another backend;
+/// every clone sees the replacement.
+#[derive(Clone)]
+pub struct InputCapability {
+    handle: Arc<std::sync::RwLock<Arc<dyn SessionHandle>>>,
+    health: InputHealth,
+    liveness: SessionLiveness,
+}
+
+impl InputCapability {
+    /// Input capability over `handle`, closed together with `liveness`
+    pub fn new(handle: Arc<dyn SessionHandle>, liveness: SessionLiveness) -> Self {
+        Self {
+            handle: Arc::new(std::sync::RwLock::new(handle)),
+            health: InputHealth::new(),
+            liveness,
+        }
+    }
+
+    /// Current input handle
+    fn handle(&self) -> Arc<dyn SessionHandle> {
+        Arc::clone(&self.handle.read().expect("input handle lock poisoned"))
+    }
+
+    /// Deliver input through `handle` from now on
+    pub fn replace(&self, handle: Arc<dyn SessionHandle>) {
+        *self.handle.write().expect("input handle lock poisoned") = handle;
+        self.health.reset();
+    }
+
+    /// Backend currently delivering input
+    pub fn session_type(&self) -> SessionType {
+        self.handle().session_type()
+    }
+
+    /// Health score of the current handle
+    pub fn health(&self) -> &InputHealth {
+        &self.health
+    }
+
+    /// Inject keyboard keycode event (Linux evdev keycode)
+    pub async fn notify_keyboard_keycode(&self, keycode: i32, pressed: bool) -> Result<()> {
+        self.liveness.ensure_open()?;
+        let result = self
+            .handle()
+            .notify_keyboard_keycode(keycode, pressed)
+            .await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Inject absolute pointer motion (stream-relative coordinates)
+    pub async fn notify_pointer_motion_absolute(
+        &self,
+        stream_id: u32,
+        x: f64,
+        y: f64,
+    ) -> Result<()> {
+        self.liveness.ensure_open()?;
+        let result = self
+            .handle()
+            .notify_pointer_motion_absolute(stream_id, x, y)
+            .await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Inject pointer button event (evdev button code)
+    pub async fn notify_pointer_button(&self, button: i32, pressed: bool) -> Result<()> {
+        self.liveness.ensure_open()?;
+        let result = self.handle().notify_pointer_button(button, pressed).await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Inject pointer axis (scroll) event
+    pub async fn notify_pointer_axis(&self, dx: f64, dy: f64) -> Result<()> {
+        self.liveness.ensure_open()?;
+        let result = self.handle().notify_pointer_axis(dx, dy).await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Match the compositor's lock keys to the client's
+    pub async fn sync_lock_keys(&self, locks: LockKeys) -> Result<()> {
+        self.liveness.ensure_open()?;
+        let result = self.handle().sync_lock_keys(locks).await;
+        self.health.record(&result);
+        result
+    }
+
+    /// Current input delivery state (Ready for handles that don't report one)
+    pub fn status(&self) -> InputStatus {
+        match self.handle().input_status() {
+            Some(status) => status.borrow().clone(),
+            None => InputStatus::Ready,
+        }
+    }
+
+    /// Whether the session has been closed
+    pub fn is_closed(&self) -> bool {
+        self.liveness.is_closed()
+    }
+}
+
+/// Clipboard side of a session
+#[derive(Clone)]
+pub struct ClipboardCapability {
+    manager: Option<Arc<lamco_portal::ClipboardManager>>,
+    session: Option<RemoteDesktopSession>,
+    liveness: SessionLiveness,
+}
+
+impl ClipboardCapability {
+    /// Portal clipboard manager (None on Portal v1, which has no clipboard,
+    /// and without a portal session)
+    pub fn manager(&self) -> Option<Arc<lamco_portal::ClipboardManager>> {
+        self.manager.clone()
+    }
+
+    /// Portal session the clipboard manager operates on (None without a
+    /// portal session)
+    pub fn session(&self) -> Option<RemoteDesktopSession> {
+        self.session.clone()
+    }
+
+    /// Whether the session has been closed
+    pub fn is_closed(&self) -> bool {
+        self.liveness.is_closed()
+    }
+}
+
+/// One session shared by screencast, input injection and clipboard
+#[derive(Clone)]
+pub struct SharedSession {
+    screencast: ScreencastCapability,
+    input: InputCapability,
+    clipboard: ClipboardCapability,
+    liveness: SessionLiveness,
+    /// Backends to move input to when it fails
+    failover: Option<Arc<FailoverChain>>,
+}
+
+impl SharedSession {
+    /// Share a session created by a strategy
+    ///
+    /// `screencast` provides video. `input` and `clipboard` are either the
+    /// same session (Portal strategy) or a companion portal session (hybrid
+    /// Mutter strategy). Sessions without a portal have no `clipboard`.
+    pub fn new(
+        screencast: Arc<dyn SessionHandle>,
+        input: Arc<dyn SessionHandle>,
+        clipboard: Option<ClipboardComponents>,
+    ) -> Self {
+        let (manager, session) = match clipboard {
+            Some(clipboard) => (clipboard.manager, Some(clipboard.session)),
+            None => (None, None),
+        };
+        let liveness = SessionLiveness::new();
+        Self {
+            screencast: ScreencastCapability {
+                handle: screencast,
+                liveness: liveness.clone(),
+            },
+            input: InputCapability::new(input, liveness.clone()),
+            clipboard: ClipboardCapability {
+                manager,
+                session,
+                liveness: liveness.clone(),
+            },
+            liveness,
+            failover: None,
+        }
+    }
+
+    /// Fail input over along `chain` instead of closing the session
+    pub fn with_input_failover(mut self, chain: FailoverChain) -> Self {
+        self.failover = Some(Arc::new(chain));
+        self
+    }
+
+    /// Video capture capability
+    pub fn screencast(&self) -> ScreencastCapability {
+        self.screencast.clone()
+    }
+
+    /// Input injection capability
+    pub fn input(&self) -> InputCapability {
+        self.input.clone()
+    }
+
+    /// Clipboard capability
+    pub fn clipboard(&self) -> ClipboardCapability {
+        self.clipboard.clone()
+    }
+
+    /// Strategy session handle (for strategy cleanup)
+    pub fn handle(&self) -> &Arc<dyn SessionHandle> {
+        &self.screencast.handle
+    }
+
+    /// Liveness state shared by all capabilities
+    pub fn liveness(&self) -> &SessionLiveness {
+        &self.liveness
+    }
+
+    /// Mark the session closed for every capability
+    pub fn close(&self, reason: &str) {
+        self.liveness.close(reason);
+    }
+
+    /// Whether the session has been closed
+    pub fn is_closed(&self) -> bool {
+        self.liveness.is_closed()
+    }
+
+    /// Resolve once the session has been closed, with the reason
+    pub async fn closed(&self) -> String {
+        self.liveness.closed().await
+    }
+
+    /// Close the session when the portal reports it closed
+    ///
+    /// The portal emits `Closed` when the user stops sharing, the compositor
+    /// revokes the session or the portal backend goes away. The watch ends
+    /// once the session is closed for any reason. Sessions without a portal
+    /// have nothing to watch.
+    ///
+    /// When the portal session is only a companion for input and clipboard
+    /// and input can fail over, video goes on: input moves to the next
+    /// backend and clipboard sync stops.
+    pub fn spawn_closed_watch(&self) {
+        let Some(session) = self.clipboard.session() else {
+            return;
+        };
+        let liveness = self.liveness.clone();
+        let companion = (self.failover.is_some()
+            && self.screencast.session_type() != SessionType::Portal)
+            .then(|| self.input.clone());
+        tokio::spawn(async move {
+            // The session is only ever locked for reading, so holding the
+            // guard while waiting blocks neither input nor clipboard
+            let session = session.read().await;
+            let mut closed = match session.receive_closed().await {
+                Ok(closed) => closed,
+                Err(e) => {
+                    warn!("Cannot watch portal session for closure: {}", e);
+                    return;
+                }
+            };
+            tokio::select! {
+                signal = closed.next() => {
+                    if signal.is_none() {
+                        return;
+                    }
+                    match companion {
+                        Some(input) => {
+                            warn!("⚠️  Companion portal session closed, clipboard sync stops");
+                            if input.session_type() == SessionType::Portal {
+                                input.health.fail();
+                            }
+                        }
+                        None => liveness.close("portal session closed by the compositor"),
+                    }
+                }
+                _ = liveness.closed() => {}
+            }
+        });
+    }
+
+    /// Close the session when its input handle reports input lost
+    ///
+    /// Handles that recover on their own (libei reconnecting to EIS)
+    /// report `Suspended` meanwhile; only `Lost` ends the session, so it
+    /// is re-created like a lost capture session.
+    ///
+    /// With input failover, a lost handle or one whose health score drops
+    /// to zero is replaced by the next backend of the chain, given the
+    /// captured streams. The session is only closed once no backend is
+    /// left.
+    pub fn spawn_input_watch(&self) {
+        if self.failover.is_none() && self.input.handle().input_status().is_none() {
+            return;
+        }
+        let input = self.input.clone();
+        let failover = self.failover.clone();
+        let streams = self.screencast.streams();
+        let liveness = self.liveness.clone();
+        tokio::spawn(async move {
+            loop {
+                let Some(reason) = input_failure(&input, &liveness, failover.is_some()).await
+                else {
+                    return;
+                };
+                let Some(ref chain) = failover else {
+                    liveness.close(&format!("input lost: {}", reason));
+                    return;
+                };
+
+                let current = input.session_type();
+                warn!("⚠️  {} input failed: {}", current, reason);
+                match chain.fail_over(current, &streams).await {
+                    Some(handle) => {
+                        info!(
+                            "🔀 Input failed over from {} to {}",
+                            current,
+                            handle.session_type()
+                        );
+                        input.replace(handle);
+                    }
+                    None => {
+                        liveness.close(&format!("input lost: {}", reason));
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Wait for the current input handle to fail, with the reason
+///
+/// Watches the handle's status and, with `watch_health`, its health score.
+/// None once the session is closed.
+async fn input_failure(
+    input: &InputCapability,
+    liveness: &SessionLiveness,
+    watch_health: bool,
+) -> Option<String> {
+    let mut status = input.handle().input_status();
+    loop {
+        if let Some(ref mut status) = status {
+            let current = status.borrow_and_update().clone();
+            input.health.record_status(&current);
+            match current {
+                InputStatus::Ready => debug!("Session input ready"),
+                InputStatus::Suspended(reason) => {
+                    warn!("Session input suspended: {}", reason);
+                }
+                InputStatus::Lost(reason) => return Some(reason),
+            }
+        }
+        tokio::select! {
+            changed = status_changed(&mut status) => {
+                if !changed {
+                    // The handle is gone, only its health is left to watch
+                    if !watch_health {
+                        return None;
+                    }
+                    status = None;
+                }
+            }
+            () = input.health.failed(), if watch_health => {
+                return Some("too many failed injections".to_string());
+            }
+            _ = liveness.closed() => return None,
+        }
+    }
+}
+
+async fn status_changed(status: &mut Option<watch::Receiver<InputStatus>>) -> bool {
+    match status {
+        Some(status) => status.changed().await.is_ok(),
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Session handle counting injected events
+    #[derive(Default)]
+    struct CountingHandle {
+        injected: AtomicUsize,
+        status: Option<watch::Receiver<InputStatus>>,
+    }
+
+    #[async_trait]
+    impl SessionHandle for CountingHandle {
+        fn pipewire_access(&self) -> PipeWireAccess {
+            PipeWireAccess::NodeId(42)
+        }
+
+        fn streams(&self) -> Vec<StreamInfo> {
+            vec![]
+        }
+
+        fn session_type(&self) -> SessionType {
+            SessionType::MutterDirect
+        }
+
+        async fn notify_keyboard_keycode(&self, _keycode: i32, _pressed: bool) -> Result<()> {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn notify_pointer_motion_absolute(&self, _: u32, _: f64, _: f64) -> Result<()> {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn notify_pointer_button(&self, _button: i32, _pressed: bool) -> Result<()> {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn notify_pointer_axis(&self, _dx: f64, _dy: f64) -> Result<()> {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn input_status(&self) -> Option<watch::Receiver<InputStatus>> {
+            self.status.clone()
+        }
+
+        fn portal_clipboard(&self) -> Option<ClipboardComponents> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_input_fails_once_closed() {
+        let handle = Arc::new(CountingHandle::default());
+        let liveness = SessionLiveness::new();
+        let input = InputCapability::new(handle.clone(), liveness.clone());
+
+        input.notify_keyboard_keycode(30, true).await.unwrap();
+        input.notify_pointer_button(272, true).await.unwrap();
+        assert_eq!(handle.injected.load(Ordering::Relaxed), 2);
+
+        liveness.close("stopped by user");
+        assert!(input.is_closed());
+        let err = input.notify_pointer_axis(0.0, 15.0).await.unwrap_err();
+        assert!(err.to_string().contains("stopped by user"));
+        assert_eq!(handle.injected.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_closed_keeps_first_reason() {
+        let liveness = SessionLiveness::new();
+        let waiter = tokio::spawn({
+            let liveness = liveness.clone();
+            async move { liveness.closed().await }
+        });
+
+        liveness.close("portal crashed");
+        liveness.close("server shutdown");
+        let reason = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reason, "portal crashed");
+        assert_eq!(liveness.close_reason().as_deref(), Some("portal crashed"));
+    }
+
+    #[tokio::test]
+    async fn test_lost_input_closes_session() {
+        let (status_tx, status_rx) = watch::channel(InputStatus::Ready);
+        let handle: Arc<dyn SessionHandle> = Arc::new(CountingHandle {
+            status: Some(status_rx),
+            ..Default::default()
+        });
+        let session = SharedSession::new(Arc::clone(&handle), handle, None);
+        session.spawn_input_watch();
+
+        // Suspended input recovers on its own
+        status_tx
+            .send(InputStatus::Suspended("devices paused".into()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!session.is_closed());
+        assert_eq!(
+            session.input().status(),
+            InputStatus::Suspended("devices paused".into())
+        );
+
+        status_tx
+            .send(InputStatus::Lost("EIS reconnect failed".into()))
+            .unwrap();
+        let reason = tokio::time::timeout(Duration::from_secs(1), session.closed())
+            .await
+            .unwrap();
+        assert_eq!(reason, "input lost: EIS reconnect failed");
+    }
+
+    /// Provider handing out one prepared input handle
+    struct Standby(Arc<CountingHandle>);
+
+    #[async_trait]
+    impl crate::session::failover::InputProvider for Standby {
+        async fn create_input(
+            &self,
+            _backend: SessionType,
+            _streams: &[StreamInfo],
+        ) -> Result<Arc<dyn SessionHandle>> {
+            Ok(Arc::clone(&self.0) as Arc<dyn SessionHandle>)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lost_input_fails_over() {
+        let (status_tx, status_rx) = watch::channel(InputStatus::Ready);
+        let handle: Arc<dyn SessionHandle> = Arc::new(CountingHandle {
+            status: Some(status_rx),
+            ..Default::default()
+        });
+        let standby = Arc::new(CountingHandle::default());
+        let chain = FailoverChain::new(
+            vec![SessionType::Libei, SessionType::WlrDirect],
+            Arc::new(Standby(Arc::clone(&standby))),
+        );
+        let session =
+            SharedSession::new(Arc::clone(&handle), handle, None).with_input_failover(chain);
+        let input = session.input();
+        session.spawn_input_watch();
+
+        status_tx
+            .send(InputStatus::Lost("EIS reconnect failed".into()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!session.is_closed());
+
+        input.notify_keyboard_keycode(30, true).await.unwrap();
+        assert_eq!(standby.injected.load(Ordering::Relaxed), 1);
+        assert_eq!(input.health().score(), 100);
+    }
+}
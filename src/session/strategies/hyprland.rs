@@ -0,0 +1,106 @@
+//! Hyprland Strategy
+//!
+//! wlr-direct with Hyprland's IPC on top. Input goes through the virtual
+//! keyboard and pointer protocols and video through wlr-screencopy, as with
+//! any wlroots compositor, but the outputs to capture come from Hyprland's
+//! monitor list: monitors mirroring another one are not captured a second
+//! time, disabled ones are skipped, and positions are those of the layout
+//! Hyprland reports. The focused window for application overrides is also
+//! read from the IPC socket (see [`crate::compositor::hyprland`]).
+//!
+//! Selected when `HYPRLAND_INSTANCE_SIGNATURE` points at a reachable
+//! Hyprland instance, unless `[wlr] hyprland_ipc = false`.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::compositor::hyprland;
+use crate::session::strategy::{SessionHandle, SessionStrategy};
+
+use super::wlr_direct::{OutputPlacement, WlrDirectStrategy};
+
+/// Hyprland strategy implementation
+pub struct HyprlandStrategy {
+    /// Capture video with wlr-screencopy
+    screencopy: bool,
+}
+
+impl HyprlandStrategy {
+    /// Create a new Hyprland strategy (input only)
+    pub fn new() -> Self {
+        Self { screencopy: false }
+    }
+
+    /// Capture video with wlr-screencopy as well
+    pub fn with_screencopy(mut self, enabled: bool) -> Self {
+        self.screencopy = enabled;
+        self
+    }
+
+    /// Check that Hyprland's IPC answers and the wlr-direct protocols bind
+    pub async fn is_available() -> bool {
+        if !hyprland::is_running() {
+            return false;
+        }
+        let monitors = tokio::task::spawn_blocking(hyprland::monitors).await;
+        matches!(monitors, Ok(Ok(_))) && WlrDirectStrategy::is_available().await
+    }
+}
+
+impl Default for HyprlandStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SessionStrategy for HyprlandStrategy {
+    fn name(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn requires_initial_setup(&self) -> bool {
+        false
+    }
+
+    fn supports_unattended_restore(&self) -> bool {
+        true
+    }
+
+    async fn create_session(&self) -> Result<Arc<dyn SessionHandle>> {
+        let monitors = tokio::task::spawn_blocking(hyprland::monitors)
+            .await?
+            .context("Failed to read Hyprland's monitors")?;
+        let layout: Vec<OutputPlacement> = monitors
+            .iter()
+            .filter(|monitor| monitor.is_active())
+            .map(|monitor| OutputPlacement {
+                connector: monitor.name.clone(),
+                position_x: monitor.x,
+                position_y: monitor.y,
+            })
+            .collect();
+        for monitor in monitors.iter().filter(|monitor| !monitor.is_active()) {
+            match monitor.mirror_of {
+                Some(ref source) => info!(
+                    "🖥️ hyprland: {} mirrors {}, not captured",
+                    monitor.name, source
+                ),
+                None => info!("🖥️ hyprland: {} is disabled, not captured", monitor.name),
+            }
+        }
+
+        WlrDirectStrategy::new()
+            .with_screencopy(self.screencopy)
+            .with_output_layout(layout)
+            .create_session()
+            .await
+    }
+
+    async fn cleanup(&self, session: &dyn SessionHandle) -> Result<()> {
+        WlrDirectStrategy::new().cleanup(session).await
+    }
+}
@@ -0,0 +1,465 @@
+//! KWin ScreenCast Strategy Implementation
+//!
+//! Uses KWin's `zkde_screencast_unstable_v1` Wayland protocol to have the
+//! compositor create PipeWire streams directly, the way Plasma's own
+//! xdg-desktop-portal-kde backend does. Compared with the portal:
+//!
+//! - no permission dialog for video
+//! - one hop less between KWin and the PipeWire consumer
+//! - virtual outputs: KWin can create a new output of a given size just for
+//!   the remote session (protocol version 2+)
+//!
+//! # Authorization
+//!
+//! KWin only advertises the protocol to clients whose desktop file lists it
+//! in `X-KDE-Wayland-Interfaces=zkde_screencast_unstable_v1`. Without that
+//! entry the global is missing and the selector falls back to the portal.
+//!
+//! # Limitations
+//!
+//! - **Video only**: input and clipboard go through a companion Portal
+//!   RemoteDesktop session, as with the Mutter strategy
+//! - **Not Flatpak-compatible** (requires direct Wayland socket access)
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_output, wl_registry};
+use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum};
+use wayland_protocols_plasma::screencast::v1::client::{
+    zkde_screencast_stream_unstable_v1::{self, ZkdeScreencastStreamUnstableV1},
+    zkde_screencast_unstable_v1::{Pointer, ZkdeScreencastUnstableV1},
+};
+
+use crate::session::strategy::{
+    ClipboardComponents, PipeWireAccess, SessionHandle, SessionStrategy, SessionType, StreamInfo,
+};
+
+/// Wayland interface name of the KWin screencast protocol
+pub const KDE_SCREENCAST_INTERFACE: &str = "zkde_screencast_unstable_v1";
+
+/// Time KWin gets to create the PipeWire streams
+const STREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Name of the virtual output created for remote sessions
+const VIRTUAL_OUTPUT_NAME: &str = "lamco-rdp";
+
+/// Output as announced by wl_output
+#[derive(Debug, Default, Clone)]
+struct OutputInfo {
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Progress of one requested stream
+#[derive(Debug, Clone)]
+enum StreamState {
+    Pending,
+    Created(u32),
+    Failed(String),
+    Closed,
+}
+
+/// State for Wayland protocol dispatch
+struct KwinState {
+    outputs: Vec<OutputInfo>,
+    streams: Vec<StreamState>,
+}
+
+impl KwinState {
+    fn new() -> Self {
+        Self {
+            outputs: Vec::new(),
+            streams: Vec::new(),
+        }
+    }
+}
+
+/// KWin session handle
+///
+/// Keeps the Wayland connection and stream objects alive: KWin tears the
+/// PipeWire streams down when they are closed or the connection goes away.
+pub struct KwinSessionHandleImpl {
+    connection: Connection,
+    event_queue: Mutex<EventQueue<KwinState>>,
+    screencast: ZkdeScreencastUnstableV1,
+    stream_objects: Vec<ZkdeScreencastStreamUnstableV1>,
+    streams: Vec<StreamInfo>,
+}
+
+impl KwinSessionHandleImpl {
+    fn video_only<T>(&self) -> Result<T> {
+        Err(anyhow!(
+            "KWin screencast provides video only; input goes through the Portal session"
+        ))
+    }
+}
+
+#[async_trait]
+impl SessionHandle for KwinSessionHandleImpl {
+    fn pipewire_access(&self) -> PipeWireAccess {
+        // KWin creates the streams on the user's PipeWire daemon
+        PipeWireAccess::NodeId(self.streams.first().map(|s| s.node_id).unwrap_or(0))
+    }
+
+    fn streams(&self) -> Vec<StreamInfo> {
+        self.streams.clone()
+    }
+
+    fn session_type(&self) -> SessionType {
+        SessionType::KwinScreencast
+    }
+
+    async fn notify_keyboard_keycode(&self, _keycode: i32, _pressed: bool) -> Result<()> {
+        self.video_only()
+    }
+
+    async fn notify_pointer_motion_absolute(&self, _: u32, _: f64, _: f64) -> Result<()> {
+        self.video_only()
+    }
+
+    async fn notify_pointer_button(&self, _button: i32, _pressed: bool) -> Result<()> {
+        self.video_only()
+    }
+
+    async fn notify_pointer_axis(&self, _dx: f64, _dy: f64) -> Result<()> {
+        self.video_only()
+    }
+
+    fn portal_clipboard(&self) -> Option<ClipboardComponents> {
+        // KWin's screencast protocol has no clipboard
+        // Caller must create a separate Portal session for clipboard operations
+        None
+    }
+}
+
+impl Drop for KwinSessionHandleImpl {
+    fn drop(&mut self) {
+        for stream in &self.stream_objects {
+            stream.close();
+        }
+        self.screencast.destroy();
+        if let Err(e) = self.connection.flush() {
+            debug!("[kwin] Failed to flush stream close requests: {}", e);
+        }
+        // Events for the closed streams are of no interest any more
+        if let Ok(mut queue) = self.event_queue.lock() {
+            let _ = queue.dispatch_pending(&mut KwinState::new());
+        }
+    }
+}
+
+/// KWin screencast strategy
+///
+/// Streams every output (or one virtual output) through KWin's screencast
+/// protocol. Requires KDE Plasma and authorization via the desktop file.
+pub struct KwinScreencastStrategy {
+    /// Size of a virtual output to create, or None to stream the real outputs
+    virtual_output: Option<(u32, u32)>,
+}
+
+impl KwinScreencastStrategy {
+    /// Create a new KWin screencast strategy
+    ///
+    /// # Arguments
+    ///
+    /// * `virtual_output` - Size of a virtual output to create for the
+    ///   session, or None to stream the physical outputs
+    pub fn new(virtual_output: Option<(u32, u32)>) -> Self {
+        Self { virtual_output }
+    }
+
+    /// Check if KWin advertises the screencast protocol to us
+    pub async fn is_available() -> bool {
+        let conn = match Connection::connect_to_env() {
+            Ok(conn) => conn,
+            Err(e) => {
+                debug!("[kwin] Wayland connection failed: {}", e);
+                return false;
+            }
+        };
+        match registry_queue_init::<KwinState>(&conn) {
+            Ok((globals, _queue)) => globals.contents().with_list(|list| {
+                list.iter()
+                    .any(|global| global.interface == KDE_SCREENCAST_INTERFACE)
+            }),
+            Err(e) => {
+                debug!("[kwin] Registry enumeration failed: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Request streams and wait for KWin to create them (blocking)
+    fn start_streams(virtual_output: Option<(u32, u32)>) -> Result<KwinSessionHandleImpl> {
+        let conn = Connection::connect_to_env()
+            .context("Failed to connect to Wayland display. Ensure WAYLAND_DISPLAY is set.")?;
+        let (globals, mut event_queue) = registry_queue_init::<KwinState>(&conn)
+            .context("Failed to initialize Wayland registry")?;
+        let qh = event_queue.handle();
+        let mut state = KwinState::new();
+
+        let screencast: ZkdeScreencastUnstableV1 = globals.bind(&qh, 1..=3, ()).context(
+            "Failed to bind zkde_screencast_unstable_v1. \
+             KWin only offers it to clients authorized in their desktop file.",
+        )?;
+        debug!(
+            "[kwin] Bound zkde_screencast_unstable_v1 v{}",
+            screencast.version()
+        );
+
+        let pointer = u32::from(Pointer::Metadata);
+        let mut stream_objects = Vec::new();
+        let mut stream_outputs = Vec::new();
+
+        match virtual_output {
+            Some((width, height)) => {
+                if screencast.version() < 2 {
+                    bail!("KWin does not support virtual outputs (screencast protocol v1)");
+                }
+                info!("[kwin] Requesting virtual output {}x{}", width, height);
+                stream_objects.push(screencast.stream_virtual_output(
+                    VIRTUAL_OUTPUT_NAME.to_string(),
+                    width as i32,
+                    height as i32,
+                    1.0,
+                    pointer,
+                    &qh,
+                    0,
+                ));
+                state.streams.push(StreamState::Pending);
+                stream_outputs.push(OutputInfo {
+                    name: Some(VIRTUAL_OUTPUT_NAME.to_string()),
+                    width,
+                    height,
+                    ..OutputInfo::default()
+                });
+            }
+            None => {
+                // Bind every output and learn its position and mode
+                let outputs: Vec<wl_output::WlOutput> = globals.contents().with_list(|list| {
+                    list.iter()
+                        .filter(|global| global.interface == "wl_output")
+                        .enumerate()
+                        .map(|(index, global)| {
+                            globals
+                                .registry()
+                                .bind(global.name, global.version.min(4), &qh, index)
+                        })
+                        .collect()
+                });
+                if outputs.is_empty() {
+                    bail!("No outputs to stream");
+                }
+                state.outputs = vec![OutputInfo::default(); outputs.len()];
+                event_queue
+                    .roundtrip(&mut state)
+                    .context("Failed to receive output information")?;
+
+                for (index, output) in outputs.iter().enumerate() {
+                    stream_objects.push(screencast.stream_output(output, pointer, &qh, index));
+                    state.streams.push(StreamState::Pending);
+                }
+                stream_outputs = state.outputs.clone();
+            }
+        }
+
+        // KWin answers each request with created or failed
+        let deadline = Instant::now() + STREAM_TIMEOUT;
+        while state
+            .streams
+            .iter()
+            .any(|s| matches!(s, StreamState::Pending))
+        {
+            if Instant::now() >= deadline {
+                bail!(
+                    "Timeout waiting for KWin to create PipeWire streams ({}s)",
+                    STREAM_TIMEOUT.as_secs()
+                );
+            }
+            event_queue
+                .roundtrip(&mut state)
+                .context("Wayland roundtrip failed while waiting for streams")?;
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let mut streams = Vec::new();
+        for (output, stream) in stream_outputs.iter().zip(&state.streams) {
+            let name = output.name.as_deref().unwrap_or("unnamed output");
+            match stream {
+                StreamState::Created(node_id) => streams.push(StreamInfo {
+                    node_id: *node_id,
+                    width: output.width,
+                    height: output.height,
+                    position_x: output.x,
+                    position_y: output.y,
+                }),
+                StreamState::Failed(error) => warn!("[kwin] Streaming {} failed: {}", name, error),
+                StreamState::Closed => warn!("[kwin] Stream for {} closed by KWin", name),
+                StreamState::Pending => {}
+            }
+        }
+        if streams.is_empty() {
+            bail!("KWin did not create any PipeWire stream");
+        }
+
+        Ok(KwinSessionHandleImpl {
+            connection: conn,
+            event_queue: Mutex::new(event_queue),
+            screencast,
+            stream_objects,
+            streams,
+        })
+    }
+}
+
+#[async_trait]
+impl SessionStrategy for KwinScreencastStrategy {
+    fn name(&self) -> &'static str {
+        "KWin ScreenCast"
+    }
+
+    fn requires_initial_setup(&self) -> bool {
+        // Authorization comes from the desktop file, not a dialog
+        false
+    }
+
+    fn supports_unattended_restore(&self) -> bool {
+        true
+    }
+
+    async fn create_session(&self) -> Result<Arc<dyn SessionHandle>> {
+        info!("Creating session using KWin screencast protocol (no dialog for video)");
+
+        if std::path::Path::new("/.flatpak-info").exists() {
+            bail!("KWin screencast protocol not available in Flatpak");
+        }
+
+        let virtual_output = self.virtual_output;
+        let handle = tokio::task::spawn_blocking(move || Self::start_streams(virtual_output))
+            .await
+            .context("KWin stream setup task failed")??;
+
+        info!("✅ KWin screencast session created");
+        for (idx, stream) in handle.streams.iter().enumerate() {
+            info!(
+                "  Stream {}: {}x{} at ({}, {}), PipeWire node: {}",
+                idx,
+                stream.width,
+                stream.height,
+                stream.position_x,
+                stream.position_y,
+                stream.node_id
+            );
+        }
+
+        Ok(Arc::new(handle))
+    }
+
+    async fn cleanup(&self, _session: &dyn SessionHandle) -> Result<()> {
+        // Streams are closed when the handle is dropped
+        debug!("KWin session cleanup (streams close with the handle)");
+        Ok(())
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for KwinState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Registry events handled by registry_queue_init
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for KwinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.get_mut(*index) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry { x, y, .. } => {
+                output.x = x;
+                output.y = y;
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                output.width = width.max(0) as u32;
+                output.height = height.max(0) as u32;
+            }
+            wl_output::Event::Name { name } => output.name = Some(name),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZkdeScreencastUnstableV1, ()> for KwinState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZkdeScreencastUnstableV1,
+        _event: <ZkdeScreencastUnstableV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // No events expected from the screencast manager
+    }
+}
+
+impl Dispatch<ZkdeScreencastStreamUnstableV1, usize> for KwinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZkdeScreencastStreamUnstableV1,
+        event: zkde_screencast_stream_unstable_v1::Event,
+        index: &usize,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(stream) = state.streams.get_mut(*index) else {
+            return;
+        };
+        *stream = match event {
+            zkde_screencast_stream_unstable_v1::Event::Created { node } => {
+                debug!("[kwin] Stream {} created: PipeWire node {}", index, node);
+                StreamState::Created(node)
+            }
+            zkde_screencast_stream_unstable_v1::Event::Failed { error } => {
+                StreamState::Failed(error)
+            }
+            zkde_screencast_stream_unstable_v1::Event::Closed => StreamState::Closed,
+            _ => return,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kwin_availability_check() {
+        let available = KwinScreencastStrategy::is_available().await;
+        println!("KWin screencast protocol available: {}", available);
+    }
+}
@@ -0,0 +1,221 @@
+//! EIS keyboard keymap and lock state tracking
+//!
+//! An EIS keyboard device may announce the XKB keymap the compositor
+//! uses for it (`ei_keyboard.keymap`) and the modifier state it currently
+//! applies (`ei_keyboard.modifiers`). Key events themselves are evdev
+//! keycodes, so the keymap does not change which code is sent; it tells
+//! us which codes the device actually has and which modifier bits carry
+//! Caps Lock and Num Lock, so the lock state the compositor applies can
+//! be compared with the RDP client's.
+//!
+//! # Keycode Format
+//!
+//! XKB keymaps built from the evdev rules number their keys evdev + 8:
+//! - KEY_A = 30 → XKB keycode 38 (`<AC01>`)
+//! - KEY_CAPSLOCK = 58 → XKB keycode 66 (`<CAPS>`)
+//!
+//! The input handler has already translated RDP scancodes to evdev
+//! keycodes, so lookups here only add the offset.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::os::fd::OwnedFd;
+use tracing::debug;
+use xkbcommon::xkb;
+
+use crate::session::strategy::LockKeys;
+
+/// Offset between evdev keycodes and XKB keycodes
+const EVDEV_OFFSET: u32 = 8;
+
+/// Largest keymap we accept from the EIS implementation
+const MAX_KEYMAP_SIZE: usize = 1024 * 1024;
+
+/// Lock keys kept in sync with the RDP client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LockKey {
+    Caps,
+    Num,
+    Scroll,
+}
+
+impl LockKey {
+    pub(super) const ALL: [Self; 3] = [Self::Caps, Self::Num, Self::Scroll];
+
+    /// evdev keycode that toggles the lock
+    pub(super) fn evdev_keycode(self) -> u32 {
+        match self {
+            Self::Caps => 58,   // KEY_CAPSLOCK
+            Self::Num => 69,    // KEY_NUMLOCK
+            Self::Scroll => 70, // KEY_SCROLLLOCK
+        }
+    }
+
+    /// Whether the RDP client has this lock on
+    pub(super) fn is_on(self, locks: LockKeys) -> bool {
+        match self {
+            Self::Caps => locks.caps_lock,
+            Self::Num => locks.num_lock,
+            Self::Scroll => locks.scroll_lock,
+        }
+    }
+
+    /// XKB modifier the lock sets, if it has one
+    fn modifier_name(self) -> Option<&'static str> {
+        match self {
+            Self::Caps => Some(xkb::MOD_NAME_CAPS),
+            Self::Num => Some(xkb::MOD_NAME_NUM),
+            Self::Scroll => None,
+        }
+    }
+}
+
+/// Keymap announced by the EIS keyboard, reduced to what we track
+///
+/// The XKB objects are only used while compiling: the device keeps the
+/// keys it has and which modifier bit each lock sets, and follows the
+/// lock state itself.
+pub(super) struct EisKeymap {
+    /// evdev keycodes the keymap defines
+    keycodes: HashSet<u32>,
+    /// Modifier mask each lock key sets, where the keymap has one
+    lock_masks: [Option<u32>; 3],
+    /// Lock state as last reported by the compositor or toggled by us
+    locked: [bool; 3],
+}
+
+impl EisKeymap {
+    /// Compile the keymap from an `ei_keyboard.keymap` event
+    ///
+    /// The fd must be mapped privately; `size` includes the trailing NUL.
+    pub(super) fn from_fd(fd: OwnedFd, size: u32) -> Result<Self> {
+        let size = size as usize;
+        if size == 0 || size > MAX_KEYMAP_SIZE {
+            bail!("Keymap size {} out of range", size);
+        }
+
+        let file = File::from(fd);
+        // SAFETY: private read-only mapping; the sender never truncates it
+        let map = unsafe {
+            memmap2::MmapOptions::new()
+                .len(size)
+                .map_copy_read_only(&file)
+        }
+        .context("Failed to map EIS keymap")?;
+
+        let text = std::str::from_utf8(&map)
+            .context("EIS keymap is not UTF-8")?
+            .trim_end_matches('\0');
+
+        Self::from_string(text.to_string())
+    }
+
+    fn from_string(text: String) -> Result<Self> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_string(
+            &context,
+            text,
+            xkb::KEYMAP_FORMAT_TEXT_V1,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .ok_or_else(|| anyhow!("Failed to compile EIS keymap"))?;
+
+        let keycodes = (keymap.min_keycode().raw()..=keymap.max_keycode().raw())
+            .filter(|&code| code >= EVDEV_OFFSET)
+            .filter(|&code| keymap.key_get_name(xkb::Keycode::new(code)).is_some())
+            .map(|code| code - EVDEV_OFFSET)
+            .collect::<HashSet<_>>();
+
+        let lock_masks = LockKey::ALL.map(|lock| {
+            let name = lock.modifier_name()?;
+            let index = keymap.mod_get_index(name);
+            (index != xkb::MOD_INVALID).then(|| 1 << index)
+        });
+
+        debug!(
+            "[libei] Keymap compiled: {} layout(s), {} keys",
+            keymap.num_layouts(),
+            keycodes.len()
+        );
+
+        Ok(Self {
+            keycodes,
+            lock_masks,
+            locked: [false; 3],
+        })
+    }
+
+    /// Whether the device's keymap has a key for this evdev keycode
+    pub(super) fn has_key(&self, evdev_keycode: u32) -> bool {
+        self.keycodes.contains(&evdev_keycode)
+    }
+
+    /// Follow a key we sent, so lock state stays current between
+    /// `modifiers` events
+    pub(super) fn update_key(&mut self, evdev_keycode: u32, pressed: bool) {
+        if !pressed {
+            return;
+        }
+        for (i, lock) in LockKey::ALL.iter().enumerate() {
+            if lock.evdev_keycode() == evdev_keycode {
+                self.locked[i] = !self.locked[i];
+            }
+        }
+    }
+
+    /// Apply the locked mask of an `ei_keyboard.modifiers` event
+    ///
+    /// Locks without a modifier bit (Scroll Lock in most keymaps) keep
+    /// the state we tracked.
+    pub(super) fn set_locked_modifiers(&mut self, locked: u32) {
+        for (i, mask) in self.lock_masks.iter().enumerate() {
+            if let Some(mask) = mask {
+                self.locked[i] = locked & mask != 0;
+            }
+        }
+    }
+
+    /// Whether the lock is on in the tracked state
+    pub(super) fn lock_active(&self, lock: LockKey) -> bool {
+        self.locked[lock as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_key_tracking() {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let Some(keymap) = xkb::Keymap::new_from_names(
+            &context,
+            "evdev",
+            "pc105",
+            "de",
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        ) else {
+            // XKB data isn't installed in every test environment
+            return;
+        };
+        let mut keymap = EisKeymap::from_string(keymap.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1))
+            .expect("round-tripped keymap compiles");
+
+        assert!(keymap.has_key(30)); // KEY_A
+        assert!(!keymap.lock_active(LockKey::Caps));
+
+        let caps = LockKey::Caps.evdev_keycode();
+        keymap.update_key(caps, true);
+        keymap.update_key(caps, false);
+        assert!(keymap.lock_active(LockKey::Caps));
+
+        // Compositor reports Num Lock on and Caps Lock off
+        let num = keymap.lock_masks[LockKey::Num as usize].expect("Mod2 in keymap");
+        keymap.set_locked_modifiers(num);
+        assert!(!keymap.lock_active(LockKey::Caps));
+        assert!(keymap.lock_active(LockKey::Num));
+    }
+}
@@ -39,6 +39,13 @@
 //! 5. Send input events via devices
 //! 6. Frame events to group related inputs
 //!
+//! Devices may only be used between `Resumed` and `Paused`; input arriving
+//! while no device is resumed is dropped and the handle reports
+//! [`InputStatus::Suspended`]. When the EIS connection ends (the portal
+//! backend restarted or disconnected us) the handle calls ConnectToEIS
+//! again and re-runs discovery, and reports [`InputStatus::Lost`] if that
+//! keeps failing.
+//!
 //! # Compatibility
 //!
 //! **Works with:**
@@ -47,14 +54,26 @@
 //! - Any portal backend implementing RemoteDesktop v2+ with ConnectToEIS
 //!
 //! **Flatpak compatible:** Yes (Portal provides socket FD across sandbox boundary)
+//!
+//! # Keyboard Layout
+//!
+//! EIS key events carry evdev keycodes and the compositor applies its own
+//! layout, so non-US layouts work as long as we send the positional key.
+//! When the keyboard announces its keymap we only send keys it defines,
+//! and follow Caps/Num/Scroll Lock so RDP Synchronize events can correct
+//! them (see [`keymap`]).
+
+mod keymap;
 
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 use async_trait::async_trait;
 use futures::stream::StreamExt;
 use std::collections::HashMap;
 use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
 use ashpd::desktop::remote_desktop::{DeviceType, RemoteDesktop};
@@ -63,10 +82,15 @@ use reis::ei;
 use reis::tokio::EiEventStream;
 use reis::PendingRequestResult;
 
+use crate::compositor::PortalCapabilities;
 use crate::session::strategy::{
-    ClipboardComponents, PipeWireAccess, SessionHandle, SessionStrategy, SessionType, StreamInfo,
+    ClipboardComponents, InputStatus, LockKeys, PipeWireAccess, SessionHandle, SessionStrategy,
+    SessionType, StreamInfo,
 };
 
+use crate::utils::spawn_in_current_span;
+use keymap::{EisKeymap, LockKey};
+
 /// libei/EIS strategy implementation
 ///
 /// Provides input injection via Portal RemoteDesktop + EIS protocol.
@@ -83,20 +107,19 @@ impl LibeiStrategy {
 
     /// Check if libei/EIS is available
     ///
-    /// Verifies that Portal RemoteDesktop with ConnectToEIS support is available.
+    /// Consults the portal capability matrix: ConnectToEIS needs RemoteDesktop v2+.
     pub async fn is_available() -> bool {
-        // Check if Portal RemoteDesktop is available
-        match RemoteDesktop::new().await {
-            Ok(rd) => {
-                // Try to check if ConnectToEIS is available
-                // This is a v2+ method, older portals won't have it
-                debug!("[libei] Portal RemoteDesktop proxy created successfully");
-                // We can't easily check for ConnectToEIS without creating a session
-                // Assume available if RemoteDesktop portal exists
-                true
+        match PortalCapabilities::probe().await {
+            Ok(caps) => {
+                debug!(
+                    "[libei] Portal RemoteDesktop v{}, ConnectToEIS: {}",
+                    caps.remote_desktop_version,
+                    caps.supports_connect_to_eis()
+                );
+                caps.supports_connect_to_eis()
             }
             Err(e) => {
-                debug!("[libei] Portal RemoteDesktop not available: {}", e);
+                debug!("[libei] Portal not available: {}", e);
                 false
             }
         }
@@ -161,59 +184,32 @@ impl SessionStrategy for LibeiStrategy {
 
         info!("✅ libei: RemoteDesktop session started");
 
-        // Get EIS socket FD via ConnectToEIS
-        info!("🔌 libei: Calling ConnectToEIS to get socket FD");
-
-        let fd = remote_desktop
-            .connect_to_eis(&session)
-            .await
-            .context("ConnectToEIS failed - portal may not support this method (requires v2+)")?;
-
-        info!("✅ libei: Received EIS socket FD");
-
-        // Create UnixStream from FD
-        let stream = UnixStream::from(fd);
-
-        // Create EIS context
-        let context =
-            ei::Context::new(stream).context("Failed to create EIS context from socket")?;
-
-        info!("🔑 libei: EIS context created, performing handshake");
-
-        // Perform handshake and get event stream (tokio-async)
-        let mut events =
-            EiEventStream::new(context.clone()).context("Failed to create EIS event stream")?;
-
-        let handshake_resp = reis::tokio::ei_handshake(
-            &mut events,
-            "lamco-rdp-server",
-            ei::handshake::ContextType::Sender,
-        )
-        .await
-        .context("EIS handshake failed")?;
-
-        info!("✅ libei: EIS handshake complete, connection established");
+        let eis = connect_eis(&remote_desktop, &session).await?;
 
         // Create session handle with event-driven architecture
+        let (status, _) = watch::channel(InputStatus::Suspended(
+            "waiting for EIS devices".to_string(),
+        ));
         let handle = Arc::new(LibeiSessionHandleImpl {
+            remote_desktop,
             portal_session: Arc::new(RwLock::new(session)),
-            context: Arc::new(context),
-            connection: Arc::new(Mutex::new(handshake_resp.connection)),
-            event_stream: Arc::new(Mutex::new(events)),
+            context: Arc::new(Mutex::new(eis.context)),
+            connection: Arc::new(Mutex::new(eis.connection)),
             seats: Arc::new(Mutex::new(HashMap::new())),
             devices: Arc::new(Mutex::new(HashMap::new())),
             keyboard_device: Arc::new(Mutex::new(None)),
             pointer_device: Arc::new(Mutex::new(None)),
+            keymap: Arc::new(Mutex::new(None)),
             streams: Arc::new(Mutex::new(vec![])),
-            last_serial: Arc::new(Mutex::new(handshake_resp.serial)),
+            last_serial: Arc::new(Mutex::new(eis.serial)),
+            status: Arc::new(status),
+            dropped_events: Arc::new(AtomicU64::new(0)),
         });
 
         // Spawn background task to handle EIS events
         let handle_clone = handle.clone();
-        tokio::spawn(async move {
-            if let Err(e) = handle_clone.event_loop().await {
-                error!("❌ libei: Event loop error: {:#}", e);
-            }
+        spawn_in_current_span(async move {
+            handle_clone.event_loop(eis.events).await;
         });
 
         info!("✅ libei: Session created with background event loop");
@@ -227,6 +223,65 @@ impl SessionStrategy for LibeiStrategy {
     }
 }
 
+/// Reconnection attempts after the EIS connection is lost
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Delay before the first reconnection attempt (doubles each time)
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// A connected and handshaken EIS context
+struct EisConnection {
+    context: ei::Context,
+    events: EiEventStream,
+    connection: ei::Connection,
+    serial: u32,
+}
+
+/// Ask the portal for an EIS socket and perform the handshake
+async fn connect_eis(
+    remote_desktop: &RemoteDesktop<'static>,
+    session: &ashpd::desktop::Session<'static, RemoteDesktop<'static>>,
+) -> Result<EisConnection> {
+    // Get EIS socket FD via ConnectToEIS
+    info!("🔌 libei: Calling ConnectToEIS to get socket FD");
+
+    let fd = remote_desktop
+        .connect_to_eis(session)
+        .await
+        .context("ConnectToEIS failed - portal may not support this method (requires v2+)")?;
+
+    info!("✅ libei: Received EIS socket FD");
+
+    // Create UnixStream from FD
+    let stream = UnixStream::from(fd);
+
+    // Create EIS context
+    let context = ei::Context::new(stream).context("Failed to create EIS context from socket")?;
+
+    info!("🔑 libei: EIS context created, performing handshake");
+
+    // Perform handshake and get event stream (tokio-async)
+    let mut events =
+        EiEventStream::new(context.clone()).context("Failed to create EIS event stream")?;
+
+    let handshake_resp = reis::tokio::ei_handshake(
+        &mut events,
+        "lamco-rdp-server",
+        ei::handshake::ContextType::Sender,
+    )
+    .await
+    .context("EIS handshake failed")?;
+
+    info!("✅ libei: EIS handshake complete, connection established");
+
+    Ok(EisConnection {
+        context,
+        events,
+        connection: handshake_resp.connection,
+        serial: handshake_resp.serial,
+    })
+}
+
 /// Device data for EIS devices
 #[derive(Default)]
 struct DeviceData {
@@ -234,6 +289,8 @@ struct DeviceData {
     device_type: Option<ei::device::DeviceType>,
     interfaces: HashMap<String, reis::Object>,
     seat: Option<ei::Seat>,
+    /// Devices start paused; events may only be sent between Resumed and Paused
+    resumed: bool,
 }
 
 impl DeviceData {
@@ -252,26 +309,61 @@ struct SeatData {
 /// libei session handle implementation
 ///
 /// Implements SessionHandle trait using event-driven EIS protocol.
+///
+/// Input is dropped (and counted) while the compositor has the devices
+/// paused or the EIS connection is being re-established; the state is
+/// published through [`SessionHandle::input_status`].
 pub struct LibeiSessionHandleImpl {
+    remote_desktop: RemoteDesktop<'static>,
     portal_session: Arc<RwLock<ashpd::desktop::Session<'static, RemoteDesktop<'static>>>>,
-    context: Arc<ei::Context>,
+    context: Arc<Mutex<ei::Context>>,
     connection: Arc<Mutex<ei::Connection>>,
-    event_stream: Arc<Mutex<EiEventStream>>,
     seats: Arc<Mutex<HashMap<ei::Seat, SeatData>>>,
     devices: Arc<Mutex<HashMap<ei::Device, DeviceData>>>,
     keyboard_device: Arc<Mutex<Option<ei::Device>>>,
     pointer_device: Arc<Mutex<Option<ei::Device>>>,
+    /// Keymap announced by the keyboard device (None until it arrives)
+    keymap: Arc<Mutex<Option<EisKeymap>>>,
     streams: Arc<Mutex<Vec<StreamInfo>>>,
     last_serial: Arc<Mutex<u32>>,
+    status: Arc<watch::Sender<InputStatus>>,
+    /// Input events dropped since input was last ready
+    dropped_events: Arc<AtomicU64>,
 }
 
 impl LibeiSessionHandleImpl {
     /// Background event loop for EIS protocol
     ///
     /// Handles seat/device discovery and maintains EIS connection state.
-    async fn event_loop(&self) -> Result<()> {
-        let mut events = self.event_stream.lock().await;
+    /// When the connection ends it asks the portal for a new EIS socket and
+    /// re-runs device discovery; after [`RECONNECT_ATTEMPTS`] failures input
+    /// is reported lost.
+    async fn event_loop(&self, mut events: EiEventStream) {
+        loop {
+            let reason = self.run_events(&mut events).await;
+            warn!("⚠️  libei: EIS connection lost: {}", reason);
+            self.reset_devices().await;
+            self.set_status(InputStatus::Suspended(format!("reconnecting: {}", reason)));
+
+            match self.reconnect().await {
+                Ok(eis) => {
+                    *self.context.lock().await = eis.context;
+                    *self.connection.lock().await = eis.connection;
+                    *self.last_serial.lock().await = eis.serial;
+                    events = eis.events;
+                    info!("✅ libei: Reconnected to EIS, waiting for devices");
+                }
+                Err(e) => {
+                    error!("❌ libei: Giving up on EIS: {:#}", e);
+                    self.set_status(InputStatus::Lost(format!("{:#}", e)));
+                    return;
+                }
+            }
+        }
+    }
 
+    /// Dispatch events until the connection ends, returning why it ended
+    async fn run_events(&self, events: &mut EiEventStream) -> String {
         while let Some(result) = events.next().await {
             let event = match result {
                 Ok(PendingRequestResult::Request(event)) => event,
@@ -283,16 +375,130 @@ impl LibeiSessionHandleImpl {
                     debug!("[libei] Invalid object ID: {}", obj_id);
                     continue;
                 }
+                Err(e) => return format!("event stream error: {}", e),
+            };
+
+            if let ei::Event::Connection(
+                _,
+                ei::connection::Event::Disconnected {
+                    reason,
+                    explanation,
+                    ..
+                },
+            ) = &event
+            {
+                return format!("disconnected by EIS ({:?}: {:?})", reason, explanation);
+            }
+
+            if let Err(e) = self.handle_event(event).await {
+                return format!("event handling failed: {:#}", e);
+            }
+        }
+
+        "EIS socket closed".to_string()
+    }
+
+    /// Ask the portal for a new EIS connection, with backoff
+    async fn reconnect(&self) -> Result<EisConnection> {
+        let mut delay = RECONNECT_DELAY;
+        let mut last_error = None;
+
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+
+            let session = self.portal_session.read().await;
+            match connect_eis(&self.remote_desktop, &session).await {
+                Ok(eis) => return Ok(eis),
                 Err(e) => {
-                    error!("❌ libei: Event stream error: {}", e);
-                    return Err(e.into());
+                    warn!(
+                        "⚠️  libei: Reconnect attempt {}/{} failed: {:#}",
+                        attempt, RECONNECT_ATTEMPTS, e
+                    );
+                    last_error = Some(e);
                 }
-            };
+            }
+        }
 
-            self.handle_event(event).await?;
+        Err(last_error
+            .unwrap_or_else(|| anyhow!("no attempts made"))
+            .context("EIS reconnection failed (portal backend gone?)"))
+    }
+
+    /// Forget seats and devices of a dead connection
+    async fn reset_devices(&self) {
+        self.seats.lock().await.clear();
+        self.devices.lock().await.clear();
+        *self.keyboard_device.lock().await = None;
+        *self.pointer_device.lock().await = None;
+        *self.keymap.lock().await = None;
+    }
+
+    /// Publish the input state, logging transitions
+    fn set_status(&self, status: InputStatus) {
+        self.status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+            if status == InputStatus::Ready {
+                let dropped = self.dropped_events.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    info!(
+                        "libei: {} input event(s) dropped while input was suspended",
+                        dropped
+                    );
+                }
+            }
+            *current = status;
+            true
+        });
+    }
+
+    /// Re-derive the input state after a device paused or resumed
+    async fn update_status_from_devices(&self) {
+        let resumed = self.devices.lock().await.values().any(|d| d.resumed);
+        if resumed {
+            self.set_status(InputStatus::Ready);
+        } else {
+            self.set_status(InputStatus::Suspended(
+                "devices paused by the compositor".to_string(),
+            ));
         }
+    }
+
+    /// Device in `slot` and its `T` interface, or None while input is suspended
+    ///
+    /// Events for a missing or paused device are dropped rather than failed:
+    /// the condition is reported once through the input status instead of
+    /// once per event.
+    async fn active_interface<T: reis::Interface>(
+        &self,
+        slot: &Mutex<Option<ei::Device>>,
+    ) -> Result<Option<(ei::Device, T)>> {
+        let Some(device) = slot.lock().await.clone() else {
+            self.dropped_events.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        };
 
-        info!("🔌 libei: Event loop terminated");
+        let devices = self.devices.lock().await;
+        let Some(data) = devices.get(&device).filter(|d| d.resumed) else {
+            self.dropped_events.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        };
+
+        let interface = data
+            .interface::<T>()
+            .ok_or_else(|| anyhow!("{} interface not found on device", T::NAME))?;
+
+        Ok(Some((device, interface)))
+    }
+
+    /// Frame the events just sent on `device` and flush them
+    async fn frame_and_flush(&self, device: &ei::Device) -> Result<()> {
+        let serial = self.current_serial().await;
+        let time = Self::current_time_us();
+        device.frame(serial, time);
+        self.context.lock().await.flush()?;
         Ok(())
     }
 
@@ -307,7 +513,7 @@ impl LibeiSessionHandleImpl {
                 }
                 ei::connection::Event::Ping { ping } => {
                     ping.done(0);
-                    let _ = self.context.flush();
+                    let _ = self.context.lock().await.flush();
                 }
                 _ => {}
             },
@@ -332,7 +538,7 @@ impl LibeiSessionHandleImpl {
                         let connection = self.connection.lock().await;
                         connection.sync(1);
                         drop(connection);
-                        let _ = self.context.flush();
+                        let _ = self.context.lock().await.flush();
 
                         info!(
                             "✅ libei: Seat '{}' ready with capabilities: {:?}",
@@ -409,15 +615,62 @@ impl LibeiSessionHandleImpl {
                         );
                     }
                     ei::device::Event::Resumed { serial } => {
+                        data.resumed = true;
+                        drop(devices);
                         *self.last_serial.lock().await = serial;
                         debug!("[libei] Device resumed with serial: {}", serial);
+                        self.update_status_from_devices().await;
+                    }
+                    ei::device::Event::Paused { serial } => {
+                        data.resumed = false;
+                        drop(devices);
+                        *self.last_serial.lock().await = serial;
+                        debug!("[libei] Device paused with serial: {}", serial);
+                        self.update_status_from_devices().await;
                     }
                     _ => {}
                 }
             }
 
+            ei::Event::Keyboard(_keyboard, request) => match request {
+                ei::keyboard::Event::Keymap {
+                    keymap_type,
+                    size,
+                    keymap,
+                } => {
+                    if keymap_type != ei::keyboard::KeymapType::Xkb {
+                        warn!("⚠️  libei: Unsupported keymap type {:?}", keymap_type);
+                    } else {
+                        match EisKeymap::from_fd(keymap, size) {
+                            Ok(parsed) => {
+                                *self.keymap.lock().await = Some(parsed);
+                                info!("✅ libei: Keyboard keymap received ({} bytes)", size);
+                            }
+                            Err(e) => warn!("⚠️  libei: Ignoring keyboard keymap: {:#}", e),
+                        }
+                    }
+                }
+                ei::keyboard::Event::Modifiers {
+                    serial,
+                    depressed,
+                    locked,
+                    latched,
+                    group,
+                } => {
+                    *self.last_serial.lock().await = serial;
+                    if let Some(keymap) = self.keymap.lock().await.as_mut() {
+                        keymap.set_locked_modifiers(locked);
+                    }
+                    debug!(
+                        "[libei] Modifiers: depressed={:#x}, latched={:#x}, locked={:#x}, group={}",
+                        depressed, latched, locked, group
+                    );
+                }
+                _ => {}
+            },
+
             _ => {
-                // Ignore other events (keyboard keymap, etc.)
+                // Ignore other events
             }
         }
 
@@ -461,29 +714,31 @@ impl SessionHandle for LibeiSessionHandleImpl {
     }
 
     async fn notify_keyboard_keycode(&self, keycode: i32, pressed: bool) -> Result<()> {
-        // Get keyboard device
-        let kbd_device_opt = {
-            let kbd = self.keyboard_device.lock().await;
-            kbd.clone()
+        let Some((device, keyboard)) = self
+            .active_interface::<ei::Keyboard>(&self.keyboard_device)
+            .await?
+        else {
+            debug!("[libei] Keyboard suspended, dropping keycode {}", keycode);
+            return Ok(());
         };
 
-        let device = kbd_device_opt.ok_or_else(|| anyhow!("Keyboard device not yet available"))?;
-
-        // Get device data to access keyboard interface
-        let devices = self.devices.lock().await;
-        let device_data = devices
-            .get(&device)
-            .ok_or_else(|| anyhow!("Keyboard device data not found"))?;
-
-        let keyboard = device_data
-            .interface::<ei::Keyboard>()
-            .ok_or_else(|| anyhow!("Keyboard interface not found on device"))?;
-
-        drop(devices);
+        // EIS key events take evdev keycodes, the same codes the input
+        // handler produces - the compositor's keymap does the layout
+        let eis_keycode = keycode as u32;
+        {
+            let mut keymap = self.keymap.lock().await;
+            if let Some(keymap) = keymap.as_mut() {
+                if !keymap.has_key(eis_keycode) {
+                    debug!(
+                        "[libei] Dropping keycode {} - not in the device keymap",
+                        keycode
+                    );
+                    return Ok(());
+                }
+                keymap.update_key(eis_keycode, pressed);
+            }
+        }
 
-        // Send key event
-        // Note: EIS keycodes are offset by 8 from evdev (Linux kernel offset)
-        let eis_keycode = (keycode - 8) as u32;
         let state = if pressed {
             ei::keyboard::KeyState::Press
         } else {
@@ -491,54 +746,50 @@ impl SessionHandle for LibeiSessionHandleImpl {
         };
 
         keyboard.key(eis_keycode, state);
-
-        // Frame the event
-        let serial = self.current_serial().await;
-        let time = Self::current_time_us();
-        device.frame(serial, time);
-
-        // Flush to send
-        self.context.flush()?;
+        self.frame_and_flush(&device).await?;
 
         debug!(
-            "[libei] Keyboard event: keycode={} (eis={}), pressed={}",
-            keycode, eis_keycode, pressed
+            "[libei] Keyboard event: keycode={}, pressed={}",
+            keycode, pressed
         );
 
         Ok(())
     }
 
-    async fn notify_pointer_motion_absolute(&self, stream_id: u32, x: f64, y: f64) -> Result<()> {
-        // Get pointer device
-        let ptr_device_opt = {
-            let ptr = self.pointer_device.lock().await;
-            ptr.clone()
+    async fn sync_lock_keys(&self, locks: LockKeys) -> Result<()> {
+        // Without a keymap we don't know the compositor's lock state
+        let toggles: Vec<u32> = {
+            let keymap = self.keymap.lock().await;
+            let Some(keymap) = keymap.as_ref() else {
+                return Ok(());
+            };
+            LockKey::ALL
+                .into_iter()
+                .filter(|&lock| keymap.lock_active(lock) != lock.is_on(locks))
+                .map(LockKey::evdev_keycode)
+                .collect()
         };
 
-        let device = ptr_device_opt.ok_or_else(|| anyhow!("Pointer device not yet available"))?;
-
-        // Get device data to access pointer interface
-        let devices = self.devices.lock().await;
-        let device_data = devices
-            .get(&device)
-            .ok_or_else(|| anyhow!("Pointer device data not found"))?;
+        for keycode in toggles {
+            debug!("[libei] Toggling lock key {} to match client", keycode);
+            self.notify_keyboard_keycode(keycode as i32, true).await?;
+            self.notify_keyboard_keycode(keycode as i32, false).await?;
+        }
 
-        let pointer_abs = device_data
-            .interface::<ei::PointerAbsolute>()
-            .ok_or_else(|| anyhow!("PointerAbsolute interface not found on device"))?;
+        Ok(())
+    }
 
-        drop(devices);
+    async fn notify_pointer_motion_absolute(&self, stream_id: u32, x: f64, y: f64) -> Result<()> {
+        let Some((device, pointer_abs)) = self
+            .active_interface::<ei::PointerAbsolute>(&self.pointer_device)
+            .await?
+        else {
+            return Ok(());
+        };
 
         // Send motion event (x, y in logical pixels as f32)
         pointer_abs.motion_absolute(x as f32, y as f32);
-
-        // Frame the event
-        let serial = self.current_serial().await;
-        let time = Self::current_time_us();
-        device.frame(serial, time);
-
-        // Flush to send
-        self.context.flush()?;
+        self.frame_and_flush(&device).await?;
 
         debug!(
             "[libei] Pointer motion: stream={}, x={}, y={}",
@@ -549,26 +800,14 @@ impl SessionHandle for LibeiSessionHandleImpl {
     }
 
     async fn notify_pointer_button(&self, button: i32, pressed: bool) -> Result<()> {
-        // Get pointer device
-        let ptr_device_opt = {
-            let ptr = self.pointer_device.lock().await;
-            ptr.clone()
+        let Some((device, button_interface)) = self
+            .active_interface::<ei::Button>(&self.pointer_device)
+            .await?
+        else {
+            debug!("[libei] Pointer suspended, dropping button {}", button);
+            return Ok(());
         };
 
-        let device = ptr_device_opt.ok_or_else(|| anyhow!("Pointer device not yet available"))?;
-
-        // Get device data to access button interface
-        let devices = self.devices.lock().await;
-        let device_data = devices
-            .get(&device)
-            .ok_or_else(|| anyhow!("Pointer device data not found"))?;
-
-        let button_interface = device_data
-            .interface::<ei::Button>()
-            .ok_or_else(|| anyhow!("Button interface not found on device"))?;
-
-        drop(devices);
-
         // Send button event
         button_interface.button(
             button as u32,
@@ -578,14 +817,7 @@ impl SessionHandle for LibeiSessionHandleImpl {
                 ei::button::ButtonState::Released
             },
         );
-
-        // Frame the event
-        let serial = self.current_serial().await;
-        let time = Self::current_time_us();
-        device.frame(serial, time);
-
-        // Flush to send
-        self.context.flush()?;
+        self.frame_and_flush(&device).await?;
 
         debug!(
             "[libei] Pointer button: button={}, pressed={}",
@@ -596,26 +828,13 @@ impl SessionHandle for LibeiSessionHandleImpl {
     }
 
     async fn notify_pointer_axis(&self, dx: f64, dy: f64) -> Result<()> {
-        // Get pointer device
-        let ptr_device_opt = {
-            let ptr = self.pointer_device.lock().await;
-            ptr.clone()
+        let Some((device, scroll)) = self
+            .active_interface::<ei::Scroll>(&self.pointer_device)
+            .await?
+        else {
+            return Ok(());
         };
 
-        let device = ptr_device_opt.ok_or_else(|| anyhow!("Pointer device not yet available"))?;
-
-        // Get device data to access scroll interface
-        let devices = self.devices.lock().await;
-        let device_data = devices
-            .get(&device)
-            .ok_or_else(|| anyhow!("Pointer device data not found"))?;
-
-        let scroll = device_data
-            .interface::<ei::Scroll>()
-            .ok_or_else(|| anyhow!("Scroll interface not found on device"))?;
-
-        drop(devices);
-
         // Send scroll events (convert f64 to f32 for reis API)
         if dx.abs() > 0.01 {
             scroll.scroll(dx as f32, 0.0);
@@ -623,20 +842,17 @@ impl SessionHandle for LibeiSessionHandleImpl {
         if dy.abs() > 0.01 {
             scroll.scroll(0.0, dy as f32);
         }
-
-        // Frame the event
-        let serial = self.current_serial().await;
-        let time = Self::current_time_us();
-        device.frame(serial, time);
-
-        // Flush to send
-        self.context.flush()?;
+        self.frame_and_flush(&device).await?;
 
         debug!("[libei] Pointer axis: dx={}, dy={}", dx, dy);
 
         Ok(())
     }
 
+    fn input_status(&self) -> Option<watch::Receiver<InputStatus>> {
+        Some(self.status.subscribe())
+    }
+
     fn portal_clipboard(&self) -> Option<ClipboardComponents> {
         // libei can share the Portal session for clipboard
         // The session is managed separately from input devices
@@ -72,10 +72,17 @@ impl SessionHandle for MutterSessionHandleImpl {
 
         // Mutter needs the stream object path, not just the node ID
         // Find the stream path that corresponds to this node ID
+        let index = self
+            .mutter_handle
+            .stream_info
+            .iter()
+            .position(|s| s.node_id == stream_id)
+            .unwrap_or(0);
         let stream_path = self
             .mutter_handle
             .streams
-            .first()
+            .get(index)
+            .or_else(|| self.mutter_handle.streams.first())
             .ok_or_else(|| anyhow!("No streams available"))?;
 
         rd_session
@@ -8,6 +8,7 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
+use crate::compositor::{PortalCapabilities, PortalFeature};
 use crate::portal::PortalManager;
 use crate::services::ServiceRegistry;
 use crate::session::strategy::{
@@ -181,12 +182,18 @@ impl SessionStrategy for PortalTokenStrategy {
     async fn create_session(&self) -> Result<Arc<dyn SessionHandle>> {
         info!("Creating session using Portal + Token strategy");
 
+        let portal_caps = &self.service_registry.compositor_capabilities().portal;
+        let restore_tokens = portal_caps.supports(PortalFeature::RestoreTokens);
+
         // Load existing token (may be None on first run)
-        let restore_token = self
-            .token_manager
-            .load_token("default")
-            .await
-            .context("Failed to load restore token")?;
+        let restore_token = if restore_tokens {
+            self.token_manager
+                .load_token("default")
+                .await
+                .context("Failed to load restore token")?
+        } else {
+            None
+        };
 
         if let Some(ref token) = restore_token {
             info!(
@@ -201,9 +208,17 @@ impl SessionStrategy for PortalTokenStrategy {
         let mut portal_config = lamco_portal::PortalConfig::default();
         portal_config.restore_token = restore_token.clone();
 
-        // Some portals reject persistence for RemoteDesktop sessions
+        // Portals before ScreenCast v4 don't know persist_mode at all
+        if !restore_tokens {
+            info!(
+                "Portal ScreenCast v{} has no restore tokens, not requesting persistence",
+                portal_caps.version
+            );
+            portal_config.persist_mode = ashpd::desktop::PersistMode::DoNot;
+        }
+
+        // Some portals still reject persistence for RemoteDesktop sessions
         // Start with ExplicitlyRevoked (default), but if that fails, we'll retry with DoNot
-        // persist_mode is already ExplicitlyRevoked in default
 
         debug!(
             "Portal config: persist_mode={:?}, has_token={}",
@@ -252,18 +267,7 @@ impl SessionStrategy for PortalTokenStrategy {
                                 .context("Failed to create Portal manager without persistence")?,
                         );
 
-                        // Create clipboard manager only if supported (Portal RemoteDesktop v2+)
-                        // On Portal v1 (RHEL 9), clipboard isn't available - skip it
-                        let clipboard_mgr = match lamco_portal::ClipboardManager::new().await {
-                            Ok(mgr) => {
-                                info!("Clipboard manager created for non-persistent session");
-                                Some(Arc::new(mgr))
-                            }
-                            Err(e) => {
-                                warn!("Clipboard not available (Portal v1?): {}", e);
-                                None
-                            }
-                        };
+                        let clipboard_mgr = create_clipboard_manager(portal_caps).await;
 
                         let result = no_persist_manager
                             .create_session(
@@ -291,8 +295,8 @@ impl SessionStrategy for PortalTokenStrategy {
             info!("✅ New restore token saved successfully");
         } else if restore_token.is_some() {
             info!("No new token returned (existing token may have been used successfully)");
-        } else {
-            warn!("⚠️  Portal did not return restore token (portal v3 or below?)");
+        } else if restore_tokens {
+            warn!("⚠️  Portal did not return restore token");
         }
 
         // Extract fields from portal_handle
@@ -312,23 +316,13 @@ impl SessionStrategy for PortalTokenStrategy {
         // Move session out of portal_handle
         let session = portal_handle.session;
 
-        // Use the clipboard manager from non-persistent retry if it exists,
-        // otherwise try to create one (may fail on Portal v1)
-        let clipboard_manager = if let Some(clipboard_mgr) = pre_created_clipboard_mgr {
-            info!("Using clipboard manager from non-persistent session retry");
-            Some(clipboard_mgr)
-        } else {
-            // Try to create clipboard manager - will fail on Portal v1 (no clipboard support)
-            match lamco_portal::ClipboardManager::new().await {
-                Ok(mgr) => {
-                    info!("Portal clipboard manager created for session");
-                    Some(Arc::new(mgr))
-                }
-                Err(e) => {
-                    warn!("Clipboard not available on this Portal version: {}", e);
-                    None
-                }
+        // Use the clipboard manager from non-persistent retry if it exists
+        let clipboard_manager = match pre_created_clipboard_mgr {
+            Some(clipboard_mgr) => {
+                info!("Using clipboard manager from non-persistent session retry");
+                Some(clipboard_mgr)
             }
+            None => create_clipboard_manager(portal_caps).await,
         };
 
         // Wrap in our handle type with input injection and clipboard support
@@ -353,6 +347,36 @@ impl SessionStrategy for PortalTokenStrategy {
     }
 }
 
+/// Create the portal clipboard manager if the capability matrix has Clipboard
+///
+/// Portals without it (RemoteDesktop v1 on RHEL 9, no Clipboard interface
+/// before xdg-desktop-portal 1.18) run the session without clipboard.
+async fn create_clipboard_manager(
+    portal_caps: &PortalCapabilities,
+) -> Option<Arc<lamco_portal::ClipboardManager>> {
+    if !portal_caps.supports(PortalFeature::Clipboard) {
+        info!(
+            "Portal has no clipboard (RemoteDesktop v{}, Clipboard v{}), skipping",
+            portal_caps.remote_desktop_version, portal_caps.clipboard_version
+        );
+        return None;
+    }
+
+    match lamco_portal::ClipboardManager::new().await {
+        Ok(mgr) => {
+            info!("Portal clipboard manager created for session");
+            Some(Arc::new(mgr))
+        }
+        Err(e) => {
+            warn!(
+                "Portal advertises clipboard, but manager creation failed: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,15 +5,18 @@
 //!
 //! Priority:
 //! 1. Mutter Direct API (GNOME, zero dialogs)
-//! 2. wlr-direct (wlroots native, zero dialogs)
-//! 3. libei/EIS (wlroots via Portal, Flatpak-compatible)
-//! 4. Portal + Token (universal, one-time dialog)
-//! 5. Basic Portal (fallback, dialog each time)
+//! 2. KWin ScreenCast (KDE Plasma, zero dialogs for video)
+//! 3. wlr-direct (wlroots native, zero dialogs, screencopy video), with
+//!    Hyprland's IPC on Hyprland
+//! 4. libei/EIS (wlroots via Portal, Flatpak-compatible)
+//! 5. Portal + Token (universal, one-time dialog)
+//! 6. Basic Portal (fallback, dialog each time)
 
 use anyhow::Result;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
+use crate::config::types::{KwinConfig, MutterConfig, WlrConfig};
 use crate::services::{ServiceId, ServiceLevel, ServiceRegistry};
 use crate::session::strategy::SessionStrategy;
 use crate::session::TokenManager;
@@ -31,6 +34,9 @@ use super::portal_token::PortalTokenStrategy;
 pub struct SessionStrategySelector {
     service_registry: Arc<ServiceRegistry>,
     token_manager: Arc<TokenManager>,
+    mutter: MutterConfig,
+    kwin: KwinConfig,
+    wlr: WlrConfig,
 }
 
 impl SessionStrategySelector {
@@ -44,16 +50,39 @@ impl SessionStrategySelector {
         Self {
             service_registry,
             token_manager,
+            mutter: MutterConfig::default(),
+            kwin: KwinConfig::default(),
+            wlr: WlrConfig::default(),
         }
     }
 
+    /// Apply `[mutter]`: whether to use the Mutter API and which monitor
+    pub fn with_mutter_config(mut self, mutter: MutterConfig) -> Self {
+        self.mutter = mutter;
+        self
+    }
+
+    /// Apply `[kwin]`: whether to use KWin's screencast protocol and how
+    pub fn with_kwin_config(mut self, kwin: KwinConfig) -> Self {
+        self.kwin = kwin;
+        self
+    }
+
+    /// Apply `[wlr]`: whether wlr-direct captures video with screencopy and
+    /// uses Hyprland's IPC
+    pub fn with_wlr_config(mut self, wlr: WlrConfig) -> Self {
+        self.wlr = wlr;
+        self
+    }
+
     /// Select the best available session strategy
     ///
     /// Returns a boxed SessionStrategy implementation based on detected capabilities.
     ///
     /// Priority order:
     /// 1. Mutter Direct API (GNOME only, zero dialogs)
-    /// 2. Portal + Token (universal, one-time dialog)
+    /// 2. KWin ScreenCast (KDE Plasma, zero dialogs for video)
+    /// 3. Portal + Token (universal, one-time dialog)
     /// 3. Basic Portal (fallback, dialog each time - NOT IMPLEMENTED)
     pub async fn select_strategy(&self) -> Result<Box<dyn SessionStrategy>> {
         info!("Selecting session creation strategy...");
@@ -110,7 +139,9 @@ impl SessionStrategySelector {
         }
 
         // PRIORITY 1: Mutter Direct API (GNOME only, zero dialogs ever)
-        if self
+        if !self.mutter.enabled {
+            info!("Mutter Direct API disabled by configuration");
+        } else if self
             .service_registry
             .service_level(ServiceId::DirectCompositorAPI)
             >= ServiceLevel::BestEffort
@@ -120,8 +151,8 @@ impl SessionStrategySelector {
                 info!("✅ Selected: Mutter Direct API strategy");
                 info!("   Zero permission dialogs (not even first time)");
 
-                // Check if we should use physical monitor or virtual
-                let monitor_connector = self.detect_primary_monitor().await;
+                // Physical monitor or virtual, as configured
+                let monitor_connector = self.mutter_monitor().await;
 
                 return Ok(Box::new(MutterDirectStrategy::new(monitor_connector)));
             } else {
@@ -130,51 +161,91 @@ impl SessionStrategySelector {
             }
         }
 
-        // PRIORITY 2: wlr-direct (wlroots compositors, native protocols)
+        // PRIORITY 2: KWin ScreenCast (KDE Plasma, video without a dialog)
+        #[cfg(feature = "wayland")]
+        if self.kwin.enabled && caps.has_kde_screencast() {
+            use super::kwin_screencast::KwinScreencastStrategy;
+
+            // Verify KWin still offers the protocol to us
+            if KwinScreencastStrategy::is_available().await {
+                let virtual_output = self.kwin.virtual_output_size().unwrap_or_else(|e| {
+                    warn!("{}, streaming physical outputs", e);
+                    None
+                });
+                info!("✅ Selected: KWin ScreenCast strategy");
+                info!("   No permission dialog for video");
+                info!("   Note: Video only (input via Portal RemoteDesktop)");
+
+                return Ok(Box::new(KwinScreencastStrategy::new(virtual_output)));
+            } else {
+                warn!("Compositor advertises zkde_screencast_unstable_v1, but binding failed");
+                warn!("Falling back to next available strategy");
+            }
+        }
+
+        // PRIORITY 3: wlr-direct (wlroots compositors, native protocols)
         #[cfg(feature = "wayland")]
         if self
             .service_registry
             .service_level(ServiceId::WlrDirectInput)
             >= ServiceLevel::BestEffort
         {
+            use super::hyprland::HyprlandStrategy;
             use super::wlr_direct::WlrDirectStrategy;
 
+            let screencopy = self.wlr.screencopy && self.service_registry.has_wlr_screencopy();
+
+            // Hyprland: output layout and focus from its IPC socket
+            if self.wlr.hyprland_ipc && HyprlandStrategy::is_available().await {
+                info!("✅ Selected: Hyprland strategy");
+                info!("   wlr-direct protocols with Hyprland IPC for outputs and focus");
+                if !screencopy {
+                    info!("   Note: Input only (video via Portal ScreenCast)");
+                }
+
+                return Ok(Box::new(
+                    HyprlandStrategy::new().with_screencopy(screencopy),
+                ));
+            }
+
             // Verify protocols are actually accessible
             if WlrDirectStrategy::is_available().await {
                 info!("✅ Selected: wlr-direct strategy");
                 info!("   Native Wayland protocols for wlroots compositors");
                 info!("   Compositor: {}", caps.compositor);
-                info!("   Note: Input only (video via Portal ScreenCast)");
+                if screencopy {
+                    info!("   Video via wlr-screencopy (no portal)");
+                } else {
+                    info!("   Note: Input only (video via Portal ScreenCast)");
+                }
 
-                return Ok(Box::new(WlrDirectStrategy::new()));
+                return Ok(Box::new(
+                    WlrDirectStrategy::new().with_screencopy(screencopy),
+                ));
             } else {
                 warn!("Service Registry reports wlr-direct available, but protocol binding failed");
                 warn!("Falling back to next available strategy");
             }
         }
 
-        // PRIORITY 3: libei/EIS (wlroots via Portal RemoteDesktop, Flatpak-compatible)
+        // PRIORITY 4: libei/EIS (wlroots via Portal RemoteDesktop, Flatpak-compatible)
+        //
+        // The registry only advertises LibeiInput when the portal matrix
+        // reports ConnectToEIS, so there's nothing left to probe here.
         #[cfg(feature = "libei")]
         if self.service_registry.service_level(ServiceId::LibeiInput) >= ServiceLevel::BestEffort {
             use super::libei::LibeiStrategy;
 
-            // Verify Portal RemoteDesktop with ConnectToEIS is accessible
-            if LibeiStrategy::is_available().await {
-                info!("✅ Selected: libei strategy");
-                info!("   Portal RemoteDesktop + EIS protocol for wlroots");
-                info!("   Compositor: {}", caps.compositor);
-                info!("   Flatpak-compatible: Yes");
-                info!("   Note: Input only (video via Portal ScreenCast)");
+            info!("✅ Selected: libei strategy");
+            info!("   Portal RemoteDesktop + EIS protocol for wlroots");
+            info!("   Compositor: {}", caps.compositor);
+            info!("   Flatpak-compatible: Yes");
+            info!("   Note: Input only (video via Portal ScreenCast)");
 
-                return Ok(Box::new(LibeiStrategy::new(None)));
-            } else {
-                warn!("Service Registry reports libei available, but Portal ConnectToEIS failed");
-                warn!("Portal backend may not support ConnectToEIS method");
-                warn!("Falling back to Portal strategy");
-            }
+            return Ok(Box::new(LibeiStrategy::new(None)));
         }
 
-        // PRIORITY 4: Portal + Token (works on all DEs with portal v4+)
+        // PRIORITY 5: Portal + Token (works on all DEs with portal v4+)
         if self.service_registry.supports_session_persistence() {
             info!("✅ Selected: Portal + Token strategy");
             info!("   One-time permission dialog, then unattended operation");
@@ -187,7 +258,10 @@ impl SessionStrategySelector {
 
         // FALLBACK: Portal without tokens (portal v3 or below)
         warn!("⚠️  No session persistence available");
-        warn!("   Portal version: {}", caps.portal.version);
+        warn!(
+            "   Portal ScreenCast v{} (restore tokens need v4)",
+            caps.portal.version
+        );
         warn!("   Falling back to Portal + Token strategy");
         warn!("   Permission dialog will appear on every server start");
 
@@ -198,6 +272,21 @@ impl SessionStrategySelector {
         )))
     }
 
+    /// Monitor connector the Mutter strategy records (None = virtual)
+    async fn mutter_monitor(&self) -> Option<String> {
+        match self.mutter.monitor.trim() {
+            "auto" => self.detect_primary_monitor().await,
+            "virtual" => {
+                info!("Using virtual monitor (configured)");
+                None
+            }
+            connector => {
+                info!("Recording configured monitor: {}", connector);
+                Some(connector.to_string())
+            }
+        }
+    }
+
     /// Detect primary monitor connector for Mutter
     ///
     /// Returns Some(connector) if physical monitor detected, None for virtual
@@ -296,10 +385,24 @@ mod tests {
                 .expect("Failed to create TokenManager"),
         );
 
-        let selector = SessionStrategySelector::new(registry, token_manager);
+        let selector = SessionStrategySelector::new(registry.clone(), token_manager.clone());
 
         // Should not panic
         let _strategy_name = selector.recommended_strategy_name();
+
+        // Configured Mutter monitors bypass DRM detection
+        let selector = selector.with_mutter_config(MutterConfig {
+            enabled: true,
+            monitor: "virtual".to_string(),
+        });
+        assert_eq!(selector.mutter_monitor().await, None);
+        let selector = SessionStrategySelector::new(registry, token_manager).with_mutter_config(
+            MutterConfig {
+                enabled: true,
+                monitor: "DP-1".to_string(),
+            },
+        );
+        assert_eq!(selector.mutter_monitor().await.as_deref(), Some("DP-1"));
     }
 
     #[test]
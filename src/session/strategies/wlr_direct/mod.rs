@@ -10,6 +10,8 @@
 //!
 //! - `zwp_virtual_keyboard_v1` (virtual-keyboard-unstable-v1) - Standard keyboard protocol
 //! - `zwlr_virtual_pointer_v1` (wlr-virtual-pointer-unstable-v1) - wlroots pointer protocol
+//! - `zwlr_screencopy_manager_v1` (wlr-screencopy-unstable-v1) - video capture, when enabled
+//!   with `[wlr] screencopy` (see [`screencopy`])
 //!
 //! # Supported Compositors
 //!
@@ -30,18 +32,21 @@
 //!   │   └─> wl_seat (default seat)
 //!   └─> WlrSessionHandleImpl
 //!       ├─> VirtualKeyboard (XKB keymap + key injection)
-//!       └─> VirtualPointer (motion + button + scroll injection)
+//!       ├─> VirtualPointer (motion + button + scroll injection)
+//!       └─> Outputs as streams (captured per pipeline by ScreencopyCapture)
 //! ```
 //!
-//! # Limitations (MVP)
+//! With screencopy the session needs no portal at all.
+//!
+//! # Limitations
 //!
-//! - **Input injection only** (no video capture)
 //! - **No clipboard support** (use FUSE approach or separate Portal session)
 //! - **Not Flatpak-compatible** (requires direct Wayland socket access)
-//! - For video, this strategy would need integration with wlr-screencopy or Portal
+//! - Without screencopy the strategy provides input only
 
 mod keyboard;
 mod pointer;
+pub mod screencopy;
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -62,6 +67,7 @@ use pointer::{Axis, AxisSource, ButtonState, VirtualPointer};
 // Re-export for external use
 pub use keyboard::VirtualKeyboard as WlrVirtualKeyboard;
 pub use pointer::VirtualPointer as WlrVirtualPointer;
+pub use screencopy::ScreencopyCapture;
 
 /// State for Wayland protocol dispatch
 struct WlrState {
@@ -80,15 +86,60 @@ impl WlrState {
     }
 }
 
+/// Position of an output in the session's layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputPlacement {
+    /// Connector name ("DP-1")
+    pub connector: String,
+    pub position_x: i32,
+    pub position_y: i32,
+}
+
 /// wlr-direct strategy implementation
 ///
-/// Provides input injection via native Wayland protocols for wlroots compositors.
-pub struct WlrDirectStrategy;
+/// Provides input injection via native Wayland protocols for wlroots compositors,
+/// and video via wlr-screencopy when enabled.
+pub struct WlrDirectStrategy {
+    /// Capture video with wlr-screencopy
+    screencopy: bool,
+    /// Outputs to capture and where, from the compositor's own IPC
+    layout: Option<Vec<OutputPlacement>>,
+    /// Streams captured by another backend, for input-only sessions
+    input_streams: Vec<StreamInfo>,
+}
 
 impl WlrDirectStrategy {
-    /// Create a new wlr-direct strategy
+    /// Create a new wlr-direct strategy (input only)
     pub fn new() -> Self {
-        Self
+        Self {
+            screencopy: false,
+            layout: None,
+            input_streams: Vec::new(),
+        }
+    }
+
+    /// Capture video with wlr-screencopy as well
+    pub fn with_screencopy(mut self, enabled: bool) -> Self {
+        self.screencopy = enabled;
+        self
+    }
+
+    /// Capture only the outputs of `layout`, at its positions
+    ///
+    /// Outputs are matched by connector name, which needs wl_output
+    /// version 4; without any match every output is captured.
+    pub fn with_output_layout(mut self, layout: Vec<OutputPlacement>) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Scale absolute pointer motion to `streams` when input only
+    ///
+    /// For input taking over from another backend while the video keeps
+    /// coming from the original session.
+    pub fn with_input_streams(mut self, streams: Vec<StreamInfo>) -> Self {
+        self.input_streams = streams;
+        self
     }
 
     /// Check if wlr-direct protocols are available
@@ -161,13 +212,45 @@ impl SessionStrategy for WlrDirectStrategy {
 
         info!("✅ wlr_direct: Virtual keyboard and pointer created successfully");
 
+        // Outputs become streams; each pipeline captures them itself
+        let screencopy = self.screencopy && screencopy::is_available(&conn);
+        let streams = if screencopy {
+            let streams = match self.layout {
+                Some(ref layout) => place_outputs(
+                    screencopy::named_outputs(&conn)
+                        .context("Failed to enumerate outputs for screencopy")?,
+                    layout,
+                ),
+                None => screencopy::output_streams(&conn)
+                    .context("Failed to enumerate outputs for screencopy")?,
+            };
+            for (idx, stream) in streams.iter().enumerate() {
+                info!(
+                    "  Output {}: {}x{} at ({}, {}), wl_output: {}",
+                    idx,
+                    stream.width,
+                    stream.height,
+                    stream.position_x,
+                    stream.position_y,
+                    stream.node_id
+                );
+            }
+            streams
+        } else {
+            if self.screencopy {
+                warn!("⚠️  wlr_direct: Compositor does not offer wlr-screencopy, input only");
+            }
+            self.input_streams.clone()
+        };
+
         // Create session handle
         let handle = WlrSessionHandleImpl {
             connection: conn,
             event_queue: Mutex::new(event_queue),
             keyboard,
             pointer,
-            streams: vec![], // Populated by video capture strategy
+            streams,
+            screencopy,
         };
 
         Ok(Arc::new(handle))
@@ -180,6 +263,31 @@ impl SessionStrategy for WlrDirectStrategy {
     }
 }
 
+/// Outputs of `layout` at its positions, or all of them if none matches
+fn place_outputs(
+    outputs: Vec<(String, StreamInfo)>,
+    layout: &[OutputPlacement],
+) -> Vec<StreamInfo> {
+    let placed: Vec<StreamInfo> = layout
+        .iter()
+        .filter_map(|placement| {
+            outputs
+                .iter()
+                .find(|(connector, _)| *connector == placement.connector)
+                .map(|(_, stream)| StreamInfo {
+                    position_x: placement.position_x,
+                    position_y: placement.position_y,
+                    ..stream.clone()
+                })
+        })
+        .collect();
+    if placed.is_empty() {
+        warn!("⚠️  wlr_direct: No output matches the compositor's layout, capturing all outputs");
+        return outputs.into_iter().map(|(_, stream)| stream).collect();
+    }
+    placed
+}
+
 /// wlr-direct session handle implementation
 ///
 /// Implements the SessionHandle trait for wlroots direct protocol access.
@@ -198,6 +306,8 @@ pub struct WlrSessionHandleImpl {
     keyboard: VirtualKeyboard,
     pointer: VirtualPointer,
     streams: Vec<StreamInfo>,
+    /// Video is captured with wlr-screencopy
+    screencopy: bool,
 }
 
 impl WlrSessionHandleImpl {
@@ -243,8 +353,10 @@ impl WlrSessionHandleImpl {
 #[async_trait]
 impl SessionHandle for WlrSessionHandleImpl {
     fn pipewire_access(&self) -> PipeWireAccess {
-        // wlr-direct does not provide video capture (input only)
-        // Video would come from a separate strategy (Portal or wlr-screencopy)
+        if self.screencopy {
+            return PipeWireAccess::Screencopy;
+        }
+        // Without screencopy wlr-direct does not provide video capture (input only)
         warn!(
             "⚠️  wlr_direct: pipewire_access() called but this strategy provides input only. \
              Video capture requires Portal ScreenCast or wlr-screencopy."
@@ -273,11 +385,8 @@ impl SessionHandle for WlrSessionHandleImpl {
     }
 
     async fn notify_pointer_motion_absolute(&self, stream_id: u32, x: f64, y: f64) -> Result<()> {
-        // For MVP with input-only support, we don't have stream info from video capture
-        // Use default screen dimensions or accept that motion may not work without video
-        //
-        // In a full implementation, this would be populated by the video capture strategy
-        // For now, use a sensible default or the first stream if available
+        // Streams come from the outputs captured with screencopy or from the
+        // backend input took over from; without any, use a sensible default
 
         let (x_extent, y_extent) = if self.streams.is_empty() {
             // No video streams - use common default dimensions
@@ -559,6 +668,37 @@ mod tests {
         assert_eq!(session.session_type(), SessionType::WlrDirect);
     }
 
+    #[test]
+    fn test_place_outputs() {
+        let output = |node_id, position_x| StreamInfo {
+            node_id,
+            width: 1920,
+            height: 1080,
+            position_x,
+            position_y: 0,
+        };
+        let outputs = vec![
+            ("DP-1".to_string(), output(40, 0)),
+            ("HDMI-A-1".to_string(), output(41, 1920)),
+        ];
+        let layout = vec![OutputPlacement {
+            connector: "HDMI-A-1".to_string(),
+            position_x: 0,
+            position_y: 0,
+        }];
+
+        let streams = place_outputs(outputs.clone(), &layout);
+        assert_eq!(streams.len(), 1);
+        assert_eq!((streams[0].node_id, streams[0].position_x), (41, 0));
+
+        // Unnamed outputs (wl_output < 4) are all captured
+        let unnamed = outputs
+            .into_iter()
+            .map(|(_, stream)| (String::new(), stream))
+            .collect();
+        assert_eq!(place_outputs(unnamed, &layout).len(), 2);
+    }
+
     #[test]
     fn test_current_time_millis() {
         let time = current_time_millis();
@@ -0,0 +1,793 @@
+//! wlr-screencopy Video Capture
+//!
+//! Copies output contents with `zwlr_screencopy_manager_v1` into shared
+//! memory and hands them to the display pipeline as [`VideoFrame`]s, the same
+//! frames the PipeWire thread produces. Together with the virtual keyboard
+//! and pointer this lets wlroots compositors run without any portal.
+//!
+//! # Capture Loop
+//!
+//! Each pipeline owns a capture thread with its own Wayland connection. Every
+//! output has at most one frame in flight; a new one is requested once the
+//! previous frame is ready and the frame interval has passed. From protocol
+//! version 2 on, frames are copied with damage: the compositor holds the copy
+//! until the output changes, so a static desktop costs nothing.
+//!
+//! Frames are always requested as wl_shm buffers. Version 3 compositors offer
+//! linux-dmabuf buffers as well, but those would have to be read back from the
+//! GPU before encoding, which is what the compositor does for shm anyway.
+//!
+//! Screencopy has no cursor metadata, so the compositor paints the cursor
+//! into the frames.
+
+use anyhow::{anyhow, bail, Context, Result};
+use memmap2::MmapMut;
+use std::ffi::CString;
+use std::os::fd::{AsFd, AsRawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool};
+use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+use crate::pipewire::{PixelFormat, VideoFrame};
+use crate::session::strategy::StreamInfo;
+
+/// Wayland interface name of the wlr screencopy protocol
+pub const SCREENCOPY_INTERFACE: &str = "zwlr_screencopy_manager_v1";
+
+/// Longest wait for compositor events before checking for a stop request
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Consecutive failed copies of an output before capture gives up
+const MAX_FAILURES: u32 = 30;
+
+/// Check whether the compositor offers screencopy and shared memory buffers
+pub fn is_available(conn: &Connection) -> bool {
+    match registry_queue_init::<OutputProbe>(conn) {
+        Ok((globals, _queue)) => globals.contents().with_list(|list| {
+            let has = |interface: &str| list.iter().any(|global| global.interface == interface);
+            has(SCREENCOPY_INTERFACE) && has("wl_shm")
+        }),
+        Err(e) => {
+            debug!("[screencopy] Registry enumeration failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Outputs to capture, as streams
+///
+/// The node ID of each stream is the output's wl_output global name, which
+/// is the same for every client of the compositor.
+pub fn output_streams(conn: &Connection) -> Result<Vec<StreamInfo>> {
+    Ok(named_outputs(conn)?
+        .into_iter()
+        .map(|(_, stream)| stream)
+        .collect())
+}
+
+/// Outputs with their connector names ("DP-1"), as streams
+///
+/// Names come with wl_output version 4; older compositors leave them empty.
+pub fn named_outputs(conn: &Connection) -> Result<Vec<(String, StreamInfo)>> {
+    let (globals, mut event_queue) = registry_queue_init::<OutputProbe>(conn)
+        .context("Failed to initialize Wayland registry")?;
+    let qh = event_queue.handle();
+
+    let names: Vec<u32> = globals.contents().with_list(|list| {
+        list.iter()
+            .filter(|global| global.interface == "wl_output")
+            .enumerate()
+            .map(|(index, global)| {
+                let _: wl_output::WlOutput =
+                    globals
+                        .registry()
+                        .bind(global.name, global.version.min(4), &qh, index);
+                global.name
+            })
+            .collect()
+    });
+    if names.is_empty() {
+        bail!("Compositor has no outputs");
+    }
+
+    let mut probe = OutputProbe {
+        outputs: vec![StreamInfo::default(); names.len()],
+        connectors: vec![String::new(); names.len()],
+    };
+    event_queue
+        .roundtrip(&mut probe)
+        .context("Failed to receive output information")?;
+
+    Ok(names
+        .into_iter()
+        .zip(probe.connectors.into_iter().zip(probe.outputs))
+        .map(|(name, (connector, output))| {
+            (
+                connector,
+                StreamInfo {
+                    node_id: name,
+                    ..output
+                },
+            )
+        })
+        .collect())
+}
+
+/// Latest frame of each output, waiting for the display pipeline
+///
+/// A new frame replaces an unconsumed one, so a slow pipeline encodes the
+/// current contents instead of working through a backlog.
+struct FrameSlots {
+    slots: Vec<Option<VideoFrame>>,
+    /// Output to look at first, so that every output gets its turn
+    next: usize,
+}
+
+impl FrameSlots {
+    fn put(&mut self, index: usize, frame: VideoFrame) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            *slot = Some(frame);
+        }
+    }
+
+    fn take(&mut self) -> Option<VideoFrame> {
+        let count = self.slots.len();
+        for offset in 0..count {
+            let index = (self.next + offset) % count;
+            if let Some(frame) = self.slots[index].take() {
+                self.next = (index + 1) % count;
+                return Some(frame);
+            }
+        }
+        None
+    }
+}
+
+/// Output capture running on its own thread
+///
+/// Capture stops when this is dropped.
+pub struct ScreencopyCapture {
+    frames: Arc<Mutex<FrameSlots>>,
+    stop: Arc<AtomicBool>,
+    _thread: JoinHandle<()>,
+}
+
+impl ScreencopyCapture {
+    /// Start capturing outputs
+    ///
+    /// # Arguments
+    ///
+    /// * `outputs` - wl_output global names (stream node IDs) to capture
+    /// * `max_fps` - Frames per second to capture at most, per output
+    pub fn start(outputs: &[u32], max_fps: u32) -> Result<Self> {
+        let conn = Connection::connect_to_env()
+            .context("Failed to connect to Wayland display. Ensure WAYLAND_DISPLAY is set.")?;
+        let (globals, event_queue) = registry_queue_init::<CaptureState>(&conn)
+            .context("Failed to initialize Wayland registry")?;
+        let qh = event_queue.handle();
+
+        let manager: ZwlrScreencopyManagerV1 = globals.bind(&qh, 1..=3, ()).context(
+            "Failed to bind zwlr_screencopy_manager_v1. \
+             Compositor does not support wlr-screencopy.",
+        )?;
+        let shm: wl_shm::WlShm = globals
+            .bind(&qh, 1..=1, ())
+            .context("Failed to bind wl_shm")?;
+
+        let now = Instant::now();
+        let captures = outputs
+            .iter()
+            .map(|&name| {
+                let version = globals.contents().with_list(|list| {
+                    list.iter()
+                        .find(|global| global.name == name && global.interface == "wl_output")
+                        .map(|global| global.version)
+                });
+                let version = version.ok_or_else(|| anyhow!("Output {} is gone", name))?;
+                Ok(OutputCapture {
+                    output: globals.registry().bind(name, version.min(4), &qh, ()),
+                    frame: None,
+                    offered: None,
+                    buffer: None,
+                    y_invert: false,
+                    next_capture: now,
+                    failures: 0,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!(
+            "📸 wlr-screencopy v{}: capturing {} output(s) at up to {} fps",
+            manager.version(),
+            captures.len(),
+            max_fps
+        );
+
+        let frames = Arc::new(Mutex::new(FrameSlots {
+            slots: vec![None; captures.len()],
+            next: 0,
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let state = CaptureState {
+            shm,
+            outputs: captures,
+            frames: Arc::clone(&frames),
+            frame_id: 0,
+            error: None,
+        };
+        let interval = Duration::from_secs(1) / max_fps.max(1);
+
+        let thread = std::thread::Builder::new()
+            .name("screencopy".to_string())
+            .spawn({
+                let stop = Arc::clone(&stop);
+                move || run_capture(conn, event_queue, state, manager, interval, stop)
+            })
+            .context("Failed to spawn screencopy thread")?;
+
+        Ok(Self {
+            frames,
+            stop,
+            _thread: thread,
+        })
+    }
+
+    /// Next captured frame, if one is waiting (non-blocking)
+    pub fn try_recv_frame(&self) -> Option<VideoFrame> {
+        self.frames.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl Drop for ScreencopyCapture {
+    fn drop(&mut self) {
+        // The thread notices within one poll interval; no need to wait for it
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Capture loop: request frames as outputs become due, dispatch events
+fn run_capture(
+    conn: Connection,
+    mut event_queue: EventQueue<CaptureState>,
+    mut state: CaptureState,
+    manager: ZwlrScreencopyManagerV1,
+    interval: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    let qh = event_queue.handle();
+
+    while !stop.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let mut timeout = POLL_INTERVAL;
+        for (index, output) in state.outputs.iter_mut().enumerate() {
+            if output.frame.is_some() {
+                continue;
+            }
+            if now >= output.next_capture {
+                // Overlay the cursor: screencopy has no cursor metadata
+                output.offered = None;
+                output.frame = Some(manager.capture_output(1, &output.output, &qh, index));
+                output.next_capture = now + interval;
+            } else {
+                timeout = timeout.min(output.next_capture - now);
+            }
+        }
+
+        if let Err(e) = dispatch_timeout(&conn, &mut event_queue, &mut state, timeout) {
+            warn!("📸 wlr-screencopy capture stopped: {:#}", e);
+            break;
+        }
+        if let Some(error) = state.error.take() {
+            warn!("📸 wlr-screencopy capture stopped: {}", error);
+            break;
+        }
+    }
+
+    for output in &mut state.outputs {
+        if let Some(frame) = output.frame.take() {
+            frame.destroy();
+        }
+        output.buffer = None;
+    }
+    manager.destroy();
+    let _ = conn.flush();
+    debug!("[screencopy] Capture thread exiting");
+}
+
+/// Dispatch compositor events, waiting up to `timeout` for new ones
+fn dispatch_timeout(
+    conn: &Connection,
+    event_queue: &mut EventQueue<CaptureState>,
+    state: &mut CaptureState,
+    timeout: Duration,
+) -> Result<()> {
+    conn.flush().context("Failed to flush Wayland connection")?;
+    event_queue
+        .dispatch_pending(state)
+        .context("Failed to dispatch Wayland events")?;
+
+    if let Some(guard) = event_queue.prepare_read() {
+        let mut fds = [libc::pollfd {
+            fd: guard.connection_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        // SAFETY: fds is a valid array of one pollfd for the duration of the call
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout.as_millis() as libc::c_int) };
+        if ready > 0 {
+            guard.read().context("Failed to read Wayland events")?;
+        }
+        // Otherwise dropping the guard cancels the read
+    }
+
+    event_queue
+        .dispatch_pending(state)
+        .context("Failed to dispatch Wayland events")?;
+    Ok(())
+}
+
+/// Shared memory layout of a frame, as offered by the compositor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BufferFormat {
+    format: wl_shm::Format,
+    width: u32,
+    height: u32,
+    stride: u32,
+}
+
+impl BufferFormat {
+    /// Formats that convert to BGRx without more than a channel swap
+    fn is_supported(format: wl_shm::Format) -> bool {
+        matches!(
+            format,
+            wl_shm::Format::Xrgb8888
+                | wl_shm::Format::Argb8888
+                | wl_shm::Format::Xbgr8888
+                | wl_shm::Format::Abgr8888
+        )
+    }
+
+    /// Red and blue are in swapped positions relative to BGRx
+    fn is_bgr(&self) -> bool {
+        matches!(
+            self.format,
+            wl_shm::Format::Xbgr8888 | wl_shm::Format::Abgr8888
+        )
+    }
+}
+
+/// Shared memory buffer the compositor copies frames into
+struct ShmBuffer {
+    format: BufferFormat,
+    pool: wl_shm_pool::WlShmPool,
+    buffer: wl_buffer::WlBuffer,
+    map: MmapMut,
+}
+
+impl ShmBuffer {
+    fn new(
+        shm: &wl_shm::WlShm,
+        format: BufferFormat,
+        qh: &QueueHandle<CaptureState>,
+    ) -> Result<Self> {
+        use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+
+        let size = format.stride as usize * format.height as usize;
+        let name = CString::new("lamco-rdp-screencopy")?;
+        let fd = memfd_create(&name, MemFdCreateFlag::MFD_CLOEXEC)
+            .context("Failed to create memfd for screencopy buffer")?;
+        nix::unistd::ftruncate(&fd, size as libc::off_t)
+            .context("Failed to size screencopy buffer")?;
+        let file = std::fs::File::from(fd);
+        // SAFETY: the memfd is private to this process and the compositor,
+        // which only writes to it between copy and ready
+        let map = unsafe { MmapMut::map_mut(&file) }.context("Failed to map screencopy buffer")?;
+
+        let pool = shm.create_pool(file.as_fd(), size as i32, qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            format.width as i32,
+            format.height as i32,
+            format.stride as i32,
+            format.format,
+            qh,
+            (),
+        );
+        Ok(Self {
+            format,
+            pool,
+            buffer,
+            map,
+        })
+    }
+}
+
+impl Drop for ShmBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+    }
+}
+
+/// Convert a copied frame to tightly packed, top-down BGRx
+fn to_bgrx(data: &[u8], format: &BufferFormat, y_invert: bool) -> Vec<u8> {
+    let row_len = format.width as usize * 4;
+    let mut out = Vec::with_capacity(row_len * format.height as usize);
+    for row in 0..format.height as usize {
+        let src_row = if y_invert {
+            format.height as usize - 1 - row
+        } else {
+            row
+        };
+        let start = src_row * format.stride as usize;
+        let Some(src) = data.get(start..start + row_len) else {
+            break;
+        };
+        if format.is_bgr() {
+            for pixel in src.chunks_exact(4) {
+                out.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        } else {
+            out.extend_from_slice(src);
+        }
+    }
+    out
+}
+
+/// Capture progress of one output
+struct OutputCapture {
+    output: wl_output::WlOutput,
+    /// Frame in flight
+    frame: Option<ZwlrScreencopyFrameV1>,
+    /// Shared memory buffer offered for the frame in flight
+    offered: Option<BufferFormat>,
+    buffer: Option<ShmBuffer>,
+    y_invert: bool,
+    next_capture: Instant,
+    failures: u32,
+}
+
+/// State for Wayland protocol dispatch on the capture thread
+struct CaptureState {
+    shm: wl_shm::WlShm,
+    outputs: Vec<OutputCapture>,
+    frames: Arc<Mutex<FrameSlots>>,
+    frame_id: u64,
+    /// Fatal error ending the capture
+    error: Option<String>,
+}
+
+impl CaptureState {
+    /// Copy the frame of output `index` into a matching buffer
+    fn copy(&mut self, index: usize, frame: &ZwlrScreencopyFrameV1, qh: &QueueHandle<Self>) {
+        let output = &mut self.outputs[index];
+        let Some(format) = output.offered else {
+            self.error = Some("compositor offers no supported shared memory format".to_string());
+            return;
+        };
+        if output.buffer.as_ref().map(|buffer| buffer.format) != Some(format) {
+            debug!(
+                "[screencopy] Output {}: {}x{} {:?} buffer",
+                index, format.width, format.height, format.format
+            );
+            output.buffer = None;
+            match ShmBuffer::new(&self.shm, format, qh) {
+                Ok(buffer) => output.buffer = Some(buffer),
+                Err(e) => {
+                    self.error = Some(format!("{:#}", e));
+                    return;
+                }
+            }
+        }
+        if let Some(buffer) = output.buffer.as_ref() {
+            if frame.version() >= 2 {
+                frame.copy_with_damage(&buffer.buffer);
+            } else {
+                frame.copy(&buffer.buffer);
+            }
+        }
+    }
+
+    /// Hand the copied frame of output `index` to the pipeline
+    fn ready(&mut self, index: usize) {
+        let output = &mut self.outputs[index];
+        if let Some(frame) = output.frame.take() {
+            frame.destroy();
+        }
+        output.failures = 0;
+        let Some(buffer) = output.buffer.as_ref() else {
+            return;
+        };
+
+        self.frame_id += 1;
+        let format = buffer.format;
+        let mut frame = VideoFrame::new(
+            self.frame_id,
+            format.width,
+            format.height,
+            format.width * 4,
+            PixelFormat::BGRx,
+            index as u32,
+        );
+        frame.data = Arc::new(to_bgrx(&buffer.map, &format, output.y_invert));
+        self.frames
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .put(index, frame);
+    }
+
+    /// Retry output `index` on its next turn, up to [`MAX_FAILURES`] times
+    fn failed(&mut self, index: usize) {
+        let output = &mut self.outputs[index];
+        if let Some(frame) = output.frame.take() {
+            frame.destroy();
+        }
+        output.failures += 1;
+        debug!(
+            "[screencopy] Output {}: copy failed ({} in a row)",
+            index, output.failures
+        );
+        if output.failures >= MAX_FAILURES {
+            self.error = Some(format!(
+                "output {} failed {} copies in a row",
+                index, MAX_FAILURES
+            ));
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, usize> for CaptureState {
+    fn event(
+        state: &mut Self,
+        frame: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        index: &usize,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let index = *index;
+        if index >= state.outputs.len() {
+            return;
+        }
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } => {
+                let output = &mut state.outputs[index];
+                if output.offered.is_none() && BufferFormat::is_supported(format) {
+                    output.offered = Some(BufferFormat {
+                        format,
+                        width,
+                        height,
+                        stride,
+                    });
+                }
+                // Before version 3 the single buffer offer is all there is
+                if frame.version() < 3 {
+                    state.copy(index, frame, qh);
+                }
+            }
+            zwlr_screencopy_frame_v1::Event::BufferDone => state.copy(index, frame, qh),
+            zwlr_screencopy_frame_v1::Event::Flags {
+                flags: WEnum::Value(flags),
+            } => {
+                state.outputs[index].y_invert =
+                    flags.contains(zwlr_screencopy_frame_v1::Flags::YInvert);
+            }
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => state.ready(index),
+            zwlr_screencopy_frame_v1::Event::Failed => state.failed(index),
+            // Damage regions are detected downstream; dmabuf offers are not used
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Registry events handled by registry_queue_init
+    }
+}
+
+impl Dispatch<ZwlrScreencopyManagerV1, ()> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwlrScreencopyManagerV1,
+        _event: <ZwlrScreencopyManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // No events expected from the screencopy manager
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_output::WlOutput,
+        _event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Frame sizes come with each frame's buffer offer
+    }
+}
+
+impl Dispatch<wl_shm::WlShm, ()> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_shm::WlShm,
+        _event: wl_shm::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Formats are offered per frame
+    }
+}
+
+impl Dispatch<wl_shm_pool::WlShmPool, ()> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_shm_pool::WlShmPool,
+        _event: wl_shm_pool::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // No events expected from shm pools
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for CaptureState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_buffer::WlBuffer,
+        _event: wl_buffer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Buffers are reused once the frame is ready; release is implied
+    }
+}
+
+/// State for enumerating outputs
+struct OutputProbe {
+    outputs: Vec<StreamInfo>,
+    /// Connector name of each output
+    connectors: Vec<String>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for OutputProbe {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // Registry events handled by registry_queue_init
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for OutputProbe {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some(connector) = state.connectors.get_mut(*index) {
+                *connector = name;
+            }
+            return;
+        }
+        let Some(output) = state.outputs.get_mut(*index) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry { x, y, .. } => {
+                output.position_x = x;
+                output.position_y = y;
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                output.width = width.max(0) as u32;
+                output.height = height.max(0) as u32;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(format: wl_shm::Format) -> BufferFormat {
+        // 2x2 with 4 bytes of row padding
+        BufferFormat {
+            format,
+            width: 2,
+            height: 2,
+            stride: 12,
+        }
+    }
+
+    const FRAME: [u8; 24] = [
+        1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0, //
+        9, 10, 11, 12, 13, 14, 15, 16, 0, 0, 0, 0,
+    ];
+
+    #[test]
+    fn test_to_bgrx_strips_padding_and_flips() {
+        let xrgb = format(wl_shm::Format::Xrgb8888);
+        assert_eq!(
+            to_bgrx(&FRAME, &xrgb, false),
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+        assert_eq!(
+            to_bgrx(&FRAME, &xrgb, true),
+            [9, 10, 11, 12, 13, 14, 15, 16, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+
+        // xBGR in memory is RGBx: red and blue swap places
+        let xbgr = format(wl_shm::Format::Xbgr8888);
+        assert_eq!(
+            &to_bgrx(&FRAME, &xbgr, false)[..8],
+            [3, 2, 1, 4, 7, 6, 5, 8]
+        );
+
+        // A short buffer yields the complete rows only
+        assert_eq!(to_bgrx(&FRAME[..18], &xrgb, false).len(), 8);
+    }
+
+    #[test]
+    fn test_frame_slots_keep_latest_and_rotate() {
+        let frame = |id| VideoFrame::new(id, 1, 1, 4, PixelFormat::BGRx, 0);
+        let mut slots = FrameSlots {
+            slots: vec![None, None],
+            next: 0,
+        };
+        assert!(slots.take().is_none());
+
+        slots.put(0, frame(1));
+        slots.put(0, frame(2));
+        slots.put(1, frame(3));
+        assert_eq!(slots.take().map(|f| f.frame_id), Some(2));
+        slots.put(0, frame(4));
+        // Output 1 has waited longer than the new frame of output 0
+        assert_eq!(slots.take().map(|f| f.frame_id), Some(3));
+        assert_eq!(slots.take().map(|f| f.frame_id), Some(4));
+        assert!(slots.take().is_none());
+    }
+}
@@ -3,13 +3,14 @@
 //! Defines the common interface for different session creation strategies:
 //! - Portal + Token Strategy (universal)
 //! - Mutter Direct API (GNOME only)
+//! - KWin ScreenCast (KDE Plasma, video only)
 //! - libei/EIS (wlroots via Portal, Flatpak-compatible)
 //! - wlr-direct (wlroots native protocols, no Flatpak)
 
 use anyhow::Result;
 use async_trait::async_trait;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 /// Portal clipboard components
 ///
@@ -89,6 +90,21 @@ pub trait SessionHandle: Send + Sync {
     /// * `dy` - Vertical scroll delta
     async fn notify_pointer_axis(&self, dx: f64, dy: f64) -> Result<()>;
 
+    /// Bring Caps/Num/Scroll Lock in line with the RDP client
+    ///
+    /// Called for RDP Synchronize events. Strategies that can't see the
+    /// compositor's lock state leave it to the client's own key presses.
+    async fn sync_lock_keys(&self, _locks: LockKeys) -> Result<()> {
+        Ok(())
+    }
+
+    /// Input delivery state, for handles that can lose input on their own
+    ///
+    /// Handles whose input only fails together with the session return None.
+    fn input_status(&self) -> Option<watch::Receiver<InputStatus>> {
+        None
+    }
+
     // === Clipboard Support ===
 
     /// Get Portal clipboard components (if available)
@@ -98,6 +114,25 @@ pub trait SessionHandle: Send + Sync {
     fn portal_clipboard(&self) -> Option<ClipboardComponents>;
 }
 
+/// Input delivery state reported by a session handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputStatus {
+    /// Input reaches the compositor
+    Ready,
+    /// Input is dropped for now (devices paused, reconnecting)
+    Suspended(String),
+    /// Input can no longer be delivered
+    Lost(String),
+}
+
+/// Lock key state reported by the RDP client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockKeys {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+    pub scroll_lock: bool,
+}
+
 /// PipeWire access method
 #[derive(Debug, Clone)]
 pub enum PipeWireAccess {
@@ -105,10 +140,13 @@ pub enum PipeWireAccess {
     FileDescriptor(std::os::fd::RawFd),
     /// Mutter provides a PipeWire node ID
     NodeId(u32),
+    /// No PipeWire: frames are copied with wlr-screencopy, and stream node
+    /// IDs are wl_output global names
+    Screencopy,
 }
 
 /// Stream information (unified across strategies)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct StreamInfo {
     pub node_id: u32,
     pub width: u32,
@@ -128,6 +166,8 @@ pub enum SessionType {
     WlrDirect,
     /// libei/EIS protocol via Portal RemoteDesktop
     Libei,
+    /// KWin screencast protocol (video only)
+    KwinScreencast,
 }
 
 impl std::fmt::Display for SessionType {
@@ -137,6 +177,7 @@ impl std::fmt::Display for SessionType {
             SessionType::MutterDirect => write!(f, "Mutter Direct API"),
             SessionType::WlrDirect => write!(f, "wlr-direct"),
             SessionType::Libei => write!(f, "libei/EIS"),
+            SessionType::KwinScreencast => write!(f, "KWin ScreenCast"),
         }
     }
 }
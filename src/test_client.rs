@@ -0,0 +1,658 @@
+//! Loopback Test Client
+//!
+//! A minimal headless RDP client on the IronRDP client crates, for
+//! `lamco-rdp-server selftest` and the integration tests. It connects to a
+//! running server the way a real client would and checks each part of a
+//! session end to end:
+//!
+//! - **Handshake**: TLS (and NLA when asked), capability exchange and
+//!   activation
+//! - **Video**: graphics updates arrive and decode
+//! - **Input**: pointer motion and a Shift press/release are accepted
+//! - **Clipboard**: the CLIPRDR channel becomes ready, the client's text is
+//!   announced, and text the server offers is pasted back
+//!
+//! The client does not advertise the graphics pipeline, so the server sends
+//! plain bitmap updates; H.264 encoding is covered by `bench` instead.
+//!
+//! Requires the `test-client` feature.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ironrdp::connector::{self, ClientConnector, ConnectionResult, Credentials, DesktopSize};
+use ironrdp::graphics::image_processing::PixelFormat;
+use ironrdp::input::{Database, MousePosition, Operation, Scancode};
+use ironrdp::pdu::gcc::KeyboardType;
+use ironrdp::pdu::rdp::capability_sets::MajorPlatformType;
+use ironrdp::session::image::DecodedImage;
+use ironrdp::session::{ActiveStage, ActiveStageOutput};
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    ClipboardFormat, ClipboardFormatId, ClipboardGeneralCapabilityFlags, FileContentsRequest,
+    FileContentsResponse, FormatDataRequest, FormatDataResponse, LockDataId,
+    OwnedFormatDataResponse,
+};
+use ironrdp_cliprdr::CliprdrClient;
+use ironrdp_core::AsAny;
+use ironrdp_tokio::reqwest::ReqwestNetworkClient;
+use ironrdp_tokio::TokioFramed;
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tracing::{debug, info};
+
+/// Text the client puts on its clipboard unless told otherwise
+pub const DEFAULT_CLIPBOARD_TEXT: &str = "lamco-rdp-server loopback test";
+
+/// Left Shift: pressing it changes nothing on the desktop
+const SHIFT_SCANCODE: u8 = 0x2A;
+
+/// How often pending clipboard work is looked at between PDUs
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Headless client that connects once and reports what worked
+#[derive(Debug, Clone)]
+pub struct TestClient {
+    address: String,
+    username: String,
+    password: String,
+    domain: Option<String>,
+    nla: bool,
+    width: u16,
+    height: u16,
+    frames: usize,
+    timeout: Duration,
+    clipboard_text: String,
+}
+
+impl TestClient {
+    /// Client for the server at `address` ("host:port")
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            username: String::new(),
+            password: String::new(),
+            domain: None,
+            nla: false,
+            width: 1280,
+            height: 720,
+            frames: 3,
+            timeout: Duration::from_secs(30),
+            clipboard_text: DEFAULT_CLIPBOARD_TEXT.to_string(),
+        }
+    }
+
+    /// Log in as `username` (the server's `auth_method` decides whether it
+    /// is checked)
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.username = username.to_string();
+        self.password = password.to_string();
+        self
+    }
+
+    /// Windows domain of the credentials
+    pub fn with_domain(mut self, domain: Option<String>) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Authenticate with CredSSP, for servers with `enable_nla`
+    pub fn with_nla(mut self, nla: bool) -> Self {
+        self.nla = nla;
+        self
+    }
+
+    /// Desktop size the client asks for
+    pub fn with_desktop_size(mut self, width: u16, height: u16) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Graphics updates to wait for
+    pub fn with_frames(mut self, frames: usize) -> Self {
+        self.frames = frames.max(1);
+        self
+    }
+
+    /// Give up on checks that have not passed after `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Text announced on the client's clipboard
+    pub fn with_clipboard_text(mut self, text: impl Into<String>) -> Self {
+        self.clipboard_text = text.into();
+        self
+    }
+
+    /// Connect, run the checks and disconnect
+    ///
+    /// Fails only when no session could be established; checks that did not
+    /// pass in time are listed by [`LoopbackReport::problems`].
+    pub async fn run(&self) -> Result<LoopbackReport> {
+        let start = Instant::now();
+        let deadline = start + self.timeout;
+
+        let stream = tokio::time::timeout_at(deadline.into(), TcpStream::connect(&self.address))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to {}", self.address))?
+            .with_context(|| format!("Failed to connect to {}", self.address))?;
+        let client_addr = stream.local_addr()?;
+        let server_addr = stream.peer_addr()?;
+
+        let clipboard = ClipboardState::shared(&self.clipboard_text);
+        let connector = ClientConnector::new(self.connector_config(), client_addr)
+            .with_static_channel(CliprdrClient::new(Box::new(LoopbackClipboard {
+                state: Arc::clone(&clipboard),
+            })));
+
+        let (connection, mut framed) = tokio::time::timeout_at(
+            deadline.into(),
+            self.handshake(stream, connector, server_addr),
+        )
+        .await
+        .map_err(|_| anyhow!("Timed out during the RDP handshake"))??;
+
+        let mut report = LoopbackReport {
+            handshake_ms: start.elapsed().as_millis() as u64,
+            desktop_width: connection.desktop_size.width,
+            desktop_height: connection.desktop_size.height,
+            ..Default::default()
+        };
+        info!(
+            "🔗 Connected to {} ({}x{}) in {} ms",
+            self.address, report.desktop_width, report.desktop_height, report.handshake_ms
+        );
+
+        let mut image = DecodedImage::new(
+            PixelFormat::RgbA32,
+            connection.desktop_size.width,
+            connection.desktop_size.height,
+        );
+        let mut stage = ActiveStage::new(connection);
+        let mut input = Database::new();
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            if report.frames >= self.frames
+                && report.input_events > 0
+                && clipboard_settled(&clipboard)
+            {
+                break;
+            }
+
+            let outputs = tokio::select! {
+                _ = tokio::time::sleep_until(deadline.into()) => {
+                    report.timed_out = true;
+                    break;
+                }
+                pdu = framed.read_pdu() => {
+                    let (action, payload) = pdu.context("Connection lost")?;
+                    stage
+                        .process(&mut image, action, &payload)
+                        .map_err(|e| anyhow!("Failed to process PDU: {}", e))?
+                }
+                _ = poll.tick() => Vec::new(),
+            };
+
+            for output in outputs {
+                match output {
+                    ActiveStageOutput::ResponseFrame(frame) => framed.write_all(&frame).await?,
+                    ActiveStageOutput::GraphicsUpdate(region) => {
+                        report.frames += 1;
+                        if report.first_frame_ms.is_none() {
+                            report.first_frame_ms = Some(start.elapsed().as_millis() as u64);
+                            debug!("First graphics update: {:?}", region);
+                        }
+                    }
+                    ActiveStageOutput::Terminate(reason) => {
+                        report.terminated = Some(reason.description().to_string());
+                    }
+                    ActiveStageOutput::DeactivateAll(_) => {
+                        // The client never resizes, so this only happens
+                        // when the server restarts its capture
+                        report.terminated = Some("server reactivated the session".to_string());
+                    }
+                    _ => {}
+                }
+            }
+            if report.terminated.is_some() {
+                break;
+            }
+
+            // Input once the desktop is showing, so it lands on a live session
+            if report.frames > 0 && report.input_events == 0 {
+                let events =
+                    input.apply(input_sequence(report.desktop_width, report.desktop_height));
+                report.input_events = events.len();
+                let outputs = stage
+                    .process_fastpath_input(&mut image, &events)
+                    .map_err(|e| anyhow!("Failed to encode input: {}", e))?;
+                write_responses(&mut framed, outputs).await?;
+            }
+
+            self.service_clipboard(&mut stage, &mut framed, &clipboard)
+                .await?;
+        }
+
+        report.clipboard = clipboard.lock().unwrap().report();
+
+        if report.terminated.is_none() {
+            if let Ok(outputs) = stage.graceful_shutdown() {
+                write_responses(&mut framed, outputs).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    fn connector_config(&self) -> connector::Config {
+        connector::Config {
+            credentials: Credentials::UsernamePassword {
+                username: self.username.clone(),
+                password: self.password.clone(),
+            },
+            domain: self.domain.clone(),
+            enable_tls: true,
+            enable_credssp: self.nla,
+            keyboard_type: KeyboardType::IbmEnhanced,
+            keyboard_subtype: 0,
+            keyboard_layout: 0,
+            keyboard_functional_keys_count: 12,
+            ime_file_name: String::new(),
+            dig_product_id: String::new(),
+            desktop_size: DesktopSize {
+                width: self.width,
+                height: self.height,
+            },
+            desktop_scale_factor: 0,
+            bitmap: None,
+            client_build: 0,
+            client_name: "lamco-selftest".to_string(),
+            client_dir: String::new(),
+            platform: MajorPlatformType::UNIX,
+            hardware_id: None,
+            request_data: None,
+            autologon: false,
+            enable_audio_playback: false,
+            license_cache: None,
+            enable_server_pointer: false,
+            pointer_software_rendering: true,
+            performance_flags: Default::default(),
+            timezone_info: Default::default(),
+        }
+    }
+
+    /// X.224 negotiation, TLS upgrade and the rest of the connection sequence
+    async fn handshake(
+        &self,
+        stream: TcpStream,
+        mut connector: ClientConnector,
+        server_addr: SocketAddr,
+    ) -> Result<(
+        ConnectionResult,
+        TokioFramed<ironrdp_tls::TlsStream<TcpStream>>,
+    )> {
+        let mut framed = TokioFramed::new(stream);
+        let should_upgrade = ironrdp_tokio::connect_begin(&mut framed, &mut connector)
+            .await
+            .map_err(|e| anyhow!("Connection negotiation failed: {}", e))?;
+
+        let (stream, leftover) = framed.into_inner();
+        if !leftover.is_empty() {
+            bail!("Server sent data before the TLS upgrade");
+        }
+        let server_name = server_addr.ip().to_string();
+        let (stream, certificate) = ironrdp_tls::upgrade(stream, &server_name)
+            .await
+            .context("TLS upgrade failed")?;
+        let server_public_key = ironrdp_tls::extract_tls_server_public_key(&certificate)
+            .context("Server certificate has no public key")?
+            .to_owned();
+        let upgraded = ironrdp_tokio::mark_as_upgraded(should_upgrade, &mut connector);
+
+        let mut framed = TokioFramed::new(stream);
+        let connection = ironrdp_tokio::connect_finalize(
+            upgraded,
+            connector,
+            &mut framed,
+            &mut ReqwestNetworkClient::new(),
+            connector::ServerName::new(server_name),
+            server_public_key,
+            None,
+        )
+        .await
+        .map_err(|e| anyhow!("Connection sequence failed: {}", e))?;
+        Ok((connection, framed))
+    }
+
+    /// Announce our text, paste the server's and answer its data requests
+    async fn service_clipboard(
+        &self,
+        stage: &mut ActiveStage,
+        framed: &mut TokioFramed<ironrdp_tls::TlsStream<TcpStream>>,
+        clipboard: &Mutex<ClipboardState>,
+    ) -> Result<()> {
+        let Some(cliprdr) = stage.get_svc_processor::<CliprdrClient>() else {
+            return Ok(());
+        };
+        let mut messages = Vec::new();
+        {
+            let mut state = clipboard.lock().unwrap();
+            if !state.ready {
+                return Ok(());
+            }
+            if !state.copy_announced {
+                state.copy_announced = true;
+                messages.push(
+                    cliprdr
+                        .initiate_copy(&[ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)]),
+                );
+            }
+            if state.offers_text() && !state.paste_requested {
+                state.paste_requested = true;
+                messages.push(cliprdr.initiate_paste(ClipboardFormatId::CF_UNICODETEXT));
+            }
+            for _ in 0..std::mem::take(&mut state.pending_requests) {
+                state.copy_requested = true;
+                messages.push(cliprdr.submit_format_data(
+                    OwnedFormatDataResponse::new_unicode_string(&state.local_text),
+                ));
+            }
+        }
+
+        for message in messages {
+            let message = message.map_err(|e| anyhow!("Failed to encode clipboard PDU: {}", e))?;
+            let frame = stage
+                .process_svc_processor_messages(message)
+                .map_err(|e| anyhow!("Failed to send clipboard PDU: {}", e))?;
+            framed.write_all(&frame).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a loopback run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoopbackReport {
+    /// Time from TCP connect to an active session
+    pub handshake_ms: u64,
+    /// Desktop width the server settled on
+    pub desktop_width: u16,
+    /// Desktop height the server settled on
+    pub desktop_height: u16,
+    /// Graphics updates received
+    pub frames: usize,
+    /// Time from TCP connect to the first graphics update
+    pub first_frame_ms: Option<u64>,
+    /// Fast-path input events sent
+    pub input_events: usize,
+    /// Clipboard channel checks
+    pub clipboard: ClipboardReport,
+    /// Why the server ended the session, if it did
+    pub terminated: Option<String>,
+    /// Whether the timeout cut the checks short
+    pub timed_out: bool,
+}
+
+impl LoopbackReport {
+    /// Checks that did not pass
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(reason) = &self.terminated {
+            problems.push(format!("server ended the session: {}", reason));
+        }
+        if self.frames == 0 {
+            problems.push("no graphics updates received".to_string());
+        }
+        if self.input_events == 0 {
+            problems.push("no input sent (no frame to send it on)".to_string());
+        }
+        if !self.clipboard.ready {
+            problems.push("clipboard channel never became ready".to_string());
+        } else if self.clipboard.offers_text() && self.clipboard.remote_text.is_none() {
+            problems.push("server offered text but did not send it".to_string());
+        }
+        if self.timed_out && problems.is_empty() {
+            problems.push("timed out before all checks finished".to_string());
+        }
+        problems
+    }
+
+    /// Whether every check passed
+    pub fn is_ok(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+/// Clipboard channel checks
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClipboardReport {
+    /// Capabilities were exchanged and the server sent Monitor Ready
+    pub ready: bool,
+    /// The client's text was announced to the server
+    pub copy_announced: bool,
+    /// The server asked for the client's text (something pasted it)
+    pub copy_requested: bool,
+    /// Format IDs the server offered
+    pub remote_formats: Vec<u32>,
+    /// Text pasted from the server
+    pub remote_text: Option<String>,
+}
+
+impl ClipboardReport {
+    fn offers_text(&self) -> bool {
+        offers_text(&self.remote_formats)
+    }
+}
+
+/// Clipboard progress shared between the CLIPRDR backend and the session loop
+#[derive(Debug, Default)]
+struct ClipboardState {
+    local_text: String,
+    ready: bool,
+    copy_announced: bool,
+    copy_requested: bool,
+    paste_requested: bool,
+    pending_requests: usize,
+    remote_formats: Vec<u32>,
+    remote_text: Option<String>,
+}
+
+impl ClipboardState {
+    fn shared(local_text: &str) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            local_text: local_text.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    fn offers_text(&self) -> bool {
+        offers_text(&self.remote_formats)
+    }
+
+    fn report(&self) -> ClipboardReport {
+        ClipboardReport {
+            ready: self.ready,
+            copy_announced: self.copy_announced,
+            copy_requested: self.copy_requested,
+            remote_formats: self.remote_formats.clone(),
+            remote_text: self.remote_text.clone(),
+        }
+    }
+}
+
+/// Whether the clipboard checks have nothing left to wait for
+fn clipboard_settled(clipboard: &Mutex<ClipboardState>) -> bool {
+    let state = clipboard.lock().unwrap();
+    state.ready
+        && state.copy_announced
+        && state.pending_requests == 0
+        && (!state.offers_text() || state.remote_text.is_some())
+}
+
+/// CLIPRDR backend recording what the server does
+#[derive(Debug)]
+struct LoopbackClipboard {
+    state: Arc<Mutex<ClipboardState>>,
+}
+
+impl AsAny for LoopbackClipboard {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl CliprdrBackend for LoopbackClipboard {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        ClipboardGeneralCapabilityFlags::USE_LONG_FORMAT_NAMES
+    }
+
+    fn on_ready(&mut self) {
+        self.state.lock().unwrap().ready = true;
+    }
+
+    fn on_request_format_list(&mut self) {
+        // Answered by the copy announced once the channel is ready
+    }
+
+    fn on_process_negotiated_capabilities(
+        &mut self,
+        capabilities: ClipboardGeneralCapabilityFlags,
+    ) {
+        debug!("Clipboard capabilities: {:?}", capabilities);
+    }
+
+    fn on_remote_copy(&mut self, available_formats: &[ClipboardFormat]) {
+        let mut state = self.state.lock().unwrap();
+        state.remote_formats = available_formats.iter().map(|f| f.id().value()).collect();
+        state.paste_requested = false;
+        state.remote_text = None;
+    }
+
+    fn on_format_data_request(&mut self, request: FormatDataRequest) {
+        debug!("Server requested clipboard format {:?}", request.format);
+        self.state.lock().unwrap().pending_requests += 1;
+    }
+
+    fn on_format_data_response(&mut self, response: FormatDataResponse<'_>) {
+        if response.is_error() {
+            return;
+        }
+        let text = decode_unicode_text(response.data());
+        self.state.lock().unwrap().remote_text = Some(text);
+    }
+
+    fn on_file_contents_request(&mut self, _request: FileContentsRequest) {}
+
+    fn on_file_contents_response(&mut self, _response: FileContentsResponse<'_>) {}
+
+    fn on_lock(&mut self, _data_id: LockDataId) {}
+
+    fn on_unlock(&mut self, _data_id: LockDataId) {}
+}
+
+/// Pointer to the middle of the desktop and back, then a Shift tap
+fn input_sequence(width: u16, height: u16) -> Vec<Operation> {
+    vec![
+        Operation::MouseMove(MousePosition {
+            x: width / 2,
+            y: height / 2,
+        }),
+        Operation::MouseMove(MousePosition {
+            x: width / 2 + 1,
+            y: height / 2 + 1,
+        }),
+        Operation::KeyPressed(Scancode::from_u8(false, SHIFT_SCANCODE)),
+        Operation::KeyReleased(Scancode::from_u8(false, SHIFT_SCANCODE)),
+    ]
+}
+
+/// Whether the server's clipboard holds text
+fn offers_text(formats: &[u32]) -> bool {
+    formats.contains(&ClipboardFormatId::CF_UNICODETEXT.value())
+}
+
+/// CF_UNICODETEXT payload: NUL-terminated UTF-16LE
+fn decode_unicode_text(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+async fn write_responses(
+    framed: &mut TokioFramed<ironrdp_tls::TlsStream<TcpStream>>,
+    outputs: Vec<ActiveStageOutput>,
+) -> Result<()> {
+    for output in outputs {
+        if let ActiveStageOutput::ResponseFrame(frame) = output {
+            framed.write_all(&frame).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_problems() {
+        let mut report = LoopbackReport::default();
+        assert_eq!(report.problems().len(), 3);
+
+        report.frames = 4;
+        report.input_events = 6;
+        report.clipboard.ready = true;
+        assert!(report.is_ok());
+
+        report.clipboard.remote_formats = vec![ClipboardFormatId::CF_UNICODETEXT.value()];
+        assert_eq!(
+            report.problems(),
+            vec!["server offered text but did not send it".to_string()]
+        );
+
+        report.clipboard.remote_text = Some("hello".to_string());
+        report.terminated = Some("logged off".to_string());
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_clipboard_backend_tracks_server() {
+        let state = ClipboardState::shared("copied");
+        let mut backend = LoopbackClipboard {
+            state: Arc::clone(&state),
+        };
+        assert!(!clipboard_settled(&state));
+
+        backend.on_ready();
+        backend.on_remote_copy(&[ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)]);
+        state.lock().unwrap().copy_announced = true;
+        assert!(!clipboard_settled(&state));
+
+        let text: Vec<u8> = "hi\0"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        backend.on_format_data_response(FormatDataResponse::new_data(text));
+        assert!(clipboard_settled(&state));
+        assert_eq!(
+            state.lock().unwrap().report().remote_text.as_deref(),
+            Some("hi")
+        );
+    }
+}
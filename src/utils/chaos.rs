@@ -0,0 +1,432 @@
+//! Chaos Testing
+//!
+//! Built with the `chaos` feature, the server can inject faults into its
+//! own operation to exercise the paths that recover from them:
+//!
+//! | Fault | Injected at | Exercises |
+//! |-------|-------------|-----------|
+//! | `portal_delay_ms` | Before each capture session is created | Slow permission dialogs and portal backends |
+//! | `frame_drop` | Captured frames, before the display pipeline sees them | Capture watchdog, damage tracking across gaps |
+//! | `encoder_fail` | H.264 encoder calls | Dropped frames without codec fallback |
+//! | `sever_after_secs` | Client connections, closed at a random time up to this | Dead-peer handling and reconnects |
+//!
+//! Faults are given as `LAMCO_RDP_CHAOS`, e.g.
+//! `portal_delay_ms=3000,frame_drop=0.3,encoder_fail=0.1,sever_after_secs=20,seed=7`.
+//! Rates are shares between 0 and 1, delays are drawn up to the given
+//! maximum. A fixed `seed` repeats the same decisions for the same
+//! sequence of calls. The integration tests in `tests/chaos_test.rs` run
+//! against a server started this way.
+//!
+//! Without the feature, the hooks compile to nothing and a set
+//! `LAMCO_RDP_CHAOS` is ignored with a warning.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Variable holding the faults to inject
+pub const ENV_VAR: &str = "LAMCO_RDP_CHAOS";
+
+/// Faults to inject
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultPlan {
+    /// Longest delay added before a capture session is created
+    pub portal_delay: Duration,
+    /// Share of captured frames dropped
+    pub frame_drop_rate: f64,
+    /// Share of encoder calls failing
+    pub encoder_failure_rate: f64,
+    /// Longest time a client connection lives before it is severed
+    pub sever_after: Option<Duration>,
+    /// Seed of the random decisions
+    pub seed: u64,
+}
+
+impl FaultPlan {
+    /// Whether the plan injects anything
+    pub fn is_empty(&self) -> bool {
+        self.portal_delay.is_zero()
+            && self.frame_drop_rate == 0.0
+            && self.encoder_failure_rate == 0.0
+            && self.sever_after.is_none()
+    }
+}
+
+impl std::fmt::Display for FaultPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "portal delay up to {}ms, {:.0}% frames dropped, {:.0}% encoder failures",
+            self.portal_delay.as_millis(),
+            self.frame_drop_rate * 100.0,
+            self.encoder_failure_rate * 100.0
+        )?;
+        match self.sever_after {
+            Some(after) => write!(f, ", connections severed within {}s", after.as_secs()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for FaultPlan {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rate = |key: &str, value: &str| -> Result<f64> {
+            let rate: f64 = value
+                .parse()
+                .with_context(|| format!("Invalid {}: {}", key, value))?;
+            if !(0.0..=1.0).contains(&rate) {
+                anyhow::bail!("{} must be between 0 and 1", key);
+            }
+            Ok(rate)
+        };
+        let number = |key: &str, value: &str| -> Result<u64> {
+            value
+                .parse()
+                .with_context(|| format!("Invalid {}: {}", key, value))
+        };
+
+        let mut plan = FaultPlan::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, value) = entry
+                .split_once('=')
+                .with_context(|| format!("Expected key=value, got {:?}", entry))?;
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "portal_delay_ms" => {
+                    plan.portal_delay = Duration::from_millis(number(key, value)?);
+                }
+                "frame_drop" => plan.frame_drop_rate = rate(key, value)?,
+                "encoder_fail" => plan.encoder_failure_rate = rate(key, value)?,
+                "sever_after_secs" => {
+                    plan.sever_after = match number(key, value)? {
+                        0 => None,
+                        secs => Some(Duration::from_secs(secs)),
+                    };
+                }
+                "seed" => plan.seed = number(key, value)?,
+                _ => anyhow::bail!("Unknown fault: {}", key),
+            }
+        }
+        Ok(plan)
+    }
+}
+
+/// Faults injected so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    /// Capture session creations delayed
+    pub portal_delays: u64,
+    /// Captured frames dropped
+    pub frames_dropped: u64,
+    /// Encoder calls failed
+    pub encoder_failures: u64,
+    /// Client connections severed
+    pub connections_severed: u64,
+}
+
+/// Decides which calls a [`FaultPlan`] hits
+#[derive(Debug)]
+pub struct FaultInjector {
+    plan: FaultPlan,
+    state: AtomicU64,
+    portal_delays: AtomicU64,
+    frames_dropped: AtomicU64,
+    encoder_failures: AtomicU64,
+    connections_severed: AtomicU64,
+}
+
+impl FaultInjector {
+    /// Injector for `plan`
+    pub fn new(plan: FaultPlan) -> Self {
+        Self {
+            state: AtomicU64::new(plan.seed),
+            plan,
+            portal_delays: AtomicU64::new(0),
+            frames_dropped: AtomicU64::new(0),
+            encoder_failures: AtomicU64::new(0),
+            connections_severed: AtomicU64::new(0),
+        }
+    }
+
+    /// The plan injected
+    pub fn plan(&self) -> &FaultPlan {
+        &self.plan
+    }
+
+    /// Delay for the next capture session
+    pub fn portal_delay(&self) -> Option<Duration> {
+        if self.plan.portal_delay.is_zero() {
+            return None;
+        }
+        self.portal_delays.fetch_add(1, Ordering::Relaxed);
+        Some(self.up_to(self.plan.portal_delay))
+    }
+
+    /// Whether to drop the next captured frame
+    pub fn drop_frame(&self) -> bool {
+        let drop = self.chance(self.plan.frame_drop_rate);
+        self.frames_dropped
+            .fetch_add(u64::from(drop), Ordering::Relaxed);
+        drop
+    }
+
+    /// Whether to fail the next encoder call
+    pub fn fail_encode(&self) -> bool {
+        let fail = self.chance(self.plan.encoder_failure_rate);
+        self.encoder_failures
+            .fetch_add(u64::from(fail), Ordering::Relaxed);
+        fail
+    }
+
+    /// Lifetime of the next client connection
+    pub fn sever_after(&self) -> Option<Duration> {
+        self.plan.sever_after.map(|max| self.up_to(max))
+    }
+
+    /// Faults injected so far
+    pub fn counts(&self) -> FaultCounts {
+        FaultCounts {
+            portal_delays: self.portal_delays.load(Ordering::Relaxed),
+            frames_dropped: self.frames_dropped.load(Ordering::Relaxed),
+            encoder_failures: self.encoder_failures.load(Ordering::Relaxed),
+            connections_severed: self.connections_severed.load(Ordering::Relaxed),
+        }
+    }
+
+    fn chance(&self, rate: f64) -> bool {
+        rate > 0.0 && self.next_unit() < rate
+    }
+
+    fn up_to(&self, max: Duration) -> Duration {
+        max.mul_f64(self.next_unit())
+    }
+
+    /// Next value in [0, 1) of a SplitMix64 sequence
+    fn next_unit(&self) -> f64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(feature = "chaos")]
+static INJECTOR: std::sync::RwLock<Option<std::sync::Arc<FaultInjector>>> =
+    std::sync::RwLock::new(None);
+
+#[cfg(feature = "chaos")]
+fn injector() -> Option<std::sync::Arc<FaultInjector>> {
+    INJECTOR.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Inject the faults of `plan` from now on (an empty plan stops injecting)
+///
+/// Returns false when built without the `chaos` feature.
+pub fn install(plan: FaultPlan) -> bool {
+    #[cfg(feature = "chaos")]
+    {
+        let injector = (!plan.is_empty()).then(|| std::sync::Arc::new(FaultInjector::new(plan)));
+        *INJECTOR.write().unwrap_or_else(|e| e.into_inner()) = injector;
+        true
+    }
+    #[cfg(not(feature = "chaos"))]
+    {
+        let _ = plan;
+        false
+    }
+}
+
+/// Inject the faults of `LAMCO_RDP_CHAOS`, if set
+///
+/// Returns the plan injected.
+pub fn install_from_env() -> Result<Option<FaultPlan>> {
+    let Ok(spec) = std::env::var(ENV_VAR) else {
+        return Ok(None);
+    };
+    let plan: FaultPlan = spec
+        .parse()
+        .with_context(|| format!("Invalid {}", ENV_VAR))?;
+    if plan.is_empty() {
+        return Ok(None);
+    }
+    if !install(plan.clone()) {
+        tracing::warn!("⚠️  {} ignored: built without the chaos feature", ENV_VAR);
+        return Ok(None);
+    }
+    Ok(Some(plan))
+}
+
+/// Faults injected so far
+pub fn counts() -> FaultCounts {
+    #[cfg(feature = "chaos")]
+    if let Some(injector) = injector() {
+        return injector.counts();
+    }
+    FaultCounts::default()
+}
+
+/// Wait out an injected portal delay
+pub async fn delay_portal() {
+    #[cfg(feature = "chaos")]
+    if let Some(delay) = injector().and_then(|injector| injector.portal_delay()) {
+        tracing::warn!("💥 Chaos: delaying the portal by {}ms", delay.as_millis());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Whether to drop a captured frame
+#[inline]
+pub fn drop_frame() -> bool {
+    #[cfg(feature = "chaos")]
+    if let Some(injector) = injector() {
+        return injector.drop_frame();
+    }
+    false
+}
+
+/// Whether to fail an encoder call
+#[inline]
+pub fn fail_encode() -> bool {
+    #[cfg(feature = "chaos")]
+    if let Some(injector) = injector() {
+        return injector.fail_encode();
+    }
+    false
+}
+
+/// Whether client connections are severed
+pub fn severs_connections() -> bool {
+    #[cfg(feature = "chaos")]
+    if let Some(injector) = injector() {
+        return injector.plan().sever_after.is_some();
+    }
+    false
+}
+
+/// Sever `stream` at a random time, unless the guard is dropped first
+///
+/// Both directions are shut down while the server's socket stays open, so
+/// the client loses the connection and the server learns of it from failing
+/// reads and writes, as with a connection reset mid-session. The guard must
+/// live only as long as the connection.
+pub fn sever_later(stream: &tokio::net::TcpStream) -> Option<SeverGuard> {
+    #[cfg(feature = "chaos")]
+    {
+        use std::os::fd::AsFd;
+
+        let injector = injector()?;
+        let after = injector.sever_after()?;
+        let socket = match stream.as_fd().try_clone_to_owned() {
+            Ok(fd) => socket2::Socket::from(fd),
+            Err(e) => {
+                tracing::warn!("Chaos: cannot sever connection: {}", e);
+                return None;
+            }
+        };
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(after).await;
+            tracing::warn!(
+                "💥 Chaos: severing the connection after {:.1}s",
+                after.as_secs_f64()
+            );
+            injector.connections_severed.fetch_add(1, Ordering::Relaxed);
+            let _ = socket.shutdown(std::net::Shutdown::Both);
+        });
+        Some(SeverGuard(task))
+    }
+    #[cfg(not(feature = "chaos"))]
+    {
+        let _ = stream;
+        None
+    }
+}
+
+/// Cancels a pending [`sever_later`] when dropped
+#[derive(Debug)]
+pub struct SeverGuard(tokio::task::JoinHandle<()>);
+
+impl Drop for SeverGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan() {
+        let plan: FaultPlan =
+            "portal_delay_ms=3000, frame_drop=0.3,encoder_fail=0.1,sever_after_secs=20,seed=7"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            plan,
+            FaultPlan {
+                portal_delay: Duration::from_secs(3),
+                frame_drop_rate: 0.3,
+                encoder_failure_rate: 0.1,
+                sever_after: Some(Duration::from_secs(20)),
+                seed: 7,
+            }
+        );
+        assert!("".parse::<FaultPlan>().unwrap().is_empty());
+        assert!("frame_drop=1.5".parse::<FaultPlan>().is_err());
+        assert!("encoder_fail".parse::<FaultPlan>().is_err());
+        assert!("disk_full=1".parse::<FaultPlan>().is_err());
+    }
+
+    #[test]
+    fn test_rates_and_seeds() {
+        let plan = FaultPlan {
+            frame_drop_rate: 0.25,
+            encoder_failure_rate: 1.0,
+            seed: 42,
+            ..Default::default()
+        };
+        let injector = FaultInjector::new(plan.clone());
+        let drops: Vec<bool> = (0..4000).map(|_| injector.drop_frame()).collect();
+        let dropped = drops.iter().filter(|&&d| d).count();
+        assert!(
+            (800..1200).contains(&dropped),
+            "{} of 4000 dropped",
+            dropped
+        );
+        assert!((0..10).all(|_| injector.fail_encode()));
+        assert_eq!(injector.counts().frames_dropped, dropped as u64);
+        assert_eq!(injector.counts().encoder_failures, 10);
+
+        // The same seed makes the same decisions
+        let again = FaultInjector::new(plan);
+        assert!(drops.iter().all(|&d| again.drop_frame() == d));
+
+        // Nothing is injected without a rate
+        let none = FaultInjector::new(FaultPlan::default());
+        assert!((0..100).all(|_| !none.drop_frame() && !none.fail_encode()));
+        assert_eq!(none.portal_delay(), None);
+        assert_eq!(none.sever_after(), None);
+    }
+
+    #[test]
+    fn test_delays_within_maximum() {
+        let injector = FaultInjector::new(FaultPlan {
+            portal_delay: Duration::from_millis(500),
+            sever_after: Some(Duration::from_secs(10)),
+            ..Default::default()
+        });
+        for _ in 0..100 {
+            assert!(injector.portal_delay().unwrap() <= Duration::from_millis(500));
+            assert!(injector.sever_after().unwrap() <= Duration::from_secs(10));
+        }
+        assert_eq!(injector.counts().portal_delays, 100);
+    }
+}
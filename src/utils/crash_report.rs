@@ -0,0 +1,493 @@
+//! Crash Reports
+//!
+//! A panic hook that writes `crash-<time>-<pid>.json` to the
+//! `[crash_report]` directory, so a daemon that failed overnight leaves
+//! more behind than a journal line:
+//!
+//! - panic message, location, thread and backtrace,
+//! - sections registered with [`add_section`] (the server adds its
+//!   connected sessions),
+//! - state published with [`CrashState`] (each display pipeline publishes
+//!   its encoder), and
+//! - the last log lines, kept by [`RecentLogLayer`].
+//!
+//! With `webhook_url` set, a summary is POSTed as JSON as well. Panics in
+//! any thread are reported, including those of tokio tasks the runtime
+//! survives. Faults that end the process without unwinding (a segfault in
+//! a C library, the OOM killer) run no code of ours and leave no report;
+//! core dumps cover those.
+//!
+//! The hook must not hang a crashing process, so sections are collected on
+//! a helper thread with a deadline, and locks are only tried.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use super::session_log::format_event;
+use crate::config::types::CrashReportConfig;
+
+/// Log lines kept for reports (`log_lines` takes the newest of these)
+pub const LOG_CAPACITY: usize = 1000;
+
+/// Time the sections get to report
+const SECTION_DEADLINE: Duration = Duration::from_secs(2);
+
+/// Time the webhook gets to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Produces a report section
+pub type SectionProvider = Box<dyn Fn() -> Value + Send + Sync>;
+
+/// Most recent log lines, shared with the panic hook
+#[derive(Debug, Clone, Default)]
+pub struct RecentLogs(Arc<Mutex<VecDeque<String>>>);
+
+impl RecentLogs {
+    /// The newest `count` lines, oldest first (empty if the buffer is busy)
+    pub fn lines(&self, count: usize) -> Vec<String> {
+        match self.0.try_lock() {
+            Ok(lines) => lines
+                .iter()
+                .skip(lines.len().saturating_sub(count))
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn push(&self, line: String) {
+        let mut lines = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == LOG_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// Tracing layer keeping the last [`LOG_CAPACITY`] log lines
+#[derive(Debug, Default)]
+pub struct RecentLogLayer {
+    logs: RecentLogs,
+}
+
+impl RecentLogLayer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle for reading the lines
+    pub fn logs(&self) -> RecentLogs {
+        self.logs.clone()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RecentLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut line = format_event(event);
+        line.truncate(line.trim_end().len());
+        self.logs.push(line);
+    }
+}
+
+struct Reporter {
+    dir: PathBuf,
+    log_lines: usize,
+    max_reports: usize,
+    webhook_url: Option<String>,
+    logs: RecentLogs,
+    sections: Mutex<Vec<(String, Arc<SectionProvider>)>>,
+}
+
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+/// Set while a report is written, so a panic inside the hook isn't reported
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// State published with [`CrashState`], by key
+static STATE: Mutex<BTreeMap<String, Value>> = Mutex::new(BTreeMap::new());
+
+static NEXT_STATE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Directory reports go to without `crash_report.dir`
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("lamco-rdp-server").join("crashes"))
+}
+
+/// Install the panic hook for `config` (no-op when disabled)
+///
+/// The previous hook still runs afterwards, so panics are logged to stderr
+/// as before. Returns the report directory.
+pub fn install(config: &CrashReportConfig, logs: RecentLogs) -> Result<Option<PathBuf>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let dir = match config.dir.clone().or_else(default_dir) {
+        Some(dir) => dir,
+        None => anyhow::bail!("No directory for crash reports; set crash_report.dir"),
+    };
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create crash report directory {}", dir.display()))?;
+
+    let reporter = Reporter {
+        dir: dir.clone(),
+        log_lines: config.log_lines.min(LOG_CAPACITY),
+        max_reports: config.max_reports,
+        webhook_url: Some(config.webhook_url.clone()).filter(|url| !url.is_empty()),
+        logs,
+        sections: Mutex::new(Vec::new()),
+    };
+    if REPORTER.set(reporter).is_err() {
+        // Installed by an earlier server instance of this process
+        return Ok(Some(dir));
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !REPORTING.swap(true, Ordering::SeqCst) {
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "(non-string panic payload)".to_string());
+            let location = info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+            if let Some(reporter) = REPORTER.get() {
+                reporter.report(message, location);
+            }
+            REPORTING.store(false, Ordering::SeqCst);
+        }
+        previous(info);
+    }));
+    Ok(Some(dir))
+}
+
+/// Add a section to future reports, replacing one of the same name
+///
+/// `provider` runs on a helper thread while the process is crashing; one
+/// still running after two seconds is reported as timed out. Does nothing
+/// unless [`install`] enabled reports.
+pub fn add_section(name: impl Into<String>, provider: impl Fn() -> Value + Send + Sync + 'static) {
+    if let Some(reporter) = REPORTER.get() {
+        let mut sections = reporter.sections.lock().unwrap_or_else(|e| e.into_inner());
+        let name = name.into();
+        sections.retain(|(existing, _)| *existing != name);
+        sections.push((name, Arc::new(Box::new(provider))));
+    }
+}
+
+/// State included in crash reports for as long as the handle lives
+///
+/// Keyed `<kind>#<n>`, so several pipelines can publish the same kind.
+#[derive(Debug)]
+pub struct CrashState {
+    key: String,
+}
+
+impl CrashState {
+    /// Handle publishing nothing yet
+    pub fn new(kind: &str) -> Self {
+        Self {
+            key: format!("{}#{}", kind, NEXT_STATE_ID.fetch_add(1, Ordering::Relaxed)),
+        }
+    }
+
+    /// Replace the published state
+    pub fn set(&self, value: Value) {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        state.insert(self.key.clone(), value);
+    }
+}
+
+impl Drop for CrashState {
+    fn drop(&mut self) {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        state.remove(&self.key);
+    }
+}
+
+/// Contents of a crash report file
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    /// Server version
+    pub version: &'static str,
+    /// Time of the panic (RFC 3339)
+    pub time: String,
+    /// Process ID
+    pub pid: u32,
+    /// Name of the panicking thread
+    pub thread: String,
+    /// Panic message
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Stack of the panicking thread
+    pub backtrace: String,
+    /// Registered sections by name
+    pub sections: BTreeMap<String, Value>,
+    /// Published state by key
+    pub state: BTreeMap<String, Value>,
+    /// Last log lines, oldest first
+    pub log: Vec<String>,
+}
+
+impl Reporter {
+    fn report(&self, message: String, location: Option<String>) {
+        let report = CrashReport {
+            version: env!("CARGO_PKG_VERSION"),
+            time: chrono::Local::now().to_rfc3339(),
+            pid: std::process::id(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_string(),
+            message,
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            sections: self.collect_sections(SECTION_DEADLINE),
+            state: STATE.try_lock().map(|s| s.clone()).unwrap_or_default(),
+            log: self.logs.lines(self.log_lines),
+        };
+
+        // Logging from the hook could deadlock on the subscriber
+        let path = match write_report(&self.dir, &report, self.max_reports) {
+            Ok(path) => {
+                eprintln!("Crash report written to {}", path.display());
+                Some(path)
+            }
+            Err(e) => {
+                eprintln!("Failed to write crash report: {:#}", e);
+                None
+            }
+        };
+        if let Some(ref url) = self.webhook_url {
+            if let Err(e) = notify_webhook(url, &report, path.as_deref()) {
+                eprintln!("Crash report webhook failed: {:#}", e);
+            }
+        }
+    }
+
+    /// Run the section providers, giving up on those past `deadline`
+    fn collect_sections(&self, deadline: Duration) -> BTreeMap<String, Value> {
+        let sections: Vec<_> = match self.sections.try_lock() {
+            Ok(sections) => sections.clone(),
+            Err(_) => return BTreeMap::new(),
+        };
+        collect_sections(sections, deadline)
+    }
+}
+
+fn collect_sections(
+    sections: Vec<(String, Arc<SectionProvider>)>,
+    deadline: Duration,
+) -> BTreeMap<String, Value> {
+    let names: Vec<String> = sections.iter().map(|(name, _)| name.clone()).collect();
+    let (tx, rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("crash-report".to_string())
+        .spawn(move || {
+            for (name, provider) in sections {
+                let value = std::panic::catch_unwind(AssertUnwindSafe(|| provider()))
+                    .unwrap_or_else(|_| Value::String("(section panicked)".to_string()));
+                if tx.send((name, value)).is_err() {
+                    return;
+                }
+            }
+        });
+    if spawned.is_err() {
+        return BTreeMap::new();
+    }
+
+    let until = Instant::now() + deadline;
+    let mut collected = BTreeMap::new();
+    while let Ok((name, value)) = rx.recv_timeout(until.saturating_duration_since(Instant::now())) {
+        collected.insert(name, value);
+    }
+    for name in names {
+        collected
+            .entry(name)
+            .or_insert_with(|| Value::String("(timed out)".to_string()));
+    }
+    collected
+}
+
+/// Write `report` to `dir`, keeping the newest `max_reports` reports
+pub fn write_report(dir: &Path, report: &CrashReport, max_reports: usize) -> Result<PathBuf> {
+    let path = dir.join(format!(
+        "crash-{}-{}.json",
+        chrono::Local::now().format("%Y%m%dT%H%M%S%.3f"),
+        report.pid
+    ));
+    std::fs::write(&path, serde_json::to_vec_pretty(report)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let mut reports = list_reports(dir);
+    while reports.len() > max_reports.max(1) {
+        let oldest = reports.remove(0);
+        let _ = std::fs::remove_file(oldest);
+    }
+    Ok(path)
+}
+
+/// Crash reports in `dir`, oldest first
+pub fn list_reports(dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".json"))
+        })
+        .collect();
+    // Names start with the time, so they sort chronologically
+    reports.sort();
+    reports
+}
+
+/// POST a summary of `report` to `url`
+///
+/// Runs on its own thread and runtime, as the panicking thread may be a
+/// runtime worker.
+fn notify_webhook(url: &str, report: &CrashReport, path: Option<&Path>) -> Result<()> {
+    let body = serde_json::json!({
+        "event": "crash",
+        "host": hostname::get().ok().map(|h| h.to_string_lossy().into_owned()),
+        "version": report.version,
+        "time": report.time,
+        "pid": report.pid,
+        "thread": report.thread,
+        "message": report.message,
+        "location": report.location,
+        "report": path,
+    });
+    let url = url.to_string();
+    let (tx, rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("crash-webhook".to_string())
+        .spawn(move || {
+            let result = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .context("Failed to create runtime")
+                .and_then(|runtime| {
+                    runtime.block_on(async {
+                        reqwest::Client::builder()
+                            .timeout(WEBHOOK_TIMEOUT)
+                            .build()?
+                            .post(&url)
+                            .json(&body)
+                            .send()
+                            .await?
+                            .error_for_status()?;
+                        Ok::<_, anyhow::Error>(())
+                    })
+                });
+            let _ = tx.send(result);
+        })
+        .context("Failed to start webhook thread")?;
+    rx.recv_timeout(WEBHOOK_TIMEOUT + Duration::from_secs(1))
+        .context("Webhook timed out")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn report(pid: u32) -> CrashReport {
+        CrashReport {
+            version: "0.0.0",
+            time: String::new(),
+            pid,
+            thread: "main".to_string(),
+            message: "boom".to_string(),
+            location: Some("src/main.rs:1:1".to_string()),
+            backtrace: String::new(),
+            sections: BTreeMap::new(),
+            state: BTreeMap::new(),
+            log: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recent_logs_keep_newest_lines() {
+        let layer = RecentLogLayer::new();
+        let logs = layer.logs();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..LOG_CAPACITY + 5 {
+                tracing::info!(i, "line");
+            }
+        });
+
+        let lines = logs.lines(2);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(&format!("i={}", LOG_CAPACITY + 3)));
+        assert!(lines[1].contains(&format!("i={}", LOG_CAPACITY + 4)));
+        assert_eq!(logs.lines(usize::MAX).len(), LOG_CAPACITY);
+    }
+
+    #[test]
+    fn test_sections_past_deadline_time_out() {
+        let fast: Arc<SectionProvider> = Arc::new(Box::new(|| serde_json::json!({"clients": 2})));
+        let stuck: Arc<SectionProvider> = Arc::new(Box::new(|| {
+            std::thread::sleep(Duration::from_secs(5));
+            Value::Null
+        }));
+        let failing: Arc<SectionProvider> = Arc::new(Box::new(|| panic!("section bug")));
+
+        let sections = collect_sections(
+            vec![
+                ("sessions".to_string(), fast),
+                ("failing".to_string(), failing),
+                ("stuck".to_string(), stuck),
+            ],
+            Duration::from_millis(200),
+        );
+        assert_eq!(sections["sessions"]["clients"], 2);
+        assert_eq!(sections["failing"], "(section panicked)");
+        assert_eq!(sections["stuck"], "(timed out)");
+    }
+
+    #[test]
+    fn test_state_lives_with_handle() {
+        let state = CrashState::new("encoder");
+        state.set(serde_json::json!({"codec": "AVC420"}));
+        let key = state.key.clone();
+        assert!(STATE.lock().unwrap().contains_key(&key));
+        drop(state);
+        assert!(!STATE.lock().unwrap().contains_key(&key));
+    }
+
+    #[test]
+    fn test_old_reports_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        for pid in 0..4 {
+            write_report(dir.path(), &report(pid), 2).unwrap();
+            // Report names have millisecond resolution
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        let reports = list_reports(dir.path());
+        assert_eq!(reports.len(), 2);
+        let newest: Value = serde_json::from_slice(&std::fs::read(&reports[1]).unwrap()).unwrap();
+        assert_eq!(newest["pid"], 3);
+        assert_eq!(newest["message"], "boom");
+    }
+}
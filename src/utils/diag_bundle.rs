@@ -0,0 +1,192 @@
+//! Diagnostics Bundle
+//!
+//! Collects what a bug report needs into one tarball, for
+//! `lamco-rdp-server diag`: system and GPU information, the effective
+//! configuration, self-test results and recent logs. Files are staged in a
+//! temporary directory and packed with the system `tar`, which every
+//! distribution ships.
+//!
+//! Secrets are redacted from the configuration; logs are copied as written,
+//! so the bundle should be looked over before it is shared publicly.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+/// Files of a bug report, packed into `<name>.tar.gz`
+pub struct DiagBundle {
+    name: String,
+    staging: PathBuf,
+    files: Vec<String>,
+}
+
+impl DiagBundle {
+    /// Empty bundle named after the host and the current time
+    pub fn new() -> Result<Self> {
+        let host = hostname::get()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let name = format!(
+            "lamco-rdp-server-diag-{}-{}",
+            host,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let staging = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        fs::create_dir_all(staging.join(&name))
+            .with_context(|| format!("Failed to create {}", staging.display()))?;
+        Ok(Self {
+            name,
+            staging,
+            files: Vec::new(),
+        })
+    }
+
+    /// Add a file with `contents`
+    pub fn add(&mut self, file: &str, contents: impl AsRef<[u8]>) -> Result<()> {
+        let path = self.staging.join(&self.name).join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", file))?;
+        self.files.push(file.to_string());
+        Ok(())
+    }
+
+    /// Add `value` as pretty-printed JSON
+    pub fn add_json(&mut self, file: &str, value: &impl Serialize) -> Result<()> {
+        self.add(file, serde_json::to_string_pretty(value)?)
+    }
+
+    /// Add the last `lines` lines of the file at `path`, if it can be read
+    pub fn add_log(&mut self, file: &str, path: &Path, lines: usize) -> Result<bool> {
+        let Ok(text) = fs::read(path) else {
+            return Ok(false);
+        };
+        self.add(file, tail(&String::from_utf8_lossy(&text), lines))?;
+        Ok(true)
+    }
+
+    /// Add the output of a command, or why it could not run
+    pub fn add_command(&mut self, file: &str, program: &str, args: &[&str]) -> Result<()> {
+        let contents = match Command::new(program).args(args).output() {
+            Ok(output) => {
+                let mut contents = output.stdout;
+                contents.extend_from_slice(&output.stderr);
+                if !output.status.success() {
+                    contents.extend_from_slice(format!("\n[{}]\n", output.status).as_bytes());
+                }
+                contents
+            }
+            Err(e) => format!("{} {}: {}\n", program, args.join(" "), e).into_bytes(),
+        };
+        self.add(file, contents)
+    }
+
+    /// Files added so far
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Pack the bundle into `<dir>/<name>.tar.gz` and return its path
+    pub fn write_tarball(self, dir: &Path) -> Result<PathBuf> {
+        let tarball = dir.join(format!("{}.tar.gz", self.name));
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&tarball)
+            .arg("-C")
+            .arg(&self.staging)
+            .arg(&self.name)
+            .status()
+            .context("Failed to run tar")?;
+        let _ = fs::remove_dir_all(&self.staging);
+        if !status.success() {
+            bail!("tar failed ({}) writing {}", status, tarball.display());
+        }
+        Ok(tarball)
+    }
+}
+
+/// A GPU as the kernel's DRM subsystem lists it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DrmDevice {
+    /// `card0`, ...
+    pub card: String,
+    /// Kernel driver (`i915`, `amdgpu`, `nvidia`, ...)
+    pub driver: Option<String>,
+    /// PCI vendor:device ID
+    pub pci_id: Option<String>,
+}
+
+/// GPUs of `/sys/class/drm`
+pub fn drm_devices() -> Vec<DrmDevice> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut devices: Vec<DrmDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let card = entry.file_name().to_string_lossy().into_owned();
+            // Connectors are listed as card0-DP-1 next to their card
+            if !card.starts_with("card") || card.contains('-') {
+                return None;
+            }
+            let uevent = fs::read_to_string(entry.path().join("device/uevent")).ok()?;
+            Some(parse_uevent(card, &uevent))
+        })
+        .collect();
+    devices.sort_by(|a, b| a.card.cmp(&b.card));
+    devices
+}
+
+fn parse_uevent(card: String, uevent: &str) -> DrmDevice {
+    let value = |key: &str| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_string)
+    };
+    DrmDevice {
+        card,
+        driver: value("DRIVER"),
+        pci_id: value("PCI_ID"),
+    }
+}
+
+/// NVIDIA kernel driver version line, when the proprietary driver is loaded
+pub fn nvidia_driver_version() -> Option<String> {
+    let version = fs::read_to_string("/proc/driver/nvidia/version").ok()?;
+    version.lines().next().map(|line| line.trim().to_string())
+}
+
+/// Last `lines` lines of `text`
+fn tail(text: &str, lines: usize) -> String {
+    let start = text.lines().count().saturating_sub(lines);
+    let mut tail: String = text.lines().skip(start).collect::<Vec<_>>().join("\n");
+    tail.push('\n');
+    tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uevent() {
+        let device = parse_uevent(
+            "card1".to_string(),
+            "DRIVER=amdgpu\nPCI_CLASS=30000\nPCI_ID=1002:73BF\nPCI_SLOT_NAME=0000:03:00.0\n",
+        );
+        assert_eq!(device.driver.as_deref(), Some("amdgpu"));
+        assert_eq!(device.pci_id.as_deref(), Some("1002:73BF"));
+        assert_eq!(parse_uevent("card0".to_string(), "").driver, None);
+    }
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(tail("a\nb\nc\n", 2), "b\nc\n");
+        assert_eq!(tail("a\nb", 5), "a\nb\n");
+    }
+}
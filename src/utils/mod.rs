@@ -4,11 +4,12 @@
 //!
 //! # Overview
 //!
-//! This module provides three key utilities for operational visibility and debugging:
+//! This module provides four key utilities for operational visibility and debugging:
 //!
 //! 1. **Diagnostics** - System information and capability detection
 //! 2. **Metrics** - Performance monitoring and statistics collection
 //! 3. **Error Formatting** - User-friendly error messages with troubleshooting hints
+//! 4. **Session Logging** - Per-session tracing spans and log files
 //!
 //! ## Diagnostics
 //!
@@ -90,15 +91,55 @@
 //! - Config errors → Syntax validation, missing fields
 //!
 //! This makes troubleshooting accessible to users unfamiliar with Wayland/Portal internals.
+//!
+//! ## Session Logging
+//!
+//! The [`session_log`] module tags everything done for a client with a
+//! `session` span (`session_id`, `peer`, `kind`), so logs from concurrent
+//! clients can be separated, and can write one log file per session.
+//!
+//! ## Diagnostics Bundle
+//!
+//! The [`diag_bundle`] module packs system and GPU information, the
+//! effective configuration, self-test results and recent logs into one
+//! tarball for bug reports (`lamco-rdp-server diag`).
+//!
+//! ## Crash Reports
+//!
+//! The [`crash_report`] module installs a panic hook writing a JSON report
+//! (backtrace, sessions, encoder state, last log lines) to a crash
+//! directory, and optionally notifies a webhook.
+//!
+//! ## Chaos Testing
+//!
+//! The [`chaos`] module injects faults (portal delays, dropped frames,
+//! encoder failures, severed connections) when built with the `chaos`
+//! feature and given `LAMCO_RDP_CHAOS`, to exercise the recovery paths.
+//!
+//! ## Wakeup Audit
+//!
+//! The [`wakeup_audit`] module counts how often the server's loops and
+//! timers wake up, and how often for nothing. Built with the `wakeup-audit`
+//! feature, the rates are logged periodically to track down idle power draw.
 
+pub mod chaos;
+pub mod crash_report;
+pub mod diag_bundle;
 pub mod diagnostics;
 pub mod errors;
 pub mod metrics;
+pub mod session_log;
+pub mod wakeup_audit;
 
 // Re-export key types
+pub use crash_report::{CrashState, RecentLogLayer, RecentLogs};
+pub use diag_bundle::DiagBundle;
 pub use diagnostics::{
     detect_compositor, detect_portal_backend, get_pipewire_version, log_startup_diagnostics,
     RuntimeStats, SystemInfo,
 };
 pub use errors::format_user_error;
 pub use metrics::{metric_names, HistogramStats, MetricsCollector, MetricsSnapshot, Timer};
+pub use session_log::{
+    session_span, spawn_in_current_span, SessionLogDir, SessionLogLayer, SESSION_SPAN,
+};
@@ -0,0 +1,219 @@
+//! Per-Session Logging
+//!
+//! Every client connection runs inside a `session` tracing span carrying a
+//! stable `session_id` (the server-assigned client ID), the peer address and
+//! the pipeline kind. Tasks started for the session (display pipeline, input,
+//! clipboard, EGFX) are spawned with [`spawn_in_current_span`], so their logs
+//! carry the same fields and concurrent clients can be told apart, e.g. with
+//! `RUST_LOG='lamco[session{session_id=3}]=debug'`.
+//!
+//! [`SessionLogLayer`] additionally copies each session's events to
+//! `<log_dir>/session-<id>.log` once a directory is set
+//! (`logging.per_session_files`).
+
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Instrument, Span, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Name of the span wrapping a client session
+pub const SESSION_SPAN: &str = "session";
+
+/// Span for a client session
+pub fn session_span(session_id: u64, peer: SocketAddr, kind: impl std::fmt::Display) -> Span {
+    tracing::info_span!("session", session_id, %peer, %kind)
+}
+
+/// Spawn a task that stays in the caller's tracing span
+///
+/// Use for work done on behalf of a session so its logs keep the session ID.
+pub fn spawn_in_current_span<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future.in_current_span())
+}
+
+/// Directory for per-session log files (None = disabled)
+#[derive(Debug, Clone, Default)]
+pub struct SessionLogDir(Arc<RwLock<Option<PathBuf>>>);
+
+impl SessionLogDir {
+    /// Start (or stop, with `None`) writing per-session files
+    ///
+    /// Applies to sessions that start afterwards.
+    pub fn set(&self, dir: Option<PathBuf>) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = dir;
+    }
+
+    fn get(&self) -> Option<PathBuf> {
+        self.0.read().ok().and_then(|dir| dir.clone())
+    }
+}
+
+/// Tracing layer writing each session's events to its own file
+///
+/// Installed at startup (before the configuration is known); files are only
+/// written once [`SessionLogDir::set`] has been given a directory.
+#[derive(Debug, Default)]
+pub struct SessionLogLayer {
+    dir: SessionLogDir,
+}
+
+impl SessionLogLayer {
+    /// Create a layer with per-session files disabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle for setting the log directory later
+    pub fn directory(&self) -> SessionLogDir {
+        self.dir.clone()
+    }
+}
+
+/// Open log file stored in the session span's extensions
+struct SessionLogFile(Mutex<File>);
+
+impl<S> Layer<S> for SessionLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != SESSION_SPAN {
+            return;
+        }
+        let Some(dir) = self.dir.get() else {
+            return;
+        };
+        let mut visitor = SessionIdVisitor(None);
+        attrs.record(&mut visitor);
+        let Some(session_id) = visitor.0 else {
+            return;
+        };
+
+        let path = dir.join(format!("session-{}.log", session_id));
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => {
+                if let Some(span) = ctx.span(id) {
+                    span.extensions_mut()
+                        .insert(SessionLogFile(Mutex::new(file)));
+                }
+            }
+            // Logging from inside the subscriber would recurse
+            Err(e) => eprintln!("Failed to open session log {}: {}", path.display(), e),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        for span in scope {
+            let extensions = span.extensions();
+            if let Some(log) = extensions.get::<SessionLogFile>() {
+                let line = format_event(event);
+                if let Ok(mut file) = log.0.lock() {
+                    let _ = file.write_all(line.as_bytes());
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Extracts `session_id` from span attributes
+struct SessionIdVisitor(Option<u64>);
+
+impl Visit for SessionIdVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "session_id" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Appends event fields to a log line
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+pub(crate) fn format_event(event: &Event<'_>) -> String {
+    let metadata = event.metadata();
+    let mut line = format!(
+        "{} {:>5} {}:",
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+        metadata.level(),
+        metadata.target()
+    );
+    event.record(&mut LineVisitor(&mut line));
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_session_events_go_to_session_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let layer = SessionLogLayer::new();
+        layer.directory().set(Some(dir.path().to_path_buf()));
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside any session");
+            let span = session_span(7, SocketAddr::from(([127, 0, 0, 1], 1)), "primary");
+            let _entered = span.enter();
+            tracing::info!(bytes = 42, "frame sent");
+            tracing::debug_span!("encoder").in_scope(|| tracing::warn!("nested"));
+        });
+
+        let log = std::fs::read_to_string(dir.path().join("session-7.log")).unwrap();
+        assert!(log.contains("frame sent bytes=42"));
+        assert!(log.contains("nested"));
+        assert!(!log.contains("outside any session"));
+    }
+
+    #[test]
+    fn test_disabled_without_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscriber = tracing_subscriber::registry().with(SessionLogLayer::new());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = session_span(8, SocketAddr::from(([127, 0, 0, 1], 1)), "shared");
+            span.in_scope(|| tracing::info!("not written"));
+        });
+
+        assert!(!dir.path().join("session-8.log").exists());
+    }
+}
@@ -0,0 +1,169 @@
+//! Wakeup Audit
+//!
+//! Each wakeup of an otherwise idle process keeps a laptop's CPU out of its
+//! deep sleep states for a moment, so a loop polling every few milliseconds
+//! costs battery even when there is nothing to do.
+//!
+//! Built with the `wakeup-audit` feature, the server's loops and timers
+//! report each wakeup here, noting whether it found work. The housekeeping
+//! tick logs the rates per task and thread every [`REPORT_INTERVAL`], and
+//! warns about tasks that keep waking up for nothing. Without the feature,
+//! [`wakeup`] compiles to nothing and no report is logged.
+
+use std::collections::HashMap;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// How often the rates are logged
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Wakeups per second with nothing to do above which a task is flagged
+pub const IDLE_WAKEUP_WARN_RATE: f64 = 5.0;
+
+/// Wakeup rate of one task on one thread
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakeupRate {
+    /// Task name
+    pub task: &'static str,
+    /// Thread name (or ID for unnamed threads)
+    pub thread: String,
+    /// Wakeups per second
+    pub per_sec: f64,
+    /// Wakeups per second that found nothing to do
+    pub idle_per_sec: f64,
+}
+
+/// Wakeup counters of a set of tasks
+#[derive(Debug)]
+pub struct WakeupCounts {
+    since: Instant,
+    counts: HashMap<(&'static str, ThreadId), Counter>,
+}
+
+#[derive(Debug)]
+struct Counter {
+    thread: String,
+    total: u64,
+    idle: u64,
+}
+
+impl WakeupCounts {
+    /// Start counting at `now`
+    pub fn new(now: Instant) -> Self {
+        Self {
+            since: now,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Count a wakeup of `task` on the current thread
+    pub fn record(&mut self, task: &'static str, idle: bool) {
+        let thread = std::thread::current();
+        let counter = self
+            .counts
+            .entry((task, thread.id()))
+            .or_insert_with(|| Counter {
+                thread: thread
+                    .name()
+                    .map_or_else(|| format!("{:?}", thread.id()), str::to_string),
+                total: 0,
+                idle: 0,
+            });
+        counter.total += 1;
+        counter.idle += u64::from(idle);
+    }
+
+    /// Rates since the last call (or creation), busiest first
+    pub fn take_rates(&mut self, now: Instant) -> Vec<WakeupRate> {
+        let secs = now
+            .saturating_duration_since(self.since)
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        self.since = now;
+
+        let mut rates: Vec<WakeupRate> = self
+            .counts
+            .drain()
+            .map(|((task, _), counter)| WakeupRate {
+                task,
+                thread: counter.thread,
+                per_sec: counter.total as f64 / secs,
+                idle_per_sec: counter.idle as f64 / secs,
+            })
+            .collect();
+        rates.sort_by(|a, b| b.per_sec.total_cmp(&a.per_sec));
+        rates
+    }
+}
+
+#[cfg(feature = "wakeup-audit")]
+fn counts() -> &'static std::sync::Mutex<WakeupCounts> {
+    use std::sync::{Mutex, OnceLock};
+
+    static COUNTS: OnceLock<Mutex<WakeupCounts>> = OnceLock::new();
+    COUNTS.get_or_init(|| Mutex::new(WakeupCounts::new(Instant::now())))
+}
+
+/// Note a wakeup of `task`; `idle` when it found nothing to do
+#[inline]
+pub fn wakeup(task: &'static str, idle: bool) {
+    #[cfg(feature = "wakeup-audit")]
+    counts()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .record(task, idle);
+    #[cfg(not(feature = "wakeup-audit"))]
+    let _ = (task, idle);
+}
+
+/// Log the rates since the last report
+pub fn log_report() {
+    #[cfg(feature = "wakeup-audit")]
+    {
+        use tracing::{info, warn};
+
+        let rates = counts()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take_rates(Instant::now());
+        for rate in rates {
+            if rate.idle_per_sec >= IDLE_WAKEUP_WARN_RATE {
+                warn!(
+                    "⏰ Wakeups: {} on {}: {:.1}/s, {:.1}/s with nothing to do",
+                    rate.task, rate.thread, rate.per_sec, rate.idle_per_sec
+                );
+            } else {
+                info!(
+                    "⏰ Wakeups: {} on {}: {:.1}/s ({:.1}/s idle)",
+                    rate.task, rate.thread, rate.per_sec, rate.idle_per_sec
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_per_task() {
+        let start = Instant::now();
+        let mut counts = WakeupCounts::new(start);
+        for i in 0..20 {
+            counts.record("poll", i % 2 == 0);
+        }
+        counts.record("tick", false);
+
+        let rates = counts.take_rates(start + Duration::from_secs(2));
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].task, "poll");
+        assert_eq!(rates[0].per_sec, 10.0);
+        assert_eq!(rates[0].idle_per_sec, 5.0);
+        assert_eq!(rates[1].task, "tick");
+        assert_eq!(rates[1].per_sec, 0.5);
+
+        // Counting starts over
+        assert!(counts.take_rates(start + Duration::from_secs(3)).is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+//! Resilience tests against a server injecting faults
+//!
+//! NOTE: Requires a server built with the `chaos` feature, running in a
+//! desktop session with portal permission already granted, and started
+//! with the faults named by the test, e.g.:
+//!   LAMCO_RDP_CHAOS=frame_drop=0.3,encoder_fail=0.1 cargo run --features chaos
+//! Then run that test manually with:
+//!   LAMCO_RDP_TEST_ADDR=127.0.0.1:3389 \
+//!     cargo test --features test-client --test chaos_test -- --ignored --nocapture video
+//!
+//! The login variables are those of `loopback_test.rs`.
+
+#![cfg(feature = "test-client")]
+
+use std::time::Duration;
+
+use lamco_rdp_server::test_client::TestClient;
+
+fn client() -> TestClient {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let address = std::env::var("LAMCO_RDP_TEST_ADDR").unwrap_or_else(|_| "127.0.0.1:3389".into());
+    TestClient::new(address)
+        .with_credentials(&var("LAMCO_RDP_TEST_USER"), &var("LAMCO_RDP_TEST_PASSWORD"))
+        .with_nla(var("LAMCO_RDP_TEST_NLA") == "1")
+        .with_timeout(Duration::from_secs(60))
+}
+
+#[tokio::test]
+#[ignore] // Needs a running server with LAMCO_RDP_CHAOS
+async fn test_video_survives_dropped_frames_and_encoder_failures() {
+    // LAMCO_RDP_CHAOS=frame_drop=0.3,encoder_fail=0.1
+    // Dropped frames and failed encodes cost frames, not the session
+    let report = client()
+        .with_frames(10)
+        .run()
+        .await
+        .expect("Failed to connect");
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+
+    assert!(
+        report.frames >= 10,
+        "only {} graphics updates",
+        report.frames
+    );
+    assert!(report.terminated.is_none(), "{:?}", report.terminated);
+}
+
+#[tokio::test]
+#[ignore] // Needs a running server with LAMCO_RDP_CHAOS
+async fn test_reconnect_after_severed_connections() {
+    // LAMCO_RDP_CHAOS=sever_after_secs=20
+    // Sessions outlasting the limit are severed before the client's timeout
+    for attempt in 0..2 {
+        let report = client().with_frames(100_000).run().await;
+        println!(
+            "Severed session {}: {:?}",
+            attempt,
+            report.as_ref().map(|r| r.frames)
+        );
+    }
+
+    // Later connections are still served (a few, as each may be severed early)
+    let mut served = false;
+    for _ in 0..3 {
+        if let Ok(report) = client().with_frames(1).run().await {
+            served |= report.frames > 0;
+        }
+    }
+    assert!(served, "no graphics updates after severed connections");
+}
+
+#[tokio::test]
+#[ignore] // Needs a running server with LAMCO_RDP_CHAOS
+async fn test_separate_session_after_portal_delay() {
+    // LAMCO_RDP_CHAOS=portal_delay_ms=10000, server.multi_client = "separate"
+    // The second client waits for a capture session of its own, created late
+    let (first, second) = tokio::join!(client().with_frames(1000).run(), async {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        client().run().await
+    });
+    println!("First client: {:?}", first.map(|r| r.frames));
+
+    let report = second.expect("Failed to connect the second client");
+    assert!(report.frames > 0, "no graphics updates");
+    assert!(report.clipboard.ready, "clipboard channel not ready");
+}
@@ -0,0 +1,56 @@
+//! Loopback test against a running server
+//!
+//! NOTE: Requires a server running in a desktop session with portal
+//! permission already granted (see `lamco-rdp-server setup`).
+//! Run manually with:
+//!   LAMCO_RDP_TEST_ADDR=127.0.0.1:3389 \
+//!     cargo test --features test-client --test loopback_test -- --ignored --nocapture
+//!
+//! `LAMCO_RDP_TEST_USER` and `LAMCO_RDP_TEST_PASSWORD` set the login and
+//! `LAMCO_RDP_TEST_NLA=1` authenticates with CredSSP.
+
+#![cfg(feature = "test-client")]
+
+use std::time::Duration;
+
+use lamco_rdp_server::test_client::TestClient;
+
+fn client() -> TestClient {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let address = std::env::var("LAMCO_RDP_TEST_ADDR").unwrap_or_else(|_| "127.0.0.1:3389".into());
+    TestClient::new(address)
+        .with_credentials(&var("LAMCO_RDP_TEST_USER"), &var("LAMCO_RDP_TEST_PASSWORD"))
+        .with_nla(var("LAMCO_RDP_TEST_NLA") == "1")
+        .with_timeout(Duration::from_secs(30))
+}
+
+#[tokio::test]
+#[ignore] // Needs a running server
+async fn test_loopback_session() {
+    let report = client().run().await.expect("Failed to connect");
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+
+    assert!(report.frames > 0, "no graphics updates");
+    assert!(report.input_events > 0, "no input sent");
+    assert!(report.clipboard.ready, "clipboard channel not ready");
+    assert!(report.clipboard.copy_announced);
+    assert!(report.is_ok(), "{:?}", report.problems());
+}
+
+#[tokio::test]
+#[ignore] // Needs a running server
+async fn test_loopback_reconnect() {
+    // The server must accept a new client once the previous one left
+    for attempt in 0..2 {
+        let report = client()
+            .with_frames(1)
+            .run()
+            .await
+            .expect("Failed to connect");
+        assert!(
+            report.frames > 0,
+            "no graphics updates on attempt {}",
+            attempt
+        );
+    }
+}